@@ -5,8 +5,8 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::graph::serialization::SerializedBjkGraph;
-use crate::graph::{BjkGraph, BjkNodeId};
-use crate::graph_interpreter::run_graph;
+use crate::graph::{BjkGraph, BjkNodeId, BlackjackValue, DataType};
+use crate::graph_interpreter::{cook_node, run_graph, ExternalParameter, ExternalParameterValues};
 use crate::lua_engine::{LuaRuntime, ProgramResult, RenderableThing};
 use crate::prelude::*;
 
@@ -52,6 +52,7 @@ fn run_example(example: &Example, rt: &LuaRuntime) -> ProgramResult {
         rt_data.external_parameters.unwrap(),
         &rt.node_definitions,
         None,
+        None,
     )
     .unwrap()
 }
@@ -93,3 +94,323 @@ pub fn test_examples_folder() {
         }
     }
 }
+
+#[test]
+pub fn test_cook_node() {
+    let rt = LuaRuntime::initialize_with_std("../blackjack_lua".into()).unwrap();
+
+    // Missing inputs take their default values
+    let outputs = cook_node(&rt.lua, &rt.node_definitions, "MakeBox", HashMap::new()).unwrap();
+    let cube = match RenderableThing::from_lua_value(outputs.get("out_mesh").unwrap()).unwrap() {
+        RenderableThing::HalfEdgeMesh(mesh) => mesh,
+        _ => panic!("Expected a mesh"),
+    };
+    assert_eq!(cube.read_connectivity().num_faces(), 6);
+
+    // Meshes can be passed in directly
+    let mut inputs = HashMap::new();
+    inputs.insert(
+        "mesh".to_string(),
+        mlua::Value::UserData(rt.lua.create_userdata(cube).unwrap()),
+    );
+    inputs.insert("iterations".to_string(), mlua::Value::Number(2.0));
+    let outputs = cook_node(&rt.lua, &rt.node_definitions, "Subdivide", inputs).unwrap();
+    match RenderableThing::from_lua_value(outputs.get("out_mesh").unwrap()).unwrap() {
+        RenderableThing::HalfEdgeMesh(mesh) => {
+            assert_eq!(mesh.read_connectivity().num_faces(), 96)
+        }
+        _ => panic!("Expected a mesh"),
+    }
+
+    // Meshes have no default, and unknown inputs are an error
+    assert!(cook_node(&rt.lua, &rt.node_definitions, "Subdivide", HashMap::new()).is_err());
+    let mut inputs = HashMap::new();
+    inputs.insert("not_an_input".to_string(), mlua::Value::Nil);
+    assert!(cook_node(&rt.lua, &rt.node_definitions, "MakeBox", inputs).is_err());
+}
+
+#[test]
+pub fn test_native_nodes() {
+    let rt = LuaRuntime::initialize_with_std("../blackjack_lua".into()).unwrap();
+
+    let mut inputs = HashMap::new();
+    inputs.insert("x".to_string(), mlua::Value::Number(0.5));
+    inputs.insert("to_max".to_string(), mlua::Value::Number(10.0));
+    let outputs = cook_node(&rt.lua, &rt.node_definitions, "MapRange", inputs).unwrap();
+    assert_eq!(outputs.get::<_, f32>("out").unwrap(), 5.0);
+
+    // A box whose size comes from a chain of native nodes, which gets folded
+    // into a constant. Changing the parameter still updates the box.
+    let mut graph = BjkGraph::new();
+    let scalar = graph.add_node("MakeScalar", None);
+    graph
+        .add_input(scalar, "x", DataType::Scalar, None)
+        .unwrap();
+    graph.add_output(scalar, "x", DataType::Scalar).unwrap();
+    let vector = graph.add_node("MakeVector", None);
+    graph.add_output(vector, "v", DataType::Vector).unwrap();
+    for axis in ["x", "y", "z"] {
+        graph
+            .add_input(vector, axis, DataType::Scalar, None)
+            .unwrap();
+        graph.add_connection(scalar, "x", vector, axis).unwrap();
+    }
+    let cube = graph.add_node("MakeBox", Some("out_mesh".into()));
+    graph
+        .add_input(cube, "origin", DataType::Vector, None)
+        .unwrap();
+    graph
+        .add_input(cube, "size", DataType::Vector, None)
+        .unwrap();
+    graph.add_output(cube, "out_mesh", DataType::Mesh).unwrap();
+    graph.add_connection(vector, "v", cube, "size").unwrap();
+
+    let run = |size: f32| {
+        let mut params = ExternalParameterValues::default();
+        params.0.insert(
+            ExternalParameter::new(scalar, "x".into()),
+            BlackjackValue::Scalar(size),
+        );
+        params.0.insert(
+            ExternalParameter::new(cube, "origin".into()),
+            BlackjackValue::Vector(Vec3::ZERO),
+        );
+        let result = run_graph(
+            &rt.lua,
+            &graph,
+            cube,
+            params,
+            &rt.node_definitions,
+            None,
+            None,
+        )
+        .unwrap();
+        match result.renderable {
+            Some(RenderableThing::HalfEdgeMesh(mesh)) => mesh.bbox().1 .0,
+            _ => panic!("Expected a mesh"),
+        }
+    };
+    assert_eq!(run(2.0), Vec3::ONE);
+    assert_eq!(run(2.0), Vec3::ONE);
+    assert_eq!(run(4.0), Vec3::splat(2.0));
+}
+
+#[test]
+pub fn test_partial_recook() {
+    use crate::events::{EngineEvent, EventKind};
+    use crate::graph_interpreter::GizmoState;
+
+    let rt = LuaRuntime::initialize_with_std("../blackjack_lua".into()).unwrap();
+    let cooked = rt.subscribe(EventKind::NodeCooked);
+
+    // A subdivided box, moved by an edit node with its gizmo active
+    let mut graph = BjkGraph::new();
+    let cube = graph.add_node("MakeBox", None);
+    graph
+        .add_input(cube, "origin", DataType::Vector, None)
+        .unwrap();
+    graph
+        .add_input(cube, "size", DataType::Vector, None)
+        .unwrap();
+    graph.add_output(cube, "out_mesh", DataType::Mesh).unwrap();
+    let subdivide = graph.add_node("Subdivide", None);
+    graph
+        .add_input(subdivide, "mesh", DataType::Mesh, None)
+        .unwrap();
+    graph
+        .add_input(subdivide, "technique", DataType::String, None)
+        .unwrap();
+    graph
+        .add_input(subdivide, "iterations", DataType::Scalar, None)
+        .unwrap();
+    graph
+        .add_output(subdivide, "out_mesh", DataType::Mesh)
+        .unwrap();
+    graph
+        .add_connection(cube, "out_mesh", subdivide, "mesh")
+        .unwrap();
+    let edit = graph.add_node("EditGeometry", Some("out_mesh".into()));
+    graph.add_input(edit, "mesh", DataType::Mesh, None).unwrap();
+    graph
+        .add_input(edit, "geometry", DataType::String, None)
+        .unwrap();
+    graph
+        .add_input(edit, "selection", DataType::Selection, None)
+        .unwrap();
+    for param in ["translate", "rotate", "scale"] {
+        graph
+            .add_input(edit, param, DataType::Vector, None)
+            .unwrap();
+    }
+    graph.add_output(edit, "out_mesh", DataType::Mesh).unwrap();
+    graph
+        .add_connection(subdivide, "out_mesh", edit, "mesh")
+        .unwrap();
+
+    let run = |iterations: f32, translate: Vec3| {
+        let mut params = ExternalParameterValues::default();
+        let mut set = |node, name: &str, value| {
+            params
+                .0
+                .insert(ExternalParameter::new(node, name.into()), value);
+        };
+        set(cube, "origin", BlackjackValue::Vector(Vec3::ZERO));
+        set(cube, "size", BlackjackValue::Vector(Vec3::ONE));
+        set(
+            subdivide,
+            "technique",
+            BlackjackValue::String("linear".into()),
+        );
+        set(subdivide, "iterations", BlackjackValue::Scalar(iterations));
+        set(edit, "geometry", BlackjackValue::String("Vertex".into()));
+        set(
+            edit,
+            "selection",
+            BlackjackValue::Selection("*".into(), Some(SelectionExpression::All)),
+        );
+        set(edit, "translate", BlackjackValue::Vector(translate));
+        set(edit, "rotate", BlackjackValue::Vector(Vec3::ZERO));
+        set(edit, "scale", BlackjackValue::Vector(Vec3::ONE));
+        let mut gizmos = slotmap::SecondaryMap::new();
+        gizmos.insert(edit, GizmoState::default());
+        let result = run_graph(
+            &rt.lua,
+            &graph,
+            edit,
+            params,
+            &rt.node_definitions,
+            Some(gizmos),
+            None,
+        )
+        .unwrap();
+        let cooked_nodes = cooked
+            .try_iter()
+            .map(|event| match event {
+                EngineEvent::NodeCooked { node_id, .. } => node_id,
+                _ => unreachable!(),
+            })
+            .collect_vec();
+        match result.renderable {
+            Some(RenderableThing::HalfEdgeMesh(mesh)) => (mesh, cooked_nodes),
+            _ => panic!("Expected a mesh"),
+        }
+    };
+
+    let (mesh, cooked_nodes) = run(1.0, Vec3::ZERO);
+    assert_eq!(cooked_nodes.len(), 3);
+    assert_eq!(mesh.read_connectivity().num_faces(), 24);
+
+    // Only the edit node cooks again, on top of the same subdivided box
+    let (mesh, cooked_nodes) = run(1.0, Vec3::X);
+    assert_eq!(cooked_nodes, [edit]);
+    assert_eq!(mesh.read_connectivity().num_faces(), 24);
+    assert_eq!(mesh.bbox().0 .0.x, 0.5);
+
+    let (mesh, cooked_nodes) = run(2.0, Vec3::X);
+    assert_eq!(cooked_nodes, [subdivide, edit]);
+    assert_eq!(mesh.read_connectivity().num_faces(), 96);
+}
+
+#[test]
+pub fn test_recook_on_material_change() {
+    use crate::graph_interpreter::GizmoState;
+    use crate::materials::{Material, MaterialRegistry, MATERIAL_CHANNEL};
+
+    let rt = LuaRuntime::initialize_with_std("../blackjack_lua".into()).unwrap();
+
+    // A box assigned a material, moved by an edit node with its gizmo active
+    // so the nodes before it are kept across cooks.
+    let mut graph = BjkGraph::new();
+    let cube = graph.add_node("MakeBox", None);
+    graph
+        .add_input(cube, "origin", DataType::Vector, None)
+        .unwrap();
+    graph
+        .add_input(cube, "size", DataType::Vector, None)
+        .unwrap();
+    graph.add_output(cube, "out_mesh", DataType::Mesh).unwrap();
+    let assign = graph.add_node("AssignMaterial", None);
+    graph
+        .add_input(assign, "mesh", DataType::Mesh, None)
+        .unwrap();
+    graph
+        .add_input(assign, "faces", DataType::Selection, None)
+        .unwrap();
+    graph
+        .add_input(assign, "material", DataType::String, None)
+        .unwrap();
+    graph
+        .add_output(assign, "out_mesh", DataType::Mesh)
+        .unwrap();
+    graph
+        .add_connection(cube, "out_mesh", assign, "mesh")
+        .unwrap();
+    let edit = graph.add_node("EditGeometry", Some("out_mesh".into()));
+    graph.add_input(edit, "mesh", DataType::Mesh, None).unwrap();
+    graph
+        .add_input(edit, "geometry", DataType::String, None)
+        .unwrap();
+    graph
+        .add_input(edit, "selection", DataType::Selection, None)
+        .unwrap();
+    for param in ["translate", "rotate", "scale"] {
+        graph
+            .add_input(edit, param, DataType::Vector, None)
+            .unwrap();
+    }
+    graph.add_output(edit, "out_mesh", DataType::Mesh).unwrap();
+    graph
+        .add_connection(assign, "out_mesh", edit, "mesh")
+        .unwrap();
+
+    let run = || {
+        let mut params = ExternalParameterValues::default();
+        let mut set = |node, name: &str, value| {
+            params
+                .0
+                .insert(ExternalParameter::new(node, name.into()), value);
+        };
+        let all = || BlackjackValue::Selection("*".into(), Some(SelectionExpression::All));
+        set(cube, "origin", BlackjackValue::Vector(Vec3::ZERO));
+        set(cube, "size", BlackjackValue::Vector(Vec3::ONE));
+        set(assign, "faces", all());
+        set(assign, "material", BlackjackValue::String("metal".into()));
+        set(edit, "geometry", BlackjackValue::String("Vertex".into()));
+        set(edit, "selection", all());
+        set(edit, "translate", BlackjackValue::Vector(Vec3::ZERO));
+        set(edit, "rotate", BlackjackValue::Vector(Vec3::ZERO));
+        set(edit, "scale", BlackjackValue::Vector(Vec3::ONE));
+        let mut gizmos = slotmap::SecondaryMap::new();
+        gizmos.insert(edit, GizmoState::default());
+        let result = run_graph(
+            &rt.lua,
+            &graph,
+            edit,
+            params,
+            &rt.node_definitions,
+            Some(gizmos),
+            None,
+        )
+        .unwrap();
+        let mesh = match result.renderable {
+            Some(RenderableThing::HalfEdgeMesh(mesh)) => mesh,
+            _ => panic!("Expected a mesh"),
+        };
+        let materials = mesh
+            .channels
+            .read_channel_by_name::<FaceId, f32>(MATERIAL_CHANNEL)
+            .unwrap();
+        materials.iter().map(|(_, index)| *index).collect_vec()
+    };
+
+    let mut registry = MaterialRegistry::default();
+    registry.add(Material::new("wood")).unwrap();
+    registry.add(Material::new("metal")).unwrap();
+    registry.install(&rt.lua);
+    assert!(run().iter().all(|index| *index == 1.0));
+
+    // Removing a material shifts the index of the ones after it
+    registry.remove(0);
+    registry.install(&rt.lua);
+    assert!(run().iter().all(|index| *index == 0.0));
+}
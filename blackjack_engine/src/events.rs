@@ -0,0 +1,151 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::graph::BjkNodeId;
+use crate::prelude::*;
+
+/// Something that happened while the engine ran a graph.
+#[derive(Clone, Debug)]
+pub enum EngineEvent {
+    /// The `op` of a node ran. The `duration` only counts the node itself,
+    /// not the nodes it depends on.
+    NodeCooked {
+        node_id: BjkNodeId,
+        op_name: String,
+        duration: Duration,
+    },
+    /// The mesh returned by the target node of a graph run may be different
+    /// from the one returned by the previous run of that node. This happens
+    /// when a parameter the node depends on, the graph, or the current frame
+    /// change, or when a generator node yielded new partial results.
+    MeshChanged { node_id: BjkNodeId },
+    /// A node parameter changed. The engine emits this when a gizmo modifies
+    /// a parameter, and hosts can emit it for their own edits.
+    ParamChanged {
+        node_id: BjkNodeId,
+        param_name: String,
+    },
+}
+
+/// The kinds of [`EngineEvent`], used to subscribe to them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    NodeCooked,
+    MeshChanged,
+    ParamChanged,
+}
+
+impl EngineEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            EngineEvent::NodeCooked { .. } => EventKind::NodeCooked,
+            EngineEvent::MeshChanged { .. } => EventKind::MeshChanged,
+            EngineEvent::ParamChanged { .. } => EventKind::ParamChanged,
+        }
+    }
+}
+
+/// Delivers engine events to the subscribers of each kind of event. Events
+/// are sent through channels, so subscribers can live in other threads and
+/// read them whenever it suits them.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<(EventKind, Sender<EngineEvent>)>>,
+    /// For each node, the fingerprint of the last run that had it as its
+    /// target. Used to tell when the result of a node may have changed.
+    last_runs: Mutex<HashMap<BjkNodeId, u64>>,
+}
+
+impl EventBus {
+    /// Returns a receiver for all the future events of the given `kind`.
+    /// Dropping the receiver cancels the subscription.
+    pub fn subscribe(&self, kind: EventKind) -> Receiver<EngineEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push((kind, tx));
+        rx
+    }
+
+    /// Returns true when someone is subscribed to events of `kind`. Used to
+    /// skip computing events nobody is going to read.
+    pub fn has_subscribers(&self, kind: EventKind) -> bool {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(k, _)| *k == kind)
+    }
+
+    /// Sends `event` to all the subscribers of its kind.
+    pub fn emit(&self, event: EngineEvent) {
+        let kind = event.kind();
+        self.subscribers
+            .lock()
+            .unwrap()
+            // Disconnected subscribers are removed
+            .retain(|(k, tx)| *k != kind || tx.send(event.clone()).is_ok());
+    }
+
+    /// Stores the `fingerprint` of a run with `node_id` as its target, and
+    /// returns true when it's different from the previous one.
+    pub(crate) fn update_last_run(&self, node_id: BjkNodeId, fingerprint: u64) -> bool {
+        self.last_runs.lock().unwrap().insert(node_id, fingerprint) != Some(fingerprint)
+    }
+
+    /// Makes the graphs run from `lua` send their events to this bus.
+    pub fn install(self: &Arc<Self>, lua: &mlua::Lua) {
+        lua.set_app_data(Arc::clone(self));
+    }
+
+    /// Returns the bus installed in `lua`, if any.
+    pub fn current(lua: &mlua::Lua) -> Option<Arc<Self>> {
+        lua.app_data_ref::<Arc<EventBus>>()
+            .map(|bus| Arc::clone(&bus))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_event_bus() {
+        let bus = EventBus::default();
+        let cooked = bus.subscribe(EventKind::NodeCooked);
+        let params = bus.subscribe(EventKind::ParamChanged);
+        assert!(!bus.has_subscribers(EventKind::MeshChanged));
+
+        bus.emit(EngineEvent::NodeCooked {
+            node_id: BjkNodeId::default(),
+            op_name: "MakeBox".into(),
+            duration: Duration::from_millis(1),
+        });
+        assert!(matches!(
+            cooked.try_recv(),
+            Ok(EngineEvent::NodeCooked { .. })
+        ));
+        assert!(params.try_recv().is_err());
+
+        // Dropping a receiver removes its subscription
+        drop(params);
+        bus.emit(EngineEvent::ParamChanged {
+            node_id: BjkNodeId::default(),
+            param_name: "size".into(),
+        });
+        assert!(!bus.has_subscribers(EventKind::ParamChanged));
+
+        assert!(bus.update_last_run(BjkNodeId::default(), 1));
+        assert!(!bus.update_last_run(BjkNodeId::default(), 1));
+        assert!(bus.update_last_run(BjkNodeId::default(), 2));
+    }
+}
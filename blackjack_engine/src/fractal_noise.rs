@@ -0,0 +1,204 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use noise::{NoiseFn, Seedable};
+
+use crate::prelude::*;
+
+/// The base noise function, which is layered to build fractal noise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseKind {
+    Perlin,
+    Simplex,
+}
+
+impl NoiseKind {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Perlin" => Ok(Self::Perlin),
+            "Simplex" => Ok(Self::Simplex),
+            _ => bail!("Invalid noise kind: {name}"),
+        }
+    }
+}
+
+/// Parameters for [`FractalNoise`].
+#[derive(Clone, Debug)]
+pub struct NoiseParams {
+    pub kind: NoiseKind,
+    pub seed: u32,
+    /// Frequency of the first octave.
+    pub frequency: f32,
+    /// Amplitude of the first octave.
+    pub amplitude: f32,
+    /// Number of layers of noise.
+    pub octaves: usize,
+    /// Frequency multiplier between octaves.
+    pub lacunarity: f32,
+    /// Amplitude multiplier between octaves.
+    pub persistence: f32,
+}
+
+impl Default for NoiseParams {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::Perlin,
+            seed: 0,
+            frequency: 1.0,
+            amplitude: 1.0,
+            octaves: 4,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        }
+    }
+}
+
+impl NoiseParams {
+    /// Reads the parameters from a Lua table. Missing fields take their
+    /// default values.
+    pub fn from_table(table: &mlua::Table) -> Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            kind: match table.get::<_, Option<String>>("kind")? {
+                Some(kind) => NoiseKind::from_name(&kind)?,
+                None => defaults.kind,
+            },
+            seed: table
+                .get::<_, Option<u32>>("seed")?
+                .unwrap_or(defaults.seed),
+            frequency: table
+                .get::<_, Option<f32>>("frequency")?
+                .unwrap_or(defaults.frequency),
+            amplitude: table
+                .get::<_, Option<f32>>("amplitude")?
+                .unwrap_or(defaults.amplitude),
+            octaves: table
+                .get::<_, Option<usize>>("octaves")?
+                .unwrap_or(defaults.octaves),
+            lacunarity: table
+                .get::<_, Option<f32>>("lacunarity")?
+                .unwrap_or(defaults.lacunarity),
+            persistence: table
+                .get::<_, Option<f32>>("persistence")?
+                .unwrap_or(defaults.persistence),
+        })
+    }
+}
+
+enum NoiseSource {
+    Perlin(noise::Perlin),
+    Simplex(noise::OpenSimplex),
+}
+
+/// Fractal Brownian motion: Several octaves of a base noise function, each
+/// with a higher frequency and lower amplitude than the previous one.
+pub struct FractalNoise {
+    params: NoiseParams,
+    source: NoiseSource,
+}
+
+impl FractalNoise {
+    pub fn new(params: NoiseParams) -> Self {
+        let source = match params.kind {
+            NoiseKind::Perlin => NoiseSource::Perlin(noise::Perlin::new().set_seed(params.seed)),
+            NoiseKind::Simplex => {
+                NoiseSource::Simplex(noise::OpenSimplex::new().set_seed(params.seed))
+            }
+        };
+        Self { params, source }
+    }
+
+    fn fbm(&self, sample: impl Fn(&NoiseSource, f64) -> f64) -> f32 {
+        let (mut value, mut amplitude, mut frequency) =
+            (0.0, self.params.amplitude, self.params.frequency);
+        for _ in 0..self.params.octaves {
+            value += sample(&self.source, frequency as f64) as f32 * amplitude;
+            amplitude *= self.params.persistence;
+            frequency *= self.params.lacunarity;
+        }
+        value
+    }
+
+    /// Samples the noise at the 2d point `p`.
+    pub fn sample_2d(&self, p: Vec2) -> f32 {
+        // NOTE: The noise crate crashes when given non-finite numbers.
+        if !p.is_finite() {
+            return f32::NAN;
+        }
+        let p = p.as_dvec2();
+        self.fbm(|source, f| {
+            let q = [p.x * f, p.y * f];
+            match source {
+                NoiseSource::Perlin(n) => n.get(q),
+                NoiseSource::Simplex(n) => n.get(q),
+            }
+        })
+    }
+
+    /// Samples the noise at the 3d point `p`.
+    pub fn sample_3d(&self, p: Vec3) -> f32 {
+        if !p.is_finite() {
+            return f32::NAN;
+        }
+        let p = p.as_dvec3();
+        self.fbm(|source, f| {
+            let q = [p.x * f, p.y * f, p.z * f];
+            match source {
+                NoiseSource::Perlin(n) => n.get(q),
+                NoiseSource::Simplex(n) => n.get(q),
+            }
+        })
+    }
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Constructs a new fractal noise function. The `params` table may set
+    /// any of `kind` ("Perlin" or "Simplex"), `seed`, `frequency`,
+    /// `amplitude`, `octaves`, `lacunarity` and `persistence`.
+    #[lua(under = "FractalNoise")]
+    pub fn new(params: mlua::Table) -> Result<FractalNoise> {
+        Ok(FractalNoise::new(NoiseParams::from_table(&params)?))
+    }
+
+    #[lua_impl]
+    impl FractalNoise {
+        /// Samples the noise at coordinates `(x, y)`.
+        #[lua]
+        pub fn get_2d(&self, x: f32, y: f32) -> f32 {
+            self.sample_2d(Vec2::new(x, y))
+        }
+
+        /// Samples the noise at coordinates `(x, y, z)`.
+        #[lua]
+        pub fn get_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+            self.sample_3d(Vec3::new(x, y, z))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_fractal_noise() {
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex] {
+            let params = NoiseParams {
+                kind,
+                seed: 3,
+                ..Default::default()
+            };
+            let a = FractalNoise::new(params.clone());
+            let b = FractalNoise::new(params);
+            let p = Vec3::new(0.3, 1.7, -2.1);
+            assert_eq!(a.sample_3d(p), b.sample_3d(p));
+            assert!(a.sample_2d(Vec2::new(f32::NAN, 0.0)).is_nan());
+        }
+    }
+}
@@ -18,6 +18,16 @@ use slotmap::SlotMap;
 /// The core `bjk` file format
 pub mod serialization;
 
+/// Ramp (curve and gradient) parameter values
+pub mod ramp;
+use ramp::{Ramp, RampInterpolation, RampKey};
+
+/// Validation and resolution of file path parameters
+pub mod file_path;
+
+/// Math and logic nodes evaluated natively by the interpreter
+pub mod native_nodes;
+
 pub struct LuaExpression(pub String);
 
 /// A node has inputs (dependencies) that need to be met. A dependency can be
@@ -43,6 +53,7 @@ pub enum DataType {
     Mesh,
     String,
     HeightMap,
+    Ramp,
 }
 
 impl DataType {
@@ -50,7 +61,11 @@ impl DataType {
     pub fn can_be_enabled(&self) -> bool {
         match self {
             DataType::Mesh | DataType::HeightMap => true,
-            DataType::Vector | DataType::Scalar | DataType::Selection | DataType::String => false,
+            DataType::Vector
+            | DataType::Scalar
+            | DataType::Selection
+            | DataType::String
+            | DataType::Ramp => false,
         }
     }
 
@@ -63,16 +78,18 @@ impl DataType {
             DataType::String => matches!(value, BlackjackValue::String(_)),
             DataType::Mesh => matches!(value, BlackjackValue::None),
             DataType::HeightMap => matches!(value, BlackjackValue::None),
+            DataType::Ramp => matches!(value, BlackjackValue::Ramp(_)),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BlackjackValue {
     Vector(glam::Vec3),
     Scalar(f32),
     String(String),
     Selection(String, Option<SelectionExpression>),
+    Ramp(Ramp),
     None,
 }
 
@@ -83,6 +100,7 @@ impl<'lua> ToLua<'lua> for BlackjackValue {
             BlackjackValue::Scalar(s) => Ok(s.cast_to_lua(lua)),
             BlackjackValue::String(s) => s.to_lua(lua),
             BlackjackValue::Selection(_, sel) => sel.to_lua(lua),
+            BlackjackValue::Ramp(ramp) => ramp.to_lua(lua),
             BlackjackValue::None => Ok(mlua::Value::Nil),
         }
     }
@@ -104,6 +122,9 @@ impl<'lua> FromLua<'lua> for BlackjackValue {
                     let sel = u.borrow::<SelectionExpression>()?.clone();
                     return Ok(BlackjackValue::Selection(sel.unparse(), Some(sel)));
                 }
+                if u.is::<Ramp>() {
+                    return Ok(BlackjackValue::Ramp(u.borrow::<Ramp>()?.clone()));
+                }
             }
             _ => {}
         }
@@ -199,6 +220,10 @@ pub enum InputValueConfig {
     Vector {
         default: glam::Vec3,
     },
+    /// A vector holding RGB components in the 0..1 range.
+    Color {
+        default: glam::Vec3,
+    },
     Scalar {
         default: f32,
         min: Option<f32>,
@@ -209,6 +234,9 @@ pub enum InputValueConfig {
     },
     Selection {
         default_selection: SelectionExpression,
+        /// The kind of elements this selection refers to, when the node
+        /// definition specifies it. Used to preview the selection.
+        kind: Option<ChannelKeyType>,
     },
     Enum {
         values: Vec<String>,
@@ -217,12 +245,22 @@ pub enum InputValueConfig {
     FilePath {
         default_path: Option<String>,
         file_path_mode: FilePathMode,
+        /// The allowed file extensions, without the leading dot. Used to filter
+        /// the files in the file picker and to validate the chosen path. When
+        /// empty, any file is accepted.
+        extensions: Vec<String>,
     },
     String {
         multiline: bool,
         default_text: String,
     },
     LuaString {},
+    Ramp {
+        default: Ramp,
+        /// When set, the ramp is a color gradient. Otherwise, it is a scalar
+        /// curve.
+        color: bool,
+    },
     None,
 }
 
@@ -248,6 +286,7 @@ impl DataType {
             DataType::String => BlackjackValue::String("".into()),
             DataType::Mesh => BlackjackValue::None,
             DataType::HeightMap => BlackjackValue::None,
+            DataType::Ramp => BlackjackValue::Ramp(Ramp::default()),
         }
     }
 }
@@ -260,15 +299,21 @@ impl InputDefinition {
             (DataType::Vector, InputValueConfig::Vector { default }) => {
                 BlackjackValue::Vector(*default)
             }
+            (DataType::Vector, InputValueConfig::Color { default }) => {
+                BlackjackValue::Vector(*default)
+            }
             (DataType::Scalar, InputValueConfig::Scalar { default, .. }) => {
                 BlackjackValue::Scalar(*default)
             }
-            (DataType::Selection, InputValueConfig::Selection { default_selection }) => {
-                BlackjackValue::Selection(
-                    default_selection.unparse(),
-                    Some(default_selection.clone()),
-                )
-            }
+            (
+                DataType::Selection,
+                InputValueConfig::Selection {
+                    default_selection, ..
+                },
+            ) => BlackjackValue::Selection(
+                default_selection.unparse(),
+                Some(default_selection.clone()),
+            ),
             (DataType::Mesh, InputValueConfig::None) => BlackjackValue::None,
             (
                 DataType::String,
@@ -297,6 +342,9 @@ impl InputDefinition {
             }
             (DataType::String, InputValueConfig::LuaString {}) => default_string(),
             (DataType::HeightMap, InputValueConfig::None) => BlackjackValue::None,
+            (DataType::Ramp, InputValueConfig::Ramp { default, .. }) => {
+                BlackjackValue::Ramp(default.clone())
+            }
 
             // Fallback: When config is not valud, return some valid value
             (data_type, _) => data_type.default_value(),
@@ -332,6 +380,12 @@ pub struct NodeDefinition {
     pub executable: bool,
     /// This node has an available interactive gizmo.
     pub has_gizmo: bool,
+    /// Generator nodes run their `op` as a coroutine, which may yield partial
+    /// outputs before returning the final ones. See the `graph_interpreter`.
+    pub generator: bool,
+    /// Native nodes have no `op` function. They are evaluated by the graph
+    /// interpreter itself. See `native_nodes`.
+    pub native: bool,
 }
 
 #[derive(Default)]
@@ -380,6 +434,7 @@ impl NodeDefinitions {
 fn data_type_from_str(s: &str) -> Result<DataType> {
     match s {
         "vec3" => Ok(DataType::Vector),
+        "color" => Ok(DataType::Vector),
         "scalar" => Ok(DataType::Scalar),
         "selection" => Ok(DataType::Selection),
         "mesh" => Ok(DataType::Mesh),
@@ -388,6 +443,8 @@ fn data_type_from_str(s: &str) -> Result<DataType> {
         "file" => Ok(DataType::String),
         "string" => Ok(DataType::String),
         "lua_string" => Ok(DataType::String),
+        "ramp" => Ok(DataType::Ramp),
+        "color_ramp" => Ok(DataType::Ramp),
         _ => Err(anyhow!("Invalid datatype in node definition {:?}", s)),
     }
 }
@@ -398,6 +455,9 @@ impl InputDefinition {
         let type_str: String = table.get::<_, String>("type")?;
         let data_type = data_type_from_str(&type_str)?;
         let value = match data_type {
+            DataType::Vector if type_str == "color" => InputValueConfig::Color {
+                default: table.get::<_, LVec3>("default")?.0,
+            },
             DataType::Vector => InputValueConfig::Vector {
                 default: table.get::<_, LVec3>("default")?.0,
             },
@@ -411,6 +471,13 @@ impl InputDefinition {
             },
             DataType::Selection => InputValueConfig::Selection {
                 default_selection: SelectionExpression::None,
+                kind: match table.get::<_, Option<String>>("kind")?.as_deref() {
+                    Some("vertex") => Some(ChannelKeyType::VertexId),
+                    Some("face") => Some(ChannelKeyType::FaceId),
+                    Some("edge") => Some(ChannelKeyType::HalfEdgeId),
+                    Some(other) => bail!("Invalid selection kind: {other}"),
+                    None => None,
+                },
             },
             DataType::Mesh => InputValueConfig::None,
             DataType::HeightMap => InputValueConfig::None,
@@ -432,6 +499,11 @@ impl InputDefinition {
                     } else {
                         bail!("Undefined mode {mode}")
                     },
+                    extensions: table
+                        .get::<_, Option<Table>>("extensions")?
+                        .map(|t| t.sequence_values::<String>().collect::<Result<Vec<_>, _>>())
+                        .transpose()?
+                        .unwrap_or_default(),
                 }
             }
             DataType::String if type_str == "lua_string" => InputValueConfig::LuaString {},
@@ -439,6 +511,10 @@ impl InputDefinition {
                 default_text: table.get::<_, String>("default")?,
                 multiline: table.get::<_, bool>("multiline")?,
             },
+            DataType::Ramp => InputValueConfig::Ramp {
+                default: ramp_from_lua(&table)?,
+                color: type_str == "color_ramp",
+            },
         };
 
         Ok(InputDefinition {
@@ -449,6 +525,32 @@ impl InputDefinition {
     }
 }
 
+/// Parses the default value of a ramp parameter from its Lua table. Keys are
+/// given as `{position, value}` pairs, where the value may be a number or a
+/// vector.
+fn ramp_from_lua(table: &Table) -> Result<Ramp> {
+    let keys = table
+        .get::<_, Table>("keys")?
+        .sequence_values::<Table>()
+        .map(|key| {
+            let key = key?;
+            let position = key.get::<_, f32>(1)?;
+            let value = match key.get::<_, mlua::Value>(2)? {
+                mlua::Value::Vector(x, y, z) => Vec3::new(x, y, z),
+                mlua::Value::Number(n) => Vec3::splat(n as f32),
+                mlua::Value::Integer(i) => Vec3::splat(i as f32),
+                other => bail!("Invalid ramp key value {other:?}"),
+            };
+            Ok(RampKey { position, value })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let interpolation = match table.get::<_, Option<String>>("interpolation")? {
+        Some(name) => RampInterpolation::from_name(&name)?,
+        None => RampInterpolation::Linear,
+    };
+    Ok(Ramp::new(keys, interpolation))
+}
+
 impl OutputDefinition {
     /// Parses from a Lua table describing this [`OutputDefinition`]
     pub fn from_lua(table: Table) -> Result<Self> {
@@ -474,6 +576,11 @@ impl NodeDefinition {
             .map(|x| OutputDefinition::from_lua(x?))
             .collect::<Result<Vec<_>>>()?;
 
+        let native = table.get::<_, Option<bool>>("native")?.unwrap_or(false);
+        if native && !native_nodes::is_native_op(&name) {
+            bail!("Node {name} is marked as native, but has no native implementation");
+        }
+
         Ok(NodeDefinition {
             op_name: name,
             inputs,
@@ -482,6 +589,8 @@ impl NodeDefinition {
             returns: table.get::<_, Option<String>>("returns")?,
             executable: table.get::<_, Option<bool>>("executable")?.unwrap_or(false),
             has_gizmo: table.get::<_, mlua::Value>("gizmos")? != mlua::Value::Nil,
+            generator: table.get::<_, Option<bool>>("generator")?.unwrap_or(false),
+            native,
         })
     }
 
@@ -0,0 +1,116 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::path::{Path, PathBuf};
+
+use super::FilePathMode;
+use crate::prelude::*;
+
+/// Resolves the value of a file path parameter. Relative paths are resolved
+/// against `base_dir`, typically the folder containing the currently open
+/// graph. When there is no base dir, relative paths are left untouched and
+/// will be relative to the working directory.
+pub fn resolve_file_path(path: &str, base_dir: Option<&Path>) -> PathBuf {
+    let path = Path::new(path);
+    match base_dir {
+        Some(base_dir) if path.is_relative() => base_dir.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// The inverse of [`resolve_file_path`]. Returns `path` relative to
+/// `base_dir` when it is inside it, so graphs can be moved around along with
+/// the files they reference.
+pub fn relativize_file_path(path: &Path, base_dir: Option<&Path>) -> PathBuf {
+    base_dir
+        .and_then(|base_dir| path.strip_prefix(base_dir).ok())
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+/// Checks that `path` has one of the given `extensions`. An empty list of
+/// extensions accepts any file. The comparison is case insensitive.
+pub fn has_valid_extension(path: &Path, extensions: &[String]) -> bool {
+    if extensions.is_empty() {
+        return true;
+    }
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        .unwrap_or(false)
+}
+
+/// Validates an already resolved `path` for the given `mode`: Files that are
+/// opened must exist, and files that are saved must be in an existing folder.
+/// The extension must be one of `extensions`, if any are given. An empty path
+/// is an error, so callers where the file is optional should check for it
+/// first.
+pub fn validate_file_path(path: &Path, mode: FilePathMode, extensions: &[String]) -> Result<()> {
+    if path.as_os_str().is_empty() {
+        bail!("No file selected");
+    }
+    if !has_valid_extension(path, extensions) {
+        bail!(
+            "The file {} should have one of these extensions: {}",
+            path.display(),
+            extensions.join(", ")
+        );
+    }
+    match mode {
+        FilePathMode::Open => {
+            if !path.is_file() {
+                bail!("The file {} does not exist", path.display());
+            }
+        }
+        FilePathMode::Save => {
+            if path.is_dir() {
+                bail!("The path {} is a folder, not a file", path.display());
+            }
+            if let Some(parent) = path.parent() {
+                if !parent.as_os_str().is_empty() && !parent.is_dir() {
+                    bail!("The folder {} does not exist", parent.display());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_file_path_resolution() {
+        let base = Path::new("/projects/bjk");
+        assert_eq!(
+            resolve_file_path("out/mesh.obj", Some(base)),
+            PathBuf::from("/projects/bjk/out/mesh.obj")
+        );
+        assert_eq!(
+            resolve_file_path("/tmp/mesh.obj", Some(base)),
+            PathBuf::from("/tmp/mesh.obj")
+        );
+        assert_eq!(
+            resolve_file_path("mesh.obj", None),
+            PathBuf::from("mesh.obj")
+        );
+        assert_eq!(
+            relativize_file_path(Path::new("/projects/bjk/out/mesh.obj"), Some(base)),
+            PathBuf::from("out/mesh.obj")
+        );
+        assert_eq!(
+            relativize_file_path(Path::new("/tmp/mesh.obj"), Some(base)),
+            PathBuf::from("/tmp/mesh.obj")
+        );
+
+        let obj = vec!["obj".to_string()];
+        assert!(has_valid_extension(Path::new("a/b.OBJ"), &obj));
+        assert!(!has_valid_extension(Path::new("a/b.stl"), &obj));
+        assert!(!has_valid_extension(Path::new("a/b"), &obj));
+        assert!(has_valid_extension(Path::new("a/b"), &[]));
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Math and logic nodes, evaluated by the graph interpreter itself instead of
+//! calling an `op` function in Lua. These nodes are small and used all over
+//! data-driven graphs, so the overhead of a Lua call for each of them adds up.
+//!
+//! Native nodes are still declared in the Lua node library, with `native =
+//! true` and no `op`, so their parameters are described like any other node.
+//! Booleans are represented as scalars: Zero is false and any other value is
+//! true. Nodes producing booleans output 0 or 1.
+
+use super::BlackjackValue;
+use crate::prelude::*;
+
+/// The outputs of a native node, indexed by name.
+pub type NativeOutputs = Vec<(&'static str, BlackjackValue)>;
+
+/// The op names of all the nodes implemented in this module.
+const NATIVE_OPS: &[&str] = &[
+    "MakeScalar",
+    "MakeVector",
+    "SeparateVector",
+    "ScalarMath",
+    "VectorMath",
+    "VectorScalarMath",
+    "Clamp",
+    "MapRange",
+    "Compare",
+    "Logic",
+];
+
+/// Returns whether the node named `op_name` is implemented natively.
+pub fn is_native_op(op_name: &str) -> bool {
+    NATIVE_OPS.contains(&op_name)
+}
+
+fn input<'a>(
+    inputs: &'a HashMap<String, BlackjackValue>,
+    name: &str,
+) -> Result<&'a BlackjackValue> {
+    inputs
+        .get(name)
+        .ok_or_else(|| anyhow!("Missing input '{name}'"))
+}
+
+fn scalar(inputs: &HashMap<String, BlackjackValue>, name: &str) -> Result<f32> {
+    match input(inputs, name)? {
+        BlackjackValue::Scalar(x) => Ok(*x),
+        other => bail!("Input '{name}' should be a scalar, got {other:?}"),
+    }
+}
+
+fn vector(inputs: &HashMap<String, BlackjackValue>, name: &str) -> Result<Vec3> {
+    match input(inputs, name)? {
+        BlackjackValue::Vector(v) => Ok(*v),
+        other => bail!("Input '{name}' should be a vector, got {other:?}"),
+    }
+}
+
+fn string<'a>(inputs: &'a HashMap<String, BlackjackValue>, name: &str) -> Result<&'a str> {
+    match input(inputs, name)? {
+        BlackjackValue::String(s) => Ok(s),
+        other => bail!("Input '{name}' should be a string, got {other:?}"),
+    }
+}
+
+fn boolean(b: bool) -> f32 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Divides `a` by `b`, returning zero instead of infinity or NaN when `b` is
+/// zero. A graph where a slider briefly hits zero shouldn't break everything
+/// downstream.
+fn safe_div(a: f32, b: f32) -> f32 {
+    if b == 0.0 {
+        0.0
+    } else {
+        a / b
+    }
+}
+
+fn scalar_math(op: &str, x: f32, y: f32) -> Result<f32> {
+    Ok(match op {
+        "Add" => x + y,
+        "Sub" => x - y,
+        "Mul" => x * y,
+        "Div" => safe_div(x, y),
+        "Mod" => {
+            if y == 0.0 {
+                0.0
+            } else {
+                x.rem_euclid(y)
+            }
+        }
+        "Pow" => x.powf(y),
+        "Min" => x.min(y),
+        "Max" => x.max(y),
+        "Atan2" => x.atan2(y),
+        // Unary operations, which ignore `y`
+        "Negate" => -x,
+        "Abs" => x.abs(),
+        "Sign" => x.signum(),
+        "Floor" => x.floor(),
+        "Ceil" => x.ceil(),
+        "Round" => x.round(),
+        "Fract" => x.fract(),
+        "Sqrt" => x.max(0.0).sqrt(),
+        "Exp" => x.exp(),
+        "Log" => x.ln(),
+        "Sin" => x.sin(),
+        "Cos" => x.cos(),
+        "Tan" => x.tan(),
+        "Asin" => x.clamp(-1.0, 1.0).asin(),
+        "Acos" => x.clamp(-1.0, 1.0).acos(),
+        "Atan" => x.atan(),
+        "ToRadians" => x.to_radians(),
+        "ToDegrees" => x.to_degrees(),
+        _ => bail!("Invalid scalar math operation: {op}"),
+    })
+}
+
+fn vector_math(op: &str, a: Vec3, b: Vec3) -> Result<Vec3> {
+    Ok(match op {
+        "Add" => a + b,
+        "Sub" => a - b,
+        "Mul" => a * b,
+        "Div" => Vec3::new(safe_div(a.x, b.x), safe_div(a.y, b.y), safe_div(a.z, b.z)),
+        "Min" => a.min(b),
+        "Max" => a.max(b),
+        "Cross" => a.cross(b),
+        "Project" => {
+            if b.length_squared() < f32::EPSILON {
+                Vec3::ZERO
+            } else {
+                a.project_onto(b)
+            }
+        }
+        "Reflect" => {
+            let n = b.normalize_or_zero();
+            a - 2.0 * a.dot(n) * n
+        }
+        // Unary operations, which ignore `b`
+        "Normalize" => a.normalize_or_zero(),
+        "Negate" => -a,
+        "Abs" => a.abs(),
+        _ => bail!("Invalid vector math operation: {op}"),
+    })
+}
+
+fn vector_scalar_math(op: &str, a: Vec3, b: Vec3) -> Result<f32> {
+    Ok(match op {
+        "Dot" => a.dot(b),
+        "Distance" => a.distance(b),
+        "Angle" => {
+            if a == Vec3::ZERO || b == Vec3::ZERO {
+                0.0
+            } else {
+                a.angle_between(b)
+            }
+        }
+        // Unary operations, which ignore `b`
+        "Length" => a.length(),
+        _ => bail!("Invalid vector to scalar operation: {op}"),
+    })
+}
+
+fn compare(op: &str, x: f32, y: f32, epsilon: f32) -> Result<bool> {
+    Ok(match op {
+        "Less" => x < y,
+        "LessEqual" => x <= y,
+        "Greater" => x > y,
+        "GreaterEqual" => x >= y,
+        "Equal" => (x - y).abs() <= epsilon,
+        "NotEqual" => (x - y).abs() > epsilon,
+        _ => bail!("Invalid comparison: {op}"),
+    })
+}
+
+fn logic(op: &str, a: bool, b: bool) -> Result<bool> {
+    Ok(match op {
+        "And" => a && b,
+        "Or" => a || b,
+        "Xor" => a != b,
+        "Not" => !a,
+        _ => bail!("Invalid logic operation: {op}"),
+    })
+}
+
+/// Remaps `x` from the `from_min..from_max` range to `to_min..to_max`. When
+/// `clamp` is set, the result never leaves the target range.
+fn map_range(x: f32, from_min: f32, from_max: f32, to_min: f32, to_max: f32, clamp: bool) -> f32 {
+    let mut t = safe_div(x - from_min, from_max - from_min);
+    if clamp {
+        t = t.clamp(0.0, 1.0);
+    }
+    to_min + (to_max - to_min) * t
+}
+
+/// Evaluates the native node named `op_name` with the given `inputs`.
+pub fn run_native_node(
+    op_name: &str,
+    inputs: &HashMap<String, BlackjackValue>,
+) -> Result<NativeOutputs> {
+    use BlackjackValue::{Scalar, Vector};
+    let scalar = |name: &str| scalar(inputs, name);
+    let vector = |name: &str| vector(inputs, name);
+    let op = || string(inputs, "op");
+
+    Ok(match op_name {
+        "MakeScalar" => vec![("x", Scalar(scalar("x")?))],
+        "MakeVector" => vec![(
+            "v",
+            Vector(Vec3::new(scalar("x")?, scalar("y")?, scalar("z")?)),
+        )],
+        "SeparateVector" => {
+            let v = vector("v")?;
+            vec![("x", Scalar(v.x)), ("y", Scalar(v.y)), ("z", Scalar(v.z))]
+        }
+        "ScalarMath" => vec![(
+            "out",
+            Scalar(scalar_math(op()?, scalar("x")?, scalar("y")?)?),
+        )],
+        "VectorMath" => vec![(
+            "out",
+            Vector(vector_math(op()?, vector("vec_a")?, vector("vec_b")?)?),
+        )],
+        "VectorScalarMath" => vec![(
+            "out",
+            Scalar(vector_scalar_math(
+                op()?,
+                vector("vec_a")?,
+                vector("vec_b")?,
+            )?),
+        )],
+        "Clamp" => {
+            let (min, max) = (scalar("min")?, scalar("max")?);
+            vec![("out", Scalar(scalar("x")?.max(min).min(max)))]
+        }
+        "MapRange" => vec![(
+            "out",
+            Scalar(map_range(
+                scalar("x")?,
+                scalar("from_min")?,
+                scalar("from_max")?,
+                scalar("to_min")?,
+                scalar("to_max")?,
+                string(inputs, "clamp")? == "Clamped",
+            )),
+        )],
+        "Compare" => vec![(
+            "out",
+            Scalar(boolean(compare(
+                op()?,
+                scalar("x")?,
+                scalar("y")?,
+                scalar("epsilon")?,
+            )?)),
+        )],
+        "Logic" => vec![(
+            "out",
+            Scalar(boolean(logic(
+                op()?,
+                scalar("a")? != 0.0,
+                scalar("b")? != 0.0,
+            )?)),
+        )],
+        _ => bail!("{op_name} is not a native node"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(op_name: &str, inputs: &[(&str, BlackjackValue)]) -> NativeOutputs {
+        let inputs = inputs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        run_native_node(op_name, &inputs).unwrap()
+    }
+
+    fn out_scalar(outputs: &NativeOutputs) -> f32 {
+        match outputs[0].1 {
+            BlackjackValue::Scalar(x) => x,
+            _ => panic!("Expected a scalar"),
+        }
+    }
+
+    #[test]
+    fn test_native_nodes() {
+        use BlackjackValue::{Scalar, Vector};
+        let string = |s: &str| BlackjackValue::String(s.into());
+        let op = |name: &str| ("op", string(name));
+
+        let out = run(
+            "ScalarMath",
+            &[op("Div"), ("x", Scalar(1.0)), ("y", Scalar(0.0))],
+        );
+        assert_eq!(out_scalar(&out), 0.0);
+        let out = run(
+            "ScalarMath",
+            &[op("Mod"), ("x", Scalar(-1.0)), ("y", Scalar(3.0))],
+        );
+        assert_eq!(out_scalar(&out), 2.0);
+
+        let out = run(
+            "VectorMath",
+            &[
+                op("Cross"),
+                ("vec_a", Vector(Vec3::X)),
+                ("vec_b", Vector(Vec3::Y)),
+            ],
+        );
+        assert!(matches!(out[0].1, Vector(v) if v == Vec3::Z));
+        let out = run(
+            "VectorMath",
+            &[
+                op("Project"),
+                ("vec_a", Vector(Vec3::X)),
+                ("vec_b", Vector(Vec3::ZERO)),
+            ],
+        );
+        assert!(matches!(out[0].1, Vector(v) if v == Vec3::ZERO));
+        let out = run(
+            "VectorScalarMath",
+            &[
+                op("Length"),
+                ("vec_a", Vector(Vec3::new(3.0, 4.0, 0.0))),
+                ("vec_b", Vector(Vec3::ZERO)),
+            ],
+        );
+        assert_eq!(out_scalar(&out), 5.0);
+
+        let map = |x: f32, clamp: &str| {
+            out_scalar(&run(
+                "MapRange",
+                &[
+                    ("x", Scalar(x)),
+                    ("from_min", Scalar(1.0)),
+                    ("from_max", Scalar(3.0)),
+                    ("to_min", Scalar(0.0)),
+                    ("to_max", Scalar(10.0)),
+                    ("clamp", string(clamp)),
+                ],
+            ))
+        };
+        assert_eq!(map(2.0, "Unclamped"), 5.0);
+        assert_eq!(map(5.0, "Unclamped"), 20.0);
+        assert_eq!(map(5.0, "Clamped"), 10.0);
+
+        let compare = |name: &str, x: f32, y: f32| {
+            out_scalar(&run(
+                "Compare",
+                &[
+                    op(name),
+                    ("x", Scalar(x)),
+                    ("y", Scalar(y)),
+                    ("epsilon", Scalar(0.01)),
+                ],
+            ))
+        };
+        assert_eq!(compare("Less", 1.0, 2.0), 1.0);
+        assert_eq!(compare("Equal", 1.0, 1.005), 1.0);
+        assert_eq!(compare("NotEqual", 1.0, 1.005), 0.0);
+
+        let out = run(
+            "Logic",
+            &[op("Xor"), ("a", Scalar(1.0)), ("b", Scalar(2.0))],
+        );
+        assert_eq!(out_scalar(&out), 0.0);
+
+        let inputs = [("x", Scalar(1.0))].map(|(k, v)| (k.to_string(), v)).into();
+        assert!(run_native_node("ScalarMath", &inputs).is_err());
+        assert!(run_native_node("MakeBox", &inputs).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// How values are interpolated between two consecutive keys of a [`Ramp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RampInterpolation {
+    /// Holds the value of the previous key until the next key is reached.
+    Constant,
+    Linear,
+    /// Like linear, but eases in and out of each key.
+    Smooth,
+}
+
+impl RampInterpolation {
+    pub fn all() -> [RampInterpolation; 3] {
+        [Self::Constant, Self::Linear, Self::Smooth]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RampInterpolation::Constant => "constant",
+            RampInterpolation::Linear => "linear",
+            RampInterpolation::Smooth => "smooth",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::all()
+            .into_iter()
+            .find(|i| i.name() == name)
+            .ok_or_else(|| anyhow!("Invalid ramp interpolation: {name}"))
+    }
+}
+
+/// A single control point of a [`Ramp`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RampKey {
+    pub position: f32,
+    pub value: Vec3,
+}
+
+/// A ramp maps a position (typically in the 0..1 range) to a value by
+/// interpolating between a list of keys. Ramps are used to drive gradients,
+/// falloffs and profiles.
+///
+/// Key values are always stored as vectors. Scalar ramps (curves) store the
+/// same value in all three coordinates and are sampled with [`Ramp::sample`],
+/// while color ramps (gradients) are sampled with [`Ramp::sample_color`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ramp {
+    /// The keys of this ramp, sorted by position.
+    pub keys: Vec<RampKey>,
+    pub interpolation: RampInterpolation,
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Self::linear(Vec3::ZERO, Vec3::ONE)
+    }
+}
+
+impl Ramp {
+    /// Creates a new ramp from the given `keys`, in any order.
+    pub fn new(keys: Vec<RampKey>, interpolation: RampInterpolation) -> Self {
+        let mut ramp = Self {
+            keys,
+            interpolation,
+        };
+        ramp.sort_keys();
+        ramp
+    }
+
+    /// A linear ramp going from `from` at position 0 to `to` at position 1.
+    pub fn linear(from: Vec3, to: Vec3) -> Self {
+        Self {
+            keys: vec![
+                RampKey {
+                    position: 0.0,
+                    value: from,
+                },
+                RampKey {
+                    position: 1.0,
+                    value: to,
+                },
+            ],
+            interpolation: RampInterpolation::Linear,
+        }
+    }
+
+    /// Restores the key ordering invariant. Must be called after editing the
+    /// key positions directly.
+    pub fn sort_keys(&mut self) {
+        self.keys.sort_by(|a, b| a.position.total_cmp(&b.position));
+    }
+
+    /// Samples the ramp at position `t`. Positions outside the range of the
+    /// keys take the value of the first or last key.
+    pub fn sample_color(&self, t: f32) -> Vec3 {
+        let (first, last) = match (self.keys.first(), self.keys.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Vec3::ZERO,
+        };
+        if t.is_nan() || t <= first.position {
+            return first.value;
+        }
+        if t >= last.position {
+            return last.value;
+        }
+
+        let (a, b) = self
+            .keys
+            .iter()
+            .tuple_windows()
+            .find(|(_, b)| t < b.position)
+            .expect("t is strictly inside the key range");
+
+        let span = b.position - a.position;
+        if span <= f32::EPSILON {
+            return b.value;
+        }
+        let u = (t - a.position) / span;
+        match self.interpolation {
+            RampInterpolation::Constant => a.value,
+            RampInterpolation::Linear => a.value.lerp(b.value, u),
+            RampInterpolation::Smooth => a.value.lerp(b.value, u * u * (3.0 - 2.0 * u)),
+        }
+    }
+
+    /// Samples a scalar ramp at position `t`.
+    pub fn sample(&self, t: f32) -> f32 {
+        self.sample_color(t).x
+    }
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+
+    /// Returns a linear scalar ramp going from `from` at position 0 to `to` at
+    /// position 1.
+    #[lua(under = "Ramp")]
+    pub fn linear(from: f32, to: f32) -> Ramp {
+        Ramp::linear(Vec3::splat(from), Vec3::splat(to))
+    }
+
+    /// Returns a linear color ramp going from color `from` at position 0 to
+    /// color `to` at position 1.
+    #[lua(under = "Ramp")]
+    pub fn linear_color(from: LVec3, to: LVec3) -> Ramp {
+        Ramp::linear(from.0, to.0)
+    }
+
+    #[lua_impl]
+    impl Ramp {
+        /// Samples this ramp at position `t`, returning a scalar.
+        #[lua]
+        pub fn sample(&self, t: f32) -> f32;
+
+        /// Samples this ramp at position `t`, returning a color vector.
+        #[lua(map = "LVec3(x)")]
+        pub fn sample_color(&self, t: f32) -> Vec3;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_ramp_sampling() {
+        let ramp = Ramp::new(
+            vec![
+                RampKey {
+                    position: 1.0,
+                    value: Vec3::splat(2.0),
+                },
+                RampKey {
+                    position: 0.0,
+                    value: Vec3::ZERO,
+                },
+                RampKey {
+                    position: 0.5,
+                    value: Vec3::splat(1.0),
+                },
+            ],
+            RampInterpolation::Linear,
+        );
+
+        assert_eq!(ramp.sample(-1.0), 0.0);
+        assert_eq!(ramp.sample(0.25), 0.5);
+        assert_eq!(ramp.sample(0.75), 1.5);
+        assert_eq!(ramp.sample(2.0), 2.0);
+
+        let constant = Ramp {
+            interpolation: RampInterpolation::Constant,
+            ..ramp
+        };
+        assert_eq!(constant.sample(0.25), 0.0);
+        assert_eq!(constant.sample(0.5), 1.0);
+    }
+}
@@ -5,7 +5,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     io::{BufRead, BufReader, BufWriter, Write},
     path::Path,
 };
@@ -18,11 +18,13 @@ use slotmap::{SecondaryMap, SlotMap};
 
 use crate::{
     graph_interpreter::{ExternalParameter, ExternalParameterValues},
+    materials::MaterialRegistry,
     prelude::selection::SelectionExpression,
+    reference_images::ReferenceImage,
 };
 
 use super::{
-    BjkGraph, BjkNode, BjkNodeId, BjkSnippet, BlackjackValue, DataType, DependencyKind,
+    ramp::Ramp, BjkGraph, BjkNode, BjkNodeId, BjkSnippet, BlackjackValue, DataType, DependencyKind,
     InputParameter, Output,
 };
 
@@ -125,7 +127,7 @@ pub struct SerializedUiData {
     pub locked_gizmo_nodes: Vec<usize>,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SerializedParamLocation {
     pub node_idx: usize,
     pub param_name: String,
@@ -137,11 +139,13 @@ pub enum SerializedBlackjackValue {
     Scalar(f32),
     String(String),
     Selection(String),
+    Ramp(Ramp),
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SerializedExternalParameters {
-    pub param_values: HashMap<SerializedParamLocation, SerializedBlackjackValue>,
+    /// Stored sorted, so serializing the same graph twice gives the same output.
+    pub param_values: BTreeMap<SerializedParamLocation, SerializedBlackjackValue>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -150,6 +154,12 @@ pub struct SerializedBjkGraph {
     pub default_node: Option<usize>,
     pub ui_data: Option<SerializedUiData>,
     pub external_parameters: Option<SerializedExternalParameters>,
+    /// The materials defined in the project. Missing in older files.
+    #[serde(default)]
+    pub materials: MaterialRegistry,
+    /// The images shown in the viewport as a guide. Missing in older files.
+    #[serde(default)]
+    pub reference_images: Vec<ReferenceImage>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -223,6 +233,13 @@ impl SerializedBjkGraph {
         Ok(())
     }
 
+    pub fn into_string(&self) -> Result<String> {
+        let mut w = BufWriter::new(Vec::<u8>::new());
+        SerializationVersion::latest().to_writer(&mut w)?;
+        ron::ser::to_writer_pretty(&mut w, self, PrettyConfig::default())?;
+        Ok(String::from_utf8(w.into_inner()?)?)
+    }
+
     pub fn from_runtime(runtime_data: RuntimeData) -> Result<(Self, IdMappings)> {
         let RuntimeData {
             graph,
@@ -251,6 +268,8 @@ impl SerializedBjkGraph {
                     None
                 },
                 ui_data: None,
+                materials: MaterialRegistry::default(),
+                reference_images: Vec::new(),
             },
             mappings,
         ))
@@ -259,6 +278,14 @@ impl SerializedBjkGraph {
     pub fn set_ui_data(&mut self, ui_data: SerializedUiData) {
         self.ui_data = Some(ui_data);
     }
+
+    pub fn set_materials(&mut self, materials: MaterialRegistry) {
+        self.materials = materials;
+    }
+
+    pub fn set_reference_images(&mut self, reference_images: Vec<ReferenceImage>) {
+        self.reference_images = reference_images;
+    }
 }
 
 impl SerializedBjkSnippet {
@@ -327,7 +354,7 @@ impl SerializedExternalParameters {
         external_param_values: ExternalParameterValues,
         mapping: &IdMappings,
     ) -> Result<SerializedExternalParameters> {
-        let mut param_values = HashMap::new();
+        let mut param_values = BTreeMap::new();
         for (loc, value) in external_param_values.0 {
             if let Some(val) = SerializedBlackjackValue::from_runtime(value.clone()) {
                 let ExternalParameter {
@@ -355,6 +382,7 @@ impl SerializedBlackjackValue {
             BlackjackValue::Scalar(s) => Some(Self::Scalar(s)),
             BlackjackValue::String(s) => Some(Self::String(s)),
             BlackjackValue::Selection(s, _) => Some(Self::Selection(s)),
+            BlackjackValue::Ramp(r) => Some(Self::Ramp(r)),
             BlackjackValue::None => None,
         }
     }
@@ -395,6 +423,7 @@ fn serialize_data_type(data_type: DataType) -> String {
         super::DataType::Mesh => "BJK_MESH",
         super::DataType::String => "BJK_STRING",
         super::DataType::HeightMap => "BJK_HEIGHTMAP",
+        super::DataType::Ramp => "BJK_RAMP",
     }
     .to_owned()
 }
@@ -578,6 +607,7 @@ fn deserialize_data_type(data_type_str: &str) -> Option<DataType> {
         "BJK_MESH" => Some(super::DataType::Mesh),
         "BJK_STRING" => Some(super::DataType::String),
         "BJK_HEIGHTMAP" => Some(super::DataType::HeightMap),
+        "BJK_RAMP" => Some(super::DataType::Ramp),
         _ => None,
     }
     .to_owned()
@@ -602,6 +632,7 @@ impl SerializedExternalParameters {
                                 let expr = SelectionExpression::parse(&x).ok();
                                 BlackjackValue::Selection(x, expr)
                             }
+                            SerializedBlackjackValue::Ramp(x) => BlackjackValue::Ramp(x),
                         },
                     ))
                 })
@@ -4,13 +4,28 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use mlua::{Table, ToLua};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::Instant;
+
+use mlua::{Table, ThreadStatus, ToLua};
 use slotmap::SecondaryMap;
 
+use crate::events::{EngineEvent, EventBus, EventKind};
 use crate::gizmos::BlackjackGizmo;
-use crate::graph::{BjkGraph, BjkNodeId, BlackjackValue, NodeDefinitions};
+use crate::graph::file_path::{resolve_file_path, validate_file_path};
+use crate::graph::native_nodes::{run_native_node, NativeOutputs};
+use crate::graph::{
+    BjkGraph, BjkNodeId, BlackjackValue, InputValueConfig, NodeDefinition, NodeDefinitions,
+};
 use crate::lua_engine::{ProgramResult, RenderableThing};
+use crate::mesh::halfedge::lazy_mesh::LazyMesh;
+use crate::mesh::heightmap::HeightMap;
 use crate::prelude::*;
+use crate::progress::{ProgressSink, ProgressTracker};
+use crate::time::FrameContext;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct ExternalParameter {
@@ -41,6 +56,10 @@ pub struct InterpreterContext<'a, 'lua> {
     /// Stores the gizmo outputs for each node. This is not filled if
     /// gizmo_state is None.
     gizmo_outputs: &'a mut SecondaryMap<BjkNodeId, Vec<BlackjackGizmo>>,
+    /// Relative file path parameters are resolved against this folder.
+    base_dir: Option<&'a Path>,
+    /// Set when a generator node yielded new outputs during this run.
+    generators_advanced: bool,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -56,6 +75,7 @@ pub fn run_graph(
     mut external_param_values: ExternalParameterValues,
     node_definitions: &NodeDefinitions,
     gizmos_state: Option<SecondaryMap<BjkNodeId, GizmoState>>,
+    base_dir: Option<&Path>,
 ) -> Result<ProgramResult> {
     let gizmos_enabled = gizmos_state.is_some();
 
@@ -66,11 +86,31 @@ pub fn run_graph(
         node_definitions,
         gizmo_state: gizmos_state,
         gizmo_outputs: &mut gizmo_outputs,
+        base_dir,
+        generators_advanced: false,
     };
 
     // Ensure the outputs cache is populated.
     run_node(lua, graph, &mut context, target_node)?;
 
+    // Forget about the generators and constants of nodes that are no longer
+    // in the graph.
+    with_app_data(lua, |states: &mut GeneratorStates| {
+        states
+            .0
+            .retain(|node_id, _| graph.nodes.contains_key(*node_id))
+    });
+    with_app_data(lua, |constants: &mut FoldedConstants| {
+        constants
+            .0
+            .retain(|node_id, _| graph.nodes.contains_key(*node_id))
+    });
+    with_app_data(lua, |cooked: &mut CookedOutputs| {
+        cooked
+            .0
+            .retain(|node_id, _| graph.nodes.contains_key(*node_id))
+    });
+
     let renderable = if let Some(return_value) = &graph.nodes[target_node].return_value {
         let output = context
             .outputs_cache
@@ -83,6 +123,20 @@ pub fn run_graph(
         None
     };
 
+    if let Some(bus) = EventBus::current(lua).filter(|_| renderable.is_some()) {
+        if bus.has_subscribers(EventKind::MeshChanged) {
+            let mut hasher = DefaultHasher::new();
+            input_fingerprint(graph, target_node, context.external_param_values).hash(&mut hasher);
+            FrameContext::current(lua).frame.hash(&mut hasher);
+            let changed = bus.update_last_run(target_node, hasher.finish());
+            if changed || context.generators_advanced {
+                bus.emit(EngineEvent::MeshChanged {
+                    node_id: target_node,
+                });
+            }
+        }
+    }
+
     Ok(ProgramResult {
         renderable,
         updated_gizmos: if gizmos_enabled {
@@ -94,6 +148,255 @@ pub fn run_graph(
     })
 }
 
+/// Runs the graph up to `target_node` and returns the value of its output
+/// named `output_name`. Unlike `run_graph`, the node doesn't need to be the
+/// final node of the graph, so this can be used to inspect or export any
+/// intermediate result. Gizmos are not run.
+pub fn run_node_output(
+    lua: &mlua::Lua,
+    graph: &BjkGraph,
+    target_node: BjkNodeId,
+    output_name: &str,
+    mut external_param_values: ExternalParameterValues,
+    node_definitions: &NodeDefinitions,
+    base_dir: Option<&Path>,
+) -> Result<RenderableThing> {
+    let mut gizmo_outputs = Default::default();
+    let mut context = InterpreterContext {
+        outputs_cache: Default::default(),
+        external_param_values: &mut external_param_values,
+        node_definitions,
+        gizmo_state: None,
+        gizmo_outputs: &mut gizmo_outputs,
+        base_dir,
+        generators_advanced: false,
+    };
+
+    run_node(lua, graph, &mut context, target_node)?;
+
+    let output = context
+        .outputs_cache
+        .get(&target_node)
+        .expect("Target node should be in the outputs cache");
+    let value: mlua::Value = output.get(output_name)?;
+    if let mlua::Value::Nil = value {
+        bail!(
+            "Node {} has no output named '{output_name}'",
+            target_node.display_id()
+        );
+    }
+    // The caller wants the real result, not a placeholder
+    RenderableThing::from_lua_value(load_lazy_value(lua, value)?)
+}
+
+/// Runs a single node in isolation, without building a graph. The node is
+/// identified by its `op_name`, and receives the given `inputs`, indexed by
+/// parameter name. Inputs that are not given take the default value from the
+/// node definition, except for meshes and heightmaps, which are required.
+///
+/// Returns the table of outputs of the node. Gizmos are not run, file paths
+/// are passed to the node as-is, and generator nodes are run to completion.
+/// This is meant for tests, the Lua console and external tools.
+pub fn cook_node<'lua>(
+    lua: &'lua mlua::Lua,
+    node_definitions: &NodeDefinitions,
+    op_name: &str,
+    mut inputs: HashMap<String, mlua::Value<'lua>>,
+) -> Result<Table<'lua>> {
+    let node_def = node_definitions
+        .node_def(op_name)
+        .ok_or_else(|| anyhow!("Node definition not found for {op_name}"))?;
+
+    let input_map = lua.create_table()?;
+    for input_def in &node_def.inputs {
+        let value = match inputs.remove(&input_def.name) {
+            Some(value) => load_lazy_value(lua, value)?,
+            None => match input_def.default_value() {
+                BlackjackValue::None => bail!(
+                    "Input '{}' of node {op_name} has no default value and must be given",
+                    input_def.name
+                ),
+                default => default.to_lua(lua)?,
+            },
+        };
+        input_map.set(input_def.name.as_str(), value)?;
+    }
+    if let Some(name) = inputs.keys().next() {
+        bail!("Node {op_name} has no input named '{name}'");
+    }
+    if node_def.native {
+        let outputs = run_native_node(op_name, &native_inputs(&node_def, &input_map)?)?;
+        return native_outputs_to_lua(lua, &outputs);
+    }
+
+    let node_table = lua
+        .load(&(format!("require('node_library'):getNode('{op_name}')")))
+        .eval::<mlua::Table>()?;
+    let op_fn: mlua::Function = node_table
+        .get("op")
+        .map_err(|err| anyhow!("Node should always have an 'op'. {err}"))?;
+
+    let outputs = if node_def.generator {
+        let thread = lua.create_thread(op_fn)?;
+        let mut outputs = None;
+        while thread.status() == ThreadStatus::Resumable {
+            outputs = Some(thread.resume::<_, mlua::Value>(input_map.clone())?);
+        }
+        outputs.unwrap_or(mlua::Value::Nil)
+    } else {
+        op_fn.call(input_map)?
+    };
+    match outputs {
+        mlua::Value::Table(t) => Ok(t),
+        other => bail!("A node's `op` function should always return a table, got {other:?}"),
+    }
+}
+
+/// Lazy meshes are only loaded when their geometry is needed. This returns
+/// the loaded mesh when `value` is a lazy mesh, or `value` as-is otherwise.
+fn load_lazy_value<'lua>(
+    lua: &'lua mlua::Lua,
+    value: mlua::Value<'lua>,
+) -> Result<mlua::Value<'lua>> {
+    match value {
+        mlua::Value::UserData(lazy) if lazy.is::<LazyMesh>() => {
+            let mesh = lazy
+                .borrow::<LazyMesh>()?
+                .load(ProgressTracker::current(lua).as_ref())?;
+            Ok(mlua::Value::UserData(lua.create_userdata(mesh)?))
+        }
+        other => Ok(other),
+    }
+}
+
+/// Reads the inputs of a native node from the `input_map` that would be sent
+/// to a Lua `op`.
+fn native_inputs(
+    node_def: &NodeDefinition,
+    input_map: &Table,
+) -> Result<HashMap<String, BlackjackValue>> {
+    node_def
+        .inputs
+        .iter()
+        .map(|input| Ok((input.name.clone(), input_map.get(input.name.as_str())?)))
+        .collect()
+}
+
+/// Converts the outputs of a native node to a table, like the ones returned
+/// by Lua `op`s.
+fn native_outputs_to_lua<'lua>(
+    lua: &'lua mlua::Lua,
+    outputs: &NativeOutputs,
+) -> Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    for (name, value) in outputs {
+        table.set(*name, value.clone().to_lua(lua)?)?;
+    }
+    Ok(table)
+}
+
+/// Returns whether `node_id` is a native node whose inputs only come from
+/// external parameters or other such nodes. The outputs of these nodes only
+/// change along with their parameters, so they can be folded into constants.
+fn is_constant(
+    graph: &BjkGraph,
+    node_definitions: &NodeDefinitions,
+    node_id: BjkNodeId,
+    known: &mut HashMap<BjkNodeId, bool>,
+) -> bool {
+    if let Some(constant) = known.get(&node_id) {
+        return *constant;
+    }
+    let node = &graph.nodes[node_id];
+    let native = node_definitions
+        .node_def(&node.op_name)
+        .map_or(false, |def| def.native);
+    let constant = native
+        && node.inputs.iter().all(|input| match &input.kind {
+            crate::graph::DependencyKind::Connection { node, .. } => {
+                is_constant(graph, node_definitions, *node, known)
+            }
+            crate::graph::DependencyKind::External { .. } => true,
+        });
+    known.insert(node_id, constant);
+    constant
+}
+
+/// The outputs of the native nodes folded into constants, stored as Lua app
+/// data so they outlive a single cook. Each one comes with the fingerprint of
+/// the inputs it was computed from.
+#[derive(Default)]
+struct FoldedConstants(HashMap<BjkNodeId, (u64, NativeOutputs)>);
+
+/// The outputs of the nodes cooked during interactive cooks, stored as Lua
+/// app data so they outlive a single cook. When a node is edited from the
+/// viewport, only that node and the ones downstream of it cook again, and the
+/// rest reuse their outputs from here. Each one comes with the key it was
+/// cooked with, see [`cache_key`].
+#[derive(Default)]
+struct CookedOutputs(HashMap<BjkNodeId, (u64, mlua::RegistryKey)>);
+
+/// Forgets the outputs kept across interactive cooks. This should be called
+/// when something the graph doesn't know about changes, like the code of the
+/// node library after a hot reload.
+pub fn clear_cooked_outputs(lua: &mlua::Lua) {
+    with_app_data(lua, |cooked: &mut CookedOutputs| cooked.0.clear());
+}
+
+/// Returns the key the outputs of `node_id` are kept with across interactive
+/// cooks, or None when they can't be kept. Generators and nodes with gizmos
+/// in this cook change without their inputs changing, so neither them nor
+/// the nodes downstream of them are kept.
+///
+/// Besides the fingerprint of the inputs, the key covers the current frame
+/// and when the files read by the node and its dependencies were modified,
+/// so changes to those files on disk are picked up.
+fn cache_key(
+    lua: &mlua::Lua,
+    graph: &BjkGraph,
+    ctx: &InterpreterContext,
+    node_id: BjkNodeId,
+) -> Option<u64> {
+    let gizmo_state = ctx.gizmo_state.as_ref()?;
+    let mut hasher = DefaultHasher::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![node_id];
+    while let Some(id) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let node = &graph.nodes[id];
+        let node_def = ctx.node_definitions.node_def(&node.op_name)?;
+        if node_def.generator || gizmo_state.contains_key(id) {
+            return None;
+        }
+        for input in &node.inputs {
+            match &input.kind {
+                crate::graph::DependencyKind::Connection { node, .. } => stack.push(*node),
+                crate::graph::DependencyKind::External { .. } => {
+                    let is_file = node_def.inputs.iter().any(|i| {
+                        i.name == input.name
+                            && matches!(i.config, InputValueConfig::FilePath { .. })
+                    });
+                    let ext = ExternalParameter::new(id, input.name.clone());
+                    if let (true, Some(BlackjackValue::String(path))) =
+                        (is_file, ctx.external_param_values.0.get(&ext))
+                    {
+                        let path = resolve_file_path(path, ctx.base_dir);
+                        std::fs::metadata(path)
+                            .and_then(|metadata| metadata.modified())
+                            .ok()
+                            .hash(&mut hasher);
+                    }
+                }
+            }
+        }
+    }
+    input_fingerprint(graph, node_id, ctx.external_param_values).hash(&mut hasher);
+    FrameContext::current(lua).frame.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
 pub fn run_node<'lua>(
     lua: &'lua mlua::Lua,
     graph: &BjkGraph,
@@ -107,6 +410,52 @@ pub fn run_node<'lua>(
         .node_def(op_name)
         .ok_or_else(|| anyhow!("Node definition not found for {op_name}"))?;
 
+    // Constant native nodes are only computed again when their parameters
+    // change. Their dependencies don't even need to run.
+    let fold_fingerprint = if node_def.native
+        && is_constant(graph, ctx.node_definitions, node_id, &mut HashMap::new())
+    {
+        let fingerprint = input_fingerprint(graph, node_id, ctx.external_param_values);
+        let folded = with_app_data(lua, |constants: &mut FoldedConstants| {
+            constants
+                .0
+                .get(&node_id)
+                .filter(|(f, _)| *f == fingerprint)
+                .map(|(_, outputs)| outputs.clone())
+        });
+        if let Some(outputs) = folded {
+            ctx.outputs_cache
+                .insert(node_id, native_outputs_to_lua(lua, &outputs)?);
+            return Ok(());
+        }
+        Some(fingerprint)
+    } else {
+        None
+    };
+
+    // On interactive cooks, nodes that didn't change since the last cook
+    // reuse their outputs, so their dependencies don't need to run either.
+    let cook_key = if node_def.native {
+        None
+    } else {
+        cache_key(lua, graph, ctx, node_id)
+    };
+    if let Some(key) = cook_key {
+        let cooked = with_app_data(lua, |cooked: &mut CookedOutputs| {
+            cooked
+                .0
+                .get(&node_id)
+                .filter(|(k, _)| *k == key)
+                .map(|(_, outputs)| lua.registry_value::<Table>(outputs))
+        })
+        .transpose()?;
+        if let Some(outputs) = cooked {
+            ctx.outputs_cache
+                .insert(node_id, copy_outputs(lua, &outputs)?);
+            return Ok(());
+        }
+    }
+
     // Stores the arguments that will be sent to this node's `op` fn
     let mut input_map = lua.create_table()?;
 
@@ -132,10 +481,8 @@ pub fn run_node<'lua>(
                         .expect("Cache should be populated after calling run_node.")
                 };
 
-                input_map.set(
-                    input.name.as_str(),
-                    cached_output_map.get::<_, mlua::Value>(param_name.as_str())?,
-                )?;
+                let value = cached_output_map.get::<_, mlua::Value>(param_name.as_str())?;
+                input_map.set(input.name.as_str(), load_lazy_value(lua, value)?)?;
             }
             crate::graph::DependencyKind::External { promoted: _ } => {
                 let ext = ExternalParameter::new(node_id, input.name.clone());
@@ -146,14 +493,73 @@ pub fn run_node<'lua>(
                         node_id.display_id(),
                     )
                 })?;
-                input_map.set(input.name.as_str(), val.clone().to_lua(lua)?)?;
-                if let Some(m) = &mut referenced_external_params {
-                    m.push(ext);
+                let config = node_def
+                    .inputs
+                    .iter()
+                    .find(|i| i.name == input.name)
+                    .map(|i| &i.config);
+                if let (
+                    BlackjackValue::String(path),
+                    Some(InputValueConfig::FilePath {
+                        file_path_mode,
+                        extensions,
+                        ..
+                    }),
+                ) = (val, config)
+                {
+                    // File paths are resolved and validated before reaching
+                    // the node, so nodes can use them as-is. They are not
+                    // written back from gizmos, to avoid storing the resolved
+                    // path in place of the original one.
+                    //
+                    // An empty path means no file was selected. It reaches the
+                    // node unchanged, since the file may be optional. Nodes
+                    // that read it report the error themselves.
+                    if path.is_empty() {
+                        input_map.set(input.name.as_str(), "")?;
+                        continue;
+                    }
+                    let path = resolve_file_path(path, ctx.base_dir);
+                    validate_file_path(&path, *file_path_mode, extensions).map_err(|err| {
+                        anyhow!(
+                            "Invalid path in parameter '{}' from node {}. {err}",
+                            &input.name,
+                            node_id.display_id(),
+                        )
+                    })?;
+                    input_map.set(input.name.as_str(), path.to_string_lossy().as_ref())?;
+                } else {
+                    input_map.set(input.name.as_str(), val.clone().to_lua(lua)?)?;
+                    if let Some(m) = &mut referenced_external_params {
+                        m.push(ext);
+                    }
                 }
             }
         }
     }
 
+    let events = EventBus::current(lua);
+
+    if node_def.native {
+        let cook_start = Instant::now();
+        let outputs = run_native_node(op_name, &native_inputs(&node_def, &input_map)?)?;
+        if let Some(bus) = &events {
+            bus.emit(EngineEvent::NodeCooked {
+                node_id,
+                op_name: op_name.clone(),
+                duration: cook_start.elapsed(),
+            });
+        }
+        if let Some(fingerprint) = fold_fingerprint {
+            with_app_data(lua, |constants: &mut FoldedConstants| {
+                constants.0.insert(node_id, (fingerprint, outputs.clone()))
+            });
+        }
+        ctx.outputs_cache
+            .insert(node_id, native_outputs_to_lua(lua, &outputs)?);
+        return Ok(());
+    }
+
     // This special value is injected into the inputs to signal nodes that the
     // gizmos are being processed. This is useful to let nodes optimize out
     // parts of the computation when they're running on a game engine.
@@ -289,6 +695,12 @@ pub fn run_node<'lua>(
                     .0
                     .get_mut(param)
                     .expect("Should be there") = new_val;
+                if let Some(bus) = &events {
+                    bus.emit(EngineEvent::ParamChanged {
+                        node_id,
+                        param_name: param.param_name.clone(),
+                    });
+                }
             }
         }
     }
@@ -297,14 +709,34 @@ pub fn run_node<'lua>(
     let op_fn: mlua::Function = node_table
         .get("op")
         .map_err(|err| anyhow!("Node should always have an 'op'. {err}"))?;
-    let outputs = match op_fn.call(input_map.clone())? {
-        mlua::Value::Table(t) => t,
-        other => {
-            bail!("A node's `op` function should always return a table, got {other:?}");
+    let cook_start = Instant::now();
+    let outputs = if node_def.generator {
+        run_generator(lua, graph, ctx, node_id, op_fn, input_map.clone())?
+    } else {
+        match op_fn.call(input_map.clone())? {
+            mlua::Value::Table(t) => t,
+            other => {
+                bail!("A node's `op` function should always return a table, got {other:?}");
+            }
         }
     };
 
+    if let Some(bus) = &events {
+        bus.emit(EngineEvent::NodeCooked {
+            node_id,
+            op_name: op_name.clone(),
+            duration: cook_start.elapsed(),
+        });
+    }
+
     ctx.outputs_cache.insert(node_id, outputs.clone());
+    if let Some(key) = cook_key {
+        // The host takes ownership of the meshes it renders, so a copy is kept
+        let kept = lua.create_registry_value(copy_outputs(lua, &outputs)?)?;
+        with_app_data(lua, |cooked: &mut CookedOutputs| {
+            cooked.0.insert(node_id, (key, kept))
+        });
+    }
 
     // Run post-gizmo
     for (gz_descr, enabled) in gizmo_descriptors.iter_mut().zip(&enabled_gizmos) {
@@ -338,3 +770,174 @@ pub fn run_node<'lua>(
 
     Ok(())
 }
+
+/// The state of a generator node between cooks.
+struct GeneratorState {
+    /// The fingerprint of the inputs the generator was started with. The
+    /// generator starts over when this changes.
+    fingerprint: u64,
+    thread: mlua::RegistryKey,
+    /// The last outputs yielded or returned by the generator.
+    outputs: mlua::RegistryKey,
+}
+
+/// The generators of each node, stored as Lua app data so they outlive a
+/// single cook.
+#[derive(Default)]
+struct GeneratorStates(HashMap<BjkNodeId, GeneratorState>);
+
+/// Runs `f` over the app data of type `T` stored in `lua`, installing its
+/// default value first if needed.
+fn with_app_data<T: Default + 'static, R>(lua: &mlua::Lua, f: impl FnOnce(&mut T) -> R) -> R {
+    if lua.app_data_ref::<T>().is_none() {
+        lua.set_app_data(T::default());
+    }
+    let mut data = lua
+        .app_data_mut::<T>()
+        .expect("App data should be installed");
+    f(&mut data)
+}
+
+/// Hashes everything the inputs of `node_id` depend on: The external
+/// parameters of the node and its dependencies, and how they're connected.
+fn input_fingerprint(
+    graph: &BjkGraph,
+    node_id: BjkNodeId,
+    values: &ExternalParameterValues,
+) -> u64 {
+    fn visit(
+        graph: &BjkGraph,
+        node_id: BjkNodeId,
+        values: &ExternalParameterValues,
+        visited: &mut HashSet<BjkNodeId>,
+        out: &mut String,
+    ) {
+        let node = &graph.nodes[node_id];
+        let _ = write!(out, "{}:{}(", node_id.display_id(), node.op_name);
+        // Shared dependencies are only described the first time.
+        if visited.insert(node_id) {
+            for input in &node.inputs {
+                match &input.kind {
+                    crate::graph::DependencyKind::Connection { node, param_name } => {
+                        let _ = write!(out, "{}<-{param_name}@", input.name);
+                        visit(graph, *node, values, visited, out);
+                    }
+                    crate::graph::DependencyKind::External { .. } => {
+                        let ext = ExternalParameter::new(node_id, input.name.clone());
+                        let _ = write!(out, "{}={:?}", input.name, values.0.get(&ext));
+                    }
+                }
+                out.push(',');
+            }
+        }
+        out.push(')');
+    }
+
+    let mut description = String::new();
+    visit(
+        graph,
+        node_id,
+        values,
+        &mut HashSet::new(),
+        &mut description,
+    );
+    let mut hasher = DefaultHasher::new();
+    description.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Runs the `op` of a generator node. Generators are Lua coroutines that
+/// yield partial outputs, using `coroutine.yield(outputs)`, before returning
+/// their final outputs. Every resume receives the node's inputs again.
+///
+/// On interactive cooks (those that process gizmos), generators are resumed
+/// a single step per cook and the last yielded outputs are used as the
+/// node's result, so the host displays them while the generator runs. Other
+/// cooks run generators to completion. In both cases, generators stop
+/// between yields when the user cancels, and start over when their inputs
+/// change.
+///
+/// NOTE: Yields must come from Lua code. Yielding from inside a callback
+/// called by Rust code (e.g. a selection iterator) is an error.
+fn run_generator<'lua>(
+    lua: &'lua mlua::Lua,
+    graph: &BjkGraph,
+    ctx: &mut InterpreterContext<'_, 'lua>,
+    node_id: BjkNodeId,
+    op_fn: mlua::Function<'lua>,
+    input_map: Table<'lua>,
+) -> Result<Table<'lua>> {
+    let progressive = ctx.gizmo_state.is_some();
+    let progress = ProgressTracker::current(lua);
+    let fingerprint = input_fingerprint(graph, node_id, ctx.external_param_values);
+
+    let state = with_app_data(lua, |states: &mut GeneratorStates| {
+        states.0.remove(&node_id)
+    })
+    .filter(|state| state.fingerprint == fingerprint);
+    let (thread, mut outputs) = match state {
+        Some(state) => (
+            lua.registry_value::<mlua::Thread>(&state.thread)?,
+            Some(lua.registry_value::<Table>(&state.outputs)?),
+        ),
+        None => (lua.create_thread(op_fn)?, None),
+    };
+
+    while thread.status() == ThreadStatus::Resumable {
+        if progress.is_cancelled() {
+            if progressive && outputs.is_some() {
+                // Keep showing the last partial result
+                break;
+            }
+            progress.check_cancelled()?;
+        }
+        outputs = match thread.resume(input_map.clone())? {
+            mlua::Value::Table(t) => Some(t),
+            other => {
+                bail!("A generator node should always yield or return a table, got {other:?}");
+            }
+        };
+        ctx.generators_advanced = true;
+        if progressive {
+            break;
+        }
+    }
+
+    let outputs = outputs.ok_or_else(|| {
+        anyhow!(
+            "The generator of node {} ended without outputs",
+            node_id.display_id()
+        )
+    })?;
+    let state = GeneratorState {
+        fingerprint,
+        thread: lua.create_registry_value(thread)?,
+        outputs: lua.create_registry_value(outputs.clone())?,
+    };
+    with_app_data(lua, |states: &mut GeneratorStates| {
+        states.0.insert(node_id, state)
+    });
+    copy_outputs(lua, &outputs)
+}
+
+/// Returns a copy of the `outputs` of a node. Generator outputs and the ones
+/// of interactive cooks are kept across cooks, and meshes may be used by a
+/// generator's next step, but the host takes ownership of the meshes it
+/// renders, so these are cloned.
+fn copy_outputs<'lua>(lua: &'lua mlua::Lua, outputs: &Table<'lua>) -> Result<Table<'lua>> {
+    let copy = lua.create_table()?;
+    for pair in outputs.clone().pairs::<mlua::Value, mlua::Value>() {
+        let (key, value) = pair?;
+        let value = match value {
+            mlua::Value::UserData(ud) if ud.is::<HalfEdgeMesh>() => {
+                mlua::Value::UserData(lua.create_userdata(ud.borrow::<HalfEdgeMesh>()?.clone())?)
+            }
+            mlua::Value::UserData(ud) if ud.is::<HeightMap>() => {
+                mlua::Value::UserData(lua.create_userdata(ud.borrow::<HeightMap>()?.clone())?)
+            }
+            other => other,
+        };
+        copy.set(key, value)?;
+    }
+    Ok(copy)
+}
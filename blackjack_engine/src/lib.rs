@@ -25,6 +25,24 @@ pub mod graph_interpreter;
 /// Gizmos allow visual modifications of a node's parameters.
 pub mod gizmos;
 
+/// Named surface materials that can be assigned to mesh faces.
+pub mod materials;
+
+/// Images displayed in the viewport as a modeling guide.
+pub mod reference_images;
+
+/// Layered noise functions for procedural generation.
+pub mod fractal_noise;
+
+/// The current frame, for graphs that change over time.
+pub mod time;
+
+/// Progress reporting and cancellation for long-running operations.
+pub mod progress;
+
+/// Notifications about what the engine does, like cooking nodes.
+pub mod events;
+
 /// Conditional types to allow HalfEdgeMesh et al. be `Send` + `Sync` with the sync feature.
 pub mod sync;
 
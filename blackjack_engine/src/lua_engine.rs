@@ -5,28 +5,33 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::{
-    sync::{
-        mpsc::{self, Receiver},
-        Arc,
-    },
-    time::Duration,
+    path::PathBuf,
+    sync::{mpsc::Receiver, Arc},
 };
+#[cfg(feature = "file_watcher")]
+use std::{sync::mpsc, time::Duration};
 
 use crate::{
+    events::{EngineEvent, EventBus, EventKind},
     gizmos::BlackjackGizmo,
     graph::{BjkNodeId, NodeDefinitions},
     graph_interpreter::ExternalParameterValues,
+    mesh::halfedge::lazy_mesh::{LazyMesh, MeshProxy},
     mesh::heightmap::HeightMap,
     prelude::*,
 };
 use mlua::Lua;
+#[cfg(feature = "file_watcher")]
 use notify::{DebouncedEvent, Watcher};
 use slotmap::SecondaryMap;
 
-use self::lua_stdlib::{load_node_definitions, LuaFileIo, StdLuaFileIo};
+use self::lua_stdlib::{load_node_definitions, BytecodeCache, LuaFileIo, StdLuaFileIo};
 
 pub mod lua_stdlib;
 
+/// Evaluation and pretty-printing for interactive Lua consoles.
+pub mod console;
+
 pub trait ToLuaError<T> {
     fn map_lua_err(self) -> mlua::Result<T>;
 }
@@ -47,6 +52,8 @@ impl<T> ToLuaError<T> for Result<T, TraversalError> {
 pub enum RenderableThing {
     HalfEdgeMesh(HalfEdgeMesh),
     HeightMap(HeightMap),
+    /// The placeholder of an imported mesh that hasn't been loaded yet.
+    MeshProxy(MeshProxy),
 }
 
 impl RenderableThing {
@@ -58,6 +65,9 @@ impl RenderableThing {
             mlua::Value::UserData(renderable) if renderable.is::<HeightMap>() => {
                 Ok(RenderableThing::HeightMap(renderable.take()?))
             }
+            mlua::Value::UserData(renderable) if renderable.is::<LazyMesh>() => Ok(
+                RenderableThing::MeshProxy(renderable.borrow::<LazyMesh>()?.proxy()?),
+            ),
             _ => {
                 bail!("Object {renderable:?} is not a thing we can render.")
             }
@@ -78,6 +88,7 @@ pub struct ProgramResult {
     pub updated_values: ExternalParameterValues,
 }
 
+#[cfg(feature = "file_watcher")]
 pub struct LuaFileWatcher {
     pub watcher: notify::RecommendedWatcher,
     pub watcher_channel: Receiver<notify::DebouncedEvent>,
@@ -86,6 +97,9 @@ pub struct LuaFileWatcher {
 pub struct LuaRuntime {
     pub lua: Lua,
     pub node_definitions: NodeDefinitions,
+    /// Receives the events of the graphs run with this runtime.
+    pub events: Arc<EventBus>,
+    #[cfg(feature = "file_watcher")]
     pub file_watcher: Option<LuaFileWatcher>,
     pub lua_io: Arc<dyn LuaFileIo + 'static>,
 }
@@ -97,6 +111,19 @@ impl LuaRuntime {
     pub fn initialize_with_std(node_libraries_path: String) -> anyhow::Result<LuaRuntime> {
         Self::initialize_custom(StdLuaFileIo {
             base_folder: node_libraries_path,
+            bytecode_cache: None,
+        })
+    }
+
+    /// Same as `initialize_with_std`, but the compiled bytecode of the Lua
+    /// files is cached in `cache_folder`, so later runs start faster.
+    pub fn initialize_with_std_cached(
+        node_libraries_path: String,
+        cache_folder: PathBuf,
+    ) -> anyhow::Result<LuaRuntime> {
+        Self::initialize_custom(StdLuaFileIo {
+            base_folder: node_libraries_path,
+            bytecode_cache: Some(BytecodeCache::new(cache_folder)),
         })
     }
 
@@ -105,15 +132,26 @@ impl LuaRuntime {
         let lua_io = Arc::new(lua_io);
         lua_stdlib::load_lua_bindings(&lua, lua_io.clone())?;
         let node_definitions = NodeDefinitions::new(load_node_definitions(&lua, lua_io.as_ref())?);
+        let events = Arc::new(EventBus::default());
+        events.install(&lua);
 
         Ok(LuaRuntime {
             lua,
             node_definitions,
+            events,
+            #[cfg(feature = "file_watcher")]
             file_watcher: None,
             lua_io,
         })
     }
 
+    /// Returns a receiver for the future events of the given `kind`, like
+    /// nodes being cooked. See [`EventBus`].
+    pub fn subscribe(&self, kind: EventKind) -> Receiver<EngineEvent> {
+        self.events.subscribe(kind)
+    }
+
+    #[cfg(feature = "file_watcher")]
     pub fn start_file_watcher(&mut self) -> Result<()> {
         let (tx, rx) = mpsc::channel();
         let mut watcher = notify::watcher(tx, Duration::from_secs(1))?;
@@ -127,6 +165,7 @@ impl LuaRuntime {
 
     /// Watches the lua source folders for changes. Returns true when a change
     /// was detected and the `NodeDefinitions` were successfully updated.
+    #[cfg(feature = "file_watcher")]
     pub fn watch_for_changes(&mut self) -> anyhow::Result<bool> {
         let file_watcher = self
             .file_watcher
@@ -152,6 +191,8 @@ impl LuaRuntime {
                     // be executed and the node definitions will be reloaded.
                     self.node_definitions
                         .update(load_node_definitions(&self.lua, self.lua_io.as_ref())?);
+                    // Node outputs from previous cooks may come from old code
+                    crate::graph_interpreter::clear_cooked_outputs(&self.lua);
                 }
                 _ => {}
             }
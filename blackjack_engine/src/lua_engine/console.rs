@@ -0,0 +1,134 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use mlua::{Lua, MultiValue, Value};
+
+use crate::{mesh::heightmap::HeightMap, prelude::*};
+
+/// Nested tables deeper than this are not expanded when printing.
+const MAX_DEPTH: usize = 3;
+
+/// Tables with more entries than this are truncated when printing.
+const MAX_ENTRIES: usize = 32;
+
+/// Evaluates a line of Lua code typed in an interactive console, and returns
+/// its results, pretty-printed. The `code` is first evaluated as an
+/// expression, so typing `1 + 2` prints `3`. When it's not a valid expression,
+/// it's run as a statement instead.
+pub fn eval(lua: &Lua, code: &str) -> Result<String> {
+    let values = match lua
+        .load(&format!("return {code}"))
+        .set_name("=console")?
+        .into_function()
+    {
+        Ok(function) => function.call::<_, MultiValue>(())?,
+        Err(_) => lua.load(code).set_name("=console")?.eval::<MultiValue>()?,
+    };
+    Ok(values
+        .iter()
+        .map(|value| pretty_print(lua, value))
+        .join("\n"))
+}
+
+/// Returns a human readable representation of a Lua `value`. Engine types
+/// are summarized instead of showing an opaque userdata, and tables are
+/// expanded a few levels deep.
+pub fn pretty_print(lua: &Lua, value: &Value) -> String {
+    let mut out = String::new();
+    print_value(lua, value, 0, &mut out);
+    out
+}
+
+fn print_value(lua: &Lua, value: &Value, depth: usize, out: &mut String) {
+    match value {
+        Value::Nil => out.push_str("nil"),
+        Value::Boolean(b) => out.push_str(&b.to_string()),
+        Value::Integer(i) => out.push_str(&i.to_string()),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::Vector(x, y, z) => out.push_str(&format!("vector({x}, {y}, {z})")),
+        Value::String(s) => out.push_str(&format!("{:?}", s.to_string_lossy())),
+        Value::Table(table) => print_table(lua, table, depth, out),
+        Value::UserData(ud) => {
+            if let Ok(mesh) = ud.borrow::<HalfEdgeMesh>() {
+                let stats = mesh.stats();
+                out.push_str(&format!(
+                    "HalfEdgeMesh {{ vertices: {}, edges: {}, faces: {} }}",
+                    stats.vertices, stats.edges, stats.faces
+                ));
+            } else if let Ok(heightmap) = ud.borrow::<HeightMap>() {
+                let (width, height) = heightmap.dimensions();
+                out.push_str(&format!("HeightMap {{ width: {width}, height: {height} }}"));
+            } else {
+                out.push_str(&tostring(lua, value));
+            }
+        }
+        _ => out.push_str(&tostring(lua, value)),
+    }
+}
+
+fn print_table(lua: &Lua, table: &mlua::Table, depth: usize, out: &mut String) {
+    if depth >= MAX_DEPTH {
+        out.push_str("{...}");
+        return;
+    }
+    let indent = "  ".repeat(depth + 1);
+    let sequential = table.raw_len() > 0;
+    let mut count = 0;
+    out.push('{');
+    for entry in table.clone().pairs::<Value, Value>() {
+        let (key, value) = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        if count == MAX_ENTRIES {
+            out.push_str(&format!("\n{indent}..."));
+            break;
+        }
+        out.push_str(&format!("\n{indent}"));
+        if !sequential {
+            match &key {
+                Value::String(s) => out.push_str(&s.to_string_lossy()),
+                _ => {
+                    out.push('[');
+                    print_value(lua, &key, depth + 1, out);
+                    out.push(']');
+                }
+            }
+            out.push_str(" = ");
+        }
+        print_value(lua, &value, depth + 1, out);
+        out.push(',');
+        count += 1;
+    }
+    if count > 0 {
+        out.push_str(&format!("\n{}", "  ".repeat(depth)));
+    }
+    out.push('}');
+}
+
+/// Falls back to Lua's `tostring`, which uses the `__tostring` metamethod of
+/// the types that have one.
+fn tostring(lua: &Lua, value: &Value) -> String {
+    lua.globals()
+        .get::<_, mlua::Function>("tostring")
+        .and_then(|tostring| tostring.call::<_, String>(value.clone()))
+        .unwrap_or_else(|_| format!("<{}>", value.type_name()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_console_eval() {
+        let lua = Lua::new();
+        assert_eq!(eval(&lua, "1 + 2").unwrap(), "3");
+        assert_eq!(eval(&lua, "x = 5").unwrap(), "");
+        assert_eq!(eval(&lua, "x, 'a'").unwrap(), "5\n\"a\"");
+        assert_eq!(eval(&lua, "{1, 2}").unwrap(), "{\n  1,\n  2,\n}");
+        assert!(eval(&lua, "error('oops')").is_err());
+    }
+}
@@ -22,6 +22,9 @@ pub use runtime_types::*;
 pub mod lua_require_io;
 pub use lua_require_io::*;
 
+pub mod bytecode_cache;
+pub use bytecode_cache::*;
+
 mod lua_core_library;
 
 pub mod lua_documentation;
@@ -39,6 +42,32 @@ pub struct LuaDocstringData {
 }
 inventory::collect!(LuaDocstringData);
 
+/// Structured metadata about a single item exported to Lua via a `#[lua]`
+/// annotation. Used to build the Lua API reference.
+#[derive(Debug)]
+pub struct LuaApiEntry {
+    /// One of "function", "method" or "constant".
+    pub kind: &'static str,
+    /// The global table (for functions and constants) or class (for methods)
+    /// this entry is exported under.
+    pub parent: &'static str,
+    pub name: &'static str,
+    /// Parameter names and their Rust types, as written in the source.
+    pub params: &'static [(&'static str, &'static str)],
+    /// The Rust return type (or the type of a constant), as written in the
+    /// source.
+    pub returns: &'static str,
+    /// The docstring, one element per line.
+    pub doc: &'static [&'static str],
+}
+
+/// All the API entries for a single annotated module. Stored globally using
+/// `inventory`.
+pub struct LuaApiRegistryData {
+    pub data: &'static [LuaApiEntry],
+}
+inventory::collect!(LuaApiRegistryData);
+
 /// Loads all blackjack Rust function wrappers to the Lua API
 pub fn load_lua_bindings(lua: &Lua, lua_io: Arc<dyn LuaFileIo + 'static>) -> anyhow::Result<()> {
     lua_core_library::load(lua, lua_io)?;
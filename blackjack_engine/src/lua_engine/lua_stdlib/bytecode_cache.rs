@@ -0,0 +1,216 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use super::LuaSourceFile;
+
+/// Stores the compiled bytecode of Lua source files on disk, keyed by a hash
+/// of their contents. Loading the bytecode skips parsing and compiling the
+/// source, which speeds up startup when the node libraries are large.
+///
+/// Bytecode is loaded without the checks done when compiling, so the cache
+/// folder is only used when no other user can access it. Each file is stored
+/// with a checksum of its bytecode and source, and it's only loaded when the
+/// checksum matches.
+///
+/// The cache is only an optimization: Errors reading or writing it are
+/// ignored, and the sources are compiled instead.
+pub struct BytecodeCache {
+    /// The cache folder, or `None` when it can't be used.
+    folder: Option<PathBuf>,
+}
+
+/// A chunk of compiled Luau bytecode
+struct BytecodeChunk<'a> {
+    bytecode: Vec<u8>,
+    name: &'a str,
+}
+
+impl<'lua, 'a> mlua::AsChunk<'lua> for BytecodeChunk<'a> {
+    fn source(&self) -> std::result::Result<Cow<'_, [u8]>, std::io::Error> {
+        Ok(Cow::Borrowed(&self.bytecode))
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.name.into())
+    }
+
+    fn mode(&self) -> Option<mlua::ChunkMode> {
+        Some(mlua::ChunkMode::Binary)
+    }
+}
+
+/// Creates `folder` if needed, so that only the current user can access it.
+/// Fails when an existing folder is accessible to other users.
+fn create_private_folder(folder: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(folder)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(folder)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!(
+                    "The folder {} is accessible to other users",
+                    folder.display()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Returns a digest of `source`. The engine version is part of it, so
+/// bytecode compiled by an older Luau version is not reused after an update.
+fn source_digest(source: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update([0u8]);
+    hasher.update(source);
+    hasher.finalize().into()
+}
+
+/// Returns the checksum stored next to the `bytecode` compiled from the
+/// source with the given digest.
+fn checksum(source_digest: &[u8; 32], bytecode: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(source_digest);
+    hasher.update(bytecode);
+    to_hex(&hasher.finalize())
+}
+
+/// Returns the paths of the bytecode and its checksum, for the source with
+/// the given digest.
+fn cache_paths(folder: &Path, source_digest: &[u8; 32]) -> (PathBuf, PathBuf) {
+    let name = to_hex(&source_digest[..8]);
+    (
+        folder.join(format!("{name}.luac")),
+        folder.join(format!("{name}.sha256")),
+    )
+}
+
+impl BytecodeCache {
+    /// Creates a cache in `folder`. The folder is created, accessible only
+    /// to the current user. When that's not possible, or the folder already
+    /// exists and other users can access it, the cache is disabled.
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        let folder = folder.into();
+        match create_private_folder(&folder) {
+            Ok(()) => Self {
+                folder: Some(folder),
+            },
+            Err(err) => {
+                println!("Lua bytecode cache disabled. {err}");
+                Self { folder: None }
+            }
+        }
+    }
+
+    /// Returns the cached bytecode for the source with the given digest, if
+    /// it's present and matches its checksum.
+    fn read_cached(folder: &Path, source_digest: &[u8; 32]) -> Option<Vec<u8>> {
+        let (bytecode_path, checksum_path) = cache_paths(folder, source_digest);
+        let bytecode = std::fs::read(bytecode_path).ok()?;
+        let expected = std::fs::read_to_string(checksum_path).ok()?;
+        (expected.trim() == checksum(source_digest, &bytecode)).then_some(bytecode)
+    }
+
+    /// Returns the bytecode for `source`. It is read from the cache when
+    /// present, otherwise it's compiled and stored.
+    fn bytecode(folder: &Path, source: &str) -> Vec<u8> {
+        let digest = source_digest(source);
+        if let Some(bytecode) = Self::read_cached(folder, &digest) {
+            return bytecode;
+        }
+        let bytecode = mlua::Compiler::new().compile(source);
+        let (bytecode_path, checksum_path) = cache_paths(folder, &digest);
+        let _ = std::fs::write(bytecode_path, &bytecode)
+            .and_then(|_| std::fs::write(checksum_path, checksum(&digest, &bytecode)));
+        bytecode
+    }
+
+    /// Loads `file` as a Lua function, using the cached bytecode. If the
+    /// cached bytecode can't be loaded, it's removed and the source is loaded
+    /// instead, so syntax errors are reported as usual.
+    pub fn load<'lua>(
+        &self,
+        lua: &'lua mlua::Lua,
+        file: &LuaSourceFile,
+    ) -> mlua::Result<mlua::Function<'lua>> {
+        let folder = match &self.folder {
+            Some(folder) => folder,
+            None => return lua.load(file).into_function(),
+        };
+        let chunk = BytecodeChunk {
+            bytecode: Self::bytecode(folder, &file.contents),
+            name: &file.name,
+        };
+        match lua.load(&chunk).into_function() {
+            Ok(function) => Ok(function),
+            Err(_) => {
+                let (bytecode_path, checksum_path) =
+                    cache_paths(folder, &source_digest(&file.contents));
+                let _ = std::fs::remove_file(bytecode_path);
+                let _ = std::fs::remove_file(checksum_path);
+                lua.load(file).into_function()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_bytecode_cache() {
+        let folder = std::env::temp_dir().join(format!(
+            "blackjack_bytecode_cache_test_{}",
+            std::process::id()
+        ));
+        let cache = BytecodeCache::new(&folder);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&folder).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        let lua = mlua::Lua::new();
+        let file = LuaSourceFile {
+            contents: "return 1 + 1".into(),
+            name: "test".into(),
+        };
+        let run = || cache.load(&lua, &file).unwrap().call::<_, i32>(()).unwrap();
+        assert_eq!(run(), 2);
+        let (bytecode_path, checksum_path) = cache_paths(&folder, &source_digest(&file.contents));
+        assert!(bytecode_path.is_file() && checksum_path.is_file());
+
+        // Bytecode that does not match its checksum is compiled again
+        std::fs::write(&bytecode_path, mlua::Compiler::new().compile("return 3")).unwrap();
+        assert_eq!(run(), 2);
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
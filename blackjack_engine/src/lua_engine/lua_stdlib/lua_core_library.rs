@@ -45,7 +45,9 @@ pub fn load(lua: &Lua, lua_io: Arc<dyn LuaFileIo + 'static>) -> anyhow::Result<(
                     // know this is a regular lua file from the filesystem.
                     {
                         let file_chunk = lua_io.load_file_require(&file).map_lua_err()?;
-                        let value = lua.load(&file_chunk).eval::<mlua::Value>()?;
+                        let value = lua_io
+                            .load_chunk(lua, &file_chunk)?
+                            .call::<_, mlua::Value>(())?;
                         loaded.set(file, value.clone())?;
                         Ok(value)
                     }
@@ -5,10 +5,11 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use anyhow::{bail, Result};
+use itertools::Itertools;
 use std::io::Write;
 use std::{collections::BTreeMap, fs::File, path::PathBuf};
 
-use crate::lua_engine::lua_stdlib::LuaDocstringData;
+use crate::lua_engine::lua_stdlib::{LuaApiEntry, LuaApiRegistryData, LuaDocstringData};
 
 pub fn generate_lua_documentation(out_path: &str) -> Result<()> {
     let mut docs_by_module = BTreeMap::<&str, Vec<&str>>::new();
@@ -63,3 +64,109 @@ pub fn generate_lua_documentation(out_path: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Returns all the items exported to Lua, sorted by their parent table (or
+/// class) and then by name.
+pub fn lua_api_registry() -> Vec<&'static LuaApiEntry> {
+    let mut entries = inventory::iter::<LuaApiRegistryData>()
+        .flat_map(|registry| registry.data.iter())
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| (e.parent, e.name));
+    entries
+}
+
+/// Renders the Lua API reference as a Markdown document, with one section per
+/// global table or class.
+pub fn render_lua_api_markdown() -> String {
+    use std::fmt::Write;
+    let mut md = String::new();
+    writeln!(md, "# Lua API Reference").unwrap();
+
+    let mut current_parent = None;
+    for entry in lua_api_registry() {
+        if current_parent != Some(entry.parent) {
+            current_parent = Some(entry.parent);
+            writeln!(md, "\n## {}", entry.parent).unwrap();
+        }
+
+        let sep = if entry.kind == "method" { ":" } else { "." };
+        if entry.kind == "constant" {
+            writeln!(md, "\n### `{}{sep}{}`\n", entry.parent, entry.name).unwrap();
+        } else {
+            let params = entry
+                .params
+                .iter()
+                .filter(|(name, _)| *name != "self")
+                .map(|(name, _)| *name)
+                .join(", ");
+            writeln!(
+                md,
+                "\n### `{}{sep}{}({params})`\n",
+                entry.parent, entry.name
+            )
+            .unwrap();
+        }
+
+        writeln!(md, "*{}*\n", entry.kind).unwrap();
+        for line in entry.doc {
+            writeln!(md, "{line}").unwrap();
+        }
+        if !entry.doc.is_empty() {
+            writeln!(md).unwrap();
+        }
+        for (name, typ) in entry.params {
+            writeln!(md, "- `{name}`: `{typ}`").unwrap();
+        }
+        writeln!(md, "- Returns: `{}`", entry.returns).unwrap();
+    }
+    md
+}
+
+/// Renders the Lua API reference as a JSON array of objects. Each object has
+/// the fields `kind`, `parent`, `name`, `params` (a list of `{name, type}`
+/// objects), `returns` and `doc`.
+pub fn render_lua_api_json() -> String {
+    fn json_str(s: &str) -> String {
+        let mut out = String::with_capacity(s.len() + 2);
+        out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+        out
+    }
+
+    let entries = lua_api_registry()
+        .into_iter()
+        .map(|entry| {
+            let params = entry
+                .params
+                .iter()
+                .map(|(name, typ)| {
+                    format!(
+                        "{{\"name\":{},\"type\":{}}}",
+                        json_str(name),
+                        json_str(typ)
+                    )
+                })
+                .join(",");
+            let doc = json_str(&entry.doc.join("\n"));
+            format!(
+                "{{\"kind\":{},\"parent\":{},\"name\":{},\"params\":[{params}],\"returns\":{},\"doc\":{doc}}}",
+                json_str(entry.kind),
+                json_str(entry.parent),
+                json_str(entry.name),
+                json_str(entry.returns),
+            )
+        })
+        .join(",\n");
+    format!("[\n{entries}\n]\n")
+}
@@ -8,6 +8,8 @@ use std::{borrow::Cow, path::PathBuf};
 
 use crate::graph::{NodeDefinition, NodeDefinitionsInner};
 
+use super::BytecodeCache;
+
 pub struct LuaSourceFile {
     pub contents: String,
     pub name: String,
@@ -53,10 +55,31 @@ pub trait LuaFileIo {
     /// `path`. The path is relative to $BLACKJACK_LUA/lib. This function will
     /// be used when Lua code calls the `require` function.
     fn load_file_require(&self, path: &str) -> anyhow::Result<LuaSourceFile>;
+
+    /// Returns the cache where the compiled bytecode of Lua files is stored
+    /// between runs, if any. Without a cache, files are compiled every time
+    /// they are loaded.
+    fn bytecode_cache(&self) -> Option<&BytecodeCache> {
+        None
+    }
+
+    /// Loads the given `file` as a Lua function, using the bytecode cache
+    /// when there is one.
+    fn load_chunk<'lua>(
+        &self,
+        lua: &'lua mlua::Lua,
+        file: &LuaSourceFile,
+    ) -> mlua::Result<mlua::Function<'lua>> {
+        match self.bytecode_cache() {
+            Some(cache) => cache.load(lua, file),
+            None => lua.load(file).into_function(),
+        }
+    }
 }
 
 pub struct StdLuaFileIo {
     pub base_folder: String,
+    pub bytecode_cache: Option<BytecodeCache>,
 }
 
 impl LuaFileIo for StdLuaFileIo {
@@ -99,6 +122,10 @@ impl LuaFileIo for StdLuaFileIo {
             name: path.display().to_string(),
         })
     }
+
+    fn bytecode_cache(&self) -> Option<&BytecodeCache> {
+        self.bytecode_cache.as_ref()
+    }
 }
 
 /// Scans and runs all files inside $BLACKJACK_LUA/run. Then, parses every
@@ -109,7 +136,7 @@ pub fn load_node_definitions(
 ) -> anyhow::Result<NodeDefinitionsInner> {
     for path in lua_io.find_run_files() {
         let file = lua_io.load_file_absolute(&path)?;
-        lua.load(&file).exec()?;
+        lua_io.load_chunk(lua, &file)?.call::<_, ()>(())?;
     }
 
     let table = lua
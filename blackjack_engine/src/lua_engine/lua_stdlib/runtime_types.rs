@@ -43,6 +43,200 @@ impl LVec3 {
     }
 }
 
+/// Returns the value as a scalar, if it is a Lua number.
+fn lua_scalar(value: &mlua::Value) -> Option<f32> {
+    match value {
+        mlua::Value::Number(n) => Some(*n as f32),
+        mlua::Value::Integer(i) => Some(*i as f32),
+        _ => None,
+    }
+}
+
+/// A 2d vector. Unlike `LVec3`, Luau has no native type for these, so they
+/// are exposed as userdata.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct LVec2(pub glam::Vec2);
+impl<'lua> FromLua<'lua> for LVec2 {
+    fn from_lua(lua_value: mlua::Value<'lua>, _lua: &'lua Lua) -> mlua::Result<Self> {
+        match lua_value {
+            mlua::Value::UserData(ud) => Ok(LVec2(ud.borrow::<LVec2>()?.0)),
+            // A 3d vector can be passed where a 2d one is expected, the z
+            // coordinate is dropped.
+            mlua::Value::Vector(x, y, _) => Ok(LVec2(glam::Vec2::new(x, y))),
+            _ => Err(mlua::Error::FromLuaConversionError {
+                from: lua_value.type_name(),
+                to: "Vec2",
+                message: None,
+            }),
+        }
+    }
+}
+impl From<glam::Vec2> for LVec2 {
+    fn from(v: glam::Vec2) -> Self {
+        Self(v)
+    }
+}
+impl UserData for LVec2 {
+    fn add_fields<'lua, F: mlua::UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("x", |_, this| Ok(this.0.x));
+        fields.add_field_method_get("y", |_, this| Ok(this.0.y));
+    }
+
+    fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_function(mlua::MetaMethod::Add, |_, (a, b): (LVec2, LVec2)| {
+            Ok(LVec2(a.0 + b.0))
+        });
+        methods.add_meta_function(mlua::MetaMethod::Sub, |_, (a, b): (LVec2, LVec2)| {
+            Ok(LVec2(a.0 - b.0))
+        });
+        methods.add_meta_function(
+            mlua::MetaMethod::Mul,
+            |lua, (a, b): (mlua::Value, mlua::Value)| match (lua_scalar(&a), lua_scalar(&b)) {
+                (Some(s), _) => Ok(LVec2(LVec2::from_lua(b, lua)?.0 * s)),
+                (_, Some(s)) => Ok(LVec2(LVec2::from_lua(a, lua)?.0 * s)),
+                _ => Ok(LVec2(
+                    LVec2::from_lua(a, lua)?.0 * LVec2::from_lua(b, lua)?.0,
+                )),
+            },
+        );
+        methods.add_meta_function(
+            mlua::MetaMethod::Div,
+            |lua, (a, b): (LVec2, mlua::Value)| match lua_scalar(&b) {
+                Some(s) => Ok(LVec2(a.0 / s)),
+                None => Ok(LVec2(a.0 / LVec2::from_lua(b, lua)?.0)),
+            },
+        );
+        methods.add_meta_method(mlua::MetaMethod::Unm, |_, this, ()| Ok(LVec2(-this.0)));
+        methods.add_meta_function(mlua::MetaMethod::Eq, |_, (a, b): (LVec2, LVec2)| {
+            Ok(a.0 == b.0)
+        });
+        methods.add_meta_method(mlua::MetaMethod::ToString, |_, this, ()| {
+            Ok(format!("vec2({}, {})", this.0.x, this.0.y))
+        });
+
+        methods.add_method("length", |_, this, ()| Ok(this.0.length()));
+        methods.add_method("normalize", |_, this, ()| {
+            Ok(LVec2(this.0.normalize_or_zero()))
+        });
+        methods.add_method("dot", |_, this, other: LVec2| Ok(this.0.dot(other.0)));
+        methods.add_method("extend", |_, this, z: f32| Ok(LVec3(this.0.extend(z))));
+    }
+}
+
+/// A rotation, stored as a quaternion. Anywhere an `LQuat` is expected, a
+/// regular vector can be passed instead, and it will be interpreted as XYZ
+/// euler angles (in radians).
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct LQuat(pub glam::Quat);
+impl<'lua> FromLua<'lua> for LQuat {
+    fn from_lua(lua_value: mlua::Value<'lua>, _lua: &'lua Lua) -> mlua::Result<Self> {
+        match lua_value {
+            mlua::Value::UserData(ud) => Ok(LQuat(ud.borrow::<LQuat>()?.0)),
+            mlua::Value::Vector(x, y, z) => {
+                Ok(LQuat(glam::Quat::from_euler(glam::EulerRot::XYZ, x, y, z)))
+            }
+            _ => Err(mlua::Error::FromLuaConversionError {
+                from: lua_value.type_name(),
+                to: "Quat",
+                message: None,
+            }),
+        }
+    }
+}
+impl From<glam::Quat> for LQuat {
+    fn from(q: glam::Quat) -> Self {
+        Self(q)
+    }
+}
+impl UserData for LQuat {
+    fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_function(
+            mlua::MetaMethod::Mul,
+            |lua, (a, b): (LQuat, mlua::Value)| match b {
+                mlua::Value::Vector(x, y, z) => LVec3(a.0 * glam::Vec3::new(x, y, z)).to_lua(lua),
+                b => LQuat(a.0 * LQuat::from_lua(b, lua)?.0).to_lua(lua),
+            },
+        );
+        methods.add_meta_function(mlua::MetaMethod::Eq, |_, (a, b): (LQuat, LQuat)| {
+            Ok(a.0 == b.0)
+        });
+        methods.add_meta_method(mlua::MetaMethod::ToString, |_, this, ()| {
+            let q = this.0;
+            Ok(format!("quat({}, {}, {}, {})", q.x, q.y, q.z, q.w))
+        });
+
+        methods.add_method("inverse", |_, this, ()| Ok(LQuat(this.0.inverse())));
+        methods.add_method("normalize", |_, this, ()| Ok(LQuat(this.0.normalize())));
+        methods.add_method("slerp", |_, this, (other, t): (LQuat, f32)| {
+            Ok(LQuat(this.0.slerp(other.0, t)))
+        });
+        methods.add_method("to_euler", |_, this, ()| {
+            let (x, y, z) = this.0.to_euler(glam::EulerRot::XYZ);
+            Ok(LVec3(glam::Vec3::new(x, y, z)))
+        });
+        methods.add_method("to_axis_angle", |_, this, ()| {
+            let (axis, angle) = this.0.to_axis_angle();
+            Ok((LVec3(axis), angle))
+        });
+    }
+}
+
+/// A 4x4 affine transformation matrix.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct LMat4(pub glam::Mat4);
+impl<'lua> FromLua<'lua> for LMat4 {
+    fn from_lua(lua_value: mlua::Value<'lua>, _lua: &'lua Lua) -> mlua::Result<Self> {
+        match lua_value {
+            mlua::Value::UserData(ud) => Ok(LMat4(ud.borrow::<LMat4>()?.0)),
+            _ => Err(mlua::Error::FromLuaConversionError {
+                from: lua_value.type_name(),
+                to: "Mat4",
+                message: None,
+            }),
+        }
+    }
+}
+impl From<glam::Mat4> for LMat4 {
+    fn from(m: glam::Mat4) -> Self {
+        Self(m)
+    }
+}
+impl UserData for LMat4 {
+    fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_function(
+            mlua::MetaMethod::Mul,
+            |lua, (a, b): (LMat4, mlua::Value)| match b {
+                mlua::Value::Vector(x, y, z) => {
+                    LVec3(a.0.transform_point3(glam::Vec3::new(x, y, z))).to_lua(lua)
+                }
+                b => LMat4(a.0 * LMat4::from_lua(b, lua)?.0).to_lua(lua),
+            },
+        );
+        methods.add_meta_function(mlua::MetaMethod::Eq, |_, (a, b): (LMat4, LMat4)| {
+            Ok(a.0 == b.0)
+        });
+        methods.add_meta_method(mlua::MetaMethod::ToString, |_, this, ()| {
+            Ok(format!("mat4({:?})", this.0.to_cols_array()))
+        });
+
+        methods.add_method("transform_point", |_, this, p: LVec3| {
+            Ok(LVec3(this.0.transform_point3(p.0)))
+        });
+        methods.add_method("transform_vector", |_, this, v: LVec3| {
+            Ok(LVec3(this.0.transform_vector3(v.0)))
+        });
+        methods.add_method("inverse", |_, this, ()| Ok(LMat4(this.0.inverse())));
+        methods.add_method("transpose", |_, this, ()| Ok(LMat4(this.0.transpose())));
+        methods.add_method("to_scale_rotation_translation", |_, this, ()| {
+            let (s, r, t) = this.0.to_scale_rotation_translation();
+            Ok((LVec3(s), LQuat(r), LVec3(t)))
+        });
+    }
+}
+
 /// Vertex ids cross the Rust<->Lua boundary a lot, so we can't pay the price of
 /// boxing that a `UserData` requires. Instead we use LightUserData by casting
 /// the slotmap key to u64, and then to a pointer.
@@ -91,6 +285,14 @@ fn ligthdata_to_keydata(d: mlua::LightUserData) -> slotmap::KeyData {
 impl UserData for ChannelKeyType {}
 impl UserData for ChannelValueType {}
 
+/// Normalizes a direction given from Lua, which would give NaN rotations
+/// when it is zero.
+fn normalized_axis(axis: LVec3) -> anyhow::Result<glam::Vec3> {
+    axis.0
+        .try_normalize()
+        .ok_or_else(|| anyhow::anyhow!("Expected a non-zero direction, got {}", axis.0))
+}
+
 pub struct PerlinNoise(pub noise::Perlin);
 
 #[blackjack_macros::blackjack_lua_module]
@@ -123,13 +325,15 @@ mod perlin_noise {
 #[blackjack_macros::blackjack_lua_module]
 mod vector_math {
     use super::*;
+    use anyhow::Result;
     use glam::Quat;
 
     /// Return vector `v` rotated around given `axis` and `angle` (in radians).
+    /// The `axis` can't be zero.
     #[lua(under = "NativeMath")]
-    pub fn rotate_around_axis(v: LVec3, axis: LVec3, angle: f32) -> LVec3 {
-        let q = Quat::from_axis_angle(axis.0.normalize(), angle);
-        LVec3(q * v.0)
+    pub fn rotate_around_axis(v: LVec3, axis: LVec3, angle: f32) -> Result<LVec3> {
+        let q = Quat::from_axis_angle(normalized_axis(axis)?, angle);
+        Ok(LVec3(q * v.0))
     }
 
     /// Returns the `cross` product of vectors `v` and `v2`.
@@ -138,3 +342,160 @@ mod vector_math {
         LVec3(v.0.cross(v2.0))
     }
 }
+
+#[blackjack_macros::blackjack_lua_module]
+mod vec2_api {
+    use super::*;
+
+    /// Constructs a new 2d vector with coordinates `(x, y)`.
+    #[lua(under = "Vec2")]
+    pub fn new(x: f32, y: f32) -> LVec2 {
+        LVec2(glam::Vec2::new(x, y))
+    }
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod quat_api {
+    use super::*;
+    use anyhow::Result;
+    use glam::Quat;
+
+    /// Returns the identity rotation.
+    #[lua(under = "Quat")]
+    pub fn identity() -> LQuat {
+        LQuat(Quat::IDENTITY)
+    }
+
+    /// Returns a rotation of `angle` radians around the given `axis`, which
+    /// can't be zero.
+    #[lua(under = "Quat")]
+    pub fn from_axis_angle(axis: LVec3, angle: f32) -> Result<LQuat> {
+        Ok(LQuat(Quat::from_axis_angle(normalized_axis(axis)?, angle)))
+    }
+
+    /// Returns a rotation from the given XYZ euler `angles`, in radians.
+    #[lua(under = "Quat")]
+    pub fn from_euler(angles: LVec3) -> LQuat {
+        LQuat(Quat::from_euler(
+            glam::EulerRot::XYZ,
+            angles.0.x,
+            angles.0.y,
+            angles.0.z,
+        ))
+    }
+
+    /// Returns the shortest rotation that takes direction `from` to direction
+    /// `to`. Neither of them can be zero.
+    #[lua(under = "Quat")]
+    pub fn from_rotation_arc(from: LVec3, to: LVec3) -> Result<LQuat> {
+        Ok(LQuat(Quat::from_rotation_arc(
+            normalized_axis(from)?,
+            normalized_axis(to)?,
+        )))
+    }
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod mat4_api {
+    use super::*;
+    use glam::Mat4;
+
+    /// Returns the identity matrix.
+    #[lua(under = "Mat4")]
+    pub fn identity() -> LMat4 {
+        LMat4(Mat4::IDENTITY)
+    }
+
+    /// Returns a matrix that translates by `translation`.
+    #[lua(under = "Mat4")]
+    pub fn from_translation(translation: LVec3) -> LMat4 {
+        LMat4(Mat4::from_translation(translation.0))
+    }
+
+    /// Returns a matrix that applies the given `rotation`.
+    #[lua(under = "Mat4")]
+    pub fn from_quat(rotation: LQuat) -> LMat4 {
+        LMat4(Mat4::from_quat(rotation.0))
+    }
+
+    /// Returns a matrix that scales by `scale`.
+    #[lua(under = "Mat4")]
+    pub fn from_scale(scale: LVec3) -> LMat4 {
+        LMat4(Mat4::from_scale(scale.0))
+    }
+
+    /// Returns a matrix that applies `scale`, then `rotation` and then
+    /// `translation`.
+    #[lua(under = "Mat4")]
+    pub fn from_scale_rotation_translation(
+        scale: LVec3,
+        rotation: LQuat,
+        translation: LVec3,
+    ) -> LMat4 {
+        LMat4(Mat4::from_scale_rotation_translation(
+            scale.0,
+            rotation.0,
+            translation.0,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lua_engine::LuaRuntime;
+
+    fn eval(rt: &LuaRuntime, code: &str) -> mlua::Result<bool> {
+        rt.lua.load(code).eval::<bool>()
+    }
+
+    #[test]
+    pub fn test_math_metamethods() {
+        let rt = LuaRuntime::initialize_with_std("../blackjack_lua".into()).unwrap();
+        let checks = [
+            "Vec2.new(1, 2) + Vec2.new(3, 4) == Vec2.new(4, 6)",
+            "Vec2.new(1, 2) - Vec2.new(3, 4) == Vec2.new(-2, -2)",
+            "2 * Vec2.new(1, 2) == Vec2.new(2, 4)",
+            "Vec2.new(1, 2) * Vec2.new(3, 4) == Vec2.new(3, 8)",
+            "Vec2.new(2, 4) / 2 == Vec2.new(1, 2)",
+            "-Vec2.new(1, 2) == Vec2.new(-1, -2)",
+            "Vec2.new(1, 2).x == 1 and Vec2.new(1, 2).y == 2",
+            "tostring(Vec2.new(1, 2)) == 'vec2(1, 2)'",
+            "Quat.identity() * vector(1, 2, 3) == vector(1, 2, 3)",
+            "(Quat.from_axis_angle(vector(0, 0, 1), math.pi / 2) * vector(1, 0, 0)).y > 0.999",
+            "Quat.identity() * Quat.identity() == Quat.identity()",
+            "Mat4.from_translation(vector(1, 0, 0)) * vector(1, 2, 3) == vector(2, 2, 3)",
+            "Mat4.from_scale(vector(2, 2, 2)) * Mat4.from_translation(vector(1, 0, 0)) \
+             == Mat4.from_scale_rotation_translation(vector(2, 2, 2), Quat.identity(), \
+             vector(2, 0, 0))",
+        ];
+        for check in checks {
+            assert!(eval(&rt, &format!("return {check}")).unwrap(), "{check}");
+        }
+
+        // Zero directions are an error instead of a NaN rotation
+        assert!(eval(
+            &rt,
+            "return Quat.from_axis_angle(vector(0, 0, 0), 1) ~= nil"
+        )
+        .is_err());
+        assert!(eval(
+            &rt,
+            "return NativeMath.rotate_around_axis(vector(1, 0, 0), vector(0, 0, 0), 1) ~= nil"
+        )
+        .is_err());
+    }
+
+    #[test]
+    pub fn test_transform_matrix() {
+        let rt = LuaRuntime::initialize_with_std("../blackjack_lua".into()).unwrap();
+        let code = "
+            local mesh = Primitives.cube(vector(0, 0, 0), vector(1, 1, 1))
+            local m = Mat4.from_scale_rotation_translation(
+                vector(2, 2, 2), Quat.identity(), vector(1, 0, 0))
+            Ops.transform_matrix(mesh, m)
+            local min, max = mesh:bbox()
+            return min == vector(0, -1, -1) and max == vector(2, 1, 1)
+        ";
+        assert!(eval(&rt, code).unwrap());
+    }
+}
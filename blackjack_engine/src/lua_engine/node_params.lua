@@ -35,6 +35,41 @@ Params.v3 = function(name, default)
     return { name = name, default = default, type = "vec3" }
 end
 
+--- A color parameter, with given `default` value. Colors are vectors with
+--- their RGB components in the 0..1 range. Shows a color picker widget on the
+--- UI.
+Params.color = function(name, default)
+    return { name = name, default = default or vector(1, 1, 1), type = "color" }
+end
+
+--- A ramp parameter, mapping a position in the 0..1 range to a scalar value.
+--- The optional `config` table can contain a list of `keys`, each one being a
+--- `{position, value}` pair, and an `interpolation` mode, which can be one of
+--- `"constant"`, `"linear"` or `"smooth"`.
+---
+--- Inside ops, ramps can be sampled with `ramp:sample(t)`.
+Params.ramp = function(name, config)
+    config = config or {}
+    return {
+        name = name,
+        type = "ramp",
+        keys = config.keys or { { 0.0, 0.0 }, { 1.0, 1.0 } },
+        interpolation = config.interpolation,
+    }
+end
+
+--- Same as `Params.ramp`, but the key values are colors. Inside ops, color
+--- ramps can be sampled with `ramp:sample_color(t)`.
+Params.color_ramp = function(name, config)
+    config = config or {}
+    return {
+        name = name,
+        type = "color_ramp",
+        keys = config.keys or { { 0.0, vector(0, 0, 0) }, { 1.0, vector(1, 1, 1) } },
+        interpolation = config.interpolation,
+    }
+end
+
 --- A mesh parameter. Meshes can't be set by the user directly via widget, so
 --- this has no additional settings.
 Params.mesh = function(name)
@@ -42,9 +77,11 @@ Params.mesh = function(name)
 end
 
 --- A selection parameter. Lets user specify a group of vertices, halfedges or
---- faces. The selected element is context-dependent.
-Params.selection = function(name)
-    return { name = name, type = "selection" }
+--- faces. The selected element is context-dependent. The optional `kind`, one
+--- of "vertex", "edge" or "face", tells which elements are selected, so the UI
+--- can preview the selection.
+Params.selection = function(name, kind)
+    return { name = name, type = "selection", kind = kind }
 end
 
 --- A string parameter, with a given `default` value. If `multiline` is set, the
@@ -75,10 +112,15 @@ end
 --- widget on the UI.
 ---
 --- The `mode` specifies whether the file picker is used to create a new file
---- with `"save"` or open an existing one with `"open"`
-Params.file = function(name, mode)
+--- with `"save"` or open an existing one with `"open"`. The optional
+--- `extensions` is a list of allowed file extensions, without the dot (e.g.
+--- `{"obj"}`). Relative paths are resolved against the folder of the graph.
+Params.file = function(name, mode, extensions)
     mode = mode or "save" -- keep backwards compatibility
-    return { name = name, type = "file", mode = mode }
+    if extensions ~= nil then
+        assert(type(extensions) == 'table', "extensions should be a table")
+    end
+    return { name = name, type = "file", mode = mode, extensions = extensions }
 end
 
 --- A heightmap mesh parameter. Like a regular mesh, it can't be set by the user
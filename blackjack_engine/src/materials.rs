@@ -0,0 +1,173 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// The name of the face channel storing material assignments. Its values are
+/// indices into the [`MaterialRegistry`].
+pub const MATERIAL_CHANNEL: &str = "material";
+
+/// A named surface material. Materials are defined per project, and faces
+/// reference them by index through the [`MATERIAL_CHANNEL`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Material {
+    pub name: String,
+    /// Linear RGB, in the 0..1 range.
+    pub base_color: Vec3,
+    pub roughness: f32,
+    pub metallic: f32,
+    /// Path to the base color (albedo) texture, if any.
+    pub base_color_texture: Option<String>,
+    /// Path to the normal map texture, if any.
+    pub normal_texture: Option<String>,
+}
+
+impl Material {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            base_color: Vec3::splat(0.8),
+            roughness: 0.5,
+            metallic: 0.0,
+            base_color_texture: None,
+            normal_texture: None,
+        }
+    }
+}
+
+/// The list of materials defined in a project. It is persisted along with the
+/// graph. Material names are unique within a registry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaterialRegistry {
+    materials: Vec<Material>,
+}
+
+impl MaterialRegistry {
+    /// Adds a new material, returning its index. Fails if there's already a
+    /// material with the same name.
+    pub fn add(&mut self, material: Material) -> Result<usize> {
+        if self.index_of(&material.name).is_some() {
+            bail!("There's already a material named '{}'", material.name);
+        }
+        self.materials.push(material);
+        Ok(self.materials.len() - 1)
+    }
+
+    /// Removes the material at `index`. Note that this shifts the indices of
+    /// all the materials after it. Meshes store those indices, but nodes look
+    /// them up by name when they cook, and [`MaterialRegistry::install`]
+    /// makes the graph cook again with the new indices.
+    pub fn remove(&mut self, index: usize) -> Option<Material> {
+        (index < self.materials.len()).then(|| self.materials.remove(index))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Material> {
+        self.materials.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Material> {
+        self.materials.get_mut(index)
+    }
+
+    /// Returns the material for a value of the [`MATERIAL_CHANNEL`].
+    pub fn for_channel_value(&self, value: f32) -> Option<&Material> {
+        if value < 0.0 {
+            return None;
+        }
+        self.get(value.round() as usize)
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.materials.iter().position(|m| m.name == name)
+    }
+
+    /// Returns a name derived from `base` that no material is using yet.
+    pub fn unique_name(&self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut i = 1;
+        while self.index_of(&name).is_some() {
+            name = format!("{base}.{i:03}");
+            i += 1;
+        }
+        name
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Material> {
+        self.materials.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+
+    /// Makes this registry available to the Lua code running in `lua`, which
+    /// can then look up materials by name. Must be called again whenever the
+    /// registry changes.
+    ///
+    /// Nodes resolve material names to indices when they cook, so the outputs
+    /// kept across interactive cooks are forgotten when the registry differs
+    /// from the installed one.
+    pub fn install(&self, lua: &mlua::Lua) {
+        let changed = lua
+            .app_data_ref::<MaterialRegistry>()
+            .map_or(true, |installed| *installed != *self);
+        if changed {
+            lua.set_app_data(self.clone());
+            crate::graph_interpreter::clear_cooked_outputs(lua);
+        }
+    }
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use mlua::Lua;
+
+    /// Returns the index of the material named `name`, suitable to be stored
+    /// in the `material` face channel. Errors when there's no such material.
+    #[lua(under = "Materials")]
+    fn index_of(lua: &Lua, name: String) -> Result<f32> {
+        lua.app_data_ref::<MaterialRegistry>()
+            .and_then(|registry| registry.index_of(&name))
+            .map(|idx| idx as f32)
+            .ok_or_else(|| anyhow!("There is no material named '{name}'"))
+    }
+
+    /// Returns the names of all the materials defined in the project.
+    #[lua(under = "Materials")]
+    fn names(lua: &Lua) -> Vec<String> {
+        lua.app_data_ref::<MaterialRegistry>()
+            .map(|registry| registry.iter().map(|m| m.name.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_material_registry() {
+        let mut registry = MaterialRegistry::default();
+        assert_eq!(registry.add(Material::new("wood")).unwrap(), 0);
+        assert_eq!(registry.add(Material::new("metal")).unwrap(), 1);
+        assert!(registry.add(Material::new("wood")).is_err());
+        assert_eq!(registry.unique_name("wood"), "wood.001");
+
+        assert_eq!(registry.for_channel_value(1.0).unwrap().name, "metal");
+        assert!(registry.for_channel_value(2.0).is_none());
+        assert!(registry.for_channel_value(-1.0).is_none());
+
+        registry.remove(0);
+        assert_eq!(registry.index_of("metal"), Some(0));
+    }
+}
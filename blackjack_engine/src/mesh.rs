@@ -10,3 +10,42 @@ pub mod halfedge;
 /// A heightmap data structure. A different mesh representation based on
 /// heightmaps. Supports different operations.
 pub mod heightmap;
+
+/// Generation of meshes from text, using TrueType and OpenType fonts.
+pub mod text;
+
+/// Boolean operations (union, difference and intersection) between meshes.
+pub mod csg;
+
+/// Mesh simplification by edge collapses, using quadric error metrics.
+pub mod decimate;
+
+/// Remeshing through a voxel grid, producing closed manifold surfaces.
+pub mod voxel_remesh;
+
+/// Approximate convex decomposition of meshes, to generate collision shapes.
+pub mod convex_decomposition;
+
+/// Slicing of meshes into layers of contours, to preview 3D prints.
+pub mod slicing;
+
+/// Analysis of meshes for 3D printing, like wall thickness.
+pub mod analysis;
+
+/// Lattice infill for the inside of closed meshes.
+pub mod infill;
+
+/// Engraving and embossing of text and curves onto the surface of meshes.
+pub mod engrave;
+
+/// Cage-based deformation of meshes with a lattice of control points.
+pub mod lattice;
+
+/// Measurements of meshes, like distances and angles, to drive parameters.
+pub mod measure;
+
+/// Projection of meshes onto the surface of other meshes.
+pub mod shrinkwrap;
+
+/// Per-element Lua snippets that edit mesh channels through `@name` accessors.
+pub mod wrangle;
@@ -0,0 +1,392 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Analysis of meshes for printability. Results are written to vertex
+//! channels, which can be displayed with `color_by_gradient`.
+
+use float_ord::FloatOrd;
+use rstar::{PointDistance, RTree, RTreeObject, AABB};
+
+use crate::prelude::*;
+
+/// Name of the vertex channel written by [`analyze_thickness`].
+pub const THICKNESS_CHANNEL: &str = "thickness";
+/// Name of the face group with the faces [`analyze_overhangs`] finds in need
+/// of support.
+pub const OVERHANG_GROUP: &str = "overhang";
+/// Name of the face and vertex channels where [`analyze_overhangs`] stores
+/// the overhang angles.
+pub const OVERHANG_ANGLE_CHANNEL: &str = "overhang_angle";
+
+/// Rays start this far inside the surface, so they don't hit the faces
+/// around the vertex they start from.
+const RAY_OFFSET: f32 = 1e-4;
+
+/// A triangle of the mesh being analyzed, stored in a spatial index.
+pub(crate) struct Triangle([Vec3; 3]);
+
+impl RTreeObject for Triangle {
+    type Envelope = AABB<[f32; 3]>;
+    fn envelope(&self) -> Self::Envelope {
+        let [a, b, c] = self.0;
+        AABB::from_corners(a.min(b).min(c).to_array(), a.max(b).max(c).to_array())
+    }
+}
+
+impl PointDistance for Triangle {
+    fn distance_2(&self, point: &[f32; 3]) -> f32 {
+        let point = Vec3::from_array(*point);
+        self.closest_point(point).distance_squared(point)
+    }
+}
+
+impl Triangle {
+    /// Returns the normal of this triangle, following its winding order.
+    pub(crate) fn normal(&self) -> Vec3 {
+        let [a, b, c] = self.0;
+        (b - a).cross(c - a).normalize_or_zero()
+    }
+
+    /// Returns the point of this triangle closest to `p`. See "Real-Time
+    /// Collision Detection", by Christer Ericson, section 5.1.5.
+    pub(crate) fn closest_point(&self, p: Vec3) -> Vec3 {
+        let [a, b, c] = self.0;
+        let (ab, ac, ap) = (b - a, c - a, p - a);
+        let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+        let bp = p - b;
+        let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return a + ab * (d1 / (d1 - d3));
+        }
+        let cp = p - c;
+        let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+        if d6 >= 0.0 && d5 <= d6 {
+            return c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return a + ac * (d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+        let denom = 1.0 / (va + vb + vc);
+        a + ab * (vb * denom) + ac * (vc * denom)
+    }
+
+    /// Returns the distance along the ray from `origin` in the normalized
+    /// `direction` where it hits this triangle, if it does. Uses the
+    /// Möller-Trumbore algorithm.
+    fn ray_hit(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let [a, b, c] = self.0;
+        let (e1, e2) = (b - a, c - a);
+        let h = direction.cross(e2);
+        let det = e1.dot(h);
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let s = origin - a;
+        let u = s.dot(h) / det;
+        let q = s.cross(e1);
+        let v = direction.dot(q) / det;
+        let t = e2.dot(q) / det;
+        ((0.0..=1.0).contains(&u) && v >= 0.0 && u + v <= 1.0 && t > 0.0).then_some(t)
+    }
+}
+
+/// Builds a spatial index with the triangles of every face in `mesh`, and
+/// returns it along with the normal of each face.
+pub(crate) fn triangle_index(mesh: &HalfEdgeMesh) -> (RTree<Triangle>, HashMap<FaceId, Vec3>) {
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let mut triangles = vec![];
+    let mut normals = HashMap::new();
+    for (face, _) in conn.iter_faces() {
+        let points = conn
+            .face_vertices(face)
+            .iter()
+            .map(|v| positions[*v])
+            .collect::<SVec<_>>();
+        if points.len() < 3 {
+            continue;
+        }
+        // Newell's method, robust for non-planar faces
+        let normal = points
+            .iter()
+            .circular_tuple_windows()
+            .fold(Vec3::ZERO, |acc, (a, b)| acc + a.cross(*b));
+        normals.insert(face, normal.normalize_or_zero());
+        for (b, c) in points[1..].iter_cpy().tuple_windows() {
+            triangles.push(Triangle([points[0], b, c]));
+        }
+    }
+    (RTree::bulk_load(triangles), normals)
+}
+
+/// Returns the distance to the closest triangle in `index` hit by a ray from
+/// `origin` in the normalized `direction`, up to `max_distance`.
+pub(crate) fn cast_ray(
+    index: &RTree<Triangle>,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+) -> Option<f32> {
+    cast_ray_triangle(index, origin, direction, max_distance).map(|(t, _)| t)
+}
+
+/// Same as [`cast_ray`], but also returns the triangle that was hit.
+pub(crate) fn cast_ray_triangle(
+    index: &RTree<Triangle>,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+) -> Option<(f32, &Triangle)> {
+    let end = origin + direction * max_distance;
+    let query = AABB::from_corners(origin.min(end).to_array(), origin.max(end).to_array());
+    index
+        .locate_in_envelope_intersecting(&query)
+        .filter_map(|tri| Some((tri.ray_hit(origin, direction)?, tri)))
+        .filter(|(t, _)| *t <= max_distance)
+        .min_by_key(|(t, _)| FloatOrd(*t))
+}
+
+/// Measures the wall thickness of `mesh` at each vertex, by casting rays
+/// inwards and measuring the distance to the opposite side of the surface.
+/// Rays are cast against the vertex normal, and against the normal of each
+/// face around the vertex, and the shortest distance is kept. The result is
+/// stored in the `thickness` vertex channel. Vertices where no ray hits the
+/// surface within `max_distance` get `max_distance`.
+pub fn analyze_thickness(mesh: &mut HalfEdgeMesh, max_distance: f32) -> Result<()> {
+    if max_distance <= 0.0 {
+        bail!("The maximum distance must be positive");
+    }
+    let (index, face_normals) = triangle_index(mesh);
+
+    let mut thickness = vec![];
+    {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let mut vertex_normals = HashMap::<VertexId, SVec<Vec3>>::new();
+        for (face, normal) in &face_normals {
+            for v in conn.face_vertices(*face) {
+                vertex_normals.entry(v).or_default().push(*normal);
+            }
+        }
+
+        for (v, _) in conn.iter_vertices() {
+            let normals = vertex_normals.get(&v).map(|n| n.as_slice()).unwrap_or(&[]);
+            let average = normals.iter_cpy().sum::<Vec3>().normalize_or_zero();
+            let min = std::iter::once(average)
+                .chain(normals.iter_cpy())
+                .filter(|n| *n != Vec3::ZERO)
+                .filter_map(|n| {
+                    let origin = positions[v] - n * RAY_OFFSET;
+                    cast_ray(&index, origin, -n, max_distance)
+                })
+                .map(|t| t + RAY_OFFSET)
+                .fold(max_distance, f32::min);
+            thickness.push((v, min));
+        }
+    }
+
+    let ch_id = mesh
+        .channels
+        .ensure_channel::<VertexId, f32>(THICKNESS_CHANNEL);
+    let mut ch = mesh.channels.write_channel(ch_id)?;
+    for (v, t) in thickness {
+        ch[v] = t;
+    }
+    Ok(())
+}
+
+/// A summary of the overhangs found by [`analyze_overhangs`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OverhangReport {
+    /// The number of faces that need support.
+    pub faces: usize,
+    /// The total area of the faces that need support.
+    pub area: f32,
+    /// The total area of all the faces of the mesh.
+    pub total_area: f32,
+    /// The largest overhang angle in the mesh, in radians.
+    pub max_angle: f32,
+}
+
+/// Finds the faces of `mesh` that would need support when 3D printed in the
+/// `build_direction`. The overhang angle of a face is how far it leans past
+/// vertical walls, from zero for walls and faces looking upwards, to a right
+/// angle for faces looking straight down. Faces leaning more than
+/// `threshold_angle` radians are overhangs, except for those resting on the
+/// build plate, at the lowest point of the mesh.
+///
+/// The angle of each face is stored in the `overhang_angle` face channel, and
+/// the overhangs are put in the `overhang` face group. For display, each
+/// vertex gets the largest angle of the faces around it in the
+/// `overhang_angle` vertex channel.
+pub fn analyze_overhangs(
+    mesh: &mut HalfEdgeMesh,
+    build_direction: Vec3,
+    threshold_angle: f32,
+) -> Result<OverhangReport> {
+    let up = build_direction
+        .try_normalize()
+        .ok_or_else(|| anyhow!("The build direction can't be zero"))?;
+    let mut report = OverhangReport::default();
+    let mut face_angles = vec![];
+    let mut vertex_angles = HashMap::<VertexId, f32>::new();
+    {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let bed = conn
+            .iter_vertices()
+            .map(|(v, _)| positions[v].dot(up))
+            .fold(f32::INFINITY, f32::min);
+        for (face, _) in conn.iter_faces() {
+            let vertices = conn.face_vertices(face);
+            let points = vertices.iter().map(|v| positions[*v]).collect::<SVec<_>>();
+            let normal = points
+                .iter()
+                .circular_tuple_windows()
+                .fold(Vec3::ZERO, |acc, (a, b)| acc + a.cross(*b));
+            let area = normal.length() * 0.5;
+            let on_bed = points.iter().all(|p| p.dot(up) - bed < 1e-4);
+            let angle = match normal.try_normalize() {
+                Some(normal) if !on_bed => (-normal.dot(up)).clamp(0.0, 1.0).asin(),
+                _ => 0.0,
+            };
+
+            report.total_area += area;
+            if angle > threshold_angle {
+                report.faces += 1;
+                report.area += area;
+            }
+            report.max_angle = report.max_angle.max(angle);
+            for v in vertices {
+                let max = vertex_angles.entry(v).or_insert(0.0);
+                *max = max.max(angle);
+            }
+            face_angles.push((face, angle));
+        }
+    }
+
+    let group_id = mesh.channels.ensure_channel::<FaceId, bool>(OVERHANG_GROUP);
+    let face_ch_id = mesh
+        .channels
+        .ensure_channel::<FaceId, f32>(OVERHANG_ANGLE_CHANNEL);
+    let vertex_ch_id = mesh
+        .channels
+        .ensure_channel::<VertexId, f32>(OVERHANG_ANGLE_CHANNEL);
+    let mut group = mesh.channels.write_channel(group_id)?;
+    let mut face_ch = mesh.channels.write_channel(face_ch_id)?;
+    let mut vertex_ch = mesh.channels.write_channel(vertex_ch_id)?;
+    for (face, angle) in face_angles {
+        group[face] = angle > threshold_angle;
+        face_ch[face] = angle;
+    }
+    for (v, angle) in vertex_angles {
+        vertex_ch[v] = angle;
+    }
+    Ok(report)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+    use mlua::Lua;
+
+    /// Measures the wall thickness of `mesh` at each vertex, up to
+    /// `max_distance`, and stores it in the `thickness` vertex channel.
+    #[lua(under = "Ops")]
+    fn analyze_thickness(mesh: &mut HalfEdgeMesh, max_distance: f32) -> Result<()> {
+        super::analyze_thickness(mesh, max_distance)
+    }
+
+    /// Finds the faces of `mesh` leaning more than `threshold_angle` radians
+    /// past vertical when printed in the `build_direction`. Writes the
+    /// `overhang` face group and the `overhang_angle` channels, and returns a
+    /// summary table with the number of overhanging `faces`, their `area`,
+    /// the `total_area` of the mesh and the `max_angle`.
+    #[lua(under = "Ops")]
+    fn analyze_overhangs<'lua>(
+        lua: &'lua Lua,
+        mesh: &mut HalfEdgeMesh,
+        build_direction: LVec3,
+        threshold_angle: f32,
+    ) -> Result<mlua::Table<'lua>> {
+        let report = super::analyze_overhangs(mesh, build_direction.0, threshold_angle)?;
+        let table = lua.create_table()?;
+        table.set("faces", report.faces)?;
+        table.set("area", report.area)?;
+        table.set("total_area", report.total_area)?;
+        table.set("max_angle", report.max_angle)?;
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    fn test_analyze_thickness() {
+        // A slab, 0.2 units thick
+        let mut mesh = Box::build(Vec3::ZERO, Vec3::new(4.0, 0.2, 4.0)).unwrap();
+        analyze_thickness(&mut mesh, 1.0).unwrap();
+        let ch = mesh
+            .channels
+            .read_channel_by_name::<VertexId, f32>(THICKNESS_CHANNEL)
+            .unwrap();
+        for (v, _) in mesh.read_connectivity().iter_vertices() {
+            assert!((ch[v] - 0.2).abs() < 1e-3, "{}", ch[v]);
+        }
+        drop(ch);
+
+        // Nothing is hit within a short distance
+        analyze_thickness(&mut mesh, 0.1).unwrap();
+        let ch = mesh
+            .channels
+            .read_channel_by_name::<VertexId, f32>(THICKNESS_CHANNEL)
+            .unwrap();
+        assert!(mesh
+            .read_connectivity()
+            .iter_vertices()
+            .all(|(v, _)| ch[v] == 0.1));
+    }
+
+    #[test]
+    fn test_analyze_overhangs() {
+        // A T shape: the bottom of the bar overhangs, the box's doesn't
+        let mut mesh = Box::build(Vec3::new(0.0, 0.5, 0.0), Vec3::ONE).unwrap();
+        let bar = Box::build(Vec3::new(0.0, 1.25, 0.0), Vec3::new(3.0, 0.5, 1.0)).unwrap();
+        mesh.merge_with(&bar);
+
+        let report = analyze_overhangs(&mut mesh, Vec3::Y, 45f32.to_radians()).unwrap();
+        assert_eq!(report.faces, 1);
+        assert!((report.area - 3.0).abs() < 1e-4);
+        assert!((report.max_angle - std::f32::consts::FRAC_PI_2).abs() < 1e-4);
+        let group = mesh
+            .channels
+            .read_channel_by_name::<FaceId, bool>(OVERHANG_GROUP)
+            .unwrap();
+        assert_eq!(
+            mesh.read_connectivity()
+                .iter_faces()
+                .filter(|(f, _)| group[*f])
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Approximate convex decomposition, in the spirit of V-HACD.
+//!
+//! The volume of the mesh is voxelized, and the set of voxels is recursively
+//! split in two by axis-aligned planes. At each step, the part whose convex
+//! hull differs the most from its own volume (its concavity) is split at the
+//! plane that minimizes the concavity of the two halves. The result is the
+//! convex hull of each part. Hulls are built from the corners of the voxels,
+//! so they slightly overestimate the volume of the mesh, which is usually
+//! what collision shapes want.
+
+use float_ord::FloatOrd;
+
+use crate::prelude::*;
+use crate::progress::{NoProgress, ProgressSink};
+
+use super::voxel_remesh::{compute_signs, mesh_triangles, Grid};
+
+/// Name of the face group containing the faces of collision shapes.
+pub const COLLISION_GROUP: &str = "collision";
+/// Name of the face channel storing the index of the hull each face belongs
+/// to, so exporters can tell the hulls apart once they're merged.
+pub const HULL_CHANNEL: &str = "hull";
+
+/// The number of planes tried along each axis when splitting a part.
+const SPLIT_CANDIDATES: usize = 8;
+
+/// Parts with a concavity below this fraction of the volume of the mesh are
+/// considered convex, and not split further.
+const MIN_CONCAVITY: f32 = 0.01;
+
+/// The convex hull of a set of points, as a list of triangles over them. The
+/// triangles are oriented to face outwards.
+pub struct ConvexHull {
+    pub points: Vec<Vec3>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl ConvexHull {
+    /// Computes the convex hull of `points`, adding one point at a time to
+    /// the hull of the previous ones. Returns `None` when the points are all
+    /// coplanar, since they have no volume.
+    pub fn new(points: &[Vec3]) -> Option<Self> {
+        let (min, max) = points.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        let eps = (max - min).length() * 1e-5;
+        if points.len() < 4 || eps <= 0.0 {
+            return None;
+        }
+
+        // The initial tetrahedron, as large as possible
+        let farthest = |distance: &dyn Fn(Vec3) -> f32| {
+            (0..points.len()).max_by_key(|i| FloatOrd(distance(points[*i])))
+        };
+        let a = farthest(&|p| -p.x)?;
+        let b = farthest(&|p| p.distance(points[a]))?;
+        let ab = points[b] - points[a];
+        let c = farthest(&|p| (p - points[a]).cross(ab).length())?;
+        let normal = ab.cross(points[c] - points[a]).normalize_or_zero();
+        let d = farthest(&|p| normal.dot(p - points[a]).abs())?;
+        if points[a].distance(points[b]) <= eps
+            || (points[c] - points[a]).cross(ab).length() <= eps * ab.length()
+            || normal.dot(points[d] - points[a]).abs() <= eps
+        {
+            return None;
+        }
+        // The base must face away from the fourth point
+        let (b, c) = if normal.dot(points[d] - points[a]) > 0.0 {
+            (c, b)
+        } else {
+            (b, c)
+        };
+
+        let face_normal = |[a, b, c]: [usize; 3]| {
+            (points[b] - points[a])
+                .cross(points[c] - points[a])
+                .normalize_or_zero()
+        };
+        let mut faces = [[a, b, c], [a, d, b], [b, d, c], [c, d, a]]
+            .into_iter()
+            .map(|tri| (tri, face_normal(tri)))
+            .collect_vec();
+
+        for (i, p) in points.iter().enumerate() {
+            let (visible, hidden): (Vec<_>, Vec<_>) = faces
+                .into_iter()
+                .partition(|(tri, normal)| normal.dot(*p - points[tri[0]]) > eps);
+            faces = hidden;
+            if visible.is_empty() {
+                continue;
+            }
+            // The horizon is made of the edges of the visible faces whose
+            // other side is not visible. It is connected to the new point.
+            let edges: HashSet<(usize, usize)> = visible
+                .iter()
+                .flat_map(|([a, b, c], _)| [(*a, *b), (*b, *c), (*c, *a)])
+                .collect();
+            for (a, b) in edges.iter().copied() {
+                if !edges.contains(&(b, a)) {
+                    let tri = [a, b, i];
+                    faces.push((tri, face_normal(tri)));
+                }
+            }
+        }
+
+        Some(Self {
+            points: points.to_vec(),
+            triangles: faces.into_iter().map(|(tri, _)| tri).collect(),
+        })
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.triangles
+            .iter()
+            .map(|[a, b, c]| self.points[*a].dot(self.points[*b].cross(self.points[*c])) / 6.0)
+            .sum()
+    }
+
+    /// Builds a mesh with the triangles of the hull. Points inside the hull
+    /// are left out.
+    pub fn to_mesh(&self) -> Result<HalfEdgeMesh> {
+        let mut index = HashMap::new();
+        let mut positions = vec![];
+        let triangles = self
+            .triangles
+            .iter()
+            .map(|tri| {
+                tri.map(|i| {
+                    *index.entry(i).or_insert_with(|| {
+                        positions.push(self.points[i]);
+                        positions.len() - 1
+                    })
+                })
+            })
+            .collect_vec();
+        HalfEdgeMesh::build_from_polygons(&positions, &triangles)
+    }
+}
+
+/// A set of voxels of the mesh, and its convex hull.
+struct Part {
+    voxels: Vec<[usize; 3]>,
+    hull: ConvexHull,
+    /// The volume of the hull not covered by the voxels.
+    concavity: f32,
+    /// Set when no plane can split the part in two.
+    done: bool,
+}
+
+impl Part {
+    fn new(voxels: Vec<[usize; 3]>, voxel_size: f32, origin: Vec3) -> Option<Self> {
+        // Only the voxels on the outside of the part can add points to its
+        // hull.
+        let set: HashSet<[usize; 3]> = voxels.iter().copied().collect();
+        let is_inner = |[i, j, k]: [usize; 3]| {
+            i > 0
+                && j > 0
+                && k > 0
+                && [
+                    [i - 1, j, k],
+                    [i + 1, j, k],
+                    [i, j - 1, k],
+                    [i, j + 1, k],
+                    [i, j, k - 1],
+                    [i, j, k + 1],
+                ]
+                .iter()
+                .all(|n| set.contains(n))
+        };
+        let mut corners = HashSet::new();
+        for [i, j, k] in voxels.iter_cpy().filter(|v| !is_inner(*v)) {
+            for c in 0..8 {
+                corners.insert([i + (c & 1), j + ((c >> 1) & 1), k + ((c >> 2) & 1)]);
+            }
+        }
+        // Voxels are centered on the grid points
+        // Sorted, so the hulls don't depend on the iteration order of the set
+        let points = corners
+            .into_iter()
+            .sorted()
+            .map(|[i, j, k]| {
+                origin + (Vec3::new(i as f32, j as f32, k as f32) - Vec3::splat(0.5)) * voxel_size
+            })
+            .collect_vec();
+
+        let hull = ConvexHull::new(&points)?;
+        let concavity = (hull.volume() - voxels.len() as f32 * voxel_size.powi(3)).max(0.0);
+        Some(Self {
+            voxels,
+            hull,
+            concavity,
+            done: false,
+        })
+    }
+
+    /// Splits the part in two along the plane giving the least concavity.
+    fn split(&self, voxel_size: f32, origin: Vec3) -> Option<(Part, Part)> {
+        let mut best: Option<(Part, Part)> = None;
+        for axis in 0..3 {
+            let min = self.voxels.iter().map(|v| v[axis]).min()?;
+            let max = self.voxels.iter().map(|v| v[axis]).max()?;
+            let planes = (1..=SPLIT_CANDIDATES)
+                .map(|n| min + (max + 1 - min) * n / (SPLIT_CANDIDATES + 1))
+                .filter(|plane| *plane > min && *plane <= max)
+                .dedup();
+            for plane in planes {
+                let (left, right): (Vec<_>, Vec<_>) =
+                    self.voxels.iter().copied().partition(|v| v[axis] < plane);
+                if let (Some(left), Some(right)) = (
+                    Part::new(left, voxel_size, origin),
+                    Part::new(right, voxel_size, origin),
+                ) {
+                    let cost = left.concavity + right.concavity;
+                    if best
+                        .as_ref()
+                        .map_or(true, |(l, r)| cost < l.concavity + r.concavity)
+                    {
+                        best = Some((left, right));
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Approximates the volume of `mesh` with at most `max_hulls` convex hulls.
+/// The mesh is voxelized with `resolution` voxels along its longest side,
+/// and should be closed. Each hull has all its faces in the
+/// [`COLLISION_GROUP`], and its index in the [`HULL_CHANNEL`].
+pub fn convex_decompose(
+    mesh: &HalfEdgeMesh,
+    max_hulls: usize,
+    resolution: usize,
+) -> Result<Vec<HalfEdgeMesh>> {
+    convex_decompose_with_progress(mesh, max_hulls, resolution, &NoProgress)
+}
+
+/// Same as `convex_decompose`, but reports the progress to the given
+/// `progress` sink, and stops early when it gets cancelled.
+pub fn convex_decompose_with_progress(
+    mesh: &HalfEdgeMesh,
+    max_hulls: usize,
+    resolution: usize,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<HalfEdgeMesh>> {
+    let name = "Convex decomposition";
+    if max_hulls == 0 {
+        bail!("At least one hull is needed");
+    }
+    let triangles = mesh_triangles(mesh);
+    if triangles.is_empty() {
+        bail!("Cannot decompose a mesh without faces");
+    }
+
+    let (min, max) = triangles.iter().flatten().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    );
+    let resolution = resolution.clamp(4, 128);
+    let voxel_size = (max - min).max_element() / resolution as f32;
+    if voxel_size <= 0.0 {
+        bail!("Cannot decompose a mesh without volume");
+    }
+    let origin = min - Vec3::splat(voxel_size);
+    let dims = ((max - min) / voxel_size)
+        .to_array()
+        .map(|x| x.ceil() as usize + 3);
+    let mut grid = Grid {
+        origin,
+        voxel_size,
+        dims,
+        values: vec![1.0; dims.iter().product()],
+    };
+    compute_signs(&mut grid, &triangles);
+
+    let mut voxels = vec![];
+    for k in 0..dims[2] {
+        for j in 0..dims[1] {
+            for i in 0..dims[0] {
+                if grid.values[grid.index(i, j, k)] < 0.0 {
+                    voxels.push([i, j, k]);
+                }
+            }
+        }
+    }
+    let total_volume = voxels.len() as f32 * voxel_size.powi(3);
+    let mut parts = vec![Part::new(voxels, voxel_size, origin)
+        .ok_or_else(|| anyhow!("The mesh has no volume at this resolution. Is it closed?"))?];
+
+    while parts.len() < max_hulls {
+        let worst = parts
+            .iter()
+            .enumerate()
+            .filter(|(_, part)| !part.done)
+            .max_by_key(|(_, part)| FloatOrd(part.concavity))
+            .map(|(i, _)| i);
+        let worst = match worst {
+            Some(i) if parts[i].concavity > MIN_CONCAVITY * total_volume => i,
+            _ => break,
+        };
+        match parts[worst].split(voxel_size, origin) {
+            Some((left, right)) => {
+                parts.swap_remove(worst);
+                parts.push(left);
+                parts.push(right);
+            }
+            None => parts[worst].done = true,
+        }
+        progress.report(name, parts.len() as f32 / max_hulls as f32);
+        progress.check_cancelled()?;
+    }
+    progress.report(name, 1.0);
+
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let mut hull = part.hull.to_mesh()?;
+            let faces = hull
+                .read_connectivity()
+                .iter_faces()
+                .map(|(f, _)| f)
+                .collect_vec();
+            let group_id = hull
+                .channels
+                .ensure_channel::<FaceId, bool>(COLLISION_GROUP);
+            let hull_id = hull.channels.ensure_channel::<FaceId, f32>(HULL_CHANNEL);
+            let mut group = hull.channels.write_channel(group_id)?;
+            let mut hull_ch = hull.channels.write_channel(hull_id)?;
+            for face in faces {
+                group[face] = true;
+                hull_ch[face] = i as f32;
+            }
+            drop((group, hull_ch));
+            Ok(hull)
+        })
+        .collect()
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::progress::ProgressTracker;
+    use mlua::Lua;
+
+    /// Approximates the volume of `mesh` with a list of at most `max_hulls`
+    /// convex meshes, to be used as collision shapes. The mesh is voxelized
+    /// with `resolution` voxels along its longest side. The faces of the
+    /// hulls are in the `collision` group, and the `hull` face channel stores
+    /// the index of their hull.
+    #[lua(under = "Ops")]
+    fn convex_decompose<'lua>(
+        lua: &'lua Lua,
+        mesh: &HalfEdgeMesh,
+        max_hulls: usize,
+        resolution: usize,
+    ) -> Result<mlua::Table<'lua>> {
+        let hulls = super::convex_decompose_with_progress(
+            mesh,
+            max_hulls,
+            resolution,
+            ProgressTracker::current(lua).as_ref(),
+        )?;
+        Ok(lua.create_sequence_from(hulls)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    fn test_convex_hull() {
+        let mut points = vec![];
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    points.push(Vec3::new(x as f32, y as f32, z as f32));
+                }
+            }
+        }
+        let hull = ConvexHull::new(&points).unwrap();
+        assert!((hull.volume() - 27.0).abs() < 1e-3);
+        let mesh = hull.to_mesh().unwrap();
+        assert_eq!(mesh.read_connectivity().num_vertices(), 8);
+
+        assert!(ConvexHull::new(&[Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::X + Vec3::Y]).is_none());
+    }
+
+    #[test]
+    fn test_convex_decompose() {
+        // An L shape made of two boxes, which needs at least two hulls
+        let mut mesh = Box::build(Vec3::new(0.0, 0.5, 0.0), Vec3::new(3.0, 1.0, 1.0)).unwrap();
+        let top = Box::build(Vec3::new(-1.0, 2.0, 0.0), Vec3::new(1.0, 2.0, 1.0)).unwrap();
+        mesh.merge_with(&top);
+
+        let single = convex_decompose(&mesh, 1, 32).unwrap();
+        assert_eq!(single.len(), 1);
+        let hulls = convex_decompose(&mesh, 4, 32).unwrap();
+        assert!(hulls.len() >= 2 && hulls.len() <= 4);
+        for hull in &hulls {
+            let conn = hull.read_connectivity();
+            let group = hull
+                .channels
+                .read_channel_by_name::<FaceId, bool>(COLLISION_GROUP)
+                .unwrap();
+            assert!(conn.iter_faces().all(|(f, _)| group[f]));
+        }
+    }
+}
@@ -0,0 +1,781 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Boolean operations between closed meshes, using BSP trees.
+//!
+//! Each mesh is stored as a BSP tree of convex polygons. The trees are then
+//! used to clip the polygons of the other mesh, keeping the parts inside or
+//! outside of it depending on the operation. This is the same approach used by
+//! the csg.js library.
+
+use crate::prelude::*;
+use crate::progress::{NoProgress, ProgressSink};
+
+/// Distance under which a point is considered to lie on a plane.
+const EPSILON: f32 = 1e-5;
+
+/// Vertices of the result closer than this distance are merged together.
+const WELD_DISTANCE: f32 = 1e-4;
+
+/// Name of the halfedge group containing the edges of the result of a boolean
+/// operation where the surfaces of both meshes meet.
+pub const SEAM_GROUP: &str = "boolean_seam";
+
+/// Profile of the bevels made by [`fillet_seams`], which gives a circular
+/// arc. See [`edit_ops::BevelProfile`].
+const FILLET_PROFILE: edit_ops::BevelProfile<'static> = edit_ops::BevelProfile::Superellipse(0.7);
+
+/// The boolean operation to apply between two meshes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanMode {
+    /// Keeps the volume inside either mesh.
+    Union,
+    /// Keeps the volume inside the first mesh, but not the second one.
+    Difference,
+    /// Keeps the volume inside both meshes.
+    Intersection,
+}
+
+impl BooleanMode {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Union" => Ok(Self::Union),
+            "Difference" => Ok(Self::Difference),
+            "Intersection" => Ok(Self::Intersection),
+            _ => bail!("Invalid boolean mode: {name}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Plane {
+    normal: Vec3,
+    /// Distance from the origin, along the normal.
+    w: f32,
+}
+
+impl Plane {
+    fn flip(&mut self) {
+        self.normal = -self.normal;
+        self.w = -self.w;
+    }
+
+    fn distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) - self.w
+    }
+}
+
+/// A convex polygon.
+#[derive(Clone, Debug)]
+struct Polygon {
+    vertices: Vec<Vec3>,
+    plane: Plane,
+    /// Whether the polygon comes from the second mesh of the operation.
+    from_b: bool,
+}
+
+impl Polygon {
+    /// Returns the polygon with the given vertices, or None when it is
+    /// degenerate. The plane is computed using Newell's method, which is
+    /// robust to nearly collinear vertices.
+    fn new(vertices: Vec<Vec3>, from_b: bool) -> Option<Self> {
+        if vertices.len() < 3 {
+            return None;
+        }
+        let normal = vertices
+            .iter_cpy()
+            .circular_tuple_windows()
+            .fold(Vec3::ZERO, |acc, (a, b)| acc + a.cross(b))
+            .try_normalize()?;
+        let centroid =
+            vertices.iter_cpy().fold(Vec3::ZERO, |acc, v| acc + v) / vertices.len() as f32;
+        Some(Self {
+            plane: Plane {
+                normal,
+                w: normal.dot(centroid),
+            },
+            vertices,
+            from_b,
+        })
+    }
+
+    fn flip(&mut self) {
+        self.vertices.reverse();
+        self.plane.flip();
+    }
+
+    fn is_convex(&self) -> bool {
+        self.vertices
+            .iter_cpy()
+            .circular_tuple_windows()
+            .all(|(a, b, c)| (b - a).cross(c - b).dot(self.plane.normal) >= -EPSILON)
+    }
+}
+
+/// The pieces of a set of polygons after splitting them by a plane.
+#[derive(Default)]
+struct Split {
+    coplanar_front: Vec<Polygon>,
+    coplanar_back: Vec<Polygon>,
+    front: Vec<Polygon>,
+    back: Vec<Polygon>,
+}
+
+impl Plane {
+    /// Splits `polygon` by this plane, and adds the pieces to `split`.
+    /// Polygons lying on the plane are classified depending on whether they
+    /// face the same way as the plane.
+    fn split_polygon(&self, polygon: Polygon, split: &mut Split) {
+        const COPLANAR: u8 = 0;
+        const FRONT: u8 = 1;
+        const BACK: u8 = 2;
+        const SPANNING: u8 = FRONT | BACK;
+
+        let types = polygon
+            .vertices
+            .iter()
+            .map(|v| {
+                let t = self.distance(*v);
+                if t < -EPSILON {
+                    BACK
+                } else if t > EPSILON {
+                    FRONT
+                } else {
+                    COPLANAR
+                }
+            })
+            .collect_vec();
+        let polygon_type = types.iter().fold(COPLANAR, |acc, t| acc | t);
+
+        match polygon_type {
+            COPLANAR => {
+                if self.normal.dot(polygon.plane.normal) > 0.0 {
+                    split.coplanar_front.push(polygon)
+                } else {
+                    split.coplanar_back.push(polygon)
+                }
+            }
+            FRONT => split.front.push(polygon),
+            BACK => split.back.push(polygon),
+            _ => {
+                debug_assert_eq!(polygon_type, SPANNING);
+                let mut front = vec![];
+                let mut back = vec![];
+                let n = polygon.vertices.len();
+                for i in 0..n {
+                    let j = (i + 1) % n;
+                    let (ti, tj) = (types[i], types[j]);
+                    let (vi, vj) = (polygon.vertices[i], polygon.vertices[j]);
+                    if ti != BACK {
+                        front.push(vi);
+                    }
+                    if ti != FRONT {
+                        back.push(vi);
+                    }
+                    if ti | tj == SPANNING {
+                        let t = (self.w - self.normal.dot(vi)) / self.normal.dot(vj - vi);
+                        let v = vi.lerp(vj, t);
+                        front.push(v);
+                        back.push(v);
+                    }
+                }
+                // The pieces keep the plane of the original polygon, so
+                // slivers don't end up with a badly estimated normal.
+                if front.len() >= 3 {
+                    split.front.push(Polygon {
+                        vertices: front,
+                        plane: polygon.plane,
+                        from_b: polygon.from_b,
+                    });
+                }
+                if back.len() >= 3 {
+                    split.back.push(Polygon {
+                        vertices: back,
+                        plane: polygon.plane,
+                        from_b: polygon.from_b,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct BspNode {
+    plane: Option<Plane>,
+    front: Option<usize>,
+    back: Option<usize>,
+    /// The polygons lying on this node's plane.
+    polygons: Vec<Polygon>,
+}
+
+/// A BSP tree of polygons. Nodes are stored in a flat list, and all the
+/// traversals use an explicit stack, so large meshes don't overflow the call
+/// stack.
+#[derive(Default)]
+struct BspTree {
+    /// The root node, if any, is the first one.
+    nodes: Vec<BspNode>,
+}
+
+impl BspTree {
+    fn new(polygons: Vec<Polygon>) -> Self {
+        let mut tree = Self::default();
+        tree.build(polygons);
+        tree
+    }
+
+    /// Swaps the inside and outside of the solid represented by this tree.
+    fn invert(&mut self) {
+        for node in &mut self.nodes {
+            for polygon in &mut node.polygons {
+                polygon.flip();
+            }
+            if let Some(plane) = &mut node.plane {
+                plane.flip();
+            }
+            std::mem::swap(&mut node.front, &mut node.back);
+        }
+    }
+
+    /// Returns the parts of `polygons` that are outside of this tree.
+    fn clip_polygons(&self, polygons: Vec<Polygon>) -> Vec<Polygon> {
+        if self.nodes.is_empty() {
+            return polygons;
+        }
+        let mut result = vec![];
+        let mut stack = vec![(0, polygons)];
+        while let Some((n, polygons)) = stack.pop() {
+            let node = &self.nodes[n];
+            let plane = match node.plane {
+                Some(plane) => plane,
+                None => {
+                    result.extend(polygons);
+                    continue;
+                }
+            };
+            let mut split = Split::default();
+            for polygon in polygons {
+                plane.split_polygon(polygon, &mut split);
+            }
+            let mut front = split.front;
+            front.extend(split.coplanar_front);
+            let mut back = split.back;
+            back.extend(split.coplanar_back);
+
+            match node.front {
+                Some(front_node) => stack.push((front_node, front)),
+                None => result.extend(front),
+            }
+            // Polygons behind a leaf are inside the solid, and get discarded
+            if let Some(back_node) = node.back {
+                stack.push((back_node, back));
+            }
+        }
+        result
+    }
+
+    /// Removes all the parts of the polygons in this tree that are inside
+    /// `other`.
+    fn clip_to(&mut self, other: &BspTree) {
+        for node in &mut self.nodes {
+            node.polygons = other.clip_polygons(std::mem::take(&mut node.polygons));
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<Polygon> {
+        self.nodes
+            .iter()
+            .flat_map(|node| node.polygons.iter().cloned())
+            .collect()
+    }
+
+    /// Adds `polygons` to this tree, splitting them as needed.
+    fn build(&mut self, polygons: Vec<Polygon>) {
+        if polygons.is_empty() {
+            return;
+        }
+        if self.nodes.is_empty() {
+            self.nodes.push(BspNode::default());
+        }
+        let mut stack = vec![(0, polygons)];
+        while let Some((n, polygons)) = stack.pop() {
+            if polygons.is_empty() {
+                continue;
+            }
+            let plane = *self.nodes[n].plane.get_or_insert(polygons[0].plane);
+            let mut split = Split::default();
+            for polygon in polygons {
+                plane.split_polygon(polygon, &mut split);
+            }
+            let node = &mut self.nodes[n];
+            node.polygons.extend(split.coplanar_front);
+            node.polygons.extend(split.coplanar_back);
+
+            for (polygons, is_front) in [(split.front, true), (split.back, false)] {
+                if polygons.is_empty() {
+                    continue;
+                }
+                let existing = if is_front {
+                    self.nodes[n].front
+                } else {
+                    self.nodes[n].back
+                };
+                let child = existing.unwrap_or_else(|| {
+                    self.nodes.push(BspNode::default());
+                    let child = self.nodes.len() - 1;
+                    if is_front {
+                        self.nodes[n].front = Some(child);
+                    } else {
+                        self.nodes[n].back = Some(child);
+                    }
+                    child
+                });
+                stack.push((child, polygons));
+            }
+        }
+    }
+}
+
+/// Converts the faces of `mesh` into convex polygons. Concave faces are
+/// triangulated. The polygons are marked as coming from the second mesh of
+/// the operation when `from_b` is true.
+fn mesh_to_polygons(mesh: &HalfEdgeMesh, from_b: bool) -> Vec<Polygon> {
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let mut polygons = vec![];
+    for (face, _) in conn.iter_faces() {
+        let vertices = conn
+            .face_vertices(face)
+            .iter_cpy()
+            .map(|v| positions[v])
+            .collect_vec();
+        let polygon = match Polygon::new(vertices, from_b) {
+            Some(polygon) => polygon,
+            None => continue,
+        };
+        if polygon.is_convex() {
+            polygons.push(polygon);
+            continue;
+        }
+
+        // Project the face on its plane, and triangulate it there
+        let normal = polygon.plane.normal;
+        let u = normal.any_orthonormal_vector();
+        let v = normal.cross(u);
+        let coords = polygon
+            .vertices
+            .iter()
+            .flat_map(|p| [p.dot(u) as f64, p.dot(v) as f64])
+            .collect_vec();
+        let triangles = earcutr::earcut(&coords, &[], 2);
+        for tri in triangles.chunks_exact(3) {
+            polygons.extend(Polygon::new(
+                tri.iter().map(|i| polygon.vertices[*i]).collect(),
+                from_b,
+            ));
+        }
+    }
+    polygons
+}
+
+/// Inserts the vertices lying in the middle of an edge of `faces` into it.
+///
+/// Clipping splits each polygon on its own, so the two faces sharing an edge
+/// may be cut at different points. The vertex where one of them was cut ends
+/// up in the middle of the other's edge, a T-junction, which would leave a
+/// hole in the mesh.
+fn resolve_t_junctions(positions: &[Vec3], faces: &mut [SVec<usize>]) {
+    // Only edges without a twin can have vertices in their middle, and those
+    // vertices are always the endpoints of other edges without a twin.
+    let edges = faces
+        .iter()
+        .flat_map(|face| face.iter_cpy().circular_tuple_windows())
+        .collect::<HashSet<(usize, usize)>>();
+    let open_edges = edges
+        .iter_cpy()
+        .filter(|(a, b)| !edges.contains(&(*b, *a)))
+        .collect_vec();
+    if open_edges.is_empty() {
+        return;
+    }
+
+    // Candidate vertices are bucketed in a grid with cells as large as the
+    // longest open edge, so each edge only needs to look at a few cells.
+    let cell_size = open_edges
+        .iter()
+        .map(|(a, b)| positions[*a].distance(positions[*b]))
+        .fold(WELD_DISTANCE, f32::max);
+    let cell = |p: Vec3| (p / cell_size).floor().to_array().map(|x| x as i64);
+    let mut grid = HashMap::<[i64; 3], Vec<usize>>::new();
+    for v in open_edges.iter().flat_map(|(a, b)| [*a, *b]).unique() {
+        grid.entry(cell(positions[v])).or_default().push(v);
+    }
+
+    let mut splits = HashMap::<(usize, usize), SVec<usize>>::new();
+    for (a, b) in open_edges {
+        let (pa, pb) = (positions[a], positions[b]);
+        let dir = pb - pa;
+        let min = cell(pa.min(pb) - Vec3::splat(WELD_DISTANCE));
+        let max = cell(pa.max(pb) + Vec3::splat(WELD_DISTANCE));
+        let mut on_edge = vec![];
+        for x in min[0]..=max[0] {
+            for y in min[1]..=max[1] {
+                for z in min[2]..=max[2] {
+                    for v in grid.get(&[x, y, z]).into_iter().flatten().copied() {
+                        let p = positions[v];
+                        let t = (p - pa).dot(dir) / dir.length_squared();
+                        if v != a
+                            && v != b
+                            && t > 0.0
+                            && t < 1.0
+                            && p.distance(pa + dir * t) < WELD_DISTANCE
+                        {
+                            on_edge.push((t, v));
+                        }
+                    }
+                }
+            }
+        }
+        if !on_edge.is_empty() {
+            on_edge.sort_by(|(t1, _), (t2, _)| t1.total_cmp(t2));
+            splits.insert((a, b), on_edge.into_iter().map(|(_, v)| v).collect());
+        }
+    }
+
+    for face in faces {
+        let edges = face.iter_cpy().circular_tuple_windows().collect_vec();
+        if edges.iter().any(|edge| splits.contains_key(edge)) {
+            *face = edges
+                .into_iter()
+                .flat_map(|(a, b)| {
+                    std::iter::once(a).chain(splits.get(&(a, b)).into_iter().flatten().copied())
+                })
+                .collect();
+        }
+    }
+}
+
+/// Builds a mesh out of `polygons`, merging the vertices that are closer
+/// than [`WELD_DISTANCE`] and resolving the T-junctions left by clipping.
+/// The edges between polygons coming from different meshes are added to the
+/// [`SEAM_GROUP`].
+fn polygons_to_mesh(polygons: &[Polygon]) -> Result<HalfEdgeMesh> {
+    let mut positions = vec![];
+    let mut vertex_ids = HashMap::<[i64; 3], usize>::new();
+    let mut faces: Vec<SVec<usize>> = vec![];
+    let mut face_from_b = vec![];
+    for polygon in polygons {
+        let mut face = SVec::new();
+        for p in polygon.vertices.iter_cpy() {
+            let key = (p / WELD_DISTANCE).round().to_array().map(|x| x as i64);
+            let id = *vertex_ids.entry(key).or_insert_with(|| {
+                positions.push(p);
+                positions.len() - 1
+            });
+            if face.last() != Some(&id) {
+                face.push(id);
+            }
+        }
+        if face.len() > 1 && face.first() == face.last() {
+            face.pop();
+        }
+        if face.len() >= 3 {
+            faces.push(face);
+            face_from_b.push(polygon.from_b);
+        }
+    }
+    resolve_t_junctions(&positions, &mut faces);
+    let mut mesh = HalfEdgeMesh::build_from_polygons(&positions, &faces)
+        .context("The result of the boolean operation is not a valid mesh")?;
+
+    // NOTE: Relies on `build_from_polygons` allocating faces in the same order
+    // as the polygons.
+    let seam_ch = mesh.channels.ensure_channel::<HalfEdgeId, bool>(SEAM_GROUP);
+    {
+        let conn = mesh.read_connectivity();
+        let from_b = conn
+            .iter_faces()
+            .map(|(face, _)| face)
+            .zip(face_from_b)
+            .collect::<HashMap<_, _>>();
+        let mut seam_ch = mesh.channels.write_channel(seam_ch)?;
+        for (h, _) in conn.iter_halfedges() {
+            let face = conn.at_halfedge(h).face().try_end().ok();
+            let twin_face = conn.at_halfedge(h).twin().face().try_end().ok();
+            if let (Some(face), Some(twin_face)) = (face, twin_face) {
+                seam_ch[h] = from_b[&face] != from_b[&twin_face];
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+/// Computes the boolean operation between `a` and `b`, which should be
+/// closed meshes. Only the vertex positions of the result are computed,
+/// other channels are discarded. The edges where the surfaces of both meshes
+/// meet are added to the `boolean_seam` halfedge group.
+pub fn boolean(a: &HalfEdgeMesh, b: &HalfEdgeMesh, mode: BooleanMode) -> Result<HalfEdgeMesh> {
+    boolean_with_progress(a, b, mode, &NoProgress)
+}
+
+/// Same as `boolean`, but reports the progress to the given `progress` sink,
+/// and stops early when it gets cancelled.
+pub fn boolean_with_progress(
+    a: &HalfEdgeMesh,
+    b: &HalfEdgeMesh,
+    mode: BooleanMode,
+    progress: &dyn ProgressSink,
+) -> Result<HalfEdgeMesh> {
+    let name = "Boolean operation";
+    // Each step reports progress and checks for cancellation
+    let step = |fraction: f32| {
+        progress.report(name, fraction);
+        progress.check_cancelled()
+    };
+
+    step(0.0)?;
+    let mut a = BspTree::new(mesh_to_polygons(a, false));
+    step(0.2)?;
+    let mut b = BspTree::new(mesh_to_polygons(b, true));
+    step(0.4)?;
+
+    // See the csg.js documentation for an explanation of these steps
+    match mode {
+        BooleanMode::Union => {
+            a.clip_to(&b);
+            b.clip_to(&a);
+            step(0.6)?;
+            b.invert();
+            b.clip_to(&a);
+            b.invert();
+            step(0.8)?;
+            a.build(b.all_polygons());
+        }
+        BooleanMode::Difference => {
+            a.invert();
+            a.clip_to(&b);
+            b.clip_to(&a);
+            step(0.6)?;
+            b.invert();
+            b.clip_to(&a);
+            b.invert();
+            step(0.8)?;
+            a.build(b.all_polygons());
+            a.invert();
+        }
+        BooleanMode::Intersection => {
+            a.invert();
+            b.clip_to(&a);
+            b.invert();
+            step(0.6)?;
+            a.clip_to(&b);
+            b.clip_to(&a);
+            step(0.8)?;
+            a.build(b.all_polygons());
+            a.invert();
+        }
+    }
+
+    let mesh = polygons_to_mesh(&a.all_polygons())?;
+    progress.report(name, 1.0);
+    Ok(mesh)
+}
+
+/// Rounds the seams left by a boolean operation in `mesh`, which are the
+/// edges in the `boolean_seam` group. Each seam is beveled by `radius`, with a
+/// circular profile of `segments` faces. A single segment gives a chamfer.
+pub fn fillet_seams(mesh: &HalfEdgeMesh, radius: f32, segments: usize) -> Result<HalfEdgeMesh> {
+    let result = mesh.clone();
+    let seam_ch = result
+        .channels
+        .read_channel_by_name::<HalfEdgeId, bool>(SEAM_GROUP)
+        .map_err(|_| anyhow!("The mesh has no boolean seams. Is it the result of a boolean?"))?;
+    let seams = result
+        .read_connectivity()
+        .iter_halfedges()
+        .filter(|(h, _)| seam_ch[*h])
+        .map(|(h, _)| h)
+        .collect_vec();
+    drop(seam_ch);
+    edit_ops::bevel_edges_with_profile(
+        &mut result.write_connectivity(),
+        &mut result.write_positions(),
+        &seams,
+        radius,
+        segments,
+        FILLET_PROFILE,
+    )?;
+    Ok(result)
+}
+
+/// Number of vertices around the threads made by [`thread`].
+const THREAD_SEGMENTS: usize = 32;
+
+/// Adds an external ISO metric screw thread to `mesh`, or cuts an internal
+/// one into it when `internal` is true. The thread runs along the Y axis,
+/// centered at `center`, with the given major `diameter`, `pitch` and
+/// `length`. External threads are usually placed over an existing cylinder,
+/// and internal ones through a hole or solid part.
+pub fn thread(
+    mesh: &HalfEdgeMesh,
+    center: Vec3,
+    diameter: f32,
+    pitch: f32,
+    length: f32,
+    internal: bool,
+) -> Result<HalfEdgeMesh> {
+    thread_with_progress(mesh, center, diameter, pitch, length, internal, &NoProgress)
+}
+
+/// Same as `thread`, but reports the progress to the given `progress` sink,
+/// and stops early when it gets cancelled.
+pub fn thread_with_progress(
+    mesh: &HalfEdgeMesh,
+    center: Vec3,
+    diameter: f32,
+    pitch: f32,
+    length: f32,
+    internal: bool,
+    progress: &dyn ProgressSink,
+) -> Result<HalfEdgeMesh> {
+    let rod = primitives::Thread::build(center, diameter, pitch, length, THREAD_SEGMENTS)?;
+    let mode = if internal {
+        BooleanMode::Difference
+    } else {
+        BooleanMode::Union
+    };
+    boolean_with_progress(mesh, &rod, mode, progress)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+    use crate::progress::ProgressTracker;
+    use mlua::Lua;
+
+    /// Computes a boolean operation between the closed meshes `mesh_a` and
+    /// `mesh_b`. The `mode` can be "Union", "Difference" (`mesh_a` minus
+    /// `mesh_b`) or "Intersection". Only vertex positions are kept.
+    #[lua(under = "Ops")]
+    fn boolean(
+        lua: &Lua,
+        mesh_a: &HalfEdgeMesh,
+        mesh_b: &HalfEdgeMesh,
+        mode: String,
+    ) -> Result<HalfEdgeMesh> {
+        super::boolean_with_progress(
+            mesh_a,
+            mesh_b,
+            BooleanMode::from_name(&mode)?,
+            ProgressTracker::current(lua).as_ref(),
+        )
+    }
+
+    /// Rounds the seams left by a boolean operation in `mesh`, beveling them
+    /// by `radius` with a circular profile of `segments` faces.
+    #[lua(under = "Ops")]
+    fn fillet_seams(mesh: &HalfEdgeMesh, radius: f32, segments: usize) -> Result<HalfEdgeMesh> {
+        super::fillet_seams(mesh, radius, segments)
+    }
+
+    /// Adds an ISO metric screw thread along the Y axis to `mesh`, centered
+    /// at `center`, with the given major `diameter`, `pitch` and `length`.
+    /// When `internal` is true, the thread is cut into the mesh instead.
+    #[lua(under = "Ops")]
+    fn thread(
+        lua: &Lua,
+        mesh: &HalfEdgeMesh,
+        center: LVec3,
+        diameter: f32,
+        pitch: f32,
+        length: f32,
+        internal: bool,
+    ) -> Result<HalfEdgeMesh> {
+        super::thread_with_progress(
+            mesh,
+            center.0,
+            diameter,
+            pitch,
+            length,
+            internal,
+            ProgressTracker::current(lua).as_ref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::{Box, UVSphere};
+
+    fn volume(mesh: &HalfEdgeMesh) -> f32 {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        conn.iter_faces()
+            .map(|(face, _)| {
+                let vertices = conn.face_vertices(face);
+                let p0 = positions[vertices[0]];
+                vertices[1..]
+                    .iter_cpy()
+                    .tuple_windows()
+                    .map(|(v1, v2)| p0.dot(positions[v1].cross(positions[v2])) / 6.0)
+                    .sum::<f32>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_boolean() {
+        let a = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let b = Box::build(Vec3::splat(0.5), Vec3::ONE).unwrap();
+        let approx = |mesh: HalfEdgeMesh, expected: f32| {
+            let v = volume(&mesh).abs();
+            assert!((v - expected).abs() < 1e-3, "{v} != {expected}");
+        };
+        approx(boolean(&a, &b, BooleanMode::Union).unwrap(), 2.0 - 0.125);
+        approx(
+            boolean(&a, &b, BooleanMode::Difference).unwrap(),
+            1.0 - 0.125,
+        );
+        approx(boolean(&a, &b, BooleanMode::Intersection).unwrap(), 0.125);
+
+        // The seams are where the faces of both boxes meet
+        let result = boolean(&a, &b, BooleanMode::Intersection).unwrap();
+        let seams = result
+            .channels
+            .read_channel_by_name::<HalfEdgeId, bool>(SEAM_GROUP)
+            .unwrap();
+        let conn = result.read_connectivity();
+        assert!(conn.iter_halfedges().any(|(h, _)| seams[h]));
+        assert!(!conn.iter_halfedges().all(|(h, _)| seams[h]));
+    }
+
+    #[test]
+    fn test_boolean_is_watertight() {
+        // The faces of the sphere are not aligned with the box, so clipping
+        // cuts the edges they share at different points.
+        let sphere = UVSphere::build(Vec3::ZERO, 24, 16, 1.0).unwrap();
+        let cube = Box::build(Vec3::new(0.6, 0.5, 0.4), Vec3::ONE).unwrap();
+        for mode in [
+            BooleanMode::Union,
+            BooleanMode::Difference,
+            BooleanMode::Intersection,
+        ] {
+            let result = boolean(&sphere, &cube, mode).unwrap();
+            let conn = result.read_connectivity();
+            assert!(
+                conn.iter_halfedges()
+                    .all(|(h, _)| conn.at_halfedge(h).face().try_end().is_ok()),
+                "{mode:?} has holes"
+            );
+        }
+
+        let difference = boolean(&sphere, &cube, BooleanMode::Difference).unwrap();
+        assert!(volume(&difference).abs() < volume(&sphere).abs());
+    }
+}
@@ -0,0 +1,717 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Mesh simplification using quadric error metrics (Garland & Heckbert).
+//!
+//! Each vertex accumulates a quadric measuring the squared distance to the
+//! planes of its original triangles. Edges are then collapsed in order of
+//! increasing error, moving the remaining vertex to the position minimizing
+//! the error of both endpoints. Collapses that would break the manifold
+//! structure or flip triangles are skipped.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use float_ord::FloatOrd;
+use glam::{DMat3, DVec3};
+
+use crate::materials::MATERIAL_CHANNEL;
+use crate::prelude::*;
+use crate::progress::{NoProgress, ProgressSink};
+
+use super::halfedge::edit_ops::{triangulate, TriangulateMode};
+
+/// Boundary edges are kept in place by adding quadrics for planes
+/// perpendicular to their faces, scaled by this weight.
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// Collapses that change the normal of a triangle by more than this (as the
+/// cosine of the angle) are rejected, to avoid folding the surface over.
+const MIN_NORMAL_DOT: f64 = 0.2;
+
+/// When to stop simplifying a mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecimateTarget {
+    /// Collapse edges until the mesh has this number of triangles.
+    FaceCount(usize),
+    /// Collapse edges while the error is below this value. The error is
+    /// roughly the distance the surface moves away from the original one.
+    MaxError(f32),
+}
+
+/// A symmetric 4x4 matrix measuring the sum of squared distances to a set of
+/// planes. Only the upper triangle is stored.
+#[derive(Clone, Copy, Debug, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// The quadric of the plane with the given unit `normal`, containing
+    /// `point`.
+    fn from_plane(normal: DVec3, point: DVec3, weight: f64) -> Self {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        let d = -normal.dot(point);
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|x| x * weight),
+        )
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let mut result = *self;
+        for (x, y) in result.0.iter_mut().zip(other.0) {
+            *x += y;
+        }
+        result
+    }
+
+    fn error(&self, p: DVec3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (p.x, p.y, p.z);
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    /// The point minimizing the error, if the quadric isn't singular.
+    fn optimal_point(&self) -> Option<DVec3> {
+        let q = &self.0;
+        let a = DMat3::from_cols(
+            DVec3::new(q[0], q[1], q[2]),
+            DVec3::new(q[1], q[4], q[5]),
+            DVec3::new(q[2], q[5], q[7]),
+        );
+        if a.determinant().abs() < 1e-12 {
+            return None;
+        }
+        Some(a.inverse() * -DVec3::new(q[3], q[6], q[8]))
+    }
+}
+
+fn to_dvec3(v: Vec3) -> DVec3 {
+    DVec3::new(v.x as f64, v.y as f64, v.z as f64)
+}
+
+fn triangle_normal(a: DVec3, b: DVec3, c: DVec3) -> DVec3 {
+    (b - a).cross(c - a)
+}
+
+/// The state of the simplification: An indexed triangle mesh, where each
+/// vertex knows the triangles around it.
+struct Decimation {
+    positions: Vec<DVec3>,
+    quadrics: Vec<Quadric>,
+    triangles: Vec<[usize; 3]>,
+    triangle_alive: Vec<bool>,
+    vertex_triangles: Vec<Vec<usize>>,
+    vertex_alive: Vec<bool>,
+    /// Incremented when a vertex changes, to discard outdated collapses.
+    vertex_version: Vec<u32>,
+    num_triangles: usize,
+    /// Vertices that can't be moved or collapsed away. Other vertices can
+    /// still be collapsed into them.
+    locked: Vec<bool>,
+    /// The UVs at the corners of each triangle, when they are preserved.
+    corner_uvs: Option<Vec<[Vec3; 3]>>,
+}
+
+/// An edge collapse waiting in the queue. Outdated entries are skipped.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Collapse {
+    cost: FloatOrd<f64>,
+    v: usize,
+    w: usize,
+    versions: (u32, u32),
+}
+
+impl Decimation {
+    fn new(positions: Vec<DVec3>, triangles: Vec<[usize; 3]>) -> Self {
+        let n = positions.len();
+        let mut vertex_triangles = vec![vec![]; n];
+        let mut quadrics = vec![Quadric::default(); n];
+        for (t, tri) in triangles.iter().enumerate() {
+            let [a, b, c] = tri.map(|i| positions[i]);
+            let normal = triangle_normal(a, b, c);
+            let area = normal.length() * 0.5;
+            let quadric = Quadric::from_plane(normal.normalize_or_zero(), a, area);
+            for i in tri {
+                vertex_triangles[*i].push(t);
+                quadrics[*i] = quadrics[*i].add(&quadric);
+            }
+        }
+
+        let mut decimation = Self {
+            positions,
+            quadrics,
+            triangle_alive: vec![true; triangles.len()],
+            num_triangles: triangles.len(),
+            triangles,
+            vertex_triangles,
+            vertex_alive: vec![true; n],
+            vertex_version: vec![0; n],
+            locked: vec![false; n],
+            corner_uvs: None,
+        };
+
+        // Keep boundaries in place with planes perpendicular to the faces
+        for t in 0..decimation.triangles.len() {
+            let tri = decimation.triangles[t];
+            let [a, b, c] = tri.map(|i| decimation.positions[i]);
+            let normal = triangle_normal(a, b, c).normalize_or_zero();
+            for i in 0..3 {
+                let (v, w) = (tri[i], tri[(i + 1) % 3]);
+                if decimation.edge_triangles(v, w).count() == 1 {
+                    let (p, q) = (decimation.positions[v], decimation.positions[w]);
+                    let edge_normal = (q - p).cross(normal).normalize_or_zero();
+                    let weight = BOUNDARY_WEIGHT * (q - p).length_squared();
+                    let quadric = Quadric::from_plane(edge_normal, p, weight);
+                    for x in [v, w] {
+                        decimation.quadrics[x] = decimation.quadrics[x].add(&quadric);
+                    }
+                }
+            }
+        }
+        decimation
+    }
+
+    /// The alive triangles containing both `v` and `w`.
+    fn edge_triangles(&self, v: usize, w: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_triangles[v]
+            .iter_cpy()
+            .filter(move |t| self.triangle_alive[*t] && self.triangles[*t].contains(&w))
+    }
+
+    /// The vertices sharing a triangle with `v`.
+    fn neighbors(&self, v: usize) -> HashSet<usize> {
+        self.vertex_triangles[v]
+            .iter()
+            .filter(|t| self.triangle_alive[**t])
+            .flat_map(|t| self.triangles[*t])
+            .filter(|x| *x != v)
+            .collect()
+    }
+
+    fn is_boundary_vertex(&self, v: usize) -> bool {
+        self.neighbors(v)
+            .into_iter()
+            .any(|w| self.edge_triangles(v, w).count() == 1)
+    }
+
+    /// Returns the cost and resulting position of collapsing the edge
+    /// between `v` and `w`.
+    fn collapse_target(&self, v: usize, w: usize) -> (f64, DVec3) {
+        let quadric = self.quadrics[v].add(&self.quadrics[w]);
+        let (p, q) = (self.positions[v], self.positions[w]);
+        if self.locked[v] {
+            return (quadric.error(p).max(0.0), p);
+        } else if self.locked[w] {
+            return (quadric.error(q).max(0.0), q);
+        }
+        quadric
+            .optimal_point()
+            .into_iter()
+            .chain([p, q, (p + q) * 0.5])
+            .map(|x| (quadric.error(x).max(0.0), x))
+            .min_by_key(|(error, _)| FloatOrd(*error))
+            .expect("There's always at least one candidate")
+    }
+
+    fn push_collapse(&self, queue: &mut BinaryHeap<Reverse<Collapse>>, v: usize, w: usize) {
+        let (cost, _) = self.collapse_target(v, w);
+        queue.push(Reverse(Collapse {
+            cost: FloatOrd(cost),
+            v,
+            w,
+            versions: (self.vertex_version[v], self.vertex_version[w]),
+        }));
+    }
+
+    /// Returns true when the edge between `v` and `w` can be collapsed into
+    /// a vertex at `target` without breaking the mesh.
+    fn can_collapse(&self, v: usize, w: usize, target: DVec3) -> bool {
+        if self.locked[v] && self.locked[w] {
+            return false;
+        }
+        // Link condition: The only vertices connected to both endpoints must
+        // be the opposite vertices of the triangles sharing the edge.
+        let shared = self.edge_triangles(v, w).collect_vec();
+        if shared.is_empty() || shared.len() > 2 {
+            return false;
+        }
+        let common = self.neighbors(v).intersection(&self.neighbors(w)).count();
+        if common != shared.len() {
+            return false;
+        }
+        // Joining two boundaries through an interior edge would pinch the
+        // surface.
+        if shared.len() == 2 && self.is_boundary_vertex(v) && self.is_boundary_vertex(w) {
+            return false;
+        }
+
+        // The remaining triangles around both vertices must not flip.
+        for x in [v, w] {
+            for t in self.vertex_triangles[x].iter_cpy() {
+                if !self.triangle_alive[t] || shared.contains(&t) {
+                    continue;
+                }
+                let tri = self.triangles[t];
+                let [a, b, c] = tri.map(|i| self.positions[i]);
+                let [a2, b2, c2] = tri.map(|i| if i == x { target } else { self.positions[i] });
+                let before = triangle_normal(a, b, c).normalize_or_zero();
+                let after = triangle_normal(a2, b2, c2);
+                if after.length_squared() < 1e-20 || before.dot(after.normalize()) < MIN_NORMAL_DOT
+                {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Collapses `w` into `v`, moving `v` to `target`. When `w` is locked,
+    /// `v` is collapsed into `w` instead. Returns the remaining vertex.
+    fn collapse(&mut self, v: usize, w: usize, target: DVec3) -> usize {
+        let (v, w) = if self.locked[w] { (w, v) } else { (v, w) };
+        let shared = self.edge_triangles(v, w).collect_vec();
+
+        // The UV at the new vertex is interpolated along the collapsed edge.
+        // Neither vertex is on a seam unless it's locked, so the UVs of the
+        // shared triangles are the ones of the whole neighborhood.
+        let new_uv = self.corner_uvs.as_ref().and_then(|corner_uvs| {
+            let t = *shared.first()?;
+            let uv_at = |x: usize| {
+                let i = self.triangles[t].iter().position(|i| *i == x)?;
+                Some(corner_uvs[t][i])
+            };
+            let (p, q) = (self.positions[v], self.positions[w]);
+            let along = if p.distance_squared(q) > 1e-20 {
+                ((target - p).dot(q - p) / p.distance_squared(q)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            Some(uv_at(v)?.lerp(uv_at(w)?, along as f32))
+        });
+
+        for t in shared {
+            self.triangle_alive[t] = false;
+            self.num_triangles -= 1;
+        }
+        let mut moved = vec![];
+        for t in std::mem::take(&mut self.vertex_triangles[w]) {
+            if self.triangle_alive[t] {
+                for i in self.triangles[t].iter_mut() {
+                    if *i == w {
+                        *i = v;
+                    }
+                }
+                self.vertex_triangles[v].push(t);
+                moved.push(t);
+            }
+        }
+        let triangle_alive = &self.triangle_alive;
+        self.vertex_triangles[v].retain(|t| triangle_alive[*t]);
+
+        if let (Some(corner_uvs), Some(uv)) = (&mut self.corner_uvs, new_uv) {
+            // A locked vertex may be on a seam, so only the corners coming
+            // from `w` are updated. Those are on the same side as `w`.
+            let updated = if self.locked[v] {
+                &moved
+            } else {
+                &self.vertex_triangles[v]
+            };
+            for t in updated.iter_cpy() {
+                for (i, x) in self.triangles[t].iter().enumerate() {
+                    if *x == v {
+                        corner_uvs[t][i] = uv;
+                    }
+                }
+            }
+        }
+
+        self.positions[v] = target;
+        self.quadrics[v] = self.quadrics[v].add(&self.quadrics[w]);
+        self.vertex_alive[w] = false;
+        self.vertex_version[v] += 1;
+        self.vertex_version[w] += 1;
+        v
+    }
+
+    fn run(&mut self, target: DecimateTarget, progress: &dyn ProgressSink) -> Result<()> {
+        let name = "Decimate";
+        let initial_triangles = self.num_triangles;
+        let target_triangles = match target {
+            DecimateTarget::FaceCount(count) => count,
+            DecimateTarget::MaxError(_) => 0,
+        };
+        let max_error = match target {
+            DecimateTarget::FaceCount(_) => f64::INFINITY,
+            // Errors are squared distances
+            DecimateTarget::MaxError(error) => (error as f64).powi(2),
+        };
+
+        let mut queue = BinaryHeap::new();
+        for v in 0..self.positions.len() {
+            for w in self.neighbors(v) {
+                if v < w {
+                    self.push_collapse(&mut queue, v, w);
+                }
+            }
+        }
+
+        let mut steps = 0;
+        while self.num_triangles > target_triangles {
+            let collapse = match queue.pop() {
+                Some(Reverse(collapse)) => collapse,
+                None => break,
+            };
+            let (v, w) = (collapse.v, collapse.w);
+            if !self.vertex_alive[v]
+                || !self.vertex_alive[w]
+                || collapse.versions != (self.vertex_version[v], self.vertex_version[w])
+            {
+                continue;
+            }
+            if collapse.cost.0 > max_error {
+                break;
+            }
+            let (_, position) = self.collapse_target(v, w);
+            if !self.can_collapse(v, w, position) {
+                continue;
+            }
+            let v = self.collapse(v, w, position);
+            for x in self.neighbors(v) {
+                self.push_collapse(&mut queue, v, x);
+            }
+
+            steps += 1;
+            if steps % 1000 == 0 {
+                let removed = initial_triangles - self.num_triangles;
+                let to_remove = initial_triangles.saturating_sub(target_triangles).max(1);
+                progress.report(name, removed as f32 / to_remove as f32);
+                progress.check_cancelled()?;
+            }
+        }
+        progress.report(name, 1.0);
+        Ok(())
+    }
+}
+
+/// Simplifies `mesh` by collapsing edges until the `target` is met. The mesh
+/// is triangulated first, and boundaries are preserved as much as possible.
+/// Only the vertex positions of the result are computed, other channels are
+/// discarded.
+pub fn decimate(mesh: &HalfEdgeMesh, target: DecimateTarget) -> Result<HalfEdgeMesh> {
+    decimate_with_progress(mesh, target, false, &NoProgress)
+}
+
+/// Same as `decimate`, but reports the progress to the given `progress` sink,
+/// and stops early when it gets cancelled.
+///
+/// When `preserve_seams` is true, the UVs and materials of the mesh are kept
+/// in the result. Vertices on UV seams or material boundaries are not moved
+/// or removed, so these stay in place.
+pub fn decimate_with_progress(
+    mesh: &HalfEdgeMesh,
+    target: DecimateTarget,
+    preserve_seams: bool,
+    progress: &dyn ProgressSink,
+) -> Result<HalfEdgeMesh> {
+    let triangulated = mesh.clone();
+    triangulate(&triangulated, TriangulateMode::Auto)?;
+
+    let conn = triangulated.read_connectivity();
+    let mesh_positions = triangulated.read_positions();
+    let uvs = triangulated.read_uvs().filter(|_| preserve_seams);
+    let materials = triangulated
+        .channels
+        .read_channel_by_name::<FaceId, f32>(MATERIAL_CHANNEL)
+        .ok()
+        .filter(|_| preserve_seams);
+
+    let mut index = HashMap::new();
+    let mut positions = vec![];
+    for (v, _) in conn.iter_vertices() {
+        index.insert(v, positions.len());
+        positions.push(to_dvec3(mesh_positions[v]));
+    }
+    let mut triangles = vec![];
+    let mut corner_uvs = vec![];
+    let mut triangle_materials = vec![];
+    for (face, _) in conn.iter_faces() {
+        let halfedges = conn.face_edges(face);
+        if halfedges.len() != 3 {
+            continue;
+        }
+        let vertices = halfedges
+            .iter()
+            .map(|h| conn.at_halfedge(*h).vertex().try_end())
+            .collect::<Result<SVec<_>, _>>()?;
+        triangles.push([0, 1, 2].map(|i| index[&vertices[i]]));
+        if let Some(uvs) = &uvs {
+            corner_uvs.push([0, 1, 2].map(|i| uvs[halfedges[i]]));
+        }
+        if let Some(materials) = &materials {
+            triangle_materials.push(materials[face]);
+        }
+    }
+
+    let mut decimation = Decimation::new(positions, triangles);
+    if preserve_seams {
+        for (h, _) in conn.iter_halfedges() {
+            if is_seam(&conn, uvs.as_deref(), materials.as_deref(), h)? {
+                let (src, dst) = conn.at_halfedge(h).src_dst_pair()?;
+                decimation.locked[index[&src]] = true;
+                decimation.locked[index[&dst]] = true;
+            }
+        }
+        if uvs.is_some() {
+            decimation.corner_uvs = Some(corner_uvs);
+        }
+    }
+    let has_uvs = uvs.is_some();
+    let has_materials = materials.is_some();
+    drop((conn, mesh_positions, uvs, materials));
+
+    decimation.run(target, progress)?;
+
+    let positions = decimation
+        .positions
+        .iter()
+        .map(|p| Vec3::new(p.x as f32, p.y as f32, p.z as f32))
+        .collect_vec();
+    let alive = (0..decimation.triangles.len())
+        .filter(|t| decimation.triangle_alive[*t])
+        .collect_vec();
+    let triangles = alive.iter().map(|t| decimation.triangles[*t]).collect_vec();
+    let mut result = HalfEdgeMesh::build_from_polygons(&positions, &triangles)
+        .context("The result of the decimation is not a valid mesh")?;
+
+    // NOTE: Relies on `build_from_polygons` allocating faces, and then the
+    // halfedges of each face, in the same order as the triangles.
+    if has_uvs {
+        let corner_uvs = decimation
+            .corner_uvs
+            .as_ref()
+            .expect("UVs are preserved when the mesh has them");
+        let mut uv_ch = Channel::<HalfEdgeId, Vec3>::new();
+        {
+            let conn = result.read_connectivity();
+            let corners = alive.iter().flat_map(|t| corner_uvs[*t]);
+            for ((h, _), uv) in conn.iter_halfedges().zip(corners) {
+                uv_ch[h] = uv;
+            }
+        }
+        let ch_id = result.channels.replace_or_create_channel("uv", uv_ch);
+        result.default_channels.uvs = Some(ch_id);
+    }
+    if has_materials {
+        let ch_id = result
+            .channels
+            .ensure_channel::<FaceId, f32>(MATERIAL_CHANNEL);
+        let faces = result
+            .read_connectivity()
+            .iter_faces()
+            .map(|(f, _)| f)
+            .collect_vec();
+        let mut material_ch = result.channels.write_channel(ch_id)?;
+        for (face, t) in faces.into_iter().zip(&alive) {
+            material_ch[face] = triangle_materials[*t];
+        }
+    }
+    Ok(result)
+}
+
+/// Returns whether the edge of halfedge `h` separates faces with different
+/// UVs or materials.
+fn is_seam(
+    conn: &MeshConnectivity,
+    uvs: Option<&Channel<HalfEdgeId, Vec3>>,
+    materials: Option<&Channel<FaceId, f32>>,
+    h: HalfEdgeId,
+) -> Result<bool> {
+    let twin = conn.at_halfedge(h).twin().try_end()?;
+    let (f1, f2) = match (
+        conn.at_halfedge(h).face().try_end(),
+        conn.at_halfedge(twin).face().try_end(),
+    ) {
+        (Ok(f1), Ok(f2)) => (f1, f2),
+        // Boundaries are handled by the boundary quadrics
+        _ => return Ok(false),
+    };
+    if let Some(materials) = materials {
+        if materials[f1] != materials[f2] {
+            return Ok(true);
+        }
+    }
+    if let Some(uvs) = uvs {
+        // Both faces need to agree on the UVs at the shared vertices
+        let h_next = conn.at_halfedge(h).next().try_end()?;
+        let twin_next = conn.at_halfedge(twin).next().try_end()?;
+        let differ = |a: HalfEdgeId, b: HalfEdgeId| uvs[a].distance_squared(uvs[b]) > 1e-10;
+        if differ(h, twin_next) || differ(twin, h_next) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Generates a chain of levels of detail for `mesh`, one for each of the
+/// given `ratios`. Each ratio is the fraction of the triangles of `mesh` kept
+/// in that level, and they're expected in decreasing order. Each level is
+/// simplified from the previous one. UVs and materials are preserved.
+pub fn generate_lods(
+    mesh: &HalfEdgeMesh,
+    ratios: &[f32],
+    progress: &dyn ProgressSink,
+) -> Result<Vec<HalfEdgeMesh>> {
+    let num_triangles = count_triangles(mesh);
+    let mut lods: Vec<HalfEdgeMesh> = vec![];
+    for ratio in ratios {
+        let target = (num_triangles as f32 * ratio.clamp(0.0, 1.0)).ceil() as usize;
+        let previous = lods.last().unwrap_or(mesh);
+        let lod =
+            decimate_with_progress(previous, DecimateTarget::FaceCount(target), true, progress)?;
+        lods.push(lod);
+    }
+    Ok(lods)
+}
+
+/// The number of triangles of `mesh` once triangulated.
+fn count_triangles(mesh: &HalfEdgeMesh) -> usize {
+    let conn = mesh.read_connectivity();
+    conn.iter_faces()
+        .map(|(face, _)| conn.face_edges(face).len().saturating_sub(2))
+        .sum()
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::progress::ProgressTracker;
+    use mlua::Lua;
+
+    /// Simplifies `mesh` using quadric error metrics, until its number of
+    /// triangles is `ratio` (between 0 and 1) times the original one. The
+    /// result is a triangle mesh with only vertex positions.
+    #[lua(under = "Ops")]
+    fn decimate(lua: &Lua, mesh: &HalfEdgeMesh, ratio: f32) -> Result<HalfEdgeMesh> {
+        let num_triangles = count_triangles(mesh);
+        let target = (num_triangles as f32 * ratio.clamp(0.0, 1.0)).ceil() as usize;
+        super::decimate_with_progress(
+            mesh,
+            DecimateTarget::FaceCount(target),
+            false,
+            ProgressTracker::current(lua).as_ref(),
+        )
+    }
+
+    /// Simplifies `mesh` using quadric error metrics, collapsing edges while
+    /// the surface moves less than roughly `max_error` away from the
+    /// original. The result is a triangle mesh with only vertex positions.
+    #[lua(under = "Ops")]
+    fn decimate_to_error(lua: &Lua, mesh: &HalfEdgeMesh, max_error: f32) -> Result<HalfEdgeMesh> {
+        super::decimate_with_progress(
+            mesh,
+            DecimateTarget::MaxError(max_error),
+            false,
+            ProgressTracker::current(lua).as_ref(),
+        )
+    }
+
+    /// Returns a list with a level of detail of `mesh` for each of the given
+    /// `ratios`, the fraction of triangles kept in that level. Ratios should
+    /// go in decreasing order. UV seams and material boundaries are kept in
+    /// place, so the levels can use the same textures as the original.
+    #[lua(under = "Ops")]
+    fn generate_lods<'lua>(
+        lua: &'lua Lua,
+        mesh: &HalfEdgeMesh,
+        ratios: mlua::Table,
+    ) -> Result<mlua::Table<'lua>> {
+        let ratios = ratios
+            .sequence_values::<f32>()
+            .collect::<Result<Vec<_>, _>>()?;
+        let lods = super::generate_lods(mesh, &ratios, ProgressTracker::current(lua).as_ref())?;
+        Ok(lua.create_sequence_from(lods)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::UVSphere;
+
+    #[test]
+    fn test_decimate_sphere() {
+        let sphere = UVSphere::build(Vec3::ZERO, 16, 16, 1.0).unwrap();
+        let original = sphere.read_connectivity().num_faces();
+        let result = decimate(&sphere, DecimateTarget::FaceCount(original / 2)).unwrap();
+        let conn = result.read_connectivity();
+        assert!(conn.num_faces() <= original / 2 + 1);
+        assert!(conn.num_faces() > original / 4);
+        // The result stays close to the original sphere
+        let positions = result.read_positions();
+        for (v, _) in conn.iter_vertices() {
+            assert!((positions[v].length() - 1.0).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_generate_lods() {
+        let mut sphere = UVSphere::build(Vec3::ZERO, 16, 16, 1.0).unwrap();
+        // The upper half of the sphere gets a different material
+        let ch_id = sphere
+            .channels
+            .ensure_channel::<FaceId, f32>(MATERIAL_CHANNEL);
+        {
+            let conn = sphere.read_connectivity();
+            let positions = sphere.read_positions();
+            let mut materials = sphere.channels.write_channel(ch_id).unwrap();
+            for (face, _) in conn.iter_faces() {
+                let center = conn.face_vertex_average(&positions, face);
+                materials[face] = if center.y > 0.0 { 1.0 } else { 0.0 };
+            }
+        }
+
+        let lods = generate_lods(&sphere, &[0.5, 0.25], &NoProgress).unwrap();
+        assert_eq!(lods.len(), 2);
+        let num_faces = lods
+            .iter()
+            .map(|lod| lod.read_connectivity().num_faces())
+            .collect_vec();
+        assert!(num_faces[0] > num_faces[1]);
+
+        // Vertices on the material boundary stay on the equator
+        let lod = &lods[1];
+        let conn = lod.read_connectivity();
+        let positions = lod.read_positions();
+        let materials = lod
+            .channels
+            .read_channel_by_name::<FaceId, f32>(MATERIAL_CHANNEL)
+            .unwrap();
+        for (face, _) in conn.iter_faces() {
+            let center = conn.face_vertex_average(&positions, face);
+            assert_eq!(materials[face], if center.y > 0.0 { 1.0 } else { 0.0 });
+        }
+    }
+}
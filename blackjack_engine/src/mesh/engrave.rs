@@ -0,0 +1,344 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Engraving and embossing of shapes, like text or logos, onto the surface of
+//! a mesh.
+//!
+//! The outline of the shape is projected onto the surface along a direction,
+//! and turned into a thin solid that follows the surface, reaching `depth`
+//! units on both sides of it. Subtracting that solid from the mesh carves a
+//! groove, and adding it raises the shape over the surface.
+
+use rstar::RTreeObject;
+
+use crate::prelude::*;
+use crate::progress::{NoProgress, ProgressSink};
+
+use super::analysis::{cast_ray, triangle_index};
+use super::csg::{boolean_with_progress, BooleanMode};
+use super::halfedge::selection::SelectionExpression;
+
+/// Projects the `shape` onto the surface of `mesh` along `direction`, and
+/// engraves it `depth` units deep, or embosses it `depth` units high when
+/// `emboss` is true. The shape can be a flat mesh, like the one generated by
+/// the text primitive, whose faces give the outline. It can also be a
+/// polyline, which is engraved as a stroke of the given `width`. The mesh
+/// should be closed, and the whole shape must land on its surface.
+pub fn engrave(
+    mesh: &HalfEdgeMesh,
+    shape: &HalfEdgeMesh,
+    direction: Vec3,
+    depth: f32,
+    width: f32,
+    emboss: bool,
+) -> Result<HalfEdgeMesh> {
+    engrave_with_progress(mesh, shape, direction, depth, width, emboss, &NoProgress)
+}
+
+/// Same as `engrave`, but reports the progress of the boolean operation to
+/// the given `progress` sink, and stops early when it gets cancelled.
+pub fn engrave_with_progress(
+    mesh: &HalfEdgeMesh,
+    shape: &HalfEdgeMesh,
+    direction: Vec3,
+    depth: f32,
+    width: f32,
+    emboss: bool,
+    progress: &dyn ProgressSink,
+) -> Result<HalfEdgeMesh> {
+    let direction = direction
+        .try_normalize()
+        .ok_or_else(|| anyhow!("The projection direction can't be zero"))?;
+    if depth <= 0.0 {
+        bail!("The depth must be positive, got {depth}");
+    }
+    let (points, polygons) = if shape.read_connectivity().num_faces() > 0 {
+        shape_faces(shape)
+    } else {
+        if width <= 0.0 {
+            bail!("The stroke width must be positive, got {width}");
+        }
+        stroke(shape, direction, width)?
+    };
+    let cutter = project_shape(mesh, &points, &polygons, direction, depth)?;
+    let mode = if emboss {
+        BooleanMode::Union
+    } else {
+        BooleanMode::Difference
+    };
+    boolean_with_progress(mesh, &cutter, mode, progress)
+}
+
+/// Returns the vertex positions and the faces of `shape`, as indices into the
+/// positions.
+fn shape_faces(shape: &HalfEdgeMesh) -> (Vec<Vec3>, Vec<SVec<usize>>) {
+    let conn = shape.read_connectivity();
+    let positions = shape.read_positions();
+    let mut index = HashMap::new();
+    let mut points = vec![];
+    for (v, _) in conn.iter_vertices() {
+        index.insert(v, points.len());
+        points.push(positions[v]);
+    }
+    let polygons = conn
+        .iter_faces()
+        .map(|(face, _)| conn.face_vertices(face).iter().map(|v| index[v]).collect())
+        .collect();
+    (points, polygons)
+}
+
+/// Builds a ribbon of the given `width` along the polyline in `curve`, facing
+/// the projection `direction`.
+fn stroke(
+    curve: &HalfEdgeMesh,
+    direction: Vec3,
+    width: f32,
+) -> Result<(Vec<Vec3>, Vec<SVec<usize>>)> {
+    let conn = curve.read_connectivity();
+    let positions = curve.read_positions();
+    let bag = curve.resolve_halfedge_selection_full(&SelectionExpression::All)?;
+    let (vertices, closed) = edit_ops::sort_bag_of_edges(&conn, &bag)?;
+    let path = vertices.iter().map(|v| positions[*v]).collect_vec();
+    if path.len() < 2 {
+        bail!("The curve to engrave needs at least two points");
+    }
+
+    let n = path.len();
+    let tangent_at = |i: usize| {
+        let prev = if closed || i > 0 {
+            path[(i + n - 1) % n]
+        } else {
+            path[i]
+        };
+        let next = if closed || i + 1 < n {
+            path[(i + 1) % n]
+        } else {
+            path[i]
+        };
+        (next - prev).normalize_or_zero()
+    };
+    let mut points = vec![];
+    for (i, p) in path.iter_cpy().enumerate() {
+        let side = tangent_at(i)
+            .cross(direction)
+            .try_normalize()
+            .ok_or_else(|| anyhow!("The curve can't be parallel to the projection direction"))?;
+        points.push(p + side * width * 0.5);
+        points.push(p - side * width * 0.5);
+    }
+    let polygons = (0..n)
+        .branch(
+            closed,
+            |x| x.circular_tuple_windows(),
+            |x| x.tuple_windows(),
+        )
+        .map(|(i, j)| SVec::from_slice(&[2 * i, 2 * j, 2 * j + 1, 2 * i + 1]))
+        .collect();
+    Ok((points, polygons))
+}
+
+/// Projects each of the `points` onto the surface of `mesh` along
+/// `direction`, and builds a closed mesh with the `polygons` of the shape
+/// spanning `depth` units on both sides of the surface.
+fn project_shape(
+    mesh: &HalfEdgeMesh,
+    points: &[Vec3],
+    polygons: &[SVec<usize>],
+    direction: Vec3,
+    depth: f32,
+) -> Result<HalfEdgeMesh> {
+    let (index, _) = triangle_index(mesh);
+    if index.size() == 0 {
+        bail!("Cannot engrave a mesh without faces");
+    }
+    let envelope = index.root().envelope();
+    let (min, max) = (
+        Vec3::from_array(envelope.lower()),
+        Vec3::from_array(envelope.upper()),
+    );
+    let extent = (max - min).length();
+    let center = (min + max) * 0.5;
+
+    // Rays start behind the mesh, so points on either side of it are
+    // projected onto the surface facing the shape.
+    let mut hits = vec![];
+    for p in points.iter_cpy() {
+        let reach = extent + (p - center).length();
+        let origin = p - direction * reach;
+        let t = cast_ray(&index, origin, direction, 2.0 * reach)
+            .ok_or_else(|| anyhow!("The shape to engrave doesn't fully land on the surface"))?;
+        hits.push(origin + direction * t);
+    }
+
+    // The outer copy of each point goes first, then the inner copy.
+    let n = points.len();
+    let mut positions = hits.iter().map(|h| *h - direction * depth).collect_vec();
+    positions.extend(hits.iter().map(|h| *h + direction * depth));
+
+    let mut edges = HashSet::new();
+    for polygon in polygons {
+        for (a, b) in polygon.iter_cpy().circular_tuple_windows() {
+            edges.insert((a, b));
+        }
+    }
+    let mut faces = vec![];
+    for polygon in polygons {
+        faces.push(polygon.clone());
+        faces.push(polygon.iter().rev().map(|i| i + n).collect());
+        for (a, b) in polygon.iter_cpy().circular_tuple_windows() {
+            if !edges.contains(&(b, a)) {
+                faces.push(SVec::from_slice(&[a, a + n, b + n, b]));
+            }
+        }
+    }
+
+    // The outer faces must point away from the mesh, against the direction.
+    let area = polygons
+        .iter()
+        .flat_map(|polygon| polygon.iter_cpy().circular_tuple_windows())
+        .fold(Vec3::ZERO, |acc, (a, b)| acc + points[a].cross(points[b]));
+    if area.dot(direction) > 0.0 {
+        for face in &mut faces {
+            face.reverse();
+        }
+    }
+    HalfEdgeMesh::build_from_polygons(&positions, &faces)
+        .context("Could not build the engraving, is the shape self-intersecting?")
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+    use crate::progress::ProgressTracker;
+    use mlua::Lua;
+
+    /// Projects `shape` onto the surface of `mesh` along `direction` and
+    /// engraves it `depth` units deep, or embosses it when `emboss` is true.
+    /// The shape can be a flat mesh, like text, or a polyline which is drawn
+    /// as a stroke of the given `width`.
+    #[lua(under = "Ops")]
+    fn engrave(
+        lua: &Lua,
+        mesh: &HalfEdgeMesh,
+        shape: &HalfEdgeMesh,
+        direction: LVec3,
+        depth: f32,
+        width: f32,
+        emboss: bool,
+    ) -> Result<HalfEdgeMesh> {
+        super::engrave_with_progress(
+            mesh,
+            shape,
+            direction.0,
+            depth,
+            width,
+            emboss,
+            ProgressTracker::current(lua).as_ref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::{Box, Line, Polygon};
+
+    fn volume(mesh: &HalfEdgeMesh) -> f32 {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        conn.iter_faces()
+            .map(|(face, _)| {
+                let vertices = conn.face_vertices(face);
+                let p0 = positions[vertices[0]];
+                vertices[1..]
+                    .iter_cpy()
+                    .tuple_windows()
+                    .map(|(v1, v2)| p0.dot(positions[v1].cross(positions[v2])) / 6.0)
+                    .sum::<f32>()
+            })
+            .sum()
+    }
+
+    fn is_closed(mesh: &HalfEdgeMesh) -> bool {
+        let conn = mesh.read_connectivity();
+        conn.iter_halfedges()
+            .all(|(h, _)| conn.at_halfedge(h).face().try_end().is_ok())
+    }
+
+    /// An L-shaped outline above the box, in the XZ plane, with an area of
+    /// 0.2. Being concave, it gets triangulated by the boolean operation.
+    fn l_shape(offset: Vec3) -> HalfEdgeMesh {
+        let points = [
+            (-0.3, -0.3),
+            (0.3, -0.3),
+            (0.3, -0.1),
+            (-0.1, -0.1),
+            (-0.1, 0.3),
+            (-0.3, 0.3),
+        ]
+        .iter()
+        .map(|(x, z)| Vec3::new(*x, 2.0, *z) + offset)
+        .collect();
+        Polygon::build_from_points(points).unwrap()
+    }
+
+    #[test]
+    fn test_engrave_shape() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let shape = l_shape(Vec3::ZERO);
+
+        let engraved = engrave(&mesh, &shape, -Vec3::Y, 0.1, 0.0, false).unwrap();
+        assert!(is_closed(&engraved));
+        let v = volume(&engraved).abs();
+        assert!((v - 0.98).abs() < 1e-3, "{v} != 0.98");
+
+        let embossed = engrave(&mesh, &shape, -Vec3::Y, 0.1, 0.0, true).unwrap();
+        assert!(is_closed(&embossed));
+        let v = volume(&embossed).abs();
+        assert!((v - 1.02).abs() < 1e-3, "{v} != 1.02");
+    }
+
+    #[test]
+    fn test_engrave_stroke() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let curve = Line::build_from_points(vec![
+            Vec3::new(-0.3, 2.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.2),
+            Vec3::new(0.3, 2.0, 0.0),
+        ])
+        .unwrap();
+
+        let engraved = engrave(&mesh, &curve, -Vec3::Y, 0.05, 0.1, false).unwrap();
+        assert!(is_closed(&engraved));
+        assert!(volume(&engraved).abs() < 1.0);
+
+        let embossed = engrave(&mesh, &curve, -Vec3::Y, 0.05, 0.1, true).unwrap();
+        assert!(is_closed(&embossed));
+        assert!(volume(&embossed).abs() > 1.0);
+
+        assert!(engrave(&mesh, &curve, -Vec3::Y, 0.05, 0.0, false).is_err());
+    }
+
+    #[test]
+    fn test_engrave_errors() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+
+        // Half of the shape hangs over the side of the box
+        let shape = l_shape(Vec3::new(0.5, 0.0, 0.0));
+        let err = engrave(&mesh, &shape, -Vec3::Y, 0.1, 0.0, false)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "The shape to engrave doesn't fully land on the surface"
+        );
+
+        let shape = l_shape(Vec3::ZERO);
+        assert!(engrave(&mesh, &shape, Vec3::ZERO, 0.1, 0.0, false).is_err());
+        assert!(engrave(&mesh, &shape, -Vec3::Y, 0.0, 0.0, false).is_err());
+    }
+}
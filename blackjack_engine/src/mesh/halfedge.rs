@@ -37,6 +37,12 @@ pub mod edit_ops;
 /// Import / Export of HalfEdgeMesh data structure to Wavefront OBJ files
 pub mod wavefront_obj;
 
+/// Imported meshes that are only loaded when their geometry is needed
+pub mod lazy_mesh;
+
+/// Unit conversion and cleanup applied to meshes when importing them
+pub mod import_options;
+
 /// A compact halfedge graph specifically optimized for some operations
 pub mod compact_mesh;
 
@@ -44,9 +50,22 @@ pub mod compact_mesh;
 pub mod selection;
 
 /// Generate vertex and index buffers suitable to be uploaded to the GPU for rendering
+#[cfg(feature = "gpu_buffers")]
 pub mod gpu_buffer_generation;
+#[cfg(feature = "gpu_buffers")]
 pub use gpu_buffer_generation::*;
 
+/// Size and complexity statistics of a mesh, like element counts
+pub mod stats;
+pub use stats::MeshStats;
+
+/// Spatial queries to snap points onto the elements of a mesh
+pub mod snapping;
+
+/// Named, oriented points that move along with a mesh
+pub mod anchors;
+pub use anchors::{Anchor, MeshAnchors};
+
 pub mod halfedge_lua_api;
 
 pub mod channels;
@@ -143,13 +162,57 @@ pub struct MeshConnectivity {
     debug_vertices: HashMap<VertexId, DebugMark>,
 }
 
+/// The ways in which the normals of a mesh can be generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadingMode {
+    /// Per-face normals.
+    Flat,
+    /// Per-vertex normals.
+    Smooth,
+    /// Per-vertex normals, except where faces meet at an angle larger than
+    /// the `auto_smooth_angle`. Those edges are kept sharp.
+    AutoSmooth,
+    /// Per-vertex normals for the faces in the [`SMOOTH_FACES_GROUP`], and
+    /// per-face normals for the rest.
+    Mixed,
+}
+
+/// The name of the face group marking the faces shaded smooth in
+/// [`ShadingMode::Mixed`].
+pub const SMOOTH_FACES_GROUP: &str = "smooth";
+
+impl ShadingMode {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "flat" => Ok(Self::Flat),
+            "smooth" => Ok(Self::Smooth),
+            "auto_smooth" => Ok(Self::AutoSmooth),
+            "mixed" => Ok(Self::Mixed),
+            _ => bail!(
+                "Invalid shading mode '{name}'. Expected 'flat', 'smooth', 'auto_smooth' or 'mixed'"
+            ),
+        }
+    }
+}
+
 /// This struct contains some parameters that allow configuring the way in which
-/// a mesh is generated.
-#[derive(Default, Debug, Clone)]
+/// a mesh is generated. Both the viewport and the exporters follow it.
+#[derive(Debug, Clone)]
 pub struct MeshGenerationConfig {
-    /// Should this mesh be generated using smooth (i.e. per-vertex) normals? Or
-    /// flat (i.e. per-face) normals?
-    pub smooth_normals: bool,
+    /// How the normals of this mesh are generated.
+    pub shading: ShadingMode,
+    /// For [`ShadingMode::AutoSmooth`], the largest angle between two faces,
+    /// in radians, for which the edge between them is still shaded smooth.
+    pub auto_smooth_angle: f32,
+}
+
+impl Default for MeshGenerationConfig {
+    fn default() -> Self {
+        Self {
+            shading: ShadingMode::Flat,
+            auto_smooth_angle: std::f32::consts::FRAC_PI_6,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -157,8 +220,9 @@ pub struct MeshGenerationConfig {
 pub struct HalfEdgeMesh {
     connectivity: InteriorMutable<MeshConnectivity>,
     pub channels: MeshChannels,
-    default_channels: DefaultChannels,
+    pub(crate) default_channels: DefaultChannels,
     pub gen_config: MeshGenerationConfig,
+    anchors: InteriorMutable<MeshAnchors>,
 }
 
 #[cfg(feature = "sync")]
@@ -169,6 +233,7 @@ impl Clone for HalfEdgeMesh {
             channels: self.channels.clone(),
             default_channels: self.default_channels.clone(),
             gen_config: self.gen_config.clone(),
+            anchors: InteriorMutable::new(self.anchors.borrow().clone()),
         }
     }
 }
@@ -526,6 +591,7 @@ impl HalfEdgeMesh {
             default_channels,
             connectivity: InteriorMutable::new(MeshConnectivity::new()),
             gen_config: MeshGenerationConfig::default(),
+            anchors: InteriorMutable::new(MeshAnchors::default()),
         }
     }
 
@@ -552,6 +618,14 @@ impl HalfEdgeMesh {
         self.connectivity.borrow_mut()
     }
 
+    pub fn read_anchors(&self) -> BorrowedRef<'_, MeshAnchors> {
+        self.anchors.borrow()
+    }
+
+    pub fn write_anchors(&self) -> MutableRef<'_, MeshAnchors> {
+        self.anchors.borrow_mut()
+    }
+
     pub fn read_positions(&self) -> BorrowedRef<'_, Positions> {
         self.channels
             .read_channel(self.default_channels.position)
@@ -574,6 +648,14 @@ impl HalfEdgeMesh {
         })
     }
 
+    pub fn read_corner_normals(&self) -> Option<BorrowedRef<'_, Channel<HalfEdgeId, Vec3>>> {
+        self.default_channels.corner_normals.map(|ch_id| {
+            self.channels
+                .read_channel(ch_id)
+                .expect("Could not read corner normals")
+        })
+    }
+
     pub fn read_uvs(&self) -> Option<BorrowedRef<'_, Channel<HalfEdgeId, Vec3>>> {
         self.default_channels.uvs.map(|ch_id| {
             self.channels
@@ -736,7 +818,8 @@ impl HalfEdgeMesh {
     }
 
     /// Merges this halfedge mesh with another one. No additional connectivity
-    /// data is generated between the two.
+    /// data is generated between the two. The anchors of `mesh_b` are added to
+    /// this mesh, renaming them when their names are already taken.
     pub fn merge_with(&mut self, mesh_b: &HalfEdgeMesh) {
         let mut vmap = SecondaryMap::<VertexId, VertexId>::new();
         let mut hmap = SecondaryMap::<HalfEdgeId, HalfEdgeId>::new();
@@ -793,6 +876,8 @@ impl HalfEdgeMesh {
         }
         drop(a_conn);
 
+        self.write_anchors().merge_with(&mesh_b.read_anchors());
+
         // Finally, once the connectivity data is correct, we merge the channels
         // for both meshes.
 
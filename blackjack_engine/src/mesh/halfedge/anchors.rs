@@ -0,0 +1,129 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// A named, oriented point attached to a mesh. Anchors are not part of the
+/// geometry, but they move along with it, so they can be used as sockets to
+/// snap several parts together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+
+impl Anchor {
+    /// Returns the transform that takes points from the local space of this
+    /// anchor to the space of its mesh.
+    pub fn to_matrix(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position)
+    }
+}
+
+/// The anchors of a mesh, indexed by name. Names are kept sorted, so anchors
+/// are always listed in the same order.
+#[derive(Debug, Clone, Default)]
+pub struct MeshAnchors {
+    anchors: BTreeMap<String, Anchor>,
+}
+
+impl MeshAnchors {
+    /// Adds an anchor with the given `name`, replacing any existing anchor
+    /// with that same name.
+    pub fn insert(&mut self, name: impl Into<String>, anchor: Anchor) {
+        self.anchors.insert(name.into(), anchor);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Anchor> {
+        self.anchors.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Anchor> {
+        self.anchors.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Anchor)> {
+        self.anchors
+            .iter()
+            .map(|(name, anchor)| (name.as_str(), anchor))
+    }
+
+    pub fn len(&self) -> usize {
+        self.anchors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+
+    /// Moves all the anchors by the given affine transformation `matrix`. Only
+    /// the rotation part of the matrix affects the orientation of the anchors,
+    /// so they stay orthonormal under a non-uniform scale.
+    pub fn transform(&mut self, matrix: Mat4) {
+        let (_, rotation, _) = matrix.to_scale_rotation_translation();
+        for anchor in self.anchors.values_mut() {
+            anchor.position = matrix.transform_point3(anchor.position);
+            anchor.rotation = (rotation * anchor.rotation).normalize();
+        }
+    }
+
+    /// Adds the anchors in `other` to this set. When a name is already taken,
+    /// the incoming anchor gets a numeric suffix, like `socket.1`, so merging
+    /// several copies of the same part keeps all of their anchors.
+    pub fn merge_with(&mut self, other: &MeshAnchors) {
+        for (name, anchor) in other.iter() {
+            let mut unique_name = name.to_owned();
+            let mut suffix = 1;
+            while self.anchors.contains_key(&unique_name) {
+                unique_name = format!("{name}.{suffix}");
+                suffix += 1;
+            }
+            self.anchors.insert(unique_name, *anchor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_anchors() {
+        let mut anchors = MeshAnchors::default();
+        anchors.insert(
+            "socket",
+            Anchor {
+                position: Vec3::X,
+                rotation: Quat::IDENTITY,
+            },
+        );
+
+        anchors.transform(Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 1.0),
+            Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            Vec3::Z,
+        ));
+        let socket = anchors.get("socket").unwrap();
+        assert!(socket.position.distance(Vec3::new(0.0, 2.0, 1.0)) < 1e-5);
+        assert!(
+            socket
+                .rotation
+                .angle_between(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+                .abs()
+                < 1e-5
+        );
+
+        let other = anchors.clone();
+        anchors.merge_with(&other);
+        anchors.merge_with(&other);
+        assert_eq!(
+            anchors.iter().map(|(name, _)| name).collect_vec(),
+            vec!["socket", "socket.1", "socket.2"]
+        );
+    }
+}
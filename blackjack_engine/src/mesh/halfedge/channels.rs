@@ -242,6 +242,9 @@ pub struct DefaultChannels {
     /// the outgoing halfedges to represent this relation and store UVs in them
     /// instead.
     pub uvs: Option<ChannelId<HalfEdgeId, Vec3>>,
+    /// Normals for auto smooth shading, which can be different for every
+    /// face a vertex belongs to. Stored in the outgoing halfedges, like uvs.
+    pub corner_normals: Option<ChannelId<HalfEdgeId, Vec3>>,
 }
 
 impl<K: ChannelKey, V: ChannelValue> std::ops::Index<K> for Channel<K, V> {
@@ -369,6 +372,10 @@ pub trait DynChannel: Any + Debug {
         get_ids: &dyn Fn(ChannelKeyType) -> Rc<Vec<slotmap::KeyData>>,
         id_map: &dyn Fn(ChannelKeyType, slotmap::KeyData) -> slotmap::KeyData,
     );
+
+    /// Copies the value at the first key of each pair in `copies` into the
+    /// second key. Pairs are processed in order.
+    fn copy_values_dyn(&mut self, copies: &[(slotmap::KeyData, slotmap::KeyData)]);
 }
 impl<K: ChannelKey, V: ChannelValue> DynChannel for Channel<K, V> {
     fn as_any(&self) -> &dyn Any {
@@ -474,6 +481,12 @@ impl<K: ChannelKey, V: ChannelValue> DynChannel for Channel<K, V> {
             )
         }
     }
+
+    fn copy_values_dyn(&mut self, copies: &[(slotmap::KeyData, slotmap::KeyData)]) {
+        for (from, to) in copies {
+            self[K::from(*to)] = self[K::from(*from)];
+        }
+    }
 }
 
 impl<K: ChannelKey, V: ChannelValue> ChannelGroup<K, V> {
@@ -572,6 +585,9 @@ pub trait DynChannelGroup: Any + Debug + dyn_clone::DynClone + MaybeSync {
     fn channel_rc_dyn(&self, raw_id: RawChannelId) -> RefCounted<InteriorMutable<dyn DynChannel>>;
     /// Returns the names of the channels present in this group
     fn channel_names(&self) -> Box<dyn Iterator<Item = &str> + '_>;
+    /// Returns the approximate number of bytes allocated by the channels in
+    /// this group
+    fn memory_usage(&self) -> usize;
 }
 
 impl<K: ChannelKey, V: ChannelValue> Clone for ChannelGroup<K, V> {
@@ -649,6 +665,13 @@ impl<K: ChannelKey, V: ChannelValue> DynChannelGroup for ChannelGroup<K, V> {
     fn channel_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
         Box::new(self.channel_names.iter().map(|(l, _)| l.as_str()))
     }
+
+    fn memory_usage(&self) -> usize {
+        self.channels
+            .values()
+            .map(|ch| ch.borrow().inner.capacity() * std::mem::size_of::<V>())
+            .sum()
+    }
 }
 
 impl MeshChannels {
@@ -915,6 +938,19 @@ impl MeshChannels {
         self.channels.get(&(kty, vty))?.channel_id_dyn(name)
     }
 
+    /// Returns the names of all the channels with key and value types given
+    /// at runtime.
+    pub fn channel_names_dyn(
+        &self,
+        kty: ChannelKeyType,
+        vty: ChannelValueType,
+    ) -> impl Iterator<Item = &str> + '_ {
+        self.channels
+            .get(&(kty, vty))
+            .into_iter()
+            .flat_map(|group| group.channel_names())
+    }
+
     /// Calls `channel_name` for the channel group with key and value type
     pub fn channel_name<K: ChannelKey, V: ChannelValue>(
         &self,
@@ -934,6 +970,14 @@ impl MeshChannels {
             .collect()
     }
 
+    /// Returns the approximate number of bytes allocated by all the channels
+    pub fn memory_usage(&self) -> usize {
+        self.channels
+            .values()
+            .map(|group| group.memory_usage())
+            .sum()
+    }
+
     pub fn merge_with(
         &mut self,
         other: &Self,
@@ -958,6 +1002,28 @@ impl MeshChannels {
         }
     }
 
+    /// Copies values between the elements of key type `kty`, in every channel
+    /// with that key type. See [`DynChannel::copy_values_dyn`]. This is
+    /// useful for operations that split elements, so the new elements keep
+    /// the data of the original ones.
+    pub fn copy_values_dyn(
+        &self,
+        kty: ChannelKeyType,
+        copies: &[(slotmap::KeyData, slotmap::KeyData)],
+    ) {
+        for ((group_kty, _), group) in self.channels.iter() {
+            if *group_kty != kty {
+                continue;
+            }
+            for ch_name in group.channel_names() {
+                let id = group
+                    .channel_id_dyn(ch_name)
+                    .expect("We know it exists because we're iterating the channel names");
+                group.write_channel_dyn(id).copy_values_dyn(copies);
+            }
+        }
+    }
+
     /// Sets a channel directly, by name. If the channel doesn't exist, it is
     /// created, otherwise its contents are dropped and the new channel data is
     /// used. Returns the id of the channel that was created.
@@ -982,6 +1048,7 @@ impl DefaultChannels {
             vertex_normals: None,
             face_normals: None,
             uvs: None,
+            corner_normals: None,
         }
     }
 }
@@ -41,9 +41,93 @@ pub struct CompactMesh<const Subdivided: bool> {
     pub edge: Vec<u32>,
     pub face: Vec<u32>,
     pub vertex_positions: Vec<Vec3>,
+    /// Channels of the original mesh carried along during subdivision. Only
+    /// filled when the mesh is created with `from_halfedge_with_attributes`.
+    pub attributes: Vec<CompactAttribute>,
     pub counts: MeshCounts,
 }
 
+/// The values of a mesh channel, stored in the same order as the elements of
+/// a [`CompactMesh`]. Scalar channels are stored in the `x` coordinate.
+#[derive(Debug)]
+pub struct CompactAttribute {
+    pub name: String,
+    pub key: ChannelKeyType,
+    pub value: ChannelValueType,
+    pub values: Vec<Vec3>,
+}
+
+/// Channels that are not carried along as attributes: Positions are handled
+/// separately and normals are no longer valid once the mesh is subdivided.
+const SKIPPED_ATTRIBUTES: &[&str] = &["position", "vertex_normal", "face_normal", "corner_normal"];
+
+/// The channel values that can be interpolated as attributes.
+trait AttributeValue: ChannelValue {
+    fn to_vec3(self) -> Vec3;
+    fn from_vec3(v: Vec3) -> Self;
+}
+
+impl AttributeValue for Vec3 {
+    fn to_vec3(self) -> Vec3 {
+        self
+    }
+    fn from_vec3(v: Vec3) -> Self {
+        v
+    }
+}
+
+impl AttributeValue for f32 {
+    fn to_vec3(self) -> Vec3 {
+        Vec3::new(self, 0.0, 0.0)
+    }
+    fn from_vec3(v: Vec3) -> Self {
+        v.x
+    }
+}
+
+/// Appends to `attributes` all the channels of `mesh` with key `K` and value
+/// `V`, reading the values in the order given by `ids`.
+fn collect_attributes<K: ChannelKey, V: AttributeValue>(
+    mesh: &HalfEdgeMesh,
+    ids: &[K],
+    attributes: &mut Vec<CompactAttribute>,
+) -> Result<()> {
+    for name in mesh
+        .channels
+        .channel_names_dyn(K::key_type(), V::value_type())
+    {
+        if SKIPPED_ATTRIBUTES.contains(&name) {
+            continue;
+        }
+        let ch = mesh.channels.read_channel_by_name::<K, V>(name)?;
+        attributes.push(CompactAttribute {
+            name: name.into(),
+            key: K::key_type(),
+            value: V::value_type(),
+            values: ids.iter().map(|id| ch[*id].to_vec3()).collect(),
+        });
+    }
+    Ok(())
+}
+
+/// Writes the values of `attr` into a new channel of `mesh`, using `ids` to
+/// map the indices of the attribute to mesh elements.
+fn write_attribute<K: ChannelKey, V: AttributeValue>(
+    mesh: &mut HalfEdgeMesh,
+    ids: &[K],
+    attr: &CompactAttribute,
+) -> ChannelId<K, V> {
+    let ch_id = mesh.channels.ensure_channel::<K, V>(&attr.name);
+    let mut ch = mesh
+        .channels
+        .write_channel(ch_id)
+        .expect("We just ensured the channel exists");
+    for (id, value) in ids.iter().zip(&attr.values) {
+        ch[*id] = V::from_vec3(*value);
+    }
+    ch_id
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct MeshCounts {
     /// The number of vertices
@@ -78,8 +162,22 @@ impl MeshCounts {
 
 #[allow(non_upper_case_globals)]
 impl<const Subdivided: bool> CompactMesh<Subdivided> {
-    #[profiling::function]
     pub fn from_halfedge(mesh: &HalfEdgeMesh) -> Result<CompactMesh<false>> {
+        Self::from_halfedge_impl(mesh, false)
+    }
+
+    /// Like `from_halfedge`, but also stores the vertex, face and halfedge
+    /// channels of the mesh, so they're interpolated during subdivision. Only
+    /// `Vec3` and `f32` channels are supported, other channels are dropped.
+    pub fn from_halfedge_with_attributes(mesh: &HalfEdgeMesh) -> Result<CompactMesh<false>> {
+        Self::from_halfedge_impl(mesh, true)
+    }
+
+    #[profiling::function]
+    fn from_halfedge_impl(
+        mesh: &HalfEdgeMesh,
+        with_attributes: bool,
+    ) -> Result<CompactMesh<false>> {
         let conn = mesh.read_connectivity();
 
         // Create mappings between ids and indices in the compact arrays. This
@@ -177,6 +275,26 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
             .map(|(v_id, _)| positions[v_id])
             .collect();
 
+        let mut attributes = vec![];
+        if with_attributes {
+            fn ids_by_index<K: ChannelKey>(map: &slotmap::SecondaryMap<K, u32>) -> Vec<K> {
+                let mut ids = vec![K::default(); map.len()];
+                for (id, idx) in map.iter() {
+                    ids[*idx as usize] = id;
+                }
+                ids
+            }
+            let v_ids = ids_by_index(&v_id_to_idx);
+            let h_ids = ids_by_index(&h_id_to_idx);
+            let f_ids = ids_by_index(&f_id_to_idx);
+            collect_attributes::<_, Vec3>(mesh, &v_ids, &mut attributes)?;
+            collect_attributes::<_, f32>(mesh, &v_ids, &mut attributes)?;
+            collect_attributes::<_, Vec3>(mesh, &h_ids, &mut attributes)?;
+            collect_attributes::<_, f32>(mesh, &h_ids, &mut attributes)?;
+            collect_attributes::<_, Vec3>(mesh, &f_ids, &mut attributes)?;
+            collect_attributes::<_, f32>(mesh, &f_ids, &mut attributes)?;
+        }
+
         Ok(CompactMesh {
             twin,
             next,
@@ -185,6 +303,7 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
             edge,
             face,
             vertex_positions,
+            attributes,
             counts: MeshCounts {
                 num_halfedges,
                 num_vertices,
@@ -198,7 +317,7 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
 
     #[profiling::function]
     pub fn to_halfedge(&self) -> HalfEdgeMesh {
-        let mesh = HalfEdgeMesh::new();
+        let mut mesh = HalfEdgeMesh::new();
         let mut conn = mesh.write_connectivity();
         let mut positions = mesh.write_positions();
 
@@ -248,6 +367,36 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
 
         drop(conn);
         drop(positions);
+
+        for attr in &self.attributes {
+            use ChannelKeyType as K;
+            use ChannelValueType as V;
+            match (attr.key, attr.value) {
+                (K::VertexId, V::Vec3) => {
+                    write_attribute::<_, Vec3>(&mut mesh, &v_idx_to_id, attr);
+                }
+                (K::VertexId, V::f32) => {
+                    write_attribute::<_, f32>(&mut mesh, &v_idx_to_id, attr);
+                }
+                (K::HalfEdgeId, V::Vec3) => {
+                    let ch_id = write_attribute::<_, Vec3>(&mut mesh, &h_idx_to_id, attr);
+                    if attr.name == "uv" {
+                        mesh.default_channels.uvs = Some(ch_id);
+                    }
+                }
+                (K::HalfEdgeId, V::f32) => {
+                    write_attribute::<_, f32>(&mut mesh, &h_idx_to_id, attr);
+                }
+                (K::FaceId, V::Vec3) => {
+                    write_attribute::<_, Vec3>(&mut mesh, &f_idx_to_id, attr);
+                }
+                (K::FaceId, V::f32) => {
+                    write_attribute::<_, f32>(&mut mesh, &f_idx_to_id, attr);
+                }
+                (_, V::bool) => unreachable!("Boolean channels are never stored as attributes"),
+            }
+        }
+
         mesh
     }
 
@@ -344,8 +493,13 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
     ///
     /// If `catmull_clark` is set to true, smooth subdivision using the Catmull
     /// Clark algorithm is performed, otherwise linear subdivision is performed.
+    ///
+    /// By default, Catmull Clark keeps boundary vertices in place. When
+    /// `smooth_boundaries` is set, boundaries are smoothed instead, using the
+    /// cubic B-spline rules so they converge to the correct limit curve.
+    /// Corners, vertices with a single face, are kept in place.
     #[profiling::function]
-    pub fn subdivide(&self, catmull_clark: bool) -> CompactMesh<true> {
+    pub fn subdivide(&self, catmull_clark: bool, smooth_boundaries: bool) -> CompactMesh<true> {
         use rayon::prelude::*;
 
         // Compute the counts for the new mesh
@@ -489,15 +643,60 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
                         / (n * n);
 
                     new_vertex_positions[v].fetch_add(inc, Ordering::Relaxed);
-                } else {
+                } else if !(catmull_clark && smooth_boundaries) {
                     new_vertex_positions[v].store(self.vertex_positions[v], Ordering::Relaxed);
                 }
             });
 
+        // --- Smooth boundary vertex points ---
+        if catmull_clark && smooth_boundaries {
+            // Each boundary halfedge contributes to both of its vertices. A
+            // boundary vertex has one outgoing and one incoming boundary
+            // halfedge, so it ends up at 3/4 of its position plus 1/8 of
+            // each of its two boundary neighbours.
+            (0..self.counts.num_halfedges)
+                .into_par_iter()
+                .filter(|h| self.twin[*h].is_none())
+                .for_each(|h| {
+                    let h_next = self.get_next(h);
+                    let h_prev = self.get_prev(h);
+                    let v_start = self.vert[h] as usize;
+                    let v_end = self.vert[h_next] as usize;
+                    let p_start = self.vertex_positions[v_start];
+                    let p_end = self.vertex_positions[v_end];
+
+                    // When the other boundary halfedge of a vertex is in the
+                    // same face, the vertex is a corner.
+                    if self.twin[h_prev].is_none() {
+                        new_vertex_positions[v_start].store(p_start, Ordering::Relaxed);
+                    } else {
+                        new_vertex_positions[v_start]
+                            .fetch_add(0.375 * p_start + 0.125 * p_end, Ordering::Relaxed);
+                    }
+                    if self.twin[h_next].is_none() {
+                        new_vertex_positions[v_end].store(p_end, Ordering::Relaxed);
+                    } else {
+                        new_vertex_positions[v_end]
+                            .fetch_add(0.375 * p_end + 0.125 * p_start, Ordering::Relaxed);
+                    }
+                });
+        }
+
         // SAFETY: Same as above, Vec3 and AtomicVec3 have the same memory layout
         let new_vertex_positions =
             unsafe { transmute_vec::<AtomicVec3, Vec3>(new_vertex_positions) };
 
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|attr| CompactAttribute {
+                name: attr.name.clone(),
+                key: attr.key,
+                value: attr.value,
+                values: self.subdivide_attribute(&attr.key, &attr.values, &get_cycle_length),
+            })
+            .collect();
+
         CompactMesh {
             twin: new_twin,
             // NOTE: Empty vecs represent analytically computed properties
@@ -507,15 +706,73 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
             edge: new_edge,
             face: vec![],
             vertex_positions: new_vertex_positions,
+            attributes,
             counts: new_counts,
         }
     }
 
+    /// Computes the values of an attribute after one step of subdivision.
+    /// Attributes are always interpolated linearly: The new face points take
+    /// the average of the face and the new edge points the average of the
+    /// edge. Face attributes are copied to the new faces.
+    fn subdivide_attribute(
+        &self,
+        key: &ChannelKeyType,
+        values: &[Vec3],
+        cycle_length: &dyn Fn(usize) -> u32,
+    ) -> Vec<Vec3> {
+        let v_d = self.counts.num_vertices;
+        let f_d = self.counts.num_faces;
+        let h_d = self.counts.num_halfedges;
+        match key {
+            ChannelKeyType::VertexId => {
+                let mut new_values = vec![Vec3::ZERO; self.counts.subdiv().num_vertices];
+                new_values[..v_d].copy_from_slice(values);
+                for h in 0..h_d {
+                    let v = self.vert[h] as usize;
+                    let v_end = self.vert[self.get_next(h)] as usize;
+                    new_values[v_d + self.get_face(h)] += values[v] / cycle_length(h) as f32;
+                    new_values[v_d + f_d + self.edge[h] as usize] =
+                        (values[v] + values[v_end]) / 2.0;
+                }
+                new_values
+            }
+            // Halfedge values belong to the corner of a face at the halfedge's
+            // vertex. The four new halfedges spawning from `h` are the corners
+            // of the new face for the corner `h`.
+            ChannelKeyType::HalfEdgeId => {
+                let mut face_averages = vec![Vec3::ZERO; f_d];
+                for h in 0..h_d {
+                    face_averages[self.get_face(h)] += values[h] / cycle_length(h) as f32;
+                }
+                (0..h_d)
+                    .flat_map(|h| {
+                        let next = values[self.get_next(h)];
+                        let prev = values[self.get_prev(h)];
+                        [
+                            values[h],
+                            (values[h] + next) / 2.0,
+                            face_averages[self.get_face(h)],
+                            (prev + values[h]) / 2.0,
+                        ]
+                    })
+                    .collect()
+            }
+            // After subdivision, there is one face per original halfedge.
+            ChannelKeyType::FaceId => (0..h_d).map(|h| values[self.get_face(h)]).collect(),
+        }
+    }
+
     #[profiling::function]
-    pub fn subdivide_multi(&self, iterations: usize, catmull_clark: bool) -> CompactMesh<true> {
-        let mut mesh = self.subdivide(catmull_clark);
+    pub fn subdivide_multi(
+        &self,
+        iterations: usize,
+        catmull_clark: bool,
+        smooth_boundaries: bool,
+    ) -> CompactMesh<true> {
+        let mut mesh = self.subdivide(catmull_clark, smooth_boundaries);
         for _ in 0..(iterations - 1) {
-            mesh = mesh.subdivide(catmull_clark);
+            mesh = mesh.subdivide(catmull_clark, smooth_boundaries);
         }
         mesh
     }
@@ -648,4 +905,56 @@ pub mod test {
             ]
         );
     }
+
+    #[test]
+    pub fn smooth_subdivision_test() {
+        // A strip of two quads, with a bend in the middle of its bottom side
+        let mut mesh = HalfEdgeMesh::build_from_polygons(
+            &[
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, -0.5, 0.0),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(2.0, 1.0, 0.0),
+            ],
+            &[[0, 1, 4, 3], [1, 2, 5, 4]],
+        )
+        .unwrap();
+        edit_ops::set_full_range_uvs(&mut mesh).unwrap();
+        let color = mesh.channels.ensure_channel::<VertexId, f32>("color");
+        for (v, pos) in mesh.read_positions().iter() {
+            mesh.channels.write_channel(color).unwrap()[v] = pos.x;
+        }
+
+        let min_y = |mesh: &HalfEdgeMesh| {
+            mesh.read_positions()
+                .iter()
+                .map(|(_, pos)| pos.y)
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        let compact = CompactMesh::<false>::from_halfedge_with_attributes(&mesh).unwrap();
+        let preserved = compact.subdivide(true, false).to_halfedge();
+        assert_eq!(min_y(&preserved), -0.5);
+
+        let smooth = compact.subdivide(true, true).to_halfedge();
+        assert!((min_y(&smooth) + 0.375).abs() < 1e-6);
+        assert_eq!(smooth.read_connectivity().num_faces(), 8);
+        assert!(smooth.read_uvs().is_some());
+
+        // The color matches the x coordinate, so it interpolates linearly
+        let colors = smooth
+            .channels
+            .read_channel_by_name::<VertexId, f32>("color")
+            .unwrap();
+        let mut colors = smooth
+            .read_connectivity()
+            .iter_vertices()
+            .map(|(v, _)| colors[v])
+            .collect_vec();
+        colors.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        colors.dedup();
+        assert_eq!(colors, vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+    }
 }
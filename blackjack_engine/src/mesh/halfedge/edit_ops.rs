@@ -6,21 +6,54 @@
 
 use std::{
     collections::{BTreeMap, BTreeSet},
-    f32::consts::PI,
+    f32::consts::{FRAC_PI_2, PI},
+    rc::Rc,
 };
 
 use anyhow::{anyhow, bail};
 use float_ord::FloatOrd;
-use glam::EulerRot;
 use smallvec::SmallVec;
 
 use crate::prelude::*;
 
-use super::selection::SelectionExpression;
+use super::selection::{SelectionExpression, SelectionFragment};
 
 /// Just a place where commented-out code goes to die
 pub mod deprecated;
 
+/// Helper ops to generate procedural buildings
+pub mod architecture;
+
+/// Skinning of line networks into pipes
+pub mod pipes;
+
+/// Procedural tree generation
+pub mod tree;
+
+/// Scattering of points over the surface of meshes
+pub mod scatter;
+
+/// Axis-based nonlinear deformers, like twist, bend and taper
+pub mod deform;
+
+/// Displacement of surfaces along their normals, by noise or textures
+pub mod displace;
+
+/// Splitting of sharp edges, to make normals discontinuous along them
+pub mod edge_split;
+
+/// Cutting of meshes along a plane, optionally clearing and capping one side
+pub mod bisect;
+
+/// Connected components of meshes, and operations applied to each of them
+pub mod islands;
+
+/// Exploded views of assemblies, moving their pieces away from a point
+pub mod explode;
+
+/// Conversion of the edges of meshes into strips and frames
+pub mod wireframe;
+
 /// Removes `h_l` and its twin `h_r`, merging their respective faces together.
 /// The face on the L side will be kept, and the R side removed. Both sides of
 /// the edge that will be dissolved need to be on a face. Boundary halfedges are
@@ -290,9 +323,58 @@ pub fn chamfer_vertex(
     interpolation_factor: f32,
 ) -> Result<(Option<FaceId>, SVec<VertexId>)> {
     let outgoing = mesh.at_vertex(v).outgoing_halfedges()?;
-    let mut vertices = SVec::new();
+    let factors = vec![interpolation_factor; outgoing.len()];
+    chamfer_vertex_with_factors(mesh, positions, v, &outgoing, &factors)
+}
+
+/// Replaces the vertex `v` with a face, by cutting off its corner at an
+/// `amount` distance along each of its edges. Unlike [`chamfer_vertex`], the
+/// `amount` is a distance, and it's limited to half the length of each edge
+/// so the bevels of neighbouring vertices never overlap.
+///
+/// Vertices in the boundary are left untouched, since there would be no face
+/// to cut off. Returns the new face, or `None` when the vertex was not
+/// modified.
+pub fn bevel_vertex(
+    mesh: &mut halfedge::MeshConnectivity,
+    positions: &mut Positions,
+    v: VertexId,
+    amount: f32,
+) -> Result<Option<FaceId>> {
+    if amount <= 0.0 {
+        return Ok(None);
+    }
+    let outgoing = mesh.at_vertex(v).outgoing_halfedges()?;
+    let mut factors = Vec::with_capacity(outgoing.len());
     for &h in &outgoing {
-        vertices.push(divide_edge(mesh, positions, h, interpolation_factor)?);
+        if mesh.at_halfedge(h).is_boundary()? || mesh.at_halfedge(h).twin().is_boundary()? {
+            return Ok(None);
+        }
+        let (src, dst) = mesh.at_halfedge(h).src_dst_pair()?;
+        let length = positions[src].distance(positions[dst]);
+        factors.push(if length > 0.0 {
+            amount.min(length * 0.5) / length
+        } else {
+            0.0
+        });
+    }
+    let (face, _) = chamfer_vertex_with_factors(mesh, positions, v, &outgoing, &factors)?;
+    Ok(face)
+}
+
+/// Shared implementation of [`chamfer_vertex`] and [`bevel_vertex`]. Each of
+/// the `outgoing` halfedges of `v` is divided at the interpolation factor in
+/// the same position of `factors`.
+fn chamfer_vertex_with_factors(
+    mesh: &mut halfedge::MeshConnectivity,
+    positions: &mut Positions,
+    v: VertexId,
+    outgoing: &[HalfEdgeId],
+    factors: &[f32],
+) -> Result<(Option<FaceId>, SVec<VertexId>)> {
+    let mut vertices = SVec::new();
+    for (&h, &factor) in outgoing.iter().zip(factors) {
+        vertices.push(divide_edge(mesh, positions, h, factor)?);
     }
 
     let mut is_boundary = false;
@@ -460,6 +542,147 @@ pub fn collapse_edge(mesh: &mut MeshConnectivity, h: HalfEdgeId) -> Result<Verte
     Ok(v)
 }
 
+/// Splits and collapses edges so that their lengths get closer to
+/// `target_length`. Edges longer than 4/3 of the target are split into equal
+/// segments, and edges shorter than 4/5 of the target are collapsed, as long
+/// as the collapse doesn't break the topology of the mesh or create edges that
+/// are too long. Boundary vertices stay on the boundary.
+pub fn quantize_edges(mesh: &HalfEdgeMesh, target_length: f32) -> Result<()> {
+    if target_length <= 0.0 {
+        bail!("The target edge length must be positive, got {target_length}");
+    }
+    let max_length = target_length * 4.0 / 3.0;
+    let min_length = target_length * 4.0 / 5.0;
+
+    let mut conn = mesh.write_connectivity();
+    let mut positions = mesh.write_positions();
+
+    /// Returns a single halfedge for every edge in the mesh.
+    fn unique_edges(conn: &MeshConnectivity) -> Vec<HalfEdgeId> {
+        let mut visited = HashSet::new();
+        let mut edges = vec![];
+        for (h, _) in conn.iter_halfedges() {
+            if let Ok(t) = conn.at_halfedge(h).twin().try_end() {
+                if visited.insert(h) && visited.insert(t) {
+                    edges.push(h);
+                }
+            }
+        }
+        edges
+    }
+
+    let edge_length = |conn: &MeshConnectivity, positions: &Positions, h: HalfEdgeId| {
+        conn.at_halfedge(h)
+            .src_dst_pair()
+            .map(|(v, w)| positions[v].distance(positions[w]))
+    };
+
+    // --- Split long edges ---
+    for h in unique_edges(&conn) {
+        let length = edge_length(&conn, &positions, h)?;
+        if length > max_length {
+            let segments = ((length / target_length).round() as usize).max(2);
+            // After each division, `h` is the remaining part of the edge, so
+            // the factor grows to keep all segments the same length.
+            for i in 0..segments - 1 {
+                divide_edge(&mut conn, &mut positions, h, 1.0 / (segments - i) as f32)?;
+            }
+        }
+    }
+
+    // --- Collapse short edges ---
+    let is_boundary_vertex = |conn: &MeshConnectivity, v: VertexId| -> Result<bool> {
+        for h in conn.at_vertex(v).outgoing_halfedges()? {
+            if conn.at_halfedge(h).is_boundary()? || conn.at_halfedge(h).twin().is_boundary()? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+    let neighbors = |conn: &MeshConnectivity, v: VertexId| -> Result<SVec<VertexId>> {
+        conn.at_vertex(v)
+            .outgoing_halfedges()?
+            .iter_cpy()
+            .map(|h| Ok(conn.at_halfedge(h).dst_vertex().try_end()?))
+            .collect()
+    };
+
+    // Collapsing an edge changes the length of its neighbors, so this is
+    // repeated until there's nothing left to collapse.
+    const MAX_ITERATIONS: usize = 10;
+    for _ in 0..MAX_ITERATIONS {
+        let mut short_edges = vec![];
+        for h in unique_edges(&conn) {
+            let length = edge_length(&conn, &positions, h)?;
+            if length < min_length {
+                short_edges.push((h, length));
+            }
+        }
+        short_edges.sort_by_key(|(_, l)| FloatOrd(*l));
+
+        let mut collapsed_any = false;
+        for (h, _) in short_edges {
+            // The edge may have been removed, or its length may have changed
+            // by a previous collapse.
+            if conn.halfedge(h).is_none() || edge_length(&conn, &positions, h)? >= min_length {
+                continue;
+            }
+            let t = conn.at_halfedge(h).twin().try_end()?;
+            let (v, w) = conn.at_halfedge(h).src_dst_pair()?;
+            let v_boundary = is_boundary_vertex(&conn, v)?;
+            let w_boundary = is_boundary_vertex(&conn, w)?;
+            let edge_boundary =
+                conn.at_halfedge(h).is_boundary()? || conn.at_halfedge(t).is_boundary()?;
+            if v_boundary && w_boundary && !edge_boundary {
+                // This would pinch the mesh together.
+                continue;
+            }
+
+            // Link condition: The endpoints can only share the neighbors that
+            // form a triangle with the edge. Otherwise the collapse would
+            // create non-manifold geometry.
+            let is_triangle = |h: HalfEdgeId| {
+                conn.at_halfedge(h).face().try_end().is_ok()
+                    && conn.halfedge_loop_iter(h).count() == 3
+            };
+            let num_triangles = [h, t].into_iter().filter(|h| is_triangle(*h)).count();
+            let v_neighbors = neighbors(&conn, v)?;
+            let w_neighbors = neighbors(&conn, w)?;
+            let num_shared = v_neighbors
+                .iter()
+                .filter(|x| w_neighbors.contains(x))
+                .count();
+            if num_shared != num_triangles {
+                continue;
+            }
+
+            let new_pos = match (v_boundary, w_boundary) {
+                (true, false) => positions[v],
+                (false, true) => positions[w],
+                _ => (positions[v] + positions[w]) * 0.5,
+            };
+            let too_long = v_neighbors
+                .iter()
+                .chain(w_neighbors.iter())
+                .filter(|x| **x != v && **x != w)
+                .any(|x| positions[*x].distance(new_pos) > max_length);
+            if too_long {
+                continue;
+            }
+
+            let v = collapse_edge(&mut conn, h)?;
+            positions[v] = new_pos;
+            collapsed_any = true;
+        }
+
+        if !collapsed_any {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
 /// Adjusts the connectivity of the mesh in preparation for a bevel operation.
 /// Any `halfedges` passed in will get "duplicated", and a face will be created
 /// in-between, consistently adjusting the connectivity everywhere.
@@ -646,8 +869,84 @@ pub fn bevel_edges(
     halfedges: &[HalfEdgeId],
     amount: f32,
 ) -> Result<()> {
+    bevel_edges_with_profile(
+        mesh,
+        positions,
+        halfedges,
+        amount,
+        1,
+        BevelProfile::Superellipse(0.5),
+    )
+}
+
+/// The shape of the strip of faces replacing each edge in
+/// [`bevel_edges_with_profile`].
+#[derive(Clone, Copy, Debug)]
+pub enum BevelProfile<'a> {
+    /// A quarter of a superellipse, with its middle point at the given
+    /// fraction of the way from the middle of the bevel to the original edge.
+    /// A value of 0.5 gives a flat chamfer, around 0.7 gives a circular arc,
+    /// and values closer to 1 make the profile approach the original sharp
+    /// edge.
+    Superellipse(f32),
+    /// A custom shape, drawn in the square having the original edge at one
+    /// corner and the two sides of the bevel at the adjacent ones. As the
+    /// profile goes from one side to the other, the ramp gives how far it has
+    /// moved in the perpendicular direction. A linear ramp gives a flat
+    /// chamfer, ramps rising quickly give a convex profile, ramps rising
+    /// slowly a concave one, and constant interpolation gives steps.
+    Ramp(&'a crate::graph::ramp::Ramp),
+}
+
+impl BevelProfile<'_> {
+    /// Returns the point of the profile going from `a` to `b`, where `corner`
+    /// is the original position of the beveled edge. `t` goes from 0 (at `a`)
+    /// to 1 (at `b`).
+    fn point(&self, a: Vec3, b: Vec3, corner: Vec3, t: f32) -> Vec3 {
+        // Fractions of the way from the corner to `a` and `b`
+        let (to_a, to_b) = match self {
+            BevelProfile::Superellipse(profile) => {
+                // The exponent of the superellipse |x|^r + |y|^r = 1 having
+                // its middle point at the given profile is r = -1 /
+                // log2(profile). It is sampled as (cos(θ)^(2/r), sin(θ)^(2/r)).
+                let exponent = -2.0 * profile.clamp(1e-4, 1.0).log2();
+                let theta = t * FRAC_PI_2;
+                let x = theta.cos().max(0.0).powf(exponent);
+                let y = theta.sin().max(0.0).powf(exponent);
+                (1.0 - y, 1.0 - x)
+            }
+            BevelProfile::Ramp(ramp) => (1.0 - ramp.sample(t), t),
+        };
+        corner + (a - corner) * to_a + (b - corner) * to_b
+    }
+}
+
+/// Bevels the given edges by a given distance `amount`. Each edge is replaced
+/// by a strip of `segments` faces, following the shape of the `profile`.
+/// Where three or more beveled edges meet, a corner face is created,
+/// following the profiles of the edges around it.
+pub fn bevel_edges_with_profile(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    halfedges: &[HalfEdgeId],
+    amount: f32,
+    segments: usize,
+    profile: BevelProfile,
+) -> Result<()> {
+    if segments == 0 {
+        bail!("A bevel needs at least one segment");
+    }
     let beveled_edges = bevel_edges_connectivity(mesh, positions, halfedges)?;
 
+    // All the vertices of the beveled edges are still at the position of the
+    // original vertex they come from. This is the corner of the profiles.
+    let mut corners = HashMap::<VertexId, Vec3>::new();
+    for h in beveled_edges.iter().copied() {
+        let (v, w) = mesh.at_halfedge(h).src_dst_pair()?;
+        corners.insert(v, positions[v]);
+        corners.insert(w, positions[w]);
+    }
+
     // --- Adjust vertex positions ---
 
     // Movement of vertices in a bevel can be modelled as a set of pulls. For
@@ -656,7 +955,7 @@ pub fn bevel_edges(
     // depending on their location of the halfedge (head, tail resp.). The final
     // move direction of a vertice is the sum of all its pulls.
     let mut move_ops = HashMap::<VertexId, HashSet<Vec3Ord>>::new();
-    for h in beveled_edges {
+    for h in beveled_edges.iter().copied() {
         mesh.add_debug_halfedge(h, DebugMark::green("bvl"));
 
         if mesh.at_halfedge(h).is_boundary()? {
@@ -685,6 +984,81 @@ pub fn bevel_edges(
         }
     }
 
+    if segments == 1 {
+        return Ok(());
+    }
+
+    // --- Split the bevel faces into strips ---
+
+    // Each bevel face is a quad between the twins of two beveled halfedges,
+    // `a` and `b`, joined by two "rails" going across the bevel. Faces that
+    // are not quads (e.g. at the boundary) are left as a single strip. The
+    // rails are stored as vertex pairs starting at the side of `a`, since
+    // dividing them changes their halfedges.
+    let mut bevel_faces = vec![];
+    let mut rails = vec![];
+    let mut visited_faces = HashSet::new();
+    let mut visited_rails = HashSet::new();
+    for h in beveled_edges.iter().copied() {
+        let a = mesh.at_halfedge(h).twin().try_end()?;
+        match mesh.at_halfedge(a).face().try_end() {
+            Ok(face) if visited_faces.insert(face) => {}
+            _ => continue,
+        };
+        let face_halfedges = mesh.halfedge_loop_iter(a).collect::<SVec<_>>();
+        if face_halfedges.len() != 4 {
+            continue;
+        }
+        let (b, r1, r2) = (face_halfedges[2], face_halfedges[1], face_halfedges[3]);
+        if !beveled_edges.contains(&mesh.at_halfedge(b).twin().try_end()?) {
+            continue;
+        }
+        let (r1_src, r1_dst) = mesh.at_halfedge(r1).src_dst_pair()?;
+        let (r2_src, r2_dst) = mesh.at_halfedge(r2).src_dst_pair()?;
+        bevel_faces.push([(r1_src, r1_dst), (r2_dst, r2_src)]);
+        for (rail, src, dst) in [(r1, r1_src, r1_dst), (r2, r2_src, r2_dst)] {
+            if visited_rails.insert((src.min(dst), src.max(dst))) {
+                rails.push((rail, src, dst));
+            }
+        }
+    }
+
+    // Rails are divided once, even when shared by two bevel faces. The new
+    // vertices of each rail are stored in order, from its src to its dst.
+    let mut rail_vertices = HashMap::<(VertexId, VertexId), SVec<VertexId>>::new();
+    for (rail, src, dst) in rails {
+        let (start, end) = (positions[src], positions[dst]);
+        let corner = match (corners.get(&src), corners.get(&dst)) {
+            (Some(c1), Some(c2)) => (*c1 + *c2) * 0.5,
+            _ => (start + end) * 0.5,
+        };
+        let mut vertices = SVec::new();
+        for i in 1..segments {
+            // After each division, `rail` is the remaining part of the edge,
+            // from the new vertex to `dst`.
+            let v = divide_edge(mesh, positions, rail, 0.5)?;
+            let t = i as f32 / segments as f32;
+            positions[v] = profile.point(start, end, corner, t);
+            vertices.push(v);
+        }
+        rail_vertices.insert((src, dst), vertices);
+    }
+    let rail_from_to = |v: VertexId, w: VertexId| -> Option<SVec<VertexId>> {
+        rail_vertices.get(&(v, w)).cloned().or_else(|| {
+            rail_vertices
+                .get(&(w, v))
+                .map(|vs| vs.iter_cpy().rev().collect())
+        })
+    };
+
+    for [(a1, b1), (a2, b2)] in bevel_faces {
+        if let (Some(side_1), Some(side_2)) = (rail_from_to(a1, b1), rail_from_to(a2, b2)) {
+            for (v, w) in side_1.iter_cpy().zip(side_2.iter_cpy()) {
+                cut_face(mesh, v, w)?;
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -697,22 +1071,7 @@ pub fn extrude_faces(
     amount: f32,
 ) -> Result<()> {
     let face_set: HashSet<FaceId> = faces.iter().cloned().collect();
-
-    // Find the set of all halfedges not adjacent to another extruded face.
-    let mut halfedges = vec![];
-    for f in faces {
-        for h in mesh.at_face(*f).halfedges()? {
-            let twin = mesh.at_halfedge(h).twin().try_end()?;
-            if let Ok(tw_face) = mesh.at_halfedge(twin).face().try_end() {
-                if !face_set.contains(&tw_face) {
-                    halfedges.push(h);
-                }
-            } else {
-                halfedges.push(h);
-            }
-        }
-    }
-
+    let halfedges = region_boundary_halfedges(mesh, &face_set)?;
     let _beveled_edges = bevel_edges_connectivity(mesh, positions, &halfedges)?;
 
     // --- Adjust vertex positions ---
@@ -742,80 +1101,1031 @@ pub fn extrude_faces(
     Ok(())
 }
 
-/// Generates the flat normals channel for this mesh
-pub fn generate_flat_normals_channel(mesh: &HalfEdgeMesh) -> Result<Channel<FaceId, Vec3>> {
-    let positions = mesh.read_positions();
-    let conn = mesh.read_connectivity();
-    let mut normals = Channel::<FaceId, Vec3>::new();
+/// Extrudes the given set of faces by `amount` in `segments` steps, adding a
+/// ring of faces around the extruded region at each step. The cross-section
+/// of the walls follows the `profile` ramp: At a fraction `t` of the height,
+/// the walls are inset by `width` times the value of the ramp at `t`.
+/// Negative values make the walls go outwards instead.
+pub fn extrude_faces_with_profile(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    faces: &[FaceId],
+    amount: f32,
+    segments: usize,
+    width: f32,
+    profile: &crate::graph::ramp::Ramp,
+) -> Result<()> {
+    if segments == 0 {
+        bail!("An extrusion needs at least one segment");
+    }
+    // Each step moves the region on top of the previous one, so insets are
+    // relative to the previous ring.
+    let mut prev_inset = 0.0;
+    for i in 1..=segments {
+        let inset = width * profile.sample(i as f32 / segments as f32);
+        inset_region(
+            mesh,
+            positions,
+            faces,
+            inset - prev_inset,
+            amount / segments as f32,
+        )?;
+        prev_inset = inset;
+    }
+    Ok(())
+}
 
-    for (face, _) in conn.iter_faces() {
-        // NOTE: Faces with only 2 vertices get a zero normal.
-        normals[face] = conn.face_normal(&positions, face).unwrap_or(Vec3::ZERO);
+/// Returns the halfedges of the given `faces` not adjacent to another face in
+/// the set, that is, the boundary of the region they cover.
+fn region_boundary_halfedges(
+    mesh: &MeshConnectivity,
+    faces: &HashSet<FaceId>,
+) -> Result<Vec<HalfEdgeId>> {
+    let mut halfedges = vec![];
+    for f in faces {
+        for h in mesh.at_face(*f).halfedges()? {
+            let twin = mesh.at_halfedge(h).twin().try_end()?;
+            if let Ok(tw_face) = mesh.at_halfedge(twin).face().try_end() {
+                if !faces.contains(&tw_face) {
+                    halfedges.push(h);
+                }
+            } else {
+                halfedges.push(h);
+            }
+        }
     }
+    Ok(halfedges)
+}
 
-    Ok(normals)
+/// Insets the given set of faces, surrounding them with a ring of new faces.
+/// The boundary of the inset faces is moved `thickness` units inwards, and
+/// the faces are then pushed `depth` units along their normal.
+///
+/// When `individual` is true, each face gets its own ring. Otherwise, faces
+/// connected by an edge are inset together, and only the boundary of the
+/// region they cover gets a ring.
+pub fn inset_faces(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    faces: &[FaceId],
+    thickness: f32,
+    depth: f32,
+    individual: bool,
+) -> Result<()> {
+    if individual {
+        for f in faces {
+            inset_region(mesh, positions, &[*f], thickness, depth)?;
+        }
+        Ok(())
+    } else {
+        inset_region(mesh, positions, faces, thickness, depth)
+    }
 }
 
-/// Computes the flat normal channel for this mesh and configures the mesh to
-/// generate flat normals. Flat normals are attached to faces.
-pub fn set_flat_normals(mesh: &mut HalfEdgeMesh) -> Result<()> {
-    let normals = generate_flat_normals_channel(mesh)?;
-    let normals_ch_id = mesh
-        .channels
-        .replace_or_create_channel("face_normal", normals);
+fn inset_region(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    faces: &[FaceId],
+    thickness: f32,
+    depth: f32,
+) -> Result<()> {
+    let face_set: HashSet<FaceId> = faces.iter().cloned().collect();
+    let halfedges = region_boundary_halfedges(mesh, &face_set)?;
+    // The inset faces keep their ids, but their boundary now runs along the
+    // newly created ring of faces.
+    bevel_edges_connectivity(mesh, positions, &halfedges)?;
+    let boundary = region_boundary_halfedges(mesh, &face_set)?;
+
+    // The normal at each vertex of the region is the average of its faces'.
+    let mut normals = HashMap::<VertexId, Vec3>::new();
+    for face in faces {
+        let normal = mesh
+            .face_normal(positions, *face)
+            .ok_or_else(|| anyhow!("Attempted to inset a face with only two vertices."))?;
+        for v in mesh.at_face(*face).vertices()? {
+            *normals.entry(v).or_default() += normal;
+        }
+    }
 
-    mesh.default_channels.face_normals = Some(normals_ch_id);
-    mesh.gen_config.smooth_normals = false;
+    // The directions of the boundary edges coming in and out of each vertex.
+    let mut boundary_dirs = HashMap::<VertexId, (Vec3, Vec3)>::new();
+    for h in boundary {
+        let (src, dst) = mesh.at_halfedge(h).src_dst_pair()?;
+        let dir = (positions[dst] - positions[src]).normalize_or_zero();
+        boundary_dirs.entry(src).or_default().1 = dir;
+        boundary_dirs.entry(dst).or_default().0 = dir;
+    }
+
+    for (v, normal) in normals {
+        let normal = normal.normalize_or_zero();
+        let mut offset = normal * depth;
+        if let Some((incoming, outgoing)) = boundary_dirs.get(&v) {
+            // Moving along the miter keeps the ring at the same thickness on
+            // both sides of the corner.
+            let d1 = normal.cross(*incoming).normalize_or_zero();
+            let d2 = normal.cross(*outgoing).normalize_or_zero();
+            let denom = 1.0 + d1.dot(d2);
+            offset += if denom > 1e-4 {
+                (d1 + d2) / denom * thickness
+            } else {
+                d1 * thickness
+            };
+        }
+        positions[v] += offset;
+    }
 
     Ok(())
 }
 
-/// Generates the smooth normals channel for this mesh.
-pub fn generate_smooth_normals_channel(mesh: &HalfEdgeMesh) -> Result<Channel<VertexId, Vec3>> {
-    let positions = mesh.read_positions();
-    let conn = mesh.read_connectivity();
-    let mut normals = Channel::<VertexId, Vec3>::new();
+/// How [`triangulate`] splits faces into triangles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriangulateMode {
+    /// Fan triangulation for convex faces, and ear clipping for concave ones.
+    Auto,
+    /// Fan triangulation from the first vertex of every face. This is the
+    /// fastest mode, but gives overlapping triangles for concave faces.
+    Fan,
+    /// Ear clipping for every face.
+    EarClip,
+}
 
-    for (vertex, _) in conn.iter_vertices() {
-        let adjacent_faces = conn.at_vertex(vertex).adjacent_faces()?;
-        let mut normal = Vec3::ZERO;
-        for face in adjacent_faces.iter_cpy() {
-            normal += conn.face_normal(&positions, face).unwrap_or(Vec3::ZERO);
+impl TriangulateMode {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Auto" => Ok(Self::Auto),
+            "Fan" => Ok(Self::Fan),
+            "EarClip" => Ok(Self::EarClip),
+            _ => bail!("Invalid triangulation mode: {name}"),
         }
-        normals[vertex] = normal.normalize_or_zero();
     }
+}
 
-    Ok(normals)
+/// Returns true when `triangles` are a valid triangulation of a polygon with
+/// `n` vertices: Each polygon edge is used once and each diagonal is shared
+/// by two triangles with opposite winding.
+fn is_valid_triangulation(n: usize, triangles: &[[usize; 3]]) -> bool {
+    if triangles.len() != n - 2 {
+        return false;
+    }
+    let mut edges = HashSet::new();
+    for tri in triangles {
+        for i in 0..3 {
+            if !edges.insert((tri[i], tri[(i + 1) % 3])) {
+                return false;
+            }
+        }
+    }
+    edges
+        .iter()
+        .all(|&(a, b)| b == (a + 1) % n || edges.contains(&(b, a)))
 }
 
-/// Computes "flat" normals for this mesh. Flat normals are attached to faces.
-pub fn set_smooth_normals(mesh: &mut HalfEdgeMesh) -> Result<()> {
-    let normals = generate_smooth_normals_channel(mesh)?;
-    let normals_ch_id = mesh
-        .channels
-        .replace_or_create_channel("vertex_normal", normals);
+/// Returns the triangles of a polygon with the given vertex `positions`, as
+/// triplets of indices into `positions` with the same winding as the polygon.
+pub(crate) fn triangulate_polygon(positions: &[Vec3], mode: TriangulateMode) -> Vec<[usize; 3]> {
+    let n = positions.len();
+    let fan = || (1..n - 1).map(|i| [0, i, i + 1]).collect_vec();
 
-    mesh.gen_config.smooth_normals = true;
-    mesh.default_channels.vertex_normals = Some(normals_ch_id);
+    // Newell's method, which is robust for concave and non-planar polygons
+    let normal = (0..n).fold(Vec3::ZERO, |acc, i| {
+        acc + positions[i].cross(positions[(i + 1) % n])
+    });
+    if mode == TriangulateMode::Fan || normal.length_squared() < 1e-12 {
+        return fan();
+    }
+    let normal = normal.normalize();
+
+    let is_convex = (0..n).all(|i| {
+        let prev = positions[(i + n - 1) % n];
+        let next = positions[(i + 1) % n];
+        (positions[i] - prev).cross(next - positions[i]).dot(normal) >= -1e-6
+    });
+    if mode == TriangulateMode::Auto && is_convex {
+        return fan();
+    }
+
+    // Ear clipping, with the polygon projected on its plane. The (u, v)
+    // frame keeps the polygon's winding counter-clockwise.
+    let u = normal.any_orthonormal_vector();
+    let v = normal.cross(u);
+    let coords = positions
+        .iter()
+        .flat_map(|p| [p.dot(u) as f64, p.dot(v) as f64])
+        .collect_vec();
+    let triangles = earcutr::earcut(&coords, &[], 2)
+        .chunks_exact(3)
+        .map(|t| {
+            let (a, b, c) = (t[0], t[1], t[2]);
+            let area = (coords[2 * b] - coords[2 * a]) * (coords[2 * c + 1] - coords[2 * a + 1])
+                - (coords[2 * b + 1] - coords[2 * a + 1]) * (coords[2 * c] - coords[2 * a]);
+            if area < 0.0 {
+                [a, c, b]
+            } else {
+                [a, b, c]
+            }
+        })
+        .collect_vec();
+
+    // Ear clipping may drop triangles on degenerate polygons.
+    if is_valid_triangulation(n, &triangles) {
+        triangles
+    } else {
+        fan()
+    }
+}
+
+/// Splits `face` into `triangles`, given as triplets of indices into the
+/// face's halfedges. The original face is kept as the first triangle. The
+/// pairs of (original, new) faces, and of (original, new) halfedges sharing
+/// the same corner, are pushed to `face_copies` and `corner_copies`.
+fn split_face_into_triangles(
+    conn: &mut MeshConnectivity,
+    face: FaceId,
+    triangles: &[[usize; 3]],
+    face_copies: &mut Vec<(FaceId, FaceId)>,
+    corner_copies: &mut Vec<(HalfEdgeId, HalfEdgeId)>,
+) -> Result<()> {
+    let halfedges = conn.face_edges(face);
+    let n = halfedges.len();
+    let vertices = halfedges
+        .iter()
+        .map(|h| conn.at_halfedge(*h).vertex().try_end())
+        .collect::<Result<SVec<_>, _>>()?;
+
+    let mut diagonals = HashMap::<(usize, usize), HalfEdgeId>::new();
+    for (i, tri) in triangles.iter().enumerate() {
+        let tri_face = if i == 0 {
+            face
+        } else {
+            let new_face = conn.alloc_face(None);
+            face_copies.push((face, new_face));
+            new_face
+        };
 
+        let mut tri_halfedges = [HalfEdgeId::default(); 3];
+        for j in 0..3 {
+            let (a, b) = (tri[j], tri[(j + 1) % 3]);
+            tri_halfedges[j] = if b == (a + 1) % n {
+                halfedges[a]
+            } else {
+                let h = conn.alloc_halfedge(HalfEdge::default());
+                conn[h].vertex = Some(vertices[a]);
+                if let Some(&twin) = diagonals.get(&(b, a)) {
+                    conn[h].twin = Some(twin);
+                    conn[twin].twin = Some(h);
+                }
+                diagonals.insert((a, b), h);
+                corner_copies.push((halfedges[a], h));
+                h
+            };
+        }
+        for j in 0..3 {
+            let h = tri_halfedges[j];
+            conn[h].next = Some(tri_halfedges[(j + 1) % 3]);
+            conn[h].face = Some(tri_face);
+        }
+        conn[tri_face].halfedge = Some(tri_halfedges[0]);
+    }
     Ok(())
 }
 
-/// Generates an UV channel for the mesh where ever polygon is mapped to the
-/// full UV range. Triangles will take half the UV space, quads will take the
-/// full space, and n-gons will take as much space as possible, being centered
-/// in the middle.
-pub fn generate_full_range_uvs_channel(mesh: &HalfEdgeMesh) -> Result<Channel<HalfEdgeId, Vec3>> {
-    let conn = mesh.read_connectivity();
-    let mut uvs = Channel::<HalfEdgeId, Vec3>::new();
+/// Splits every face of `mesh` with more than three vertices into triangles.
+/// The new faces keep the channel values of the face they come from, and the
+/// new halfedges those of the corner they start at.
+pub fn triangulate(mesh: &HalfEdgeMesh, mode: TriangulateMode) -> Result<()> {
+    use slotmap::Key;
 
-    for (face, _) in conn.iter_faces() {
-        // We use halfedges as a proxy for vertices, because we are interested
-        // in vertices, not just as points in space, but we actually want
-        // separate vertices for each face.
-        let halfedges = conn.face_edges(face);
-        match halfedges.len() {
-            x if x <= 2 => { /* Ignore */ }
+    let mut face_copies = vec![];
+    let mut corner_copies = vec![];
+    {
+        let mut conn = mesh.write_connectivity();
+        let positions = mesh.read_positions();
+        let faces = conn.iter_faces().map(|(face, _)| face).collect_vec();
+        for face in faces {
+            let vertices = conn.face_vertices(face);
+            if vertices.len() <= 3 {
+                continue;
+            }
+            let points = vertices.iter().map(|v| positions[*v]).collect_vec();
+            let triangles = triangulate_polygon(&points, mode);
+            split_face_into_triangles(
+                &mut conn,
+                face,
+                &triangles,
+                &mut face_copies,
+                &mut corner_copies,
+            )?;
+        }
+    }
+
+    mesh.channels.copy_values_dyn(
+        ChannelKeyType::FaceId,
+        &face_copies
+            .iter()
+            .map(|(from, to)| (from.data(), to.data()))
+            .collect_vec(),
+    );
+    mesh.channels.copy_values_dyn(
+        ChannelKeyType::HalfEdgeId,
+        &corner_copies
+            .iter()
+            .map(|(from, to)| (from.data(), to.data()))
+            .collect_vec(),
+    );
+    Ok(())
+}
+
+/// Merges pairs of adjacent triangles back into quads. Two triangles are only
+/// merged when the angle between their normals is at most `angle_threshold`
+/// (in radians), the resulting quad is convex, and they don't have a
+/// different material or a UV seam between them. When a triangle could be
+/// merged with several neighbors, the pairs giving the flattest and most
+/// square-shaped quads are merged first.
+pub fn tris_to_quads(mesh: &HalfEdgeMesh, angle_threshold: f32) -> Result<()> {
+    let mut conn = mesh.write_connectivity();
+    let positions = mesh.read_positions();
+    let uvs = mesh.read_uvs();
+    let materials = mesh
+        .channels
+        .read_channel_by_name::<FaceId, f32>(crate::materials::MATERIAL_CHANNEL)
+        .ok();
+
+    let mut candidates = vec![];
+    for (h, _) in conn.iter_halfedges() {
+        let twin = match conn.at_halfedge(h).twin().try_end() {
+            Ok(twin) if h < twin => twin,
+            // Boundary edges, or edges that were already visited
+            _ => continue,
+        };
+        let (f1, f2) = match (
+            conn.at_halfedge(h).face().try_end(),
+            conn.at_halfedge(twin).face().try_end(),
+        ) {
+            (Ok(f1), Ok(f2)) => (f1, f2),
+            _ => continue,
+        };
+        if conn.face_edges(f1).len() != 3 || conn.face_edges(f2).len() != 3 {
+            continue;
+        }
+        if let Some(materials) = &materials {
+            if materials[f1] != materials[f2] {
+                continue;
+            }
+        }
+
+        let h_next = conn.at_halfedge(h).next().try_end()?;
+        let twin_next = conn.at_halfedge(twin).next().try_end()?;
+        if let Some(uvs) = &uvs {
+            // Both triangles need to agree on the UVs at the shared vertices
+            let seam = |a: HalfEdgeId, b: HalfEdgeId| uvs[a].distance_squared(uvs[b]) > 1e-10;
+            if seam(h, twin_next) || seam(twin, h_next) {
+                continue;
+            }
+        }
+
+        // The quad that results from removing the edge, in the winding order
+        // of the faces.
+        let quad = [
+            conn.at_halfedge(h_next).vertex().try_end()?,
+            conn.at_halfedge(h_next).next().vertex().try_end()?,
+            conn.at_halfedge(twin_next).vertex().try_end()?,
+            conn.at_halfedge(twin_next).next().vertex().try_end()?,
+        ]
+        .map(|v| positions[v]);
+
+        let triangle_normal = |a: Vec3, b: Vec3, c: Vec3| (b - a).cross(c - b).normalize_or_zero();
+        let n1 = triangle_normal(quad[0], quad[1], quad[2]);
+        let n2 = triangle_normal(quad[2], quad[3], quad[0]);
+        if n1 == Vec3::ZERO || n2 == Vec3::ZERO {
+            continue;
+        }
+        let normals_angle = n1.angle_between(n2);
+        if normals_angle > angle_threshold {
+            continue;
+        }
+
+        let normal = (n1 + n2).normalize_or_zero();
+        let mut is_convex = true;
+        let mut shape_error = 0.0;
+        for i in 0..4 {
+            let prev = quad[(i + 3) % 4];
+            let next = quad[(i + 1) % 4];
+            let (a, b) = (prev - quad[i], next - quad[i]);
+            is_convex &= b.cross(a).dot(normal) > 0.0;
+            shape_error += (a.angle_between(b) - FRAC_PI_2).abs();
+        }
+        if is_convex {
+            candidates.push((FloatOrd(normals_angle + shape_error), h, f1, f2));
+        }
+    }
+
+    candidates.sort_by_key(|(score, ..)| *score);
+    let mut merged_faces = HashSet::new();
+    let mut edges_to_dissolve = vec![];
+    for (_, h, f1, f2) in candidates {
+        if !merged_faces.contains(&f1) && !merged_faces.contains(&f2) {
+            merged_faces.insert(f1);
+            merged_faces.insert(f2);
+            edges_to_dissolve.push(h);
+        }
+    }
+    for h in edges_to_dissolve {
+        dissolve_edge(&mut conn, h)?;
+    }
+    Ok(())
+}
+
+/// The elements removed by [`dissolve`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DissolveKind {
+    /// Removes edges, merging the faces on both sides.
+    Edges,
+    /// Removes vertices, merging the faces around them. Vertices with only
+    /// two edges join those edges into one instead.
+    Vertices,
+}
+
+impl DissolveKind {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Edges" => Ok(Self::Edges),
+            "Vertices" => Ok(Self::Vertices),
+            _ => bail!("Invalid dissolve kind: {name}"),
+        }
+    }
+}
+
+/// Removes the edges left dangling inside `face` after merging two faces that
+/// shared more than one edge. Those are the halfedges whose next halfedge is
+/// their own twin. The vertex at the tip of each dangling edge is removed too.
+fn remove_dangling_edges(conn: &mut MeshConnectivity, face: FaceId) -> Result<()> {
+    loop {
+        let start = conn.at_face(face).halfedge().try_end()?;
+        let mut dangling = None;
+        for h in conn.halfedge_loop(start) {
+            let twin = conn.at_halfedge(h).twin().try_end()?;
+            if conn.at_halfedge(h).next().try_end()? == twin {
+                dangling = Some((h, twin));
+                break;
+            }
+        }
+        let (h, twin) = match dangling {
+            Some(dangling) => dangling,
+            None => return Ok(()),
+        };
+
+        let (v, tip) = conn.at_halfedge(h).src_dst_pair()?;
+        let prev = conn.at_halfedge(h).previous().try_end()?;
+        let next = conn.at_halfedge(twin).next().try_end()?;
+        conn[prev].next = Some(next);
+        if conn[face].halfedge == Some(h) || conn[face].halfedge == Some(twin) {
+            conn[face].halfedge = Some(next);
+        }
+        if conn[v].halfedge == Some(h) {
+            conn[v].halfedge = Some(next);
+        }
+        conn.remove_halfedge(h);
+        conn.remove_halfedge(twin);
+        conn.remove_vertex(tip);
+    }
+}
+
+/// Dissolves the edge of `h` like [`dissolve_edge`], but leaves it in place
+/// when it's on the boundary or merging its faces wouldn't give a valid face.
+/// When both faces share a chain of several edges, the whole chain is
+/// removed. Returns the merged face, if the edge was dissolved.
+fn try_dissolve_edge(conn: &mut MeshConnectivity, h: HalfEdgeId) -> Result<Option<FaceId>> {
+    let twin = conn.at_halfedge(h).twin().try_end()?;
+    let (f_l, f_r) = match (
+        conn.at_halfedge(h).face().try_end(),
+        conn.at_halfedge(twin).face().try_end(),
+    ) {
+        (Ok(f_l), Ok(f_r)) if f_l != f_r => (f_l, f_r),
+        _ => return Ok(None),
+    };
+
+    // The edges shared by both faces need to form a single chain. Otherwise,
+    // the merged face would have a hole in it.
+    let loop_l = conn.halfedge_loop(h);
+    let shared = loop_l
+        .iter()
+        .map(|h| conn.at_halfedge(*h).twin().face().try_end().ok() == Some(f_r))
+        .collect_vec();
+    let num_shared = shared.iter().filter(|s| **s).count();
+    let num_chains = shared
+        .iter()
+        .circular_tuple_windows()
+        .filter(|(a, b)| !**a && **b)
+        .count();
+    let len_r = conn.halfedge_loop_iter(twin).count();
+    if num_chains != 1 || loop_l.len() + len_r < 3 + 2 * num_shared {
+        return Ok(None);
+    }
+
+    dissolve_edge(conn, h)?;
+    remove_dangling_edges(conn, f_l)?;
+    Ok(Some(f_l))
+}
+
+/// Dissolves the vertex `v`. Vertices with two edges are removed by joining
+/// both edges into one, unless that would leave a face with less than three
+/// sides. Other vertices are removed with [`dissolve_vertex`], as long as
+/// they're not on the boundary. Returns whether the vertex was removed.
+fn try_dissolve_vertex(conn: &mut MeshConnectivity, v: VertexId) -> Result<bool> {
+    let outgoing = conn.at_vertex(v).outgoing_halfedges()?;
+    let has_face = |h: HalfEdgeId| conn.at_halfedge(h).face().try_end().is_ok();
+    if outgoing.len() == 2 {
+        for &h in &outgoing {
+            if has_face(h) && conn.halfedge_loop_iter(h).count() <= 3 {
+                return Ok(false);
+            }
+        }
+        // Collapsing the incoming edge keeps the vertex at its other end
+        let incoming = conn.at_halfedge(outgoing[0]).twin().try_end()?;
+        collapse_edge(conn, incoming)?;
+        Ok(true)
+    } else if outgoing.len() > 2
+        && outgoing
+            .iter()
+            .all(|h| has_face(*h) && conn.at_halfedge(*h).twin().face().try_end().is_ok())
+    {
+        dissolve_vertex(conn, v)?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Dissolves the elements of `mesh` in `selection`, merging the faces around
+/// them into n-gons. Depending on `kind`, the selection is a set of edges or
+/// vertices. Boundary edges, and elements whose removal would leave an
+/// invalid face, are kept.
+pub fn dissolve(
+    mesh: &HalfEdgeMesh,
+    selection: &SelectionExpression,
+    kind: DissolveKind,
+) -> Result<()> {
+    match kind {
+        DissolveKind::Edges => {
+            let halfedges = mesh.resolve_halfedge_selection_full(selection)?;
+            let mut conn = mesh.write_connectivity();
+            for h in halfedges {
+                // Both halfedges of an edge may be selected, and dissolving a
+                // chain of edges removes several of them at once.
+                if conn.halfedge(h).is_some() {
+                    try_dissolve_edge(&mut conn, h)?;
+                }
+            }
+        }
+        DissolveKind::Vertices => {
+            let vertices = mesh.resolve_vertex_selection_full(selection)?;
+            let mut conn = mesh.write_connectivity();
+            for v in vertices {
+                if conn.vertex(v).is_some() {
+                    try_dissolve_vertex(&mut conn, v)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merges adjacent faces of `mesh` into n-gons, when the angle between their
+/// normals is at most `angle_threshold` (in radians). Then, removes the
+/// vertices in the middle of edges that bend less than that same angle. Faces
+/// with a different material are not merged. The flattest edges are
+/// dissolved first, and the normals of the merged faces are updated as they
+/// grow, so curved surfaces don't collapse into a single face.
+pub fn limited_dissolve(mesh: &HalfEdgeMesh, angle_threshold: f32) -> Result<()> {
+    let mut conn = mesh.write_connectivity();
+    let positions = mesh.read_positions();
+    let materials = mesh
+        .channels
+        .read_channel_by_name::<FaceId, f32>(crate::materials::MATERIAL_CHANNEL)
+        .ok();
+
+    // Newell normals of merged faces are the sum of the normals of the faces
+    // being merged, so they don't need to be computed again.
+    let mut normals = conn
+        .iter_faces()
+        .map(|(f, _)| {
+            let points = conn
+                .face_vertices(f)
+                .iter()
+                .map(|v| positions[*v])
+                .collect_vec();
+            (f, newell_normal(&points))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let faces_to_merge = |conn: &MeshConnectivity,
+                          normals: &HashMap<FaceId, Vec3>,
+                          h: HalfEdgeId|
+     -> Option<(f32, FaceId, FaceId)> {
+        let twin = conn.at_halfedge(h).twin().try_end().ok()?;
+        let f1 = conn.at_halfedge(h).face().try_end().ok()?;
+        let f2 = conn.at_halfedge(twin).face().try_end().ok()?;
+        if f1 == f2 {
+            return None;
+        }
+        if let Some(materials) = &materials {
+            if materials[f1] != materials[f2] {
+                return None;
+            }
+        }
+        let (n1, n2) = (normals[&f1], normals[&f2]);
+        if n1 == Vec3::ZERO || n2 == Vec3::ZERO {
+            return None;
+        }
+        let angle = n1.angle_between(n2);
+        (angle <= angle_threshold).then_some((angle, f1, f2))
+    };
+
+    let mut candidates = conn
+        .iter_halfedges()
+        .filter_map(|(h, _)| {
+            let (angle, ..) = faces_to_merge(&conn, &normals, h)?;
+            Some((FloatOrd(angle), h))
+        })
+        .collect_vec();
+    candidates.sort_by_key(|(angle, _)| *angle);
+    for (_, h) in candidates {
+        if conn.halfedge(h).is_none() {
+            continue;
+        }
+        if let Some((_, f1, f2)) = faces_to_merge(&conn, &normals, h) {
+            if try_dissolve_edge(&mut conn, h)?.is_some() {
+                let merged = normals[&f1] + normals[&f2];
+                normals.insert(f1, merged);
+                normals.remove(&f2);
+            }
+        }
+    }
+
+    let vertices = conn.iter_vertices().map(|(v, _)| v).collect_vec();
+    for v in vertices {
+        let outgoing = match conn.at_vertex(v).outgoing_halfedges() {
+            Ok(outgoing) if outgoing.len() == 2 => outgoing,
+            _ => continue,
+        };
+        let a = positions[conn.at_halfedge(outgoing[0]).src_dst_pair()?.1];
+        let b = positions[conn.at_halfedge(outgoing[1]).src_dst_pair()?.1];
+        let (in_dir, out_dir) = (positions[v] - a, b - positions[v]);
+        if in_dir == Vec3::ZERO || out_dir == Vec3::ZERO {
+            continue;
+        }
+        if in_dir.angle_between(out_dir) <= angle_threshold {
+            try_dissolve_vertex(&mut conn, v)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes all the boundary halfedges of `conn`, leaving their twins without
+/// a twin. Operations that detach or remove faces do this first, and then
+/// call `add_boundary_halfedges` so the old and new boundaries are linked
+/// together.
+fn remove_boundary_halfedges(conn: &mut MeshConnectivity) {
+    let boundary = conn
+        .iter_halfedges()
+        .filter(|(_, halfedge)| halfedge.face.is_none())
+        .map(|(h, _)| h)
+        .collect_vec();
+    for b in boundary {
+        if let Some(twin) = conn[b].twin {
+            conn[twin].twin = None;
+        }
+        conn.remove_halfedge(b);
+    }
+}
+
+/// Points vertices back to one of their outgoing halfedges, when theirs was
+/// removed or now starts at a different vertex.
+fn fix_vertex_halfedges(conn: &mut MeshConnectivity) {
+    let mut outgoing = HashMap::new();
+    for (h, halfedge) in conn.iter_halfedges() {
+        if let Some(v) = halfedge.vertex {
+            outgoing.entry(v).or_insert(h);
+        }
+    }
+    let vertices = conn.iter_vertices().map(|(v, _)| v).collect_vec();
+    for v in vertices {
+        let valid = conn[v]
+            .halfedge
+            .and_then(|h| conn.halfedge(h))
+            .map_or(false, |halfedge| halfedge.vertex == Some(v));
+        if !valid {
+            conn[v].halfedge = outgoing.get(&v).copied();
+        }
+    }
+}
+
+/// Closes the holes of `mesh` by capping each of its boundary loops with a
+/// new face. Loops with more than `max_hole_edges` edges are left open, unless
+/// it's zero, and so are loops that pass more than once through the same
+/// vertex. When `triangulate` is true, caps are split into triangles instead
+/// of being left as n-gons. Returns the number of holes filled.
+pub fn fill_holes(mesh: &HalfEdgeMesh, max_hole_edges: usize, triangulate: bool) -> Result<usize> {
+    let mut conn = mesh.write_connectivity();
+    let positions = mesh.read_positions();
+
+    let mut visited = HashSet::new();
+    let mut loops = vec![];
+    for (h, halfedge) in conn.iter_halfedges() {
+        if halfedge.face.is_some() || visited.contains(&h) {
+            continue;
+        }
+        let mut boundary_loop = SVec::<HalfEdgeId>::new();
+        let mut it = h;
+        loop {
+            if !visited.insert(it) {
+                break;
+            }
+            boundary_loop.push(it);
+            it = conn.at_halfedge(it).next().try_end()?;
+        }
+        if it == h {
+            loops.push(boundary_loop);
+        }
+    }
+
+    let mut filled = 0;
+    let mut face_copies = vec![];
+    let mut corner_copies = vec![];
+    for boundary_loop in loops {
+        let vertices = boundary_loop
+            .iter()
+            .map(|h| conn.at_halfedge(*h).vertex().try_end())
+            .collect::<Result<SVec<_>, _>>()?;
+        let too_large = max_hole_edges > 0 && boundary_loop.len() > max_hole_edges;
+        if boundary_loop.len() < 3 || too_large || vertices.iter().duplicates().next().is_some() {
+            continue;
+        }
+        let face = conn.alloc_face(Some(boundary_loop[0]));
+        for h in boundary_loop.iter_cpy() {
+            conn[h].face = Some(face);
+        }
+        if triangulate && vertices.len() > 3 {
+            let points = vertices.iter().map(|v| positions[*v]).collect_vec();
+            let triangles = triangulate_polygon(&points, TriangulateMode::Auto);
+            split_face_into_triangles(
+                &mut conn,
+                face,
+                &triangles,
+                &mut face_copies,
+                &mut corner_copies,
+            )?;
+        }
+        filled += 1;
+    }
+    Ok(filled)
+}
+
+/// Generates the flat normals channel for this mesh
+pub fn generate_flat_normals_channel(mesh: &HalfEdgeMesh) -> Result<Channel<FaceId, Vec3>> {
+    let positions = mesh.read_positions();
+    let conn = mesh.read_connectivity();
+    let mut normals = Channel::<FaceId, Vec3>::new();
+
+    for (face, _) in conn.iter_faces() {
+        // NOTE: Faces with only 2 vertices get a zero normal.
+        normals[face] = conn.face_normal(&positions, face).unwrap_or(Vec3::ZERO);
+    }
+
+    Ok(normals)
+}
+
+/// Computes the flat normal channel for this mesh and configures the mesh to
+/// generate flat normals. Flat normals are attached to faces.
+pub fn set_flat_normals(mesh: &mut HalfEdgeMesh) -> Result<()> {
+    let normals = generate_flat_normals_channel(mesh)?;
+    let normals_ch_id = mesh
+        .channels
+        .replace_or_create_channel("face_normal", normals);
+
+    mesh.default_channels.face_normals = Some(normals_ch_id);
+    mesh.gen_config.shading = ShadingMode::Flat;
+
+    Ok(())
+}
+
+/// Generates the smooth normals channel for this mesh.
+pub fn generate_smooth_normals_channel(mesh: &HalfEdgeMesh) -> Result<Channel<VertexId, Vec3>> {
+    let positions = mesh.read_positions();
+    let conn = mesh.read_connectivity();
+    let mut normals = Channel::<VertexId, Vec3>::new();
+
+    for (vertex, _) in conn.iter_vertices() {
+        let adjacent_faces = conn.at_vertex(vertex).adjacent_faces()?;
+        let mut normal = Vec3::ZERO;
+        for face in adjacent_faces.iter_cpy() {
+            normal += conn.face_normal(&positions, face).unwrap_or(Vec3::ZERO);
+        }
+        normals[vertex] = normal.normalize_or_zero();
+    }
+
+    Ok(normals)
+}
+
+/// Computes "flat" normals for this mesh. Flat normals are attached to faces.
+pub fn set_smooth_normals(mesh: &mut HalfEdgeMesh) -> Result<()> {
+    let normals = generate_smooth_normals_channel(mesh)?;
+    let normals_ch_id = mesh
+        .channels
+        .replace_or_create_channel("vertex_normal", normals);
+
+    mesh.gen_config.shading = ShadingMode::Smooth;
+    mesh.default_channels.vertex_normals = Some(normals_ch_id);
+
+    Ok(())
+}
+
+/// Generates normals for every face corner (halfedge) of this mesh. Corners
+/// average the normals of the faces around their vertex, but only those that
+/// meet their own face at an angle of at most `angle` radians.
+pub fn generate_auto_smooth_normals_channel(
+    mesh: &HalfEdgeMesh,
+    angle: f32,
+) -> Result<Channel<HalfEdgeId, Vec3>> {
+    let face_normals = generate_flat_normals_channel(mesh)?;
+    let conn = mesh.read_connectivity();
+    let mut normals = Channel::<HalfEdgeId, Vec3>::new();
+    // A small tolerance, so that an angle of zero still smooths coplanar faces
+    let min_cos = angle.cos() - 1e-5;
+
+    for (face, _) in conn.iter_faces() {
+        let face_normal = face_normals[face];
+        for h in conn.face_edges(face) {
+            let v = conn.at_halfedge(h).vertex().try_end()?;
+            let mut normal = Vec3::ZERO;
+            for other in conn.at_vertex(v).adjacent_faces()?.iter_cpy() {
+                let other_normal = face_normals[other];
+                if other == face || face_normal.dot(other_normal) >= min_cos {
+                    normal += other_normal;
+                }
+            }
+            normals[h] = normal.normalize_or_zero();
+        }
+    }
+
+    Ok(normals)
+}
+
+/// Computes auto smooth normals for this mesh, and configures the mesh to be
+/// shaded smooth except for edges sharper than `angle` radians. Auto smooth
+/// normals are attached to face corners.
+pub fn set_auto_smooth_normals(mesh: &mut HalfEdgeMesh, angle: f32) -> Result<()> {
+    let normals = generate_auto_smooth_normals_channel(mesh, angle)?;
+    let normals_ch_id = mesh
+        .channels
+        .replace_or_create_channel("corner_normal", normals);
+
+    mesh.gen_config.shading = ShadingMode::AutoSmooth;
+    mesh.gen_config.auto_smooth_angle = angle;
+    mesh.default_channels.corner_normals = Some(normals_ch_id);
+
+    Ok(())
+}
+
+/// Generates normals for every face corner (halfedge) of this mesh. Corners
+/// of the faces in the [`SMOOTH_FACES_GROUP`] average the normals of the
+/// smooth faces around their vertex, while the other faces get flat normals.
+pub fn generate_mixed_normals_channel(mesh: &HalfEdgeMesh) -> Result<Channel<HalfEdgeId, Vec3>> {
+    let face_normals = generate_flat_normals_channel(mesh)?;
+    let smooth_ch = mesh
+        .channels
+        .read_channel_by_name::<FaceId, bool>(SMOOTH_FACES_GROUP)
+        .ok();
+    let is_smooth = |face: FaceId| smooth_ch.as_ref().map_or(false, |ch| ch[face]);
+    let conn = mesh.read_connectivity();
+    let mut normals = Channel::<HalfEdgeId, Vec3>::new();
+
+    for (face, _) in conn.iter_faces() {
+        let smooth = is_smooth(face);
+        for h in conn.face_edges(face) {
+            normals[h] = if smooth {
+                let v = conn.at_halfedge(h).vertex().try_end()?;
+                conn.at_vertex(v)
+                    .adjacent_faces()?
+                    .iter_cpy()
+                    .filter(|f| is_smooth(*f))
+                    .map(|f| face_normals[f])
+                    .sum::<Vec3>()
+                    .normalize_or_zero()
+            } else {
+                face_normals[face]
+            };
+        }
+    }
+
+    Ok(normals)
+}
+
+/// Computes mixed normals for this mesh, and configures the mesh to shade the
+/// faces in the [`SMOOTH_FACES_GROUP`] smooth, and the rest flat. Mixed
+/// normals are attached to face corners.
+pub fn set_mixed_normals(mesh: &mut HalfEdgeMesh) -> Result<()> {
+    let normals = generate_mixed_normals_channel(mesh)?;
+    let normals_ch_id = mesh
+        .channels
+        .replace_or_create_channel("corner_normal", normals);
+
+    mesh.gen_config.shading = ShadingMode::Mixed;
+    mesh.default_channels.corner_normals = Some(normals_ch_id);
+
+    Ok(())
+}
+
+/// Adds the faces in `selection` to the [`SMOOTH_FACES_GROUP`], so they are
+/// shaded smooth when the mesh uses [`ShadingMode::Mixed`].
+pub fn set_smooth_faces(mesh: &mut HalfEdgeMesh, selection: &SelectionExpression) -> Result<()> {
+    let ch_id = mesh
+        .channels
+        .ensure_channel::<FaceId, bool>(SMOOTH_FACES_GROUP);
+    let mut smooth_ch = mesh.channels.write_channel(ch_id)?;
+    for face in mesh.resolve_face_selection_full(selection)? {
+        smooth_ch[face] = true;
+    }
+    Ok(())
+}
+
+/// Computes the normals channel matching the `mode`, and configures the mesh
+/// to use it. The `angle` is only used for [`ShadingMode::AutoSmooth`].
+pub fn set_shading(mesh: &mut HalfEdgeMesh, mode: ShadingMode, angle: f32) -> Result<()> {
+    match mode {
+        ShadingMode::Flat => set_flat_normals(mesh),
+        ShadingMode::Smooth => set_smooth_normals(mesh),
+        ShadingMode::AutoSmooth => set_auto_smooth_normals(mesh, angle),
+        ShadingMode::Mixed => set_mixed_normals(mesh),
+    }
+}
+
+/// Returns the normal of every face corner (halfedge) of the mesh, following
+/// its shading configuration. Existing normal channels are used when the
+/// mesh has them. This lets consumers that store one normal per face corner,
+/// like exporters, support every shading mode the same way.
+pub fn generate_corner_normals_channel(mesh: &HalfEdgeMesh) -> Result<Channel<HalfEdgeId, Vec3>> {
+    if let ShadingMode::AutoSmooth | ShadingMode::Mixed = mesh.gen_config.shading {
+        return match mesh.read_corner_normals() {
+            Some(normals) => Ok(normals.clone()),
+            None if mesh.gen_config.shading == ShadingMode::Mixed => {
+                generate_mixed_normals_channel(mesh)
+            }
+            None => generate_auto_smooth_normals_channel(mesh, mesh.gen_config.auto_smooth_angle),
+        };
+    }
+
+    let conn = mesh.read_connectivity();
+    let mut normals = Channel::<HalfEdgeId, Vec3>::new();
+    if mesh.gen_config.shading == ShadingMode::Smooth {
+        let generated;
+        let existing = mesh.read_vertex_normals();
+        let vertex_normals = match existing.as_deref() {
+            Some(normals) => normals,
+            None => {
+                generated = generate_smooth_normals_channel(mesh)?;
+                &generated
+            }
+        };
+        for (h, _) in conn.iter_halfedges() {
+            if let Ok(v) = conn.at_halfedge(h).vertex().try_end() {
+                normals[h] = vertex_normals[v];
+            }
+        }
+    } else {
+        let generated;
+        let existing = mesh.read_face_normals();
+        let face_normals = match existing.as_deref() {
+            Some(normals) => normals,
+            None => {
+                generated = generate_flat_normals_channel(mesh)?;
+                &generated
+            }
+        };
+        for (face, _) in conn.iter_faces() {
+            for h in conn.face_edges(face) {
+                normals[h] = face_normals[face];
+            }
+        }
+    }
+    Ok(normals)
+}
+
+/// Generates an UV channel for the mesh where ever polygon is mapped to the
+/// full UV range. Triangles will take half the UV space, quads will take the
+/// full space, and n-gons will take as much space as possible, being centered
+/// in the middle.
+pub fn generate_full_range_uvs_channel(mesh: &HalfEdgeMesh) -> Result<Channel<HalfEdgeId, Vec3>> {
+    let conn = mesh.read_connectivity();
+    let mut uvs = Channel::<HalfEdgeId, Vec3>::new();
+
+    for (face, _) in conn.iter_faces() {
+        // We use halfedges as a proxy for vertices, because we are interested
+        // in vertices, not just as points in space, but we actually want
+        // separate vertices for each face.
+        let halfedges = conn.face_edges(face);
+        match halfedges.len() {
+            x if x <= 2 => { /* Ignore */ }
             3 => {
                 // Triangle
                 uvs[halfedges[0]] = Vec3::new(1.0, 0.0, 0.0);
@@ -1218,16 +2528,404 @@ pub fn bridge_chains_ui(
     Ok(())
 }
 
-pub fn transform(mesh: &HalfEdgeMesh, translate: Vec3, rotate: Vec3, scale: Vec3) -> Result<()> {
+pub fn transform(mesh: &HalfEdgeMesh, translate: Vec3, rotate: Quat, scale: Vec3) -> Result<()> {
+    transform_matrix(
+        mesh,
+        Mat4::from_scale_rotation_translation(scale, rotate, translate),
+    )
+}
+
+/// Returns a copy of `mesh` together with its reflection across the plane
+/// through `plane_origin` with the given `plane_normal`. The faces of the
+/// reflected half have their winding flipped, so normals keep pointing
+/// outwards. Vertices closer than `merge_distance` to the plane are snapped
+/// onto it and shared by both halves, welding them together.
+///
+/// Note that the mesh is rebuilt by this operation, so only vertex positions
+/// are kept.
+pub fn mirror(
+    mesh: &HalfEdgeMesh,
+    plane_origin: Vec3,
+    plane_normal: Vec3,
+    merge_distance: f32,
+) -> Result<HalfEdgeMesh> {
+    let normal = plane_normal
+        .try_normalize()
+        .ok_or_else(|| anyhow!("The mirror plane normal can't be zero"))?;
+    let conn = mesh.read_connectivity();
+    let mesh_positions = mesh.read_positions();
+
+    let mut positions = vec![];
+    let mut index = HashMap::<VertexId, usize>::new();
+    for (v, _) in conn.iter_vertices() {
+        index.insert(v, positions.len());
+        positions.push(mesh_positions[v]);
+    }
+    let num_vertices = positions.len();
+
+    // Welded vertices are their own reflection
+    let mut mirrored = Vec::with_capacity(num_vertices);
+    for i in 0..num_vertices {
+        let distance = (positions[i] - plane_origin).dot(normal);
+        if distance.abs() <= merge_distance {
+            positions[i] -= normal * distance;
+            mirrored.push(i);
+        } else {
+            mirrored.push(positions.len());
+            positions.push(positions[i] - normal * distance * 2.0);
+        }
+    }
+
+    let mut polygons = vec![];
+    let mut mirrored_polygons = vec![];
+    for (face, _) in conn.iter_faces() {
+        let polygon = conn
+            .face_vertices(face)
+            .iter()
+            .map(|v| index[v])
+            .collect_vec();
+        // Faces on the plane would overlap with their own reflection
+        if !polygon.iter().all(|i| mirrored[*i] == *i) {
+            mirrored_polygons.push(polygon.iter().rev().map(|i| mirrored[*i]).collect_vec());
+        }
+        polygons.push(polygon);
+    }
+    polygons.extend(mirrored_polygons);
+
+    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+        .context("The mirrored mesh is not manifold. Check the faces touching the mirror plane.")
+}
+
+/// Name of the vertex group used by [`attach`] to mark the vertices that can
+/// be welded. It is removed from the result.
+const ATTACH_WELD_GROUP: &str = "attach_weld";
+
+/// Returns `mesh_a` merged with a copy of `mesh_b`, moved so that its
+/// `anchor_b` anchor lands on the `anchor_a` anchor of `mesh_a`, with the same
+/// orientation. Both anchors are used up by the join and removed from the
+/// result, while the rest of the anchors of both meshes are kept.
+///
+/// When a `weld_distance` is given, boundary vertices closer than that
+/// distance to each other are merged, so coincident open borders of both
+/// parts are stitched together.
+pub fn attach(
+    mesh_a: &HalfEdgeMesh,
+    anchor_a: &str,
+    mesh_b: &HalfEdgeMesh,
+    anchor_b: &str,
+    weld_distance: Option<f32>,
+) -> Result<HalfEdgeMesh> {
+    let target = *mesh_a
+        .read_anchors()
+        .get(anchor_a)
+        .ok_or_else(|| anyhow!("The first mesh has no anchor named '{anchor_a}'"))?;
+    let source = *mesh_b
+        .read_anchors()
+        .get(anchor_b)
+        .ok_or_else(|| anyhow!("The second mesh has no anchor named '{anchor_b}'"))?;
+
+    let placed_b = mesh_b.clone();
+    transform_matrix(&placed_b, target.to_matrix() * source.to_matrix().inverse())?;
+    placed_b.write_anchors().remove(anchor_b);
+
+    let mut result = mesh_a.clone();
+    result.write_anchors().remove(anchor_a);
+    result.merge_with(&placed_b);
+
+    let weld_distance = match weld_distance {
+        Some(weld_distance) => weld_distance,
+        None => return Ok(result),
+    };
+
+    let group_id = result
+        .channels
+        .ensure_channel::<VertexId, bool>(ATTACH_WELD_GROUP);
+    {
+        let conn = result.read_connectivity();
+        let mut group = result.channels.write_channel(group_id)?;
+        for (h, _) in conn.iter_halfedges() {
+            if conn.at_halfedge(h).is_boundary()? {
+                let (src, dst) = conn.at_halfedge(h).src_dst_pair()?;
+                group[src] = true;
+                group[dst] = true;
+            }
+        }
+    }
+    let mut welded = merge_by_distance(
+        &result,
+        weld_distance,
+        &SelectionExpression::Explicit(vec![SelectionFragment::Group(ATTACH_WELD_GROUP.into())]),
+    )?;
+    let group_id = welded
+        .channels
+        .ensure_channel::<VertexId, bool>(ATTACH_WELD_GROUP);
+    welded.channels.remove_channel(group_id)?;
+    Ok(welded)
+}
+
+/// Faces whose normals differ by less than this angle, in radians, are
+/// considered to be on the same plane by [`extract_walkable`].
+const WALKABLE_PLANAR_ANGLE: f32 = 0.02;
+
+/// Returns the (non-normalized) normal of a polygon, computed with Newell's
+/// method. Its length is twice the area of the polygon.
+fn newell_normal(points: &[Vec3]) -> Vec3 {
+    points
+        .iter()
+        .circular_tuple_windows()
+        .fold(Vec3::ZERO, |acc, (a, b)| acc + a.cross(*b))
+}
+
+/// Extracts the walkable surface of a mesh, like a navigation mesh would:
+/// The faces facing upwards with a slope of at most `max_slope_angle`
+/// radians. Connected faces lying on the same plane are merged into a single
+/// polygon, dropping the vertices in the middle of straight boundaries, and
+/// regions with an area smaller than `min_area` are discarded.
+///
+/// Regions with holes can't be represented as a single polygon, so their
+/// original faces are kept instead.
+pub fn extract_walkable(
+    mesh: &HalfEdgeMesh,
+    max_slope_angle: f32,
+    min_area: f32,
+) -> Result<HalfEdgeMesh> {
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let min_up = max_slope_angle.clamp(0.0, FRAC_PI_2).cos();
+
+    let mut normals = HashMap::<FaceId, Vec3>::new();
+    let mut areas = HashMap::<FaceId, f32>::new();
+    for (face, _) in conn.iter_faces() {
+        let points = conn
+            .face_vertices(face)
+            .iter()
+            .map(|v| positions[*v])
+            .collect::<SVec<_>>();
+        let normal = newell_normal(&points);
+        if let Some(unit) = normal.try_normalize() {
+            if unit.dot(Vec3::Y) >= min_up {
+                normals.insert(face, unit);
+                areas.insert(face, normal.length() * 0.5);
+            }
+        }
+    }
+
+    // Grow planar regions from the walkable faces, crossing only the edges
+    // shared with faces on the same plane as the first one.
+    let mut visited = HashSet::<FaceId>::new();
+    let mut regions = vec![];
+    for (seed, _) in conn.iter_faces() {
+        if !normals.contains_key(&seed) || !visited.insert(seed) {
+            continue;
+        }
+        let seed_normal = normals[&seed];
+        let mut region = HashSet::new();
+        let mut stack = vec![seed];
+        while let Some(face) = stack.pop() {
+            region.insert(face);
+            for h in conn.at_face(face).halfedges()? {
+                if let Ok(neighbor) = conn.at_halfedge(h).twin().face().try_end() {
+                    let planar = normals
+                        .get(&neighbor)
+                        .map(|n| n.angle_between(seed_normal) <= WALKABLE_PLANAR_ANGLE)
+                        .unwrap_or(false);
+                    if planar && visited.insert(neighbor) {
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+        if region.iter().map(|f| areas[f]).sum::<f32>() >= min_area {
+            regions.push(region);
+        }
+    }
+
+    // The polygons of each region, as lists of vertices
+    let mut region_polygons = vec![];
+    for region in &regions {
+        // Follow the boundary loops of the region. Rotating around the
+        // target vertex of a boundary halfedge, through the faces in the
+        // region, leads to the next one.
+        let boundary = region_boundary_halfedges(&conn, region)?;
+        let boundary_set = boundary.iter().copied().collect::<HashSet<_>>();
+        let mut loops = vec![];
+        let mut looped = HashSet::new();
+        for &start in &boundary {
+            if !looped.insert(start) {
+                continue;
+            }
+            let mut vertices = vec![conn.at_halfedge(start).vertex().try_end()?];
+            let mut h = conn.at_halfedge(start).next().try_end()?;
+            while h != start {
+                if boundary_set.contains(&h) {
+                    looped.insert(h);
+                    vertices.push(conn.at_halfedge(h).vertex().try_end()?);
+                    h = conn.at_halfedge(h).next().try_end()?;
+                } else {
+                    h = conn.at_halfedge(h).twin().next().try_end()?;
+                }
+            }
+            loops.push(vertices);
+        }
+
+        // A single loop that doesn't touch itself can replace the faces
+        match loops.as_slice() {
+            [vertices] if vertices.iter().duplicates().next().is_none() => {
+                let simplified = vertices
+                    .iter()
+                    .circular_tuple_windows()
+                    .filter(|(prev, v, next)| {
+                        let d1 = (positions[**v] - positions[**prev]).normalize_or_zero();
+                        let d2 = (positions[**next] - positions[**v]).normalize_or_zero();
+                        d1.cross(d2).length() > 1e-4 || d1.dot(d2) < 0.0
+                    })
+                    .map(|(_, v, _)| *v)
+                    .collect_vec();
+                if simplified.len() >= 3 {
+                    region_polygons.push(vec![simplified]);
+                }
+            }
+            _ => region_polygons.push(
+                conn.iter_faces()
+                    .filter(|(face, _)| region.contains(face))
+                    .map(|(face, _)| conn.face_vertices(face).to_vec())
+                    .collect_vec(),
+            ),
+        }
+    }
+
+    let build = |share_vertices: bool| {
+        let mut out_positions = vec![];
+        let mut index = HashMap::<(usize, VertexId), usize>::new();
+        let mut polygons = vec![];
+        for (i, region) in region_polygons.iter().enumerate() {
+            let key = if share_vertices { 0 } else { i };
+            for polygon in region {
+                polygons.push(
+                    polygon
+                        .iter()
+                        .map(|v| {
+                            *index.entry((key, *v)).or_insert_with(|| {
+                                out_positions.push(positions[*v]);
+                                out_positions.len() - 1
+                            })
+                        })
+                        .collect_vec(),
+                );
+            }
+        }
+        HalfEdgeMesh::build_from_polygons(&out_positions, &polygons)
+    };
+
+    // Regions touching each other at a single vertex can't share it
+    build(true)
+        .or_else(|_| build(false))
+        .context("The walkable surface is not manifold")
+}
+
+/// Applies the given affine transformation `matrix` to the `position` channel
+/// of this mesh. The anchors of the mesh are moved along with it.
+pub fn transform_matrix(mesh: &HalfEdgeMesh, matrix: Mat4) -> Result<()> {
     let mut positions = mesh.write_positions();
     let conn = mesh.read_connectivity();
 
     for (v, _) in conn.iter_vertices() {
-        positions[v] = Quat::from_euler(glam::EulerRot::XYZ, rotate.x, rotate.y, rotate.z)
-            * (positions[v] * scale)
-            + translate;
+        positions[v] = matrix.transform_point3(positions[v]);
+    }
+    mesh.write_anchors().transform(matrix);
+
+    Ok(())
+}
+
+/// The algorithm used by [`smooth`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmoothMethod {
+    /// Moves each vertex towards the average of its neighbors. Removes noise
+    /// quickly, but also shrinks the mesh.
+    Laplacian,
+    /// Alternates a shrinking Laplacian step with an inflating one, which
+    /// removes noise while keeping the volume of the mesh.
+    Taubin,
+}
+
+impl SmoothMethod {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Laplacian" => Ok(Self::Laplacian),
+            "Taubin" => Ok(Self::Taubin),
+            _ => bail!("Invalid smoothing method: {name}"),
+        }
+    }
+}
+
+/// The pass-band frequency of Taubin smoothing. Noise with a higher frequency
+/// is removed, while the shape of the mesh is kept.
+const TAUBIN_PASS_BAND: f32 = 0.1;
+
+/// Smooths the surface of `mesh` by relaxing its vertices `iterations` times.
+/// Each step moves the vertices towards the average of their neighbors by the
+/// given `factor`, between zero and one. When `pin_boundary` is true, the
+/// vertices on the boundary of the mesh stay in place, which is needed to
+/// keep open meshes from receding.
+pub fn smooth(
+    mesh: &HalfEdgeMesh,
+    iterations: usize,
+    factor: f32,
+    method: SmoothMethod,
+    pin_boundary: bool,
+) -> Result<()> {
+    if !(0.0..=1.0).contains(&factor) {
+        bail!("The smoothing factor must be between zero and one, got {factor}");
+    }
+    let conn = mesh.read_connectivity();
+    let mut positions = mesh.write_positions();
+
+    let vertices = conn.iter_vertices().map(|(v, _)| v).collect_vec();
+    let mut neighbors = HashMap::<VertexId, SVec<VertexId>>::new();
+    let mut boundary = HashSet::<VertexId>::new();
+    for (h, halfedge) in conn.iter_halfedges() {
+        let (src, dst) = conn.at_halfedge(h).src_dst_pair()?;
+        for (a, b) in [(src, dst), (dst, src)] {
+            let list = neighbors.entry(a).or_default();
+            if !list.contains(&b) {
+                list.push(b);
+            }
+        }
+        if halfedge.face.is_none() || halfedge.twin.is_none() {
+            boundary.insert(src);
+            boundary.insert(dst);
+        }
+    }
+
+    // Taubin's inflating step uses a negative factor slightly larger than the
+    // shrinking one, so they cancel out for low frequencies.
+    let steps = match method {
+        SmoothMethod::Laplacian => vec![factor],
+        SmoothMethod::Taubin if factor > 0.0 => {
+            vec![factor, 1.0 / (TAUBIN_PASS_BAND - 1.0 / factor)]
+        }
+        SmoothMethod::Taubin => vec![],
+    };
+
+    let mut offsets = vec![Vec3::ZERO; vertices.len()];
+    for _ in 0..iterations {
+        for &step in &steps {
+            for (offset, v) in offsets.iter_mut().zip(vertices.iter_cpy()) {
+                *offset = Vec3::ZERO;
+                if pin_boundary && boundary.contains(&v) {
+                    continue;
+                }
+                if let Some(ns) = neighbors.get(&v) {
+                    let average =
+                        ns.iter().fold(Vec3::ZERO, |acc, n| acc + positions[*n]) / ns.len() as f32;
+                    *offset = (average - positions[v]) * step;
+                }
+            }
+            for (offset, v) in offsets.iter_cpy().zip(vertices.iter_cpy()) {
+                positions[v] += offset;
+            }
+        }
     }
-
     Ok(())
 }
 
@@ -1350,6 +3048,167 @@ pub fn add_vertex(this: &mut HalfEdgeMesh, pos: Vec3) -> Result<()> {
     Ok(())
 }
 
+/// Merges the vertices in the `selection` of `mesh` that are closer than
+/// `distance` to each other. Each vertex is merged into the first vertex found
+/// within `distance` of it, so chains of close vertices don't collapse into a
+/// single point. Edges and faces left with no length or area are removed.
+/// Channel values are kept, with merged vertices taking the values of the
+/// vertex they were merged into. Loose edges are dropped.
+pub fn merge_by_distance(
+    mesh: &HalfEdgeMesh,
+    distance: f32,
+    selection: &SelectionExpression,
+) -> Result<HalfEdgeMesh> {
+    use slotmap::Key;
+
+    if distance < 0.0 {
+        bail!("The merge distance can't be negative, got {distance}");
+    }
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let selected: HashSet<VertexId> = mesh
+        .resolve_vertex_selection_full(selection)?
+        .into_iter()
+        .collect();
+
+    // Close vertices are found with a spatial hash, where each cell is as
+    // large as the merge distance, so only neighboring cells need checking.
+    let cell_size = distance.max(1e-6);
+    let cell = |p: Vec3| (p / cell_size).floor().to_array().map(|x| x as i32);
+    let mut cells = HashMap::<[i32; 3], SVec<VertexId>>::new();
+    let mut target = HashMap::<VertexId, VertexId>::new();
+    for (v, _) in conn.iter_vertices() {
+        let p = positions[v];
+        let mut found = None;
+        if selected.contains(&v) {
+            let [x, y, z] = cell(p);
+            'search: for (dx, dy, dz) in itertools::iproduct!(-1..=1, -1..=1, -1..=1) {
+                if let Some(candidates) = cells.get(&[x + dx, y + dy, z + dz]) {
+                    for w in candidates.iter_cpy() {
+                        if positions[w].distance(p) <= distance {
+                            found = Some(w);
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+        match found {
+            Some(w) => {
+                target.insert(v, w);
+            }
+            None => {
+                target.insert(v, v);
+                if selected.contains(&v) {
+                    cells.entry(cell(p)).or_default().push(v);
+                }
+            }
+        }
+    }
+
+    let mut index = HashMap::<VertexId, usize>::new();
+    let mut kept_vertices = vec![];
+    let mut new_positions = vec![];
+    for (v, _) in conn.iter_vertices() {
+        if target[&v] == v {
+            index.insert(v, new_positions.len());
+            kept_vertices.push(v);
+            new_positions.push(positions[v]);
+        }
+    }
+
+    // Runs of halfedges whose vertices got merged together are collapsed
+    // into the last one, which is the one leaving the merged vertex. Faces
+    // left with less than three distinct vertices are dropped.
+    let mut polygons = vec![];
+    let mut kept_faces = vec![];
+    let mut kept_halfedges = vec![];
+    for (face, _) in conn.iter_faces() {
+        let halfedges = conn.face_edges(face);
+        let mut corners = SVec::<(HalfEdgeId, usize)>::new();
+        for h in halfedges.iter_cpy() {
+            let v = conn.at_halfedge(h).vertex().try_end()?;
+            let i = index[&target[&v]];
+            match corners.last_mut() {
+                Some(last) if last.1 == i => last.0 = h,
+                _ => corners.push((h, i)),
+            }
+        }
+        while corners.len() > 1 && corners.first().map(|c| c.1) == corners.last().map(|c| c.1) {
+            corners.pop();
+        }
+        if corners.len() < 3 || corners.iter().map(|c| c.1).duplicates().next().is_some() {
+            continue;
+        }
+        polygons.push(corners.iter().map(|c| c.1).collect_vec());
+        kept_faces.push(face);
+        kept_halfedges.extend(corners.iter().map(|c| c.0));
+    }
+
+    let mut result = HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+        .context("Merging the vertices makes the mesh non-manifold. Try a smaller distance")?;
+
+    // NOTE: Relies on `build_from_polygons` allocating vertices in order of
+    // appearance in the polygons, and the faces and then the halfedges of each
+    // face in the same order as the polygons.
+    let mut vertex_map = HashMap::<VertexId, VertexId>::new();
+    let mut face_map = HashMap::<FaceId, FaceId>::new();
+    let mut halfedge_map = HashMap::<HalfEdgeId, HalfEdgeId>::new();
+    {
+        let mut result_conn = result.write_connectivity();
+        let mut result_positions = result.write_positions();
+        let mut new_vertices = result_conn
+            .iter_vertices()
+            .map(|(v, _)| v)
+            .collect_vec()
+            .into_iter();
+        let mut allocated = vec![None; kept_vertices.len()];
+        for i in polygons.iter().flatten().copied() {
+            if allocated[i].is_none() {
+                allocated[i] = new_vertices.next();
+            }
+        }
+        // Loose vertices aren't part of any polygon, so they are added here.
+        for (i, new_v) in allocated.iter_mut().enumerate() {
+            let new_v = *new_v.get_or_insert_with(|| {
+                result_conn.alloc_vertex(&mut result_positions, new_positions[i], None)
+            });
+            vertex_map.insert(kept_vertices[i], new_v);
+        }
+        for (old, (new, _)) in kept_faces.iter().zip(result_conn.iter_faces()) {
+            face_map.insert(*old, new);
+        }
+        for (old, (new, _)) in kept_halfedges.iter().zip(result_conn.iter_halfedges()) {
+            halfedge_map.insert(*old, new);
+        }
+    }
+
+    let vertex_ids: Rc<Vec<_>> = Rc::new(vertex_map.keys().map(|k| k.data()).collect());
+    let face_ids: Rc<Vec<_>> = Rc::new(face_map.keys().map(|k| k.data()).collect());
+    let halfedge_ids: Rc<Vec<_>> = Rc::new(halfedge_map.keys().map(|k| k.data()).collect());
+    let get_ids = |kty| match kty {
+        ChannelKeyType::VertexId => Rc::clone(&vertex_ids),
+        ChannelKeyType::FaceId => Rc::clone(&face_ids),
+        ChannelKeyType::HalfEdgeId => Rc::clone(&halfedge_ids),
+    };
+    let id_map = |kty, k| match kty {
+        ChannelKeyType::VertexId => vertex_map[&VertexId::from(k)].data(),
+        ChannelKeyType::FaceId => face_map[&FaceId::from(k)].data(),
+        ChannelKeyType::HalfEdgeId => halfedge_map[&HalfEdgeId::from(k)].data(),
+    };
+    result.channels.merge_with(&mesh.channels, get_ids, id_map);
+    if let Some(uv_name) = mesh
+        .default_channels
+        .uvs
+        .and_then(|ch_id| mesh.channels.channel_name(ch_id))
+    {
+        result.default_channels.uvs = result.channels.channel_id(uv_name);
+    }
+    result.gen_config = mesh.gen_config.clone();
+    *result.write_anchors() = mesh.read_anchors().clone();
+    Ok(result)
+}
+
 /// Returns a point cloud mesh, selecting a set of vertices from the given mesh
 pub fn point_cloud(mesh: &HalfEdgeMesh, sel: SelectionExpression) -> Result<HalfEdgeMesh> {
     let vertices = mesh.resolve_vertex_selection_full(&sel)?;
@@ -1441,7 +3300,9 @@ pub fn set_material(
     material: f32,
 ) -> Result<()> {
     // TODO: Use default channels?
-    let ch_id = mesh.channels.ensure_channel::<FaceId, f32>("material");
+    let ch_id = mesh
+        .channels
+        .ensure_channel::<FaceId, f32>(crate::materials::MATERIAL_CHANNEL);
     let mut material_ch = mesh.channels.write_channel(ch_id)?;
     let ids = mesh.resolve_face_selection_full(selection)?;
     for id in ids {
@@ -1450,6 +3311,60 @@ pub fn set_material(
     Ok(())
 }
 
+/// Fills the vertex `color` channel by mapping a scalar value of each vertex
+/// through the color `ramp`. The `source` is either one of the position axes
+/// (`"x"`, `"y"` or `"z"`) or the name of an `f32` vertex channel. Values are
+/// normalized to the range of the source in this mesh before sampling the
+/// ramp, so the lowest value maps to the start of the ramp and the highest to
+/// its end.
+pub fn color_by_gradient(
+    mesh: &mut HalfEdgeMesh,
+    source: &str,
+    ramp: &crate::graph::ramp::Ramp,
+) -> Result<()> {
+    let values: Vec<(VertexId, f32)> = {
+        let conn = mesh.read_connectivity();
+        let axis = match source.to_ascii_lowercase().as_str() {
+            "x" => Some(0),
+            "y" => Some(1),
+            "z" => Some(2),
+            _ => None,
+        };
+        if let Some(axis) = axis {
+            let positions = mesh.read_positions();
+            conn.iter_vertices()
+                .map(|(v, _)| (v, positions[v][axis]))
+                .collect()
+        } else {
+            let ch = mesh
+                .channels
+                .read_channel_by_name::<VertexId, f32>(source)
+                .map_err(|_| {
+                    anyhow!("'{source}' is neither an axis (x, y, z) nor a vertex f32 channel")
+                })?;
+            conn.iter_vertices().map(|(v, _)| (v, ch[v])).collect()
+        }
+    };
+
+    let (min, max) = values
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), (_, x)| {
+            (min.min(*x), max.max(*x))
+        });
+    let range = if max - min > f32::EPSILON {
+        max - min
+    } else {
+        1.0
+    };
+
+    let color_ch_id = mesh.channels.ensure_channel::<VertexId, Vec3>("color");
+    let mut color_ch = mesh.channels.write_channel(color_ch_id)?;
+    for (v, x) in values {
+        color_ch[v] = ramp.sample_color((x - min) / range);
+    }
+    Ok(())
+}
+
 /// TODO: Remove this once #[feature(map_first_last)] stabilizes
 pub trait MapPolyfill<T> {
     fn pop_first2(&mut self) -> Option<T>;
@@ -1476,6 +3391,41 @@ where
 }
 
 pub fn copy_to_points(points: &HalfEdgeMesh, cpy_mesh: &HalfEdgeMesh) -> Result<HalfEdgeMesh> {
+    copy_to_points_with_variation(points, &[cpy_mesh], &InstanceVariation::default(), 0)
+}
+
+/// Random variations applied to each instance by
+/// [`copy_to_points_with_variation`].
+#[derive(Clone, Debug, Default)]
+pub struct InstanceVariation {
+    /// Each instance is scaled by a random factor in the range
+    /// `[1 - scale_jitter, 1 + scale_jitter]`.
+    pub scale_jitter: f32,
+    /// Each instance is rotated around its up axis (the point's normal) by a
+    /// random angle in the range `[-rotation_jitter, rotation_jitter]`, in
+    /// radians.
+    pub rotation_jitter: f32,
+}
+
+/// Like [`copy_to_points`], but picks the mesh for each point among a list of
+/// `cpy_meshes`, and applies some random `variation` to each instance. The
+/// same `seed` always gives the same result.
+///
+/// When the points have a `variant` vertex channel, its value is used as the
+/// index of the mesh in `cpy_meshes` for each point. Otherwise, meshes are
+/// picked randomly.
+pub fn copy_to_points_with_variation(
+    points: &HalfEdgeMesh,
+    cpy_meshes: &[&HalfEdgeMesh],
+    variation: &InstanceVariation,
+    seed: u32,
+) -> Result<HalfEdgeMesh> {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    if cpy_meshes.is_empty() {
+        bail!("At least one mesh is required to copy to points");
+    }
+
     let conn = points.read_connectivity();
     let position_ch = points.read_positions();
     let size_ch = points
@@ -1487,10 +3437,29 @@ pub fn copy_to_points(points: &HalfEdgeMesh, cpy_mesh: &HalfEdgeMesh) -> Result<
     let tangent_ch = points
         .channels
         .read_channel_by_name::<VertexId, Vec3>("tangent");
+    let variant_ch = points
+        .channels
+        .read_channel_by_name::<VertexId, f32>("variant");
+
+    /// Returns a random value in the `[-range, range]` interval
+    fn jitter(rng: &mut StdRng, range: f32) -> f32 {
+        if range > 0.0 {
+            rng.gen_range(-range..=range)
+        } else {
+            0.0
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed as u64);
 
     let mut result = HalfEdgeMesh::new();
     for (i, (v, _)) in conn.iter_vertices().enumerate() {
-        let mut cpy_instance = cpy_mesh.clone();
+        let variant = match (&variant_ch, cpy_meshes.len()) {
+            (_, 1) => 0,
+            (Ok(variant_ch), len) => (variant_ch[v].max(0.0) as usize) % len,
+            (Err(_), len) => rng.gen_range(0..len),
+        };
+        let mut cpy_instance = cpy_meshes[variant].clone();
         let instance_idx_ch_id = cpy_instance.channels.create_channel("instance_idx")?;
 
         // Mark all halfedges of this instance with its index
@@ -1505,6 +3474,7 @@ pub fn copy_to_points(points: &HalfEdgeMesh, cpy_mesh: &HalfEdgeMesh) -> Result<
         } else {
             Vec3::ONE
         };
+        let scale = scale * (1.0 + jitter(&mut rng, variation.scale_jitter));
 
         let rotate =
             if let (Ok(normal_ch), Ok(tangent_ch)) = (normal_ch.as_ref(), tangent_ch.as_ref()) {
@@ -1518,101 +3488,645 @@ pub fn copy_to_points(points: &HalfEdgeMesh, cpy_mesh: &HalfEdgeMesh) -> Result<
                     glam::Vec3A::ZERO,
                 )
                 .to_scale_rotation_translation();
-                rotate.to_euler(glam::EulerRot::XYZ).into()
+                rotate
             } else {
-                Vec3::ZERO
+                Quat::IDENTITY
             };
+        let rotate = rotate * Quat::from_rotation_y(jitter(&mut rng, variation.rotation_jitter));
 
         // Drop the channels so we can mutate the whole mesh
         drop(cpy_instance_conn);
         drop(instance_idx_ch);
 
-        transform(&cpy_instance, position_ch[v], rotate, scale)?;
-        result.merge_with(&cpy_instance);
+        transform(&cpy_instance, position_ch[v], rotate, scale)?;
+        result.merge_with(&cpy_instance);
+    }
+
+    Ok(result)
+}
+
+pub fn extrude_along_curve(
+    backbone: &HalfEdgeMesh,
+    cross_section: &HalfEdgeMesh,
+    flip: usize,
+) -> Result<HalfEdgeMesh> {
+    let backbone_conn = backbone.read_connectivity();
+    let backbone_pos = backbone.read_positions();
+    let backbone_size = backbone
+        .channels
+        .read_channel_by_name::<VertexId, f32>("size");
+    let backbone_nrm = backbone
+        .channels
+        .read_channel_by_name::<VertexId, Vec3>("normal");
+    let backbone_tgt = backbone
+        .channels
+        .read_channel_by_name::<VertexId, Vec3>("tangent");
+
+    // Sort the vertices of the cross-section
+    let csect_pos = cross_section.read_positions();
+    let csect_conn = cross_section.read_connectivity();
+    let bag = cross_section.resolve_halfedge_selection_full(&SelectionExpression::All)?;
+    let (csect_chain, is_closed) = sort_bag_of_edges(&csect_conn, &bag)?;
+
+    let mut positions = vec![];
+
+    for (v, _) in backbone_conn.iter_vertices() {
+        let scale = if let Ok(ref size) = backbone_size {
+            Vec3::splat(size[v])
+        } else {
+            Vec3::ONE
+        };
+
+        let rotate = if let (Ok(normal_ch), Ok(tangent_ch)) =
+            (backbone_nrm.as_ref(), backbone_tgt.as_ref())
+        {
+            let normal = normal_ch[v];
+            let tangent = tangent_ch[v];
+            let cotangent = normal.cross(tangent);
+            let (_, rotate, _) = glam::Affine3A::from_cols(
+                cotangent.into(),
+                normal.into(),
+                tangent.into(),
+                glam::Vec3A::ZERO,
+            )
+            .to_scale_rotation_translation();
+            rotate
+        } else {
+            Quat::IDENTITY
+        };
+
+        for vc in csect_chain.iter_cpy() {
+            let pos = csect_pos[vc];
+            positions.push(rotate * (pos * scale) + backbone_pos[v]);
+        }
+    }
+
+    let mut polygons: Vec<[u32; 4]> = vec![];
+    let num_segments = backbone_conn.num_vertices();
+    let segment_length = csect_conn.num_vertices();
+
+    for seg in 0..num_segments - 1 {
+        let offset = seg * segment_length;
+        for (i, j) in (0..segment_length as u32).branch(
+            is_closed,
+            |x| x.circular_tuple_windows(),
+            |x| x.tuple_windows(),
+        ) {
+            let polygon = if flip % 2 == 0 {
+                [i, j, j + segment_length as u32, i + segment_length as u32]
+            } else {
+                [j, i, i + segment_length as u32, j + segment_length as u32]
+            }
+            .map(|i| i + offset as u32);
+            polygons.push(polygon);
+        }
+    }
+
+    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+}
+
+/// How [`sweep`] orients the profile along the path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweepAlign {
+    /// Rotation-minimizing frames, which follow the path without twisting
+    /// around it. Best for pipes and cables.
+    Frames,
+    /// Keeps the profile's Y axis as close as possible to the world up
+    /// direction. Best for rails and moldings, which should stay level.
+    Up,
+    /// The profile is only translated along the path, never rotated.
+    Fixed,
+}
+
+impl SweepAlign {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Frames" => Ok(Self::Frames),
+            "Up" => Ok(Self::Up),
+            "Fixed" => Ok(Self::Fixed),
+            _ => bail!("Invalid sweep alignment: {name}"),
+        }
+    }
+}
+
+/// Returns the normals of the rotation-minimizing frames along `points`, with
+/// the given `tangents`, computed with the double reflection method by Wang
+/// et al. On closed paths, the twist needed to make the frames meet again is
+/// spread along the whole path.
+fn rotation_minimizing_normals(points: &[Vec3], tangents: &[Vec3], closed: bool) -> Vec<Vec3> {
+    let reflect = |v: Vec3, axis: Vec3| {
+        let c = axis.length_squared();
+        if c > 1e-12 {
+            v - axis * (2.0 / c) * axis.dot(v)
+        } else {
+            v
+        }
+    };
+    let step = |i: usize, j: usize, normal: Vec3| {
+        let v1 = points[j] - points[i];
+        let normal_l = reflect(normal, v1);
+        let tangent_l = reflect(tangents[i], v1);
+        reflect(normal_l, tangents[j] - tangent_l)
+    };
+
+    let n = points.len();
+    let mut normals = Vec::with_capacity(n);
+    // Start as close to the up direction as possible
+    normals.push(
+        Vec3::Y
+            .reject_from_normalized(tangents[0])
+            .try_normalize()
+            .unwrap_or_else(|| tangents[0].any_orthonormal_vector()),
+    );
+    for i in 1..n {
+        normals.push(step(i - 1, i, normals[i - 1]));
+    }
+
+    if closed {
+        let t0 = tangents[0];
+        let back = step(n - 1, 0, normals[n - 1]);
+        let mismatch = normals[0].cross(back).dot(t0).atan2(normals[0].dot(back));
+        for (i, normal) in normals.iter_mut().enumerate() {
+            let angle = -mismatch * i as f32 / n as f32;
+            *normal = Quat::from_axis_angle(tangents[i], angle) * *normal;
+        }
+    }
+    normals
+}
+
+/// Extrudes the `profile` polyline along the `path` polyline. The profile is
+/// drawn on the XY plane, and the path tangent becomes its Z axis. The
+/// profile is rotated by `twist` radians in total around the path, and scaled
+/// from `scale_start` to `scale_end`, both proportionally to the distance
+/// travelled along it. Counter-clockwise profiles give outwards-facing faces.
+pub fn sweep(
+    profile: &HalfEdgeMesh,
+    path: &HalfEdgeMesh,
+    twist: f32,
+    scale_start: f32,
+    scale_end: f32,
+    align: SweepAlign,
+) -> Result<HalfEdgeMesh> {
+    let read_polyline = |mesh: &HalfEdgeMesh| -> Result<(Vec<Vec3>, bool)> {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let bag = mesh.resolve_halfedge_selection_full(&SelectionExpression::All)?;
+        let (chain, is_closed) = sort_bag_of_edges(&conn, &bag)?;
+        Ok((chain.iter().map(|v| positions[*v]).collect(), is_closed))
+    };
+    let (profile_points, profile_closed) = read_polyline(profile)?;
+    let (path_points, path_closed) = read_polyline(path)?;
+    if profile_points.len() < 2 || path_points.len() < 2 {
+        bail!("Both the profile and the path need at least two points");
+    }
+
+    let n = path_points.len();
+    let last = n - 1;
+    let tangents = (0..n)
+        .map(|i| {
+            let incoming = (path_closed || i > 0)
+                .then(|| (path_points[i] - path_points[(i + n - 1) % n]).normalize_or_zero());
+            let outgoing = (path_closed || i < last)
+                .then(|| (path_points[(i + 1) % n] - path_points[i]).normalize_or_zero());
+            match (incoming, outgoing) {
+                (Some(a), Some(b)) => (a + b).try_normalize().unwrap_or(b),
+                (Some(a), None) => a,
+                (None, Some(b)) => b,
+                (None, None) => Vec3::Z,
+            }
+        })
+        .collect_vec();
+    let normals = match align {
+        SweepAlign::Frames => rotation_minimizing_normals(&path_points, &tangents, path_closed),
+        SweepAlign::Up => {
+            let mut previous = Vec3::Y;
+            tangents
+                .iter()
+                .map(|t| {
+                    // Vertical segments keep the last level orientation
+                    previous = Vec3::Y
+                        .reject_from_normalized(*t)
+                        .try_normalize()
+                        .or_else(|| previous.reject_from_normalized(*t).try_normalize())
+                        .unwrap_or_else(|| t.any_orthonormal_vector());
+                    previous
+                })
+                .collect_vec()
+        }
+        SweepAlign::Fixed => vec![Vec3::Y; n],
+    };
+
+    // Twist and scale follow the length along the path, so they don't
+    // depend on how densely it's sampled.
+    let mut distances = vec![0.0];
+    for (a, b) in path_points.iter().tuple_windows() {
+        distances.push(distances.last().unwrap() + a.distance(*b));
+    }
+    let total = if path_closed {
+        distances[last] + path_points[last].distance(path_points[0])
+    } else {
+        distances[last]
+    };
+
+    let mut positions = vec![];
+    for i in 0..n {
+        let t = if total > 0.0 {
+            distances[i] / total
+        } else {
+            0.0
+        };
+        let scale = scale_start + (scale_end - scale_start) * t;
+        let rotation = Quat::from_rotation_z(twist * t);
+        let (right, up, forward) = match align {
+            SweepAlign::Fixed => (Vec3::X, Vec3::Y, Vec3::Z),
+            _ => (normals[i].cross(tangents[i]), normals[i], tangents[i]),
+        };
+        for p in profile_points.iter_cpy() {
+            let p = rotation * p * scale;
+            positions.push(path_points[i] + right * p.x + up * p.y + forward * p.z);
+        }
+    }
+
+    let len = profile_points.len();
+    let mut polygons = vec![];
+    let path_pairs = (0..n).branch(
+        path_closed,
+        |x| x.circular_tuple_windows(),
+        |x| x.tuple_windows(),
+    );
+    for (s, t) in path_pairs {
+        let profile_pairs = (0..len).branch(
+            profile_closed,
+            |x| x.circular_tuple_windows(),
+            |x| x.tuple_windows(),
+        );
+        for (i, j) in profile_pairs {
+            polygons.push([s * len + i, s * len + j, t * len + j, t * len + i]);
+        }
+    }
+
+    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+}
+
+/// Returns `count` points evenly spaced along the polyline through `points`.
+/// Closed polylines are walked back to their first point, which is not
+/// repeated at the end.
+fn resample_polyline(points: &[Vec3], closed: bool, count: usize) -> Vec<Vec3> {
+    let mut vertices = points.to_vec();
+    if closed {
+        vertices.push(points[0]);
+    }
+    let mut distances = vec![0.0];
+    for (a, b) in vertices.iter().tuple_windows() {
+        distances.push(distances.last().unwrap() + a.distance(*b));
+    }
+    let total = distances[distances.len() - 1];
+    let intervals = if closed { count } else { count - 1 };
+    (0..count)
+        .map(|i| {
+            let target = total * i as f32 / intervals.max(1) as f32;
+            let k = distances
+                .partition_point(|d| *d <= target)
+                .clamp(1, vertices.len() - 1)
+                - 1;
+            let length = distances[k + 1] - distances[k];
+            let t = if length > 0.0 {
+                (target - distances[k]) / length
+            } else {
+                0.0
+            };
+            vertices[k].lerp(vertices[k + 1], t)
+        })
+        .collect()
+}
+
+/// Skins a surface over a sequence of `profiles`, connecting corresponding
+/// points of consecutive profiles. Profiles with a different number of points
+/// are resampled to match the one with most points.
+///
+/// When `close_u` is set, each profile is treated as a closed loop. Closed
+/// profiles are rotated so their starting points line up, avoiding twists.
+/// When `close_v` is set, the last profile connects back to the first one.
+/// With a `smooth` value greater than zero, that many sections are
+/// interpolated between each pair of profiles, along Catmull-Rom curves
+/// through the corresponding points.
+pub fn loft(
+    profiles: &[&HalfEdgeMesh],
+    close_u: bool,
+    close_v: bool,
+    smooth: u32,
+) -> Result<HalfEdgeMesh> {
+    if profiles.len() < 2 {
+        bail!("A loft needs at least two profiles");
+    }
+    if close_v && profiles.len() < 3 {
+        bail!("A closed loft needs at least three profiles");
+    }
+    let mut sections = vec![];
+    for profile in profiles {
+        let conn = profile.read_connectivity();
+        let positions = profile.read_positions();
+        let bag = profile.resolve_halfedge_selection_full(&SelectionExpression::All)?;
+        let (chain, _) = sort_bag_of_edges(&conn, &bag)?;
+        sections.push(chain.iter().map(|v| positions[*v]).collect_vec());
+    }
+    if sections.iter().any(|s| s.len() < 2) {
+        bail!("The profiles of a loft need at least two points");
+    }
+    let count = sections.iter().map(|s| s.len()).max().unwrap_or(0);
+    for section in sections.iter_mut() {
+        if section.len() != count {
+            *section = resample_polyline(section, close_u, count);
+        }
+    }
+
+    if close_u {
+        // Pick the rotation of each loop closest to the previous one, once
+        // both are centered.
+        for s in 1..sections.len() {
+            let centroid = |points: &[Vec3]| points.iter_cpy().sum::<Vec3>() / points.len() as f32;
+            let (prev_center, center) = (centroid(&sections[s - 1]), centroid(&sections[s]));
+            let best = (0..count)
+                .min_by_key(|shift| {
+                    FloatOrd(
+                        (0..count)
+                            .map(|i| {
+                                (sections[s][(i + shift) % count] - center)
+                                    .distance_squared(sections[s - 1][i] - prev_center)
+                            })
+                            .sum::<f32>(),
+                    )
+                })
+                .unwrap_or(0);
+            sections[s].rotate_left(best);
+        }
+    }
+
+    // Interpolate each column of corresponding points across the sections
+    let columns = (0..count)
+        .map(|i| {
+            let control_points = sections.iter().map(|s| s[i]).collect_vec();
+            primitives::CurvePrimitive::sample(
+                &control_points,
+                primitives::CurveKind::CatmullRom,
+                smooth + 1,
+                close_v,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let rows = columns[0].len();
+    let positions = (0..rows)
+        .flat_map(|r| columns.iter().map(move |column| column[r]))
+        .collect_vec();
+
+    let mut polygons = vec![];
+    let row_pairs = (0..rows).branch(
+        close_v,
+        |x| x.circular_tuple_windows(),
+        |x| x.tuple_windows(),
+    );
+    for (r, s) in row_pairs {
+        let column_pairs = (0..count).branch(
+            close_u,
+            |x| x.circular_tuple_windows(),
+            |x| x.tuple_windows(),
+        );
+        for (i, j) in column_pairs {
+            polygons.push([r * count + i, r * count + j, s * count + j, s * count + i]);
+        }
+    }
+
+    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+}
+
+/// Sweeps the `profile` polyline around the axis through `axis_origin` with
+/// direction `axis_dir`, by an `angle` in radians, making a surface of
+/// revolution with `segments` steps around the axis. Profiles going along the
+/// axis direction get outwards-facing normals.
+///
+/// Vertices of the profile lying on the axis are shared by all the steps.
+/// When the revolution is not complete and `cap` is set, both ends are closed
+/// with a face following the profile.
+pub fn revolve(
+    profile: &HalfEdgeMesh,
+    axis_origin: Vec3,
+    axis_dir: Vec3,
+    angle: f32,
+    segments: usize,
+    cap: bool,
+) -> Result<HalfEdgeMesh> {
+    let axis = axis_dir
+        .try_normalize()
+        .ok_or_else(|| anyhow!("The revolve axis can't be zero"))?;
+    if segments == 0 {
+        bail!("A revolve needs at least one segment");
+    }
+    let conn = profile.read_connectivity();
+    let profile_pos = profile.read_positions();
+    let bag = profile.resolve_halfedge_selection_full(&SelectionExpression::All)?;
+    let (chain, is_closed) = sort_bag_of_edges(&conn, &bag)?;
+    let points = chain.iter().map(|v| profile_pos[*v]).collect_vec();
+
+    let full_turn = angle.abs() >= 2.0 * PI - 1e-4;
+    if full_turn && segments < 3 {
+        bail!("A full revolve needs at least three segments");
+    }
+    let num_steps = if full_turn { segments } else { segments + 1 };
+    let on_axis = |p: Vec3| {
+        let d = p - axis_origin;
+        (d - axis * d.dot(axis)).length() < 1e-5
+    };
+
+    // The index of each profile point at each step. Points on the axis are
+    // only added once.
+    let mut positions = vec![];
+    let mut steps = vec![vec![0; points.len()]; num_steps];
+    for (i, p) in points.iter_cpy().enumerate() {
+        if on_axis(p) {
+            for step in steps.iter_mut() {
+                step[i] = positions.len();
+            }
+            positions.push(p);
+            continue;
+        }
+        for (s, step) in steps.iter_mut().enumerate() {
+            let rotation = Quat::from_axis_angle(axis, angle * s as f32 / segments as f32);
+            step[i] = positions.len();
+            positions.push(axis_origin + rotation * (p - axis_origin));
+        }
+    }
+
+    let mut polygons: Vec<Vec<usize>> = vec![];
+    let step_pairs = (0..num_steps).branch(
+        full_turn,
+        |x| x.circular_tuple_windows(),
+        |x| x.tuple_windows(),
+    );
+    for (s, t) in step_pairs {
+        let segment_pairs = (0..points.len()).branch(
+            is_closed,
+            |x| x.circular_tuple_windows(),
+            |x| x.tuple_windows(),
+        );
+        for (i, j) in segment_pairs {
+            // Quads touching the axis collapse into triangles
+            let polygon = [steps[s][i], steps[t][i], steps[t][j], steps[s][j]]
+                .into_iter()
+                .dedup()
+                .collect_vec();
+            if polygon.len() >= 3 {
+                polygons.push(polygon);
+            }
+        }
+    }
+    if cap && !full_turn && points.len() >= 3 {
+        polygons.push(steps[0].clone());
+        polygons.push(steps[num_steps - 1].iter().rev().copied().collect());
+    }
+    // Revolving the other way around flips the faces
+    if angle < 0.0 {
+        for polygon in polygons.iter_mut() {
+            polygon.reverse();
+        }
     }
 
-    Ok(result)
+    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
 }
 
-pub fn extrude_along_curve(
-    backbone: &HalfEdgeMesh,
-    cross_section: &HalfEdgeMesh,
-    flip: usize,
-) -> Result<HalfEdgeMesh> {
-    let backbone_conn = backbone.read_connectivity();
-    let backbone_pos = backbone.read_positions();
-    let backbone_size = backbone
-        .channels
-        .read_channel_by_name::<VertexId, f32>("size");
-    let backbone_nrm = backbone
-        .channels
-        .read_channel_by_name::<VertexId, Vec3>("normal");
-    let backbone_tgt = backbone
-        .channels
-        .read_channel_by_name::<VertexId, Vec3>("tangent");
+/// Builds a single chain link, as an elongated torus with its centerline on
+/// the YZ plane and centered at the origin. The link is `length` units long
+/// on the Z axis, measured from the outside, and made of a wire with the given
+/// `wire_radius`.
+fn chain_link(length: f32, wire_radius: f32) -> Result<HalfEdgeMesh> {
+    const RING_SEGMENTS: usize = 8;
+    const ARC_SEGMENTS: usize = 8;
+
+    let end_radius = 2.2 * wire_radius;
+    let half_straight = 0.5 * length - end_radius - wire_radius;
+    if half_straight <= 0.0 {
+        bail!("Chain links are too short for their wire radius");
+    }
 
-    // Sort the vertices of the cross-section
-    let csect_pos = cross_section.read_positions();
-    let csect_conn = cross_section.read_connectivity();
-    let bag = cross_section.resolve_halfedge_selection_full(&SelectionExpression::All)?;
-    let (csect_chain, is_closed) = sort_bag_of_edges(&csect_conn, &bag)?;
+    // The centerline is made of two half circles, joined by straight segments
+    let mut centerline = vec![];
+    for (side, center_z) in [(1.0, half_straight), (-1.0, -half_straight)] {
+        for i in 0..=ARC_SEGMENTS {
+            let angle = PI * i as f32 / ARC_SEGMENTS as f32;
+            centerline.push(Vec3::new(
+                0.0,
+                side * end_radius * angle.cos(),
+                center_z + side * end_radius * angle.sin(),
+            ));
+        }
+    }
 
+    let n = centerline.len();
     let mut positions = vec![];
+    for i in 0..n {
+        let tangent = (centerline[(i + 1) % n] - centerline[(i + n - 1) % n]).normalize();
+        let binormal = tangent.cross(Vec3::X);
+        for j in 0..RING_SEGMENTS {
+            let angle = 2.0 * PI * j as f32 / RING_SEGMENTS as f32;
+            positions.push(
+                centerline[i] + (Vec3::X * angle.cos() + binormal * angle.sin()) * wire_radius,
+            );
+        }
+    }
 
-    for (v, _) in backbone_conn.iter_vertices() {
-        let scale = if let Ok(ref size) = backbone_size {
-            Vec3::splat(size[v])
-        } else {
-            Vec3::ONE
-        };
+    let mut polygons = vec![];
+    for i in 0..n {
+        let (r0, r1) = (i * RING_SEGMENTS, ((i + 1) % n) * RING_SEGMENTS);
+        for j in 0..RING_SEGMENTS {
+            let k = (j + 1) % RING_SEGMENTS;
+            polygons.push([r0 + j, r0 + k, r1 + k, r1 + j]);
+        }
+    }
 
-        let rotate = if let (Ok(normal_ch), Ok(tangent_ch)) =
-            (backbone_nrm.as_ref(), backbone_tgt.as_ref())
-        {
-            let normal = normal_ch[v];
-            let tangent = tangent_ch[v];
-            let cotangent = normal.cross(tangent);
-            let (_, rotate, _) = glam::Affine3A::from_cols(
-                cotangent.into(),
-                normal.into(),
-                tangent.into(),
-                glam::Vec3A::ZERO,
-            )
-            .to_scale_rotation_translation();
-            rotate.to_euler(glam::EulerRot::XYZ).into()
-        } else {
-            Vec3::ZERO
-        };
+    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+}
 
-        for vc in csect_chain.iter_cpy() {
-            let pos = csect_pos[vc];
-            let rot = Quat::from_euler(glam::EulerRot::XYZ, rotate.x, rotate.y, rotate.z);
-            positions.push(rot * (pos * scale) + backbone_pos[v]);
-        }
+/// Places chain links along the given `curve`, spaced every `link_length`
+/// units of arc length. Each link is rotated 90 degrees with respect to the
+/// previous one, plus an additional `twist_per_link` (in radians).
+///
+/// Links are copies of `link_mesh`, which should be oriented along the Z axis.
+/// When no link mesh is given, links are generated with the right size so
+/// that consecutive links interlock.
+pub fn chain(
+    curve: &HalfEdgeMesh,
+    link_mesh: Option<&HalfEdgeMesh>,
+    link_length: f32,
+    twist_per_link: f32,
+) -> Result<HalfEdgeMesh> {
+    if link_length <= 0.0 {
+        bail!("Link length must be positive");
     }
 
-    let mut polygons: Vec<[u32; 4]> = vec![];
-    let num_segments = backbone_conn.num_vertices();
-    let segment_length = csect_conn.num_vertices();
+    let points = {
+        let conn = curve.read_connectivity();
+        let positions = curve.read_positions();
+        let bag = curve.resolve_halfedge_selection_full(&SelectionExpression::All)?;
+        let (vertices, is_closed) = sort_bag_of_edges(&conn, &bag)?;
+        let mut points = vertices.iter_cpy().map(|v| positions[v]).collect_vec();
+        if is_closed && !points.is_empty() {
+            points.push(points[0]);
+        }
+        points
+    };
 
-    for seg in 0..num_segments - 1 {
-        let offset = seg * segment_length;
-        for (i, j) in (0..segment_length as u32).branch(
-            is_closed,
-            |x| x.circular_tuple_windows(),
-            |x| x.tuple_windows(),
-        ) {
-            let polygon = if flip % 2 == 0 {
-                [i, j, j + segment_length as u32, i + segment_length as u32]
-            } else {
-                [j, i, i + segment_length as u32, j + segment_length as u32]
-            }
-            .map(|i| i + offset as u32);
-            polygons.push(polygon);
+    // Sample the curve at regular arc length intervals, storing the position
+    // and tangent at each sample.
+    let mut samples = vec![];
+    let mut next_sample = 0.0;
+    let mut travelled = 0.0;
+    for (a, b) in points.iter_cpy().tuple_windows() {
+        let segment_length = a.distance(b);
+        if segment_length <= f32::EPSILON {
+            continue;
         }
+        let tangent = (b - a) / segment_length;
+        while next_sample <= travelled + segment_length {
+            let t = (next_sample - travelled) / segment_length;
+            samples.push((a.lerp(b, t), tangent));
+            next_sample += link_length;
+        }
+        travelled += segment_length;
     }
 
-    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+    // The interlocking distance between two links is the length of the hole
+    // inside the link, so the full link is two wire diameters longer.
+    let default_link;
+    let link_mesh = match link_mesh {
+        Some(link_mesh) => link_mesh,
+        None => {
+            let wire_radius = 0.15 * link_length;
+            default_link = chain_link(link_length + 4.0 * wire_radius, wire_radius)?;
+            &default_link
+        }
+    };
+
+    let mut result = HalfEdgeMesh::new();
+    let mut normal = Vec3::Y;
+    for (i, (position, tangent)) in samples.iter_cpy().enumerate() {
+        // Parallel transport the normal, so the links don't spin along the curve
+        normal = normal
+            .reject_from_normalized(tangent)
+            .try_normalize()
+            .unwrap_or_else(|| tangent.any_orthonormal_vector());
+        let roll = i as f32 * (0.5 * PI + twist_per_link);
+        let x_axis = Quat::from_axis_angle(tangent, roll) * normal.cross(tangent);
+        let rotate = Quat::from_mat3(&glam::Mat3::from_cols(
+            x_axis,
+            tangent.cross(x_axis),
+            tangent,
+        ));
+
+        let link = link_mesh.clone();
+        transform(&link, position, rotate, Vec3::ONE)?;
+        result.merge_with(&link);
+    }
+
+    Ok(result)
 }
 
 pub enum ResampleCurveDensity {
@@ -1899,7 +4413,7 @@ pub fn edit_geometry(
     geometry_type: ChannelKeyType,
     selection: SelectionExpression,
     translate: Vec3,
-    rotate: Vec3,
+    rotate: Quat,
     scale: Vec3,
 ) -> Result<()> {
     let conn = mesh.read_connectivity();
@@ -1929,11 +4443,7 @@ pub fn edit_geometry(
         / vertices.len() as f32;
 
     let transform_matrix = Mat4::from_translation(centroid)
-        * Mat4::from_scale_rotation_translation(
-            scale,
-            Quat::from_euler(EulerRot::XYZ, rotate.x, rotate.y, rotate.z),
-            translate,
-        )
+        * Mat4::from_scale_rotation_translation(scale, rotate, translate)
         * Mat4::from_translation(-centroid);
 
     for v in vertices {
@@ -1946,7 +4456,7 @@ pub fn edit_geometry(
 #[blackjack_macros::blackjack_lua_module]
 pub mod lua_fns {
 
-    use crate::lua_engine::lua_stdlib::LVec3;
+    use crate::lua_engine::lua_stdlib::{LMat4, LQuat, LVec3};
     use halfedge::compact_mesh::CompactMesh;
 
     use super::*;
@@ -1972,6 +4482,40 @@ pub mod lua_fns {
         Ok(())
     }
 
+    /// Merges the vertices in the `selection` of `mesh` closer than
+    /// `distance` to each other, removing the edges and faces that collapse.
+    /// Useful to weld the seams of imported or concatenated meshes.
+    #[lua(under = "Ops")]
+    pub fn merge_by_distance(
+        mesh: &HalfEdgeMesh,
+        distance: f32,
+        selection: SelectionExpression,
+    ) -> Result<HalfEdgeMesh> {
+        super::merge_by_distance(mesh, distance, &selection)
+    }
+
+    /// Replaces each vertex in the `vertices` selection of `mesh` with a face,
+    /// cutting off its corner at an `amount` distance along the incident
+    /// edges. Vertices in the boundary of the mesh are not modified.
+    #[lua(under = "Ops")]
+    pub fn bevel_vertices(
+        mesh: &HalfEdgeMesh,
+        vertices: SelectionExpression,
+        amount: f32,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        let verts = result.resolve_vertex_selection_full(&vertices)?;
+        for v in verts {
+            super::bevel_vertex(
+                &mut result.write_connectivity(),
+                &mut result.write_positions(),
+                v,
+                amount,
+            )?;
+        }
+        Ok(result)
+    }
+
     /// Bevels the given `edges`, replacing each edge with a face and indenting
     /// it by a given `amount` distance.
     #[lua(under = "Ops")]
@@ -1985,6 +4529,57 @@ pub mod lua_fns {
         )
     }
 
+    /// Bevels the given `edges` of `mesh` by an `amount` distance, replacing
+    /// each edge with a strip of `segments` faces. The `profile` controls the
+    /// shape of the strip: 0.5 is a flat chamfer, around 0.7 is a circular
+    /// arc, and 1 keeps the original sharp edge.
+    #[lua(under = "Ops")]
+    pub fn bevel_edges(
+        mesh: &HalfEdgeMesh,
+        edges: SelectionExpression,
+        amount: f32,
+        segments: usize,
+        profile: f32,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        let edges = result.resolve_halfedge_selection_full(&edges)?;
+        super::bevel_edges_with_profile(
+            &mut result.write_connectivity(),
+            &mut result.write_positions(),
+            &edges,
+            amount,
+            segments,
+            BevelProfile::Superellipse(profile),
+        )?;
+        Ok(result)
+    }
+
+    /// Same as `bevel_edges`, but the shape of the strips follows the
+    /// `profile` ramp. As the strip goes from one side of the bevel to the
+    /// other, the ramp gives how far it has moved away from the first side.
+    /// A linear ramp gives a flat chamfer, and constant interpolation gives
+    /// steps.
+    #[lua(under = "Ops")]
+    pub fn bevel_edges_with_profile(
+        mesh: &HalfEdgeMesh,
+        edges: SelectionExpression,
+        amount: f32,
+        segments: usize,
+        profile: &crate::graph::ramp::Ramp,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        let edges = result.resolve_halfedge_selection_full(&edges)?;
+        super::bevel_edges_with_profile(
+            &mut result.write_connectivity(),
+            &mut result.write_positions(),
+            &edges,
+            amount,
+            segments,
+            BevelProfile::Ramp(profile),
+        )?;
+        Ok(result)
+    }
+
     /// Extrudes the given `faces` by a given `amount` distance.
     #[lua(under = "Ops")]
     pub fn extrude(faces: SelectionExpression, amount: f32, mesh: &HalfEdgeMesh) -> Result<()> {
@@ -1998,6 +4593,58 @@ pub mod lua_fns {
         Ok(())
     }
 
+    /// Returns a copy of `mesh` where the selected `faces` have been extruded
+    /// by `amount` in `segments` steps. At a fraction `t` of the height, the
+    /// walls of the extrusion are inset by `width` times the value of the
+    /// `profile` ramp at `t`.
+    #[lua(under = "Ops")]
+    pub fn extrude_with_profile(
+        mesh: &HalfEdgeMesh,
+        faces: SelectionExpression,
+        amount: f32,
+        segments: usize,
+        width: f32,
+        profile: &crate::graph::ramp::Ramp,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        let faces = result.resolve_face_selection_full(&faces)?;
+        super::extrude_faces_with_profile(
+            &mut result.write_connectivity(),
+            &mut result.write_positions(),
+            &faces,
+            amount,
+            segments,
+            width,
+            profile,
+        )?;
+        Ok(result)
+    }
+
+    /// Returns a copy of `mesh` where the selected `faces` have been inset by
+    /// `thickness` units and pushed `depth` units along their normal. When
+    /// `individual` is true, every face gets its own ring of faces. Otherwise,
+    /// the ring goes around the boundary of each connected region.
+    #[lua(under = "Ops")]
+    pub fn inset_faces(
+        mesh: &HalfEdgeMesh,
+        faces: SelectionExpression,
+        thickness: f32,
+        depth: f32,
+        individual: bool,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        let faces = result.resolve_face_selection_full(&faces)?;
+        super::inset_faces(
+            &mut result.write_connectivity(),
+            &mut result.write_positions(),
+            &faces,
+            thickness,
+            depth,
+            individual,
+        )?;
+        Ok(result)
+    }
+
     /// Modifies the given mesh `a` by merging `b` into it. The `b` mesh remains
     /// unmodified.
     #[lua(under = "Ops")]
@@ -2017,10 +4664,114 @@ pub mod lua_fns {
     ) -> Result<HalfEdgeMesh> {
         let new_mesh = CompactMesh::<false>::from_halfedge(mesh)?;
         Ok(new_mesh
-            .subdivide_multi(iterations, catmull_clark)
+            .subdivide_multi(iterations, catmull_clark, false)
+            .to_halfedge())
+    }
+
+    /// Subdivides the given mesh using Catmull Clark, applying as many
+    /// `iterations` as given. Unlike `subdivide`, vertex, halfedge and face
+    /// channels like UVs or colors are interpolated into the new mesh. When
+    /// `preserve_boundaries` is true, boundary vertices stay in place,
+    /// otherwise the boundaries are smoothed too, keeping only the corners.
+    #[lua(under = "Ops")]
+    pub fn subdivide_smooth(
+        mesh: &HalfEdgeMesh,
+        iterations: usize,
+        preserve_boundaries: bool,
+    ) -> Result<HalfEdgeMesh> {
+        if iterations == 0 {
+            return Ok(mesh.clone());
+        }
+        let new_mesh = CompactMesh::<false>::from_halfedge_with_attributes(mesh)?;
+        Ok(new_mesh
+            .subdivide_multi(iterations, true, !preserve_boundaries)
             .to_halfedge())
     }
 
+    /// Splits all the faces of `mesh` with more than three vertices into
+    /// triangles. The `mode` can be "Auto" (fan triangulation for convex
+    /// faces, ear clipping for concave ones), "Fan" or "EarClip". Channel
+    /// values like UVs or materials are kept.
+    #[lua(under = "Ops")]
+    pub fn triangulate(mesh: &HalfEdgeMesh, mode: String) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::triangulate(&result, TriangulateMode::from_name(&mode)?)?;
+        Ok(result)
+    }
+
+    /// Closes the holes of `mesh`, capping its boundary loops with a new face.
+    /// Holes with more than `max_hole_edges` edges are left open, unless it's
+    /// zero. When `triangulate` is true, the caps are split into triangles.
+    #[lua(under = "Ops")]
+    pub fn fill_holes(
+        mesh: &HalfEdgeMesh,
+        max_hole_edges: usize,
+        triangulate: bool,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::fill_holes(&result, max_hole_edges, triangulate)?;
+        Ok(result)
+    }
+
+    /// Merges pairs of adjacent triangles of `mesh` back into quads, when the
+    /// angle between their normals is at most `angle_threshold` (in radians)
+    /// and the quad they form is convex. Useful to clean up triangulated
+    /// meshes, like many imported OBJ files.
+    #[lua(under = "Ops")]
+    pub fn tris_to_quads(mesh: &HalfEdgeMesh, angle_threshold: f32) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::tris_to_quads(&result, angle_threshold)?;
+        Ok(result)
+    }
+
+    /// Returns a copy of `mesh` where the elements in `selection` have been
+    /// dissolved, merging the faces around them into n-gons. The `kind` is
+    /// either "Edges" or "Vertices", and says what the selection refers to.
+    #[lua(under = "Ops")]
+    pub fn dissolve(
+        mesh: &HalfEdgeMesh,
+        selection: SelectionExpression,
+        kind: String,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::dissolve(&result, &selection, DissolveKind::from_name(&kind)?)?;
+        Ok(result)
+    }
+
+    /// Returns a copy of `mesh` where neighboring faces that are flat within
+    /// `angle_threshold` (in radians) have been merged into n-gons, and
+    /// vertices in the middle of straight edges removed. Useful to clean up
+    /// triangulated imports and boolean results.
+    #[lua(under = "Ops")]
+    pub fn limited_dissolve(mesh: &HalfEdgeMesh, angle_threshold: f32) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::limited_dissolve(&result, angle_threshold)?;
+        Ok(result)
+    }
+
+    /// Smooths the surface of `mesh`, relaxing its vertices `iterations`
+    /// times by the given `factor`. The `method` can be "Laplacian", which
+    /// shrinks the mesh, or "Taubin", which keeps its volume. When
+    /// `pin_boundary` is true, boundary vertices stay in place.
+    #[lua(under = "Ops")]
+    pub fn smooth(
+        mesh: &HalfEdgeMesh,
+        iterations: usize,
+        factor: f32,
+        method: String,
+        pin_boundary: bool,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::smooth(
+            &result,
+            iterations,
+            factor,
+            SmoothMethod::from_name(&method)?,
+            pin_boundary,
+        )?;
+        Ok(result)
+    }
+
     /// Computes the smooth normals channel for the given `mesh` and sets the
     /// mesh export settings to use smooth normals.
     #[lua(under = "Ops")]
@@ -2029,6 +4780,15 @@ pub mod lua_fns {
         Ok(())
     }
 
+    /// Computes the auto smooth normals channel for the given `mesh` and sets
+    /// the mesh export settings to use them. Edges where faces meet at an
+    /// angle larger than `angle` radians are shaded sharp.
+    #[lua(under = "Ops")]
+    pub fn set_auto_smooth_normals(mesh: &mut HalfEdgeMesh, angle: f32) -> Result<()> {
+        super::set_auto_smooth_normals(mesh, angle)?;
+        Ok(())
+    }
+
     /// Computes the flat normals channel for the given `mesh` and sets the
     /// mesh export settings to use flat normals.
     #[lua(under = "Ops")]
@@ -2124,18 +4884,173 @@ pub mod lua_fns {
         super::make_quad(&mut mesh.write_connectivity(), &[a, b, c, d])
     }
 
+    /// Places chain links along `curve`, every `link_length` units. Each
+    /// link is rotated 90 degrees from the previous one, plus an extra
+    /// `twist_per_link` in radians. Uses copies of `link_mesh` as links when
+    /// given, otherwise interlocking links are generated.
+    #[lua(under = "Ops")]
+    pub fn chain(
+        curve: &HalfEdgeMesh,
+        link_mesh: Option<mlua::AnyUserData>,
+        link_length: f32,
+        twist_per_link: f32,
+    ) -> Result<HalfEdgeMesh> {
+        let link_mesh = link_mesh
+            .map(|link_mesh| link_mesh.borrow::<HalfEdgeMesh>())
+            .transpose()?;
+        super::chain(curve, link_mesh.as_deref(), link_length, twist_per_link)
+    }
+
     /// Applies a transformation to the `position` channel of this mesh, by
-    /// translating, rotating and scaling the mesh with given parameters.
+    /// translating, rotating and scaling the mesh with given parameters. The
+    /// `rotate` parameter can be a `Quat`, or a vector of XYZ euler angles.
     #[lua(under = "Ops")]
     pub fn transform(
         mesh: &mut HalfEdgeMesh,
         translate: LVec3,
-        rotate: LVec3,
+        rotate: LQuat,
         scale: LVec3,
     ) -> Result<()> {
         super::transform(mesh, translate.0, rotate.0, scale.0)
     }
 
+    /// Returns a copy of `mesh` with an anchor called `name` at `position`,
+    /// oriented by `rotation`. Anchors follow the mesh through transforms and
+    /// merges, and can be used to snap other meshes to it. An existing anchor
+    /// with the same name is replaced.
+    #[lua(under = "Ops")]
+    pub fn add_anchor(
+        mesh: &HalfEdgeMesh,
+        name: String,
+        position: LVec3,
+        rotation: LQuat,
+    ) -> Result<HalfEdgeMesh> {
+        if name.is_empty() {
+            bail!("Anchor names can't be empty");
+        }
+        let result = mesh.clone();
+        result.write_anchors().insert(
+            name,
+            Anchor {
+                position: position.0,
+                rotation: rotation.0,
+            },
+        );
+        Ok(result)
+    }
+
+    /// Returns `mesh_a` merged with a copy of `mesh_b`, placed so its
+    /// `anchor_b` anchor matches the position and orientation of the
+    /// `anchor_a` anchor of `mesh_a`. Both anchors are removed from the result.
+    /// When `weld_distance` is given, boundary vertices closer than that are
+    /// merged, stitching together the open borders of both meshes.
+    #[lua(under = "Ops")]
+    pub fn attach(
+        mesh_a: &HalfEdgeMesh,
+        anchor_a: String,
+        mesh_b: &HalfEdgeMesh,
+        anchor_b: String,
+        weld_distance: Option<f32>,
+    ) -> Result<HalfEdgeMesh> {
+        super::attach(mesh_a, &anchor_a, mesh_b, &anchor_b, weld_distance)
+    }
+
+    /// Returns `mesh` together with its reflection across the plane through
+    /// `plane_origin` with normal `plane_normal`. Vertices within
+    /// `merge_distance` of the plane are welded. Only positions are kept.
+    #[lua(under = "Ops")]
+    pub fn mirror(
+        mesh: &HalfEdgeMesh,
+        plane_origin: LVec3,
+        plane_normal: LVec3,
+        merge_distance: f32,
+    ) -> Result<HalfEdgeMesh> {
+        super::mirror(mesh, plane_origin.0, plane_normal.0, merge_distance)
+    }
+
+    /// Extracts the faces of `mesh` walkable with a slope of at most
+    /// `max_slope_angle` radians, merging coplanar ones, and dropping regions
+    /// smaller than `min_area`.
+    #[lua(under = "Ops")]
+    pub fn extract_walkable(
+        mesh: &HalfEdgeMesh,
+        max_slope_angle: f32,
+        min_area: f32,
+    ) -> Result<HalfEdgeMesh> {
+        super::extract_walkable(mesh, max_slope_angle, min_area)
+    }
+
+    /// Sweeps the `profile` polyline around the axis through `axis_origin`
+    /// with direction `axis_dir`, by an `angle` in radians, in a number of
+    /// `segments`. When the revolution is partial and `cap` is set, both ends
+    /// get closed.
+    #[lua(under = "Ops")]
+    pub fn revolve(
+        profile: &HalfEdgeMesh,
+        axis_origin: LVec3,
+        axis_dir: LVec3,
+        angle: f32,
+        segments: usize,
+        cap: bool,
+    ) -> Result<HalfEdgeMesh> {
+        super::revolve(profile, axis_origin.0, axis_dir.0, angle, segments, cap)
+    }
+
+    /// Extrudes the `profile` polyline along the `path` polyline, twisting
+    /// it by `twist` radians and scaling it from `scale_start` to `scale_end`
+    /// along the way. The `align_mode` is one of "Frames", "Up" or "Fixed".
+    #[lua(under = "Ops")]
+    pub fn sweep(
+        profile: &HalfEdgeMesh,
+        path: &HalfEdgeMesh,
+        twist: f32,
+        scale_start: f32,
+        scale_end: f32,
+        align_mode: String,
+    ) -> Result<HalfEdgeMesh> {
+        super::sweep(
+            profile,
+            path,
+            twist,
+            scale_start,
+            scale_end,
+            SweepAlign::from_name(&align_mode)?,
+        )
+    }
+
+    /// Skins a surface over the list of polyline `profiles`. `close_u`
+    /// treats each profile as a closed loop, and `close_v` connects the last
+    /// profile back to the first. `smooth` is the number of sections
+    /// interpolated between each pair of profiles.
+    #[lua(under = "Ops")]
+    pub fn loft(
+        profiles: mlua::Table,
+        close_u: bool,
+        close_v: bool,
+        smooth: u32,
+    ) -> Result<HalfEdgeMesh> {
+        let profiles = profiles
+            .sequence_values::<mlua::AnyUserData>()
+            .collect::<Result<Vec<_>, _>>()?;
+        let profiles = profiles
+            .iter()
+            .map(|profile| profile.borrow::<HalfEdgeMesh>())
+            .collect::<Result<Vec<_>, _>>()?;
+        super::loft(
+            &profiles.iter().map(|profile| &**profile).collect_vec(),
+            close_u,
+            close_v,
+            smooth,
+        )
+    }
+
+    /// Applies the given affine transformation `matrix` to the `position`
+    /// channel of this mesh.
+    #[lua(under = "Ops")]
+    pub fn transform_matrix(mesh: &mut HalfEdgeMesh, matrix: LMat4) -> Result<()> {
+        super::transform_matrix(mesh, matrix.0)
+    }
+
     /// Creates a group named `group_name` in `mesh` for the given mesh element
     /// `key_type`. This will put all the elements in `selection` inside this
     /// group.
@@ -2156,8 +5071,9 @@ pub mod lua_fns {
     /// Sets the `material` channel for all faces in `selection` to use the
     /// given `material_index`.
     ///
-    /// Material indices are currently not interpreted by blackjack, but game
-    /// engine integrations may use this channel in different ways.
+    /// Material indices refer to the materials defined in the project, which
+    /// can be looked up by name with `Materials.index_of`. Game engine
+    /// integrations may use this channel in different ways.
     #[lua(under = "Ops")]
     pub fn set_material(
         mesh: &mut HalfEdgeMesh,
@@ -2167,6 +5083,27 @@ pub mod lua_fns {
         super::set_material(mesh, &selection, material_index)
     }
 
+    /// Adds the faces in `selection` to the "smooth" face group of `mesh`.
+    /// When the mesh uses the "mixed" shading mode, only the faces in this
+    /// group are shaded smooth.
+    #[lua(under = "Ops")]
+    pub fn set_smooth_faces(mesh: &mut HalfEdgeMesh, selection: SelectionExpression) -> Result<()> {
+        super::set_smooth_faces(mesh, &selection)
+    }
+
+    /// Fills the vertex `color` channel of the mesh by sampling the color
+    /// `ramp` with the normalized value of `source` at each vertex. The source
+    /// is either a position axis (`"x"`, `"y"` or `"z"`) or the name of an f32
+    /// vertex channel.
+    #[lua(under = "Ops")]
+    pub fn color_by_gradient(
+        mesh: &mut HalfEdgeMesh,
+        source: String,
+        ramp: &crate::graph::ramp::Ramp,
+    ) -> Result<()> {
+        super::color_by_gradient(mesh, &source, ramp)
+    }
+
     /// Given a source mesh (`src_mesh`) and a destination mesh (`dst_mesh`),
     /// transfers the vertex channel with given `value_type` and `channel_name`
     /// from source to mesh.
@@ -2217,6 +5154,39 @@ pub mod lua_fns {
         super::copy_to_points(points, mesh)
     }
 
+    /// Like `copy_to_points`, but instances one of the `meshes` in the given
+    /// list at each point. The optional `variant` vertex channel picks the
+    /// index of the mesh for each point, otherwise meshes are picked randomly.
+    /// Each instance is also randomly scaled by up to `scale_jitter` and
+    /// rotated around its normal by up to `rotation_jitter` radians. The
+    /// `seed` controls all the random choices.
+    #[lua(under = "Ops")]
+    pub fn copy_to_points_with_variation(
+        points: &HalfEdgeMesh,
+        meshes: mlua::Table,
+        scale_jitter: f32,
+        rotation_jitter: f32,
+        seed: u32,
+    ) -> Result<HalfEdgeMesh> {
+        let meshes = meshes
+            .sequence_values::<mlua::AnyUserData>()
+            .collect::<Result<Vec<_>, _>>()?;
+        let meshes = meshes
+            .iter()
+            .map(|mesh| mesh.borrow::<HalfEdgeMesh>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let variation = InstanceVariation {
+            scale_jitter,
+            rotation_jitter,
+        };
+        super::copy_to_points_with_variation(
+            points,
+            &meshes.iter().map(|mesh| &**mesh).collect_vec(),
+            &variation,
+            seed,
+        )
+    }
+
     /// Given a `backbone` mesh and a cross-section mesh, both polylines,
     /// returns a new mesh which extrudes the cross-section across the backbone.
     ///
@@ -2238,14 +5208,15 @@ pub mod lua_fns {
 
     /// Applies a transformation to the given selection of mesh elements
     /// (vertex, face, halfedge). The transformation is applied relative to the
-    /// elements centroid.
+    /// elements centroid. The `rotate` parameter can be a `Quat`, or a vector
+    /// of XYZ euler angles.
     #[lua(under = "Ops")]
     pub fn edit_geometry(
         mesh: &mut HalfEdgeMesh,
         geometry_type: ChannelKeyType,
         selection: SelectionExpression,
         translate: LVec3,
-        rotate: LVec3,
+        rotate: LQuat,
         scale: LVec3,
     ) -> Result<()> {
         super::edit_geometry(
@@ -2285,6 +5256,14 @@ pub mod lua_fns {
         Ok(())
     }
 
+    /// Splits and collapses edges to make their lengths closer to
+    /// `target_length`, which results in a uniform, faceted look. Boundaries
+    /// are preserved.
+    #[lua(under = "Ops")]
+    pub fn quantize_edges(mesh: &mut HalfEdgeMesh, target_length: f32) -> Result<()> {
+        super::quantize_edges(mesh, target_length)
+    }
+
     #[lua(under = "Ops")]
     pub fn divide_edges(
         mesh: &mut HalfEdgeMesh,
@@ -0,0 +1,389 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::prelude::*;
+
+use super::extrude_faces;
+use halfedge::selection::SelectionExpression;
+
+/// Name of the face channel storing the floor index of each wall face.
+pub const FLOOR_CHANNEL: &str = "floor";
+/// Name of the face group containing the walls of a building.
+pub const WALLS_GROUP: &str = "walls";
+/// Name of the face group containing the roof of a building.
+pub const ROOF_GROUP: &str = "roof";
+/// Name of the face group containing the windows inserted by
+/// [`insert_window_grid`].
+pub const WINDOWS_GROUP: &str = "windows";
+
+/// Marks the faces at the given polygon indices as members of the face group
+/// `name`. Relies on `build_from_polygons` allocating faces in the same order
+/// as the polygons it receives.
+fn set_face_group(mesh: &mut HalfEdgeMesh, name: &str, polygons: &[usize]) -> Result<()> {
+    let faces = mesh
+        .read_connectivity()
+        .iter_faces()
+        .map(|(f, _)| f)
+        .collect_vec();
+    let ch_id = mesh.channels.ensure_channel::<FaceId, bool>(name);
+    let mut ch = mesh.channels.write_channel(ch_id)?;
+    for idx in polygons {
+        ch[faces[*idx]] = true;
+    }
+    Ok(())
+}
+
+/// Builds a building shell by extruding the first face of `footprint` along
+/// its normal. The walls are split in `floors` rings of height
+/// `floor_height`, and the top is closed with a roof face.
+///
+/// Wall faces store their floor index (starting at 0) in the
+/// [`FLOOR_CHANNEL`], and are part of the [`WALLS_GROUP`]. The top face is
+/// part of the [`ROOF_GROUP`].
+pub fn extrude_footprint(
+    footprint: &HalfEdgeMesh,
+    floors: usize,
+    floor_height: f32,
+) -> Result<HalfEdgeMesh> {
+    if floors == 0 {
+        bail!("A building needs at least one floor");
+    }
+
+    let (ring, normal) = {
+        let conn = footprint.read_connectivity();
+        let positions = footprint.read_positions();
+        let (face, _) = conn
+            .iter_faces()
+            .next()
+            .ok_or_else(|| anyhow!("The footprint mesh has no faces"))?;
+        let normal = conn
+            .face_normal(&positions, face)
+            .ok_or_else(|| anyhow!("The footprint face is degenerate"))?;
+        let ring = conn
+            .face_vertices(face)
+            .iter()
+            .map(|v| positions[*v])
+            .collect_vec();
+        (ring, normal)
+    };
+    let n = ring.len();
+
+    let mut positions = Vec::with_capacity(n * (floors + 1));
+    for floor in 0..=floors {
+        let offset = normal * floor_height * floor as f32;
+        positions.extend(ring.iter().map(|p| *p + offset));
+    }
+
+    let mut polygons: Vec<Vec<usize>> = vec![];
+    // Base, facing away from the building
+    polygons.push((0..n).rev().collect());
+    let mut walls = vec![];
+    let mut wall_floors = vec![];
+    for floor in 0..floors {
+        let base = floor * n;
+        for i in 0..n {
+            let j = (i + 1) % n;
+            walls.push(polygons.len());
+            wall_floors.push(floor);
+            polygons.push(vec![base + i, base + j, base + n + j, base + n + i]);
+        }
+    }
+    let roof = polygons.len();
+    polygons.push((floors * n..(floors + 1) * n).collect());
+
+    let mut mesh = HalfEdgeMesh::build_from_polygons(&positions, &polygons)?;
+
+    let faces = mesh
+        .read_connectivity()
+        .iter_faces()
+        .map(|(f, _)| f)
+        .collect_vec();
+    let floor_ch_id = mesh.channels.ensure_channel::<FaceId, f32>(FLOOR_CHANNEL);
+    {
+        let mut floor_ch = mesh.channels.write_channel(floor_ch_id)?;
+        for (polygon, floor) in walls.iter().zip(wall_floors) {
+            floor_ch[faces[*polygon]] = floor as f32;
+        }
+    }
+    set_face_group(&mut mesh, WALLS_GROUP, &walls)?;
+    set_face_group(&mut mesh, ROOF_GROUP, &[roof])?;
+
+    Ok(mesh)
+}
+
+/// Subdivides each of the selected `faces` into a grid of `cols` by `rows`
+/// cells and places a window in every cell. Windows are inset from the cell
+/// borders by `inset`, a fraction of the cell size in the 0..0.5 range, and
+/// pushed inside the wall by `depth`.
+///
+/// The selected faces must be quads. The new window faces are part of the
+/// [`WINDOWS_GROUP`]. Note that the mesh is rebuilt by this operation, so
+/// only vertex positions are kept.
+pub fn insert_window_grid(
+    mesh: &HalfEdgeMesh,
+    faces: &SelectionExpression,
+    cols: usize,
+    rows: usize,
+    inset: f32,
+    depth: f32,
+) -> Result<HalfEdgeMesh> {
+    if cols == 0 || rows == 0 {
+        bail!("The window grid needs at least one row and one column");
+    }
+    let inset = inset.clamp(0.0, 0.49);
+
+    let selected: HashSet<FaceId> = mesh
+        .resolve_face_selection_full(faces)?
+        .into_iter()
+        .collect();
+    let conn = mesh.read_connectivity();
+    let mesh_positions = mesh.read_positions();
+
+    let mut positions = vec![];
+    let mut vertex_idx = HashMap::<VertexId, usize>::new();
+    for (v, _) in conn.iter_vertices() {
+        vertex_idx.insert(v, positions.len());
+        positions.push(mesh_positions[v]);
+    }
+
+    // The points inserted on the edges of the selected faces, in order from
+    // the source to the destination vertex. Faces sharing those edges need to
+    // include the points as well, so the mesh stays connected.
+    let mut edge_points = HashMap::<(usize, usize), Vec<usize>>::new();
+    fn add_edge_points(
+        edge_points: &mut HashMap<(usize, usize), Vec<usize>>,
+        positions: &mut Vec<Vec3>,
+        (a, b): (usize, usize),
+        segments: usize,
+    ) -> Result<()> {
+        // Edges shared by two selected faces must be divided the same way on
+        // both sides.
+        let existing = edge_points.get(&(a, b)).cloned().or_else(|| {
+            edge_points
+                .get(&(b, a))
+                .map(|p| p.iter().rev().copied().collect())
+        });
+        if let Some(points) = existing {
+            if points.len() + 1 != segments {
+                bail!("Adjacent faces need the same number of windows on their shared edge");
+            }
+            edge_points.insert((a, b), points);
+            return Ok(());
+        }
+        let (pa, pb) = (positions[a], positions[b]);
+        let points = (1..segments)
+            .map(|i| {
+                positions.push(pa.lerp(pb, i as f32 / segments as f32));
+                positions.len() - 1
+            })
+            .collect();
+        edge_points.insert((a, b), points);
+        Ok(())
+    }
+
+    let mut quads = vec![];
+    for face in conn.iter_faces().map(|(f, _)| f) {
+        if !selected.contains(&face) {
+            continue;
+        }
+        let verts = conn.face_vertices(face);
+        if verts.len() != 4 {
+            bail!(
+                "Windows can only be inserted in quads, but a face has {} vertices",
+                verts.len()
+            );
+        }
+        let [a, b, c, d] = [0, 1, 2, 3].map(|i| vertex_idx[&verts[i]]);
+        add_edge_points(&mut edge_points, &mut positions, (a, b), cols)?;
+        add_edge_points(&mut edge_points, &mut positions, (b, c), rows)?;
+        add_edge_points(&mut edge_points, &mut positions, (c, d), cols)?;
+        add_edge_points(&mut edge_points, &mut positions, (d, a), rows)?;
+        quads.push((face, [a, b, c, d]));
+    }
+    // Edges in the opposite direction, for the faces next to the selection.
+    let reversed = edge_points
+        .iter()
+        .map(|((a, b), points)| ((*b, *a), points.iter().rev().copied().collect_vec()))
+        .collect_vec();
+    for (key, points) in reversed {
+        edge_points.entry(key).or_insert(points);
+    }
+
+    let mut polygons: Vec<Vec<usize>> = vec![];
+    for (face, _) in conn.iter_faces() {
+        if selected.contains(&face) {
+            continue;
+        }
+        let verts = conn
+            .face_vertices(face)
+            .iter()
+            .map(|v| vertex_idx[v])
+            .collect_vec();
+        let mut polygon = vec![];
+        for (a, b) in verts.iter_cpy().circular_tuple_windows() {
+            polygon.push(a);
+            if let Some(points) = edge_points.get(&(a, b)) {
+                polygon.extend(points.iter_cpy());
+            }
+        }
+        polygons.push(polygon);
+    }
+
+    let mut windows = vec![];
+    for (_, [a, b, c, d]) in quads {
+        // Grid of vertex indices. `u` goes from `a` to `b` and `v` from `a` to
+        // `d`, so grid[v][u] covers the whole face.
+        let mut grid = vec![vec![0; cols + 1]; rows + 1];
+        let ab = &edge_points[&(a, b)];
+        let dc = &edge_points[&(d, c)];
+        let ad = &edge_points[&(a, d)];
+        let bc = &edge_points[&(b, c)];
+        for (v, grid_row) in grid.iter_mut().enumerate() {
+            for (u, grid_idx) in grid_row.iter_mut().enumerate() {
+                *grid_idx = match (u, v) {
+                    (0, 0) => a,
+                    (u, 0) if u == cols => b,
+                    (0, v) if v == rows => d,
+                    (u, v) if u == cols && v == rows => c,
+                    (u, 0) => ab[u - 1],
+                    (u, v) if v == rows => dc[u - 1],
+                    (0, v) => ad[v - 1],
+                    (u, v) if u == cols => bc[v - 1],
+                    (u, v) => {
+                        let top = positions[a].lerp(positions[b], u as f32 / cols as f32);
+                        let bottom = positions[d].lerp(positions[c], u as f32 / cols as f32);
+                        positions.push(top.lerp(bottom, v as f32 / rows as f32));
+                        positions.len() - 1
+                    }
+                };
+            }
+        }
+
+        for v in 0..rows {
+            for u in 0..cols {
+                let cell = [
+                    grid[v][u],
+                    grid[v][u + 1],
+                    grid[v + 1][u + 1],
+                    grid[v + 1][u],
+                ];
+                if inset <= 0.0 {
+                    windows.push(polygons.len());
+                    polygons.push(cell.to_vec());
+                    continue;
+                }
+                let center = cell.iter().fold(Vec3::ZERO, |acc, i| acc + positions[*i]) / 4.0;
+                let window = cell.map(|i| {
+                    positions.push(positions[i].lerp(center, inset * 2.0));
+                    positions.len() - 1
+                });
+                // The frame around the window
+                for ((ci, cj), (wi, wj)) in cell
+                    .iter_cpy()
+                    .circular_tuple_windows()
+                    .zip(window.iter_cpy().circular_tuple_windows())
+                {
+                    polygons.push(vec![ci, cj, wj, wi]);
+                }
+                windows.push(polygons.len());
+                polygons.push(window.to_vec());
+            }
+        }
+    }
+
+    drop(conn);
+    drop(mesh_positions);
+
+    let mut new_mesh = HalfEdgeMesh::build_from_polygons(&positions, &polygons)?;
+    set_face_group(&mut new_mesh, WINDOWS_GROUP, &windows)?;
+
+    if depth != 0.0 {
+        let window_faces =
+            new_mesh.resolve_face_selection_full(&SelectionExpression::Explicit(vec![
+                halfedge::selection::SelectionFragment::Group(WINDOWS_GROUP.into()),
+            ]))?;
+        extrude_faces(
+            &mut new_mesh.write_connectivity(),
+            &mut new_mesh.write_positions(),
+            &window_faces,
+            -depth,
+        )?;
+    }
+
+    Ok(new_mesh)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Extrudes the first face of the `footprint` mesh into a building with
+    /// the given number of `floors`, each one `floor_height` units tall. Wall
+    /// faces are grouped as `walls` and store their floor index in the
+    /// `floor` channel. The top face is grouped as `roof`.
+    #[lua(under = "Ops")]
+    fn extrude_footprint(
+        footprint: &HalfEdgeMesh,
+        floors: usize,
+        floor_height: f32,
+    ) -> Result<HalfEdgeMesh> {
+        super::extrude_footprint(footprint, floors, floor_height)
+    }
+
+    /// Returns a copy of `mesh` where each of the selected quad `faces` has
+    /// been divided in a grid of `cols` by `rows` windows. Windows are inset
+    /// from their cell by `inset`, as a fraction of the cell size, and pushed
+    /// `depth` units into the wall. The window faces are grouped as `windows`.
+    #[lua(under = "Ops")]
+    fn insert_window_grid(
+        mesh: &HalfEdgeMesh,
+        faces: SelectionExpression,
+        cols: usize,
+        rows: usize,
+        inset: f32,
+        depth: f32,
+    ) -> Result<HalfEdgeMesh> {
+        super::insert_window_grid(mesh, &faces, cols, rows, inset, depth)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_building_ops() {
+        let footprint = HalfEdgeMesh::build_from_polygons(
+            &[
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+                Vec3::new(1.0, 0.0, 1.0),
+                Vec3::new(1.0, 0.0, 0.0),
+            ],
+            &[[0, 1, 2, 3]],
+        )
+        .unwrap();
+        let building = extrude_footprint(&footprint, 3, 2.0).unwrap();
+        {
+            let conn = building.read_connectivity();
+            assert_eq!(conn.num_vertices(), 16);
+            // Base, roof and four walls per floor
+            assert_eq!(conn.num_faces(), 2 + 4 * 3);
+        }
+
+        let walls =
+            SelectionExpression::Explicit(vec![halfedge::selection::SelectionFragment::Group(
+                WALLS_GROUP.into(),
+            )]);
+        let with_windows = insert_window_grid(&building, &walls, 2, 1, 0.2, 0.0).unwrap();
+        let windows = with_windows
+            .resolve_face_selection_full(&SelectionExpression::Explicit(vec![
+                halfedge::selection::SelectionFragment::Group(WINDOWS_GROUP.into()),
+            ]))
+            .unwrap();
+        assert_eq!(windows.len(), 12 * 2);
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use slotmap::Key;
+
+use crate::prelude::*;
+
+use super::{
+    cut_face, divide_edge, fix_vertex_halfedges, newell_normal, remove_boundary_halfedges,
+};
+
+/// Vertices closer than this to the plane are considered to be on it.
+const PLANE_EPSILON: f32 = 1e-5;
+
+/// Which side of the plane [`bisect`] removes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearSide {
+    /// Both sides are kept, so the mesh is only cut.
+    None,
+    /// Removes the side the plane normal points to.
+    Positive,
+    /// Removes the side opposite to the plane normal.
+    Negative,
+}
+
+impl ClearSide {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "None" => Ok(Self::None),
+            "Positive" => Ok(Self::Positive),
+            "Negative" => Ok(Self::Negative),
+            _ => bail!("Invalid side to clear: {name}"),
+        }
+    }
+}
+
+/// Cuts the faces of `mesh` that cross the plane through `plane_origin` with
+/// the given normal, so that no face is left on both sides. Then, removes the
+/// faces on the `clear_side` of the plane, if any. When `cap` is set, the
+/// boundary loops left along the plane by the removed side are closed with a
+/// new face.
+///
+/// The new vertices and corners take their data from the edges they're on,
+/// interpolating the UVs. Faces split in two keep their data on both halves.
+pub fn bisect(
+    mesh: &HalfEdgeMesh,
+    plane_origin: Vec3,
+    plane_normal: Vec3,
+    clear_side: ClearSide,
+    cap: bool,
+) -> Result<()> {
+    let normal = plane_normal
+        .try_normalize()
+        .ok_or_else(|| anyhow!("The bisect plane normal can't be zero"))?;
+    let distance = |pos: Vec3| {
+        let d = (pos - plane_origin).dot(normal);
+        if d.abs() <= PLANE_EPSILON {
+            0.0
+        } else {
+            d
+        }
+    };
+
+    let mut vertex_copies = vec![];
+    let mut halfedge_copies = vec![];
+    let mut cut_copies = vec![];
+    let mut face_copies = vec![];
+    let mut uv_lerps = vec![];
+    let mut new_positions = vec![];
+    {
+        let mut conn = mesh.write_connectivity();
+        let mut positions = mesh.write_positions();
+
+        // Edges crossing the plane get a new vertex where they cross it
+        let mut crossing = vec![];
+        let mut visited = HashSet::new();
+        for (h, _) in conn.iter_halfedges() {
+            if !visited.insert(h) {
+                continue;
+            }
+            visited.insert(conn.at_halfedge(h).twin().try_end()?);
+            let (v, w) = conn.at_halfedge(h).src_dst_pair()?;
+            let (dv, dw) = (distance(positions[v]), distance(positions[w]));
+            if dv * dw < 0.0 {
+                crossing.push((h, dv / (dv - dw)));
+            }
+        }
+        for (h, t) in crossing {
+            let twin = conn.at_halfedge(h).twin().try_end()?;
+            let h_next = conn.at_halfedge(h).next().try_end()?;
+            let twin_next = conn.at_halfedge(twin).next().try_end()?;
+            let (v, w) = conn.at_halfedge(h).src_dst_pair()?;
+            let x = divide_edge(&mut conn, &mut positions, h, t)?;
+            // After the division, `h` goes from x to w, and `h_2` from v to x
+            let h_2 = conn.at_halfedge(h).previous().try_end()?;
+            let twin_2 = conn.at_halfedge(h_2).twin().try_end()?;
+
+            let closest = if t < 0.5 { v } else { w };
+            vertex_copies.push((closest.data(), x.data()));
+            new_positions.push((x, positions[x]));
+            halfedge_copies.push((h.data(), h_2.data()));
+            halfedge_copies.push((twin.data(), twin_2.data()));
+            uv_lerps.push((h, h, h_next, t));
+            uv_lerps.push((twin_2, twin_next, twin, t));
+        }
+
+        // Faces with vertices on both sides are cut between the vertices
+        // where the boundary of the face crosses the plane. Non-convex faces
+        // may cross it more than twice, so crossings are paired in the order
+        // they appear along the plane.
+        let faces = conn.iter_faces().map(|(f, _)| f).collect_vec();
+        for face in faces {
+            let vertices = conn.face_vertices(face);
+            let dists = vertices
+                .iter()
+                .map(|v| distance(positions[*v]))
+                .collect_vec();
+            if !dists.iter().any(|d| *d > 0.0) || !dists.iter().any(|d| *d < 0.0) {
+                continue;
+            }
+            let n = vertices.len();
+            let side_from = |i: usize, step: usize| {
+                (1..n)
+                    .map(|k| dists[(i + k * step) % n])
+                    .find(|d| *d != 0.0)
+                    .map_or(0.0, f32::signum)
+            };
+            let mut crossings = (0..n)
+                .filter(|i| dists[*i] == 0.0 && dists[(i + n - 1) % n] != 0.0)
+                .filter(|i| side_from(*i, n - 1) != side_from(*i, 1))
+                .map(|i| vertices[i])
+                .collect_vec();
+            let points = vertices.iter().map(|v| positions[*v]).collect_vec();
+            let along = normal.cross(newell_normal(&points));
+            crossings.sort_by_key(|v| float_ord::FloatOrd(positions[*v].dot(along)));
+
+            for (a, b) in crossings.iter_cpy().tuples() {
+                if conn.at_vertex(a).halfedge_to(b).try_end().is_ok() {
+                    continue;
+                }
+                let h_ab = cut_face(&mut conn, a, b)?;
+                let h_ba = conn.at_halfedge(h_ab).twin().try_end()?;
+                // The corners of the new edge take the data of the corners
+                // that were at the same vertex before the cut.
+                let old_a = conn.at_halfedge(h_ba).next().try_end()?;
+                let old_b = conn.at_halfedge(h_ab).next().try_end()?;
+                cut_copies.push((old_a.data(), h_ab.data()));
+                cut_copies.push((old_b.data(), h_ba.data()));
+                let old_face = conn.at_halfedge(h_ab).face().try_end()?;
+                let new_face = conn.at_halfedge(h_ba).face().try_end()?;
+                face_copies.push((old_face.data(), new_face.data()));
+            }
+        }
+    }
+
+    // Copying the vertex data also copies the positions, which are restored
+    mesh.channels
+        .copy_values_dyn(ChannelKeyType::VertexId, &vertex_copies);
+    mesh.channels
+        .copy_values_dyn(ChannelKeyType::HalfEdgeId, &halfedge_copies);
+    mesh.channels
+        .copy_values_dyn(ChannelKeyType::FaceId, &face_copies);
+    {
+        let mut positions = mesh.write_positions();
+        for (x, pos) in new_positions {
+            positions[x] = pos;
+        }
+    }
+    // All the interpolated UVs are read before writing any, since the corners
+    // they're read from may get a new value too.
+    if let Some(uvs) = mesh.default_channels.uvs {
+        let mut uvs = mesh.channels.write_channel(uvs)?;
+        let lerped = uv_lerps
+            .iter()
+            .map(|(h, a, b, t)| (*h, uvs[*a].lerp(uvs[*b], *t)))
+            .collect_vec();
+        for (h, uv) in lerped {
+            uvs[h] = uv;
+        }
+    }
+    // The corners of the cuts are copied last, so they see the interpolated
+    // values of the new vertices.
+    mesh.channels
+        .copy_values_dyn(ChannelKeyType::HalfEdgeId, &cut_copies);
+
+    let sign = match clear_side {
+        ClearSide::None => return Ok(()),
+        ClearSide::Positive => 1.0,
+        ClearSide::Negative => -1.0,
+    };
+    let mut conn = mesh.write_connectivity();
+    let positions = mesh.read_positions();
+
+    // After the cuts, faces are on one side of the plane or lie on it. The
+    // distance of their center tells which.
+    let cleared = conn
+        .iter_faces()
+        .map(|(f, _)| f)
+        .filter(|f| {
+            let vertices = conn.face_vertices(*f);
+            vertices
+                .iter()
+                .map(|v| distance(positions[*v]))
+                .sum::<f32>()
+                * sign
+                > 0.0
+        })
+        .collect_vec();
+
+    remove_boundary_halfedges(&mut conn);
+    let mut orphans = HashSet::new();
+    for face in cleared {
+        for h in conn.face_edges(face) {
+            if let Some(twin) = conn[h].twin {
+                conn[twin].twin = None;
+            }
+            orphans.insert(conn.at_halfedge(h).vertex().try_end()?);
+            conn.remove_halfedge(h);
+        }
+        conn.remove_face(face);
+    }
+    for (_, halfedge) in conn.iter_halfedges() {
+        if let Some(v) = halfedge.vertex {
+            orphans.remove(&v);
+        }
+    }
+    for v in orphans {
+        conn.remove_vertex(v);
+    }
+    conn.add_boundary_halfedges();
+    fix_vertex_halfedges(&mut conn);
+
+    if cap {
+        let boundary = conn
+            .iter_halfedges()
+            .filter(|(_, halfedge)| halfedge.face.is_none())
+            .map(|(h, _)| h)
+            .collect_vec();
+        let mut visited = HashSet::new();
+        for h in boundary {
+            if visited.contains(&h) {
+                continue;
+            }
+            let boundary_loop = conn.halfedge_loop(h);
+            visited.extend(boundary_loop.iter_cpy());
+            // Only the loops left by the removed side are capped, not the
+            // holes the mesh already had.
+            let on_plane = boundary_loop.iter().all(|h| {
+                conn[*h]
+                    .vertex
+                    .map_or(false, |v| distance(positions[v]) == 0.0)
+            });
+            if boundary_loop.len() < 3 || !on_plane {
+                continue;
+            }
+            let face = conn.alloc_face(Some(boundary_loop[0]));
+            for b in boundary_loop {
+                conn[b].face = Some(face);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+
+    /// Returns a copy of `mesh` cut along the plane through `plane_origin`
+    /// with normal `plane_normal`. The `clear_side` is "None" to keep both
+    /// sides, or "Positive" or "Negative" to remove the side the normal
+    /// points to or away from. When a side is removed and `cap` is set, the
+    /// cut is closed with new faces.
+    #[lua(under = "Ops")]
+    fn bisect(
+        mesh: &HalfEdgeMesh,
+        plane_origin: LVec3,
+        plane_normal: LVec3,
+        clear_side: String,
+        cap: bool,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::bisect(
+            &result,
+            plane_origin.0,
+            plane_normal.0,
+            ClearSide::from_name(&clear_side)?,
+            cap,
+        )?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    pub fn test_bisect() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let bisected = |clear_side: ClearSide, cap: bool| {
+            let result = mesh.clone();
+            bisect(&result, Vec3::ZERO, Vec3::Y, clear_side, cap).unwrap();
+            result
+        };
+
+        // The four side faces are cut in two
+        let cut = bisected(ClearSide::None, false);
+        let conn = cut.read_connectivity();
+        assert_eq!(conn.num_vertices(), 12);
+        assert_eq!(conn.num_faces(), 10);
+        assert_eq!(conn.num_halfedges(), 40);
+        let positions = cut.read_positions();
+        for (face, _) in conn.iter_faces() {
+            let ys = conn
+                .face_vertices(face)
+                .iter()
+                .map(|v| positions[*v].y)
+                .collect_vec();
+            assert!(ys.iter().all(|y| *y >= 0.0) || ys.iter().all(|y| *y <= 0.0));
+        }
+
+        // The bottom half, closed at the top
+        let capped = bisected(ClearSide::Positive, true);
+        let conn = capped.read_connectivity();
+        assert_eq!(conn.num_vertices(), 8);
+        assert_eq!(conn.num_faces(), 6);
+        assert_eq!(conn.num_halfedges(), 24);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+        let positions = capped.read_positions();
+        assert!(conn.iter_vertices().all(|(v, _)| positions[v].y <= 0.0));
+
+        // The top half, left open at the bottom
+        let open = bisected(ClearSide::Negative, false);
+        let conn = open.read_connectivity();
+        assert_eq!(conn.num_vertices(), 8);
+        assert_eq!(conn.num_faces(), 5);
+        assert_eq!(conn.num_halfedges(), 24);
+        for (h, halfedge) in conn.iter_halfedges() {
+            let twin = conn.at_halfedge(h).twin().try_end().unwrap();
+            assert!(halfedge.face.is_some() || conn[twin].face.is_some());
+        }
+        let positions = open.read_positions();
+        assert!(conn.iter_vertices().all(|(v, _)| positions[v].y >= 0.0));
+
+        assert!(bisect(&mesh, Vec3::ZERO, Vec3::ZERO, ClearSide::None, false).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::prelude::*;
+
+/// The extent of a mesh along an axis, used to parametrize the deformers in
+/// this module.
+struct AxisRange {
+    axis: Vec3,
+    min: f32,
+    max: f32,
+}
+
+impl AxisRange {
+    fn new(mesh: &HalfEdgeMesh, axis: Vec3) -> Result<Self> {
+        let axis = axis
+            .try_normalize()
+            .ok_or_else(|| anyhow!("The deformation axis can't be zero"))?;
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let (min, max) = conn
+            .iter_vertices()
+            .map(|(v, _)| positions[v].dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), h| {
+                (min.min(h), max.max(h))
+            });
+        Ok(Self { axis, min, max })
+    }
+
+    /// Returns the height of `pos` along the axis.
+    fn height(&self, pos: Vec3) -> f32 {
+        pos.dot(self.axis)
+    }
+
+    /// Returns the height of `pos` along the axis, remapped so the mesh goes
+    /// from 0 to 1. Flat meshes are at 0.
+    fn param(&self, pos: Vec3) -> f32 {
+        let length = self.max - self.min;
+        if length > f32::EPSILON {
+            ((self.height(pos) - self.min) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the height for the given `param` in the 0..1 range.
+    fn height_at(&self, param: f32) -> f32 {
+        self.min + (self.max - self.min) * param
+    }
+}
+
+/// Applies `f` to every vertex position of `mesh`.
+fn deform_positions(mesh: &HalfEdgeMesh, f: impl Fn(Vec3) -> Vec3) {
+    let conn = mesh.read_connectivity();
+    let mut positions = mesh.write_positions();
+    for (v, _) in conn.iter_vertices() {
+        positions[v] = f(positions[v]);
+    }
+}
+
+/// Rotates the vertices of `mesh` around `axis`, which goes through the
+/// origin. The rotation grows from zero at the lowest point of the mesh along
+/// the axis to `angle` radians at the highest one. The `falloff` exponent
+/// shapes that growth: 1 is linear, larger values keep the twist towards the
+/// top of the mesh and smaller ones towards the bottom.
+pub fn twist(mesh: &HalfEdgeMesh, axis: Vec3, angle: f32, falloff: f32) -> Result<()> {
+    let range = AxisRange::new(mesh, axis)?;
+    let falloff = falloff.max(0.01);
+    deform_positions(mesh, |pos| {
+        let t = range.param(pos).powf(falloff);
+        Quat::from_axis_angle(range.axis, angle * t) * pos
+    });
+    Ok(())
+}
+
+/// Scales the vertices of `mesh` away from `axis`, which goes through the
+/// origin. The scale goes linearly from 1 at the lowest point of the mesh along
+/// the axis to `1 + factor` at the highest one. Negative factors narrow the
+/// mesh, and the scale is clamped at zero so it never turns inside out.
+pub fn taper(mesh: &HalfEdgeMesh, axis: Vec3, factor: f32) -> Result<()> {
+    let range = AxisRange::new(mesh, axis)?;
+    deform_positions(mesh, |pos| {
+        let scale = (1.0 + factor * range.param(pos)).max(0.0);
+        let on_axis = range.axis * range.height(pos);
+        on_axis + (pos - on_axis) * scale
+    });
+    Ok(())
+}
+
+/// Returns the direction [`bend`] curves a mesh towards, for the given
+/// (normalized) `axis`. This is the world axis most perpendicular to it, so
+/// upright meshes bend towards X and meshes lying along X bend towards Y.
+fn bend_direction(axis: Vec3) -> Vec3 {
+    let world_axis = [Vec3::X, Vec3::Y, Vec3::Z]
+        .into_iter()
+        .min_by_key(|dir| float_ord::FloatOrd(dir.dot(axis).abs()))
+        .unwrap();
+    (world_axis - axis * world_axis.dot(axis)).normalize()
+}
+
+/// Bends `mesh` into an arc of `angle` radians. The mesh is bent along `axis`,
+/// which goes through the origin, towards the direction returned by
+/// [`bend_direction`]. The `limits` are two values in the 0..1 range, where 0
+/// is the lowest point of the mesh along the axis and 1 the highest. Only the
+/// part of the mesh between them is curved. The rest stays straight, following
+/// the arc at both of its ends.
+pub fn bend(mesh: &HalfEdgeMesh, axis: Vec3, angle: f32, limits: Vec2) -> Result<()> {
+    let range = AxisRange::new(mesh, axis)?;
+    let lo = range.height_at(limits.x.min(limits.y).clamp(0.0, 1.0));
+    let hi = range.height_at(limits.x.max(limits.y).clamp(0.0, 1.0));
+    let length = hi - lo;
+    if angle.abs() < f32::EPSILON || length < f32::EPSILON {
+        return Ok(());
+    }
+
+    let dir = bend_direction(range.axis);
+    let radius = length / angle;
+    deform_positions(mesh, |pos| {
+        let h = range.height(pos);
+        let x = pos.dot(dir);
+        let clamped_h = h.clamp(lo, hi);
+        let phi = angle * (clamped_h - lo) / length;
+        let (sin, cos) = phi.sin_cos();
+        // Points are moved on the plane formed by the axis and the bend
+        // direction, and kept in place along the remaining direction.
+        let r = radius - x;
+        let new_h = lo + r * sin + (h - clamped_h) * cos;
+        let new_x = radius - r * cos + (h - clamped_h) * sin;
+        pos + range.axis * (new_h - h) + dir * (new_x - x)
+    });
+    Ok(())
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::{LVec2, LVec3};
+
+    /// Returns a copy of `mesh` twisted around `axis` by up to `angle`
+    /// radians. The `falloff` exponent controls how the twist is distributed
+    /// along the axis, where 1 is linear.
+    #[lua(under = "Ops")]
+    fn twist(mesh: &HalfEdgeMesh, axis: LVec3, angle: f32, falloff: f32) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::twist(&result, axis.0, angle, falloff)?;
+        Ok(result)
+    }
+
+    /// Returns a copy of `mesh` bent along `axis` into an arc of `angle`
+    /// radians. Only the part of the mesh between the `limits`, given as two
+    /// fractions of the mesh length along the axis, is curved.
+    #[lua(under = "Ops")]
+    fn bend(mesh: &HalfEdgeMesh, axis: LVec3, angle: f32, limits: LVec2) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::bend(&result, axis.0, angle, limits.0)?;
+        Ok(result)
+    }
+
+    /// Returns a copy of `mesh` scaled away from `axis` by a factor that goes
+    /// from 1 at its bottom to `1 + factor` at its top.
+    #[lua(under = "Ops")]
+    fn taper(mesh: &HalfEdgeMesh, axis: LVec3, factor: f32) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::taper(&result, axis.0, factor)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!(a.distance(b) < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    pub fn test_twist_and_taper() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let bottom = |pos: Vec3| pos.y < 0.0;
+
+        let twisted = mesh.clone();
+        twist(&twisted, Vec3::Y, std::f32::consts::PI, 1.0).unwrap();
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(twisted.read_positions().iter())
+        {
+            if bottom(*a) {
+                assert_near(*a, *b);
+            } else {
+                assert_near(Vec3::new(-a.x, a.y, -a.z), *b);
+            }
+        }
+
+        let tapered = mesh.clone();
+        taper(&tapered, Vec3::Y, -2.0).unwrap();
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(tapered.read_positions().iter())
+        {
+            if bottom(*a) {
+                assert_near(*a, *b);
+            } else {
+                assert_near(Vec3::new(0.0, a.y, 0.0), *b);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_bend() {
+        let mesh = Box::build(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.2, 2.0, 0.2)).unwrap();
+        let bent = mesh.clone();
+        bend(
+            &bent,
+            Vec3::Y,
+            std::f32::consts::FRAC_PI_2,
+            Vec2::new(0.0, 1.0),
+        )
+        .unwrap();
+
+        // The box is bent around a center at the given radius. Each vertex
+        // keeps its distance to the axis of the box, and its top ends up
+        // pointing towards X.
+        let radius = 2.0 / std::f32::consts::FRAC_PI_2;
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(bent.read_positions().iter())
+        {
+            let center = Vec3::new(radius, 0.0, a.z);
+            assert!(((*b - center).length() - (radius - a.x)).abs() < 1e-4);
+            if a.y > 1.0 {
+                assert_near(*b, Vec3::new(radius, radius - a.x, a.z));
+            }
+        }
+    }
+}
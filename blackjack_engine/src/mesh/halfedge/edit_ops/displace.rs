@@ -0,0 +1,148 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::fractal_noise::{FractalNoise, NoiseParams};
+use crate::prelude::*;
+
+use super::generate_smooth_normals_channel;
+use super::scatter::sample_texture;
+
+/// Where [`displace`] reads the height of the surface from.
+pub enum DisplaceSource {
+    /// Fractal noise, sampled at the position of each vertex. Values are
+    /// roughly between `-amplitude` and `amplitude`.
+    Noise(FractalNoise),
+    /// A grayscale texture, sampled using the UV coordinates of the mesh.
+    /// Values go from 0 for black to 1 for white.
+    Texture(image::GrayImage),
+}
+
+impl DisplaceSource {
+    /// Loads the grayscale texture at `path` as a displacement source.
+    pub fn load_texture(path: &str) -> Result<Self> {
+        if path.is_empty() {
+            bail!("No displacement texture selected");
+        }
+        let texture = image::open(path)
+            .with_context(|| format!("Could not load displacement texture '{path}'"))?;
+        Ok(DisplaceSource::Texture(texture.to_luma8()))
+    }
+}
+
+/// Moves every vertex of `mesh` along its normal, by `amount` times the value
+/// of the `source` at that vertex. The mesh keeps its vertex normals, when it
+/// has them, so they should be recomputed afterwards.
+pub fn displace(mesh: &HalfEdgeMesh, amount: f32, source: &DisplaceSource) -> Result<()> {
+    let normals = match mesh.read_vertex_normals() {
+        Some(normals) => (*normals).clone(),
+        None => generate_smooth_normals_channel(mesh)?,
+    };
+
+    let conn = mesh.read_connectivity();
+    let heights = match source {
+        DisplaceSource::Noise(noise) => {
+            let positions = mesh.read_positions();
+            conn.iter_vertices()
+                .map(|(v, _)| (v, noise.sample_3d(positions[v])))
+                .collect::<HashMap<_, _>>()
+        }
+        DisplaceSource::Texture(texture) => {
+            let uvs = mesh.read_uvs().ok_or_else(|| {
+                anyhow!("Displacing with a texture requires the mesh to have UVs")
+            })?;
+            // Each face corner has its own UVs. Vertices on a UV seam get the
+            // average of the texture values at their corners.
+            let mut samples = HashMap::<VertexId, (f32, f32)>::new();
+            for (face, _) in conn.iter_faces() {
+                for h in conn.face_edges(face) {
+                    let v = conn.at_halfedge(h).vertex().try_end()?;
+                    let (sum, count) = samples.entry(v).or_default();
+                    *sum += sample_texture(texture, uvs[h]);
+                    *count += 1.0;
+                }
+            }
+            samples
+                .into_iter()
+                .map(|(v, (sum, count))| (v, sum / count))
+                .collect()
+        }
+    };
+
+    let mut positions = mesh.write_positions();
+    for (v, height) in heights {
+        positions[v] += normals[v] * height * amount;
+    }
+    Ok(())
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Returns a copy of `mesh` with its vertices moved along their normals by
+    /// `amount` times the value of the `source`. The source can be a table
+    /// with the same fields as `FractalNoise.new`, to displace by noise, or
+    /// the path to a grayscale image, to displace by a texture using the UVs
+    /// of the mesh.
+    #[lua(under = "Ops")]
+    fn displace(mesh: &HalfEdgeMesh, amount: f32, source: mlua::Value) -> Result<HalfEdgeMesh> {
+        let source = match source {
+            mlua::Value::Table(params) => {
+                DisplaceSource::Noise(FractalNoise::new(NoiseParams::from_table(&params)?))
+            }
+            mlua::Value::String(path) => DisplaceSource::load_texture(path.to_str()?)?,
+            _ => bail!("The displacement source must be a table of noise parameters or a path"),
+        };
+        let result = mesh.clone();
+        super::displace(&result, amount, &source)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::edit_ops::set_full_range_uvs;
+    use halfedge::primitives::Box;
+
+    #[test]
+    pub fn test_displace() {
+        let mut mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+
+        // Vertices only move along their normals, which point away from the
+        // center of the box.
+        let noise = DisplaceSource::Noise(FractalNoise::new(NoiseParams::default()));
+        let displaced = mesh.clone();
+        displace(&displaced, 0.5, &noise).unwrap();
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(displaced.read_positions().iter())
+        {
+            assert!(a.cross(*b).length() < 1e-4);
+        }
+
+        let white = DisplaceSource::Texture(image::GrayImage::from_pixel(2, 2, image::Luma([255])));
+        assert!(displace(&mesh, 0.5, &white).is_err());
+        set_full_range_uvs(&mut mesh).unwrap();
+        let displaced = mesh.clone();
+        displace(&displaced, 0.5, &white).unwrap();
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(displaced.read_positions().iter())
+        {
+            assert!((b.length() - a.length() - 0.5).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    pub fn test_displace_texture_path() {
+        let err = DisplaceSource::load_texture("").err().unwrap();
+        assert_eq!(err.to_string(), "No displacement texture selected");
+        assert!(DisplaceSource::load_texture("does_not_exist.png").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use slotmap::Key;
+
+use crate::prelude::*;
+
+use super::{fix_vertex_halfedges, generate_flat_normals_channel, remove_boundary_halfedges};
+
+/// Splits the edges in `selection` where faces meet at an angle larger than
+/// `angle_threshold` (in radians), so each face keeps its own copy of the
+/// edge. Vertices are duplicated where the split edges separate the faces
+/// around them, so smooth normals become discontinuous along those edges.
+/// Returns the number of edges that were split.
+///
+/// A split edge needs to reach a vertex that gets duplicated, either because
+/// it runs into another split edge or into the boundary. Otherwise, it would
+/// leave a hole without any area, and the edge is kept as is.
+pub fn edge_split(
+    mesh: &HalfEdgeMesh,
+    angle_threshold: f32,
+    selection: &SelectionExpression,
+) -> Result<usize> {
+    let selected = mesh
+        .resolve_halfedge_selection_full(selection)?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let face_normals = generate_flat_normals_channel(mesh)?;
+    let mut vertex_copies = vec![];
+
+    let num_split = {
+        let mut conn = mesh.write_connectivity();
+        let mut positions = mesh.write_positions();
+
+        let mut sharp = HashSet::new();
+        for (h, halfedge) in conn.iter_halfedges() {
+            let twin = conn.at_halfedge(h).twin().try_end()?;
+            if !selected.contains(&h) && !selected.contains(&twin) {
+                continue;
+            }
+            if let (Some(f1), Some(f2)) = (halfedge.face, conn[twin].face) {
+                if face_normals[f1].angle_between(face_normals[f2]) > angle_threshold {
+                    sharp.insert(h);
+                }
+            }
+        }
+
+        // The faces around each vertex form a fan, which gets cut into wedges
+        // by the sharp edges and the boundary. Every wedge but the first one
+        // gets its own copy of the vertex.
+        let mut wedges = vec![];
+        let sharp_vertices = sharp
+            .iter()
+            .map(|h| conn.at_halfedge(*h).vertex().try_end())
+            .collect::<Result<HashSet<_>, _>>()?;
+        for v in sharp_vertices {
+            let outgoing = conn.at_vertex(v).outgoing_halfedges()?;
+            let n = outgoing.len();
+            let has_face = |i: usize| conn[outgoing[i % n]].face.is_some();
+            // The edge of the i-th outgoing halfedge is between its face and
+            // the face of the next outgoing halfedge.
+            let is_cut =
+                |i: usize| sharp.contains(&outgoing[i]) || !has_face(i) || !has_face(i + 1);
+            let first_cut = match (0..n).find(|i| is_cut(*i)) {
+                Some(first_cut) => first_cut,
+                None => continue,
+            };
+            let mut wedge = SVec::new();
+            let mut num_wedges = 0;
+            for i in (first_cut + 1..=first_cut + n).map(|i| i % n) {
+                if has_face(i) {
+                    wedge.push(outgoing[i]);
+                }
+                if is_cut(i) && !wedge.is_empty() {
+                    if num_wedges > 0 {
+                        wedges.push((v, std::mem::take(&mut wedge)));
+                    } else {
+                        wedge.clear();
+                    }
+                    num_wedges += 1;
+                }
+            }
+        }
+
+        let split_vertices = wedges.iter().map(|(v, _)| *v).collect::<HashSet<_>>();
+        let mut split_edges = vec![];
+        for h in sharp.iter().copied() {
+            let (src, dst) = conn.at_halfedge(h).src_dst_pair()?;
+            if split_vertices.contains(&src) || split_vertices.contains(&dst) {
+                split_edges.push(h);
+            }
+        }
+
+        // Boundary halfedges are removed and built again once the faces are
+        // detached, so the new boundaries are linked with the existing ones.
+        remove_boundary_halfedges(&mut conn);
+        for h in split_edges.iter_cpy() {
+            if let Some(twin) = conn[h].twin {
+                conn[twin].twin = None;
+            }
+            conn[h].twin = None;
+        }
+        for (v, wedge) in wedges {
+            let pos = positions[v];
+            let copy = conn.alloc_vertex(&mut positions, pos, Some(wedge[0]));
+            for h in wedge {
+                conn[h].vertex = Some(copy);
+            }
+            vertex_copies.push((v.data(), copy.data()));
+        }
+        conn.add_boundary_halfedges();
+
+        // Vertices may point to a removed halfedge, or one that now belongs
+        // to a copy of the vertex.
+        fix_vertex_halfedges(&mut conn);
+
+        // Both halfedges of each split edge are in the list
+        split_edges.len() / 2
+    };
+
+    // The copies keep the data of the original vertices
+    mesh.channels
+        .copy_values_dyn(ChannelKeyType::VertexId, &vertex_copies);
+    Ok(num_split)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Returns a copy of `mesh` where the edges in `selection` with faces
+    /// meeting at an angle larger than `angle_threshold` (in radians) have
+    /// been split, so normals become discontinuous along them.
+    #[lua(under = "Ops")]
+    fn edge_split(
+        mesh: &HalfEdgeMesh,
+        angle_threshold: f32,
+        selection: SelectionExpression,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::edge_split(&result, angle_threshold, &selection)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    pub fn test_edge_split() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let all = SelectionExpression::All;
+
+        // Faces of a box meet at right angles
+        let kept = mesh.clone();
+        assert_eq!(edge_split(&kept, 2.0, &all).unwrap(), 0);
+        assert_eq!(kept.read_connectivity().num_vertices(), 8);
+
+        // Every face becomes a separate quad
+        let split = mesh.clone();
+        assert_eq!(edge_split(&split, 0.5, &all).unwrap(), 12);
+        let conn = split.read_connectivity();
+        assert_eq!(conn.num_vertices(), 24);
+        assert_eq!(conn.num_faces(), 6);
+        assert_eq!(conn.num_halfedges(), 48);
+        for (h, halfedge) in conn.iter_halfedges() {
+            assert_eq!(conn.halfedge_loop_iter(h).count(), 4);
+            if halfedge.face.is_none() {
+                let twin = conn.at_halfedge(h).twin().try_end().unwrap();
+                assert!(conn.at_halfedge(twin).face().try_end().is_ok());
+            }
+        }
+        let positions = split.read_positions();
+        for (v, _) in conn.iter_vertices() {
+            assert_eq!(positions[v].abs(), Vec3::splat(0.5));
+        }
+    }
+}
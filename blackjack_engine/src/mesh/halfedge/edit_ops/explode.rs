@@ -0,0 +1,226 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::prelude::*;
+
+use super::islands::{Islands, ISLAND_CHANNEL};
+
+/// The result of [`explode`].
+pub struct Explosion {
+    /// The pieces, moved away from the origin.
+    pub mesh: HalfEdgeMesh,
+    /// A segment for every piece that moves, from its centroid to where the
+    /// centroid is when fully exploded.
+    pub offsets: HalfEdgeMesh,
+}
+
+/// Returns the piece of each vertex in `mesh`, and the number of pieces. The
+/// pieces are the values of the island channel when the mesh has one, or its
+/// connected components otherwise.
+fn vertex_pieces(mesh: &HalfEdgeMesh) -> Result<(Vec<(VertexId, usize)>, usize)> {
+    let conn = mesh.read_connectivity();
+    match mesh
+        .channels
+        .read_channel_by_name::<VertexId, f32>(ISLAND_CHANNEL)
+    {
+        Ok(island_ch) => {
+            let pieces = conn
+                .iter_vertices()
+                .map(|(v, _)| (v, island_ch[v].max(0.0) as usize))
+                .collect_vec();
+            let num_pieces = pieces.iter().map(|(_, p)| p + 1).max().unwrap_or(0);
+            Ok((pieces, num_pieces))
+        }
+        Err(_) => {
+            let islands = Islands::find(&conn)?;
+            let pieces = islands.vertex_island.iter().map(|(v, i)| (v, *i)).collect();
+            Ok((pieces, islands.num_islands))
+        }
+    }
+}
+
+/// Moves the pieces of an assembly away from `origin`, for exploded views.
+/// When `meshes` has a single mesh, its islands are the pieces, as stored in
+/// the island channel by [`super::islands::assign_island_ids`] or found from
+/// its connectivity. Otherwise, each mesh is a piece.
+///
+/// Each piece moves along the direction from `origin` to its centroid. The
+/// piece farthest from the origin moves by `distance`, and the others by
+/// `distance * (r / r_max) ^ falloff`, where `r` is their distance to the
+/// origin. A `falloff` of zero moves all pieces the same, and a `falloff` of
+/// one keeps their proportions. The offsets are multiplied by `amount`, so
+/// the explosion can be animated from 0 to 1.
+pub fn explode(
+    meshes: &[&HalfEdgeMesh],
+    origin: Vec3,
+    distance: f32,
+    falloff: f32,
+    amount: f32,
+) -> Result<Explosion> {
+    if meshes.is_empty() {
+        bail!("At least one mesh is required to explode");
+    }
+    let single = meshes.len() == 1;
+
+    // The centroid of each piece, and the piece of every vertex
+    let mut centroids = vec![];
+    let mut mesh_pieces = vec![];
+    for mesh in meshes {
+        let (pieces, num_pieces) = if single {
+            vertex_pieces(mesh)?
+        } else {
+            let conn = mesh.read_connectivity();
+            (conn.iter_vertices().map(|(v, _)| (v, 0)).collect(), 1)
+        };
+        let positions = mesh.read_positions();
+        let mut sums = vec![(Vec3::ZERO, 0); num_pieces];
+        for (v, piece) in pieces.iter_cpy() {
+            sums[piece].0 += positions[v];
+            sums[piece].1 += 1;
+        }
+        let first_piece = centroids.len();
+        centroids.extend(
+            sums.into_iter()
+                .map(|(sum, count)| (count > 0).then(|| sum / count as f32)),
+        );
+        mesh_pieces.push((first_piece, pieces));
+    }
+
+    let max_radius = centroids
+        .iter()
+        .flatten()
+        .map(|c| c.distance(origin))
+        .fold(0.0, f32::max);
+    let full_offsets = centroids
+        .iter()
+        .map(|centroid| {
+            let centroid = (*centroid)?;
+            let direction = (centroid - origin).try_normalize()?;
+            let radius = centroid.distance(origin);
+            Some(direction * distance * (radius / max_radius).powf(falloff))
+        })
+        .collect_vec();
+
+    let mut result = HalfEdgeMesh::new();
+    for (mesh, (first_piece, pieces)) in meshes.iter().zip(mesh_pieces) {
+        let piece_mesh = (*mesh).clone();
+        {
+            let mut positions = piece_mesh.write_positions();
+            for (v, piece) in pieces {
+                if let Some(offset) = full_offsets[first_piece + piece] {
+                    positions[v] += offset * amount;
+                }
+            }
+        }
+        result.merge_with(&piece_mesh);
+    }
+
+    let mut offsets = HalfEdgeMesh::new();
+    for (centroid, offset) in centroids.iter().zip(full_offsets) {
+        if let (Some(centroid), Some(offset)) = (centroid, offset) {
+            if offset.length_squared() > 0.0 {
+                let segment =
+                    primitives::Line::build_straight_line(*centroid, *centroid + offset, 1)?;
+                offsets.merge_with(&segment);
+            }
+        }
+    }
+
+    Ok(Explosion {
+        mesh: result,
+        offsets,
+    })
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+    use mlua::Lua;
+
+    /// Moves the pieces of `mesh_or_list` away from `origin`, for exploded
+    /// views. Given a single mesh, its islands are the pieces. Given a list
+    /// of meshes, each of them is a piece. The farthest piece moves by
+    /// `distance`, and `falloff` controls how much closer pieces move. The
+    /// optional `amount` (1 by default) scales all offsets, to animate the
+    /// explosion.
+    ///
+    /// Returns a table with the exploded `mesh`, and the `offsets` of the
+    /// pieces as segments from their centroids.
+    #[lua(under = "Ops")]
+    fn explode<'lua>(
+        lua: &'lua Lua,
+        mesh_or_list: mlua::Value<'lua>,
+        origin: LVec3,
+        distance: f32,
+        falloff: f32,
+        amount: Option<f32>,
+    ) -> Result<mlua::Table<'lua>> {
+        let pieces = match mesh_or_list {
+            mlua::Value::UserData(mesh) => vec![mesh],
+            mlua::Value::Table(meshes) => meshes
+                .sequence_values::<mlua::AnyUserData>()
+                .collect::<Result<Vec<_>, _>>()?,
+            other => bail!(
+                "Expected a mesh or a list of meshes, got {}",
+                other.type_name()
+            ),
+        };
+        let pieces = pieces
+            .iter()
+            .map(|mesh| mesh.borrow::<HalfEdgeMesh>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let explosion = super::explode(
+            &pieces.iter().map(|mesh| &**mesh).collect_vec(),
+            origin.0,
+            distance,
+            falloff,
+            amount.unwrap_or(1.0),
+        )?;
+        let table = lua.create_table()?;
+        table.set("mesh", explosion.mesh)?;
+        table.set("offsets", explosion.offsets)?;
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    pub fn test_explode() {
+        let a = Box::build(Vec3::X, Vec3::ONE).unwrap();
+        let b = Box::build(Vec3::X * -2.0, Vec3::ONE).unwrap();
+        let sorted_xs = |mesh: &HalfEdgeMesh| {
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            conn.iter_vertices()
+                .map(|(v, _)| positions[v].x)
+                .sorted_by_key(|x| float_ord::FloatOrd(*x))
+                .collect_vec()
+        };
+
+        // Both boxes move the same with no falloff
+        let explosion = explode(&[&a, &b], Vec3::ZERO, 1.0, 0.0, 1.0).unwrap();
+        let xs = sorted_xs(&explosion.mesh);
+        assert_eq!(xs[0], -3.5);
+        assert_eq!(xs[15], 2.5);
+        assert_eq!(explosion.offsets.read_connectivity().num_vertices(), 4);
+
+        // The same pieces, as islands of a single mesh, with linear falloff
+        // and halfway through the animation
+        let mut merged = a.clone();
+        merged.merge_with(&b);
+        let explosion = explode(&[&merged], Vec3::ZERO, 1.0, 1.0, 0.5).unwrap();
+        let xs = sorted_xs(&explosion.mesh);
+        assert_eq!(xs[0], -3.0);
+        assert_eq!(xs[15], 1.75);
+
+        assert!(explode(&[], Vec3::ZERO, 1.0, 1.0, 1.0).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use slotmap::SecondaryMap;
+
+use crate::prelude::*;
+
+/// The vertex and face channel storing the island of each element.
+pub const ISLAND_CHANNEL: &str = "island";
+
+/// The connected components of a mesh, found by following its edges.
+pub struct Islands {
+    /// The island of each vertex, numbered from zero in the order their
+    /// first vertex is found.
+    pub vertex_island: SecondaryMap<VertexId, usize>,
+    pub num_islands: usize,
+}
+
+impl Islands {
+    pub fn find(conn: &MeshConnectivity) -> Result<Self> {
+        let mut neighbors = HashMap::<VertexId, SVec<VertexId>>::new();
+        for (h, _) in conn.iter_halfedges() {
+            let (src, dst) = conn.at_halfedge(h).src_dst_pair()?;
+            neighbors.entry(src).or_default().push(dst);
+        }
+
+        let mut vertex_island = SecondaryMap::new();
+        let mut num_islands = 0;
+        for (v, _) in conn.iter_vertices() {
+            if vertex_island.contains_key(v) {
+                continue;
+            }
+            vertex_island.insert(v, num_islands);
+            let mut stack = vec![v];
+            while let Some(v) = stack.pop() {
+                for w in neighbors.get(&v).into_iter().flatten().copied() {
+                    if !vertex_island.contains_key(w) {
+                        vertex_island.insert(w, num_islands);
+                        stack.push(w);
+                    }
+                }
+            }
+            num_islands += 1;
+        }
+        Ok(Self {
+            vertex_island,
+            num_islands,
+        })
+    }
+
+    /// Returns the average position of the vertices of each island.
+    pub fn centroids(&self, conn: &MeshConnectivity, positions: &Positions) -> Vec<Vec3> {
+        let mut sums = vec![(Vec3::ZERO, 0); self.num_islands];
+        for (v, _) in conn.iter_vertices() {
+            let (sum, count) = &mut sums[self.vertex_island[v]];
+            *sum += positions[v];
+            *count += 1;
+        }
+        sums.into_iter()
+            .map(|(sum, count)| sum / count as f32)
+            .collect()
+    }
+}
+
+/// Stores the island of each vertex and face of `mesh` in the
+/// [`ISLAND_CHANNEL`], so it can be used to vary other operations per piece.
+/// Islands are numbered from zero. Returns the number of islands.
+pub fn assign_island_ids(mesh: &HalfEdgeMesh) -> Result<usize> {
+    let conn = mesh.read_connectivity();
+    let islands = Islands::find(&conn)?;
+
+    let vertex_ch_id = mesh
+        .channels
+        .ensure_channel::<VertexId, f32>(ISLAND_CHANNEL);
+    let mut vertex_ch = mesh.channels.write_channel(vertex_ch_id)?;
+    for (v, island) in islands.vertex_island.iter() {
+        vertex_ch[v] = *island as f32;
+    }
+
+    let face_ch_id = mesh.channels.ensure_channel::<FaceId, f32>(ISLAND_CHANNEL);
+    let mut face_ch = mesh.channels.write_channel(face_ch_id)?;
+    for (f, _) in conn.iter_faces() {
+        let v = conn.at_face(f).halfedge().vertex().try_end()?;
+        face_ch[f] = islands.vertex_island[v] as f32;
+    }
+    Ok(islands.num_islands)
+}
+
+/// Transforms each island of `mesh` around its centroid. The `transform`
+/// function receives the index of the island and its centroid, and returns
+/// the translation, rotation and scale for that island. Returns the number
+/// of islands.
+pub fn transform_per_island(
+    mesh: &HalfEdgeMesh,
+    mut transform: impl FnMut(usize, Vec3) -> Result<(Vec3, Quat, Vec3)>,
+) -> Result<usize> {
+    let conn = mesh.read_connectivity();
+    let mut positions = mesh.write_positions();
+    let islands = Islands::find(&conn)?;
+    let matrices = islands
+        .centroids(&conn, &positions)
+        .into_iter()
+        .enumerate()
+        .map(|(i, centroid)| {
+            let (translate, rotate, scale) = transform(i, centroid)?;
+            Ok(Mat4::from_translation(centroid + translate)
+                * Mat4::from_scale_rotation_translation(scale, rotate, Vec3::ZERO)
+                * Mat4::from_translation(-centroid))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for (v, island) in islands.vertex_island.iter() {
+        positions[v] = matrices[*island].transform_point3(positions[v]);
+    }
+    Ok(islands.num_islands)
+}
+
+/// Scales each island of `mesh` by `factor`, around its own centroid.
+pub fn scale_islands_from_centroid(mesh: &HalfEdgeMesh, factor: f32) -> Result<usize> {
+    transform_per_island(mesh, |_, _| {
+        Ok((Vec3::ZERO, Quat::IDENTITY, Vec3::splat(factor)))
+    })
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::{LQuat, LVec3};
+
+    /// Returns a copy of `mesh` with the island of each vertex and face,
+    /// numbered from zero, in the `island` channel.
+    #[lua(under = "Ops")]
+    fn assign_island_ids(mesh: &HalfEdgeMesh) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::assign_island_ids(&result)?;
+        Ok(result)
+    }
+
+    /// Returns a copy of `mesh` where each island has been transformed
+    /// around its centroid. The `transform` function is called with the
+    /// index of each island, starting at zero, and its centroid. It returns
+    /// the translation, rotation and scale for the island. Missing values
+    /// leave the island unchanged.
+    #[lua(under = "Ops")]
+    fn transform_per_island(
+        mesh: &HalfEdgeMesh,
+        transform: mlua::Function,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::transform_per_island(&result, |island, centroid| {
+            let (translate, rotate, scale) = transform.call::<_, (
+                Option<LVec3>,
+                Option<LQuat>,
+                Option<LVec3>,
+            )>((island, LVec3(centroid)))?;
+            Ok((
+                translate.map_or(Vec3::ZERO, |t| t.0),
+                rotate.map_or(Quat::IDENTITY, |r| r.0),
+                scale.map_or(Vec3::ONE, |s| s.0),
+            ))
+        })?;
+        Ok(result)
+    }
+
+    /// Returns a copy of `mesh` where each island has been scaled by
+    /// `factor` around its own centroid.
+    #[lua(under = "Ops")]
+    fn scale_islands_from_centroid(mesh: &HalfEdgeMesh, factor: f32) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::scale_islands_from_centroid(&result, factor)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    pub fn test_islands() {
+        let mut mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let other = Box::build(Vec3::X * 3.0, Vec3::ONE).unwrap();
+        mesh.merge_with(&other);
+
+        assert_eq!(assign_island_ids(&mesh).unwrap(), 2);
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let face_ch = mesh
+            .channels
+            .read_channel_by_name::<FaceId, f32>(ISLAND_CHANNEL)
+            .unwrap();
+        for (f, _) in conn.iter_faces() {
+            let v = conn.face_vertices(f)[0];
+            let expected = if positions[v].x > 1.0 { 1.0 } else { 0.0 };
+            assert_eq!(face_ch[f], expected);
+        }
+        drop((conn, positions));
+
+        // Each box keeps its center, but shrinks to half its size
+        scale_islands_from_centroid(&mesh, 0.5).unwrap();
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        for (v, _) in conn.iter_vertices() {
+            let center = if positions[v].x > 1.0 {
+                Vec3::X * 3.0
+            } else {
+                Vec3::ZERO
+            };
+            assert!(((positions[v] - center).abs() - Vec3::splat(0.25)).length() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::f32::consts::PI;
+
+use crate::prelude::*;
+
+use halfedge::primitives::UVSphere;
+
+/// How to join pipes at the vertices of the network where they meet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipeJoinStyle {
+    /// Pipes are plain cylinders meeting at the vertex.
+    None,
+    /// A sphere is placed at every junction.
+    Sphere,
+    /// Pipes are bent around the corners where exactly two pipes meet. Other
+    /// junctions get a sphere.
+    Elbow,
+}
+
+impl PipeJoinStyle {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "None" => Ok(Self::None),
+            "Sphere" => Ok(Self::Sphere),
+            "Elbow" => Ok(Self::Elbow),
+            _ => bail!("Invalid pipe join style: {name}"),
+        }
+    }
+}
+
+/// Accumulates the geometry of the pipes as a list of polygons.
+#[derive(Default)]
+pub(crate) struct PipeBuilder {
+    positions: Vec<Vec3>,
+    polygons: Vec<SVec<usize>>,
+}
+
+impl PipeBuilder {
+    /// Sweeps a circle along the polyline given by `points`, optionally
+    /// closing the tube at its start and end. The `radius` function returns
+    /// the radius of the circle at each point. The rings of the tube are kept
+    /// aligned using parallel transport, to avoid twisting.
+    pub(crate) fn tube(
+        &mut self,
+        points: &[Vec3],
+        radius: impl Fn(usize) -> f32,
+        segments: usize,
+        caps: (bool, bool),
+    ) {
+        if points.len() < 2 {
+            return;
+        }
+        let rings = self.rings(points, radius, segments, false);
+        for (r0, r1) in rings.iter_cpy().tuple_windows() {
+            self.bridge_rings(r0, r1, segments);
+        }
+        if caps.0 {
+            self.polygons
+                .push((0..segments).rev().map(|j| rings[0] + j).collect());
+        }
+        if caps.1 {
+            self.polygons
+                .push((0..segments).map(|j| rings[rings.len() - 1] + j).collect());
+        }
+    }
+
+    /// Like `tube`, but for a closed polyline: The last point connects back
+    /// to the first one, so the tube is a ring with no caps.
+    pub(crate) fn closed_tube(
+        &mut self,
+        points: &[Vec3],
+        radius: impl Fn(usize) -> f32,
+        segments: usize,
+    ) {
+        if points.len() < 3 {
+            return;
+        }
+        let rings = self.rings(points, radius, segments, true);
+        for (r0, r1) in rings.iter_cpy().circular_tuple_windows() {
+            self.bridge_rings(r0, r1, segments);
+        }
+    }
+
+    /// Adds a ring of vertices around each of the `points`, returning the
+    /// index of the first vertex of each ring.
+    fn rings(
+        &mut self,
+        points: &[Vec3],
+        radius: impl Fn(usize) -> f32,
+        segments: usize,
+        closed: bool,
+    ) -> Vec<usize> {
+        let n = points.len();
+        let last = n - 1;
+        let tangent = |i: usize| {
+            let incoming = (closed || i > 0)
+                .then(|| (points[i] - points[(i + n - 1) % n]).normalize_or_zero());
+            let outgoing =
+                (closed || i < last).then(|| (points[(i + 1) % n] - points[i]).normalize_or_zero());
+            match (incoming, outgoing) {
+                (Some(a), Some(b)) => (a + b).try_normalize().unwrap_or(b),
+                (Some(a), None) => a,
+                (None, Some(b)) => b,
+                (None, None) => Vec3::Y,
+            }
+        };
+        let transport = |normal: Vec3, t: Vec3| {
+            normal
+                .reject_from_normalized(t)
+                .try_normalize()
+                .unwrap_or(normal)
+        };
+
+        let mut normals = Vec::with_capacity(n);
+        let mut normal = tangent(0).any_orthonormal_vector();
+        for i in 0..n {
+            normal = transport(normal, tangent(i));
+            normals.push(normal);
+        }
+
+        // On a closed loop, the transported normal doesn't generally come
+        // back to where it started. The mismatch is spread evenly along the
+        // loop, so the tube doesn't have a visible seam.
+        if closed {
+            let t0 = tangent(0);
+            let back = transport(normal, t0);
+            let mismatch = normals[0].cross(back).dot(t0).atan2(normals[0].dot(back));
+            for (i, normal) in normals.iter_mut().enumerate() {
+                let angle = -mismatch * i as f32 / n as f32;
+                *normal = Quat::from_axis_angle(tangent(i), angle) * *normal;
+            }
+        }
+
+        let mut rings = vec![];
+        for (i, point) in points.iter().enumerate() {
+            let normal = normals[i];
+            let binormal = tangent(i).cross(normal);
+            let ring_start = self.positions.len();
+            for j in 0..segments {
+                let angle = 2.0 * PI * j as f32 / segments as f32;
+                self.positions
+                    .push(*point + (normal * angle.cos() + binormal * angle.sin()) * radius(i));
+            }
+            rings.push(ring_start);
+        }
+        rings
+    }
+
+    /// Connects two rings of `segments` vertices with quads.
+    fn bridge_rings(&mut self, r0: usize, r1: usize, segments: usize) {
+        for j in 0..segments {
+            let k = (j + 1) % segments;
+            self.polygons
+                .push(smallvec::smallvec![r0 + j, r0 + k, r1 + k, r1 + j]);
+        }
+    }
+
+    pub(crate) fn build(&self) -> Result<HalfEdgeMesh> {
+        HalfEdgeMesh::build_from_polygons(&self.positions, &self.polygons)
+    }
+}
+
+/// Returns the points of a rounded corner at `corner`, between the segments
+/// going to `prev` and `next`. The corner is cut at `trim` units from it, and
+/// the cut is bridged with a quadratic bezier curve.
+fn elbow_points(prev: Vec3, corner: Vec3, next: Vec3, trim: f32, samples: usize) -> Vec<Vec3> {
+    let start = corner + (prev - corner).normalize_or_zero() * trim;
+    let end = corner + (next - corner).normalize_or_zero() * trim;
+    (0..=samples)
+        .map(|i| {
+            let t = i as f32 / samples as f32;
+            let a = start.lerp(corner, t);
+            let b = corner.lerp(end, t);
+            a.lerp(b, t)
+        })
+        .collect()
+}
+
+/// Skins the edges of `network`, a mesh made of lines, with tubes of the
+/// given `radius` and number of `segments`. Vertices where pipes meet are
+/// joined according to `join_style`. When `flanges` is set, open ends of the
+/// network get a flange ring.
+pub fn pipes(
+    network: &HalfEdgeMesh,
+    radius: f32,
+    segments: usize,
+    join_style: PipeJoinStyle,
+    flanges: bool,
+) -> Result<HalfEdgeMesh> {
+    if radius <= 0.0 {
+        bail!("Pipe radius must be positive");
+    }
+    if segments < 3 {
+        bail!("Pipes need at least 3 segments");
+    }
+
+    let conn = network.read_connectivity();
+    let positions = network.read_positions();
+
+    let mut neighbors = HashMap::<VertexId, SVec<VertexId>>::new();
+    for (v, _) in conn.iter_vertices() {
+        let adjacent = conn
+            .at_vertex(v)
+            .outgoing_halfedges()?
+            .iter_cpy()
+            .map(|h| conn.at_halfedge(h).dst_vertex().try_end())
+            .collect::<Result<SVec<_>, _>>()?;
+        neighbors.insert(v, adjacent);
+    }
+    let degree = |v: VertexId| neighbors[&v].len();
+
+    // Split the network in chains: Paths whose inner vertices join exactly
+    // two edges. Each chain becomes a single tube. Vertices with a degree
+    // other than two are visited first. Closed loops have none, so they are
+    // picked up from any of their vertices once the rest has been visited.
+    let mut visited = HashSet::<(VertexId, VertexId)>::new();
+    let mut chains: Vec<Vec<VertexId>> = vec![];
+    let starts = conn
+        .iter_vertices()
+        .map(|(v, _)| v)
+        .sorted_by_key(|v| degree(*v) == 2)
+        .collect_vec();
+    for start in starts {
+        for first in neighbors[&start].iter_cpy() {
+            if !visited.insert((start, first)) {
+                continue;
+            }
+            visited.insert((first, start));
+            let mut chain = vec![start, first];
+            let (mut prev, mut current) = (start, first);
+            while degree(current) == 2 && current != start {
+                let next = neighbors[&current]
+                    .iter_cpy()
+                    .find(|n| *n != prev)
+                    .unwrap_or(prev);
+                if !visited.insert((current, next)) {
+                    break;
+                }
+                visited.insert((next, current));
+                chain.push(next);
+                prev = current;
+                current = next;
+            }
+            chains.push(chain);
+        }
+    }
+
+    let mut builder = PipeBuilder::default();
+    for chain in &chains {
+        let mut points = vec![positions[chain[0]]];
+        for (prev, v, next) in chain.iter_cpy().tuple_windows() {
+            if join_style == PipeJoinStyle::Elbow {
+                let (p, c, n) = (positions[prev], positions[v], positions[next]);
+                let trim = (radius * 2.0).min(0.45 * c.distance(p).min(c.distance(n)));
+                points.extend(elbow_points(p, c, n, trim, 6));
+            } else {
+                points.push(positions[v]);
+            }
+        }
+        points.push(positions[chain[chain.len() - 1]]);
+        points.dedup();
+        if points.len() < 2 {
+            continue;
+        }
+        builder.tube(&points, |_| radius, segments, (true, true));
+
+        if flanges {
+            let last = points.len() - 1;
+            let ends = [
+                (chain[0], points[0], points[1]),
+                (chain[chain.len() - 1], points[last], points[last - 1]),
+            ];
+            for (v, end, inner) in ends {
+                if degree(v) == 1 {
+                    let dir = (inner - end).normalize_or_zero();
+                    builder.tube(
+                        &[end, end + dir * radius * 0.5],
+                        |_| radius * 1.5,
+                        segments,
+                        (true, true),
+                    );
+                }
+            }
+        }
+    }
+
+    let mut mesh = builder.build()?;
+
+    if join_style != PipeJoinStyle::None {
+        for (v, _) in conn.iter_vertices() {
+            let is_junction = match join_style {
+                PipeJoinStyle::Sphere => degree(v) >= 2,
+                _ => degree(v) >= 3,
+            };
+            if is_junction {
+                let rings = (segments as u32 / 2).max(3);
+                mesh.merge_with(&UVSphere::build(
+                    positions[v],
+                    segments as u32,
+                    rings,
+                    radius * 1.2,
+                )?);
+            }
+        }
+    }
+
+    Ok(mesh)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Skins a network of lines with pipes of the given `radius`, made of
+    /// `segments` sides. The `join_style` can be "None", "Sphere" or "Elbow".
+    /// When `flanges` is set, the open ends of the pipes get a flange ring.
+    #[lua(under = "Ops")]
+    fn pipes(
+        network: &HalfEdgeMesh,
+        radius: f32,
+        segments: usize,
+        join_style: String,
+        flanges: bool,
+    ) -> Result<HalfEdgeMesh> {
+        super::pipes(
+            network,
+            radius,
+            segments,
+            PipeJoinStyle::from_name(&join_style)?,
+            flanges,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Line;
+
+    #[test]
+    pub fn test_pipes() {
+        let mut network =
+            Line::build_from_points(vec![Vec3::ZERO, Vec3::X, Vec3::X + Vec3::Y]).unwrap();
+        network.merge_with(&Line::build_from_points(vec![Vec3::Z, Vec3::Z * 2.0]).unwrap());
+
+        // Each tube has a quad per segment between consecutive rings, plus its
+        // two caps. The bent pipe has 3 rings, or 9 with an elbow, and the
+        // straight one has 2. Each of the 4 open ends gets a 2 ring flange.
+        // With spheres, the corner of the bent pipe gets an 8x4 sphere.
+        let flange = (8 + 2, 16);
+        let cases = [
+            (PipeJoinStyle::None, false, (18 + 10, 24 + 16)),
+            (
+                PipeJoinStyle::None,
+                true,
+                (18 + 10 + 4 * flange.0, 24 + 16 + 4 * flange.1),
+            ),
+            (
+                PipeJoinStyle::Sphere,
+                true,
+                (18 + 10 + 4 * flange.0 + 32, 24 + 16 + 4 * flange.1 + 26),
+            ),
+            (
+                PipeJoinStyle::Elbow,
+                true,
+                (66 + 10 + 4 * flange.0, 72 + 16 + 4 * flange.1),
+            ),
+        ];
+        for (style, flanges, (faces, vertices)) in cases {
+            let mesh = pipes(&network, 0.1, 8, style, flanges).unwrap();
+            let conn = mesh.read_connectivity();
+            assert_eq!(conn.num_faces(), faces, "{style:?}");
+            assert_eq!(conn.num_vertices(), vertices, "{style:?}");
+            // All the tubes are capped, so the result is closed
+            assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+        }
+    }
+}
@@ -0,0 +1,459 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::f32::consts::PI;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::prelude::*;
+
+/// Maximum number of candidate points that are tested for every point in
+/// the result. Avoids looping forever when the density is zero everywhere.
+const MAX_ATTEMPTS_PER_POINT: usize = 100;
+
+/// Controls where points are more likely to appear when scattering.
+pub enum ScatterDensity {
+    /// Points are uniformly distributed over the surface.
+    Uniform,
+    /// The density is read from the vertex channel with the given name, and
+    /// interpolated over the faces. Values should be between 0 and 1.
+    Channel(String),
+    /// The density is read from a grayscale texture, using the UV coordinates
+    /// of the mesh. White means maximum density.
+    Texture(image::GrayImage),
+}
+
+impl ScatterDensity {
+    /// Builds the density for the given `mode`, one of "Uniform", "Channel"
+    /// or "Texture". The `source` is the channel name or the texture path,
+    /// and is ignored in uniform mode.
+    pub fn new(mode: &str, source: String) -> Result<Self> {
+        Ok(match mode {
+            "Uniform" => ScatterDensity::Uniform,
+            "Channel" => ScatterDensity::Channel(source),
+            "Texture" => {
+                if source.is_empty() {
+                    bail!("No density texture selected");
+                }
+                let texture = image::open(&source)
+                    .with_context(|| format!("Could not load density texture '{source}'"))?;
+                ScatterDensity::Texture(texture.to_luma8())
+            }
+            _ => bail!("Invalid density mode: {mode}"),
+        })
+    }
+}
+
+/// A triangle of the mesh surface, as a candidate to place points on.
+struct SurfaceTriangle {
+    positions: [Vec3; 3],
+    densities: [f32; 3],
+    uvs: [Vec3; 3],
+    normal: Vec3,
+}
+
+impl SurfaceTriangle {
+    fn area(&self) -> f32 {
+        let [a, b, c] = self.positions;
+        0.5 * (b - a).cross(c - a).length()
+    }
+}
+
+/// Returns the value of the `texture` at the given `uv` coordinates, between
+/// 0 and 1.
+pub(super) fn sample_texture(texture: &image::GrayImage, uv: Vec3) -> f32 {
+    let (width, height) = texture.dimensions();
+    if width == 0 || height == 0 {
+        return 0.0;
+    }
+    // Textures repeat outside the [0, 1] range. The V axis points up, but
+    // image rows go downwards.
+    let x = (uv.x.rem_euclid(1.0) * width as f32) as u32;
+    let y = ((1.0 - uv.y.rem_euclid(1.0)) * height as f32) as u32;
+    texture.get_pixel(x.min(width - 1), y.min(height - 1)).0[0] as f32 / 255.0
+}
+
+/// Scatters `count` points over the surface of `mesh`. Points are more
+/// likely to appear where the `density` is higher. The same `seed` always
+/// gives the same points.
+///
+/// When `min_distance` is positive, points closer than that distance to an
+/// existing point are discarded (i.e. Poisson disk sampling by dart throwing).
+/// In that case, fewer than `count` points may be returned when there is not
+/// enough room on the surface to fit them all.
+///
+/// The result is a point cloud, with "normal" and "tangent" channels so that
+/// instances placed with `copy_to_points` stand upright on the surface, with
+/// a random rotation.
+pub fn scatter_points(
+    mesh: &HalfEdgeMesh,
+    count: usize,
+    density: &ScatterDensity,
+    min_distance: f32,
+    seed: u32,
+) -> Result<HalfEdgeMesh> {
+    use rstar::RTree;
+
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let uvs = mesh.read_uvs();
+    let density_ch = match density {
+        ScatterDensity::Channel(name) => Some(
+            mesh.channels
+                .read_channel_by_name::<VertexId, f32>(name)
+                .map_err(|err| anyhow!("Cannot read density channel '{name}': {err}"))?,
+        ),
+        _ => None,
+    };
+    if matches!(density, ScatterDensity::Texture(_)) && uvs.is_none() {
+        bail!("Scattering with a density texture requires the mesh to have UVs");
+    }
+
+    let mut triangles = vec![];
+    for (face, _) in conn.iter_faces() {
+        let halfedges = conn.face_edges(face);
+        let corner = |h: HalfEdgeId| -> Result<(Vec3, f32, Vec3)> {
+            let v = conn.at_halfedge(h).vertex().try_end()?;
+            let density = density_ch.as_ref().map(|ch| ch[v]).unwrap_or(1.0);
+            let uv = uvs.as_ref().map(|uvs| uvs[h]).unwrap_or(Vec3::ZERO);
+            Ok((positions[v], density, uv))
+        };
+        if halfedges.len() < 3 {
+            continue;
+        }
+        let first = corner(halfedges[0])?;
+        for (h1, h2) in halfedges[1..].iter_cpy().tuple_windows() {
+            let corners = [first, corner(h1)?, corner(h2)?];
+            let [a, b, c] = corners.map(|(p, _, _)| p);
+            let triangle = SurfaceTriangle {
+                positions: [a, b, c],
+                densities: corners.map(|(_, d, _)| d.clamp(0.0, 1.0)),
+                uvs: corners.map(|(_, _, uv)| uv),
+                normal: (a - b).cross(b - c).normalize_or_zero(),
+            };
+            if triangle.area() > f32::EPSILON {
+                triangles.push(triangle);
+            }
+        }
+    }
+
+    // Cumulative area, to pick triangles with a probability proportional to
+    // their area.
+    let cumulative_area = triangles
+        .iter()
+        .scan(0.0, |acc, tri| {
+            *acc += tri.area();
+            Some(*acc)
+        })
+        .collect_vec();
+    let total_area = cumulative_area.last().copied().unwrap_or(0.0);
+
+    let mut rng = StdRng::seed_from_u64(seed as u64);
+    let mut points = vec![];
+    // Spatial index of the accepted points, to efficiently check the minimum
+    // distance with the new candidates.
+    let mut index = RTree::<[f32; 3]>::new();
+    if total_area > 0.0 {
+        for _ in 0..count * MAX_ATTEMPTS_PER_POINT {
+            if points.len() >= count {
+                break;
+            }
+            let target = rng.gen_range(0.0..total_area);
+            let idx = cumulative_area
+                .partition_point(|area| *area < target)
+                .min(triangles.len() - 1);
+            let tri = &triangles[idx];
+
+            // Uniform sampling of barycentric coordinates
+            let (r1, r2) = (rng.gen_range(0.0f32..1.0).sqrt(), rng.gen_range(0.0..1.0));
+            let bary = Vec3::new(1.0 - r1, r1 * (1.0 - r2), r1 * r2);
+            let interpolate = |v: [Vec3; 3]| v[0] * bary.x + v[1] * bary.y + v[2] * bary.z;
+
+            let point_density = match density {
+                ScatterDensity::Uniform => 1.0,
+                ScatterDensity::Channel(_) => Vec3::from(tri.densities).dot(bary),
+                ScatterDensity::Texture(texture) => sample_texture(texture, interpolate(tri.uvs)),
+            };
+            if rng.gen_range(0.0..1.0) >= point_density {
+                continue;
+            }
+
+            let position = interpolate(tri.positions);
+            if min_distance > 0.0 {
+                let too_close = index
+                    .locate_within_distance(position.to_array(), min_distance * min_distance)
+                    .next()
+                    .is_some();
+                if too_close {
+                    continue;
+                }
+                index.insert(position.to_array());
+            }
+
+            let rotation = Quat::from_axis_angle(tri.normal, rng.gen_range(0.0..2.0 * PI));
+            points.push((
+                position,
+                tri.normal,
+                rotation * tri.normal.any_orthonormal_vector(),
+            ));
+        }
+    }
+
+    let mut result = HalfEdgeMesh::new();
+    let normal_ch_id = result.channels.ensure_channel::<VertexId, Vec3>("normal");
+    let tangent_ch_id = result.channels.ensure_channel::<VertexId, Vec3>("tangent");
+    {
+        let mut conn = result.write_connectivity();
+        let mut positions = result.write_positions();
+        let mut normal_ch = result.channels.write_channel(normal_ch_id)?;
+        let mut tangent_ch = result.channels.write_channel(tangent_ch_id)?;
+        for (position, normal, tangent) in points {
+            let v = conn.alloc_vertex(&mut positions, position, None);
+            normal_ch[v] = normal;
+            tangent_ch[v] = tangent;
+        }
+    }
+    Ok(result)
+}
+
+/// A triangle of a collider mesh, stored in a spatial index.
+struct ColliderTriangle([Vec3; 3]);
+
+impl rstar::RTreeObject for ColliderTriangle {
+    type Envelope = rstar::AABB<[f32; 3]>;
+    fn envelope(&self) -> Self::Envelope {
+        let [a, b, c] = self.0;
+        rstar::AABB::from_corners(a.min(b).min(c).to_array(), a.max(b).max(c).to_array())
+    }
+}
+
+impl ColliderTriangle {
+    /// Returns the point of the triangle closest to `p`. Adapted from
+    /// "Real-Time Collision Detection", by Christer Ericson.
+    fn closest_point(&self, p: Vec3) -> Vec3 {
+        let [a, b, c] = self.0;
+        let (ab, ac, ap) = (b - a, c - a, p - a);
+        let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+        let bp = p - b;
+        let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return a + ab * (d1 / (d1 - d3));
+        }
+        let cp = p - c;
+        let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+        if d6 >= 0.0 && d5 <= d6 {
+            return c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return a + ac * (d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+        let denom = 1.0 / (va + vb + vc);
+        a + ab * (vb * denom) + ac * (vc * denom)
+    }
+
+    /// Returns whether a ray from `origin` towards +X hits this triangle.
+    fn hit_by_x_ray(&self, origin: Vec3) -> bool {
+        let [a, b, c] = self.0;
+        let (e1, e2) = (b - a, c - a);
+        let h = Vec3::X.cross(e2);
+        let det = e1.dot(h);
+        if det.abs() < f32::EPSILON {
+            return false;
+        }
+        let s = origin - a;
+        let u = s.dot(h) / det;
+        let q = s.cross(e1);
+        let v = Vec3::X.dot(q) / det;
+        let t = e2.dot(q) / det;
+        (0.0..=1.0).contains(&u) && v >= 0.0 && u + v <= 1.0 && t > 0.0
+    }
+}
+
+/// Returns the radius of the smallest sphere centered at the origin that
+/// contains all the vertices of `mesh`.
+fn bounding_radius(mesh: &HalfEdgeMesh) -> f32 {
+    mesh.read_positions()
+        .iter()
+        .fold(0.0, |radius, (_, pos)| radius.max(pos.length()))
+}
+
+/// Removes the points in `points` where placing a copy of `instance` with
+/// `copy_to_points` would intersect the `collider` mesh. Instances are
+/// approximated by their bounding sphere, scaled by the "size" channel of
+/// the points when present. Points inside a closed collider are also removed.
+pub fn prune_colliding_points(
+    points: &HalfEdgeMesh,
+    instance: &HalfEdgeMesh,
+    collider: &HalfEdgeMesh,
+) -> Result<()> {
+    use rstar::{RTree, AABB};
+
+    let index = {
+        let conn = collider.read_connectivity();
+        let positions = collider.read_positions();
+        let mut triangles = vec![];
+        for (face, _) in conn.iter_faces() {
+            let vertices = conn.face_vertices(face);
+            if vertices.len() < 3 {
+                continue;
+            }
+            for (v1, v2) in vertices[1..].iter_cpy().tuple_windows() {
+                triangles.push(ColliderTriangle([
+                    positions[vertices[0]],
+                    positions[v1],
+                    positions[v2],
+                ]));
+            }
+        }
+        RTree::bulk_load(triangles)
+    };
+    let max_x = index.root().envelope().upper()[0];
+
+    let radius = bounding_radius(instance);
+    let size_ch = points
+        .channels
+        .read_channel_by_name::<VertexId, f32>("size");
+
+    let mut conn = points.write_connectivity();
+    let positions = points.read_positions();
+    let colliding = conn
+        .iter_vertices()
+        .map(|(v, _)| v)
+        .filter(|v| {
+            let p = positions[*v];
+            let r = radius * size_ch.as_ref().map(|size| size[*v]).unwrap_or(1.0);
+            let query = AABB::from_corners((p - r).to_array(), (p + r).to_array());
+            let intersects = index
+                .locate_in_envelope_intersecting(&query)
+                .any(|tri| tri.closest_point(p).distance_squared(p) <= r * r);
+
+            // A point is inside a closed mesh if a ray starting at it
+            // crosses the surface an odd number of times.
+            let ray = AABB::from_corners(p.to_array(), [max_x, p.y, p.z]);
+            let crossings = index
+                .locate_in_envelope_intersecting(&ray)
+                .filter(|tri| tri.hit_by_x_ray(p))
+                .count();
+            intersects || crossings % 2 == 1
+        })
+        .collect_vec();
+
+    for v in colliding {
+        conn.remove_vertex(v);
+    }
+    Ok(())
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Scatters `count` points over the surface of `mesh`, using the given
+    /// `seed`. The `density_mode` can be "Uniform", "Channel" or "Texture".
+    /// For the last two, `density_source` is the name of a vertex channel or
+    /// the path to a grayscale image, which make points more likely to appear
+    /// where their values are higher. When given, `min_distance` is the
+    /// minimum separation between the points.
+    #[lua(under = "Ops")]
+    fn scatter_points(
+        mesh: &HalfEdgeMesh,
+        count: usize,
+        density_mode: String,
+        density_source: String,
+        seed: u32,
+        min_distance: Option<f32>,
+    ) -> Result<HalfEdgeMesh> {
+        let density = ScatterDensity::new(&density_mode, density_source)?;
+        super::scatter_points(mesh, count, &density, min_distance.unwrap_or(0.0), seed)
+    }
+
+    /// Removes the points where a copy of `instance`, placed with
+    /// `Ops.copy_to_points`, would intersect the `collider` mesh. Instances are
+    /// approximated by their bounding sphere.
+    #[lua(under = "Ops")]
+    fn prune_colliding_points(
+        points: &mut HalfEdgeMesh,
+        instance: &HalfEdgeMesh,
+        collider: &HalfEdgeMesh,
+    ) -> Result<()> {
+        super::prune_colliding_points(points, instance, collider)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::{Box, UVSphere};
+
+    #[test]
+    pub fn test_scatter_points() {
+        let mut mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let points = scatter_points(&mesh, 50, &ScatterDensity::Uniform, 0.0, 0).unwrap();
+        assert_eq!(points.read_connectivity().num_vertices(), 50);
+
+        // Only the top vertices have density, so points avoid the bottom face
+        let ch_id = mesh.channels.ensure_channel::<VertexId, f32>("density");
+        {
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            let mut density = mesh.channels.write_channel(ch_id).unwrap();
+            for (v, _) in conn.iter_vertices() {
+                density[v] = if positions[v].y > 0.0 { 1.0 } else { 0.0 };
+            }
+        }
+        let density = ScatterDensity::Channel("density".into());
+        let points = scatter_points(&mesh, 50, &density, 0.0, 0).unwrap();
+        assert!(points
+            .read_positions()
+            .iter()
+            .all(|(_, pos)| pos.y > -0.5 + f32::EPSILON));
+
+        let points = scatter_points(&mesh, 50, &ScatterDensity::Uniform, 0.2, 0).unwrap();
+        let positions = points.read_positions();
+        for ((_, a), (_, b)) in positions.iter().tuple_combinations() {
+            assert!(a.distance(*b) >= 0.2);
+        }
+    }
+
+    #[test]
+    pub fn test_scatter_density_modes() {
+        // The source is only read in the modes that need it
+        assert!(matches!(
+            ScatterDensity::new("Uniform", String::new()),
+            Ok(ScatterDensity::Uniform)
+        ));
+        let err = ScatterDensity::new("Texture", String::new()).err().unwrap();
+        assert_eq!(err.to_string(), "No density texture selected");
+        assert!(ScatterDensity::new("Gradient", String::new()).is_err());
+    }
+
+    #[test]
+    pub fn test_prune_colliding_points() {
+        let ground = Box::build(Vec3::ZERO, Vec3::new(10.0, 1.0, 10.0)).unwrap();
+        let points = scatter_points(&ground, 100, &ScatterDensity::Uniform, 0.0, 0).unwrap();
+        let building = Box::build(Vec3::new(0.0, 1.5, 0.0), Vec3::new(4.0, 2.0, 4.0)).unwrap();
+        let instance = UVSphere::build(Vec3::ZERO, 8, 8, 0.25).unwrap();
+        prune_colliding_points(&points, &instance, &building).unwrap();
+
+        let positions = points.read_positions();
+        for (v, _) in points.read_connectivity().iter_vertices() {
+            let p = positions[v];
+            assert!(p.x.abs() > 2.0 || p.z.abs() > 2.0 || p.y < 0.25);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::f32::consts::PI;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::prelude::*;
+
+use super::pipes::PipeBuilder;
+
+/// The golden angle, in radians. Used to distribute the child branches around
+/// their parent without overlapping.
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+/// Number of points along each branch. Branches are bent slightly at each
+/// point, so they don't look perfectly straight.
+const BRANCH_POINTS: usize = 5;
+
+/// Parameters controlling the shape of a procedural tree.
+#[derive(Clone, Debug)]
+pub struct TreeParams {
+    /// Height of the trunk.
+    pub trunk_height: f32,
+    /// Radius at the base of the trunk.
+    pub trunk_radius: f32,
+    /// How many branches sprout from each branch of the previous level.
+    pub branches_per_level: usize,
+    /// Angle between a branch and its parent, in radians.
+    pub branch_angle: f32,
+    /// Length of each branch level, relative to the previous one.
+    pub length_ratio: f32,
+    /// Radius of each branch level, relative to the previous one.
+    pub radius_ratio: f32,
+    /// Amount of random bending along each branch, in radians.
+    pub gnarl: f32,
+    /// Number of sides of the branch cross-sections.
+    pub segments: usize,
+}
+
+impl Default for TreeParams {
+    fn default() -> Self {
+        Self {
+            trunk_height: 3.0,
+            trunk_radius: 0.15,
+            branches_per_level: 4,
+            branch_angle: 0.7,
+            length_ratio: 0.6,
+            radius_ratio: 0.5,
+            gnarl: 0.15,
+            segments: 8,
+        }
+    }
+}
+
+struct Branch {
+    start: Vec3,
+    direction: Vec3,
+    length: f32,
+    radius: f32,
+    level: usize,
+}
+
+/// Generates a tree with the given `params`. The trunk grows upwards from
+/// the origin and splits into `branch_levels` levels of branches. When a
+/// `leaf_mesh` is given, copies of it are placed along the outermost
+/// branches, oriented with their Z axis pointing along the branch.
+///
+/// The `seed` controls all the random variations, so the same seed always
+/// produces the same tree.
+pub fn tree(
+    seed: u32,
+    params: &TreeParams,
+    branch_levels: usize,
+    leaf_mesh: Option<&HalfEdgeMesh>,
+) -> Result<HalfEdgeMesh> {
+    if params.trunk_height <= 0.0 || params.trunk_radius <= 0.0 {
+        bail!("Trunk height and radius must be positive");
+    }
+    if params.segments < 3 {
+        bail!("Branches need at least 3 segments");
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed as u64);
+    let mut builder = PipeBuilder::default();
+    let mut leaves = vec![];
+
+    let mut pending = vec![Branch {
+        start: Vec3::ZERO,
+        direction: Vec3::Y,
+        length: params.trunk_height,
+        radius: params.trunk_radius,
+        level: 0,
+    }];
+    while let Some(branch) = pending.pop() {
+        let step = branch.length / (BRANCH_POINTS - 1) as f32;
+        let mut points = vec![branch.start];
+        let mut direction = branch.direction;
+        for _ in 1..BRANCH_POINTS {
+            let axis = direction.any_orthonormal_vector();
+            let axis = Quat::from_axis_angle(direction, rng.gen_range(0.0..2.0 * PI)) * axis;
+            direction =
+                Quat::from_axis_angle(axis, rng.gen_range(0.0..=params.gnarl.max(0.0))) * direction;
+            points.push(*points.last().unwrap() + direction * step);
+        }
+
+        // Branches taper towards the radius of the next level
+        let end_radius = branch.radius * params.radius_ratio;
+        builder.tube(
+            &points,
+            |i| {
+                lerp(
+                    branch.radius,
+                    end_radius,
+                    i as f32 / (BRANCH_POINTS - 1) as f32,
+                )
+            },
+            params.segments,
+            (branch.level == 0, true),
+        );
+
+        if branch.level == branch_levels {
+            // Leaves grow along the second half of the outermost branches
+            for (a, b) in points[BRANCH_POINTS / 2..].iter_cpy().tuple_windows() {
+                leaves.push((b, (b - a).normalize()));
+            }
+            continue;
+        }
+
+        let phase = rng.gen_range(0.0..2.0 * PI);
+        for i in 0..params.branches_per_level {
+            // Children sprout from the upper part of their parent
+            let t = lerp(
+                0.3,
+                1.0,
+                (i as f32 + rng.gen_range(0.0..1.0)) / params.branches_per_level as f32,
+            );
+            let index = t * (BRANCH_POINTS - 1) as f32;
+            let (i0, i1) = (
+                index.floor() as usize,
+                (index.ceil() as usize).min(BRANCH_POINTS - 1),
+            );
+            let start = points[i0].lerp(points[i1], index.fract());
+            let parent_dir = (points[i1.max(1)] - points[i1.max(1) - 1]).normalize();
+
+            let side = Quat::from_axis_angle(parent_dir, phase + i as f32 * GOLDEN_ANGLE)
+                * parent_dir.any_orthonormal_vector();
+            let angle = params.branch_angle * rng.gen_range(0.8..1.2);
+            let direction =
+                Quat::from_axis_angle(side.cross(parent_dir).normalize(), angle) * parent_dir;
+
+            pending.push(Branch {
+                start,
+                direction,
+                length: branch.length * params.length_ratio * rng.gen_range(0.8..1.2),
+                radius: lerp(branch.radius, end_radius, t) * params.radius_ratio,
+                level: branch.level + 1,
+            });
+        }
+    }
+
+    let mut mesh = builder.build()?;
+
+    if let Some(leaf_mesh) = leaf_mesh {
+        let mut points = HalfEdgeMesh::new();
+        let normal_ch_id = points.channels.ensure_channel::<VertexId, Vec3>("normal");
+        let tangent_ch_id = points.channels.ensure_channel::<VertexId, Vec3>("tangent");
+        {
+            let mut conn = points.write_connectivity();
+            let mut positions = points.write_positions();
+            let mut normal_ch = points.channels.write_channel(normal_ch_id)?;
+            let mut tangent_ch = points.channels.write_channel(tangent_ch_id)?;
+            for (position, tangent) in leaves {
+                let v = conn.alloc_vertex(&mut positions, position, None);
+                let normal = Quat::from_axis_angle(tangent, rng.gen_range(0.0..2.0 * PI))
+                    * tangent.any_orthonormal_vector();
+                normal_ch[v] = normal;
+                tangent_ch[v] = tangent;
+            }
+        }
+        mesh.merge_with(&super::copy_to_points(&points, leaf_mesh)?);
+    }
+
+    Ok(mesh)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Generates a tree with the given `seed` and `branch_levels`. The
+    /// `trunk_params` table may set any of the fields in `TreeParams`, such
+    /// as `trunk_height` or `branch_angle`, with the rest taking their default
+    /// values. When a `leaf_mesh` is given, it is copied along the outermost
+    /// branches.
+    #[lua(under = "Ops")]
+    fn tree(
+        seed: u32,
+        trunk_params: mlua::Table,
+        branch_levels: usize,
+        leaf_mesh: Option<mlua::AnyUserData>,
+    ) -> Result<HalfEdgeMesh> {
+        let defaults = TreeParams::default();
+        let params = TreeParams {
+            trunk_height: trunk_params
+                .get::<_, Option<f32>>("trunk_height")?
+                .unwrap_or(defaults.trunk_height),
+            trunk_radius: trunk_params
+                .get::<_, Option<f32>>("trunk_radius")?
+                .unwrap_or(defaults.trunk_radius),
+            branches_per_level: trunk_params
+                .get::<_, Option<usize>>("branches_per_level")?
+                .unwrap_or(defaults.branches_per_level),
+            branch_angle: trunk_params
+                .get::<_, Option<f32>>("branch_angle")?
+                .unwrap_or(defaults.branch_angle),
+            length_ratio: trunk_params
+                .get::<_, Option<f32>>("length_ratio")?
+                .unwrap_or(defaults.length_ratio),
+            radius_ratio: trunk_params
+                .get::<_, Option<f32>>("radius_ratio")?
+                .unwrap_or(defaults.radius_ratio),
+            gnarl: trunk_params
+                .get::<_, Option<f32>>("gnarl")?
+                .unwrap_or(defaults.gnarl),
+            segments: trunk_params
+                .get::<_, Option<usize>>("segments")?
+                .unwrap_or(defaults.segments),
+        };
+        let leaf_mesh = leaf_mesh
+            .map(|leaf_mesh| leaf_mesh.borrow::<HalfEdgeMesh>())
+            .transpose()?;
+        super::tree(seed, &params, branch_levels, leaf_mesh.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Quad;
+
+    #[test]
+    pub fn test_tree() {
+        let leaf = Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE * 0.1).unwrap();
+        let params = TreeParams::default();
+        let a = tree(7, &params, 2, Some(&leaf)).unwrap();
+        let b = tree(7, &params, 2, Some(&leaf)).unwrap();
+        // Same seed, same tree
+        assert_eq!(
+            a.read_positions().iter().map(|(_, p)| *p).collect_vec(),
+            b.read_positions().iter().map(|(_, p)| *p).collect_vec(),
+        );
+        tree(0, &params, 0, None).unwrap();
+    }
+}
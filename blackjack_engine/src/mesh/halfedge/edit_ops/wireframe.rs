@@ -0,0 +1,208 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::prelude::*;
+
+use super::newell_normal;
+
+/// The shape given to the edges by [`wireframe`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireframeMode {
+    /// A flat strip of quads along the surface of the mesh.
+    Flat,
+    /// A closed box frame around each edge, with the same thickness across
+    /// and along the surface normal.
+    Frame,
+}
+
+impl WireframeMode {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Flat" => Ok(Self::Flat),
+            "Frame" => Ok(Self::Frame),
+            _ => bail!("Invalid wireframe mode: {name}"),
+        }
+    }
+}
+
+/// Returns the point where the corner of a face at `pos` moves when the two
+/// edges meeting there, towards `prev` and `next`, are offset inwards by
+/// `offset`. The face winding is counter-clockwise around `normal`. The point
+/// never goes farther than half the length of the shortest edge, so thick
+/// wireframes on small faces stay closed instead of folding over.
+fn inset_corner(pos: Vec3, prev: Vec3, next: Vec3, normal: Vec3, offset: f32) -> Vec3 {
+    let to_prev = prev - pos;
+    let to_next = next - pos;
+    let max_distance = 0.5 * to_prev.length().min(to_next.length());
+    let (to_prev, to_next) = (to_prev.normalize_or_zero(), to_next.normalize_or_zero());
+    let bisector = to_prev + to_next;
+    if bisector.length_squared() < 1e-8 {
+        // Straight corners move perpendicular to the edges
+        return pos + normal.cross(to_next) * offset.min(max_distance);
+    }
+    let bisector = bisector.normalize();
+    let sin_half_angle = to_next.cross(bisector).length().max(1e-4);
+    let sign = if bisector.dot(normal.cross(to_next)) < 0.0 {
+        // Reflex corners of concave faces
+        -1.0
+    } else {
+        1.0
+    };
+    pos + sign * bisector * (offset / sin_half_angle).min(max_distance)
+}
+
+/// Builds a wireframe of `mesh`, where every edge becomes a strip of width
+/// `thickness` or, in [`WireframeMode::Frame`] mode, a closed bar with a
+/// square section. The strips of each face meet at the edges, so the result
+/// is a single lattice. Edges without faces are skipped.
+///
+/// Unless `replace` is set, the original mesh is kept along with the
+/// wireframe. Note that only vertex positions are kept in the wireframe.
+pub fn wireframe(
+    mesh: &HalfEdgeMesh,
+    thickness: f32,
+    mode: WireframeMode,
+    replace: bool,
+) -> Result<HalfEdgeMesh> {
+    if thickness <= 0.0 {
+        bail!("The wireframe thickness should be positive");
+    }
+    let conn = mesh.read_connectivity();
+    let mesh_positions = mesh.read_positions();
+    let half = 0.5 * thickness;
+
+    // Vertex normals are the average of the normals of their faces
+    let mut face_normals = HashMap::new();
+    let mut vertex_normals = HashMap::<VertexId, Vec3>::new();
+    for (face, _) in conn.iter_faces() {
+        let vertices = conn.face_vertices(face);
+        let points = vertices.iter().map(|v| mesh_positions[*v]).collect_vec();
+        let normal = newell_normal(&points).normalize_or_zero();
+        face_normals.insert(face, normal);
+        for v in vertices {
+            *vertex_normals.entry(v).or_default() += normal;
+        }
+    }
+
+    // In frame mode, each vertex gets a top and a bottom copy, offset along
+    // its normal. In flat mode, both copies are the same point.
+    let mut positions = vec![];
+    let mut push_point = |pos: Vec3, normal: Vec3| match mode {
+        WireframeMode::Flat => {
+            positions.push(pos);
+            (positions.len() - 1, positions.len() - 1)
+        }
+        WireframeMode::Frame => {
+            positions.push(pos + normal * half);
+            positions.push(pos - normal * half);
+            (positions.len() - 2, positions.len() - 1)
+        }
+    };
+    let mut outer = HashMap::new();
+    for (v, _) in conn.iter_vertices() {
+        if let Some(normal) = vertex_normals.get(&v) {
+            outer.insert(v, push_point(mesh_positions[v], normal.normalize_or_zero()));
+        }
+    }
+
+    let mut polygons = vec![];
+    for (face, _) in conn.iter_faces() {
+        let vertices = conn.face_vertices(face);
+        let n = vertices.len();
+        let normal = face_normals[&face];
+
+        // The inner corners of the face, where the strips of its edges end
+        let inner = (0..n)
+            .map(|i| {
+                let pos = mesh_positions[vertices[i]];
+                let prev = mesh_positions[vertices[(i + n - 1) % n]];
+                let next = mesh_positions[vertices[(i + 1) % n]];
+                push_point(inset_corner(pos, prev, next, normal, half), normal)
+            })
+            .collect_vec();
+
+        for (i, h) in conn.face_edges(face).iter_cpy().enumerate() {
+            let j = (i + 1) % n;
+            let (a_top, a_bottom) = outer[&vertices[i]];
+            let (b_top, b_bottom) = outer[&vertices[j]];
+            let (ia_top, ia_bottom) = inner[i];
+            let (ib_top, ib_bottom) = inner[j];
+
+            polygons.push([a_top, b_top, ib_top, ia_top]);
+            if mode == WireframeMode::Frame {
+                polygons.push([a_bottom, ia_bottom, ib_bottom, b_bottom]);
+                polygons.push([ia_top, ib_top, ib_bottom, ia_bottom]);
+                let twin = conn.at_halfedge(h).twin().try_end()?;
+                if conn[twin].face.is_none() {
+                    polygons.push([b_top, a_top, a_bottom, b_bottom]);
+                }
+            }
+        }
+    }
+
+    let mut result = HalfEdgeMesh::build_from_polygons(&positions, &polygons)?;
+    if !replace {
+        result.merge_with(mesh);
+    }
+    Ok(result)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Returns a wireframe of `mesh`, where every edge is turned into a strip
+    /// of the given `thickness`. The optional `mode` is "Frame" (the
+    /// default), which gives each edge a square section, or "Flat", which
+    /// keeps the strips on the surface. Unless `replace` is set, the original
+    /// mesh is kept along with the wireframe.
+    #[lua(under = "Ops")]
+    fn wireframe(
+        mesh: &HalfEdgeMesh,
+        thickness: f32,
+        replace: bool,
+        mode: Option<String>,
+    ) -> Result<HalfEdgeMesh> {
+        let mode = match mode {
+            Some(mode) => WireframeMode::from_name(&mode)?,
+            None => WireframeMode::Frame,
+        };
+        super::wireframe(mesh, thickness, mode, replace)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    pub fn test_wireframe() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+
+        // One quad on each side of every edge
+        let flat = wireframe(&mesh, 0.1, WireframeMode::Flat, true).unwrap();
+        let conn = flat.read_connectivity();
+        assert_eq!(conn.num_faces(), 24);
+        assert_eq!(conn.num_vertices(), 8 + 24);
+
+        // A closed lattice, with three quads per face edge since the box has
+        // no boundary
+        let frame = wireframe(&mesh, 0.1, WireframeMode::Frame, true).unwrap();
+        let conn = frame.read_connectivity();
+        assert_eq!(conn.num_faces(), 24 * 3);
+        assert_eq!(conn.num_vertices(), 16 + 48);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+        let positions = frame.read_positions();
+        for (v, _) in conn.iter_vertices() {
+            assert!(positions[v].abs().max_element() <= 0.5 + 0.05 + 1e-5);
+        }
+
+        let kept = wireframe(&mesh, 0.1, WireframeMode::Flat, false).unwrap();
+        assert_eq!(kept.read_connectivity().num_faces(), 24 + 6);
+        assert!(wireframe(&mesh, 0.0, WireframeMode::Flat, true).is_err());
+    }
+}
@@ -4,6 +4,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use std::collections::BTreeMap;
+
+use super::selection::SelectionExpression;
 use super::*;
 
 /// The main representation to draw the halfedge's faces as triangles on the GPU
@@ -12,13 +15,39 @@ use super::*;
 pub struct VertexIndexBuffers {
     /// Vertex positions, one per vertex.
     pub positions: Vec<Vec3>,
-    /// Vertex normals, one per vertex.
+    /// Vertex normals, one per vertex. Empty when normals were not requested.
     pub normals: Vec<Vec3>,
+    /// Vertex colors, one per vertex. Empty when colors were not requested,
+    /// or the mesh has no `color` channel.
+    pub colors: Vec<Vec3>,
+    /// Texture coordinates, one per vertex. Empty when uvs were not
+    /// requested, or the mesh has no uvs.
+    pub uvs: Vec<Vec3>,
     /// Indices: 3*N where N is the number of triangles. Indices point to
-    /// elements of `positions` and `normals`.
+    /// elements of `positions` and the other per-vertex buffers.
     pub indices: Vec<u32>,
 }
 
+/// The optional channels to include in [`VertexIndexBuffers`]. Positions are
+/// always generated. Leaving out the channels that won't be displayed saves
+/// generating and uploading their buffers, which matters for large meshes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferChannels {
+    pub normals: bool,
+    pub colors: bool,
+    pub uvs: bool,
+}
+
+impl Default for BufferChannels {
+    fn default() -> Self {
+        Self {
+            normals: true,
+            colors: false,
+            uvs: false,
+        }
+    }
+}
+
 /// This representation is suitable to draw the halfedge's vertices using
 /// `wgpu::PrimitiveTopology::PointList`.
 ///
@@ -51,89 +80,215 @@ pub struct FaceOverlayBuffers {
 }
 
 impl HalfEdgeMesh {
+    /// Reads the channels used for the optional `colors` and `uvs` buffers,
+    /// when they are requested and the mesh has them.
+    fn read_display_channels(
+        &self,
+        channels: BufferChannels,
+    ) -> (
+        Option<BorrowedRef<'_, Channel<VertexId, Vec3>>>,
+        Option<BorrowedRef<'_, Channel<HalfEdgeId, Vec3>>>,
+    ) {
+        let colors = if channels.colors {
+            self.channels
+                .read_channel_by_name::<VertexId, Vec3>("color")
+                .ok()
+        } else {
+            None
+        };
+        let uvs = if channels.uvs { self.read_uvs() } else { None };
+        (colors, uvs)
+    }
+
     /// Generates the [`TriangleBuffers`] for this mesh. Suitable to be uploaded
-    /// to the GPU.
+    /// to the GPU. Only the given optional `channels` are generated.
     #[profiling::function]
-    pub fn generate_triangle_buffers_flat(&self, force_gen: bool) -> Result<VertexIndexBuffers> {
-        let positions_ch = self.read_positions();
-        let conn = self.read_connectivity();
-
+    pub fn generate_triangle_buffers_flat(
+        &self,
+        force_gen: bool,
+        channels: BufferChannels,
+    ) -> Result<VertexIndexBuffers> {
         // This ugliness is needed because we need to either borrow the channel
         // for the mesh or generate it here, but if we generate inside the if
         // statement the ref owner gets dropped at the end of the scope.
-        let normal_ch: &Channel<_, _>;
+        let normal_ch: Option<&Channel<_, _>>;
         #[allow(unused_assignments)] // Look ma, I'm smarter than clippy
         let mut extend_lifetime = None;
         let existing_normals_ch = self.read_face_normals();
-        if !force_gen && existing_normals_ch.is_some() {
-            normal_ch = existing_normals_ch.as_deref().unwrap();
+        if !channels.normals {
+            normal_ch = None;
+        } else if !force_gen && existing_normals_ch.is_some() {
+            normal_ch = existing_normals_ch.as_deref();
         } else {
             extend_lifetime = Some(edit_ops::generate_flat_normals_channel(self)?);
-            normal_ch = extend_lifetime.as_ref().unwrap();
+            normal_ch = extend_lifetime.as_ref();
         }
 
-        let mut positions = vec![];
-        let mut normals = vec![];
+        Ok(self.generate_corner_buffers(channels, |face, _, _| normal_ch.map(|ch| ch[face])))
+    }
 
-        for (face_id, _face) in conn.faces.iter() {
-            // We try to be a bit forgiving here. We don't want to stop
-            // rendering even if we have slightly malformed meshes.
-            let normal = normal_ch[face_id];
+    /// Generates [`VertexIndexBuffers`] with one normal per face corner,
+    /// following the auto smooth configuration of the mesh. Only the given
+    /// optional `channels` are generated.
+    #[profiling::function]
+    pub fn generate_triangle_buffers_auto_smooth(
+        &self,
+        channels: BufferChannels,
+    ) -> Result<VertexIndexBuffers> {
+        let normal_ch = if channels.normals {
+            Some(edit_ops::generate_corner_normals_channel(self)?)
+        } else {
+            None
+        };
+        Ok(self.generate_corner_buffers(channels, |_, h, _| normal_ch.as_ref().map(|ch| ch[h])))
+    }
 
-            let vertices = conn.face_vertices(face_id);
+    /// Generates buffers with a separate vertex for every face corner, so
+    /// that values can differ between the faces around a vertex. The `normal`
+    /// function returns the normal for a face, corner and vertex, or None when
+    /// normals are not generated.
+    fn generate_corner_buffers(
+        &self,
+        channels: BufferChannels,
+        normal: impl Fn(FaceId, HalfEdgeId, VertexId) -> Option<Vec3>,
+    ) -> VertexIndexBuffers {
+        let positions_ch = self.read_positions();
+        let conn = self.read_connectivity();
+        let (colors_ch, uvs_ch) = self.read_display_channels(channels);
 
-            let v1 = vertices[0];
+        let mut positions = vec![];
+        let mut normals = vec![];
+        let mut colors = vec![];
+        let mut uvs = vec![];
 
-            for (&v2, &v3) in vertices[1..].iter().tuple_windows() {
-                positions.push(positions_ch[v1]);
-                positions.push(positions_ch[v2]);
-                positions.push(positions_ch[v3]);
-                normals.push(normal);
-                normals.push(normal);
-                normals.push(normal);
+        for (face_id, _face) in conn.faces.iter() {
+            let halfedges = conn.face_edges(face_id);
+            let h1 = halfedges[0];
+
+            for (&h2, &h3) in halfedges[1..].iter().tuple_windows() {
+                for h in [h1, h2, h3] {
+                    let v = conn.at_halfedge(h).vertex().end();
+                    positions.push(positions_ch[v]);
+                    if let Some(normal) = normal(face_id, h, v) {
+                        normals.push(normal);
+                    }
+                    if let Some(colors_ch) = &colors_ch {
+                        colors.push(colors_ch[v]);
+                    }
+                    if let Some(uvs_ch) = &uvs_ch {
+                        uvs.push(uvs_ch[h]);
+                    }
+                }
             }
         }
 
-        Ok(VertexIndexBuffers {
+        VertexIndexBuffers {
             indices: (0u32..positions.len() as u32).collect(),
             positions,
             normals,
-        })
+            colors,
+            uvs,
+        }
     }
 
-    /// If `force_gen` is true, ignores any existing vertex normals channel in
-    /// the mesh and generates one from scratch instead. This is used in some
-    /// viewport modes.
-    pub fn generate_triangle_buffers_smooth(&self, force_gen: bool) -> Result<VertexIndexBuffers> {
+    /// Generates flat-shaded [`VertexIndexBuffers`], split in one set of
+    /// buffers per material. Faces are grouped by the value of their
+    /// [`MATERIAL_CHANNEL`](crate::materials::MATERIAL_CHANNEL). The returned
+    /// key is the material index, or -1 for meshes without the channel.
+    #[profiling::function]
+    pub fn generate_triangle_buffers_by_material(&self) -> Result<Vec<(i32, VertexIndexBuffers)>> {
         let positions_ch = self.read_positions();
         let conn = self.read_connectivity();
+        let normal_ch = edit_ops::generate_flat_normals_channel(self)?;
+        let material_ch = self
+            .channels
+            .read_channel_by_name::<FaceId, f32>(crate::materials::MATERIAL_CHANNEL)
+            .ok();
+
+        let mut groups: BTreeMap<i32, VertexIndexBuffers> = BTreeMap::new();
+        for (face_id, _face) in conn.faces.iter() {
+            let material = material_ch
+                .as_ref()
+                .map(|ch| ch[face_id].round() as i32)
+                .unwrap_or(-1);
+            let buffers = groups
+                .entry(material)
+                .or_insert_with(|| VertexIndexBuffers {
+                    positions: vec![],
+                    normals: vec![],
+                    colors: vec![],
+                    uvs: vec![],
+                    indices: vec![],
+                });
+            let normal = normal_ch[face_id];
+            let vertices = conn.face_vertices(face_id);
+            let v1 = vertices[0];
+            for (&v2, &v3) in vertices[1..].iter().tuple_windows() {
+                for v in [v1, v2, v3] {
+                    buffers.indices.push(buffers.positions.len() as u32);
+                    buffers.positions.push(positions_ch[v]);
+                    buffers.normals.push(normal);
+                }
+            }
+        }
 
+        Ok(groups.into_iter().collect())
+    }
+
+    /// If `force_gen` is true, ignores any existing vertex normals channel in
+    /// the mesh and generates one from scratch instead. This is used in some
+    /// viewport modes. Only the given optional `channels` are generated.
+    ///
+    /// Vertices are shared between faces, except when uvs are generated,
+    /// since uvs are stored per face corner.
+    pub fn generate_triangle_buffers_smooth(
+        &self,
+        force_gen: bool,
+        channels: BufferChannels,
+    ) -> Result<VertexIndexBuffers> {
         // @CopyPaste -- This couldn't get any worse... Are we Java now or what?
         // This ugliness is needed because we need to either borrow the channel
         // for the mesh or generate it here, but if we generate inside the if
         // statement the ref owner gets dropped at the end of the scope.
-        let normal_ch: &Channel<_, _>;
+        let normal_ch: Option<&Channel<_, _>>;
         #[allow(unused_assignments)] // Look ma, I'm smarter than clippy
         let mut extend_lifetime = None;
         let existing_normals_ch = self.read_vertex_normals();
-        if !force_gen && existing_normals_ch.is_some() {
-            normal_ch = existing_normals_ch.as_deref().unwrap();
+        if !channels.normals {
+            normal_ch = None;
+        } else if !force_gen && existing_normals_ch.is_some() {
+            normal_ch = existing_normals_ch.as_deref();
         } else {
             extend_lifetime = Some(edit_ops::generate_smooth_normals_channel(self)?);
-            normal_ch = extend_lifetime.as_ref().unwrap();
+            normal_ch = extend_lifetime.as_ref();
         }
 
-        let mut v_id_to_idx =
-            slotmap::SecondaryMap::<VertexId, u32>::with_capacity(conn.vertices.capacity());
+        if channels.uvs && self.read_uvs().is_some() {
+            return Ok(self.generate_corner_buffers(channels, |_, _, v| normal_ch.map(|ch| ch[v])));
+        }
+
+        let positions_ch = self.read_positions();
+        let conn = self.read_connectivity();
+        let (colors_ch, _) = self.read_display_channels(channels);
+
         let mut positions = vec![];
         let mut normals = vec![];
+        let mut colors = vec![];
+
+        let mut v_id_to_idx =
+            slotmap::SecondaryMap::<VertexId, u32>::with_capacity(conn.vertices.capacity());
 
         conn.iter_vertices_with_channel(&positions_ch)
             .enumerate()
             .try_for_each::<_, Result<()>>(|(idx, (v_id, _v, pos))| {
                 v_id_to_idx.insert(v_id, idx as u32);
                 positions.push(pos);
-                normals.push(normal_ch[v_id]);
+                if let Some(normal_ch) = normal_ch {
+                    normals.push(normal_ch[v_id]);
+                }
+                if let Some(colors_ch) = &colors_ch {
+                    colors.push(colors_ch[v_id]);
+                }
                 Ok(())
             })?;
 
@@ -151,6 +306,8 @@ impl HalfEdgeMesh {
         Ok(VertexIndexBuffers {
             positions,
             normals,
+            colors,
+            uvs: vec![],
             indices,
         })
     }
@@ -266,6 +423,63 @@ impl HalfEdgeMesh {
         Ok(LineBuffers { colors, positions })
     }
 
+    /// Generates [`LineBuffers`] highlighting the elements of this mesh that
+    /// match `selection`, for elements of the given `kind`. Edges are drawn as
+    /// lines, faces by their outline and vertices as small crosses.
+    pub fn generate_selection_highlight_buffers(
+        &self,
+        kind: ChannelKeyType,
+        selection: &SelectionExpression,
+    ) -> Result<LineBuffers> {
+        const HIGHLIGHT_COLOR: Vec3 = Vec3::new(1.0, 0.6, 0.0);
+
+        let positions_ch = self.read_positions();
+        let conn = self.read_connectivity();
+        let mut positions = Vec::new();
+
+        match kind {
+            ChannelKeyType::VertexId => {
+                // Crosses are sized relative to the mesh, so they are visible
+                // at any scale
+                let (min, max) = positions_ch.iter().fold(
+                    (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+                    |(min, max), (_, p)| (min.min(*p), max.max(*p)),
+                );
+                let size = 0.02 * (max - min).length().max(0.1);
+                for v in self.resolve_vertex_selection_full(selection)? {
+                    let p = positions_ch[v];
+                    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                        positions.push(p - axis * size);
+                        positions.push(p + axis * size);
+                    }
+                }
+            }
+            ChannelKeyType::HalfEdgeId => {
+                for h in self.resolve_halfedge_selection_full(selection)? {
+                    let (src, dst) = conn
+                        .at_halfedge(h)
+                        .src_dst_pair()
+                        .map_err(|err| anyhow!("Invalid halfedge in selection: {err}"))?;
+                    positions.push(positions_ch[src]);
+                    positions.push(positions_ch[dst]);
+                }
+            }
+            ChannelKeyType::FaceId => {
+                for f in self.resolve_face_selection_full(selection)? {
+                    for (a, b) in conn.face_vertices(f).iter_cpy().circular_tuple_windows() {
+                        positions.push(positions_ch[a]);
+                        positions.push(positions_ch[b]);
+                    }
+                }
+            }
+        }
+
+        Ok(LineBuffers {
+            colors: vec![HIGHLIGHT_COLOR; positions.len() / 2],
+            positions,
+        })
+    }
+
     /// Generates a variation of the [`LineBuffers`] which can be drawn in the
     /// exact same way, but instead of drawing a single line per edge, draws
     /// halfedges individually as tiny arrows.
@@ -6,7 +6,10 @@
 
 use super::*;
 use crate::{
-    lua_engine::{lua_stdlib::LVec3, ToLuaError},
+    lua_engine::{
+        lua_stdlib::{LQuat, LVec3},
+        ToLuaError,
+    },
     sync::RefCounted,
 };
 use mlua::{Function, Lua, ToLua, Value};
@@ -328,6 +331,23 @@ mod lua_api {
             self.read_positions()[vertex_id].into()
         }
 
+        /// Returns the minimum and maximum corners of the axis-aligned
+        /// bounding box of this mesh. Both are zero for an empty mesh.
+        #[lua]
+        pub fn bbox(&self) -> (LVec3, LVec3) {
+            let conn = self.read_connectivity();
+            let positions = self.read_positions();
+            let (min, max) = conn.iter_vertices().map(|(v, _)| positions[v]).fold(
+                (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+                |(min, max), p| (min.min(p), max.max(p)),
+            );
+            if min.x > max.x {
+                (LVec3(Vec3::ZERO), LVec3(Vec3::ZERO))
+            } else {
+                (LVec3(min), LVec3(max))
+            }
+        }
+
         // ==== FACE GETTERS ====
 
         /// Returns a list of edges for the given `face_id`.
@@ -391,6 +411,33 @@ mod lua_api {
                 .src_dst_pair()?)
         }
 
+        // ==== ANCHORS ====
+
+        /// Returns the names of the anchors in this mesh, sorted alphabetically.
+        #[lua]
+        pub fn anchor_names(&self) -> Vec<String> {
+            self.read_anchors()
+                .iter()
+                .map(|(name, _)| name.to_owned())
+                .collect()
+        }
+
+        /// Returns whether this mesh has an anchor called `name`.
+        #[lua]
+        pub fn has_anchor(&self, name: String) -> bool {
+            self.read_anchors().get(&name).is_some()
+        }
+
+        /// Returns the position and rotation of the anchor called `name`.
+        #[lua]
+        pub fn get_anchor(&self, name: String) -> Result<(LVec3, LQuat)> {
+            let anchors = self.read_anchors();
+            let anchor = anchors
+                .get(&name)
+                .ok_or_else(|| anyhow::anyhow!("Anchor '{name}' not found"))?;
+            Ok((LVec3(anchor.position), LQuat(anchor.rotation)))
+        }
+
         // ==== OPS ====
 
         /// Adds a new disconnected edge to this mesh with endpoints `start` and
@@ -408,6 +455,22 @@ mod lua_api {
             crate::prelude::halfedge::edit_ops::add_vertex(self, pos.0)
         }
 
+        /// Sets how the normals of this mesh are generated, both in the
+        /// viewport and when exporting. The `mode` is one of "flat", "smooth",
+        /// "auto_smooth" or "mixed". For auto smooth, edges between faces
+        /// meeting at an angle larger than `angle` radians stay sharp. When
+        /// `angle` is nil, the angle currently set for this mesh is kept. For
+        /// mixed, only the faces in the "smooth" face group are shaded smooth.
+        #[lua]
+        pub fn set_shading(&mut self, mode: String, angle: Option<f32>) -> Result<()> {
+            let angle = angle.unwrap_or(self.gen_config.auto_smooth_angle);
+            crate::prelude::halfedge::edit_ops::set_shading(
+                self,
+                ShadingMode::from_name(&mode)?,
+                angle,
+            )
+        }
+
         /// Returns a point cloud mesh, selecting a set of vertices `sel` from
         /// this mesh.
         #[lua]
@@ -0,0 +1,280 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::path::Path;
+
+use glam::IVec3;
+
+use crate::prelude::*;
+
+use super::edit_ops::{triangulate_polygon, TriangulateMode};
+
+/// How the axes of an imported file are converted to the Y-up convention
+/// used by blackjack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AxisSwap {
+    None,
+    /// For files where Z points up, like the ones exported by most CAD tools.
+    ZUpToYUp,
+    /// The inverse of `ZUpToYUp`.
+    YUpToZUp,
+}
+
+impl AxisSwap {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "None" => Ok(Self::None),
+            "ZUpToYUp" => Ok(Self::ZUpToYUp),
+            "YUpToZUp" => Ok(Self::YUpToZUp),
+            _ => bail!("Invalid axis swap: {name}"),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::ZUpToYUp => "ZUpToYUp",
+            Self::YUpToZUp => "YUpToZUp",
+        }
+    }
+
+    /// Both swaps are rotations, so the winding of faces is preserved.
+    fn apply(&self, v: Vec3) -> Vec3 {
+        match self {
+            Self::None => v,
+            Self::ZUpToYUp => Vec3::new(v.x, v.z, -v.y),
+            Self::YUpToZUp => Vec3::new(v.x, -v.z, v.y),
+        }
+    }
+}
+
+/// Cleanup applied by the importers to the raw positions and polygons of a
+/// file, before building the mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportOptions {
+    /// Multiplies every position, e.g. 0.01 for files in centimeters.
+    pub scale: f32,
+    pub axis_swap: AxisSwap,
+    /// Vertices closer than this distance are merged into one. Zero keeps
+    /// all vertices.
+    pub weld_distance: f32,
+    /// When set, polygons with more than four vertices are triangulated.
+    pub triangulate_ngons: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            axis_swap: AxisSwap::None,
+            weld_distance: 0.0,
+            triangulate_ngons: false,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Reads the options from a Lua table. Missing fields take their default
+    /// values.
+    pub fn from_table(table: &mlua::Table) -> Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            scale: table
+                .get::<_, Option<f32>>("scale")?
+                .unwrap_or(defaults.scale),
+            axis_swap: match table.get::<_, Option<String>>("axis_swap")? {
+                Some(axis_swap) => AxisSwap::from_name(&axis_swap)?,
+                None => defaults.axis_swap,
+            },
+            weld_distance: table
+                .get::<_, Option<f32>>("weld_distance")?
+                .unwrap_or(defaults.weld_distance),
+            triangulate_ngons: table
+                .get::<_, Option<bool>>("triangulate_ngons")?
+                .unwrap_or(defaults.triangulate_ngons),
+        })
+    }
+
+    pub fn to_table<'lua>(&self, lua: &'lua mlua::Lua) -> Result<mlua::Table<'lua>> {
+        let table = lua.create_table()?;
+        table.set("scale", self.scale)?;
+        table.set("axis_swap", self.axis_swap.name())?;
+        table.set("weld_distance", self.weld_distance)?;
+        table.set("triangulate_ngons", self.triangulate_ngons)?;
+        Ok(table)
+    }
+
+    /// Returns the options for importing the file at `path`. When `options`
+    /// are given, they're remembered for other files of the same type.
+    /// Otherwise, the options last used for that file type are returned.
+    pub fn resolve(lua: &mlua::Lua, path: &Path, options: Option<mlua::Table>) -> Result<Self> {
+        let file_type = file_type(path);
+        if lua.app_data_ref::<RememberedImportOptions>().is_none() {
+            lua.set_app_data(RememberedImportOptions::default());
+        }
+        let mut remembered = lua
+            .app_data_mut::<RememberedImportOptions>()
+            .expect("App data should be installed");
+        match options {
+            Some(table) => {
+                let options = Self::from_table(&table)?;
+                remembered.0.insert(file_type, options.clone());
+                Ok(options)
+            }
+            None => Ok(remembered.0.get(&file_type).cloned().unwrap_or_default()),
+        }
+    }
+
+    /// Converts a point of the imported file, without welding.
+    pub fn transform_point(&self, v: Vec3) -> Vec3 {
+        self.axis_swap.apply(v) * self.scale
+    }
+
+    /// Applies the options to the `positions` and `polygons` read from a
+    /// file, in a single pass over each. Polygons that collapse when welding
+    /// their vertices are dropped.
+    pub fn apply(
+        &self,
+        positions: Vec<Vec3>,
+        polygons: Vec<SVec<usize>>,
+    ) -> (Vec<Vec3>, Vec<SVec<usize>>) {
+        if *self == Self::default() {
+            return (positions, polygons);
+        }
+
+        // Welded vertices are found by bucketing positions in a grid with
+        // cells as large as the weld distance, so only the neighbouring cells
+        // need to be checked.
+        let weld = self.weld_distance > 0.0;
+        let cell_of = |p: Vec3| (p / self.weld_distance).floor().as_ivec3();
+        let mut grid = HashMap::<IVec3, SVec<usize>>::new();
+        let mut new_positions = Vec::with_capacity(positions.len());
+        let mut remap = Vec::with_capacity(positions.len());
+        for p in positions {
+            let p = self.transform_point(p);
+            if weld {
+                let cell = cell_of(p);
+                let found = (-1..=1)
+                    .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| (x, y, z))))
+                    .filter_map(|(x, y, z)| grid.get(&(cell + IVec3::new(x, y, z))))
+                    .flatten()
+                    .find(|i| new_positions[**i].distance(p) <= self.weld_distance);
+                if let Some(i) = found {
+                    remap.push(*i);
+                    continue;
+                }
+                grid.entry(cell).or_default().push(new_positions.len());
+            }
+            remap.push(new_positions.len());
+            new_positions.push(p);
+        }
+
+        let mut new_polygons = Vec::with_capacity(polygons.len());
+        for polygon in polygons {
+            let mut polygon = polygon.iter().map(|i| remap[*i]).collect::<SVec<_>>();
+            polygon.dedup();
+            while polygon.len() > 1 && polygon.first() == polygon.last() {
+                polygon.pop();
+            }
+            if polygon.len() < 3 || polygon.iter().duplicates().next().is_some() {
+                continue;
+            }
+            if self.triangulate_ngons && polygon.len() > 4 {
+                let points = polygon.iter().map(|i| new_positions[*i]).collect_vec();
+                for [a, b, c] in triangulate_polygon(&points, TriangulateMode::Auto) {
+                    new_polygons.push(SVec::from_slice(&[polygon[a], polygon[b], polygon[c]]));
+                }
+            } else {
+                new_polygons.push(polygon);
+            }
+        }
+        (new_positions, new_polygons)
+    }
+}
+
+/// The import options last used for each file type, stored as Lua app data.
+#[derive(Default)]
+struct RememberedImportOptions(HashMap<String, ImportOptions>);
+
+/// The lowercase extension of `path`, used to tell file types apart.
+fn file_type(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use mlua::Lua;
+
+    /// Returns the import options last used for files with the given
+    /// `extension`, as a table with the `scale`, `axis_swap`,
+    /// `weld_distance` and `triangulate_ngons` fields.
+    #[lua(under = "HalfEdgeMesh")]
+    fn remembered_import_options<'lua>(
+        lua: &'lua Lua,
+        extension: String,
+    ) -> Result<mlua::Table<'lua>> {
+        let path = Path::new("file").with_extension(extension);
+        ImportOptions::resolve(lua, &path, None)?.to_table(lua)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_import_options() {
+        // A quad and a pentagon sharing an edge, with duplicated vertices
+        let positions = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0002, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(2.5, 0.5, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.0002, 0.0),
+        ];
+        let polygons = vec![
+            SVec::from_slice(&[0, 1, 2, 3]),
+            SVec::from_slice(&[4, 5, 6, 7, 8]),
+        ];
+
+        let (p, f) = ImportOptions::default().apply(positions.clone(), polygons.clone());
+        assert_eq!((p.len(), f.len()), (9, 2));
+
+        let options = ImportOptions {
+            scale: 2.0,
+            axis_swap: AxisSwap::ZUpToYUp,
+            weld_distance: 0.001,
+            triangulate_ngons: true,
+        };
+        let (p, f) = options.apply(positions, polygons);
+        assert_eq!(p.len(), 7);
+        assert_eq!(f.len(), 4);
+        assert_eq!(f[1].as_slice(), &[1, 4, 5]);
+        assert_eq!(p[2], Vec3::new(2.0, 0.0, -2.0));
+        HalfEdgeMesh::build_from_polygons(&p, &f).unwrap();
+
+        let lua = mlua::Lua::new();
+        let table = options.to_table(&lua).unwrap();
+        let path = Path::new("mesh.OBJ");
+        assert_eq!(
+            ImportOptions::resolve(&lua, path, None).unwrap(),
+            Default::default()
+        );
+        assert_eq!(
+            ImportOptions::resolve(&lua, path, Some(table)).unwrap(),
+            options
+        );
+        let other = Path::new("other.obj");
+        assert_eq!(ImportOptions::resolve(&lua, other, None).unwrap(), options);
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{cell::RefCell, path::PathBuf};
+
+use super::*;
+use crate::progress::ProgressSink;
+use import_options::ImportOptions;
+use primitives::Box;
+use wavefront_obj::{scan_wavefront_obj, ObjSummary};
+
+/// A mesh imported from a file, which is only read in full the first time
+/// its geometry is needed. Until then, only the size and bounding box of the
+/// mesh are known.
+///
+/// The graph interpreter loads lazy meshes when they are passed to another
+/// node, so nodes never see them. When a lazy mesh is the final result of a
+/// graph, it is displayed as a [`MeshProxy`] instead.
+pub struct LazyMesh {
+    path: PathBuf,
+    summary: ObjSummary,
+    options: ImportOptions,
+    loaded: RefCell<Option<HalfEdgeMesh>>,
+}
+
+/// A placeholder for a [`LazyMesh`] that has not been loaded, displayed as the
+/// wireframe of its bounding box.
+pub struct MeshProxy {
+    pub path: PathBuf,
+    pub summary: ObjSummary,
+    /// The edges of the bounding box of the mesh.
+    pub wire_box: HalfEdgeMesh,
+}
+
+impl LazyMesh {
+    /// Creates a lazy mesh for the OBJ file at `path`, which is imported with
+    /// the given `options`. The file is scanned, but its mesh isn't built.
+    pub fn from_wavefront_obj(path: PathBuf, options: ImportOptions) -> Result<Self> {
+        let mut summary = scan_wavefront_obj(&path)?;
+        // The bounding box is converted like the positions will be
+        let (a, b) = (
+            options.transform_point(summary.min),
+            options.transform_point(summary.max),
+        );
+        summary.min = a.min(b);
+        summary.max = a.max(b);
+        Ok(Self {
+            path,
+            summary,
+            options,
+            loaded: RefCell::new(None),
+        })
+    }
+
+    pub fn summary(&self) -> &ObjSummary {
+        &self.summary
+    }
+
+    /// Returns the full mesh, loading it on the first call.
+    pub fn load(&self, progress: &dyn ProgressSink) -> Result<HalfEdgeMesh> {
+        if let Some(mesh) = self.loaded.borrow().as_ref() {
+            return Ok(mesh.clone());
+        }
+        let mesh = HalfEdgeMesh::from_wavefront_obj_with_progress(
+            self.path.clone(),
+            &self.options,
+            progress,
+        )?;
+        *self.loaded.borrow_mut() = Some(mesh.clone());
+        Ok(mesh)
+    }
+
+    /// Returns the placeholder to display instead of this mesh.
+    pub fn proxy(&self) -> Result<MeshProxy> {
+        let size = self.summary.max - self.summary.min;
+        let center = 0.5 * (self.summary.min + self.summary.max);
+        let wire_box = Box::build(center, size)?;
+        {
+            // Keep only the edges
+            let mut conn = wire_box.write_connectivity();
+            let faces = conn.iter_faces().map(|(f, _)| f).collect_vec();
+            for face in faces {
+                for h in conn.face_edges(face) {
+                    conn[h].face = None;
+                }
+                conn.remove_face(face);
+            }
+        }
+        Ok(MeshProxy {
+            path: self.path.clone(),
+            summary: self.summary,
+            wire_box,
+        })
+    }
+}
+
+impl mlua::UserData for LazyMesh {}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use mlua::Lua;
+
+    /// Scans the wavefront OBJ file at `path` and returns a lazy mesh, which
+    /// is displayed as its bounding box. The file is only fully loaded when
+    /// another node uses the mesh. The `options` are the same as in
+    /// `from_wavefront_obj`.
+    #[lua(under = "HalfEdgeMesh")]
+    fn lazy_wavefront_obj(
+        lua: &Lua,
+        path: String,
+        options: Option<mlua::Table>,
+    ) -> Result<LazyMesh> {
+        let path = PathBuf::from(path);
+        let options = ImportOptions::resolve(lua, &path, options)?;
+        LazyMesh::from_wavefront_obj(path, options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::progress::NoProgress;
+
+    #[test]
+    fn test_lazy_mesh() {
+        let lazy = LazyMesh::from_wavefront_obj("../test/test_mesh.obj".into(), Default::default())
+            .unwrap();
+        let proxy = lazy.proxy().unwrap();
+        assert_eq!(proxy.wire_box.read_connectivity().num_faces(), 0);
+        assert_eq!(proxy.wire_box.read_connectivity().num_vertices(), 8);
+
+        let mesh = lazy.load(&NoProgress).unwrap();
+        assert_eq!(
+            mesh.read_connectivity().num_vertices(),
+            lazy.summary().num_vertices
+        );
+    }
+}
@@ -7,6 +7,9 @@
 use std::f32::consts::PI;
 
 use super::*;
+use crate::fractal_noise::{FractalNoise, NoiseParams};
+use edit_ops::pipes::PipeBuilder;
+use selection::SelectionExpression;
 
 pub struct Box;
 
@@ -52,6 +55,89 @@ impl Box {
     }
 }
 
+/// A box with its edges and corners rounded with a fillet of the given
+/// radius. The fillet radius is clamped to half the smallest side.
+pub struct RoundedBox;
+impl RoundedBox {
+    pub fn build(
+        center: Vec3,
+        size: Vec3,
+        fillet_radius: f32,
+        fillet_segments: u32,
+    ) -> Result<HalfEdgeMesh> {
+        if fillet_radius <= 0.0 {
+            return Box::build(center, size);
+        }
+        if fillet_segments < 1 {
+            bail!("A rounded box needs at least 1 fillet segment");
+        }
+        let hsize = size.abs() * 0.5;
+        let radius = fillet_radius.min(hsize.min_element());
+        let inner = hsize - Vec3::splat(radius);
+
+        // The vertices are placed on the surface of a box, and then pushed
+        // towards the fillet. Along each axis, the box is sampled at the flat
+        // part of the faces and at offsets that give evenly spaced angles
+        // around the fillet.
+        let offsets = (0..=fillet_segments)
+            .map(|k| radius * (0.25 * PI * k as f32 / fillet_segments as f32).tan())
+            .collect_vec();
+        let samples = (0..3)
+            .map(|axis| {
+                let h = inner[axis];
+                let mut samples = offsets.iter().rev().map(|s| -(h + s)).collect_vec();
+                // When the fillets on both sides meet, the middle sample
+                // would be repeated.
+                let skip = if h > f32::EPSILON { 0 } else { 1 };
+                samples.extend(offsets.iter().skip(skip).map(|s| h + s));
+                samples
+            })
+            .collect_vec();
+
+        let mut vertices = Vec::<Vec3>::new();
+        let mut vertex_ids = HashMap::<[usize; 3], u32>::new();
+        let mut vertex = |idx: [usize; 3]| {
+            *vertex_ids.entry(idx).or_insert_with(|| {
+                let q = Vec3::new(samples[0][idx[0]], samples[1][idx[1]], samples[2][idx[2]]);
+                let core = q.clamp(-inner, inner);
+                vertices.push(center + core + (q - core).normalize() * radius);
+                vertices.len() as u32 - 1
+            })
+        };
+
+        let mut polygons = Vec::<[u32; 4]>::new();
+        for axis in 0..3 {
+            let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+            for side in [0, samples[axis].len() - 1] {
+                for u in 0..samples[b].len() - 1 {
+                    for v in 0..samples[c].len() - 1 {
+                        let corner = |du: usize, dv: usize| {
+                            let mut idx = [0; 3];
+                            idx[axis] = side;
+                            idx[b] = u + du;
+                            idx[c] = v + dv;
+                            idx
+                        };
+                        let mut quad = [
+                            vertex(corner(0, 0)),
+                            vertex(corner(1, 0)),
+                            vertex(corner(1, 1)),
+                            vertex(corner(0, 1)),
+                        ];
+                        // Quads on the negative side are flipped to face outwards
+                        if side == 0 {
+                            quad.reverse();
+                        }
+                        polygons.push(quad);
+                    }
+                }
+            }
+        }
+
+        HalfEdgeMesh::build_from_polygons(&vertices, &polygons)
+    }
+}
+
 pub struct Quad;
 impl Quad {
     pub fn build(center: Vec3, normal: Vec3, right: Vec3, size: Vec2) -> Result<HalfEdgeMesh> {
@@ -91,59 +177,318 @@ impl Circle {
 
     pub fn build_open(center: Vec3, radius: f32, num_vertices: usize) -> Result<HalfEdgeMesh> {
         let circle = Self::build(center, radius, num_vertices)?;
-        {
-            let mut conn = circle.write_connectivity();
-            let (v, _) = conn.iter_vertices().next().unwrap();
-            let halfedge = conn.at_vertex(v).halfedge().end();
-            let face = conn.at_halfedge(halfedge).face().end();
+        clear_single_face(&circle);
+        Ok(circle)
+    }
+}
 
-            // Clear the face
-            for h in conn.halfedge_loop(halfedge) {
-                conn[h].face = None;
+/// A section of a circle on the XZ plane, going counter-clockwise from
+/// `start_angle` to `end_angle`, in radians. Angles are measured from the Z
+/// axis, like in [`Circle`].
+pub struct Arc;
+impl Arc {
+    pub fn make_verts(
+        center: Vec3,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        num_vertices: usize,
+    ) -> Vec<Vec3> {
+        (0..num_vertices)
+            .map(|i| {
+                let t = i as f32 / (num_vertices - 1) as f32;
+                let q = Quat::from_rotation_y(lerp(start_angle, end_angle, t));
+                q * (Vec3::Z * radius) + center
+            })
+            .collect_vec()
+    }
+
+    /// Builds the arc as a polyline with `num_vertices` vertices. When
+    /// `filled`, the arc is closed through the center, making a pie slice.
+    pub fn build(
+        center: Vec3,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        num_vertices: usize,
+        filled: bool,
+    ) -> Result<HalfEdgeMesh> {
+        if num_vertices < 2 {
+            bail!("An arc needs at least 2 vertices");
+        }
+        if (end_angle - start_angle).abs() < 1e-6 {
+            bail!("The start and end angles of an arc must be different");
+        }
+        let verts = Self::make_verts(center, radius, start_angle, end_angle, num_vertices);
+        if filled {
+            let mut verts = verts;
+            verts.push(center);
+            let mut polygon = (0..verts.len()).collect_vec();
+            // Keep the slice facing up, like a filled circle
+            if end_angle < start_angle {
+                polygon.reverse();
             }
-            conn.remove_face(face);
+            HalfEdgeMesh::build_from_polygons(&verts, &[&polygon])
+        } else {
+            Line::build_from_points(verts)
         }
-        Ok(circle)
     }
 }
 
+/// Removes the face of a mesh made of a single polygon, leaving a closed
+/// polyline.
+fn clear_single_face(mesh: &HalfEdgeMesh) {
+    let mut conn = mesh.write_connectivity();
+    let (v, _) = conn.iter_vertices().next().unwrap();
+    let halfedge = conn.at_vertex(v).halfedge().end();
+    let face = conn.at_halfedge(halfedge).face().end();
+
+    // Clear the face
+    for h in conn.halfedge_loop(halfedge) {
+        conn[h].face = None;
+    }
+    conn.remove_face(face);
+}
+
+/// The shape built by [`NGon`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NGonShape {
+    /// A single filled polygon.
+    Disc,
+    /// A flat ring, with a hole of the inner radius.
+    Ring,
+    /// A single polygon alternating between the outer and inner radius.
+    Star,
+}
+
+impl NGonShape {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Disc" => Ok(Self::Disc),
+            "Ring" => Ok(Self::Ring),
+            "Star" => Ok(Self::Star),
+            _ => bail!("Invalid n-gon shape: {name}"),
+        }
+    }
+}
+
+/// A regular polygon on the XZ plane, optionally with an inner radius to make
+/// rings and stars. The `twist` rotates the inner vertices, in radians.
+pub struct NGon;
+impl NGon {
+    pub fn build(
+        center: Vec3,
+        num_sides: usize,
+        outer_radius: f32,
+        inner_radius: f32,
+        twist: f32,
+        shape: NGonShape,
+    ) -> Result<HalfEdgeMesh> {
+        if num_sides < 3 {
+            bail!("An n-gon needs at least 3 sides");
+        }
+        let angle_delta = (2.0 * PI) / num_sides as f32;
+        let point = |angle: f32, radius: f32| Quat::from_rotation_y(angle) * (Vec3::Z * radius);
+        let outer = (0..num_sides)
+            .map(|i| center + point(angle_delta * i as f32, outer_radius))
+            .collect_vec();
+
+        match shape {
+            NGonShape::Disc => {
+                let polygon = (0..num_sides).collect_vec();
+                HalfEdgeMesh::build_from_polygons(&outer, &[&polygon])
+            }
+            NGonShape::Ring => {
+                let mut vertices = outer;
+                vertices.extend(
+                    (0..num_sides)
+                        .map(|i| center + point(angle_delta * i as f32 + twist, inner_radius)),
+                );
+                let polygons = (0..num_sides)
+                    .map(|i| {
+                        let j = (i + 1) % num_sides;
+                        [i, j, num_sides + j, num_sides + i]
+                    })
+                    .collect_vec();
+                HalfEdgeMesh::build_from_polygons(&vertices, &polygons)
+            }
+            NGonShape::Star => {
+                // Inner vertices sit halfway between the outer ones
+                let vertices = outer
+                    .iter_cpy()
+                    .enumerate()
+                    .flat_map(|(i, v)| {
+                        let angle = angle_delta * (i as f32 + 0.5) + twist;
+                        [v, center + point(angle, inner_radius)]
+                    })
+                    .collect_vec();
+                let polygon = (0..vertices.len()).collect_vec();
+                HalfEdgeMesh::build_from_polygons(&vertices, &[&polygon])
+            }
+        }
+    }
+}
+
+/// Builds a closed mesh with the topology of a UV-sphere: A vertex on each
+/// pole, and `rings - 1` loops of `segments` vertices in between. The `point`
+/// function returns the position of each vertex, given its polar angle `phi`,
+/// from 0 at the top to PI at the bottom, and its azimuthal angle `theta`.
+fn build_uv_topology(
+    segments: u32,
+    rings: u32,
+    point: impl Fn(f32, f32) -> Vec3,
+) -> Result<HalfEdgeMesh> {
+    let mut vertices = Vec::<Vec3>::new();
+    let mut polygons = Vec::<SVec<u32>>::new();
+
+    let top_vertex = 0;
+    vertices.push(point(0.0, 0.0));
+
+    for i in 0..rings - 1 {
+        let phi = PI * (i + 1) as f32 / rings as f32;
+        for j in 0..segments {
+            let theta = 2.0 * PI * j as f32 / segments as f32;
+            vertices.push(point(phi, theta));
+        }
+    }
+
+    let bottom_vertex = vertices.len() as u32;
+    vertices.push(point(PI, 0.0));
+
+    // Top triangles
+    for i in 0..segments {
+        let i0 = i + 1;
+        let i1 = (i + 1) % segments + 1;
+        polygons.push(smallvec::smallvec![top_vertex, i1, i0]);
+    }
+    // Bottom triangles
+    for i in 0..segments {
+        let i0 = i + segments * (rings - 2) + 1;
+        let i1 = (i + 1) % segments + segments * (rings - 2) + 1;
+        polygons.push(smallvec::smallvec![bottom_vertex, i0, i1]);
+    }
+    // Middle quads
+    for j in 0..rings - 2 {
+        let j0 = j * segments + 1;
+        let j1 = (j + 1) * segments + 1;
+        for i in 0..segments {
+            let i0 = j0 + i;
+            let i1 = j0 + (i + 1) % segments;
+            let i2 = j1 + (i + 1) % segments;
+            let i3 = j1 + i;
+            polygons.push(smallvec::smallvec![i0, i1, i2, i3]);
+        }
+    }
+
+    HalfEdgeMesh::build_from_polygons(&vertices, &polygons)
+}
+
 pub struct UVSphere;
 impl UVSphere {
     pub fn build(center: Vec3, segments: u32, rings: u32, radius: f32) -> Result<HalfEdgeMesh> {
+        build_uv_topology(segments, rings, |phi, theta| {
+            let x = phi.sin() * theta.cos() * radius;
+            let y = phi.cos() * radius;
+            let z = phi.sin() * theta.sin() * radius;
+            center + Vec3::new(x, y, z)
+        })
+    }
+}
+
+/// A superellipsoid with the given `size`. The exponents control the shape of
+/// its vertical (`e1`) and horizontal (`e2`) cross-sections. Exponents of 1
+/// give an ellipsoid, values close to 0 give a box, 2 gives an octahedron and
+/// larger values give pinched, star-like shapes.
+pub struct Superellipsoid;
+impl Superellipsoid {
+    /// Signed power: Raises the absolute value of `x` to `e`, keeping its sign
+    fn spow(x: f32, e: f32) -> f32 {
+        x.signum() * x.abs().powf(e)
+    }
+
+    pub fn build(
+        center: Vec3,
+        size: Vec3,
+        e1: f32,
+        e2: f32,
+        segments: u32,
+        rings: u32,
+    ) -> Result<HalfEdgeMesh> {
+        if e1 <= 0.0 || e2 <= 0.0 {
+            bail!("Superellipsoid exponents must be positive");
+        }
+        if segments < 3 || rings < 2 {
+            bail!("A superellipsoid needs at least 3 segments and 2 rings");
+        }
+        let radii = size * 0.5;
+        build_uv_topology(segments, rings, |phi, theta| {
+            let horizontal = Self::spow(phi.sin(), e1);
+            let x = horizontal * Self::spow(theta.cos(), e2);
+            let y = Self::spow(phi.cos(), e1);
+            let z = horizontal * Self::spow(theta.sin(), e2);
+            center + Vec3::new(x, y, z) * radii
+        })
+    }
+}
+
+/// A cylinder along the Y axis with hemispherical caps. The `height` is the
+/// total height, including the caps. When it is less than twice the `radius`,
+/// the capsule degenerates into a sphere.
+pub struct Capsule;
+impl Capsule {
+    pub fn build(
+        center: Vec3,
+        radius: f32,
+        height: f32,
+        segments: u32,
+        rings: u32,
+    ) -> Result<HalfEdgeMesh> {
+        if segments < 3 || rings < 1 {
+            bail!("A capsule needs at least 3 segments and 1 ring per cap");
+        }
+
+        let half_height = (height * 0.5 - radius).max(0.0);
+
+        // The (height, radius) of each ring, from top to bottom. The caps
+        // share the rings at their equator with the cylinder, and when there
+        // is no cylinder, both caps share a single ring.
+        let mut profile = vec![];
+        for i in 1..=rings {
+            let phi = 0.5 * PI * i as f32 / rings as f32;
+            profile.push((half_height + phi.cos() * radius, phi.sin() * radius));
+        }
+        let first_bottom = if half_height > 0.0 { 0 } else { 1 };
+        for i in first_bottom..rings {
+            let phi = 0.5 * PI * (1.0 + i as f32 / rings as f32);
+            profile.push((-half_height + phi.cos() * radius, phi.sin() * radius));
+        }
+
         let mut vertices = Vec::<Vec3>::new();
         let mut polygons = Vec::<SVec<u32>>::new();
 
         let top_vertex = 0;
-        vertices.push(center + Vec3::Y * radius);
-
-        for i in 0..rings - 1 {
-            let phi = PI * (i + 1) as f32 / rings as f32;
+        vertices.push(center + Vec3::Y * (half_height + radius));
+        for (y, r) in profile.iter_cpy() {
             for j in 0..segments {
                 let theta = 2.0 * PI * j as f32 / segments as f32;
-                let x = phi.sin() * theta.cos() * radius;
-                let y = phi.cos() * radius;
-                let z = phi.sin() * theta.sin() * radius;
-                vertices.push(center + Vec3::new(x, y, z));
+                vertices.push(center + Vec3::new(theta.cos() * r, y, theta.sin() * r));
             }
         }
-
         let bottom_vertex = vertices.len() as u32;
-        vertices.push(center - Vec3::Y * radius);
+        vertices.push(center - Vec3::Y * (half_height + radius));
 
-        // Top triangles
+        let num_rings = profile.len() as u32;
         for i in 0..segments {
             let i0 = i + 1;
             let i1 = (i + 1) % segments + 1;
             polygons.push(smallvec::smallvec![top_vertex, i1, i0]);
         }
-        // Bottom triangles
         for i in 0..segments {
-            let i0 = i + segments * (rings - 2) + 1;
-            let i1 = (i + 1) % segments + segments * (rings - 2) + 1;
+            let i0 = i + segments * (num_rings - 1) + 1;
+            let i1 = (i + 1) % segments + segments * (num_rings - 1) + 1;
             polygons.push(smallvec::smallvec![bottom_vertex, i0, i1]);
         }
-        // Middle quads
-        for j in 0..rings - 2 {
+        for j in 0..num_rings - 1 {
             let j0 = j * segments + 1;
             let j1 = (j + 1) * segments + 1;
             for i in 0..segments {
@@ -159,6 +504,47 @@ impl UVSphere {
     }
 }
 
+/// A torus lying on the XZ plane. The `ring_radius` is the distance from the
+/// center to the middle of the tube, and `tube_radius` the radius of the tube.
+pub struct Torus;
+impl Torus {
+    pub fn build(
+        center: Vec3,
+        ring_radius: f32,
+        tube_radius: f32,
+        ring_segments: u32,
+        tube_segments: u32,
+    ) -> Result<HalfEdgeMesh> {
+        if ring_segments < 3 || tube_segments < 3 {
+            bail!("A torus needs at least 3 ring and tube segments");
+        }
+
+        let mut vertices = Vec::<Vec3>::new();
+        let mut polygons = Vec::<SVec<u32>>::new();
+
+        for i in 0..ring_segments {
+            let theta = 2.0 * PI * i as f32 / ring_segments as f32;
+            let dir = Vec3::new(theta.cos(), 0.0, theta.sin());
+            for j in 0..tube_segments {
+                let phi = 2.0 * PI * j as f32 / tube_segments as f32;
+                let offset = dir * (ring_radius + tube_radius * phi.cos());
+                vertices.push(center + offset + Vec3::Y * tube_radius * phi.sin());
+            }
+        }
+
+        for i in 0..ring_segments {
+            let i0 = i * tube_segments;
+            let i1 = ((i + 1) % ring_segments) * tube_segments;
+            for j in 0..tube_segments {
+                let j1 = (j + 1) % tube_segments;
+                polygons.push(smallvec::smallvec![i0 + j, i0 + j1, i1 + j1, i1 + j]);
+            }
+        }
+
+        HalfEdgeMesh::build_from_polygons(&vertices, &polygons)
+    }
+}
+
 pub struct Line;
 impl Line {
     pub fn build(position: &impl Fn(u32) -> Vec3, segments: u32) -> Result<HalfEdgeMesh> {
@@ -290,6 +676,149 @@ impl Polygon {
             .collect_vec();
         HalfEdgeMesh::build_from_polygons(&points, &[&indices])
     }
+
+    /// Builds the boundary of the polygon, as a closed polyline without
+    /// faces.
+    pub fn build_open_from_points(points: Vec<Vec3>) -> Result<HalfEdgeMesh> {
+        let polygon = Self::build_from_points(points)?;
+        clear_single_face(&polygon);
+        Ok(polygon)
+    }
+}
+
+/// The kind of spline built by [`CurvePrimitive`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveKind {
+    /// Piecewise cubic bezier. Control points are given as `point, handle,
+    /// handle, point, handle, handle, point...`.
+    Bezier,
+    /// A uniform Catmull-Rom spline, passing through all the control points.
+    CatmullRom,
+}
+
+impl CurveKind {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Bezier" => Ok(Self::Bezier),
+            "CatmullRom" => Ok(Self::CatmullRom),
+            _ => bail!("Invalid curve kind: {name}"),
+        }
+    }
+}
+
+/// A smooth curve defined by a list of control points, sampled into a
+/// polyline. Each segment of the spline is split into `resolution` segments.
+pub struct CurvePrimitive;
+impl CurvePrimitive {
+    fn bezier(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+        let u = 1.0 - t;
+        p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t)
+    }
+
+    fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+        let (t2, t3) = (t * t, t * t * t);
+        0.5 * (p1 * 2.0
+            + (p2 - p0) * t
+            + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+            + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+    }
+
+    /// Returns the points of the curve. Closed curves don't repeat their
+    /// first point at the end.
+    pub fn sample(
+        control_points: &[Vec3],
+        kind: CurveKind,
+        resolution: u32,
+        closed: bool,
+    ) -> Result<Vec<Vec3>> {
+        if resolution == 0 {
+            bail!("Curve resolution must be at least 1");
+        }
+        let cp = control_points;
+        let n = cp.len();
+
+        // Each segment is given by the four points that control it
+        let segments: Vec<[Vec3; 4]> = match kind {
+            CurveKind::Bezier => {
+                if closed {
+                    if n < 3 || n % 3 != 0 {
+                        bail!("A closed bezier curve needs a multiple of 3 control points");
+                    }
+                    (0..n / 3)
+                        .map(|i| [cp[3 * i], cp[3 * i + 1], cp[3 * i + 2], cp[(3 * i + 3) % n]])
+                        .collect()
+                } else {
+                    if n < 4 || (n - 1) % 3 != 0 {
+                        bail!("An open bezier curve needs 3k + 1 control points");
+                    }
+                    cp.iter_cpy()
+                        .step_by(3)
+                        .tuple_windows()
+                        .enumerate()
+                        .map(|(i, (a, b))| [a, cp[3 * i + 1], cp[3 * i + 2], b])
+                        .collect()
+                }
+            }
+            CurveKind::CatmullRom => {
+                if closed {
+                    if n < 3 {
+                        bail!("A closed Catmull-Rom curve needs at least 3 control points");
+                    }
+                    (0..n)
+                        .map(|i| [cp[(i + n - 1) % n], cp[i], cp[(i + 1) % n], cp[(i + 2) % n]])
+                        .collect()
+                } else {
+                    if n < 2 {
+                        bail!("A Catmull-Rom curve needs at least 2 control points");
+                    }
+                    // The ends are extended by mirroring their neighbors, so
+                    // the curve reaches the first and last points.
+                    let first = cp[0] * 2.0 - cp[1];
+                    let last = cp[n - 1] * 2.0 - cp[n - 2];
+                    (0..n - 1)
+                        .map(|i| {
+                            let p0 = if i == 0 { first } else { cp[i - 1] };
+                            let p3 = if i + 2 < n { cp[i + 2] } else { last };
+                            [p0, cp[i], cp[i + 1], p3]
+                        })
+                        .collect()
+                }
+            }
+        };
+
+        let eval = |[p0, p1, p2, p3]: [Vec3; 4], t: f32| match kind {
+            CurveKind::Bezier => Self::bezier(p0, p1, p2, p3, t),
+            CurveKind::CatmullRom => Self::catmull_rom(p0, p1, p2, p3, t),
+        };
+        let mut points = segments
+            .iter_cpy()
+            .flat_map(|seg| (0..resolution).map(move |i| (seg, i as f32 / resolution as f32)))
+            .map(|(seg, t)| eval(seg, t))
+            .collect_vec();
+        if !closed {
+            points.push(eval(segments[segments.len() - 1], 1.0));
+        }
+        Ok(points)
+    }
+
+    pub fn build(
+        control_points: &[Vec3],
+        kind: CurveKind,
+        resolution: u32,
+        closed: bool,
+    ) -> Result<HalfEdgeMesh> {
+        let points = Self::sample(control_points, kind, resolution, closed)?;
+        if closed {
+            if points.len() < 3 {
+                bail!("A closed curve needs at least 3 points. Try a higher resolution.");
+            }
+            let curve = Polygon::build_from_points(points)?;
+            clear_single_face(&curve);
+            Ok(curve)
+        } else {
+            Line::build_from_points(points)
+        }
+    }
 }
 
 pub struct Cone;
@@ -356,7 +885,8 @@ impl Cone {
     }
 }
 
-struct Cylinder;
+/// A closed cylinder along the Y axis.
+pub struct Cylinder;
 impl Cylinder {
     pub fn build(
         center: Vec3,
@@ -368,6 +898,279 @@ impl Cylinder {
     }
 }
 
+/// A spur gear on the XZ plane, with involute teeth, extruded along Y.
+pub struct Gear;
+impl Gear {
+    /// Number of points along each side of a tooth.
+    const FLANK_SAMPLES: usize = 5;
+
+    /// The involute function, giving the polar angle of the point of the
+    /// involute curve where the pressure angle is `alpha`.
+    fn involute(alpha: f32) -> f32 {
+        alpha.tan() - alpha
+    }
+
+    /// Returns the closed outline of the gear, as a list of points on the XZ
+    /// plane going counter-clockwise, like [`Circle`], along with their
+    /// angles. Angles are measured from the Z axis, which runs through the
+    /// middle of the first tooth.
+    fn profile(num_teeth: usize, module: f32, pressure_angle: f32) -> Result<Vec<(f32, f32)>> {
+        let z = num_teeth as f32;
+        let pitch_radius = module * z / 2.0;
+        let base_radius = pitch_radius * pressure_angle.cos();
+        let tip_radius = pitch_radius + module;
+        let root_radius = pitch_radius - 1.25 * module;
+
+        // Angle from the middle of the tooth to a flank at the base circle.
+        // At the pitch circle, teeth and gaps are equally wide.
+        let base_half_angle = PI / (2.0 * z) + Self::involute(pressure_angle);
+        let half_angle_at = |r: f32| {
+            let alpha = (base_radius / r.max(base_radius)).clamp(-1.0, 1.0).acos();
+            base_half_angle - Self::involute(alpha)
+        };
+        if half_angle_at(tip_radius) <= 0.0 {
+            bail!("The teeth are pointed with these parameters. Try using more teeth.");
+        }
+
+        // (radius, angle offset from the middle of the tooth) going up the
+        // right side of the tooth. Below the base circle there's no
+        // involute, so the flank continues as a radial line.
+        let flank_start = root_radius.max(base_radius);
+        let mut flank = vec![];
+        if root_radius < base_radius {
+            flank.push((root_radius, -base_half_angle));
+        }
+        for i in 0..Self::FLANK_SAMPLES {
+            let t = i as f32 / (Self::FLANK_SAMPLES - 1) as f32;
+            let r = lerp(flank_start, tip_radius, t);
+            flank.push((r, -half_angle_at(r)));
+        }
+
+        let tooth_angle = 2.0 * PI / z;
+        let mut profile = vec![];
+        for tooth in 0..num_teeth {
+            let middle = tooth as f32 * tooth_angle;
+            profile.extend(flank.iter().map(|(r, a)| (*r, middle + a)));
+            profile.extend(flank.iter().rev().map(|(r, a)| (*r, middle - a)));
+            // Bottom of the gap until the next tooth
+            profile.push((root_radius, middle + 0.5 * tooth_angle));
+        }
+        Ok(profile)
+    }
+
+    /// Builds a gear with `num_teeth` involute teeth. The `module` is the
+    /// size of the teeth: The pitch diameter of the gear is `module *
+    /// num_teeth`, and two gears mesh when they have the same module and
+    /// `pressure_angle` (in radians). The gear has the given `thickness`,
+    /// and a hole of `bore_radius` in its middle, unless the radius is zero.
+    pub fn build(
+        center: Vec3,
+        num_teeth: usize,
+        module: f32,
+        pressure_angle: f32,
+        thickness: f32,
+        bore_radius: f32,
+    ) -> Result<HalfEdgeMesh> {
+        if num_teeth < 4 {
+            bail!("A gear needs at least 4 teeth");
+        }
+        if module <= 0.0 || thickness <= 0.0 {
+            bail!("Module and thickness must be positive");
+        }
+        if pressure_angle <= 0.0 || pressure_angle >= 0.5 * PI {
+            bail!("The pressure angle must be between 0 and 90 degrees");
+        }
+        let root_radius = module * (num_teeth as f32 / 2.0 - 1.25);
+        if bore_radius < 0.0 || bore_radius >= root_radius {
+            bail!("The bore radius must be between 0 and the root radius ({root_radius})");
+        }
+
+        let profile = Self::profile(num_teeth, module, pressure_angle)?;
+        let n = profile.len();
+        let v_offset = Vec3::new(0.0, thickness / 2.0, 0.0);
+        let point = |radius: f32, angle: f32| Quat::from_rotation_y(angle) * (Vec3::Z * radius);
+
+        // Vertices are laid out as: Outline bottom, outline top and then,
+        // when there's a bore, bore bottom and bore top. The bore has a
+        // vertex at the angle of each outline vertex, so the caps are made
+        // of quads.
+        let mut verts = vec![];
+        for offset in [-v_offset, v_offset] {
+            verts.extend(profile.iter().map(|(r, a)| center + offset + point(*r, *a)));
+        }
+        let has_bore = bore_radius > 1e-5;
+        if has_bore {
+            for offset in [-v_offset, v_offset] {
+                verts.extend(
+                    profile
+                        .iter()
+                        .map(|(_, a)| center + offset + point(bore_radius, *a)),
+                );
+            }
+        }
+
+        let (bottom, top, bore_bottom, bore_top) = (0, n, 2 * n, 3 * n);
+        let mut faces: Vec<SVec<usize>> = vec![];
+        for i in 0..n {
+            let j = (i + 1) % n;
+            faces.push(smallvec::smallvec![
+                bottom + i,
+                bottom + j,
+                top + j,
+                top + i
+            ]);
+            if has_bore {
+                faces.push(smallvec::smallvec![
+                    bore_top + i,
+                    bore_top + j,
+                    bore_bottom + j,
+                    bore_bottom + i
+                ]);
+                faces.push(smallvec::smallvec![
+                    top + i,
+                    top + j,
+                    bore_top + j,
+                    bore_top + i
+                ]);
+                faces.push(smallvec::smallvec![
+                    bottom + j,
+                    bottom + i,
+                    bore_bottom + i,
+                    bore_bottom + j
+                ]);
+            }
+        }
+        if !has_bore {
+            faces.push((bottom..bottom + n).rev().collect());
+            faces.push((top..top + n).collect());
+        }
+
+        HalfEdgeMesh::build_from_polygons(&verts, &faces)
+    }
+}
+
+/// A rod with an ISO metric screw thread along the Y axis.
+pub struct Thread;
+impl Thread {
+    /// Rings of vertices along each turn of the thread.
+    const RINGS_PER_PITCH: usize = 16;
+
+    /// Depth of the basic ISO profile, relative to the pitch: 5/8 of the
+    /// height of the fundamental triangle.
+    const DEPTH: f32 = 0.541_266;
+
+    /// Returns how far below the crest the basic ISO profile is, as a
+    /// fraction of its depth, at `u` along a turn. The profile has a flat
+    /// crest of 1/8 of the pitch, 60 degree flanks and a flat root of 1/4 of
+    /// the pitch.
+    fn profile(u: f32) -> f32 {
+        const CREST: f32 = 0.125;
+        const FLANK: f32 = 0.3125;
+        const ROOT: f32 = 0.25;
+        if u < CREST {
+            0.0
+        } else if u < CREST + FLANK {
+            (u - CREST) / FLANK
+        } else if u < CREST + FLANK + ROOT {
+            1.0
+        } else {
+            1.0 - (u - CREST - FLANK - ROOT) / FLANK
+        }
+    }
+
+    /// Builds a right-handed threaded rod of `length` with the given major
+    /// `diameter` and `pitch`, the distance between turns. Rings around the
+    /// rod have `num_vertices` each. Subtracting the rod from another mesh
+    /// cuts the matching internal thread.
+    pub fn build(
+        center: Vec3,
+        diameter: f32,
+        pitch: f32,
+        length: f32,
+        num_vertices: usize,
+    ) -> Result<HalfEdgeMesh> {
+        if diameter <= 0.0 || pitch <= 0.0 || length <= 0.0 {
+            bail!("The diameter, pitch and length of a thread must be positive");
+        }
+        let radius = diameter / 2.0;
+        let depth = Self::DEPTH * pitch;
+        if depth >= radius {
+            bail!("The pitch is too large for a thread of this diameter");
+        }
+        if num_vertices < 3 {
+            bail!("A thread needs at least 3 vertices around it");
+        }
+        let num_rings = ((length / pitch) * Self::RINGS_PER_PITCH as f32).ceil() as usize + 1;
+
+        // Going up the rod, the thread turns counter-clockwise when seen from
+        // above, like the angles of `Gear`, which makes it right-handed.
+        let n = num_vertices;
+        let mut verts = vec![];
+        for ring in 0..num_rings {
+            let y = length * (ring as f32 / (num_rings - 1) as f32 - 0.5);
+            for i in 0..n {
+                let angle = 2.0 * PI * i as f32 / n as f32;
+                let u = (y / pitch - angle / (2.0 * PI)).rem_euclid(1.0);
+                let r = radius - depth * Self::profile(u);
+                verts.push(center + Vec3::Y * y + Quat::from_rotation_y(angle) * (Vec3::Z * r));
+            }
+        }
+
+        let mut faces: Vec<SVec<usize>> = vec![];
+        for ring in 0..num_rings - 1 {
+            let (bottom, top) = (ring * n, (ring + 1) * n);
+            for i in 0..n {
+                let j = (i + 1) % n;
+                faces.push(smallvec::smallvec![
+                    bottom + i,
+                    bottom + j,
+                    top + j,
+                    top + i
+                ]);
+            }
+        }
+        faces.push((0..n).rev().collect());
+        faces.push(((num_rings - 1) * n..num_rings * n).collect());
+
+        HalfEdgeMesh::build_from_polygons(&verts, &faces)
+    }
+}
+
+/// A grid on the XZ plane, centered at the origin, displaced along Y by a
+/// fractal noise heightfield.
+pub struct Terrain;
+impl Terrain {
+    /// Builds a terrain of the given `size` with `resolution` quads along
+    /// each side.
+    pub fn build(size: Vec2, resolution: UVec2, noise: &NoiseParams) -> Result<HalfEdgeMesh> {
+        if resolution.x == 0 || resolution.y == 0 {
+            bail!("Terrain resolution must be at least 1");
+        }
+        let noise = FractalNoise::new(noise.clone());
+
+        let (nx, nz) = (resolution.x + 1, resolution.y + 1);
+        let mut vertices = Vec::<Vec3>::new();
+        for i in 0..nx {
+            for j in 0..nz {
+                let uv = Vec2::new(i as f32, j as f32) / resolution.as_vec2();
+                let xz = (uv - Vec2::splat(0.5)) * size;
+                let height = noise.sample_2d(xz);
+                vertices.push(Vec3::new(xz.x, height, xz.y));
+            }
+        }
+
+        let mut polygons = Vec::<[u32; 4]>::new();
+        for i in 0..resolution.x {
+            for j in 0..resolution.y {
+                let v = |i: u32, j: u32| i * nz + j;
+                polygons.push([v(i, j), v(i, j + 1), v(i + 1, j + 1), v(i + 1, j)]);
+            }
+        }
+
+        HalfEdgeMesh::build_from_polygons(&vertices, &polygons)
+    }
+}
+
 pub struct Grid;
 impl Grid {
     pub fn build(x: u32, y: u32, spacing_x: f32, spacing_y: f32) -> Result<HalfEdgeMesh> {
@@ -451,6 +1254,48 @@ impl Catenary {
     }
 }
 
+/// A tube swept along an existing polyline, like the output of `Line` or
+/// `Catenary`.
+pub struct Tube;
+impl Tube {
+    /// Sweeps a circle of the given `radius` and number of `sides` along
+    /// `path`, which must be a single open or closed polyline. The
+    /// cross-sections are oriented using parallel transport frames, so the
+    /// tube doesn't twist. Open paths get capped when `cap_ends` is set.
+    pub fn build(
+        path: &HalfEdgeMesh,
+        radius: f32,
+        sides: usize,
+        cap_ends: bool,
+    ) -> Result<HalfEdgeMesh> {
+        if sides < 3 {
+            bail!("A tube needs at least 3 sides");
+        }
+        let (points, is_closed) = {
+            let conn = path.read_connectivity();
+            let positions = path.read_positions();
+            let bag = path.resolve_halfedge_selection_full(&SelectionExpression::All)?;
+            let (vertices, is_closed) = edit_ops::sort_bag_of_edges(&conn, &bag)?;
+            let points = vertices.iter_cpy().map(|v| positions[v]).collect_vec();
+            (points, is_closed)
+        };
+
+        let mut builder = PipeBuilder::default();
+        if is_closed {
+            if points.len() < 3 {
+                bail!("A closed path needs at least 3 points");
+            }
+            builder.closed_tube(&points, |_| radius, sides);
+        } else {
+            if points.len() < 2 {
+                bail!("The path needs at least 2 points");
+            }
+            builder.tube(&points, |_| radius, sides, (cap_ends, cap_ends));
+        }
+        builder.build()
+    }
+}
+
 /// Golden ratio, Phi, `(1 + 5.sqrt())/2`
 const PHI: f32 = 1.618_034;
 /// An Icosahedron, a regular 20-sided convex polyhedra. Useful for approximating spheres.
@@ -503,10 +1348,287 @@ impl Icosahedron {
     }
 }
 
+/// A straight staircase going up from `start` towards `end`.
+pub struct Stairs;
+
+/// The measurements of a staircase, shared by [`Stairs::build`] and
+/// [`Stairs::build_railing`].
+struct StairsLayout {
+    start: Vec3,
+    /// Horizontal direction the stairs climb towards.
+    forward: Vec3,
+    /// Horizontal direction, perpendicular to `forward`.
+    right: Vec3,
+    num_steps: usize,
+    /// The height of each step, adjusted to reach the `end` exactly.
+    rise: f32,
+    depth: f32,
+    width: f32,
+}
+
+impl StairsLayout {
+    fn new(start: Vec3, end: Vec3, step_height: f32, step_depth: f32, width: f32) -> Result<Self> {
+        if step_height <= 0.0 || step_depth <= 0.0 || width <= 0.0 {
+            bail!("Step height, step depth and width must be positive");
+        }
+        // Stairs always go up, so swap the endpoints when end is lower.
+        let (start, end) = if end.y < start.y {
+            (end, start)
+        } else {
+            (start, end)
+        };
+        let forward = Vec3::new(end.x - start.x, 0.0, end.z - start.z)
+            .try_normalize()
+            .ok_or_else(|| {
+                anyhow!("The start and end of the stairs can't be vertically aligned")
+            })?;
+        let height = end.y - start.y;
+        let num_steps = ((height / step_height).round() as usize).max(1);
+        Ok(Self {
+            start,
+            forward,
+            right: forward.cross(Vec3::Y),
+            num_steps,
+            rise: height / num_steps as f32,
+            depth: step_depth,
+            width,
+        })
+    }
+
+    /// Returns a point at distance `f` along the stairs, at height `u` over
+    /// the start and `side` units to the right of the center line.
+    fn point(&self, f: f32, u: f32, side: f32) -> Vec3 {
+        self.start + self.forward * f + Vec3::Y * u + self.right * side
+    }
+}
+
+impl Stairs {
+    /// Builds a closed, solid staircase. The number of steps is computed so
+    /// that steps are as close as possible to `step_height`, while reaching
+    /// the height of `end` exactly. The `end` point only determines the
+    /// height and direction of the stairs: Each step is `step_depth` deep.
+    pub fn build(
+        start: Vec3,
+        end: Vec3,
+        step_height: f32,
+        step_depth: f32,
+        width: f32,
+    ) -> Result<HalfEdgeMesh> {
+        let l = StairsLayout::new(start, end, step_height, step_depth, width)?;
+        let n = l.num_steps;
+
+        // For each side, looking at the stairs in profile: `b` are the points
+        // at the bottom, `tl` and `tr` the top-left and top-right corners of
+        // each step.
+        let per_side = 3 * n + 1;
+        let b = |side: usize, i: usize| side * per_side + i;
+        let tl = |side: usize, i: usize| side * per_side + n + 1 + i;
+        let tr = |side: usize, i: usize| side * per_side + 2 * n + 1 + i;
+
+        let mut positions = vec![];
+        for side in [0.5 * l.width, -0.5 * l.width] {
+            for i in 0..=n {
+                positions.push(l.point(i as f32 * l.depth, 0.0, side));
+            }
+            for i in 0..n {
+                positions.push(l.point(i as f32 * l.depth, (i + 1) as f32 * l.rise, side));
+            }
+            for i in 0..n {
+                positions.push(l.point((i + 1) as f32 * l.depth, (i + 1) as f32 * l.rise, side));
+            }
+        }
+
+        let (r, lf) = (0, 1);
+        let mut polygons: Vec<Vec<usize>> = vec![];
+        for i in 0..n {
+            // Each step has a column below it on either side. Columns after
+            // the first include the top-right corner of the previous step.
+            let mut right_col = vec![b(r, i), b(r, i + 1), tr(r, i), tl(r, i)];
+            let mut left_col = vec![b(lf, i), tl(lf, i), tr(lf, i), b(lf, i + 1)];
+            if i > 0 {
+                right_col.push(tr(r, i - 1));
+                left_col.insert(1, tr(lf, i - 1));
+            }
+            polygons.push(right_col);
+            polygons.push(left_col);
+
+            // Tread
+            polygons.push(vec![tl(r, i), tr(r, i), tr(lf, i), tl(lf, i)]);
+            // Riser
+            if i == 0 {
+                polygons.push(vec![b(r, 0), tl(r, 0), tl(lf, 0), b(lf, 0)]);
+            } else {
+                polygons.push(vec![tr(r, i - 1), tl(r, i), tl(lf, i), tr(lf, i - 1)]);
+            }
+        }
+        // Back
+        polygons.push(vec![tr(r, n - 1), b(r, n), b(lf, n), tr(lf, n - 1)]);
+        // Bottom
+        polygons.push(
+            (0..=n)
+                .rev()
+                .map(|i| b(r, i))
+                .chain((0..=n).map(|i| b(lf, i)))
+                .collect(),
+        );
+
+        HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+    }
+
+    /// Builds the railings for a staircase with the same parameters as in
+    /// [`Stairs::build`]. On each side, a round handrail of `rail_radius` is
+    /// swept along the stairs at `height` units over the treads, supported by
+    /// a post on every step.
+    pub fn build_railing(
+        start: Vec3,
+        end: Vec3,
+        step_height: f32,
+        step_depth: f32,
+        width: f32,
+        height: f32,
+        rail_radius: f32,
+    ) -> Result<HalfEdgeMesh> {
+        let l = StairsLayout::new(start, end, step_height, step_depth, width)?;
+        let n = l.num_steps;
+
+        // The profile is swept with its Y axis aligned to the curve normal and
+        // its Z axis to the tangent, so it needs to lie on the XY plane.
+        let profile = Circle::build_open(Vec3::ZERO, rail_radius, 8)?;
+        for (_, pos) in profile.write_positions().iter_mut() {
+            *pos = Quat::from_rotation_x(PI * 0.5) * *pos;
+        }
+
+        let post_size = rail_radius * 1.5;
+        let mut railing = HalfEdgeMesh::new();
+        for side in [1.0, -1.0] {
+            let offset = side * (0.5 * l.width - post_size);
+
+            // The rail goes through the center of all treads. With a single
+            // step, it spans the whole tread instead.
+            let (f_start, f_end) = if n > 1 {
+                (0.5 * l.depth, (n as f32 - 0.5) * l.depth)
+            } else {
+                (0.0, l.depth)
+            };
+            let rail_start = l.point(f_start, l.rise + height, offset);
+            let rail_end = l.point(f_end, n as f32 * l.rise + height, offset);
+            let tangent = (rail_end - rail_start).normalize();
+            let normal = Vec3::Y.reject_from_normalized(tangent).normalize();
+            let rail_path = Line::build_with_normals(
+                &|i| if i == 0 { rail_start } else { rail_end },
+                &|_| normal,
+                &|_| tangent,
+                1,
+            )?;
+            railing.merge_with(&super::edit_ops::extrude_along_curve(
+                &rail_path, &profile, 0,
+            )?);
+
+            for i in 0..n {
+                let tread = (i + 1) as f32 * l.rise;
+                let center = l.point((i as f32 + 0.5) * l.depth, tread + 0.5 * height, offset);
+                railing.merge_with(&Box::build(
+                    center,
+                    Vec3::new(post_size, height, post_size),
+                )?);
+            }
+        }
+        Ok(railing)
+    }
+}
+
+/// A procedural rock, made by displacing a subdivided icosahedron with
+/// several octaves of noise.
+pub struct Rock;
+impl Rock {
+    /// Builds a rock with roughly unit radius, scaled by `scale`. Different
+    /// values of `seed` give different rocks. The `detail` is the number of
+    /// subdivision steps, with each step also adding an octave of noise.
+    /// `flatten_bottom`, between 0 and 1, cuts off the lower part of the rock
+    /// so it can rest on the ground.
+    pub fn build(
+        seed: u32,
+        scale: Vec3,
+        detail: usize,
+        flatten_bottom: f32,
+    ) -> Result<HalfEdgeMesh> {
+        use noise::{NoiseFn, Seedable};
+
+        if detail > 6 {
+            bail!("Rock detail can't be higher than 6, got {detail}");
+        }
+        let flatten_bottom = flatten_bottom.clamp(0.0, 1.0);
+
+        let mut mesh = Icosahedron::build(Vec3::ZERO, 1.0)?;
+        if detail > 0 {
+            mesh = compact_mesh::CompactMesh::<false>::from_halfedge(&mesh)?
+                .subdivide_multi(detail, false, false)
+                .to_halfedge();
+        }
+
+        let perlin = noise::Perlin::new().set_seed(seed);
+        let octaves = detail + 2;
+        let fbm = |p: Vec3| {
+            let (mut value, mut amplitude, mut frequency) = (0.0, 0.5, 1.2);
+            for _ in 0..octaves {
+                let q = p.as_dvec3() * frequency;
+                value += perlin.get([q.x, q.y, q.z]) as f32 * amplitude;
+                amplitude *= 0.5;
+                frequency *= 2.0;
+            }
+            value
+        };
+
+        {
+            let mut positions = mesh.write_positions();
+            // Vertices are projected back to the sphere before displacing, so
+            // the noise is sampled over a smooth surface.
+            for (_, pos) in positions.iter_mut() {
+                let dir = pos.normalize();
+                *pos = dir * (1.0 + 0.6 * fbm(dir)).max(0.2);
+            }
+
+            if flatten_bottom > 0.0 {
+                let min_y = positions
+                    .iter()
+                    .fold(f32::INFINITY, |min_y, (_, pos)| min_y.min(pos.y));
+                let cut_y = min_y * (1.0 - flatten_bottom);
+                for (_, pos) in positions.iter_mut() {
+                    pos.y = pos.y.max(cut_y);
+                }
+            }
+
+            for (_, pos) in positions.iter_mut() {
+                *pos *= scale;
+            }
+        }
+
+        // Flattening squashes the bottom faces into thin slivers. Collapsing
+        // the shortest edges cleans those up and decimates the flat base.
+        if flatten_bottom > 0.0 {
+            let target_length = {
+                let conn = mesh.read_connectivity();
+                let positions = mesh.read_positions();
+                let (total, count) = conn
+                    .iter_halfedges()
+                    .filter_map(|(h, _)| conn.at_halfedge(h).src_dst_pair().ok())
+                    .fold((0.0, 0), |(total, count), (src, dst)| {
+                        (total + positions[src].distance(positions[dst]), count + 1)
+                    });
+                total / count.max(1) as f32
+            };
+            edit_ops::quantize_edges(&mesh, target_length)?;
+        }
+
+        edit_ops::set_flat_normals(&mut mesh)?;
+        Ok(mesh)
+    }
+}
+
 #[blackjack_macros::blackjack_lua_module]
 mod lua_api {
     use super::*;
-    use crate::lua_engine::lua_stdlib::LVec3;
+    use crate::lua_engine::lua_stdlib::{LVec2, LVec3};
 
     /// Creates a box with given `center` and `size` vectors.
     #[lua(under = "Primitives")]
@@ -514,11 +1636,25 @@ mod lua_api {
         Box::build(center.0, size.0)
     }
 
+    /// Creates a box with given `center` and `size` vectors, with its edges
+    /// and corners rounded by a fillet of `fillet_radius` made of
+    /// `fillet_segments` segments.
+    #[lua(under = "Primitives")]
+    fn rounded_box(
+        center: LVec3,
+        size: LVec3,
+        fillet_radius: f32,
+        fillet_segments: u32,
+    ) -> Result<HalfEdgeMesh> {
+        RoundedBox::build(center.0, size.0, fillet_radius, fillet_segments)
+    }
+
     /// Creates a single quad, located at `center` and oriented along its
-    /// `normal` and `right` vectors with given `size`.
+    /// `normal` and `right` vectors with given `size`. The `size` can be a
+    /// `Vec2` or a regular vector, in which case its `z` coordinate is ignored.
     #[lua(under = "Primitives")]
-    fn quad(center: LVec3, normal: LVec3, right: LVec3, size: LVec3) -> Result<HalfEdgeMesh> {
-        Quad::build(center.0, normal.0, right.0, size.0.truncate())
+    fn quad(center: LVec3, normal: LVec3, right: LVec3, size: LVec2) -> Result<HalfEdgeMesh> {
+        Quad::build(center.0, normal.0, right.0, size.0)
     }
 
     /// Creates an open circle (polyline) with given `center`, `radius` and
@@ -532,6 +1668,92 @@ mod lua_api {
         }
     }
 
+    /// Creates an arc on the XZ plane with given `center` and `radius`, going
+    /// from `start_angle` to `end_angle` (in radians) with `num_vertices`
+    /// vertices. When `filled` is set, the arc is closed through the center
+    /// as a pie slice, otherwise it's an open polyline.
+    #[lua(under = "Primitives")]
+    fn arc(
+        center: LVec3,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        num_vertices: u32,
+        filled: bool,
+    ) -> Result<HalfEdgeMesh> {
+        Arc::build(
+            center.0,
+            radius,
+            start_angle,
+            end_angle,
+            num_vertices as usize,
+            filled,
+        )
+    }
+
+    /// Creates a regular polygon on the XZ plane with the given `center` and
+    /// `num_sides`. The `shape` can be "Disc", a filled polygon of the
+    /// `outer_radius`, "Ring", a washer with a hole of the `inner_radius`, or
+    /// "Star", alternating between both radii. The `twist` rotates the inner
+    /// vertices, in radians.
+    #[lua(under = "Primitives")]
+    fn ngon(
+        center: LVec3,
+        num_sides: usize,
+        outer_radius: f32,
+        inner_radius: f32,
+        twist: f32,
+        shape: String,
+    ) -> Result<HalfEdgeMesh> {
+        NGon::build(
+            center.0,
+            num_sides,
+            outer_radius,
+            inner_radius,
+            twist,
+            NGonShape::from_name(&shape)?,
+        )
+    }
+
+    /// Creates a spur gear with `num_teeth` involute teeth, lying on the XZ
+    /// plane at `center`. The `module` sets the size of the teeth, and the
+    /// `pressure_angle` (in radians) their shape. Gears with the same module
+    /// and pressure angle mesh with each other. The gear is extruded to the
+    /// given `thickness`, and has a hole of `bore_radius` unless it is zero.
+    #[lua(under = "Primitives")]
+    fn gear(
+        center: LVec3,
+        num_teeth: usize,
+        module: f32,
+        pressure_angle: f32,
+        thickness: f32,
+        bore_radius: f32,
+    ) -> Result<HalfEdgeMesh> {
+        Gear::build(
+            center.0,
+            num_teeth,
+            module,
+            pressure_angle,
+            thickness,
+            bore_radius,
+        )
+    }
+
+    /// Creates a rod along the Y axis with an ISO metric screw thread, of
+    /// the given major `diameter`, `pitch` between turns and `length`, with
+    /// `num_vertices` around it. Subtract it from a mesh to cut an internal
+    /// thread.
+    #[lua(under = "Primitives")]
+    fn thread(
+        center: LVec3,
+        diameter: f32,
+        pitch: f32,
+        length: f32,
+        num_vertices: usize,
+    ) -> Result<HalfEdgeMesh> {
+        Thread::build(center.0, diameter, pitch, length, num_vertices)
+    }
+
     /// Creates a truncated cone with the given `center`, `bottom_radius`, `top_radius`,
     /// `height`, and `num_vertices` around its radius. A `top_radius` of 0 will make a standard cone.
     #[lua(under = "Primitives")]
@@ -570,6 +1792,59 @@ mod lua_api {
         UVSphere::build(center.0, segments, rings, radius)
     }
 
+    /// Creates a superellipsoid with given `center` and `size`. The exponents
+    /// `e1` and `e2` control the shape of its vertical and horizontal
+    /// cross-sections respectively: 1 is round, values near 0 are boxy, 2 is
+    /// diamond-shaped and larger values are pinched. The `segments` and
+    /// `rings` work like in `Primitives.uv_sphere`.
+    #[lua(under = "Primitives")]
+    fn superellipsoid(
+        center: LVec3,
+        size: LVec3,
+        e1: f32,
+        e2: f32,
+        segments: u32,
+        rings: u32,
+    ) -> Result<HalfEdgeMesh> {
+        Superellipsoid::build(center.0, size.0, e1, e2, segments, rings)
+    }
+
+    /// Creates a capsule with given `center`, aligned with the Y axis. The
+    /// `height` includes the hemispherical caps of the given `radius`. The
+    /// `segments` and `rings` let you specify the number of sections around
+    /// the capsule and the number of rings on each cap.
+    #[lua(under = "Primitives")]
+    fn capsule(
+        center: LVec3,
+        radius: f32,
+        height: f32,
+        segments: u32,
+        rings: u32,
+    ) -> Result<HalfEdgeMesh> {
+        Capsule::build(center.0, radius, height, segments, rings)
+    }
+
+    /// Creates a torus with given `center` on the XZ plane. The `ring_radius`
+    /// is the distance from the center to the middle of the tube, which has a
+    /// radius of `tube_radius`. The `ring_segments` and `tube_segments` let
+    /// you specify the number of sections around the ring and the tube.
+    #[lua(under = "Primitives")]
+    fn torus(
+        center: LVec3,
+        ring_radius: f32,
+        tube_radius: f32,
+        ring_segments: u32,
+        tube_segments: u32,
+    ) -> Result<HalfEdgeMesh> {
+        Torus::build(
+            center.0,
+            ring_radius,
+            tube_radius,
+            ring_segments,
+            tube_segments,
+        )
+    }
+
     /// Creates an Icosahedron with given `center` and `radius`, a regular polyhedra useful for approximating spheres
     /// without artifacts around the poles.
     #[lua(under = "Primitives")]
@@ -598,12 +1873,103 @@ mod lua_api {
         Catenary::build(start.0, end.0, sag, segments)
     }
 
+    /// Sweeps a circle of the given `radius` and number of `sides` along
+    /// `path`, a single open or closed polyline. Open paths are capped at
+    /// both ends when `cap_ends` is set.
+    #[lua(under = "Primitives")]
+    fn tube(
+        path: &HalfEdgeMesh,
+        radius: f32,
+        sides: usize,
+        cap_ends: bool,
+    ) -> Result<HalfEdgeMesh> {
+        Tube::build(path, radius, sides, cap_ends)
+    }
+
+    /// Creates a smooth polyline from the given `control_points`. The `kind`
+    /// can be "Bezier", where points are given as `point, handle, handle,
+    /// point...`, or "CatmullRom", where the curve passes through all the
+    /// points. Each segment of the curve is split into `resolution`
+    /// segments. When `closed` is set, the end of the curve is joined back to
+    /// its start.
+    #[lua(under = "Primitives")]
+    fn curve(
+        control_points: Vec<LVec3>,
+        kind: String,
+        resolution: u32,
+        closed: bool,
+    ) -> Result<HalfEdgeMesh> {
+        CurvePrimitive::build(
+            &LVec3::cast_vector(control_points),
+            CurveKind::from_name(&kind)?,
+            resolution,
+            closed,
+        )
+    }
+
     /// Creates a single polygon from a given set of points.
     #[lua(under = "Primitives")]
     fn polygon(points: Vec<LVec3>) -> Result<HalfEdgeMesh> {
         Polygon::build_from_points(LVec3::cast_vector(points))
     }
 
+    /// Creates a straight staircase climbing from `start` to `end`. Steps are
+    /// `step_depth` deep, and their height is adjusted to be as close as
+    /// possible to `step_height` while reaching the height of `end`.
+    #[lua(under = "Primitives")]
+    fn stairs(
+        start: LVec3,
+        end: LVec3,
+        step_height: f32,
+        step_depth: f32,
+        width: f32,
+    ) -> Result<HalfEdgeMesh> {
+        Stairs::build(start.0, end.0, step_height, step_depth, width)
+    }
+
+    /// Creates the railings for a staircase with the same parameters as
+    /// `Primitives.stairs`. Railings are placed on both sides, at `height`
+    /// units over the steps and with a handrail of the given `rail_radius`.
+    #[lua(under = "Primitives")]
+    fn stair_railing(
+        start: LVec3,
+        end: LVec3,
+        step_height: f32,
+        step_depth: f32,
+        width: f32,
+        height: f32,
+        rail_radius: f32,
+    ) -> Result<HalfEdgeMesh> {
+        Stairs::build_railing(
+            start.0,
+            end.0,
+            step_height,
+            step_depth,
+            width,
+            height,
+            rail_radius,
+        )
+    }
+
+    /// Creates a rock with the given `seed`, `scale`, `detail` and
+    /// `flatten_bottom` factor. See `Rock::build` for details.
+    #[lua(under = "Primitives")]
+    fn rock(seed: u32, scale: LVec3, detail: usize, flatten_bottom: f32) -> Result<HalfEdgeMesh> {
+        Rock::build(seed, scale.0, detail, flatten_bottom)
+    }
+
+    /// Creates a terrain on the XZ plane of the given `size`, with
+    /// `resolution` quads along each side, displaced by fractal noise. The
+    /// `noise_params` table accepts the same fields as `FractalNoise.new`.
+    #[lua(under = "Primitives")]
+    fn terrain(size: LVec2, resolution: u32, noise_params: mlua::Table) -> Result<HalfEdgeMesh> {
+        Terrain::build(
+            size.0,
+            UVec2::splat(resolution),
+            &NoiseParams::from_table(&noise_params)?,
+        )
+    }
+
     ///Creates a point cloud arranged in a grid
     #[lua(under = "Primitives")]
     fn grid(x: u32, y: u32, spacing_x: f32, spacing_y: f32) -> Result<HalfEdgeMesh> {
@@ -651,6 +2017,19 @@ mod test {
         assert!(pos.iter().map(|x| x.1).contains(&end));
     }
 
+    #[test]
+    fn test_tube() {
+        let path = Catenary::build(Vec3::ZERO, Vec3::new(0.0, 1.0, 1.0), 1.0, 8).unwrap();
+        let tube = Tube::build(&path, 0.1, 6, true).unwrap();
+        assert_eq!(tube.read_connectivity().num_vertices(), 9 * 6);
+        assert_eq!(tube.read_connectivity().num_faces(), 8 * 6 + 2);
+
+        let circle = Circle::build_open(Vec3::ZERO, 1.0, 12).unwrap();
+        let tube = Tube::build(&circle, 0.1, 4, true).unwrap();
+        assert_eq!(tube.read_connectivity().num_vertices(), 12 * 4);
+        assert_eq!(tube.read_connectivity().num_faces(), 12 * 4);
+    }
+
     #[test]
     fn test_line_from_points() {
         // Too few points can cause problems with normal/tangent calculations
@@ -659,8 +2038,148 @@ mod test {
         Line::build_from_points(vec![Vec3::ZERO, Vec3::Y]).unwrap();
     }
 
+    #[test]
+    fn test_curve() {
+        let points = [Vec3::ZERO, Vec3::X, Vec3::X + Vec3::Z, Vec3::Z];
+        let curve = CurvePrimitive::sample(&points, CurveKind::CatmullRom, 4, false).unwrap();
+        // The curve passes through all the control points
+        assert_eq!(curve.len(), 3 * 4 + 1);
+        for (i, p) in points.iter().enumerate() {
+            assert!(curve[i * 4].distance(*p) < 1e-5);
+        }
+        let closed = CurvePrimitive::build(&points, CurveKind::CatmullRom, 4, true).unwrap();
+        assert_eq!(closed.read_connectivity().num_vertices(), 16);
+        assert_eq!(closed.read_connectivity().num_faces(), 0);
+
+        let bezier = CurvePrimitive::sample(&points, CurveKind::Bezier, 8, false).unwrap();
+        assert!(bezier[0].distance(points[0]) < 1e-5);
+        assert!(bezier[8].distance(points[3]) < 1e-5);
+        assert!(CurvePrimitive::sample(&points[..3], CurveKind::Bezier, 8, false).is_err());
+    }
+
+    #[test]
+    fn test_rounded_box() {
+        let rounded = RoundedBox::build(Vec3::ZERO, Vec3::new(2.0, 1.0, 1.0), 0.2, 3).unwrap();
+        // Closed manifold: V - E + F = 2
+        let conn = rounded.read_connectivity();
+        let edges = conn.num_halfedges() / 2;
+        assert_eq!(
+            conn.num_vertices() as i64 - edges as i64 + conn.num_faces() as i64,
+            2
+        );
+        // A fillet as big as the box gives a sphere-like shape
+        RoundedBox::build(Vec3::ZERO, Vec3::ONE, 1.0, 2).unwrap();
+    }
+
+    #[test]
+    fn test_terrain() {
+        let terrain =
+            Terrain::build(Vec2::ONE * 10.0, UVec2::new(4, 8), &Default::default()).unwrap();
+        assert_eq!(terrain.read_connectivity().num_vertices(), 5 * 9);
+        assert_eq!(terrain.read_connectivity().num_faces(), 4 * 8);
+    }
+
+    #[test]
+    fn test_gear() {
+        let gear = Gear::build(Vec3::ZERO, 12, 0.1, PI / 9.0, 0.2, 0.2).unwrap();
+        let pitch_radius = 0.1 * 12.0 / 2.0;
+        let pos = gear.read_positions();
+        let max_radius = pos.iter().map(|(_, p)| p.xz().length()).fold(0.0, f32::max);
+        assert!((max_radius - (pitch_radius + 0.1)).abs() < 1e-4);
+        // The first tooth is centered on the Z axis
+        let tip = |sign: f32| {
+            pos.iter().any(|(_, p)| {
+                (p.xz().length() - max_radius).abs() < 1e-4 && p.x * sign > 0.0 && p.z > 0.65
+            })
+        };
+        assert!(tip(1.0) && tip(-1.0));
+        drop(pos);
+        let solid = Gear::build(Vec3::ZERO, 12, 0.1, PI / 9.0, 0.2, 0.0).unwrap();
+        assert_eq!(
+            solid.read_connectivity().num_vertices() * 2,
+            gear.read_connectivity().num_vertices()
+        );
+        assert!(Gear::build(Vec3::ZERO, 12, 0.1, PI / 9.0, 0.2, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_arc() {
+        let arc = Arc::build(Vec3::ZERO, 1.0, 0.0, PI, 9, false).unwrap();
+        assert_eq!(arc.read_connectivity().num_vertices(), 9);
+        assert_eq!(arc.read_connectivity().num_faces(), 0);
+        let pos = arc.read_positions();
+        assert!(pos.iter().any(|(_, p)| p.distance(Vec3::Z) < 1e-5));
+        assert!(pos.iter().any(|(_, p)| p.distance(-Vec3::Z) < 1e-5));
+
+        let slice = Arc::build(Vec3::ZERO, 1.0, 0.0, PI * 0.5, 5, true).unwrap();
+        assert_eq!(slice.read_connectivity().num_faces(), 1);
+        assert_eq!(slice.read_connectivity().num_vertices(), 6);
+        assert!(Arc::build(Vec3::ZERO, 1.0, 1.0, 1.0, 5, true).is_err());
+    }
+
+    #[test]
+    fn test_ngon() {
+        let ring = NGon::build(Vec3::ZERO, 6, 1.0, 0.5, 0.0, NGonShape::Ring).unwrap();
+        assert_eq!(ring.read_connectivity().num_faces(), 6);
+        let star = NGon::build(Vec3::ZERO, 5, 1.0, 0.4, 0.0, NGonShape::Star).unwrap();
+        assert_eq!(star.read_connectivity().num_vertices(), 10);
+        assert!(NGon::build(Vec3::ZERO, 2, 1.0, 0.5, 0.0, NGonShape::Disc).is_err());
+    }
+
+    #[test]
+    fn test_superellipsoid() {
+        let size = Vec3::new(2.0, 1.0, 3.0);
+        let mesh = Superellipsoid::build(Vec3::ZERO, size, 0.2, 0.2, 16, 8).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_vertices(), 2 + 16 * 7);
+        // Boxy superellipsoids stay within their bounding box
+        let pos = mesh.read_positions();
+        assert!(pos
+            .iter()
+            .all(|(_, p)| p.abs().cmple(size * 0.5 + 1e-5).all()));
+        assert!(Superellipsoid::build(Vec3::ZERO, size, 0.0, 1.0, 16, 8).is_err());
+    }
+
+    #[test]
+    fn test_capsule() {
+        let capsule = Capsule::build(Vec3::ZERO, 0.5, 2.0, 8, 4).unwrap();
+        // Two poles, four rings per cap and no duplicated seam vertices
+        assert_eq!(capsule.read_connectivity().num_vertices(), 2 + 8 * 8);
+        // A short capsule is a sphere
+        let sphere = Capsule::build(Vec3::ZERO, 0.5, 0.5, 8, 4).unwrap();
+        assert_eq!(sphere.read_connectivity().num_vertices(), 2 + 8 * 7);
+        assert!(Capsule::build(Vec3::ZERO, 0.5, 2.0, 8, 0).is_err());
+    }
+
+    #[test]
+    fn test_torus() {
+        let torus = Torus::build(Vec3::ZERO, 1.0, 0.25, 12, 8).unwrap();
+        assert_eq!(torus.read_connectivity().num_vertices(), 12 * 8);
+        assert_eq!(torus.read_connectivity().num_faces(), 12 * 8);
+        assert!(Torus::build(Vec3::ZERO, 1.0, 0.25, 2, 8).is_err());
+    }
+
     #[test]
     fn test_icosahedron() {
         Icosahedron::build(Vec3::ZERO, 1.).unwrap();
     }
+
+    #[test]
+    fn test_rock() {
+        Rock::build(0, Vec3::ONE, 0, 0.0).unwrap();
+        Rock::build(42, Vec3::new(1.0, 0.6, 1.2), 2, 0.3).unwrap();
+        assert!(Rock::build(0, Vec3::ONE, 7, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_stairs() {
+        let end = Vec3::new(0.0, 1.0, 2.0);
+        let stairs = Stairs::build(Vec3::ZERO, end, 0.25, 0.5, 1.0).unwrap();
+        // Two columns, a tread and a riser per step, plus back and bottom.
+        assert_eq!(stairs.read_connectivity().num_faces(), 4 * 4 + 2);
+        Stairs::build(end, Vec3::ZERO, 0.25, 0.5, 1.0).unwrap();
+        Stairs::build(Vec3::ZERO, end, 2.0, 0.5, 1.0).unwrap();
+        Stairs::build_railing(Vec3::ZERO, end, 0.25, 0.5, 1.0, 0.9, 0.03).unwrap();
+        assert!(Stairs::build(Vec3::ZERO, Vec3::Y, 0.25, 0.5, 1.0).is_err());
+    }
 }
@@ -0,0 +1,119 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use rstar::{PointDistance, RTree, RTreeObject, AABB};
+
+use super::*;
+
+/// The kinds of mesh elements a point can snap to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapTarget {
+    Vertex,
+    EdgeMidpoint,
+    FaceCenter,
+}
+
+struct SnapPoint {
+    pos: Vec3,
+    target: SnapTarget,
+}
+
+impl RTreeObject for SnapPoint {
+    type Envelope = AABB<[f32; 3]>;
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_point(self.pos.to_array())
+    }
+}
+
+impl PointDistance for SnapPoint {
+    fn distance_2(&self, point: &[f32; 3]) -> f32 {
+        self.pos.distance_squared(Vec3::from_slice(point))
+    }
+}
+
+/// A spatial index over the points of a mesh that other points can snap to,
+/// like its vertices or face centers. Used to precisely align gizmos and
+/// viewport tools with existing geometry.
+pub struct SnapIndex {
+    tree: RTree<SnapPoint>,
+}
+
+impl SnapIndex {
+    /// Builds an index with the points of `mesh` for each of the given kinds
+    /// of `targets`.
+    pub fn build(mesh: &HalfEdgeMesh, targets: &[SnapTarget]) -> Result<Self> {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let mut points = Vec::new();
+        for &target in targets {
+            match target {
+                SnapTarget::Vertex => {
+                    points.extend(conn.iter_vertices().map(|(v, _)| SnapPoint {
+                        pos: positions[v],
+                        target,
+                    }));
+                }
+                SnapTarget::EdgeMidpoint => {
+                    // Twin halfedges share the same midpoint
+                    for (h, halfedge) in conn.iter_halfedges() {
+                        if halfedge.twin.map(|t| h < t).unwrap_or(true) {
+                            let (src, dst) = conn.at_halfedge(h).src_dst_pair()?;
+                            points.push(SnapPoint {
+                                pos: (positions[src] + positions[dst]) * 0.5,
+                                target,
+                            });
+                        }
+                    }
+                }
+                SnapTarget::FaceCenter => {
+                    points.extend(conn.iter_faces().map(|(f, _)| SnapPoint {
+                        pos: conn.face_vertex_average(&positions, f),
+                        target,
+                    }));
+                }
+            }
+        }
+        Ok(Self {
+            tree: RTree::bulk_load(points),
+        })
+    }
+
+    /// Returns the indexed point closest to `point`, along with its kind, as
+    /// long as it's within `max_distance` of it.
+    pub fn nearest(&self, point: Vec3, max_distance: f32) -> Option<(Vec3, SnapTarget)> {
+        self.tree
+            .nearest_neighbor(&point.to_array())
+            .filter(|nearest| nearest.pos.distance(point) <= max_distance)
+            .map(|nearest| (nearest.pos, nearest.target))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.size() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_snap_index() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let index = SnapIndex::build(&mesh, &[SnapTarget::Vertex, SnapTarget::FaceCenter]).unwrap();
+        assert_eq!(
+            index.nearest(Vec3::new(0.45, 0.6, 0.5), 0.2),
+            Some((Vec3::splat(0.5), SnapTarget::Vertex))
+        );
+        assert_eq!(
+            index.nearest(Vec3::new(0.05, 0.45, 0.0), 0.2),
+            Some((Vec3::new(0.0, 0.5, 0.0), SnapTarget::FaceCenter))
+        );
+        assert_eq!(index.nearest(Vec3::splat(3.0), 0.2), None);
+
+        let edges = SnapIndex::build(&mesh, &[SnapTarget::EdgeMidpoint]).unwrap();
+        assert_eq!(edges.tree.size(), 12);
+    }
+}
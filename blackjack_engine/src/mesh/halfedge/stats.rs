@@ -0,0 +1,73 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::*;
+
+/// Summary statistics about the size and complexity of a mesh.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MeshStats {
+    pub vertices: usize,
+    /// Number of edges. A pair of twin halfedges counts as a single edge.
+    pub edges: usize,
+    pub halfedges: usize,
+    pub faces: usize,
+    /// Number of triangles the faces of this mesh would produce when
+    /// triangulated, that is, `n - 2` triangles for each n-gon.
+    pub triangles: usize,
+    /// An estimate of the memory used by this mesh, in bytes. This includes
+    /// the connectivity information and the data of all channels.
+    pub memory_bytes: usize,
+}
+
+impl MeshConnectivity {
+    /// Returns the approximate number of bytes allocated to store this
+    /// connectivity information.
+    pub fn memory_usage(&self) -> usize {
+        self.vertices.capacity() * std::mem::size_of::<Vertex>()
+            + self.faces.capacity() * std::mem::size_of::<Face>()
+            + self.halfedges.capacity() * std::mem::size_of::<HalfEdge>()
+    }
+}
+
+impl HalfEdgeMesh {
+    /// Computes the [`MeshStats`] for this mesh.
+    pub fn stats(&self) -> MeshStats {
+        let conn = self.read_connectivity();
+        let edges = conn
+            .iter_halfedges()
+            .filter(|(h, halfedge)| halfedge.twin.map(|t| *h < t).unwrap_or(true))
+            .count();
+        let triangles = conn
+            .iter_faces()
+            .map(|(f, _)| conn.face_vertices(f).len().saturating_sub(2))
+            .sum();
+        MeshStats {
+            vertices: conn.num_vertices(),
+            edges,
+            halfedges: conn.num_halfedges(),
+            faces: conn.num_faces(),
+            triangles,
+            memory_bytes: conn.memory_usage() + self.channels.memory_usage(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_mesh_stats() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let stats = mesh.stats();
+        assert_eq!(stats.vertices, 8);
+        assert_eq!(stats.edges, 12);
+        assert_eq!(stats.halfedges, 24);
+        assert_eq!(stats.faces, 6);
+        assert_eq!(stats.triangles, 12);
+        assert!(stats.memory_bytes > 0);
+    }
+}
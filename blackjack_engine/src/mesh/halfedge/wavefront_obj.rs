@@ -7,19 +7,339 @@
 use slotmap::SecondaryMap;
 use std::{
     fs::File,
-    io::{BufReader, BufWriter, Write},
-    path::PathBuf,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
 };
 use wavefront_rs::obj::{
     self,
     entity::{Entity, FaceVertex},
 };
 
+use crate::materials::{Material, MaterialRegistry, MATERIAL_CHANNEL};
 use crate::prelude::*;
+use crate::progress::{NoProgress, ProgressSink, ProgressTracker};
+
+use super::import_options::ImportOptions;
+use super::selection::SelectionExpression;
+
+/// Returns the names written to MTL and OBJ files for the materials in
+/// `registry`, by their original name. Names end at the first whitespace for
+/// most importers, so whitespace and control characters are replaced by
+/// underscores. Names that become equal get a numbered suffix, to keep the
+/// materials apart.
+fn mtl_material_names(registry: &MaterialRegistry) -> HashMap<&str, String> {
+    let mut names = HashMap::new();
+    let mut used = HashSet::new();
+    for material in registry.iter() {
+        let base = material
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_whitespace() || c.is_control() {
+                    '_'
+                } else {
+                    c
+                }
+            })
+            .collect::<String>();
+        let base = if base.is_empty() {
+            "material".to_string()
+        } else {
+            base
+        };
+        let mut name = base.clone();
+        let mut i = 1;
+        while used.contains(&name) {
+            name = format!("{base}.{i:03}");
+            i += 1;
+        }
+        used.insert(name.clone());
+        names.insert(material.name.as_str(), name);
+    }
+    names
+}
+
+/// Writes the materials in `registry` as a Wavefront MTL file at `path`,
+/// using the `names` from [`mtl_material_names`].
+fn write_mtl_file(
+    path: &Path,
+    registry: &MaterialRegistry,
+    names: &HashMap<&str, String>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(
+        writer,
+        "# Generated by Blackjack: https://github.com/setzer22/blackjack"
+    )?;
+    for material in registry.iter() {
+        let Material {
+            name,
+            base_color: c,
+            roughness,
+            metallic,
+            base_color_texture,
+            normal_texture,
+        } = material;
+        writeln!(writer)?;
+        writeln!(writer, "newmtl {}", names[name.as_str()])?;
+        writeln!(writer, "Kd {} {} {}", c.x, c.y, c.z)?;
+        // Ns is the specular exponent of the Phong model. PBR-aware importers
+        // read the roughness (Pr) and metallic (Pm) extensions instead.
+        writeln!(writer, "Ns {}", (1.0 - roughness).powi(2) * 1000.0)?;
+        writeln!(writer, "Pr {roughness}")?;
+        writeln!(writer, "Pm {metallic}")?;
+        if let Some(texture) = base_color_texture {
+            writeln!(writer, "map_Kd {texture}")?;
+        }
+        if let Some(texture) = normal_texture {
+            writeln!(writer, "norm {texture}")?;
+        }
+    }
+    Ok(())
+}
+
+/// The order in which vertices and faces are written to exported files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportOrdering {
+    /// Elements are written in the order they are stored in the mesh. This is
+    /// the fastest option, but small edits to the mesh may shuffle the whole
+    /// file around.
+    #[default]
+    Internal,
+    /// Vertices are sorted along a space-filling curve (Morton order), and
+    /// faces by their vertices. The output only depends on the geometry, so
+    /// small edits produce small changes in the exported file. Useful when
+    /// exported files are stored in version control.
+    Spatial,
+}
+
+impl ExportOrdering {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Internal" => Ok(Self::Internal),
+            "Spatial" => Ok(Self::Spatial),
+            _ => bail!("Invalid export ordering: {name}"),
+        }
+    }
+}
+
+/// Settings for [`HalfEdgeMesh::to_wavefront_obj_with_options`].
+#[derive(Clone, Copy, Default)]
+pub struct ObjExportOptions<'a> {
+    /// When set, and the mesh has a material channel, the materials are also
+    /// exported as an MTL file next to the OBJ file, and faces reference them.
+    pub materials: Option<&'a MaterialRegistry>,
+    /// The order of the vertices and faces in the file.
+    pub ordering: ExportOrdering,
+    /// When set, only these faces and their vertices are exported. Loose
+    /// edges and points are left out.
+    pub faces: Option<&'a SelectionExpression>,
+}
+
+/// Returns the path for the given `frame` of an exported sequence, by
+/// appending the zero-padded frame number to the file stem of `path`. For
+/// instance, frame 12 of `out/anim.obj` is `out/anim_0012.obj`.
+pub fn sequence_frame_path(path: &Path, frame: i32) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "obj".into());
+    path.with_file_name(format!("{stem}_{frame:04}.{extension}"))
+}
+
+/// Exports an animated sequence as numbered OBJ files, one for each frame in
+/// `frames`. The `cook` function produces the mesh for each frame, usually by
+/// running the graph with a parameter driven by the frame number (see
+/// `graph_interpreter::run_node_output`). Files are named as described in
+/// [`sequence_frame_path`]. Returns the paths of the written files.
+pub fn export_obj_sequence(
+    path: &Path,
+    frames: std::ops::RangeInclusive<i32>,
+    options: &ObjExportOptions,
+    mut cook: impl FnMut(i32) -> Result<HalfEdgeMesh>,
+) -> Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    for frame in frames {
+        let mesh = cook(frame).with_context(|| format!("Error cooking frame {frame}"))?;
+        let frame_path = sequence_frame_path(path, frame);
+        mesh.to_wavefront_obj_with_options(&frame_path, options)?;
+        written.push(frame_path);
+    }
+    Ok(written)
+}
+
+/// Spreads the lower 21 bits of `x` so that there are two zero bits between
+/// each of them.
+fn spread_bits(x: u64) -> u64 {
+    let mut x = x & 0x1f_ffff;
+    x = (x | x << 32) & 0x1f_0000_0000_ffff;
+    x = (x | x << 16) & 0x1f_0000_ff00_00ff;
+    x = (x | x << 8) & 0x100f_00f0_0f00_f00f;
+    x = (x | x << 4) & 0x10c3_0c30_c30c_30c3;
+    x = (x | x << 2) & 0x1249_2492_4924_9249;
+    x
+}
+
+/// A face to be exported, with its list of (vertex, halfedge) corners.
+type ExportFace = (FaceId, SVec<(VertexId, HalfEdgeId)>);
+
+/// Returns the vertices and faces of the mesh in the given `ordering`. When a
+/// set of `selected` faces is given, only those faces and their vertices are
+/// returned.
+fn export_order(
+    conn: &MeshConnectivity,
+    positions: &Positions,
+    ordering: ExportOrdering,
+    selected: Option<&HashSet<FaceId>>,
+) -> (Vec<VertexId>, Vec<ExportFace>) {
+    let mut faces = conn
+        .iter_faces()
+        .filter(|(f, _)| selected.map(|s| s.contains(f)).unwrap_or(true))
+        .map(|(f, _)| {
+            let corners = conn
+                .face_vertices(f)
+                .iter_cpy()
+                .zip(conn.face_edges(f).iter_cpy())
+                .collect();
+            (f, corners)
+        })
+        .collect_vec();
+    let mut vertices = if selected.is_some() {
+        let used: HashSet<VertexId> = faces
+            .iter()
+            .flat_map(|(_, face)| face.iter().map(|(v, _)| *v))
+            .collect();
+        conn.iter_vertices()
+            .map(|(v, _)| v)
+            .filter(|v| used.contains(v))
+            .collect_vec()
+    } else {
+        conn.iter_vertices().map(|(v, _)| v).collect_vec()
+    };
+
+    if ordering == ExportOrdering::Spatial {
+        let (min, max) = vertices.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), v| (min.min(positions[*v]), max.max(positions[*v])),
+        );
+        let extent = (max - min).max(Vec3::splat(f32::EPSILON));
+        let morton = |pos: Vec3| {
+            let q = ((pos - min) / extent * 0x1f_ffff as f32).as_uvec3();
+            spread_bits(q.x as u64) | spread_bits(q.y as u64) << 1 | spread_bits(q.z as u64) << 2
+        };
+        // Ties are broken by the exact position. Coincident vertices are
+        // kept in their internal order, which is the best we can do.
+        vertices.sort_by_cached_key(|v| {
+            let pos = positions[*v];
+            (morton(pos), pos.to_ord())
+        });
+
+        let rank: SecondaryMap<VertexId, usize> = vertices
+            .iter_cpy()
+            .enumerate()
+            .map(|(i, v)| (v, i))
+            .collect();
+        for (_, face) in &mut faces {
+            // Start each face at its lowest vertex, keeping the winding order
+            let first = face
+                .iter()
+                .position_min_by_key(|(v, _)| rank[*v])
+                .unwrap_or(0);
+            face.rotate_left(first);
+        }
+        faces.sort_by_cached_key(|(_, face)| face.iter().map(|(v, _)| rank[*v]).collect_vec());
+    }
+
+    (vertices, faces)
+}
+
+/// Returns the loose edges of the mesh, those with no face on either side,
+/// joined into polylines. Vertices are visited in the given `vertices` order,
+/// so the result is deterministic for a given export ordering.
+fn loose_polylines(conn: &MeshConnectivity, vertices: &[VertexId]) -> Vec<Vec<VertexId>> {
+    let mut neighbors = HashMap::<VertexId, SVec<VertexId>>::new();
+    for (h, halfedge) in conn.iter_halfedges() {
+        let twin = halfedge.twin.and_then(|t| conn.halfedge(t));
+        let is_loose = halfedge.face.is_none() && twin.map(|t| t.face.is_none()).unwrap_or(true);
+        if let (true, Some(src), Some(dst)) = (
+            is_loose,
+            halfedge.vertex,
+            conn.at_halfedge(h).dst_vertex().try_end().ok(),
+        ) {
+            neighbors.entry(src).or_default().push(dst);
+        }
+    }
+    let degree = |v: VertexId| neighbors.get(&v).map(|n| n.len()).unwrap_or(0);
+
+    let mut visited = HashSet::<(VertexId, VertexId)>::new();
+    let mut polylines = vec![];
+    // Polylines start at their endpoints, or anywhere for closed loops, which
+    // are visited last.
+    let starts = vertices
+        .iter_cpy()
+        .filter(|v| degree(*v) > 0)
+        .sorted_by_key(|v| degree(*v) == 2);
+    for start in starts {
+        for first in neighbors[&start].iter_cpy() {
+            if !visited.insert((start, first)) {
+                continue;
+            }
+            visited.insert((first, start));
+            let mut polyline = vec![start, first];
+            let (mut prev, mut current) = (start, first);
+            while degree(current) == 2 && current != start {
+                let next = neighbors[&current]
+                    .iter_cpy()
+                    .find(|n| *n != prev)
+                    .unwrap_or(prev);
+                if !visited.insert((current, next)) {
+                    break;
+                }
+                visited.insert((next, current));
+                polyline.push(next);
+                prev = current;
+                current = next;
+            }
+            polylines.push(polyline);
+        }
+    }
+    polylines
+}
 
 impl HalfEdgeMesh {
     pub fn to_wavefront_obj(&self, path: impl Into<PathBuf>) -> Result<()> {
-        let mut writer = BufWriter::new(File::create(path.into())?);
+        self.to_wavefront_obj_with_materials(path, None)
+    }
+
+    /// Same as `to_wavefront_obj`, but when a material `registry` is given and
+    /// this mesh has a material channel, the materials are also exported as
+    /// an MTL file next to the OBJ file, and faces reference them.
+    pub fn to_wavefront_obj_with_materials(
+        &self,
+        path: impl Into<PathBuf>,
+        registry: Option<&MaterialRegistry>,
+    ) -> Result<()> {
+        self.to_wavefront_obj_with_options(
+            path,
+            &ObjExportOptions {
+                materials: registry,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as `to_wavefront_obj`, with additional export `options`.
+    pub fn to_wavefront_obj_with_options(
+        &self,
+        path: impl Into<PathBuf>,
+        options: &ObjExportOptions,
+    ) -> Result<()> {
+        let path = path.into();
+        let mut writer = BufWriter::new(File::create(&path)?);
 
         // We need to store the mapping between vertex ids and indices in the
         // generated OBJ
@@ -34,45 +354,102 @@ impl HalfEdgeMesh {
         );
         writeln!(writer)?;
 
+        let material_ch = self
+            .channels
+            .read_channel_by_name::<FaceId, f32>(MATERIAL_CHANNEL)
+            .ok();
+        let registry = options
+            .materials
+            .filter(|r| !r.is_empty() && material_ch.is_some());
+        let mtl_names = registry.map(mtl_material_names).unwrap_or_default();
+        if let Some(registry) = registry {
+            let mtl_path = path.with_extension("mtl");
+            write_mtl_file(&mtl_path, registry, &mtl_names)?;
+            let mtl_name = mtl_path
+                .file_name()
+                .ok_or_else(|| anyhow!("Invalid path {}", mtl_path.display()))?;
+            writeln!(writer, "mtllib {}", mtl_name.to_string_lossy())?;
+        }
+
+        let selected = options
+            .faces
+            .map(|sel| self.resolve_face_selection_full(sel))
+            .transpose()?
+            .map(|faces| faces.into_iter().collect::<HashSet<_>>());
+
         let conn = self.read_connectivity();
+        let positions = self.read_positions();
+        let (vertices, faces) =
+            export_order(&conn, &positions, options.ordering, selected.as_ref());
 
-        for (idx, (v_id, _, pos)) in conn
-            .iter_vertices_with_channel(&self.read_positions())
-            .enumerate()
-        {
+        // Vertex colors are not part of the OBJ spec, but the `v x y z r g b`
+        // extension is understood by most tools.
+        let color_ch = self
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>("color")
+            .ok();
+
+        for (idx, v_id) in vertices.iter_cpy().enumerate() {
+            let pos = positions[v_id];
             imap.insert(v_id, (idx + 1) as i32);
+            if let Some(color_ch) = &color_ch {
+                let color = color_ch[v_id];
+                write!(
+                    writer,
+                    "v {} {} {} {} {} {}",
+                    pos.x, pos.y, pos.z, color.x, color.y, color.z
+                )?;
+            } else {
+                obj::format_writer::FormatWriter::write(
+                    &mut writer,
+                    &Entity::Vertex {
+                        x: pos.x as f64,
+                        y: pos.y as f64,
+                        z: pos.z as f64,
+                        w: None,
+                    },
+                );
+            }
+            writeln!(writer)?;
+        }
+
+        // Normals follow the shading mode of the mesh. Smooth meshes store one
+        // normal per vertex, other modes store one per face corner. The map
+        // goes from each face corner to the index of its normal.
+        let mut n_imap = SecondaryMap::<HalfEdgeId, i32>::new();
+        let write_normal = |writer: &mut BufWriter<File>, normal: Vec3| -> Result<()> {
             obj::format_writer::FormatWriter::write(
-                &mut writer,
-                &Entity::Vertex {
-                    x: pos.x as f64,
-                    y: pos.y as f64,
-                    z: pos.z as f64,
-                    w: None,
+                writer,
+                &Entity::VertexNormal {
+                    x: normal.x as f64,
+                    y: normal.y as f64,
+                    z: normal.z as f64,
                 },
             );
             writeln!(writer)?;
-        }
-
-        let mut has_normals = false;
-        if self.gen_config.smooth_normals {
-            if let Some(v_normals_ch) = self.read_vertex_normals() {
-                has_normals = true;
-                for (v, _) in conn.iter_vertices() {
-                    let normal = v_normals_ch[v];
-                    obj::format_writer::FormatWriter::write(
-                        &mut writer,
-                        &Entity::VertexNormal {
-                            x: normal.x as f64,
-                            y: normal.y as f64,
-                            z: normal.z as f64,
-                        },
-                    );
-                    writeln!(writer)?;
+            Ok(())
+        };
+        match (self.gen_config.shading, self.read_vertex_normals()) {
+            (ShadingMode::Smooth, Some(v_normals_ch)) => {
+                for v in vertices.iter_cpy() {
+                    write_normal(&mut writer, v_normals_ch[v])?;
+                }
+                for (_, face) in &faces {
+                    for (v_id, h_id) in face {
+                        n_imap.insert(*h_id, imap[*v_id]);
+                    }
+                }
+            }
+            _ => {
+                let corner_normals = edit_ops::generate_corner_normals_channel(self)?;
+                let corners = faces
+                    .iter()
+                    .flat_map(|(_, face)| face.iter().map(|(_, h)| *h));
+                for (idx, h) in corners.enumerate() {
+                    n_imap.insert(h, (idx + 1) as i32);
+                    write_normal(&mut writer, corner_normals[h])?;
                 }
             }
-        } else {
-            // TODO has_normals = true;
-            println!("TODO: Exporting per-face normals is not yet implemented.")
         }
 
         // Since UVs are stored in halfedges, we need the same mapping as `imap`
@@ -81,7 +458,10 @@ impl HalfEdgeMesh {
         let mut has_uvs = false;
         if let Some(uvs_ch) = self.read_uvs() {
             has_uvs = true;
-            for (idx, (h, _)) in conn.iter_halfedges().enumerate() {
+            let corners = faces
+                .iter()
+                .flat_map(|(_, face)| face.iter().map(|(_, h)| *h));
+            for (idx, h) in corners.enumerate() {
                 h_imap.insert(h, (idx + 1) as i32);
                 let uv = uvs_ch[h];
                 obj::format_writer::FormatWriter::write(
@@ -96,21 +476,24 @@ impl HalfEdgeMesh {
             }
         }
 
-        for (face_id, _) in conn.iter_faces() {
-            let vertices = conn
-                .face_vertices(face_id)
+        let mut current_material = None;
+        for (face_id, face) in &faces {
+            if let (Some(registry), Some(material_ch)) = (registry, &material_ch) {
+                let material = registry
+                    .for_channel_value(material_ch[*face_id])
+                    .map(|m| m.name.as_str());
+                if material != current_material {
+                    if let Some(name) = material {
+                        writeln!(writer, "usemtl {}", mtl_names[name])?;
+                    }
+                    current_material = material;
+                }
+            }
+            let vertices = face
                 .iter()
-                .zip(conn.face_edges(face_id).iter())
                 .map(|(v_id, h_id)| FaceVertex {
                     vertex: imap[*v_id] as i64,
-                    // TODO: For now we rely on emitting one normal per vertex.
-                    // Sometimes there might be less, when we implement flat
-                    // shaded normals.
-                    normal: if has_normals {
-                        Some(imap[*v_id] as i64)
-                    } else {
-                        None
-                    },
+                    normal: n_imap.get(*h_id).map(|idx| *idx as i64),
                     texture: if has_uvs {
                         Some(h_imap[*h_id] as i64)
                     } else {
@@ -122,40 +505,334 @@ impl HalfEdgeMesh {
             writeln!(writer)?;
         }
 
+        // Edges and vertices that are not part of any face are written as
+        // polylines and points, so curves and point clouds survive the export.
+        if selected.is_none() {
+            for polyline in loose_polylines(&conn, &vertices) {
+                let indices = polyline.iter().map(|v| imap[*v].to_string()).join(" ");
+                writeln!(writer, "l {indices}")?;
+            }
+            for v in vertices.iter_cpy().filter(|v| conn[*v].halfedge.is_none()) {
+                writeln!(writer, "p {}", imap[v])?;
+            }
+        }
+
         Ok(())
     }
 
     pub fn from_wavefront_obj(path: PathBuf) -> Result<HalfEdgeMesh> {
-        let mut reader = BufReader::new(File::open(path)?);
-        let mut positions = vec![];
-        let mut polygons = vec![];
-        obj::read_lexer::ReadLexer::read_to_end(&mut reader, |entity| match entity {
-            Entity::Vertex { x, y, z, w: _w } => {
-                positions.push(Vec3::new(x as f32, y as f32, z as f32));
+        Self::from_wavefront_obj_with_progress(path, &ImportOptions::default(), &NoProgress)
+    }
+
+    /// Same as `from_wavefront_obj`, but applies the given import `options`
+    /// and reports the import progress to the given `progress` sink, stopping
+    /// early when it gets cancelled.
+    ///
+    /// Lines are parsed in parallel, in chunks. The mesh topology is then
+    /// built on a single thread.
+    pub fn from_wavefront_obj_with_progress(
+        path: PathBuf,
+        options: &ImportOptions,
+        progress: &dyn ProgressSink,
+    ) -> Result<HalfEdgeMesh> {
+        let name = format!("Importing {}", path.display());
+        progress.report(&name, 0.0);
+        progress.check_cancelled()?;
+
+        let contents = std::fs::read_to_string(&path)?;
+        // More chunks than threads, so the work stays balanced when some
+        // chunks are faster to parse than others.
+        let num_chunks = rayon::current_num_threads() * 4;
+        let (positions, polygons) = parse_obj(&contents, num_chunks, &|fraction| {
+            // Parsing takes most of the time, building the mesh the rest
+            progress.report(&name, 0.7 * fraction);
+            progress.check_cancelled()
+        })?;
+
+        progress.report(&name, 0.7);
+        let (positions, polygons) = options.apply(positions, polygons);
+        let mesh = halfedge::HalfEdgeMesh::build_from_polygons(&positions, &polygons)?;
+        progress.report(&name, 1.0);
+        Ok(mesh)
+    }
+}
+
+/// The size of an OBJ file's contents, read without building its mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjSummary {
+    pub num_vertices: usize,
+    pub num_faces: usize,
+    /// The minimum corner of the bounding box of the vertices.
+    pub min: Vec3,
+    /// The maximum corner of the bounding box of the vertices.
+    pub max: Vec3,
+}
+
+impl ObjSummary {
+    fn empty() -> Self {
+        Self {
+            num_vertices: 0,
+            num_faces: 0,
+            min: Vec3::splat(f32::INFINITY),
+            max: Vec3::splat(f32::NEG_INFINITY),
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Self {
+            num_vertices: self.num_vertices + other.num_vertices,
+            num_faces: self.num_faces + other.num_faces,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// Reads the vertex count, face count and bounding box of the OBJ file at
+/// `path`. This is much faster than importing the file, since no mesh
+/// topology is built.
+pub fn scan_wavefront_obj(path: &Path) -> Result<ObjSummary> {
+    use rayon::prelude::*;
+
+    let contents = std::fs::read_to_string(path)?;
+    let chunks = split_in_chunks(&contents, rayon::current_num_threads() * 4);
+    let summary = chunks
+        .par_iter()
+        .map(|chunk| {
+            let mut summary = ObjSummary::empty();
+            for line in chunk.lines() {
+                let mut tokens = line.split_whitespace();
+                match tokens.next() {
+                    Some("v") => {
+                        let mut coords = tokens.take(3).map(|t| t.parse::<f32>().unwrap_or(0.0));
+                        let v = Vec3::new(
+                            coords.next().unwrap_or(0.0),
+                            coords.next().unwrap_or(0.0),
+                            coords.next().unwrap_or(0.0),
+                        );
+                        summary.num_vertices += 1;
+                        summary.min = summary.min.min(v);
+                        summary.max = summary.max.max(v);
+                    }
+                    Some("f") => summary.num_faces += 1,
+                    _ => {}
+                }
             }
-            Entity::Face { vertices } => {
-                // NOTE: OBJ Wavefront indices start at 1
-                let polygon: SVec<usize> =
-                    vertices.iter().map(|v| (v.vertex - 1) as usize).collect();
-                polygons.push(polygon);
+            summary
+        })
+        .reduce(ObjSummary::empty, ObjSummary::merge);
+    if summary.num_vertices == 0 {
+        bail!("No vertices found in {}", path.display());
+    }
+    Ok(summary)
+}
+
+/// A face, as found in an OBJ file.
+struct ObjFace {
+    /// The OBJ position indices. These start at 1, and may be negative.
+    indices: SVec<i64>,
+    /// The number of vertices that came before this face in its chunk. Used
+    /// to resolve relative indices.
+    vertices_before: usize,
+    line: usize,
+}
+
+/// The vertices and faces parsed from a chunk of contiguous lines of an OBJ
+/// file.
+#[derive(Default)]
+struct ObjChunk {
+    positions: Vec<Vec3>,
+    faces: Vec<ObjFace>,
+}
+
+/// Splits `contents` in roughly `num_chunks` chunks of similar size. Chunks
+/// always end at a line break.
+fn split_in_chunks(contents: &str, num_chunks: usize) -> Vec<&str> {
+    let target_size = (contents.len() / num_chunks.max(1)).max(1);
+    let mut chunks = vec![];
+    let mut rest = contents;
+    while !rest.is_empty() {
+        let end = rest.as_bytes()[target_size.min(rest.len())..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map(|i| target_size + i + 1)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/// Parses the vertex positions and faces in a chunk of an OBJ file, starting
+/// at line number `first_line`. Other entities, like normals or texture
+/// coordinates, are ignored.
+fn parse_obj_chunk(chunk: &str, first_line: usize) -> Result<ObjChunk> {
+    let mut result = ObjChunk::default();
+    for (line_number, line) in (first_line..).zip(chunk.lines()) {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let coords = tokens
+                    .take(3)
+                    .map(|t| t.parse::<f32>())
+                    .collect::<Result<SVec<_>, _>>()
+                    .with_context(|| format!("Invalid vertex at line {line_number}"))?;
+                if coords.len() != 3 {
+                    bail!("Vertex with less than 3 coordinates at line {line_number}");
+                }
+                result
+                    .positions
+                    .push(Vec3::new(coords[0], coords[1], coords[2]));
+            }
+            Some("f") => {
+                // Face vertices look like `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+                // Only the position index is used.
+                let indices = tokens
+                    .map(|t| t.split('/').next().unwrap_or_default().parse::<i64>())
+                    .collect::<Result<SVec<_>, _>>()
+                    .with_context(|| format!("Invalid face at line {line_number}"))?;
+                result.faces.push(ObjFace {
+                    indices,
+                    vertices_before: result.positions.len(),
+                    line: line_number,
+                });
             }
             _ => {}
-        })?;
-        halfedge::HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+        }
     }
+    Ok(result)
+}
+
+/// Parses the vertex positions and polygons of an OBJ file, split in
+/// `num_chunks` chunks that are parsed in parallel. The `on_progress`
+/// callback receives the fraction of parsed chunks, and can stop the parsing
+/// by returning an error.
+fn parse_obj(
+    contents: &str,
+    num_chunks: usize,
+    on_progress: &(dyn Fn(f32) -> Result<()> + Sync),
+) -> Result<(Vec<Vec3>, Vec<SVec<usize>>)> {
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let chunks = split_in_chunks(contents, num_chunks);
+    let first_lines = chunks
+        .iter()
+        .scan(1, |line, chunk| {
+            let first = *line;
+            *line += chunk.lines().count();
+            Some(first)
+        })
+        .collect_vec();
+
+    let parsed = AtomicUsize::new(0);
+    let parsed_chunks = chunks
+        .par_iter()
+        .zip(first_lines.par_iter())
+        .map(|(chunk, first_line)| {
+            let result = parse_obj_chunk(chunk, *first_line)?;
+            let done = parsed.fetch_add(1, Ordering::Relaxed) + 1;
+            on_progress(done as f32 / chunks.len() as f32)?;
+            Ok(result)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Vertex indices are global to the file, so each chunk needs to know how
+    // many vertices came before it.
+    let vertex_offsets = parsed_chunks
+        .iter()
+        .scan(0, |offset, chunk| {
+            let first = *offset;
+            *offset += chunk.positions.len();
+            Some(first)
+        })
+        .collect_vec();
+    let num_vertices: usize = parsed_chunks.iter().map(|c| c.positions.len()).sum();
+
+    let polygons = parsed_chunks
+        .par_iter()
+        .zip(vertex_offsets.par_iter())
+        .map(|(chunk, vertex_offset)| {
+            chunk
+                .faces
+                .iter()
+                .map(|face| {
+                    face.indices
+                        .iter()
+                        .map(|idx| {
+                            // NOTE: OBJ Wavefront indices start at 1. Negative
+                            // indices are relative to the last vertex.
+                            let index = match *idx {
+                                idx if idx > 0 => idx - 1,
+                                idx if idx < 0 => {
+                                    (vertex_offset + face.vertices_before) as i64 + idx
+                                }
+                                _ => -1,
+                            };
+                            usize::try_from(index)
+                                .ok()
+                                .filter(|index| *index < num_vertices)
+                                .ok_or_else(|| {
+                                    anyhow!(
+                                        "Invalid vertex index {idx} in face at line {}",
+                                        face.line
+                                    )
+                                })
+                        })
+                        .collect::<Result<SVec<usize>>>()
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+
+    let positions = parsed_chunks
+        .into_iter()
+        .flat_map(|chunk| chunk.positions)
+        .collect();
+    Ok((positions, polygons))
 }
 
 #[blackjack_macros::blackjack_lua_module]
 mod lua_api {
     use super::*;
     use anyhow::Result;
+    use mlua::Lua;
 
     /// Saves this mesh as a Wavefront OBJ file at a given `path`. The path's
     /// parent folder must exist. If there was a file at that path, it will be
     /// overwritten.
+    ///
+    /// When the mesh has a `material` face channel, the project materials are
+    /// also saved as an MTL file next to the OBJ file.
+    ///
+    /// The optional `ordering` can be "Internal" (the default) or "Spatial".
+    /// Spatial ordering sorts the vertices and faces by their position, so
+    /// re-exporting a slightly modified mesh gives a file with few changes.
+    ///
+    /// When a `faces` selection is given, only the selected faces are
+    /// exported.
     #[lua(under = "HalfEdgeMesh")]
-    pub fn to_wavefront_obj(mesh: &HalfEdgeMesh, path: String) -> Result<()> {
-        mesh.to_wavefront_obj(path)
+    pub fn to_wavefront_obj(
+        lua: &Lua,
+        mesh: &HalfEdgeMesh,
+        path: String,
+        ordering: Option<String>,
+        faces: Option<SelectionExpression>,
+    ) -> Result<()> {
+        let registry = lua.app_data_ref::<MaterialRegistry>();
+        let ordering = match ordering {
+            Some(ordering) => ExportOrdering::from_name(&ordering)?,
+            None => ExportOrdering::Internal,
+        };
+        let options = ObjExportOptions {
+            materials: registry.as_deref(),
+            ordering,
+            faces: faces.as_ref(),
+        };
+        mesh.to_wavefront_obj_with_options(path, &options)
     }
 
     /// Loads a wavefront OBJ file from disk at the given `path` and returns a
@@ -163,9 +840,24 @@ mod lua_api {
     ///
     /// NOTE: This currently only loads vertex positions, no normals or texture
     /// coordinates.
+    ///
+    /// The optional `options` table may set any of `scale`, `axis_swap`
+    /// ("None", "ZUpToYUp" or "YUpToZUp"), `weld_distance` and
+    /// `triangulate_ngons`. The options are remembered for the next OBJ files
+    /// imported without them.
     #[lua(under = "HalfEdgeMesh")]
-    pub fn from_wavefront_obj(path: String) -> Result<HalfEdgeMesh> {
-        HalfEdgeMesh::from_wavefront_obj(path.into())
+    pub fn from_wavefront_obj(
+        lua: &Lua,
+        path: String,
+        options: Option<mlua::Table>,
+    ) -> Result<HalfEdgeMesh> {
+        let path = PathBuf::from(path);
+        let options = ImportOptions::resolve(lua, &path, options)?;
+        HalfEdgeMesh::from_wavefront_obj_with_progress(
+            path,
+            &options,
+            ProgressTracker::current(lua).as_ref(),
+        )
     }
 }
 
@@ -173,6 +865,11 @@ mod lua_api {
 mod tests {
     use super::*;
 
+    /// A path in the temporary folder, unique to this test process.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("blackjack_{}_{name}", std::process::id()))
+    }
+
     #[test]
     pub fn test_load_obj() {
         HalfEdgeMesh::from_wavefront_obj("../test/test_mesh.obj".into())
@@ -180,4 +877,176 @@ mod tests {
             .to_wavefront_obj("/tmp/output.obj")
             .unwrap();
     }
+
+    #[test]
+    pub fn test_parse_obj_in_chunks() {
+        let contents = "# A comment\n\
+                        v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+                        vn 0 0 1\n\
+                        f 1/1/1 2/2/1 3/3/1\n\
+                        f 1//1 3//1 4//1 # Trailing comment\n\
+                        v 0 0 1\n\
+                        f -5 -4 -1\n";
+        let no_progress = |_: f32| -> Result<()> { Ok(()) };
+        let (positions, polygons) = parse_obj(contents, 1, &no_progress).unwrap();
+        assert_eq!(positions.len(), 5);
+        assert_eq!(polygons.len(), 3);
+        assert_eq!(polygons[2].as_slice(), &[0, 1, 4]);
+        // The result doesn't depend on how the file is split
+        for num_chunks in 2..8 {
+            let (p, f) = parse_obj(contents, num_chunks, &no_progress).unwrap();
+            assert_eq!(p, positions);
+            assert_eq!(f, polygons);
+        }
+        assert!(parse_obj("v 0 0 0\nf 1 2 3\n", 1, &no_progress).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_large_obj() {
+        // A grid of quads, big enough to be split in many chunks
+        let size = 100;
+        let mut lines = vec![];
+        for (i, j) in (0..size).cartesian_product(0..size) {
+            lines.push(format!("v {i} 0 {j}"));
+        }
+        for (i, j) in (0..size - 1).cartesian_product(0..size - 1) {
+            let v = i * size + j + 1;
+            lines.push(format!("f {} {} {} {}", v, v + 1, v + size + 1, v + size));
+        }
+        let no_progress = |_: f32| -> Result<()> { Ok(()) };
+        let expected = parse_obj(&lines.join("\n"), 1, &no_progress).unwrap();
+        assert_eq!(expected.0.len(), size * size);
+        assert_eq!(expected.1.len(), (size - 1) * (size - 1));
+
+        // Windows line endings, with and without a line break at the end
+        for separator in ["\n", "\r\n"] {
+            for ending in ["", separator] {
+                let contents = lines.join(separator) + ending;
+                for num_chunks in [1, 7, 64, contents.len()] {
+                    let chunks = split_in_chunks(&contents, num_chunks);
+                    assert_eq!(chunks.concat(), contents);
+                    assert!(chunks[..chunks.len() - 1]
+                        .iter()
+                        .all(|chunk| chunk.ends_with('\n')));
+                    let parsed = parse_obj(&contents, num_chunks, &no_progress).unwrap();
+                    assert_eq!(parsed, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_spatial_ordering() {
+        // The same quads, with vertices and faces in a different order
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::X + Vec3::Z,
+            Vec3::Z,
+            Vec3::X * 2.0,
+            Vec3::X * 2.0 + Vec3::Z,
+        ];
+        let a =
+            HalfEdgeMesh::build_from_polygons(&positions, &[[0, 3, 2, 1], [1, 2, 5, 4]]).unwrap();
+        let shuffled = positions.iter().rev().copied().collect_vec();
+        let b =
+            HalfEdgeMesh::build_from_polygons(&shuffled, &[[3, 4, 5, 2], [0, 1, 4, 3]]).unwrap();
+
+        let options = ObjExportOptions {
+            ordering: ExportOrdering::Spatial,
+            ..Default::default()
+        };
+        let (path_a, path_b) = (temp_path("spatial_a.obj"), temp_path("spatial_b.obj"));
+        a.to_wavefront_obj_with_options(&path_a, &options).unwrap();
+        b.to_wavefront_obj_with_options(&path_b, &options).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path_a).unwrap(),
+            std::fs::read_to_string(path_b).unwrap(),
+        );
+    }
+
+    #[test]
+    pub fn test_export_lines_and_points() {
+        let mut mesh =
+            primitives::Line::build_from_points(vec![Vec3::ZERO, Vec3::X, Vec3::X * 2.0]).unwrap();
+        edit_ops::add_vertex(&mut mesh, Vec3::Y).unwrap();
+        let path = temp_path("lines_and_points.obj");
+        mesh.to_wavefront_obj(&path).unwrap();
+
+        let contents = std::fs::read_to_string(path).unwrap();
+        let lines = contents
+            .lines()
+            .filter(|l| l.starts_with("l "))
+            .collect_vec();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].split_whitespace().count(), 4);
+        assert_eq!(contents.lines().filter(|l| l.starts_with("p ")).count(), 1);
+    }
+
+    #[test]
+    pub fn test_export_selected_faces() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let faces = SelectionExpression::parse("0").unwrap();
+        let options = ObjExportOptions {
+            faces: Some(&faces),
+            ..Default::default()
+        };
+        let path = temp_path("selected_faces.obj");
+        mesh.to_wavefront_obj_with_options(&path, &options).unwrap();
+
+        let contents = std::fs::read_to_string(path).unwrap();
+        let count = |prefix: &str| contents.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(count("f "), 1);
+        assert_eq!(count("v "), 4);
+    }
+
+    #[test]
+    pub fn test_export_material_names() {
+        let mut registry = MaterialRegistry::default();
+        registry.add(Material::new("Red metal")).unwrap();
+        registry.add(Material::new("Red_metal")).unwrap();
+        registry.add(Material::new("bad\nname")).unwrap();
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let ch_id = mesh
+            .channels
+            .ensure_channel::<FaceId, f32>(MATERIAL_CHANNEL);
+        {
+            let conn = mesh.read_connectivity();
+            let mut material_ch = mesh.channels.write_channel(ch_id).unwrap();
+            for (i, (face, _)) in conn.iter_faces().enumerate() {
+                material_ch[face] = (i % 3) as f32;
+            }
+        }
+        let path = temp_path("material_names.obj");
+        mesh.to_wavefront_obj_with_materials(&path, Some(&registry))
+            .unwrap();
+
+        let mtl = std::fs::read_to_string(path.with_extension("mtl")).unwrap();
+        let defined = mtl
+            .lines()
+            .filter_map(|l| l.strip_prefix("newmtl "))
+            .collect_vec();
+        assert_eq!(defined, ["Red_metal", "Red_metal.001", "bad_name"]);
+        let obj = std::fs::read_to_string(path).unwrap();
+        let used = obj
+            .lines()
+            .filter_map(|l| l.strip_prefix("usemtl "))
+            .collect::<HashSet<_>>();
+        assert!(used.iter().all(|name| defined.contains(name)));
+        assert_eq!(used.len(), 3);
+    }
+
+    #[test]
+    pub fn test_export_sequence() {
+        let paths = export_obj_sequence(
+            &temp_path("sequence.obj"),
+            1..=3,
+            &Default::default(),
+            |frame| primitives::Box::build(Vec3::Y * frame as f32, Vec3::ONE),
+        )
+        .unwrap();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0], temp_path("sequence_0001.obj"));
+        assert!(paths.iter().all(|p| p.exists()));
+    }
 }
@@ -5,10 +5,11 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use anyhow::Result;
-use glam::{Vec2, Vec3};
+use glam::Vec2;
 use noise::NoiseFn;
 
-use crate::prelude::VertexIndexBuffers;
+#[cfg(feature = "gpu_buffers")]
+use {crate::prelude::VertexIndexBuffers, glam::Vec3};
 
 #[derive(Clone)]
 pub struct HeightMap {
@@ -16,6 +17,11 @@ pub struct HeightMap {
 }
 
 impl HeightMap {
+    /// Returns the number of samples of this heightmap along each axis.
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.inner.dim()
+    }
+
     pub fn from_perlin(
         width: usize,
         height: usize,
@@ -55,12 +61,15 @@ impl HeightMap {
         }
     }
 
+    #[cfg(feature = "gpu_buffers")]
     pub fn generate_triangle_buffers(&self) -> VertexIndexBuffers {
         // If the terrain is too small to compute normals, return an empty buffer
         if self.inner.ncols() < 4 || self.inner.nrows() < 4 {
             return VertexIndexBuffers {
                 positions: vec![],
                 normals: vec![],
+                colors: vec![],
+                uvs: vec![],
                 indices: vec![],
             };
         }
@@ -120,6 +129,8 @@ impl HeightMap {
         VertexIndexBuffers {
             positions,
             normals,
+            colors: vec![],
+            uvs: vec![],
             indices,
         }
     }
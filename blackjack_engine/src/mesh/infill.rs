@@ -0,0 +1,226 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Lattice infill for the inside of closed meshes, to make 3D printed parts
+//! lighter while keeping them stiff.
+//!
+//! Lattices are defined as signed distance fields, like the voxelized mesh
+//! from [`super::voxel_remesh`]. Clipping the lattice to the volume of the
+//! mesh is then the intersection of both fields, and adding an outer shell is
+//! their union, so the result is always a single closed surface.
+
+use std::f32::consts::TAU;
+
+use crate::prelude::*;
+use crate::progress::{NoProgress, ProgressSink};
+
+use super::voxel_remesh::{
+    compute_distances, compute_signs, extract_surface, mesh_triangles, Grid, PADDING,
+};
+
+/// The shape of the cells of an infill lattice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatticeCell {
+    /// A triply periodic minimal surface. Its walls are smooth and connected
+    /// in all directions, and it prints without supports.
+    Gyroid,
+    /// Straight struts along the three axes, meeting at the corners of cubic
+    /// cells.
+    Grid,
+    /// Hexagonal walls extruded along the Y axis.
+    Honeycomb,
+}
+
+impl LatticeCell {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Gyroid" => Ok(Self::Gyroid),
+            "Grid" => Ok(Self::Grid),
+            "Honeycomb" => Ok(Self::Honeycomb),
+            _ => bail!("Invalid lattice cell type: {name}"),
+        }
+    }
+
+    /// Approximate signed distance from `p` to the solid part of a lattice of
+    /// cells of `cell_size`, with struts or walls of `strut_radius`.
+    fn distance(self, p: Vec3, cell_size: f32, strut_radius: f32) -> f32 {
+        // Offset of `x` from the closest multiple of `period`
+        let wrap = |x: f32, period: f32| x - period * (x / period).round();
+        match self {
+            LatticeCell::Gyroid => {
+                // Dividing by the frequency is a good enough approximation
+                // of the distance close to the surface.
+                let k = TAU / cell_size;
+                let q = p * k;
+                let g = q.x.sin() * q.y.cos() + q.y.sin() * q.z.cos() + q.z.sin() * q.x.cos();
+                g.abs() / k - strut_radius
+            }
+            LatticeCell::Grid => {
+                let x = wrap(p.x, cell_size);
+                let y = wrap(p.y, cell_size);
+                let z = wrap(p.z, cell_size);
+                let along_x = Vec2::new(y, z).length();
+                let along_y = Vec2::new(x, z).length();
+                let along_z = Vec2::new(x, y).length();
+                along_x.min(along_y).min(along_z) - strut_radius
+            }
+            LatticeCell::Honeycomb => {
+                // Hexagons with an inner diameter of `cell_size` are the
+                // Voronoi cells of two interleaved rectangular grids.
+                let sqrt3 = 3f32.sqrt();
+                let period = Vec2::new(sqrt3 * cell_size, cell_size);
+                let p = Vec2::new(p.x, p.z);
+                let a = Vec2::new(wrap(p.x, period.x), wrap(p.y, period.y));
+                let b = {
+                    let p = p - period * 0.5;
+                    Vec2::new(wrap(p.x, period.x), wrap(p.y, period.y))
+                };
+                let q = if a.length_squared() < b.length_squared() {
+                    a
+                } else {
+                    b
+                };
+                let q = q.abs();
+                let hex_distance = (q.x * sqrt3 * 0.5 + q.y * 0.5).max(q.y);
+                (cell_size * 0.5 - hex_distance) - strut_radius
+            }
+        }
+    }
+}
+
+/// Fills the inside of `mesh` with a lattice of `cell` shapes, `cell_size`
+/// units wide, with struts (or walls, for gyroids and honeycombs) of
+/// `strut_radius`. The result is sampled on a grid of voxels of size
+/// `voxel_size`. With a `shell_thickness` of zero, only the lattice clipped
+/// to the volume of the mesh is returned. Otherwise, it is joined to a shell
+/// of that thickness following the surface of the mesh, which is ready to
+/// print as a single part. The input should be closed.
+pub fn infill_lattice(
+    mesh: &HalfEdgeMesh,
+    cell: LatticeCell,
+    cell_size: f32,
+    strut_radius: f32,
+    voxel_size: f32,
+    shell_thickness: f32,
+) -> Result<HalfEdgeMesh> {
+    infill_lattice_with_progress(
+        mesh,
+        cell,
+        cell_size,
+        strut_radius,
+        voxel_size,
+        shell_thickness,
+        &NoProgress,
+    )
+}
+
+/// Same as `infill_lattice`, but reports the progress to the given
+/// `progress` sink, and stops early when it gets cancelled.
+pub fn infill_lattice_with_progress(
+    mesh: &HalfEdgeMesh,
+    cell: LatticeCell,
+    cell_size: f32,
+    strut_radius: f32,
+    voxel_size: f32,
+    shell_thickness: f32,
+    progress: &dyn ProgressSink,
+) -> Result<HalfEdgeMesh> {
+    let name = "Lattice infill";
+    if voxel_size <= 0.0 {
+        bail!("The voxel size must be positive, got {voxel_size}");
+    }
+    if cell_size <= 0.0 || strut_radius <= 0.0 {
+        bail!("The cell size and strut radius must be positive");
+    }
+    if shell_thickness < 0.0 {
+        bail!("The shell thickness can't be negative, got {shell_thickness}");
+    }
+    let triangles = mesh_triangles(mesh);
+    if triangles.is_empty() {
+        bail!("Cannot fill a mesh without faces");
+    }
+
+    let band = (shell_thickness / voxel_size).ceil() as usize + PADDING;
+    let mut grid = Grid::around(&triangles, voxel_size)?;
+    compute_distances(&mut grid, &triangles, band);
+    progress.report(name, 0.2);
+    progress.check_cancelled()?;
+    compute_signs(&mut grid, &triangles);
+    progress.report(name, 0.4);
+    progress.check_cancelled()?;
+
+    for index in 0..grid.values.len() {
+        let distance = grid.values[index];
+        let mut solid = cell.distance(grid.point(index), cell_size, strut_radius);
+        if shell_thickness > 0.0 {
+            solid = solid.min(-(distance + shell_thickness));
+        }
+        grid.values[index] = distance.max(solid);
+    }
+    extract_surface(&grid, |p| {
+        progress.report(name, 0.4 + 0.6 * p);
+        progress.check_cancelled()
+    })
+    .context("The lattice doesn't fit inside the mesh. Try with smaller cells")
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::progress::ProgressTracker;
+    use mlua::Lua;
+
+    /// Fills the inside of `mesh` with a lattice. The `cell_type` can be
+    /// "Gyroid", "Grid" or "Honeycomb". Cells are `cell_size` wide, with
+    /// struts or walls of `strut_radius`, sampled on voxels of `voxel_size`.
+    /// When `shell_thickness` is greater than zero, the lattice is joined to
+    /// a shell of that thickness, otherwise only the lattice is returned.
+    #[lua(under = "Ops")]
+    fn infill_lattice(
+        lua: &Lua,
+        mesh: &HalfEdgeMesh,
+        cell_type: String,
+        cell_size: f32,
+        strut_radius: f32,
+        voxel_size: f32,
+        shell_thickness: f32,
+    ) -> Result<HalfEdgeMesh> {
+        super::infill_lattice_with_progress(
+            mesh,
+            LatticeCell::from_name(&cell_type)?,
+            cell_size,
+            strut_radius,
+            voxel_size,
+            shell_thickness,
+            ProgressTracker::current(lua).as_ref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    fn test_infill_lattice() {
+        let box_mesh = Box::build(Vec3::ZERO, Vec3::splat(2.0)).unwrap();
+        for cell in [
+            LatticeCell::Gyroid,
+            LatticeCell::Grid,
+            LatticeCell::Honeycomb,
+        ] {
+            let lattice = infill_lattice(&box_mesh, cell, 0.5, 0.05, 0.05, 0.0).unwrap();
+            let conn = lattice.read_connectivity();
+            let positions = lattice.read_positions();
+            assert!(conn.num_faces() > 0);
+            // The lattice is clipped to the box
+            assert!(conn
+                .iter_vertices()
+                .all(|(v, _)| positions[v].abs().max_element() <= 1.0 + 1e-3));
+        }
+    }
+}
@@ -0,0 +1,356 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Cage-based deformation of meshes with a lattice of control points.
+//!
+//! A lattice starts as a regular grid of points around a mesh. Moving its
+//! control points bends the space inside the grid, and any mesh in that space
+//! with it. In graphs, lattices travel as point cloud meshes (see
+//! [`Lattice::to_mesh`]), so their control points can be moved in the
+//! viewport with the same tools used to edit any other geometry.
+
+use crate::prelude::*;
+
+/// Name of the vertex channel storing the grid coordinates of each control
+/// point, when a lattice is stored as a mesh.
+const LATTICE_INDEX_CHANNEL: &str = "lattice_index";
+
+/// Name of the vertex channel storing the undeformed position of each control
+/// point, when a lattice is stored as a mesh.
+const LATTICE_REST_CHANNEL: &str = "lattice_rest";
+
+/// How the deformation is interpolated between the control points of a
+/// lattice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatticeInterpolation {
+    /// Each control point only affects the cells around it. The result
+    /// follows the control points closely, but bends sharply at cell borders.
+    Trilinear,
+    /// Every control point affects the whole lattice, like the control points
+    /// of a Bézier volume. The result is smooth, but follows the control
+    /// points more loosely.
+    Bezier,
+}
+
+impl LatticeInterpolation {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Trilinear" => Ok(Self::Trilinear),
+            "Bezier" => Ok(Self::Bezier),
+            _ => bail!("Invalid lattice interpolation: {name}"),
+        }
+    }
+}
+
+/// A grid of `resolution.x` × `resolution.y` × `resolution.z` control points,
+/// initially spread evenly over the box between `min` and `max`.
+#[derive(Clone, Debug)]
+pub struct Lattice {
+    resolution: UVec3,
+    min: Vec3,
+    max: Vec3,
+    points: Vec<Vec3>,
+}
+
+impl Lattice {
+    /// Creates an undeformed lattice filling the box between `min` and `max`.
+    pub fn new(min: Vec3, max: Vec3, resolution: UVec3) -> Result<Self> {
+        if resolution.min_element() < 2 {
+            bail!("A lattice needs at least two control points along each axis");
+        }
+        let mut lattice = Self {
+            resolution,
+            min,
+            max,
+            points: Vec::new(),
+        };
+        lattice.points = lattice
+            .cells()
+            .map(|cell| lattice.rest_point(cell))
+            .collect();
+        Ok(lattice)
+    }
+
+    /// Creates an undeformed lattice around the bounding box of `mesh`, grown
+    /// by `padding` on every side.
+    pub fn fit(mesh: &HalfEdgeMesh, resolution: UVec3, padding: f32) -> Result<Self> {
+        let (min, max) = mesh.bbox();
+        // Flat meshes still need some room so the lattice has a volume.
+        let flat = (max.0 - min.0).cmplt(Vec3::splat(f32::EPSILON));
+        let padding = Vec3::select(flat, Vec3::splat(padding.max(0.5)), Vec3::splat(padding));
+        Self::new(min.0 - padding, max.0 + padding, resolution)
+    }
+
+    pub fn resolution(&self) -> UVec3 {
+        self.resolution
+    }
+
+    /// Returns the position in the list of points of the control point at the
+    /// given grid coordinates. Points are sorted by their X coordinate first,
+    /// then Y, then Z.
+    pub fn index(&self, cell: UVec3) -> usize {
+        (cell.x + self.resolution.x * (cell.y + self.resolution.y * cell.z)) as usize
+    }
+
+    pub fn point(&self, cell: UVec3) -> Vec3 {
+        self.points[self.index(cell)]
+    }
+
+    pub fn set_point(&mut self, cell: UVec3, pos: Vec3) {
+        let idx = self.index(cell);
+        self.points[idx] = pos;
+    }
+
+    /// Returns the grid coordinates of all the control points, in the same
+    /// order as [`Lattice::index`].
+    fn cells(&self) -> impl Iterator<Item = UVec3> {
+        let res = self.resolution;
+        (0..res.z).flat_map(move |k| {
+            (0..res.y).flat_map(move |j| (0..res.x).map(move |i| UVec3::new(i, j, k)))
+        })
+    }
+
+    /// Returns the position of the control point at the given grid
+    /// coordinates before any deformation.
+    fn rest_point(&self, cell: UVec3) -> Vec3 {
+        let t = cell.as_vec3() / (self.resolution - UVec3::ONE).as_vec3();
+        self.min + (self.max - self.min) * t
+    }
+
+    /// Returns the offset of the control point at the given grid coordinates
+    /// from its undeformed position.
+    fn displacement(&self, cell: UVec3) -> Vec3 {
+        self.point(cell) - self.rest_point(cell)
+    }
+
+    /// Returns the deformed position of `pos`. Points outside the lattice get
+    /// the same displacement as the closest point on its border.
+    pub fn deform_point(&self, pos: Vec3, interpolation: LatticeInterpolation) -> Vec3 {
+        let size = (self.max - self.min).max(Vec3::splat(f32::EPSILON));
+        let t = ((pos - self.min) / size).clamp(Vec3::ZERO, Vec3::ONE);
+        let displacement = match interpolation {
+            LatticeInterpolation::Trilinear => {
+                let cells = (self.resolution - UVec3::ONE).as_vec3();
+                let scaled = t * cells;
+                let base = scaled.floor().min(cells - Vec3::ONE);
+                let local = scaled - base;
+                let base = base.as_uvec3();
+                let mut acc = Vec3::ZERO;
+                for corner in 0..8u32 {
+                    let offset = UVec3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+                    let w = Vec3::select(offset.cmpeq(UVec3::ONE), local, Vec3::ONE - local);
+                    acc += self.displacement(base + offset) * w.x * w.y * w.z;
+                }
+                acc
+            }
+            LatticeInterpolation::Bezier => {
+                let bx = bernstein(self.resolution.x, t.x);
+                let by = bernstein(self.resolution.y, t.y);
+                let bz = bernstein(self.resolution.z, t.z);
+                let mut acc = Vec3::ZERO;
+                for (k, &wz) in bz.iter().enumerate() {
+                    for (j, &wy) in by.iter().enumerate() {
+                        for (i, &wx) in bx.iter().enumerate() {
+                            let cell = UVec3::new(i as u32, j as u32, k as u32);
+                            acc += self.displacement(cell) * wx * wy * wz;
+                        }
+                    }
+                }
+                acc
+            }
+        };
+        pos + displacement
+    }
+
+    /// Returns this lattice as a point cloud, with a vertex for each control
+    /// point in the same order as [`Lattice::index`]. The grid coordinates
+    /// and undeformed positions of the points are stored in channels, so the
+    /// lattice can be recovered with [`Lattice::from_mesh`] after its points
+    /// are moved.
+    pub fn to_mesh(&self) -> Result<HalfEdgeMesh> {
+        let mut mesh = HalfEdgeMesh::new();
+        let index_ch_id = mesh
+            .channels
+            .ensure_channel::<VertexId, Vec3>(LATTICE_INDEX_CHANNEL);
+        let rest_ch_id = mesh
+            .channels
+            .ensure_channel::<VertexId, Vec3>(LATTICE_REST_CHANNEL);
+        for pos in &self.points {
+            halfedge::edit_ops::add_vertex(&mut mesh, *pos)?;
+        }
+
+        // Vertices of a new mesh are iterated in the order they were added
+        let conn = mesh.read_connectivity();
+        let mut index_ch = mesh.channels.write_channel(index_ch_id)?;
+        let mut rest_ch = mesh.channels.write_channel(rest_ch_id)?;
+        for ((v, _), cell) in conn.iter_vertices().zip(self.cells()) {
+            index_ch[v] = cell.as_vec3();
+            rest_ch[v] = self.rest_point(cell);
+        }
+        drop(conn);
+        drop(index_ch);
+        drop(rest_ch);
+        Ok(mesh)
+    }
+
+    /// Recovers a lattice from a mesh generated by [`Lattice::to_mesh`],
+    /// taking the current vertex positions as its control points.
+    pub fn from_mesh(mesh: &HalfEdgeMesh) -> Result<Self> {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let index_ch = mesh
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(LATTICE_INDEX_CHANNEL)
+            .context("The mesh is not a lattice. It has no 'lattice_index' channel.")?;
+        let rest_ch = mesh
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(LATTICE_REST_CHANNEL)
+            .context("The mesh is not a lattice. It has no 'lattice_rest' channel.")?;
+
+        let mut max_cell = UVec3::ZERO;
+        let mut min = Vec3::splat(f32::INFINITY);
+        let mut max = Vec3::splat(f32::NEG_INFINITY);
+        for (v, _) in conn.iter_vertices() {
+            max_cell = max_cell.max(index_ch[v].round().as_uvec3());
+            min = min.min(rest_ch[v]);
+            max = max.max(rest_ch[v]);
+        }
+        let mut lattice = Self::new(min, max, max_cell + UVec3::ONE)?;
+
+        let mut assigned = vec![false; lattice.points.len()];
+        for (v, _) in conn.iter_vertices() {
+            let idx = lattice.index(index_ch[v].round().as_uvec3());
+            if std::mem::replace(&mut assigned[idx], true) {
+                bail!(
+                    "The lattice has more than one control point at the same position in its grid"
+                );
+            }
+            lattice.points[idx] = positions[v];
+        }
+        if assigned.iter().any(|a| !a) {
+            bail!("The lattice is missing some of its control points. Were they deleted?");
+        }
+        Ok(lattice)
+    }
+}
+
+/// Returns the `n` Bernstein basis polynomials of degree `n - 1` evaluated at
+/// `t`.
+fn bernstein(n: u32, t: f32) -> Vec<f32> {
+    let degree = n as i32 - 1;
+    let mut binomial = 1.0;
+    (0..=degree)
+        .map(|i| {
+            let b = binomial * t.powi(i) * (1.0 - t).powi(degree - i);
+            binomial = binomial * (degree - i) as f32 / (i + 1) as f32;
+            b
+        })
+        .collect()
+}
+
+/// Moves the vertices of `mesh` following the deformation of `lattice`.
+pub fn lattice_deform(
+    mesh: &HalfEdgeMesh,
+    lattice: &Lattice,
+    interpolation: LatticeInterpolation,
+) -> Result<()> {
+    let conn = mesh.read_connectivity();
+    let mut positions = mesh.write_positions();
+    for (v, _) in conn.iter_vertices() {
+        positions[v] = lattice.deform_point(positions[v], interpolation);
+    }
+    Ok(())
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+
+    /// Returns a lattice around the bounding box of `mesh`, grown by
+    /// `padding`, as a point cloud. The `resolution` is the number of control
+    /// points along each axis. Points are numbered along X first, then Y,
+    /// then Z, so they can be selected by index and moved to deform any mesh
+    /// with `Ops.lattice_deform`.
+    #[lua(under = "Ops")]
+    fn lattice_cage(mesh: &HalfEdgeMesh, resolution: LVec3, padding: f32) -> Result<HalfEdgeMesh> {
+        let resolution = resolution.0.round().max(Vec3::ZERO).as_uvec3();
+        Lattice::fit(mesh, resolution, padding)?.to_mesh()
+    }
+
+    /// Returns a copy of `mesh` deformed by `lattice`, a point cloud made with
+    /// `Ops.lattice_cage` whose points may have been moved. The
+    /// `interpolation` can be "Trilinear" or "Bezier".
+    #[lua(under = "Ops")]
+    fn lattice_deform(
+        mesh: &HalfEdgeMesh,
+        lattice: &HalfEdgeMesh,
+        interpolation: String,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::lattice_deform(
+            &result,
+            &Lattice::from_mesh(lattice)?,
+            LatticeInterpolation::from_name(&interpolation)?,
+        )?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    fn test_lattice_roundtrip() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let mut lattice = Lattice::fit(&mesh, UVec3::new(2, 3, 4), 0.0).unwrap();
+        lattice.set_point(UVec3::new(1, 2, 3), Vec3::splat(2.0));
+
+        let recovered = Lattice::from_mesh(&lattice.to_mesh().unwrap()).unwrap();
+        assert_eq!(recovered.resolution(), UVec3::new(2, 3, 4));
+        assert_eq!(recovered.points, lattice.points);
+        assert!(recovered.min.distance(Vec3::splat(-0.5)) < 1e-5);
+        assert!(recovered.max.distance(Vec3::splat(0.5)) < 1e-5);
+    }
+
+    #[test]
+    fn test_lattice_deform() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let corner = Vec3::splat(0.5);
+
+        // Moving the corner control point moves the corner of the box
+        let mut lattice = Lattice::fit(&mesh, UVec3::splat(3), 0.0).unwrap();
+        lattice.set_point(UVec3::splat(2), Vec3::splat(1.0));
+        let deformed = mesh.clone();
+        lattice_deform(&deformed, &lattice, LatticeInterpolation::Trilinear).unwrap();
+        for (_, pos) in deformed.read_positions().iter() {
+            if pos.min_element() > 0.0 {
+                assert!(pos.distance(Vec3::splat(1.0)) < 1e-5);
+            }
+        }
+
+        // Bézier lattices can reproduce a uniform scale exactly
+        let mut lattice = Lattice::fit(&mesh, UVec3::splat(4), 0.0).unwrap();
+        lattice.points.iter_mut().for_each(|p| *p *= 2.0);
+        let deformed = mesh.clone();
+        lattice_deform(&deformed, &lattice, LatticeInterpolation::Bezier).unwrap();
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(deformed.read_positions().iter())
+        {
+            assert!(b.distance(*a * 2.0) < 1e-4);
+        }
+        assert!(
+            lattice
+                .deform_point(corner, LatticeInterpolation::Bezier)
+                .distance(corner * 2.0)
+                < 1e-4
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Measurements of meshes, like distances and angles. Unlike the ones in
+//! [`super::analysis`], these return a single number, so they can drive the
+//! parameters of other nodes.
+
+use crate::prelude::*;
+
+use super::halfedge::selection::SelectionExpression;
+
+/// Returns the average position of the vertices in `selection`.
+fn selection_center(mesh: &HalfEdgeMesh, selection: &SelectionExpression) -> Result<Vec3> {
+    let vertices = mesh.resolve_vertex_selection_full(selection)?;
+    if vertices.is_empty() {
+        bail!(
+            "Can't measure an empty selection: '{}'",
+            selection.unparse()
+        );
+    }
+    let positions = mesh.read_positions();
+    Ok(vertices.iter().map(|v| positions[*v]).sum::<Vec3>() / vertices.len() as f32)
+}
+
+/// Returns the average normal of the faces in `selection`, weighted by their
+/// area.
+fn selection_normal(mesh: &HalfEdgeMesh, selection: &SelectionExpression) -> Result<Vec3> {
+    let faces = mesh.resolve_face_selection_full(selection)?;
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    // Newell's method gives a normal as long as twice the area of the face
+    let mut normal = Vec3::ZERO;
+    for face in faces {
+        for (a, b) in conn.face_vertices(face).iter().circular_tuple_windows() {
+            normal += positions[*a].cross(positions[*b]);
+        }
+    }
+    normal.try_normalize().ok_or_else(|| {
+        anyhow!(
+            "Can't measure the normal of an empty selection: '{}'",
+            selection.unparse()
+        )
+    })
+}
+
+/// Returns the distance between the centers of the vertices in `selection_a`
+/// and the vertices in `selection_b`.
+pub fn measure_distance(
+    mesh: &HalfEdgeMesh,
+    selection_a: &SelectionExpression,
+    selection_b: &SelectionExpression,
+) -> Result<f32> {
+    Ok(selection_center(mesh, selection_a)?.distance(selection_center(mesh, selection_b)?))
+}
+
+/// Returns the angle, in radians, between the average normals of the faces in
+/// `selection_a` and the faces in `selection_b`.
+pub fn measure_angle(
+    mesh: &HalfEdgeMesh,
+    selection_a: &SelectionExpression,
+    selection_b: &SelectionExpression,
+) -> Result<f32> {
+    let a = selection_normal(mesh, selection_a)?;
+    let b = selection_normal(mesh, selection_b)?;
+    Ok(a.dot(b).clamp(-1.0, 1.0).acos())
+}
+
+/// Returns the number of elements of the given `domain` (vertices, faces or
+/// halfedges) in `selection`.
+pub fn count_elements(
+    mesh: &HalfEdgeMesh,
+    domain: ChannelKeyType,
+    selection: &SelectionExpression,
+) -> Result<usize> {
+    Ok(match domain {
+        ChannelKeyType::VertexId => mesh.resolve_vertex_selection_full(selection)?.len(),
+        ChannelKeyType::FaceId => mesh.resolve_face_selection_full(selection)?.len(),
+        ChannelKeyType::HalfEdgeId => mesh.resolve_halfedge_selection_full(selection)?.len(),
+    })
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Returns the distance between the centers of two vertex selections of
+    /// `mesh`, `selection_a` and `selection_b`.
+    #[lua(under = "Ops")]
+    fn measure_distance(
+        mesh: &HalfEdgeMesh,
+        selection_a: SelectionExpression,
+        selection_b: SelectionExpression,
+    ) -> Result<f32> {
+        super::measure_distance(mesh, &selection_a, &selection_b)
+    }
+
+    /// Returns the angle, in radians, between the average normals of two face
+    /// selections of `mesh`, `selection_a` and `selection_b`.
+    #[lua(under = "Ops")]
+    fn measure_angle(
+        mesh: &HalfEdgeMesh,
+        selection_a: SelectionExpression,
+        selection_b: SelectionExpression,
+    ) -> Result<f32> {
+        super::measure_angle(mesh, &selection_a, &selection_b)
+    }
+
+    /// Returns how many elements of `mesh` are in `selection`. The `domain`
+    /// is the key type of the elements to count, like `Types.VERTEX_ID`.
+    #[lua(under = "Ops")]
+    fn count_elements(
+        mesh: &HalfEdgeMesh,
+        domain: ChannelKeyType,
+        selection: SelectionExpression,
+    ) -> Result<usize> {
+        super::count_elements(mesh, domain, &selection)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    fn test_measurements() {
+        let mesh = Box::build(Vec3::ZERO, Vec3::new(1.0, 2.0, 3.0)).unwrap();
+        let all = SelectionExpression::All;
+        assert_eq!(
+            count_elements(&mesh, ChannelKeyType::VertexId, &all).unwrap(),
+            8
+        );
+        assert_eq!(
+            count_elements(&mesh, ChannelKeyType::FaceId, &all).unwrap(),
+            6
+        );
+        assert_eq!(
+            count_elements(&mesh, ChannelKeyType::HalfEdgeId, &all).unwrap(),
+            24
+        );
+
+        // Opposite faces are parallel, neighboring ones perpendicular
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let face_center = |i: usize| {
+            let face = conn.iter_faces().nth(i).unwrap().0;
+            conn.face_vertex_average(&positions, face)
+        };
+        let face = |i: u32| SelectionExpression::parse(&i.to_string()).unwrap();
+        let opposite = (1..6)
+            .find(|i| face_center(*i).dot(face_center(0)) < 0.0)
+            .unwrap();
+        let neighbor = (1..6)
+            .find(|i| face_center(*i).dot(face_center(0)) == 0.0)
+            .unwrap();
+        drop(conn);
+        drop(positions);
+
+        let angle = measure_angle(&mesh, &face(0), &face(opposite as u32)).unwrap();
+        assert!((angle - std::f32::consts::PI).abs() < 1e-4);
+        let angle = measure_angle(&mesh, &face(0), &face(neighbor as u32)).unwrap();
+        assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 1e-4);
+        // The center of the box is at the origin, so this is half a diagonal
+        let distance = measure_distance(&mesh, &all, &face(0)).unwrap();
+        assert!((distance - Vec3::new(0.5, 1.0, 1.5).length()).abs() < 1e-4);
+        assert!(measure_distance(&mesh, &SelectionExpression::None, &all).is_err());
+    }
+}
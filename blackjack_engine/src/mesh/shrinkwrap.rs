@@ -0,0 +1,163 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Projection of the vertices of a mesh onto the surface of another one, to
+//! make it conform to its shape.
+
+use rstar::RTreeObject;
+
+use crate::prelude::*;
+
+use super::analysis::{cast_ray_triangle, triangle_index};
+use super::halfedge::edit_ops::generate_smooth_normals_channel;
+
+/// How [`shrinkwrap`] finds the point of the target surface where each vertex
+/// goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShrinkwrapMode {
+    /// Moves each vertex to the closest point of the target surface.
+    Nearest,
+    /// Moves each vertex along its normal, forwards or backwards, until it
+    /// hits the target surface. Vertices that miss it stay in place.
+    Project,
+}
+
+impl ShrinkwrapMode {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "Nearest" => Ok(Self::Nearest),
+            "Project" => Ok(Self::Project),
+            _ => bail!("Invalid shrinkwrap mode: {name}"),
+        }
+    }
+}
+
+/// Moves the vertices of `mesh` onto the surface of `target`, following the
+/// given `mode`. Vertices end up `offset` units away from the surface, along
+/// the normal of the face they land on, so positive offsets keep them outside
+/// of the target.
+pub fn shrinkwrap(
+    mesh: &HalfEdgeMesh,
+    target: &HalfEdgeMesh,
+    mode: ShrinkwrapMode,
+    offset: f32,
+) -> Result<()> {
+    let (index, _) = triangle_index(target);
+    if index.size() == 0 {
+        bail!("Cannot shrinkwrap onto a mesh without faces");
+    }
+
+    let normals = match mode {
+        ShrinkwrapMode::Nearest => None,
+        ShrinkwrapMode::Project => Some(match mesh.read_vertex_normals() {
+            Some(normals) => (*normals).clone(),
+            None => generate_smooth_normals_channel(mesh)?,
+        }),
+    };
+
+    // Rays are long enough to cross the whole target from any vertex.
+    let envelope = index.root().envelope();
+    let (min, max) = (
+        Vec3::from_array(envelope.lower()),
+        Vec3::from_array(envelope.upper()),
+    );
+    let extent = (max - min).length();
+    let center = (min + max) * 0.5;
+
+    let conn = mesh.read_connectivity();
+    let mut positions = mesh.write_positions();
+    for (v, _) in conn.iter_vertices() {
+        let pos = positions[v];
+        let hit = match &normals {
+            None => index
+                .nearest_neighbor(&pos.to_array())
+                .map(|tri| (tri.closest_point(pos), tri.normal())),
+            Some(normals) => {
+                let normal = normals[v];
+                if normal == Vec3::ZERO {
+                    continue;
+                }
+                let reach = extent + (pos - center).length();
+                [normal, -normal]
+                    .into_iter()
+                    .filter_map(|dir| {
+                        let (t, tri) = cast_ray_triangle(&index, pos, dir, reach)?;
+                        Some((t, pos + dir * t, tri.normal()))
+                    })
+                    .min_by_key(|(t, _, _)| float_ord::FloatOrd(*t))
+                    .map(|(_, point, normal)| (point, normal))
+            }
+        };
+        if let Some((point, normal)) = hit {
+            positions[v] = point + normal * offset;
+        }
+    }
+    Ok(())
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Returns a copy of `mesh` with its vertices moved onto the surface of
+    /// `target`, plus an `offset` along the surface normal. The `mode` is
+    /// either "Nearest", to use the closest point of the surface, or
+    /// "Project", to move vertices along their normals.
+    #[lua(under = "Ops")]
+    fn shrinkwrap(
+        mesh: &HalfEdgeMesh,
+        target: &HalfEdgeMesh,
+        mode: String,
+        offset: f32,
+    ) -> Result<HalfEdgeMesh> {
+        let result = mesh.clone();
+        super::shrinkwrap(&result, target, ShrinkwrapMode::from_name(&mode)?, offset)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::{Box, Quad};
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!(a.distance(b) < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_shrinkwrap() {
+        let target = Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::splat(4.0)).unwrap();
+        let mesh = Box::build(Vec3::new(0.3, 1.0, 0.2), Vec3::splat(0.5)).unwrap();
+
+        // Every vertex drops straight down onto the plane
+        let nearest = mesh.clone();
+        shrinkwrap(&nearest, &target, ShrinkwrapMode::Nearest, 0.1).unwrap();
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(nearest.read_positions().iter())
+        {
+            assert_near(Vec3::new(a.x, 0.1, a.z), *b);
+        }
+
+        // The normals at the corners of a box point diagonally away from it
+        let projected = mesh.clone();
+        shrinkwrap(&projected, &target, ShrinkwrapMode::Project, 0.0).unwrap();
+        let center = Vec3::new(0.3, 1.0, 0.2);
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(projected.read_positions().iter())
+        {
+            let dir = (*a - center).signum();
+            assert_near(*a - dir * (a.y / dir.y), *b);
+        }
+
+        let points = HalfEdgeMesh::new();
+        assert!(shrinkwrap(&mesh, &points, ShrinkwrapMode::Nearest, 0.0).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Slicing of meshes into layers of contours, like 3D printing slicers do.
+//!
+//! Each layer intersects the faces of the mesh with a plane perpendicular to
+//! the slicing axis. The segments where faces cross the plane are chained
+//! into closed contours through the edges they share. Contours are then
+//! classified with the even-odd rule: a contour inside an odd number of
+//! other contours is a hole. This doesn't rely on the orientation of the
+//! faces, so it also works for meshes with flipped normals.
+
+use float_ord::FloatOrd;
+
+use super::text::{contains, signed_area};
+use crate::prelude::*;
+
+/// Name of the vertex channel storing the index of the layer of each vertex,
+/// in the meshes built by [`contours_to_mesh`].
+pub const LAYER_CHANNEL: &str = "layer";
+/// Name of the vertex group containing the vertices of hole contours, in the
+/// meshes built by [`contours_to_mesh`].
+pub const HOLE_GROUP: &str = "hole";
+
+/// Slicing finer than this many layers is most likely a mistake, and would
+/// take forever.
+const MAX_LAYERS: usize = 100_000;
+
+/// A closed polyline where a layer crosses the mesh. Outer contours go
+/// counter-clockwise when seen from the positive side of the slicing axis,
+/// and holes go clockwise.
+#[derive(Debug, Clone)]
+pub struct SliceContour {
+    pub points: Vec<Vec3>,
+    pub is_hole: bool,
+}
+
+/// The contours of the mesh at a given `height` along the slicing axis.
+#[derive(Debug, Clone)]
+pub struct SliceLayer {
+    pub height: f32,
+    pub contours: Vec<SliceContour>,
+}
+
+/// Identifies the point where a layer crosses an edge, regardless of the
+/// direction the edge is traversed.
+type EdgeKey = (VertexId, VertexId);
+
+fn edge_key(a: VertexId, b: VertexId) -> EdgeKey {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Slices `mesh` into layers `layer_height` units apart along `axis`. Layers
+/// are sampled at the middle of their height, starting from the lowest point
+/// of the mesh. Contours that don't close, which happens when the mesh has
+/// holes, are left out.
+pub fn slice_layers(mesh: &HalfEdgeMesh, layer_height: f32, axis: Vec3) -> Result<Vec<SliceLayer>> {
+    let axis = axis
+        .try_normalize()
+        .ok_or_else(|| anyhow!("The slicing axis can't be zero"))?;
+    if layer_height <= 0.0 {
+        bail!("The layer height must be positive");
+    }
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+
+    let (min, max) = conn
+        .iter_vertices()
+        .map(|(v, _)| positions[v].dot(axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+            (min.min(d), max.max(d))
+        });
+    if min > max {
+        return Ok(vec![]);
+    }
+    let num_layers = ((max - min) / layer_height).ceil() as usize;
+    if num_layers > MAX_LAYERS {
+        bail!("Too many layers. Try with a larger layer height");
+    }
+
+    let faces = conn
+        .iter_faces()
+        .map(|(face, _)| conn.face_vertices(face))
+        .collect_vec();
+    let u = axis.any_orthonormal_vector();
+    let v = axis.cross(u);
+
+    let mut layers = vec![];
+    for i in 0..num_layers {
+        let height = min + layer_height * (i as f32 + 0.5);
+        let contours = slice_at(&faces, &positions, axis, height)
+            .into_iter()
+            .map(|points| SliceContour {
+                points,
+                is_hole: false,
+            })
+            .collect_vec();
+        layers.push(SliceLayer {
+            height,
+            contours: classify_holes(contours, u, v),
+        });
+    }
+    Ok(layers)
+}
+
+/// Returns the closed contours where the plane at `height` along `axis`
+/// crosses the given `faces`.
+fn slice_at(
+    faces: &[SVec<VertexId>],
+    positions: &Positions,
+    axis: Vec3,
+    height: f32,
+) -> Vec<Vec<Vec3>> {
+    // Vertices exactly on the plane count as being above it, so every
+    // crossing happens strictly inside an edge.
+    let distance = |v: VertexId| positions[v].dot(axis) - height;
+    let mut crossings = HashMap::<EdgeKey, Vec3>::new();
+    let mut segments = vec![];
+    for face in faces {
+        let mut face_crossings = SVec::<EdgeKey>::new();
+        for (a, b) in face.iter_cpy().circular_tuple_windows() {
+            let (da, db) = (distance(a), distance(b));
+            if (da >= 0.0) != (db >= 0.0) {
+                let key = edge_key(a, b);
+                crossings.entry(key).or_insert_with(|| {
+                    let (da, db) = (distance(key.0), distance(key.1));
+                    positions[key.0].lerp(positions[key.1], da / (da - db))
+                });
+                face_crossings.push(key);
+            }
+        }
+        // Concave faces can cross the plane more than once. The crossings of
+        // a planar polygon alternate between entering and leaving it along
+        // the line where it meets the plane.
+        if face_crossings.len() > 2 {
+            let points = face.iter().map(|v| positions[*v]).collect::<SVec<_>>();
+            let normal = points
+                .iter()
+                .circular_tuple_windows()
+                .fold(Vec3::ZERO, |acc, (a, b)| acc + a.cross(*b));
+            let direction = axis.cross(normal);
+            face_crossings.sort_by_key(|key| FloatOrd(crossings[key].dot(direction)));
+        }
+        for pair in face_crossings.chunks_exact(2) {
+            segments.push((pair[0], pair[1]));
+        }
+    }
+
+    let mut segments_at = HashMap::<EdgeKey, SVec<usize>>::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        segments_at.entry(*a).or_default().push(i);
+        segments_at.entry(*b).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut contours = vec![];
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let (first, mut key) = segments[start];
+        let mut contour = vec![crossings[&first]];
+        let closed = loop {
+            if key == first {
+                break true;
+            }
+            contour.push(crossings[&key]);
+            let next = segments_at[&key].iter().copied().find(|s| !used[*s]);
+            match next {
+                Some(next) => {
+                    used[next] = true;
+                    let (a, b) = segments[next];
+                    key = if a == key { b } else { a };
+                }
+                None => break false,
+            }
+        };
+        if closed && contour.len() >= 3 {
+            contours.push(contour);
+        }
+    }
+    contours
+}
+
+/// Marks the contours nested inside an odd number of others as holes, and
+/// orients them, using the plane spanned by `u` and `v` to compare them.
+fn classify_holes(mut contours: Vec<SliceContour>, u: Vec3, v: Vec3) -> Vec<SliceContour> {
+    let flat = contours
+        .iter()
+        .map(|c| {
+            c.points
+                .iter()
+                .map(|p| Vec2::new(p.dot(u), p.dot(v)))
+                .collect_vec()
+        })
+        .collect_vec();
+    for (i, contour) in contours.iter_mut().enumerate() {
+        let depth = (0..flat.len())
+            .filter(|j| *j != i && contains(&flat[*j], flat[i][0]))
+            .count();
+        contour.is_hole = depth % 2 == 1;
+        if (signed_area(&flat[i]) > 0.0) == contour.is_hole {
+            contour.points.reverse();
+        }
+    }
+    contours
+}
+
+/// Builds a mesh with the contours of the given `layers`, as closed
+/// polylines. The layer of each vertex is stored in the `layer` channel, and
+/// the vertices of holes are in the `hole` group.
+pub fn contours_to_mesh(layers: &[SliceLayer]) -> Result<HalfEdgeMesh> {
+    let mut mesh = HalfEdgeMesh::new();
+    for (i, layer) in layers.iter().enumerate() {
+        for contour in &layer.contours {
+            let mut polyline = primitives::Polygon::build_open_from_points(contour.points.clone())?;
+            let layer_ch = polyline
+                .channels
+                .ensure_channel::<VertexId, f32>(LAYER_CHANNEL);
+            let hole_ch = polyline
+                .channels
+                .ensure_channel::<VertexId, bool>(HOLE_GROUP);
+            {
+                let conn = polyline.read_connectivity();
+                let mut layer_ch = polyline.channels.write_channel(layer_ch)?;
+                let mut hole_ch = polyline.channels.write_channel(hole_ch)?;
+                for (v, _) in conn.iter_vertices() {
+                    layer_ch[v] = i as f32;
+                    hole_ch[v] = contour.is_hole;
+                }
+            }
+            mesh.merge_with(&polyline);
+        }
+    }
+    Ok(mesh)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+    use mlua::Lua;
+
+    /// Slices `mesh` into layers `layer_height` units apart along `axis`.
+    /// Returns a list of layers, each a table with its `height` and its
+    /// `contours`. Contours are tables with a list of `points` and an
+    /// `is_hole` flag.
+    #[lua(under = "Ops")]
+    fn slice_layers<'lua>(
+        lua: &'lua Lua,
+        mesh: &HalfEdgeMesh,
+        layer_height: f32,
+        axis: LVec3,
+    ) -> Result<mlua::Table<'lua>> {
+        let mut layers = vec![];
+        for layer in super::slice_layers(mesh, layer_height, axis.0)? {
+            let mut contours = vec![];
+            for contour in layer.contours {
+                let table = lua.create_table()?;
+                table.set(
+                    "points",
+                    lua.create_sequence_from(contour.points.into_iter().map(LVec3))?,
+                )?;
+                table.set("is_hole", contour.is_hole)?;
+                contours.push(table);
+            }
+            let table = lua.create_table()?;
+            table.set("height", layer.height)?;
+            table.set("contours", lua.create_sequence_from(contours)?)?;
+            layers.push(table);
+        }
+        Ok(lua.create_sequence_from(layers)?)
+    }
+
+    /// Slices `mesh` like `slice_layers`, and returns the contours of all the
+    /// layers as closed polylines. The `layer` vertex channel stores the
+    /// index of their layer, starting at zero, and the vertices of holes are
+    /// in the `hole` group.
+    #[lua(under = "Ops")]
+    fn slice_contours(mesh: &HalfEdgeMesh, layer_height: f32, axis: LVec3) -> Result<HalfEdgeMesh> {
+        super::contours_to_mesh(&super::slice_layers(mesh, layer_height, axis.0)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    fn test_slice_layers() {
+        // A box with a smaller box inside, like a hollowed shell
+        let mut mesh = Box::build(Vec3::new(0.0, 1.0, 0.0), Vec3::splat(2.0)).unwrap();
+        let inner = Box::build(Vec3::new(0.0, 1.0, 0.0), Vec3::splat(1.0)).unwrap();
+        mesh.merge_with(&inner);
+
+        let layers = slice_layers(&mesh, 0.5, Vec3::Y).unwrap();
+        assert_eq!(layers.len(), 4);
+        assert_eq!(layers[0].height, 0.25);
+        assert_eq!(layers[0].contours.len(), 1);
+
+        let middle = &layers[1];
+        assert_eq!(middle.contours.len(), 2);
+        let holes = middle.contours.iter().filter(|c| c.is_hole).collect_vec();
+        assert_eq!(holes.len(), 1);
+        assert_eq!(holes[0].points.len(), 4);
+        assert!(holes[0].points.iter().all(|p| p.x.abs() == 0.5));
+
+        let contours = contours_to_mesh(&layers).unwrap();
+        assert_eq!(contours.read_connectivity().num_vertices(), 4 * 6);
+        assert_eq!(contours.read_connectivity().num_faces(), 0);
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::path::Path;
+
+use crate::prelude::*;
+
+/// Number of line segments used to approximate each curve of a glyph outline.
+const CURVE_STEPS: usize = 8;
+
+/// Collects the outline of a glyph as a list of closed contours, with curves
+/// flattened into line segments.
+struct ContourBuilder {
+    contours: Vec<Vec<Vec2>>,
+    current: Vec<Vec2>,
+    /// Transforms from font units to mesh space.
+    scale: f32,
+    offset: Vec2,
+}
+
+impl ContourBuilder {
+    fn point(&self, x: f32, y: f32) -> Vec2 {
+        Vec2::new(x, y) * self.scale + self.offset
+    }
+
+    fn last(&self) -> Vec2 {
+        self.current.last().copied().unwrap_or(self.offset)
+    }
+
+    fn push(&mut self, p: Vec2) {
+        if self.current.last() != Some(&p) {
+            self.current.push(p);
+        }
+    }
+}
+
+impl ttf_parser::OutlineBuilder for ContourBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        let p = self.point(x, y);
+        self.push(p);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.push(p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, p1, p2) = (self.last(), self.point(x1, y1), self.point(x, y));
+        for i in 1..=CURVE_STEPS {
+            let t = i as f32 / CURVE_STEPS as f32;
+            self.push(p0.lerp(p1, t).lerp(p1.lerp(p2, t), t));
+        }
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let p0 = self.last();
+        let (p1, p2, p3) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        for i in 1..=CURVE_STEPS {
+            let t = i as f32 / CURVE_STEPS as f32;
+            let (a, b, c) = (p0.lerp(p1, t), p1.lerp(p2, t), p2.lerp(p3, t));
+            self.push(a.lerp(b, t).lerp(b.lerp(c, t), t));
+        }
+    }
+
+    fn close(&mut self) {
+        let mut contour = std::mem::take(&mut self.current);
+        if contour.len() > 1 && contour.first() == contour.last() {
+            contour.pop();
+        }
+        if contour.len() >= 3 {
+            self.contours.push(contour);
+        }
+    }
+}
+
+/// Twice the signed area of a polygon. Positive for counter-clockwise ones.
+pub(crate) fn signed_area(contour: &[Vec2]) -> f32 {
+    contour
+        .iter_cpy()
+        .circular_tuple_windows()
+        .map(|(a, b)| a.perp_dot(b))
+        .sum()
+}
+
+/// Whether `p` is inside the `contour`, using the even-odd rule.
+pub(crate) fn contains(contour: &[Vec2], p: Vec2) -> bool {
+    let mut inside = false;
+    for (a, b) in contour.iter_cpy().circular_tuple_windows() {
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Groups the contours of a glyph into shapes: An outer contour, made
+/// counter-clockwise, followed by its holes, made clockwise. Fonts don't
+/// agree on the orientation of contours, so holes are found by nesting.
+fn group_shapes(contours: Vec<Vec<Vec2>>) -> Vec<Vec<Vec<Vec2>>> {
+    let depth = |i: usize| {
+        (0..contours.len())
+            .filter(|j| *j != i && contains(&contours[*j], contours[i][0]))
+            .count()
+    };
+    let depths = (0..contours.len()).map(depth).collect_vec();
+
+    let mut shapes: Vec<(usize, Vec<Vec<Vec2>>)> = vec![];
+    for (i, contour) in contours.iter().enumerate() {
+        if depths[i] % 2 == 0 {
+            let mut contour = contour.clone();
+            if signed_area(&contour) < 0.0 {
+                contour.reverse();
+            }
+            shapes.push((i, vec![contour]));
+        }
+    }
+    for (i, contour) in contours.iter().enumerate() {
+        if depths[i] % 2 == 1 {
+            // The hole belongs to the innermost outer contour containing it
+            let owner = shapes
+                .iter_mut()
+                .filter(|(o, _)| contains(&contours[*o], contour[0]))
+                .max_by_key(|(o, _)| depths[*o]);
+            if let Some((_, shape)) = owner {
+                let mut contour = contour.clone();
+                if signed_area(&contour) > 0.0 {
+                    contour.reverse();
+                }
+                shape.push(contour);
+            }
+        }
+    }
+    shapes.into_iter().map(|(_, shape)| shape).collect()
+}
+
+/// Builds the polygons of a shape (an outer contour and its holes), adding
+/// its vertices to `positions`. The front cap faces +Z. When `extrude` is not
+/// zero, a back cap and side walls are also built, `extrude` units behind.
+fn build_shape(
+    shape: &[Vec<Vec2>],
+    extrude: f32,
+    positions: &mut Vec<Vec3>,
+    polygons: &mut Vec<SVec<usize>>,
+) {
+    let points = shape.iter().flatten().copied().collect_vec();
+    let mut hole_indices = vec![];
+    let mut start = 0;
+    for contour in &shape[..shape.len() - 1] {
+        start += contour.len();
+        hole_indices.push(start);
+    }
+    let data = points
+        .iter()
+        .flat_map(|p| [p.x as f64, p.y as f64])
+        .collect_vec();
+    let triangles = earcutr::earcut(&data, &hole_indices, 2);
+
+    let front = positions.len();
+    positions.extend(points.iter().map(|p| p.extend(0.0)));
+    let back = positions.len();
+    if extrude != 0.0 {
+        positions.extend(points.iter().map(|p| p.extend(-extrude)));
+    }
+
+    for tri in triangles.chunks_exact(3) {
+        let (a, b, c) = (points[tri[0]], points[tri[1]], points[tri[2]]);
+        let tri = if (b - a).perp_dot(c - a) >= 0.0 {
+            [tri[0], tri[1], tri[2]]
+        } else {
+            [tri[0], tri[2], tri[1]]
+        };
+        polygons.push(tri.iter().map(|i| front + i).collect());
+        if extrude != 0.0 {
+            polygons.push(tri.iter().rev().map(|i| back + i).collect());
+        }
+    }
+
+    if extrude != 0.0 {
+        let mut start = 0;
+        for contour in shape {
+            let n = contour.len();
+            for i in 0..n {
+                let (a, b) = (start + i, start + (i + 1) % n);
+                polygons.push(smallvec::smallvec![
+                    front + a,
+                    back + a,
+                    back + b,
+                    front + b
+                ]);
+            }
+            start += n;
+        }
+    }
+}
+
+/// Builds a mesh for the given `text` using the TrueType or OpenType font
+/// at `font_path`. The text lies on the XY plane, starting at the origin, and
+/// `size` is the height of a line of text. When `extrude` is not zero, the
+/// letters are extruded backwards by that amount.
+pub fn text_mesh(font_path: &Path, text: &str, size: f32, extrude: f32) -> Result<HalfEdgeMesh> {
+    let data = std::fs::read(font_path)
+        .with_context(|| format!("Could not read font at {}", font_path.display()))?;
+    let face = ttf_parser::Face::parse(&data, 0)
+        .map_err(|err| anyhow!("Could not parse font {}: {err}", font_path.display()))?;
+
+    let scale = size / face.units_per_em() as f32;
+    let line_height =
+        (face.ascender() as f32 - face.descender() as f32 + face.line_gap() as f32) * scale;
+
+    let mut positions = vec![];
+    let mut polygons = vec![];
+    let mut cursor = Vec2::ZERO;
+    for c in text.chars() {
+        if c == '\n' {
+            cursor = Vec2::new(0.0, cursor.y - line_height);
+            continue;
+        }
+        let glyph = match face.glyph_index(c) {
+            Some(glyph) => glyph,
+            None => face
+                .glyph_index('?')
+                .ok_or_else(|| anyhow!("Font has no glyph for '{c}'"))?,
+        };
+        let mut builder = ContourBuilder {
+            contours: vec![],
+            current: vec![],
+            scale,
+            offset: cursor,
+        };
+        face.outline_glyph(glyph, &mut builder);
+        ttf_parser::OutlineBuilder::close(&mut builder);
+        for shape in group_shapes(builder.contours) {
+            build_shape(&shape, extrude, &mut positions, &mut polygons);
+        }
+        cursor.x += face.glyph_hor_advance(glyph).unwrap_or(0) as f32 * scale;
+    }
+
+    HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Creates a mesh for the given `text` using the TrueType or OpenType
+    /// font at `font_path`. The text lies on the XY plane and `size` is the
+    /// height of a line. When `extrude` is not zero, the letters are
+    /// extruded backwards by that amount.
+    #[lua(under = "Primitives")]
+    fn text(font_path: String, text: String, size: f32, extrude: f32) -> Result<HalfEdgeMesh> {
+        text_mesh(Path::new(&font_path), &text, size, extrude)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_group_shapes() {
+        let square = |size: f32| {
+            vec![
+                Vec2::new(-size, -size),
+                Vec2::new(-size, size),
+                Vec2::new(size, size),
+                Vec2::new(size, -size),
+            ]
+        };
+        // An "O": An outer contour with a hole, both clockwise
+        let shapes = group_shapes(vec![square(2.0), square(1.0)]);
+        assert_eq!(shapes.len(), 1);
+        assert!(signed_area(&shapes[0][0]) > 0.0);
+        assert!(signed_area(&shapes[0][1]) < 0.0);
+
+        let mut positions = vec![];
+        let mut polygons = vec![];
+        build_shape(&shapes[0], 0.5, &mut positions, &mut polygons);
+        let mesh = HalfEdgeMesh::build_from_polygons(&positions, &polygons).unwrap();
+        // A closed ring: V - E + F = 0
+        let conn = mesh.read_connectivity();
+        let euler = conn.num_vertices() as i64 - (conn.num_halfedges() / 2) as i64
+            + conn.num_faces() as i64;
+        assert_eq!(euler, 0);
+    }
+}
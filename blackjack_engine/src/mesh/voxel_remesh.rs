@@ -0,0 +1,634 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Voxel remeshing: The mesh is converted to a signed distance field sampled
+//! on a regular grid, and a new surface is extracted from it.
+//!
+//! Distances are only computed in a narrow band around the surface, which is
+//! all the extraction needs. The sign of each sample comes from the winding
+//! number of rays cast along the X axis, so overlapping or self-intersecting
+//! parts of the mesh are merged together. The surface is extracted with
+//! marching tetrahedra, the variant of marching cubes that splits each cell
+//! in six tetrahedra. This avoids the ambiguous cases of marching cubes, and
+//! the result is always a closed manifold.
+//!
+//! The same distance field is used to hollow meshes: the cavity is the
+//! surface where the field reaches minus the wall thickness.
+
+use float_ord::FloatOrd;
+
+use crate::prelude::*;
+use crate::progress::{NoProgress, ProgressSink};
+
+use super::csg;
+
+/// Empty grid cells added around the bounding box of the mesh, so the
+/// extracted surface is always closed.
+pub(crate) const PADDING: usize = 2;
+
+/// Grids with more points than this are rejected, to avoid running out of
+/// memory when the voxel size is too small for the mesh.
+const MAX_GRID_POINTS: usize = 256 * 256 * 256;
+
+/// The offsets of the corners of a grid cell. Corner `n` has its X, Y and Z
+/// offsets in the bits 0, 1 and 2 of `n`.
+const CORNERS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [0, 1, 0],
+    [1, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [0, 1, 1],
+    [1, 1, 1],
+];
+
+/// The six tetrahedra each cell is split into, all sharing the diagonal from
+/// corner 0 to 7. Their vertices are positively oriented: The normal of the
+/// triangle formed by the first three points towards the fourth.
+const TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 1, 3, 7],
+    [0, 3, 2, 7],
+    [0, 2, 6, 7],
+    [0, 6, 4, 7],
+    [0, 4, 5, 7],
+    [0, 5, 1, 7],
+];
+
+/// A scalar field sampled on a regular grid.
+pub(crate) struct Grid {
+    pub(crate) origin: Vec3,
+    pub(crate) voxel_size: f32,
+    pub(crate) dims: [usize; 3],
+    pub(crate) values: Vec<f32>,
+}
+
+impl Grid {
+    pub(crate) fn index(&self, i: usize, j: usize, k: usize) -> usize {
+        i + self.dims[0] * (j + self.dims[1] * k)
+    }
+
+    pub(crate) fn point(&self, index: usize) -> Vec3 {
+        let i = index % self.dims[0];
+        let j = (index / self.dims[0]) % self.dims[1];
+        let k = index / (self.dims[0] * self.dims[1]);
+        self.origin + Vec3::new(i as f32, j as f32, k as f32) * self.voxel_size
+    }
+
+    /// Returns an empty grid covering the bounding box of `triangles`, with
+    /// some padding around it.
+    pub(crate) fn around(triangles: &[[Vec3; 3]], voxel_size: f32) -> Result<Self> {
+        let (min, max) = triangles.iter().flatten().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        let padding = PADDING as f32 * voxel_size;
+        let origin = min - Vec3::splat(padding);
+        let size = (max - min + Vec3::splat(2.0 * padding)) / voxel_size;
+        // The size is checked as floats first, since huge grids would
+        // overflow when computing their number of points.
+        let float_dims = size.to_array().map(|x| x.ceil().max(0.0) + 1.0);
+        let float_num_points = float_dims.iter().map(|x| *x as f64).product::<f64>();
+        if float_num_points > MAX_GRID_POINTS as f64 {
+            bail!(
+                "The voxel size is too small for this mesh, it would need a grid of \
+                 {}x{}x{} voxels",
+                float_dims[0],
+                float_dims[1],
+                float_dims[2]
+            );
+        }
+        let dims = float_dims.map(|x| x as usize);
+        let num_points = dims.iter().product::<usize>();
+        Ok(Grid {
+            origin,
+            voxel_size,
+            dims,
+            values: vec![0.0; num_points],
+        })
+    }
+
+    /// The range of grid coordinates along `axis` covering `min..=max`,
+    /// expanded by the padding.
+    fn range(&self, axis: usize, min: f32, max: f32) -> std::ops::Range<usize> {
+        self.range_with_padding(axis, min, max, PADDING)
+    }
+
+    /// Same as `range`, but expanded by `padding` grid points instead.
+    fn range_with_padding(
+        &self,
+        axis: usize,
+        min: f32,
+        max: f32,
+        padding: usize,
+    ) -> std::ops::Range<usize> {
+        let to_grid = |x: f32| (x - self.origin[axis]) / self.voxel_size;
+        let padding = padding as isize;
+        let start = (to_grid(min).floor() as isize - padding).max(0) as usize;
+        let end = (to_grid(max).ceil() as isize + padding + 1).max(0) as usize;
+        start..end.min(self.dims[axis])
+    }
+}
+
+/// Returns the point of the triangle `abc` that's closest to `p`.
+fn closest_point_on_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    // From "Real-Time Collision Detection", by Christer Ericson
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+/// Returns the faces of the mesh as a list of triangles.
+pub(crate) fn mesh_triangles(mesh: &HalfEdgeMesh) -> Vec<[Vec3; 3]> {
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let mut triangles = vec![];
+    for (face, _) in conn.iter_faces() {
+        let vertices = conn.face_vertices(face);
+        for (b, c) in vertices.iter().skip(1).tuple_windows() {
+            triangles.push([positions[vertices[0]], positions[*b], positions[*c]]);
+        }
+    }
+    triangles
+}
+
+/// Fills the grid with the unsigned distance to the `triangles`. Samples
+/// further than `band` voxels are clamped to that distance.
+pub(crate) fn compute_distances(grid: &mut Grid, triangles: &[[Vec3; 3]], band: usize) {
+    let far = band as f32 * grid.voxel_size;
+    grid.values.fill(far);
+    for [a, b, c] in triangles.iter_cpy() {
+        let min = a.min(b).min(c);
+        let max = a.max(b).max(c);
+        for k in grid.range_with_padding(2, min.z, max.z, band) {
+            for j in grid.range_with_padding(1, min.y, max.y, band) {
+                for i in grid.range_with_padding(0, min.x, max.x, band) {
+                    let index = grid.index(i, j, k);
+                    let p = grid.point(index);
+                    let distance = (p - closest_point_on_triangle(p, a, b, c)).length();
+                    grid.values[index] = grid.values[index].min(distance);
+                }
+            }
+        }
+    }
+}
+
+/// Negates the samples of the grid that are inside the mesh, turning the
+/// distances into signed distances. A sample is inside when the winding
+/// number of the mesh around it is not zero.
+pub(crate) fn compute_signs(grid: &mut Grid, triangles: &[[Vec3; 3]]) {
+    // Rays are slightly offset from the grid rows, so they don't hit
+    // vertices or edges of meshes aligned with the grid.
+    let offset = Vec2::new(1.31e-4, 2.77e-4) * grid.voxel_size;
+    let cross2 = |u: Vec2, v: Vec2| u.x * v.y - u.y * v.x;
+
+    // For each row of the grid along the X axis, the places where the ray
+    // crosses the mesh, and the change of the winding number there.
+    let mut crossings = vec![vec![]; grid.dims[1] * grid.dims[2]];
+    for [a, b, c] in triangles.iter_cpy() {
+        let (a2, b2, c2) = (
+            Vec2::new(a.y, a.z),
+            Vec2::new(b.y, b.z),
+            Vec2::new(c.y, c.z),
+        );
+        let area = cross2(b2 - a2, c2 - a2);
+        if area.abs() < f32::EPSILON {
+            // The triangle is parallel to the rays
+            continue;
+        }
+        // The area has the sign of the X coordinate of the normal. Rays
+        // entering the mesh hit triangles facing away from them.
+        let winding = if area < 0.0 { 1 } else { -1 };
+        let min = a2.min(b2).min(c2);
+        let max = a2.max(b2).max(c2);
+        for k in grid.range(2, min.y, max.y) {
+            for j in grid.range(1, min.x, max.x) {
+                let q = Vec2::new(
+                    grid.origin.y + j as f32 * grid.voxel_size,
+                    grid.origin.z + k as f32 * grid.voxel_size,
+                ) + offset;
+                let wb = cross2(q - a2, c2 - a2) / area;
+                let wc = cross2(b2 - a2, q - a2) / area;
+                let wa = 1.0 - wb - wc;
+                if wa >= 0.0 && wb >= 0.0 && wc >= 0.0 {
+                    let x = wa * a.x + wb * b.x + wc * c.x;
+                    crossings[j + grid.dims[1] * k].push((x, winding));
+                }
+            }
+        }
+    }
+
+    for k in 0..grid.dims[2] {
+        for j in 0..grid.dims[1] {
+            let row = &mut crossings[j + grid.dims[1] * k];
+            row.sort_by_key(|(x, _)| float_ord::FloatOrd(*x));
+            let mut winding = 0;
+            let mut next = 0;
+            for i in 0..grid.dims[0] {
+                let x = grid.origin.x + i as f32 * grid.voxel_size;
+                while next < row.len() && row[next].0 < x {
+                    winding += row[next].1;
+                    next += 1;
+                }
+                if winding != 0 {
+                    let index = grid.index(i, j, k);
+                    grid.values[index] = -grid.values[index];
+                }
+            }
+        }
+    }
+}
+
+/// Builds the surface where the grid values are zero, using marching
+/// tetrahedra. Negative values are inside.
+struct Extraction<'a> {
+    grid: &'a Grid,
+    positions: Vec<Vec3>,
+    triangles: Vec<[usize; 3]>,
+    /// The vertex created on each edge between two grid points, so
+    /// neighboring tetrahedra share their vertices.
+    edge_vertices: HashMap<(usize, usize), usize>,
+}
+
+impl<'a> Extraction<'a> {
+    fn edge_vertex(&mut self, a: usize, b: usize) -> usize {
+        let (a, b) = (a.min(b), a.max(b));
+        let grid = self.grid;
+        let positions = &mut self.positions;
+        *self.edge_vertices.entry((a, b)).or_insert_with(|| {
+            let (va, vb) = (grid.values[a], grid.values[b]);
+            let t = va / (va - vb);
+            positions.push(grid.point(a).lerp(grid.point(b), t));
+            positions.len() - 1
+        })
+    }
+
+    /// Adds the triangles of the surface crossing the tetrahedron with the
+    /// given grid points, which must be positively oriented.
+    fn polygonize_tetrahedron(&mut self, points: [usize; 4]) {
+        let inside = points.map(|p| self.grid.values[p] < 0.0);
+        let num_inside = inside.iter().filter(|x| **x).count();
+        if num_inside == 0 || num_inside == 4 {
+            return;
+        }
+
+        // Reorder the points with the inside ones first. The order must be
+        // an even permutation to keep the orientation, which tells the
+        // triangles which way to face.
+        let mut order = [0, 1, 2, 3];
+        order.sort_by_key(|x| !inside[*x]);
+        let inversions = (0..4)
+            .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+            .filter(|(i, j)| order[*i] > order[*j])
+            .count();
+        if inversions % 2 == 1 {
+            // Swap two points on the same side
+            if num_inside == 3 {
+                order.swap(0, 1);
+            } else {
+                order.swap(2, 3);
+            }
+        }
+        let [p, q, r, s] = order.map(|x| points[x]);
+
+        match num_inside {
+            1 => {
+                let tri = [
+                    self.edge_vertex(p, q),
+                    self.edge_vertex(p, r),
+                    self.edge_vertex(p, s),
+                ];
+                self.triangles.push(tri);
+            }
+            2 => {
+                let quad = [
+                    self.edge_vertex(p, r),
+                    self.edge_vertex(p, s),
+                    self.edge_vertex(q, s),
+                    self.edge_vertex(q, r),
+                ];
+                self.triangles.push([quad[0], quad[1], quad[2]]);
+                self.triangles.push([quad[0], quad[2], quad[3]]);
+            }
+            3 => {
+                let tri = [
+                    self.edge_vertex(p, s),
+                    self.edge_vertex(q, s),
+                    self.edge_vertex(r, s),
+                ];
+                self.triangles.push(tri);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Rebuilds `mesh` as the closed surface of its volume, sampled on a grid of
+/// voxels of size `voxel_size`. Fixes self-intersections and non-manifold
+/// geometry, but details smaller than a voxel are lost. The input should be
+/// closed, as holes make the inside of the mesh ill-defined. Only the vertex
+/// positions of the result are computed.
+pub fn voxel_remesh(mesh: &HalfEdgeMesh, voxel_size: f32) -> Result<HalfEdgeMesh> {
+    voxel_remesh_with_progress(mesh, voxel_size, &NoProgress)
+}
+
+/// Same as `voxel_remesh`, but reports the progress to the given `progress`
+/// sink, and stops early when it gets cancelled.
+pub fn voxel_remesh_with_progress(
+    mesh: &HalfEdgeMesh,
+    voxel_size: f32,
+    progress: &dyn ProgressSink,
+) -> Result<HalfEdgeMesh> {
+    let name = "Voxel remesh";
+    if voxel_size <= 0.0 {
+        bail!("The voxel size must be positive, got {voxel_size}");
+    }
+    let triangles = mesh_triangles(mesh);
+    if triangles.is_empty() {
+        bail!("Cannot remesh a mesh without faces");
+    }
+
+    let mut grid = Grid::around(&triangles, voxel_size)?;
+    compute_distances(&mut grid, &triangles, PADDING);
+    progress.report(name, 0.25);
+    progress.check_cancelled()?;
+    compute_signs(&mut grid, &triangles);
+    progress.report(name, 0.5);
+    progress.check_cancelled()?;
+
+    extract_surface(&grid, |p| {
+        progress.report(name, 0.5 + 0.5 * p);
+        progress.check_cancelled()
+    })
+}
+
+/// Number of sides of the cylinders drilled for drain holes.
+const DRAIN_HOLE_SIDES: usize = 16;
+
+/// Hollows out `mesh`, leaving a shell of `wall_thickness` units. The cavity
+/// is the inner offset of the volume, sampled on a grid of voxels of size
+/// `voxel_size`, while the outer surface is kept as is. A drain hole of
+/// `drain_hole_radius` is drilled through the shell at the closest point of
+/// the surface to each of the `drain_positions`, so uncured resin or powder
+/// can get out of the print. Like `voxel_remesh`, the input should be closed.
+pub fn hollow(
+    mesh: &HalfEdgeMesh,
+    wall_thickness: f32,
+    voxel_size: f32,
+    drain_hole_radius: f32,
+    drain_positions: &[Vec3],
+) -> Result<HalfEdgeMesh> {
+    hollow_with_progress(
+        mesh,
+        wall_thickness,
+        voxel_size,
+        drain_hole_radius,
+        drain_positions,
+        &NoProgress,
+    )
+}
+
+/// Same as `hollow`, but reports the progress to the given `progress` sink,
+/// and stops early when it gets cancelled.
+pub fn hollow_with_progress(
+    mesh: &HalfEdgeMesh,
+    wall_thickness: f32,
+    voxel_size: f32,
+    drain_hole_radius: f32,
+    drain_positions: &[Vec3],
+    progress: &dyn ProgressSink,
+) -> Result<HalfEdgeMesh> {
+    let name = "Hollow";
+    if voxel_size <= 0.0 {
+        bail!("The voxel size must be positive, got {voxel_size}");
+    }
+    if wall_thickness <= 0.0 {
+        bail!("The wall thickness must be positive, got {wall_thickness}");
+    }
+    let triangles = mesh_triangles(mesh);
+    if triangles.is_empty() {
+        bail!("Cannot hollow a mesh without faces");
+    }
+
+    // Distances are needed a bit further than the wall thickness, to find
+    // where the cavity starts.
+    let band = (wall_thickness / voxel_size).ceil() as usize + PADDING;
+    let mut grid = Grid::around(&triangles, voxel_size)?;
+    compute_distances(&mut grid, &triangles, band);
+    progress.report(name, 0.2);
+    progress.check_cancelled()?;
+    compute_signs(&mut grid, &triangles);
+    progress.report(name, 0.4);
+    progress.check_cancelled()?;
+
+    // The cavity is where the signed distance is below `-wall_thickness`.
+    // The field is negated so the extracted surface faces into the cavity,
+    // which is the outside of the shell.
+    for value in &mut grid.values {
+        *value = -(*value + wall_thickness);
+    }
+    if !grid.values.iter().any(|v| *v > 0.0) {
+        bail!("The walls are too thick, there is no room left for a cavity");
+    }
+    let cavity = extract_surface(&grid, |p| {
+        progress.report(name, 0.4 + 0.4 * p);
+        progress.check_cancelled()
+    })?;
+
+    let mut result = mesh.clone();
+    result.merge_with(&cavity);
+
+    if drain_positions.is_empty() {
+        return Ok(result);
+    }
+    if drain_hole_radius <= 0.0 {
+        bail!("The drain hole radius must be positive, got {drain_hole_radius}");
+    }
+    let mut drills = HalfEdgeMesh::new();
+    for &position in drain_positions {
+        let (point, normal) = closest_surface_point(&triangles, position);
+        // The drill starts outside the mesh and goes past the cavity.
+        let length = 2.0 * wall_thickness + 2.0 * voxel_size;
+        let drill =
+            primitives::Cylinder::build(Vec3::ZERO, drain_hole_radius, length, DRAIN_HOLE_SIDES)?;
+        edit_ops::transform(
+            &drill,
+            point - normal * (length * 0.5 - voxel_size),
+            Quat::from_rotation_arc(Vec3::Y, normal),
+            Vec3::ONE,
+        )?;
+        drills.merge_with(&drill);
+    }
+    let result = csg::boolean(&result, &drills, csg::BooleanMode::Difference)?;
+    progress.report(name, 1.0);
+    Ok(result)
+}
+
+/// Returns the point of the `triangles` closest to `p`, along with the
+/// normal of its triangle.
+fn closest_surface_point(triangles: &[[Vec3; 3]], p: Vec3) -> (Vec3, Vec3) {
+    triangles
+        .iter_cpy()
+        .map(|[a, b, c]| {
+            let closest = closest_point_on_triangle(p, a, b, c);
+            (closest, (b - a).cross(c - a))
+        })
+        .filter(|(_, normal)| *normal != Vec3::ZERO)
+        .min_by_key(|(closest, _)| FloatOrd(closest.distance_squared(p)))
+        .map(|(closest, normal)| (closest, normal.normalize()))
+        .unwrap_or((p, Vec3::Y))
+}
+
+/// Builds the surface where the values of `grid` cross zero, with the
+/// negative side inside. The `progress` callback gets called with the
+/// fraction of the grid processed so far, and can stop the extraction by
+/// returning an error.
+pub(crate) fn extract_surface(
+    grid: &Grid,
+    mut progress: impl FnMut(f32) -> Result<()>,
+) -> Result<HalfEdgeMesh> {
+    let dims = grid.dims;
+    let mut extraction = Extraction {
+        grid,
+        positions: vec![],
+        triangles: vec![],
+        edge_vertices: HashMap::new(),
+    };
+    for k in 0..dims[2] - 1 {
+        for j in 0..dims[1] - 1 {
+            for i in 0..dims[0] - 1 {
+                let corners = CORNERS.map(|[x, y, z]| grid.index(i + x, j + y, k + z));
+                let num_inside = corners.iter().filter(|c| grid.values[**c] < 0.0).count();
+                if num_inside == 0 || num_inside == 8 {
+                    continue;
+                }
+                for tetrahedron in TETRAHEDRA {
+                    extraction.polygonize_tetrahedron(tetrahedron.map(|c| corners[c]));
+                }
+            }
+        }
+        progress((k + 1) as f32 / (dims[2] - 1) as f32)?;
+    }
+
+    if extraction.triangles.is_empty() {
+        bail!("The mesh has no volume at this voxel size. Is it closed?");
+    }
+    HalfEdgeMesh::build_from_polygons(&extraction.positions, &extraction.triangles)
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use crate::lua_engine::lua_stdlib::LVec3;
+    use crate::progress::ProgressTracker;
+    use mlua::Lua;
+
+    /// Rebuilds `mesh` as the closed surface of its volume, sampled on a grid
+    /// of voxels of size `voxel_size`. This fixes self-intersecting and
+    /// non-manifold geometry. The result is a triangle mesh with only vertex
+    /// positions.
+    #[lua(under = "Ops")]
+    fn voxel_remesh(lua: &Lua, mesh: &HalfEdgeMesh, voxel_size: f32) -> Result<HalfEdgeMesh> {
+        super::voxel_remesh_with_progress(mesh, voxel_size, ProgressTracker::current(lua).as_ref())
+    }
+
+    /// Hollows out `mesh`, leaving a shell of `wall_thickness`. The cavity is
+    /// sampled on a grid of voxels of size `voxel_size`. A drain hole of
+    /// `drain_hole_radius` is drilled through the shell at the closest point
+    /// of the surface to each of the `drain_positions`.
+    #[lua(under = "Ops")]
+    fn hollow(
+        lua: &Lua,
+        mesh: &HalfEdgeMesh,
+        wall_thickness: f32,
+        voxel_size: f32,
+        drain_hole_radius: f32,
+        drain_positions: Vec<LVec3>,
+    ) -> Result<HalfEdgeMesh> {
+        super::hollow_with_progress(
+            mesh,
+            wall_thickness,
+            voxel_size,
+            drain_hole_radius,
+            &LVec3::cast_vector(drain_positions),
+            ProgressTracker::current(lua).as_ref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    fn volume(mesh: &HalfEdgeMesh) -> f32 {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        conn.iter_faces()
+            .map(|(face, _)| {
+                let v = conn.face_vertices(face);
+                let (a, b, c) = (positions[v[0]], positions[v[1]], positions[v[2]]);
+                a.dot(b.cross(c)) / 6.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_voxel_remesh_box() {
+        let mut box_mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+        let remeshed = voxel_remesh(&box_mesh, 0.1).unwrap();
+        assert!((volume(&remeshed) - 1.0).abs() < 0.1);
+
+        // Two overlapping boxes are merged into a single volume
+        let other = Box::build(Vec3::new(0.5, 0.0, 0.0), Vec3::ONE).unwrap();
+        box_mesh.merge_with(&other);
+        let remeshed = voxel_remesh(&box_mesh, 0.1).unwrap();
+        assert!((volume(&remeshed) - 1.5).abs() < 0.15);
+
+        // Grids too large to count their points in an usize are rejected
+        assert!(voxel_remesh(&box_mesh, 1e-30).is_err());
+    }
+
+    #[test]
+    fn test_hollow_box() {
+        let box_mesh = Box::build(Vec3::ZERO, Vec3::splat(2.0)).unwrap();
+        let hollowed = hollow(&box_mesh, 0.2, 0.1, 0.1, &[]).unwrap();
+        // The cavity faces inwards, so it gets subtracted from the volume
+        assert!((volume(&hollowed) - (8.0 - 1.6f32.powi(3))).abs() < 0.3);
+        assert!(hollow(&box_mesh, 1.5, 0.1, 0.1, &[]).is_err());
+    }
+}
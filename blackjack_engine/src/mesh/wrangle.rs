@@ -0,0 +1,389 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Short Lua snippets that run once for every element of a mesh, reading and
+//! writing its channels through `@name` accessors, like `@P = @P * 2.0`.
+//!
+//! The snippet is compiled a single time, as the body of a function. Before
+//! running it, every channel it mentions is copied to a Lua table in one go,
+//! and the tables are written back to the mesh once all the elements are
+//! done. This way, each call only does a few table lookups, which the Luau
+//! interpreter handles much better than going through the channel API.
+//!
+//! Accessors can have a type prefix, like `v@dir` for vectors, `f@weight`
+//! for scalars or `b@keep` for booleans. Without a prefix, the type of an
+//! existing channel with that name is used, or a scalar when there is none.
+//! Missing channels are created. A few names have a special meaning:
+//! - `@P`: The position of a vertex. Only available when running over
+//!   vertices.
+//! - `@N`: The normal of the element. When the mesh has no normals, smooth
+//!   ones are computed for vertices and flat ones for faces. Read only.
+//! - `@index`: The index of the element, starting at 1. Read only.
+
+use mlua::Lua;
+use slotmap::Key;
+
+use crate::lua_engine::lua_stdlib::LVec3;
+use crate::prelude::*;
+
+use super::halfedge::edit_ops::{
+    generate_corner_normals_channel, generate_flat_normals_channel, generate_smooth_normals_channel,
+};
+
+/// The name of the parameter of the compiled snippet, holding the values of
+/// the accessors for the current element.
+const ATTRIBUTES_VAR: &str = "__wrangle";
+
+/// An `@name` accessor found in a snippet, with its type prefix, if any.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Accessor {
+    name: String,
+    value_type: Option<ChannelValueType>,
+}
+
+/// Where the values of an accessor come from.
+enum Source {
+    /// The index of each element. Read only.
+    Index,
+    /// The normals of the elements. Read only.
+    Normal,
+    /// A channel of the mesh, which gets the new values written back.
+    Channel(ChannelValueType, String),
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Rewrites the `@name` accessors of `code` as fields of the attributes
+/// table, and returns the rewritten code along with the accessors that were
+/// found. Accessors inside string literals and comments are left untouched.
+fn preprocess(code: &str) -> Result<(String, Vec<Accessor>)> {
+    let chars = code.chars().collect_vec();
+    let mut out = String::with_capacity(code.len());
+    let mut accessors: Vec<Accessor> = vec![];
+    let mut quote = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if let Some(q) = quote {
+            out.push(c);
+            if c == '\\' && i < chars.len() {
+                out.push(chars[i]);
+                i += 1;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            '-' if chars.get(i) == Some(&'-') => {
+                let comment = chars[i - 1..].iter().take_while(|c| **c != '\n');
+                let len = comment.clone().count();
+                out.extend(comment);
+                i += len - 1;
+            }
+            '@' => {
+                let name: String = chars[i..]
+                    .iter()
+                    .take_while(|c| is_identifier_char(**c))
+                    .collect();
+                if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                    bail!("Expected an attribute name after '@'");
+                }
+                i += name.len();
+
+                // A single letter right before the '@' is a type prefix
+                let at = i - name.len() - 1;
+                let is_prefix = at >= 1
+                    && (at < 2 || !is_identifier_char(chars[at - 2]))
+                    && matches!(chars[at - 1], 'v' | 'f' | 'b');
+                let value_type = if is_prefix {
+                    out.pop();
+                    Some(match chars[at - 1] {
+                        'v' => ChannelValueType::Vec3,
+                        'f' => ChannelValueType::f32,
+                        _ => ChannelValueType::bool,
+                    })
+                } else {
+                    None
+                };
+
+                match accessors.iter_mut().find(|acc| acc.name == name) {
+                    Some(existing) => match (existing.value_type, value_type) {
+                        (Some(a), Some(b)) if a != b => {
+                            bail!("Attribute @{name} is used with different types")
+                        }
+                        (None, Some(_)) => existing.value_type = value_type,
+                        _ => {}
+                    },
+                    None => accessors.push(Accessor {
+                        name: name.clone(),
+                        value_type,
+                    }),
+                }
+                out.push_str(ATTRIBUTES_VAR);
+                out.push('.');
+                out.push_str(&name);
+            }
+            _ => out.push(c),
+        }
+    }
+    Ok((out, accessors))
+}
+
+/// Returns the ids of all the elements of `domain`, in iteration order.
+fn element_ids(mesh: &HalfEdgeMesh, domain: ChannelKeyType) -> Vec<u64> {
+    let conn = mesh.read_connectivity();
+    match domain {
+        ChannelKeyType::VertexId => conn
+            .iter_vertices()
+            .map(|(v, _)| v.data().as_ffi())
+            .collect(),
+        ChannelKeyType::FaceId => conn.iter_faces().map(|(f, _)| f.data().as_ffi()).collect(),
+        ChannelKeyType::HalfEdgeId => conn
+            .iter_halfedges()
+            .map(|(h, _)| h.data().as_ffi())
+            .collect(),
+    }
+}
+
+/// Returns a sequential table with the normals of the elements in `ids`,
+/// computing them when the mesh has none.
+fn normals_table<'lua>(
+    lua: &'lua Lua,
+    mesh: &HalfEdgeMesh,
+    domain: ChannelKeyType,
+    ids: &[u64],
+) -> Result<mlua::Table<'lua>> {
+    let key = |id: &u64| slotmap::KeyData::from_ffi(*id);
+    let normals: Vec<Vec3> = match domain {
+        ChannelKeyType::VertexId => {
+            let normals = match mesh.read_vertex_normals() {
+                Some(normals) => (*normals).clone(),
+                None => generate_smooth_normals_channel(mesh)?,
+            };
+            ids.iter()
+                .map(|id| normals[VertexId::from(key(id))])
+                .collect()
+        }
+        ChannelKeyType::FaceId => {
+            let normals = match mesh.read_face_normals() {
+                Some(normals) => (*normals).clone(),
+                None => generate_flat_normals_channel(mesh)?,
+            };
+            ids.iter()
+                .map(|id| normals[FaceId::from(key(id))])
+                .collect()
+        }
+        ChannelKeyType::HalfEdgeId => {
+            let normals = match mesh.read_corner_normals() {
+                Some(normals) => (*normals).clone(),
+                None => generate_corner_normals_channel(mesh)?,
+            };
+            ids.iter()
+                .map(|id| normals[HalfEdgeId::from(key(id))])
+                .collect()
+        }
+    };
+    Ok(lua.create_sequence_from(normals.into_iter().map(LVec3))?)
+}
+
+/// Runs the Lua `code` once for every element of `domain` in `mesh`, with
+/// `@name` accessors to read and write the channels of the element. See the
+/// module documentation for the available accessors.
+pub fn wrangle(
+    lua: &Lua,
+    mesh: &mut HalfEdgeMesh,
+    domain: ChannelKeyType,
+    code: &str,
+) -> Result<()> {
+    let (code, accessors) = preprocess(code)?;
+    let function: mlua::Function = lua
+        .load(&format!("return function({ATTRIBUTES_VAR})\n{code}\nend"))
+        .set_name("=wrangle")?
+        .eval()?;
+
+    let mut sources = vec![];
+    for acc in accessors {
+        let source = match acc.name.as_str() {
+            "index" => Source::Index,
+            "N" => Source::Normal,
+            "P" => {
+                if domain != ChannelKeyType::VertexId {
+                    bail!("@P is only available when running over vertices");
+                }
+                Source::Channel(ChannelValueType::Vec3, "position".into())
+            }
+            name => {
+                let value_type = acc.value_type.unwrap_or_else(|| {
+                    [
+                        ChannelValueType::Vec3,
+                        ChannelValueType::f32,
+                        ChannelValueType::bool,
+                    ]
+                    .into_iter()
+                    .find(|vty| mesh.channels.channel_id_dyn(domain, *vty, name).is_some())
+                    .unwrap_or(ChannelValueType::f32)
+                });
+                mesh.channels.ensure_channel_dyn(domain, value_type, name);
+                Source::Channel(value_type, name.into())
+            }
+        };
+        sources.push((acc.name, source));
+    }
+
+    // Channel values are copied to Lua in a single batch, before running
+    // the snippet for any element.
+    let ids = element_ids(mesh, domain);
+    let mut tables = vec![];
+    for (name, source) in &sources {
+        let table = match source {
+            Source::Index => continue,
+            Source::Normal => normals_table(lua, mesh, domain, &ids)?,
+            Source::Channel(vty, ch_name) => mesh
+                .channels
+                .dyn_read_channel_by_name(domain, *vty, ch_name)?
+                .to_seq_table(Box::new(ids.iter().copied()), lua),
+        };
+        let writable = matches!(source, Source::Channel(..));
+        tables.push((name.as_str(), table, writable));
+    }
+    let uses_index = sources
+        .iter()
+        .any(|(_, source)| matches!(source, Source::Index));
+
+    let attributes = lua.create_table()?;
+    for i in 1..=ids.len() {
+        for (name, table, _) in &tables {
+            attributes.raw_set(*name, table.raw_get::<_, mlua::Value>(i)?)?;
+        }
+        if uses_index {
+            attributes.raw_set("index", i)?;
+        }
+        function
+            .call::<_, ()>(attributes.clone())
+            .with_context(|| format!("Error running the wrangle snippet for element {i}"))?;
+        for (name, table, writable) in &tables {
+            if *writable {
+                let value = attributes.raw_get::<_, mlua::Value>(*name)?;
+                if matches!(value, mlua::Value::Nil) {
+                    bail!("@{name} was set to nil for element {i}");
+                }
+                table.raw_set(i, value)?;
+            }
+        }
+    }
+
+    for ((_, table, writable), (_, source)) in tables.into_iter().zip(&sources) {
+        if let (true, Source::Channel(vty, ch_name)) = (writable, source) {
+            mesh.channels
+                .dyn_write_channel_by_name(domain, *vty, ch_name)?
+                .set_from_seq_table(Box::new(ids.iter().copied()), lua, table)?;
+        }
+    }
+    Ok(())
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+
+    /// Returns a copy of `mesh` after running the Lua `code` once for every
+    /// element of the `domain`, like `Types.VERTEX_ID`. The code can read and
+    /// write the channels of each element with `@name` accessors, like `@P`
+    /// for vertex positions or `f@weight` for a scalar channel.
+    #[lua(under = "Ops")]
+    fn wrangle(
+        lua: &Lua,
+        mesh: &HalfEdgeMesh,
+        domain: ChannelKeyType,
+        code: String,
+    ) -> Result<HalfEdgeMesh> {
+        let mut result = mesh.clone();
+        super::wrangle(lua, &mut result, domain, &code)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use halfedge::primitives::Box;
+
+    #[test]
+    fn test_preprocess() {
+        let (code, accessors) =
+            preprocess("f@w = @P + @N -- don't use @x\nif b@keep then print('@P') end").unwrap();
+        assert_eq!(
+            code,
+            "__wrangle.w = __wrangle.P + __wrangle.N -- don't use @x\n\
+             if __wrangle.keep then print('@P') end"
+        );
+        let names = accessors.iter().map(|acc| acc.name.as_str()).collect_vec();
+        assert_eq!(names, ["w", "P", "N", "keep"]);
+        assert_eq!(accessors[0].value_type, Some(ChannelValueType::f32));
+        assert_eq!(accessors[1].value_type, None);
+        assert_eq!(accessors[3].value_type, Some(ChannelValueType::bool));
+
+        // Identifiers ending in a prefix letter are not prefixes
+        let (code, accessors) = preprocess("of@x = 1").unwrap();
+        assert_eq!(code, "of__wrangle.x = 1");
+        assert_eq!(accessors[0].value_type, None);
+
+        assert!(preprocess("f@x = v@x").is_err());
+        assert!(preprocess("@ = 1").is_err());
+    }
+
+    #[test]
+    fn test_wrangle() {
+        let lua = Lua::new();
+        let mesh = Box::build(Vec3::ZERO, Vec3::ONE).unwrap();
+
+        let mut result = mesh.clone();
+        wrangle(
+            &lua,
+            &mut result,
+            ChannelKeyType::VertexId,
+            "@P = @P * 2\nf@weight = @index\n@N = @P",
+        )
+        .unwrap();
+        for ((_, a), (_, b)) in mesh
+            .read_positions()
+            .iter()
+            .zip(result.read_positions().iter())
+        {
+            assert_eq!(*a * 2.0, *b);
+        }
+        let weights = result
+            .channels
+            .read_channel_by_name::<VertexId, f32>("weight")
+            .unwrap();
+        let conn = result.read_connectivity();
+        for (i, (v, _)) in conn.iter_vertices().enumerate() {
+            assert_eq!(weights[v], (i + 1) as f32);
+        }
+        // Normals are read only, so they aren't stored in the mesh
+        assert!(result
+            .channels
+            .channel_id_dyn(
+                ChannelKeyType::VertexId,
+                ChannelValueType::Vec3,
+                "vertex_normal"
+            )
+            .is_none());
+
+        let mut result = mesh.clone();
+        assert!(wrangle(&lua, &mut result, ChannelKeyType::FaceId, "@P = @N").is_err());
+        assert!(wrangle(&lua, &mut result, ChannelKeyType::FaceId, "f@x = nil").is_err());
+        assert!(wrangle(&lua, &mut result, ChannelKeyType::FaceId, "f@x = ").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use crate::prelude::*;
+
+/// Receives progress reports from long-running operations, like importing a
+/// large file, and lets them know when they should stop. Sinks must be `Sync`,
+/// since parallel operations report from several threads.
+pub trait ProgressSink: Sync {
+    /// Reports that a `fraction` (between 0 and 1) of the operation called
+    /// `name` is done.
+    fn report(&self, name: &str, fraction: f32);
+
+    /// Returns true when the operation should stop as soon as possible.
+    fn is_cancelled(&self) -> bool;
+
+    /// Returns an error when the operation was cancelled. Operations call this
+    /// at safe points, where stopping doesn't leave any broken state behind.
+    fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            bail!("Operation cancelled by the user")
+        }
+        Ok(())
+    }
+}
+
+/// A sink that ignores all reports and never cancels.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _name: &str, _fraction: f32) {}
+
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// The last progress report received by a [`ProgressTracker`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgressState {
+    pub name: String,
+    pub fraction: f32,
+}
+
+/// A progress sink that stores the last report, so it can be displayed by the
+/// host application. Reports and cancellation requests may come from
+/// different threads.
+///
+/// Cancellation is sticky: Once cancelled, all operations stop at their first
+/// check until `resume` is called. This keeps graphs that are cooked
+/// repeatedly from starting the same long operation over again.
+#[derive(Default)]
+pub struct ProgressTracker {
+    state: Mutex<Option<ProgressState>>,
+    cancelled: AtomicBool,
+}
+
+impl ProgressSink for ProgressTracker {
+    fn report(&self, name: &str, fraction: f32) {
+        *self.state.lock().unwrap() = Some(ProgressState {
+            name: name.into(),
+            fraction: fraction.clamp(0.0, 1.0),
+        });
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl ProgressTracker {
+    /// Returns the last progress report, if any operation reported since the
+    /// last call to `clear`.
+    pub fn state(&self) -> Option<ProgressState> {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Forgets the last progress report. Hosts call this before running the
+    /// graph.
+    pub fn clear(&self) {
+        *self.state.lock().unwrap() = None;
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    /// Makes this tracker receive the reports of the operations run from
+    /// `lua`.
+    pub fn install(self: &Arc<Self>, lua: &mlua::Lua) {
+        lua.set_app_data(Arc::clone(self));
+    }
+
+    /// Returns the tracker installed in `lua`. When the host didn't install
+    /// any, a new tracker is returned, so reports are simply discarded.
+    pub fn current(lua: &mlua::Lua) -> Arc<Self> {
+        lua.app_data_ref::<Arc<ProgressTracker>>()
+            .map(|tracker| Arc::clone(&tracker))
+            .unwrap_or_default()
+    }
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use mlua::Lua;
+
+    /// Reports that a `fraction` (between 0 and 1) of the operation called
+    /// `name` is done. Lets long-running nodes written in Lua show their
+    /// progress.
+    #[lua(under = "Progress")]
+    fn report(lua: &Lua, name: String, fraction: f32) {
+        ProgressTracker::current(lua).report(&name, fraction)
+    }
+
+    /// Returns true when the user asked to cancel long-running operations.
+    /// Nodes should stop as soon as possible when this happens.
+    #[lua(under = "Progress")]
+    fn is_cancelled(lua: &Lua) -> bool {
+        ProgressTracker::current(lua).is_cancelled()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_progress_tracker() {
+        let tracker = ProgressTracker::default();
+        assert!(tracker.state().is_none());
+        tracker.report("Import", 0.5);
+        assert_eq!(tracker.state().unwrap().fraction, 0.5);
+        assert!(tracker.check_cancelled().is_ok());
+        tracker.cancel();
+        assert!(tracker.check_cancelled().is_err());
+        tracker.resume();
+        tracker.clear();
+        assert!(!tracker.is_cancelled());
+        assert!(tracker.state().is_none());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// The axis-aligned plane a reference image is displayed on, named after the
+/// view it's meant to be seen from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferencePlane {
+    /// The XY plane, seen along the Z axis.
+    Front,
+    /// The ZY plane, seen along the X axis.
+    Side,
+    /// The XZ plane, seen from above.
+    Top,
+}
+
+impl ReferencePlane {
+    pub const ALL: [Self; 3] = [Self::Front, Self::Side, Self::Top];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReferencePlane::Front => "Front",
+            ReferencePlane::Side => "Side",
+            ReferencePlane::Top => "Top",
+        }
+    }
+
+    /// Returns the directions of the image's right and up sides on this
+    /// plane.
+    pub fn axes(&self) -> (Vec3, Vec3) {
+        match self {
+            ReferencePlane::Front => (Vec3::X, Vec3::Y),
+            ReferencePlane::Side => (Vec3::Z, Vec3::Y),
+            ReferencePlane::Top => (Vec3::X, -Vec3::Z),
+        }
+    }
+}
+
+/// An image displayed in the viewport as a modeling guide, like concept art
+/// or a blueprint. Reference images are saved with the project, but they are
+/// not part of the graph and never show up in its outputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceImage {
+    /// Path to the image file. Relative paths are resolved against the folder
+    /// containing the project.
+    pub path: String,
+    pub plane: ReferencePlane,
+    /// The position of the center of the image.
+    pub center: Vec3,
+    /// The width of the image, in world units. The height is given by the
+    /// aspect ratio of the image.
+    pub width: f32,
+    /// In the 0..1 range.
+    pub opacity: f32,
+    pub visible: bool,
+}
+
+impl ReferenceImage {
+    pub fn new(path: impl Into<String>, plane: ReferencePlane) -> Self {
+        Self {
+            path: path.into(),
+            plane,
+            center: Vec3::ZERO,
+            width: 10.0,
+            opacity: 0.5,
+            visible: true,
+        }
+    }
+
+    /// Returns the corners of the image in world space, for an image with the
+    /// given `aspect` ratio (width / height). The order is top-left,
+    /// top-right, bottom-right, bottom-left, matching the texture's rows.
+    pub fn corners(&self, aspect: f32) -> [Vec3; 4] {
+        let (right, up) = self.plane.axes();
+        let right = right * self.width * 0.5;
+        let up = up * self.width / aspect.max(f32::EPSILON) * 0.5;
+        [
+            self.center - right + up,
+            self.center + right + up,
+            self.center + right - up,
+            self.center - right - up,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_reference_image_corners() {
+        let mut image = ReferenceImage::new("blueprint.png", ReferencePlane::Front);
+        image.center = Vec3::new(0.0, 1.0, 0.0);
+        image.width = 4.0;
+        let [top_left, top_right, bottom_right, bottom_left] = image.corners(2.0);
+        assert_eq!(top_left, Vec3::new(-2.0, 2.0, 0.0));
+        assert_eq!(top_right, Vec3::new(2.0, 2.0, 0.0));
+        assert_eq!(bottom_right, Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(bottom_left, Vec3::new(-2.0, 0.0, 0.0));
+
+        // Top images are seen from above, with their top side facing -Z
+        image.plane = ReferencePlane::Top;
+        image.center = Vec3::ZERO;
+        assert_eq!(image.corners(2.0)[0], Vec3::new(-2.0, 0.0, -1.0));
+    }
+}
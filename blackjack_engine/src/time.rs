@@ -0,0 +1,73 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// The point in time at which an animated graph is evaluated. Nodes read it
+/// through the `Time` Lua API, so the same graph produces a different result
+/// for each frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameContext {
+    pub frame: i32,
+    /// Frames per second, used to convert frames to seconds.
+    pub fps: f32,
+}
+
+impl Default for FrameContext {
+    fn default() -> Self {
+        Self {
+            frame: 0,
+            fps: 24.0,
+        }
+    }
+}
+
+impl FrameContext {
+    pub fn new(frame: i32, fps: f32) -> Self {
+        Self { frame, fps }
+    }
+
+    /// The time of this frame, in seconds.
+    pub fn seconds(&self) -> f32 {
+        self.frame as f32 / self.fps
+    }
+
+    /// Makes this frame available to the Lua code running in `lua`. Must be
+    /// called again before running the graph whenever the frame changes.
+    pub fn install(&self, lua: &mlua::Lua) {
+        lua.set_app_data(*self);
+    }
+
+    /// Returns the frame installed in `lua`, or the default one when the host
+    /// didn't install any.
+    pub fn current(lua: &mlua::Lua) -> Self {
+        lua.app_data_ref::<FrameContext>()
+            .map(|ctx| *ctx)
+            .unwrap_or_default()
+    }
+}
+
+#[blackjack_macros::blackjack_lua_module]
+mod lua_api {
+    use super::*;
+    use mlua::Lua;
+
+    /// Returns the frame the graph is being evaluated at.
+    #[lua(under = "Time")]
+    fn frame(lua: &Lua) -> f32 {
+        FrameContext::current(lua).frame as f32
+    }
+
+    /// Returns the time the graph is being evaluated at, in seconds.
+    #[lua(under = "Time")]
+    fn seconds(lua: &Lua) -> f32 {
+        FrameContext::current(lua).seconds()
+    }
+
+    /// Returns the number of frames per second of the animation.
+    #[lua(under = "Time")]
+    fn fps(lua: &Lua) -> f32 {
+        FrameContext::current(lua).fps
+    }
+}
@@ -260,6 +260,10 @@ impl BlackjackApi {
                         *sel = None;
                     }
                 }
+                blackjack_engine::graph::BlackjackValue::Ramp(_) => {
+                    // TODO: Ramps can't be edited from Godot yet
+                    return None;
+                }
                 blackjack_engine::graph::BlackjackValue::None => {}
             }
             Some(true)
@@ -393,6 +397,7 @@ impl BlackjackApi {
                 jack.params.clone(),
                 &runtime.lua_runtime.node_definitions,
                 None,
+                None,
             ) {
                 Ok(ProgramResult {
                     renderable: Some(RenderableThing::HalfEdgeMesh(mesh)),
@@ -428,7 +433,7 @@ fn halfedge_to_godot_mesh(
 
     let conn = mesh.read_connectivity();
     let positions = mesh.read_positions();
-    let normals = mesh.read_vertex_normals(); // TODO: No face normal support for now
+    let normals = edit_ops::generate_corner_normals_channel(mesh)?;
     let uvs = mesh.read_uvs();
     let materials = mesh
         .channels
@@ -466,10 +471,8 @@ fn halfedge_to_godot_mesh(
             }
 
             // Normal
-            if let Some(normals) = normals.as_ref() {
-                let normal = normals[v_id];
-                gd_normals.push(Vector3::new(normal.x, normal.y, normal.z));
-            }
+            let normal = normals[h_id];
+            gd_normals.push(Vector3::new(normal.x, normal.y, normal.z));
         }
 
         // Indices. Simple fan triangulation using the face vertices.
@@ -500,9 +503,7 @@ fn halfedge_to_godot_mesh(
         if uvs.is_some() {
             arr.set(gd::Mesh::ARRAY_TEX_UV as i32, gd_uvs);
         }
-        if normals.is_some() {
-            arr.set(gd::Mesh::ARRAY_NORMAL as i32, gd_normals);
-        }
+        arr.set(gd::Mesh::ARRAY_NORMAL as i32, gd_normals);
         arr.set(gd::Mesh::ARRAY_INDEX as i32, gd_indices);
 
         mesh.add_surface_from_arrays(
@@ -30,6 +30,30 @@ local primitives = {
         gizmos = { Gz.tweak_point("origin") },
         returns = "out_mesh",
     },
+    MakeRoundedBox = {
+        label = "Rounded Box",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.rounded_box(
+                    inputs.origin,
+                    inputs.size,
+                    inputs.fillet_radius,
+                    inputs.fillet_segments
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("origin", vector(0, 0, 0)),
+            P.v3("size", vector(1, 1, 1)),
+            P.scalar("fillet_radius", { default = 0.1, min = 0.0, soft_max = 0.5 }),
+            P.scalar_int("fillet_segments", { default = 3, min = 1, soft_max = 16 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("origin") },
+        returns = "out_mesh",
+    },
     MakeQuad = {
         label = "Quad",
         op = function(inputs)
@@ -73,6 +97,62 @@ local primitives = {
         gizmos = { Gz.tweak_point("center") },
         returns = "out_mesh",
     },
+    MakeArc = {
+        label = "Arc",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.arc(
+                    inputs.center,
+                    inputs.radius,
+                    inputs.start_angle,
+                    inputs.end_angle,
+                    inputs.num_vertices,
+                    inputs.fill == "Slice"
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("center", vector(0, 0, 0)),
+            P.scalar("radius", { default = 1.0, min = 0.0 }),
+            P.scalar("start_angle", { default = 0.0, soft_min = -math.pi, soft_max = math.pi }),
+            P.scalar("end_angle", { default = math.pi, soft_min = -math.pi, soft_max = 2 * math.pi }),
+            P.scalar_int("num_vertices", { default = 9, min = 2, soft_max = 64 }),
+            P.enum("fill", { "None", "Slice" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("center") },
+        returns = "out_mesh",
+    },
+    MakeNGon = {
+        label = "N-Gon",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.ngon(
+                    inputs.center,
+                    inputs.num_sides,
+                    inputs.outer_radius,
+                    inputs.inner_radius,
+                    inputs.twist,
+                    inputs.shape
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("center", vector(0, 0, 0)),
+            P.scalar_int("num_sides", { default = 6, min = 3, soft_max = 32 }),
+            P.scalar("outer_radius", { default = 1.0, min = 0.0 }),
+            P.scalar("inner_radius", { default = 0.5, min = 0.0 }),
+            P.scalar("twist", { default = 0.0, soft_min = -3.1416, soft_max = 3.1416 }),
+            P.enum("shape", { "Disc", "Ring", "Star" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("center") },
+        returns = "out_mesh",
+    },
     MakeUVSphere = {
         label = "UV Sphere",
         op = function(inputs)
@@ -97,6 +177,86 @@ local primitives = {
         gizmos = { Gz.tweak_point("center") },
         returns = "out_mesh",
     },
+    MakeSuperellipsoid = {
+        label = "Superellipsoid",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.superellipsoid(
+                    inputs.center,
+                    inputs.size,
+                    inputs.e1,
+                    inputs.e2,
+                    inputs.segments,
+                    inputs.rings
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("center", vector(0, 0, 0)),
+            P.v3("size", vector(1, 1, 1)),
+            P.scalar("e1", { default = 0.5, min = 0.01, soft_max = 4.0 }),
+            P.scalar("e2", { default = 0.5, min = 0.01, soft_max = 4.0 }),
+            P.scalar_int("segments", { default = 24, min = 3, soft_max = 64 }),
+            P.scalar_int("rings", { default = 12, min = 2, soft_max = 64 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("center") },
+        returns = "out_mesh",
+    },
+    MakeCapsule = {
+        label = "Capsule",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.capsule(
+                    inputs.center,
+                    inputs.radius,
+                    inputs.height,
+                    inputs.segments,
+                    inputs.rings
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("center", vector(0, 0, 0)),
+            P.scalar("radius", { default = 0.5, min = 0.0 }),
+            P.scalar("height", { default = 2.0, min = 0.0 }),
+            P.scalar_int("segments", { default = 16, min = 3, soft_max = 64 }),
+            P.scalar_int("rings", { default = 6, min = 1, soft_max = 32 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("center") },
+        returns = "out_mesh",
+    },
+    MakeTorus = {
+        label = "Torus",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.torus(
+                    inputs.center,
+                    inputs.ring_radius,
+                    inputs.tube_radius,
+                    inputs.ring_segments,
+                    inputs.tube_segments
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("center", vector(0, 0, 0)),
+            P.scalar("ring_radius", { default = 1.0, min = 0.0 }),
+            P.scalar("tube_radius", { default = 0.25, min = 0.0 }),
+            P.scalar_int("ring_segments", { default = 24, min = 3, soft_max = 64 }),
+            P.scalar_int("tube_segments", { default = 12, min = 3, soft_max = 64 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("center") },
+        returns = "out_mesh",
+    },
     MakeLine = {
         label = "Line",
         op = function(inputs)
@@ -133,6 +293,56 @@ local primitives = {
         },
         returns = "out_heightmap",
     },
+    MakeText = {
+        label = "Text",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.text(inputs.font, inputs.text, inputs.size, inputs.extrude),
+            }
+        end,
+        inputs = {
+            P.file("font", "open", { "ttf", "otf" }),
+            P.strparam("text", "Text"),
+            P.scalar("size", { default = 1.0, min = 0.0, soft_max = 10.0 }),
+            P.scalar("extrude", { default = 0.2, min = 0.0, soft_max = 2.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
+    MakeNoiseTerrain = {
+        label = "Noise Terrain",
+        op = function(inputs)
+            local params = {
+                kind = inputs.kind,
+                seed = inputs.seed,
+                frequency = inputs.frequency,
+                amplitude = inputs.amplitude,
+                octaves = inputs.octaves,
+                lacunarity = inputs.lacunarity,
+                persistence = inputs.persistence,
+            }
+            return {
+                out_mesh = Primitives.terrain(inputs.size, inputs.resolution, params),
+            }
+        end,
+        inputs = {
+            P.v3("size", vector(10, 10, 0)),
+            P.scalar_int("resolution", { default = 32, min = 1, soft_max = 256 }),
+            P.enum("kind", { "Perlin", "Simplex" }, 0),
+            P.scalar_int("seed", { default = 0, min = 0, soft_max = 100 }),
+            P.scalar("frequency", { default = 0.2, min = 0.0, soft_max = 2.0 }),
+            P.scalar("amplitude", { default = 1.0, soft_min = 0.0, soft_max = 10.0 }),
+            P.scalar_int("octaves", { default = 4, min = 1, soft_max = 8 }),
+            P.scalar("lacunarity", { default = 2.0, min = 1.0, soft_max = 4.0 }),
+            P.scalar("persistence", { default = 0.5, min = 0.0, max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
     MakeCode = {
         label = "Lua String",
         op = function(inputs)
@@ -193,6 +403,62 @@ local primitives = {
         },
         returns = "out_mesh",
     },
+    MakeCurve = {
+        label = "Curve",
+        op = function(inputs)
+            local points = {}
+            -- Parse the point list, separated by space
+            for point in inputs.control_points:gmatch("([^ \n]+)") do
+                table.insert(points, V.from_string(point))
+            end
+            return {
+                out_mesh = Primitives.curve(
+                    points,
+                    inputs.kind,
+                    inputs.resolution,
+                    inputs.ends == "Closed"
+                ),
+            }
+        end,
+        inputs = {
+            P.strparam("control_points", "", true),
+            P.enum("kind", { "CatmullRom", "Bezier" }, 0),
+            P.scalar_int("resolution", { default = 8, min = 1, soft_max = 64 }),
+            P.enum("ends", { "Open", "Closed" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
+    MakeGear = {
+        label = "Gear",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.gear(
+                    inputs.center,
+                    inputs.num_teeth,
+                    inputs.module,
+                    inputs.pressure_angle,
+                    inputs.thickness,
+                    inputs.bore_radius
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("center", vector(0, 0, 0)),
+            P.scalar_int("num_teeth", { default = 12, min = 4, soft_max = 100 }),
+            P.scalar("module", { default = 0.1, min = 0.001, soft_max = 1.0 }),
+            P.scalar("pressure_angle", { default = math.pi / 9, min = 0.01, max = math.pi / 4 }),
+            P.scalar("thickness", { default = 0.2, min = 0.001, soft_max = 1.0 }),
+            P.scalar("bore_radius", { default = 0.1, min = 0.0, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("center") },
+        returns = "out_mesh",
+    },
     MakeCone = {
         label = "Cone",
         op = function(inputs)
@@ -285,6 +551,115 @@ local primitives = {
         gizmos = { Gz.tweak_point("start_point"), Gz.tweak_point("end_point") },
         returns = "out_mesh",
     },
+    MakeTube = {
+        label = "Tube",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.tube(inputs.path, inputs.radius, inputs.sides, inputs.caps == "Capped"),
+            }
+        end,
+        inputs = {
+            P.mesh("path"),
+            P.scalar("radius", { default = 0.1, min = 0.0, soft_max = 1.0 }),
+            P.scalar_int("sides", { default = 8, min = 3, soft_max = 32 }),
+            P.enum("caps", { "Capped", "Open" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
+    MakeStairs = {
+        label = "Stairs",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.stairs(
+                    inputs.start_point,
+                    inputs.end_point,
+                    inputs.step_height,
+                    inputs.step_depth,
+                    inputs.width
+                ),
+                railing = Primitives.stair_railing(
+                    inputs.start_point,
+                    inputs.end_point,
+                    inputs.step_height,
+                    inputs.step_depth,
+                    inputs.width,
+                    inputs.railing_height,
+                    inputs.rail_radius
+                ),
+            }
+        end,
+        inputs = {
+            P.v3("start_point", vector(0, 0, 0)),
+            P.v3("end_point", vector(0, 2, 3)),
+            P.scalar("step_height", { default = 0.2, min = 0.01, soft_max = 1.0 }),
+            P.scalar("step_depth", { default = 0.3, min = 0.01, soft_max = 1.0 }),
+            P.scalar("width", { default = 1.0, min = 0.01, soft_max = 5.0 }),
+            P.scalar("railing_height", { default = 0.9, min = 0.0, soft_max = 2.0 }),
+            P.scalar("rail_radius", { default = 0.03, min = 0.001, soft_max = 0.2 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+            P.mesh("railing"),
+        },
+        gizmos = { Gz.tweak_point("start_point"), Gz.tweak_point("end_point") },
+        returns = "out_mesh",
+    },
+    MakeRock = {
+        label = "Rock",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.rock(inputs.seed, inputs.scale, inputs.detail, inputs.flatten_bottom),
+            }
+        end,
+        inputs = {
+            P.scalar_int("seed", { default = 0, min = 0, soft_max = 100 }),
+            P.v3("scale", vector(1, 0.7, 1)),
+            P.scalar_int("detail", { default = 2, min = 0, max = 6 }),
+            P.scalar("flatten_bottom", { default = 0.3, min = 0.0, max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
+    MakeTree = {
+        label = "Tree",
+        op = function(inputs)
+            local trunk_params = {
+                trunk_height = inputs.trunk_height,
+                trunk_radius = inputs.trunk_radius,
+                branches_per_level = inputs.branches_per_level,
+                branch_angle = inputs.branch_angle,
+                length_ratio = inputs.length_ratio,
+                radius_ratio = inputs.radius_ratio,
+                gnarl = inputs.gnarl,
+                segments = inputs.segments,
+            }
+            return {
+                out_mesh = Ops.tree(inputs.seed, trunk_params, inputs.branch_levels, inputs.leaf_mesh),
+            }
+        end,
+        inputs = {
+            P.scalar_int("seed", { default = 0, min = 0, soft_max = 100 }),
+            P.scalar("trunk_height", { default = 3.0, min = 0.01, soft_max = 10.0 }),
+            P.scalar("trunk_radius", { default = 0.15, min = 0.001, soft_max = 1.0 }),
+            P.scalar_int("branch_levels", { default = 2, min = 0, max = 5 }),
+            P.scalar_int("branches_per_level", { default = 4, min = 0, soft_max = 8 }),
+            P.scalar("branch_angle", { default = 0.7, min = 0.0, soft_max = math.pi }),
+            P.scalar("length_ratio", { default = 0.6, min = 0.0, soft_max = 1.0 }),
+            P.scalar("radius_ratio", { default = 0.5, min = 0.0, max = 1.0 }),
+            P.scalar("gnarl", { default = 0.15, min = 0.0, soft_max = 1.0 }),
+            P.scalar_int("segments", { default = 8, min = 3, soft_max = 16 }),
+            P.mesh("leaf_mesh"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
     MakeIcosahedron = {
         label = "Icosahedron",
         op = function(inputs)
@@ -310,24 +685,45 @@ local edit_ops = {
         label = "Bevel Edges",
         inputs = {
             P.mesh("in_mesh"),
-            P.selection("edges"),
+            P.selection("edges", "edge"),
             P.scalar("amount", { default = 0.0, min = 0.0, soft_max = 1.0 }),
+            P.scalar_int("segments", { default = 1, min = 1, soft_max = 8 }),
+            P.enum("profile_shape", { "Round", "Custom" }, 0),
+            P.scalar("profile", { default = 0.7, min = 0.0, max = 1.0 }),
+            P.ramp("custom_profile"),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.in_mesh:clone()
-            Ops.bevel(inputs.edges, inputs.amount, out_mesh)
-            return { out_mesh = out_mesh }
+            if inputs.profile_shape == "Custom" then
+                return {
+                    out_mesh = Ops.bevel_edges_with_profile(
+                        inputs.in_mesh,
+                        inputs.edges,
+                        inputs.amount,
+                        inputs.segments,
+                        inputs.custom_profile
+                    ),
+                }
+            end
+            return {
+                out_mesh = Ops.bevel_edges(
+                    inputs.in_mesh,
+                    inputs.edges,
+                    inputs.amount,
+                    inputs.segments,
+                    inputs.profile
+                ),
+            }
         end,
     },
     ChamferVertices = {
         label = "Chamfer Vertices",
         inputs = {
             P.mesh("in_mesh"),
-            P.selection("vertices"),
+            P.selection("vertices", "vertex"),
             P.scalar("amount", { default = 0.0, min = 0.0, soft_max = 1.0 }),
         },
         outputs = {
@@ -340,158 +736,1320 @@ local edit_ops = {
             return { out_mesh = out_mesh }
         end,
     },
-    ExtrudeFaces = {
-        label = "Extrude Faces",
+    BevelVertices = {
+        label = "Bevel Vertices",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("vertices", "vertex"),
+            P.scalar("amount", { default = 0.1, min = 0.0, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.bevel_vertices(inputs.in_mesh, inputs.vertices, inputs.amount),
+            }
+        end,
+    },
+    FillHoles = {
+        label = "Fill Holes",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.scalar_int("max_hole_edges", { default = 0, min = 0, soft_max = 64 }),
+            P.enum("caps", { "N-gon", "Triangles" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.fill_holes(
+                    inputs.in_mesh,
+                    inputs.max_hole_edges,
+                    inputs.caps == "Triangles"
+                ),
+            }
+        end,
+    },
+    MergeByDistance = {
+        label = "Merge by Distance",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("vertices", "vertex"),
+            P.scalar("distance", { default = 0.001, min = 0.0, soft_max = 0.1 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.merge_by_distance(inputs.in_mesh, inputs.distance, inputs.vertices),
+            }
+        end,
+    },
+    ExtrudeFaces = {
+        label = "Extrude Faces",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("faces", "face"),
+            P.scalar("amount", { default = 0.0 }),
+            P.scalar_int("segments", { default = 1, min = 1, soft_max = 16 }),
+            P.scalar("profile_width", { default = 0.0, soft_min = -1.0, soft_max = 1.0 }),
+            P.ramp("profile"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            if inputs.segments == 1 and inputs.profile_width == 0 then
+                local out_mesh = inputs.in_mesh:clone()
+                Ops.extrude(inputs.faces, inputs.amount, out_mesh)
+                return { out_mesh = out_mesh }
+            end
+            return {
+                out_mesh = Ops.extrude_with_profile(
+                    inputs.in_mesh,
+                    inputs.faces,
+                    inputs.amount,
+                    inputs.segments,
+                    inputs.profile_width,
+                    inputs.profile
+                ),
+            }
+        end,
+    },
+    InsetFaces = {
+        label = "Inset Faces",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("faces", "face"),
+            P.scalar("thickness", { default = 0.1, min = 0.0, soft_max = 1.0 }),
+            P.scalar("depth", { default = 0.0, soft_min = -1.0, soft_max = 1.0 }),
+            P.enum("mode", { "Region", "Individual" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.inset_faces(
+                    inputs.in_mesh,
+                    inputs.faces,
+                    inputs.thickness,
+                    inputs.depth,
+                    inputs.mode == "Individual"
+                ),
+            }
+        end,
+    },
+    CollapseEdge = {
+        label = "Collapse Edges",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("edges", "edge"),
+            P.scalar("interp", { default = 0.5, soft_min = 0.0, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.collapse_edge(out_mesh, inputs.edges, inputs.interp)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    ExtrudeFootprint = {
+        label = "Extrude Footprint",
+        inputs = {
+            P.mesh("footprint"),
+            P.scalar_int("floors", { default = 3, min = 1, soft_max = 20 }),
+            P.scalar("floor_height", { default = 3.0, min = 0.0, soft_max = 10.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.extrude_footprint(inputs.footprint, inputs.floors, inputs.floor_height),
+            }
+        end,
+    },
+    InsertWindowGrid = {
+        label = "Insert Window Grid",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("faces", "face"),
+            P.scalar_int("cols", { default = 3, min = 1, soft_max = 10 }),
+            P.scalar_int("rows", { default = 1, min = 1, soft_max = 10 }),
+            P.scalar("inset", { default = 0.2, min = 0.0, max = 0.49 }),
+            P.scalar("depth", { default = 0.1, soft_min = 0.0, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.insert_window_grid(
+                    inputs.in_mesh,
+                    inputs.faces,
+                    inputs.cols,
+                    inputs.rows,
+                    inputs.inset,
+                    inputs.depth
+                ),
+            }
+        end,
+    },
+    Pipes = {
+        label = "Pipes",
+        inputs = {
+            P.mesh("network"),
+            P.scalar("radius", { default = 0.1, min = 0.0, soft_max = 1.0 }),
+            P.scalar_int("segments", { default = 12, min = 3, soft_max = 32 }),
+            P.enum("join_style", { "None", "Sphere", "Elbow" }, 1),
+            P.enum("ends", { "None", "Flanges" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.pipes(
+                    inputs.network,
+                    inputs.radius,
+                    inputs.segments,
+                    inputs.join_style,
+                    inputs.ends == "Flanges"
+                ),
+            }
+        end,
+    },
+    ScatterPoints = {
+        label = "Scatter Points",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar_int("count", { default = 100, min = 0, soft_max = 1000 }),
+            P.enum("density_mode", { "Uniform", "Channel", "Texture" }, 0),
+            P.strparam("density_channel", "density"),
+            P.file("density_texture", "open", { "png" }),
+            P.scalar_int("seed", { default = 0, min = 0, soft_max = 100 }),
+            P.scalar("min_distance", { default = 0.0, min = 0.0, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local density_source = inputs.density_channel
+            if inputs.density_mode == "Texture" then
+                density_source = inputs.density_texture
+            end
+            return {
+                out_mesh = Ops.scatter_points(
+                    inputs.mesh,
+                    inputs.count,
+                    inputs.density_mode,
+                    density_source,
+                    inputs.seed,
+                    inputs.min_distance
+                ),
+            }
+        end,
+    },
+    Displace = {
+        label = "Displace",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("amount", { default = 0.1, soft_min = -1.0, soft_max = 1.0 }),
+            P.enum("source", { "Noise", "Texture" }, 0),
+            P.scalar_int("seed", { default = 0, min = 0, soft_max = 100 }),
+            P.scalar("scale", { default = 1.0, min = 0.001, soft_max = 10.0 }),
+            P.scalar_int("octaves", { default = 4, min = 1, soft_max = 8 }),
+            P.file("texture", "open", { "png" }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local source = inputs.texture
+            if inputs.source == "Noise" then
+                source = {
+                    seed = inputs.seed,
+                    frequency = 1.0 / inputs.scale,
+                    octaves = inputs.octaves,
+                }
+            end
+            return {
+                out_mesh = Ops.displace(inputs.mesh, inputs.amount, source),
+            }
+        end,
+    },
+    Chain = {
+        label = "Chain",
+        inputs = {
+            P.mesh("curve"),
+            P.mesh("link_mesh"),
+            P.scalar("link_length", { default = 0.2, min = 0.001, soft_max = 1.0 }),
+            P.scalar("twist_per_link", { default = 0.0, soft_min = -math.pi, soft_max = math.pi }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.chain(inputs.curve, inputs.link_mesh, inputs.link_length, inputs.twist_per_link),
+            }
+        end,
+    },
+    QuantizeEdges = {
+        label = "Quantize Edges",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.scalar("length", { default = 0.5, min = 0.001, soft_max = 2.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.quantize_edges(out_mesh, inputs.length)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    BridgeLoops = {
+        label = "Bridge Loops",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("loop_1", "edge"),
+            P.selection("loop_2", "edge"),
+            P.scalar_int("flip", { default = 0.0, min = 0.0, soft_max = 4.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.bridge_chains(out_mesh, inputs.loop_1, inputs.loop_2, inputs.flip)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    MakeQuadFace = {
+        label = "Make Quad",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("a", "vertex"),
+            P.selection("b", "vertex"),
+            P.selection("c", "vertex"),
+            P.selection("d", "vertex"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.make_quad(out_mesh, inputs.a, inputs.b, inputs.c, inputs.d)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    MergeMeshes = {
+        label = "Merge Meshes",
+        inputs = {
+            P.mesh("mesh_a"),
+            P.mesh("mesh_b"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.mesh_a:clone()
+            Ops.merge(out_mesh, inputs.mesh_b)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    Boolean = {
+        label = "Boolean",
+        inputs = {
+            P.mesh("mesh_a"),
+            P.mesh("mesh_b"),
+            P.enum("mode", { "Union", "Difference", "Intersection" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return { out_mesh = Ops.boolean(inputs.mesh_a, inputs.mesh_b, inputs.mode) }
+        end,
+    },
+    FilletSeams = {
+        label = "Fillet Seams",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("radius", { default = 0.05, min = 0.0, soft_max = 0.5 }),
+            P.scalar_int("segments", { default = 3, min = 1, soft_max = 8 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return { out_mesh = Ops.fillet_seams(inputs.mesh, inputs.radius, inputs.segments) }
+        end,
+    },
+    VoxelRemesh = {
+        label = "Voxel Remesh",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("voxel_size", { default = 0.05, min = 0.001, soft_max = 0.5 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return { out_mesh = Ops.voxel_remesh(inputs.mesh, inputs.voxel_size) }
+        end,
+    },
+    Hollow = {
+        label = "Hollow",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("wall_thickness", { default = 0.2, min = 0.001, soft_max = 1.0 }),
+            P.scalar("voxel_size", { default = 0.05, min = 0.001, soft_max = 0.5 }),
+            P.enum("drain_holes", { "None", "One", "Two" }, 1),
+            P.scalar("drain_hole_radius", { default = 0.1, min = 0.001, soft_max = 1.0 }),
+            P.v3("drain_1", vector(0, 0, 0)),
+            P.v3("drain_2", vector(0, 0, 0)),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local drains = {}
+            if inputs.drain_holes ~= "None" then
+                table.insert(drains, inputs.drain_1)
+            end
+            if inputs.drain_holes == "Two" then
+                table.insert(drains, inputs.drain_2)
+            end
+            return {
+                out_mesh = Ops.hollow(
+                    inputs.mesh,
+                    inputs.wall_thickness,
+                    inputs.voxel_size,
+                    inputs.drain_hole_radius,
+                    drains
+                ),
+            }
+        end,
+    },
+    InfillLattice = {
+        label = "Infill Lattice",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("cell_type", { "Gyroid", "Grid", "Honeycomb" }, 0),
+            P.scalar("cell_size", { default = 0.5, min = 0.01, soft_max = 2.0 }),
+            P.scalar("strut_radius", { default = 0.05, min = 0.001, soft_max = 0.5 }),
+            P.scalar("voxel_size", { default = 0.05, min = 0.001, soft_max = 0.5 }),
+            P.enum("output", { "Separate", "Union" }, 0),
+            P.scalar("shell_thickness", { default = 0.1, min = 0.001, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            -- A separate lattice has no shell, it can be merged with a
+            -- hollowed copy of the mesh later on.
+            local shell_thickness = 0
+            if inputs.output == "Union" then
+                shell_thickness = inputs.shell_thickness
+            end
+            return {
+                out_mesh = Ops.infill_lattice(
+                    inputs.mesh,
+                    inputs.cell_type,
+                    inputs.cell_size,
+                    inputs.strut_radius,
+                    inputs.voxel_size,
+                    shell_thickness
+                ),
+            }
+        end,
+    },
+    Thread = {
+        label = "Thread",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("kind", { "External", "Internal" }, 0),
+            P.v3("center", vector(0, 0, 0)),
+            P.scalar("diameter", { default = 0.6, min = 0.001, soft_max = 5.0 }),
+            P.scalar("pitch", { default = 0.1, min = 0.001, soft_max = 1.0 }),
+            P.scalar("length", { default = 1.0, min = 0.001, soft_max = 10.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        gizmos = { Gz.tweak_point("center") },
+        returns = "out_mesh",
+        op = function(inputs)
+            -- Without a mesh, the threaded rod itself is the output
+            if inputs.mesh == nil then
+                if inputs.kind == "Internal" then
+                    error("Internal threads need a mesh to be cut into")
+                end
+                return {
+                    out_mesh = Primitives.thread(
+                        inputs.center,
+                        inputs.diameter,
+                        inputs.pitch,
+                        inputs.length,
+                        32
+                    ),
+                }
+            end
+            return {
+                out_mesh = Ops.thread(
+                    inputs.mesh,
+                    inputs.center,
+                    inputs.diameter,
+                    inputs.pitch,
+                    inputs.length,
+                    inputs.kind == "Internal"
+                ),
+            }
+        end,
+    },
+    Engrave = {
+        label = "Engrave",
+        inputs = {
+            P.mesh("mesh"),
+            P.mesh("shape"),
+            P.enum("mode", { "Engrave", "Emboss" }, 0),
+            P.v3("direction", vector(0, 0, -1)),
+            P.scalar("depth", { default = 0.05, min = 0.001, soft_max = 0.5 }),
+            P.scalar("width", { default = 0.05, min = 0.001, soft_max = 0.5 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.engrave(
+                    inputs.mesh,
+                    inputs.shape,
+                    inputs.direction,
+                    inputs.depth,
+                    inputs.width,
+                    inputs.mode == "Emboss"
+                ),
+            }
+        end,
+    },
+    Decimate = {
+        label = "Decimate",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("mode", { "Ratio", "Error" }, 0),
+            P.scalar("ratio", { default = 0.5, min = 0.0, max = 1.0 }),
+            P.scalar("max_error", { default = 0.01, min = 0.0, soft_max = 0.5 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            if inputs.mode == "Error" then
+                return { out_mesh = Ops.decimate_to_error(inputs.mesh, inputs.max_error) }
+            else
+                return { out_mesh = Ops.decimate(inputs.mesh, inputs.ratio) }
+            end
+        end,
+    },
+    GenerateLods = {
+        label = "Generate LODs",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("ratio_1", { default = 0.5, min = 0.0, max = 1.0 }),
+            P.scalar("ratio_2", { default = 0.25, min = 0.0, max = 1.0 }),
+            P.scalar("ratio_3", { default = 0.125, min = 0.0, max = 1.0 }),
+            P.enum("preview", { "LOD 0", "LOD 1", "LOD 2", "LOD 3" }, 1),
+        },
+        outputs = {
+            P.mesh("lod_1"),
+            P.mesh("lod_2"),
+            P.mesh("lod_3"),
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local ratios = { inputs.ratio_1, inputs.ratio_2, inputs.ratio_3 }
+            local lods = Ops.generate_lods(inputs.mesh, ratios)
+            local levels = { inputs.mesh, lods[1], lods[2], lods[3] }
+            local preview = levels[tonumber(inputs.preview:sub(-1)) + 1]
+            return {
+                lod_1 = lods[1],
+                lod_2 = lods[2],
+                lod_3 = lods[3],
+                out_mesh = preview:clone(),
+            }
+        end,
+    },
+    ConvexDecomposition = {
+        label = "Convex Decomposition",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar_int("max_hulls", { default = 8, min = 1, soft_max = 32 }),
+            P.scalar_int("resolution", { default = 32, min = 4, max = 128 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            -- The hulls are merged in a single mesh, but they can still be
+            -- told apart by the `hull` face channel.
+            local hulls = Ops.convex_decompose(inputs.mesh, inputs.max_hulls, inputs.resolution)
+            local out_mesh = hulls[1]
+            for i = 2, #hulls do
+                Ops.merge(out_mesh, hulls[i])
+            end
+            return { out_mesh = out_mesh }
+        end,
+    },
+    Smooth = {
+        label = "Smooth",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("method", { "Laplacian", "Taubin" }, 1),
+            P.scalar_int("iterations", { default = 5, min = 0, soft_max = 50 }),
+            P.scalar("factor", { default = 0.5, min = 0.0, max = 1.0 }),
+            P.enum("boundaries", { "Smooth", "Pin" }, 1),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.smooth(
+                    inputs.mesh,
+                    inputs.iterations,
+                    inputs.factor,
+                    inputs.method,
+                    inputs.boundaries == "Pin"
+                ),
+            }
+        end,
+    },
+    Subdivide = {
+        label = "Subdivide",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("technique", { "linear", "catmull-clark" }, 0),
+            P.scalar_int("iterations", { default = 1, min = 0, soft_max = 7 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            if inputs.iterations < 1 then
+                return { out_mesh = inputs.mesh:clone() }
+            else
+                return {
+                    out_mesh = Ops.subdivide(
+                        inputs.mesh,
+                        inputs.iterations,
+                        inputs.technique == "catmull-clark"
+                    ),
+                }
+            end
+        end,
+    },
+    SubdivideSmooth = {
+        label = "Subdivide smooth",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar_int("iterations", { default = 1, min = 0, soft_max = 7 }),
+            P.enum("boundaries", { "Smooth", "Preserve" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.subdivide_smooth(
+                    inputs.mesh,
+                    inputs.iterations,
+                    inputs.boundaries == "Preserve"
+                ),
+            }
+        end,
+    },
+    -- A generator node: Each subdivision level is shown as soon as it's ready,
+    -- and cancelling stops between levels.
+    SubdivideProgressive = {
+        label = "Subdivide progressive",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar_int("iterations", { default = 3, min = 0, soft_max = 7 }),
+            P.enum("boundaries", { "Smooth", "Preserve" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        generator = true,
+        op = function(inputs)
+            local mesh = inputs.mesh:clone()
+            for i = 1, inputs.iterations do
+                Progress.report("Subdivide", (i - 1) / inputs.iterations)
+                mesh = Ops.subdivide_smooth(mesh, 1, inputs.boundaries == "Preserve")
+                if i < inputs.iterations then
+                    coroutine.yield({ out_mesh = mesh })
+                end
+            end
+            Progress.report("Subdivide", 1.0)
+            return { out_mesh = mesh }
+        end,
+    },
+    Triangulate = {
+        label = "Triangulate",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("mode", { "Auto", "Fan", "EarClip" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.triangulate(inputs.mesh, inputs.mode),
+            }
+        end,
+    },
+    TrisToQuads = {
+        label = "Tris to quads",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("angle_threshold", { default = math.rad(40), min = 0, soft_max = math.pi }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.tris_to_quads(inputs.mesh, inputs.angle_threshold),
+            }
+        end,
+    },
+    Dissolve = {
+        label = "Dissolve",
+        inputs = {
+            P.mesh("mesh"),
+            P.selection("selection"),
+            P.enum("kind", { "Edges", "Vertices" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.dissolve(inputs.mesh, inputs.selection, inputs.kind),
+            }
+        end,
+    },
+    LimitedDissolve = {
+        label = "Limited dissolve",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("angle_threshold", { default = math.rad(5), min = 0, soft_max = math.pi }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.limited_dissolve(inputs.mesh, inputs.angle_threshold),
+            }
+        end,
+    },
+    SubdivideEdge = {
+        label = "Divide Edges",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("edges", "edge"),
+            P.scalar("interp", { default = 0.5, soft_min = 0.0, soft_max = 1.0 }),
+            P.scalar_int("divisions", { default = 1, min = 1, soft_max = 32 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.divide_edges(out_mesh, inputs.edges, inputs.interp, inputs.divisions)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    CutFace = {
+        label = "Cut Face",
+        inputs = {
+            P.mesh("in_mesh"),
+            P.selection("a", "vertex"),
+            P.selection("b", "vertex"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.cut_face(out_mesh, inputs.a, inputs.b)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    SetNormals = {
+        label = "Set Normals",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("normals", { "smooth", "flat", "auto_smooth", "mixed" }, 0),
+            P.scalar("angle", { default = math.pi / 6, min = 0, soft_max = math.pi }),
+            P.selection("smooth_faces", "face"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.mesh:clone()
+            if inputs.normals == "mixed" then
+                Ops.set_smooth_faces(out_mesh, inputs.smooth_faces)
+            end
+            out_mesh:set_shading(inputs.normals, inputs.angle)
+            return { out_mesh = out_mesh }
+        end,
+    },
+    EdgeSplit = {
+        label = "Edge split",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("angle_threshold", { default = math.rad(30), min = 0, soft_max = math.pi }),
+            P.selection("edges", "edge"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.edge_split(inputs.mesh, inputs.angle_threshold, inputs.edges),
+            }
+        end,
+    },
+    Transform = {
+        label = "Transform",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("translate", vector(0, 0, 0)),
+            P.v3("rotate", vector(0, 0, 0)),
+            P.v3("scale", vector(1, 1, 1)),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.mesh:clone()
+            Ops.transform(out_mesh, inputs.translate, inputs.rotate, inputs.scale)
+            return {
+                out_mesh = out_mesh,
+            }
+        end,
+        gizmos = { Gz.tweak_transform("translate", "rotate", "scale") },
+    },
+    AssignIslandIds = {
+        label = "Assign island ids",
+        inputs = {
+            P.mesh("mesh"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return { out_mesh = Ops.assign_island_ids(inputs.mesh) }
+        end,
+    },
+    TransformPerIsland = {
+        label = "Transform per island",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("translate", vector(0, 0, 0)),
+            P.v3("rotate", vector(0, 0, 0)),
+            P.v3("scale", vector(1, 1, 1)),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.transform_per_island(inputs.mesh, function(_island, _centroid)
+                    return inputs.translate, inputs.rotate, inputs.scale
+                end),
+            }
+        end,
+    },
+    Explode = {
+        label = "Explode",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("origin", vector(0, 0, 0)),
+            P.scalar("distance", { default = 1.0, soft_min = 0.0, soft_max = 10.0 }),
+            P.scalar("falloff", { default = 1.0, min = 0.0, soft_max = 4.0 }),
+            -- Connect the time to this input to animate the explosion
+            P.scalar("amount", { default = 1.0, soft_min = 0.0, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+            P.mesh("offsets"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local explosion = Ops.explode(
+                inputs.mesh,
+                inputs.origin,
+                inputs.distance,
+                inputs.falloff,
+                inputs.amount
+            )
+            return { out_mesh = explosion.mesh, offsets = explosion.offsets }
+        end,
+        gizmos = { Gz.tweak_point("origin") },
+    },
+    ScaleIslands = {
+        label = "Scale islands",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("factor", { default = 0.9, min = 0.0, soft_max = 2.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return { out_mesh = Ops.scale_islands_from_centroid(inputs.mesh, inputs.factor) }
+        end,
+    },
+    AddAnchor = {
+        label = "Add anchor",
+        inputs = {
+            P.mesh("mesh"),
+            P.strparam("name", "socket"),
+            P.v3("position", vector(0, 0, 0)),
+            P.v3("rotation", vector(0, 0, 0)),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.add_anchor(inputs.mesh, inputs.name, inputs.position, inputs.rotation),
+            }
+        end,
+        gizmos = { Gz.tweak_point("position") },
+    },
+    Attach = {
+        label = "Attach",
+        inputs = {
+            P.mesh("mesh_a"),
+            P.strparam("anchor_a", "socket"),
+            P.mesh("mesh_b"),
+            P.strparam("anchor_b", "socket"),
+            P.scalar("weld_distance", { default = 0.0, min = 0.0, soft_max = 0.1 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            -- A zero distance means the meshes are merged without welding
+            local weld_distance = nil
+            if inputs.weld_distance > 0 then
+                weld_distance = inputs.weld_distance
+            end
+            return {
+                out_mesh = Ops.attach(
+                    inputs.mesh_a,
+                    inputs.anchor_a,
+                    inputs.mesh_b,
+                    inputs.anchor_b,
+                    weld_distance
+                ),
+            }
+        end,
+    },
+    Twist = {
+        label = "Twist",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("axis", vector(0, 1, 0)),
+            P.scalar("angle", { default = 1.0, soft_min = -6.28, soft_max = 6.28 }),
+            P.scalar("falloff", { default = 1.0, min = 0.01, soft_max = 4.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.twist(inputs.mesh, inputs.axis, inputs.angle, inputs.falloff),
+            }
+        end,
+    },
+    Bend = {
+        label = "Bend",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("axis", vector(0, 1, 0)),
+            P.scalar("angle", { default = 1.57, soft_min = -6.28, soft_max = 6.28 }),
+            P.scalar("limit_min", { default = 0.0, min = 0.0, max = 1.0 }),
+            P.scalar("limit_max", { default = 1.0, min = 0.0, max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.bend(
+                    inputs.mesh,
+                    inputs.axis,
+                    inputs.angle,
+                    vector(inputs.limit_min, inputs.limit_max, 0)
+                ),
+            }
+        end,
+    },
+    Taper = {
+        label = "Taper",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("axis", vector(0, 1, 0)),
+            P.scalar("factor", { default = -0.5, min = -1.0, soft_max = 2.0 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.taper(inputs.mesh, inputs.axis, inputs.factor),
+            }
+        end,
+    },
+    LatticeCage = {
+        label = "Lattice cage",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("resolution", vector(3, 3, 3)),
+            P.scalar("padding", { default = 0.1, min = 0.0, soft_max = 1.0 }),
+        },
+        outputs = {
+            P.mesh("out_lattice"),
+        },
+        returns = "out_lattice",
+        op = function(inputs)
+            return {
+                out_lattice = Ops.lattice_cage(inputs.mesh, inputs.resolution, inputs.padding),
+            }
+        end,
+    },
+    LatticeDeform = {
+        label = "Lattice deform",
+        inputs = {
+            P.mesh("mesh"),
+            P.mesh("lattice"),
+            P.enum("interpolation", { "Trilinear", "Bezier" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.lattice_deform(inputs.mesh, inputs.lattice, inputs.interpolation),
+            }
+        end,
+    },
+    Shrinkwrap = {
+        label = "Shrinkwrap",
+        inputs = {
+            P.mesh("mesh"),
+            P.mesh("target"),
+            P.enum("mode", { "Nearest", "Project" }, 0),
+            P.scalar("offset", { default = 0.0, soft_min = -0.1, soft_max = 0.1 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.shrinkwrap(inputs.mesh, inputs.target, inputs.mode, inputs.offset),
+            }
+        end,
+    },
+    Mirror = {
+        label = "Mirror",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("plane_origin", vector(0, 0, 0)),
+            P.v3("plane_normal", vector(1, 0, 0)),
+            P.scalar("merge_distance", { default = 0.001, min = 0.0, soft_max = 0.1 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.mirror(
+                    inputs.mesh,
+                    inputs.plane_origin,
+                    inputs.plane_normal,
+                    inputs.merge_distance
+                ),
+            }
+        end,
+        gizmos = { Gz.tweak_point("plane_origin") },
+    },
+    Bisect = {
+        label = "Bisect",
+        inputs = {
+            P.mesh("mesh"),
+            P.v3("plane_origin", vector(0, 0, 0)),
+            P.v3("plane_normal", vector(0, 1, 0)),
+            P.enum("clear_side", { "None", "Positive", "Negative" }, 0),
+            P.enum("cap", { "None", "Fill" }, 1),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.bisect(
+                    inputs.mesh,
+                    inputs.plane_origin,
+                    inputs.plane_normal,
+                    inputs.clear_side,
+                    inputs.cap == "Fill"
+                ),
+            }
+        end,
+        gizmos = { Gz.tweak_point("plane_origin") },
+    },
+    Wireframe = {
+        label = "Wireframe",
+        inputs = {
+            P.mesh("mesh"),
+            P.scalar("thickness", { default = 0.05, min = 0.0, soft_max = 0.5 }),
+            P.enum("mode", { "Frame", "Flat" }, 0),
+            P.enum("original", { "Replace", "Keep" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.wireframe(
+                    inputs.mesh,
+                    inputs.thickness,
+                    inputs.original == "Replace",
+                    inputs.mode
+                ),
+            }
+        end,
+    },
+    Revolve = {
+        label = "Revolve",
         inputs = {
-            P.mesh("in_mesh"),
-            P.selection("faces"),
-            P.scalar("amount", { default = 0.0 }),
+            P.mesh("profile"),
+            P.v3("axis_origin", vector(0, 0, 0)),
+            P.v3("axis_dir", vector(0, 1, 0)),
+            P.scalar("angle", { default = 2 * math.pi, soft_min = 0.0, soft_max = 2 * math.pi }),
+            P.scalar_int("segments", { default = 16, min = 1, soft_max = 64 }),
+            P.enum("caps", { "None", "Fill" }, 1),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.in_mesh:clone()
-            Ops.extrude(inputs.faces, inputs.amount, out_mesh)
-            return { out_mesh = out_mesh }
+            return {
+                out_mesh = Ops.revolve(
+                    inputs.profile,
+                    inputs.axis_origin,
+                    inputs.axis_dir,
+                    inputs.angle,
+                    inputs.segments,
+                    inputs.caps == "Fill"
+                ),
+            }
         end,
     },
-    CollapseEdge = {
-        label = "Collapse Edges",
+    ExtractWalkable = {
+        label = "Extract walkable",
         inputs = {
-            P.mesh("in_mesh"),
-            P.selection("edges"),
-            P.scalar("interp", { default = 0.5, soft_min = 0.0, soft_max = 1.0 }),
+            P.mesh("mesh"),
+            P.scalar("max_slope", { default = math.rad(45), min = 0.0, max = math.pi / 2 }),
+            P.scalar("min_area", { default = 0.5, min = 0.0, soft_max = 10.0 }),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.in_mesh:clone()
-            Ops.collapse_edge(out_mesh, inputs.edges, inputs.interp)
-            return { out_mesh = out_mesh }
+            return {
+                out_mesh = Ops.extract_walkable(inputs.mesh, inputs.max_slope, inputs.min_area),
+            }
         end,
     },
-    BridgeLoops = {
-        label = "Bridge Loops",
+    SliceLayers = {
+        label = "Slice layers",
         inputs = {
-            P.mesh("in_mesh"),
-            P.selection("loop_1"),
-            P.selection("loop_2"),
-            P.scalar_int("flip", { default = 0.0, min = 0.0, soft_max = 4.0 }),
+            P.mesh("mesh"),
+            P.scalar("layer_height", { default = 0.2, min = 0.001, soft_max = 1.0 }),
+            P.v3("axis", vector(0, 1, 0)),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.in_mesh:clone()
-            Ops.bridge_chains(out_mesh, inputs.loop_1, inputs.loop_2, inputs.flip)
-            return { out_mesh = out_mesh }
+            return {
+                out_mesh = Ops.slice_contours(inputs.mesh, inputs.layer_height, inputs.axis),
+            }
         end,
     },
-    MakeQuadFace = {
-        label = "Make Quad",
+    VertexAttribTransfer = {
+        label = "Vertex Attribute Transfer",
         inputs = {
-            P.mesh("in_mesh"),
-            P.selection("a"),
-            P.selection("b"),
-            P.selection("c"),
-            P.selection("d"),
+            P.mesh("src_mesh"),
+            P.mesh("dst_mesh"),
+            P.strparam("channel", "", false),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.in_mesh:clone()
-            Ops.make_quad(out_mesh, inputs.a, inputs.b, inputs.c, inputs.d)
+            local out_mesh = inputs.dst_mesh:clone()
+            Ops.vertex_attribute_transfer(inputs.src_mesh, out_mesh, Types.VEC3, inputs.channel)
             return { out_mesh = out_mesh }
         end,
     },
-    MergeMeshes = {
-        label = "Merge Meshes",
+    SetFullRangeUVs = {
+        label = "Set Full Range UVs",
         inputs = {
-            P.mesh("mesh_a"),
-            P.mesh("mesh_b"),
+            P.mesh("mesh"),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.mesh_a:clone()
-            Ops.merge(out_mesh, inputs.mesh_b)
+            local out_mesh = inputs.mesh:clone()
+            Ops.set_full_range_uvs(out_mesh)
             return { out_mesh = out_mesh }
         end,
     },
-    Subdivide = {
-        label = "Subdivide",
+    SetMaterial = {
+        label = "Set Material",
         inputs = {
             P.mesh("mesh"),
-            P.enum("technique", { "linear", "catmull-clark" }, 0),
-            P.scalar_int("iterations", { default = 1, min = 0, soft_max = 7 }),
+            P.selection("faces", "face"),
+            P.scalar_int("material_index", { default = 0, min = 0 }),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            if inputs.iterations < 1 then
-                return { out_mesh = inputs.mesh:clone() }
-            else
-                return {
-                    out_mesh = Ops.subdivide(
-                        inputs.mesh,
-                        inputs.iterations,
-                        inputs.technique == "catmull-clark"
-                    ),
-                }
-            end
+            local out_mesh = inputs.mesh:clone()
+            Ops.set_material(out_mesh, inputs.faces, inputs.material_index)
+            return { out_mesh = out_mesh }
         end,
     },
-    SubdivideEdge = {
-        label = "Divide Edges",
+    AssignMaterial = {
+        label = "Assign Material",
         inputs = {
-            P.mesh("in_mesh"),
-            P.selection("edges"),
-            P.scalar("interp", { default = 0.5, soft_min = 0.0, soft_max = 1.0 }),
-            P.scalar_int("divisions", { default = 1, min = 1, soft_max = 32 }),
+            P.mesh("mesh"),
+            P.selection("faces", "face"),
+            P.strparam("material", ""),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.in_mesh:clone()
-            Ops.divide_edges(out_mesh, inputs.edges, inputs.interp, inputs.divisions)
+            local out_mesh = inputs.mesh:clone()
+            local material_index = Materials.index_of(inputs.material)
+            Ops.set_material(out_mesh, inputs.faces, material_index)
             return { out_mesh = out_mesh }
         end,
     },
-    CutFace = {
-        label = "Cut Face",
+    ColorByGradient = {
+        label = "Color by gradient",
         inputs = {
-            P.mesh("in_mesh"),
-            P.selection("a"),
-            P.selection("b"),
+            P.mesh("mesh"),
+            P.strparam("source", "y"),
+            P.color_ramp("gradient"),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.in_mesh:clone()
-            Ops.cut_face(out_mesh, inputs.a, inputs.b)
+            local out_mesh = inputs.mesh:clone()
+            Ops.color_by_gradient(out_mesh, inputs.source, inputs.gradient)
             return { out_mesh = out_mesh }
         end,
     },
-    SetNormals = {
-        label = "Set Normals",
+    AnalyzeThickness = {
+        label = "Analyze thickness",
         inputs = {
             P.mesh("mesh"),
-            P.enum("normals", { "smooth", "flat" }, 0),
+            P.scalar("max_distance", { default = 1.0, min = 0.001, soft_max = 10.0 }),
+            P.color_ramp("gradient"),
         },
         outputs = {
             P.mesh("out_mesh"),
@@ -499,82 +2057,90 @@ local edit_ops = {
         returns = "out_mesh",
         op = function(inputs)
             local out_mesh = inputs.mesh:clone()
-            if inputs.normals == "smooth" then
-                Ops.set_smooth_normals(out_mesh)
-            else
-                Ops.set_flat_normals(out_mesh)
-            end
+            Ops.analyze_thickness(out_mesh, inputs.max_distance)
+            Ops.color_by_gradient(out_mesh, "thickness", inputs.gradient)
             return { out_mesh = out_mesh }
         end,
     },
-    Transform = {
-        label = "Transform",
+    AnalyzeOverhangs = {
+        label = "Analyze overhangs",
         inputs = {
             P.mesh("mesh"),
-            P.v3("translate", vector(0, 0, 0)),
-            P.v3("rotate", vector(0, 0, 0)),
-            P.v3("scale", vector(1, 1, 1)),
+            P.v3("build_direction", vector(0, 1, 0)),
+            P.scalar("threshold_angle", { default = math.rad(45), min = 0.0, max = math.pi / 2 }),
+            P.color_ramp("gradient"),
         },
         outputs = {
             P.mesh("out_mesh"),
+            P.scalar("overhang_faces"),
+            P.scalar("overhang_area"),
+            P.scalar("overhang_ratio"),
         },
         returns = "out_mesh",
         op = function(inputs)
             local out_mesh = inputs.mesh:clone()
-            Ops.transform(out_mesh, inputs.translate, inputs.rotate, inputs.scale)
+            local report =
+                Ops.analyze_overhangs(out_mesh, inputs.build_direction, inputs.threshold_angle)
+            Ops.color_by_gradient(out_mesh, "overhang_angle", inputs.gradient)
+            local ratio = 0.0
+            if report.total_area > 0.0 then
+                ratio = report.area / report.total_area
+            end
             return {
                 out_mesh = out_mesh,
+                overhang_faces = report.faces,
+                overhang_area = report.area,
+                overhang_ratio = ratio,
             }
         end,
-        gizmos = { Gz.tweak_transform("translate", "rotate", "scale") },
     },
-    VertexAttribTransfer = {
-        label = "Vertex Attribute Transfer",
+    MeasureDistance = {
+        label = "Measure distance",
         inputs = {
-            P.mesh("src_mesh"),
-            P.mesh("dst_mesh"),
-            P.strparam("channel", "", false),
+            P.mesh("mesh"),
+            P.selection("selection_a", "vertex"),
+            P.selection("selection_b", "vertex"),
         },
         outputs = {
-            P.mesh("out_mesh"),
+            P.scalar("distance"),
         },
-        returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.dst_mesh:clone()
-            Ops.vertex_attribute_transfer(inputs.src_mesh, out_mesh, Types.VEC3, inputs.channel)
-            return { out_mesh = out_mesh }
+            return {
+                distance = Ops.measure_distance(inputs.mesh, inputs.selection_a, inputs.selection_b),
+            }
         end,
     },
-    SetFullRangeUVs = {
-        label = "Set Full Range UVs",
+    MeasureAngle = {
+        label = "Measure angle",
         inputs = {
             P.mesh("mesh"),
+            P.selection("selection_a", "face"),
+            P.selection("selection_b", "face"),
         },
         outputs = {
-            P.mesh("out_mesh"),
+            P.scalar("angle"),
         },
-        returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.mesh:clone()
-            Ops.set_full_range_uvs(out_mesh)
-            return { out_mesh = out_mesh }
+            return {
+                angle = Ops.measure_angle(inputs.mesh, inputs.selection_a, inputs.selection_b),
+            }
         end,
     },
-    SetMaterial = {
-        label = "Set Material",
+    CountElements = {
+        label = "Count elements",
         inputs = {
             P.mesh("mesh"),
-            P.selection("faces"),
-            P.scalar_int("material_index", { default = 0, min = 0 }),
+            P.enum("domain", { "Vertex", "Face", "Halfedge" }, 0),
+            P.selection("selection"),
         },
         outputs = {
-            P.mesh("out_mesh"),
+            P.scalar("count"),
         },
-        returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = inputs.mesh:clone()
-            Ops.set_material(out_mesh, inputs.faces, inputs.material_index)
-            return { out_mesh = out_mesh }
+            local kty = Utils.parse_ch_key(inputs.domain)
+            return {
+                count = Ops.count_elements(inputs.mesh, kty, inputs.selection),
+            }
         end,
     },
     MakeGroup = {
@@ -653,14 +2219,72 @@ local edit_ops = {
             return { out_mesh = out_mesh }
         end,
     },
+    Wrangle = {
+        label = "Wrangle",
+        inputs = {
+            P.mesh("mesh"),
+            P.enum("domain", { "Vertex", "Face", "Halfedge" }, 0),
+            P.lua_str("code"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+        op = function(inputs)
+            local domain = Utils.parse_ch_key(inputs.domain)
+            return { out_mesh = Ops.wrangle(inputs.mesh, domain, inputs.code) }
+        end,
+    },
     CopyToPoints = {
         label = "Copy To Points",
         op = function(inputs)
-            return { out_mesh = Ops.copy_to_points(inputs.points, inputs.mesh) }
+            local points = inputs.points
+            if inputs.collider ~= nil then
+                points = points:clone()
+                Ops.prune_colliding_points(points, inputs.mesh, inputs.collider)
+            end
+            return { out_mesh = Ops.copy_to_points(points, inputs.mesh) }
         end,
         inputs = {
             P.mesh("points"),
             P.mesh("mesh"),
+            P.mesh("collider"),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
+    CopyToPointsVariation = {
+        label = "Copy To Points (Variation)",
+        op = function(inputs)
+            local meshes = {}
+            -- Unconnected inputs are nil, and are skipped
+            for i = 1, 4 do
+                local mesh = inputs["mesh_" .. i]
+                if mesh ~= nil then
+                    table.insert(meshes, mesh)
+                end
+            end
+            return {
+                out_mesh = Ops.copy_to_points_with_variation(
+                    inputs.points,
+                    meshes,
+                    inputs.scale_jitter,
+                    inputs.rotation_jitter,
+                    inputs.seed
+                ),
+            }
+        end,
+        inputs = {
+            P.mesh("points"),
+            P.mesh("mesh_1"),
+            P.mesh("mesh_2"),
+            P.mesh("mesh_3"),
+            P.mesh("mesh_4"),
+            P.scalar("scale_jitter", { default = 0.0, min = 0.0, max = 1.0 }),
+            P.scalar("rotation_jitter", { default = 0.0, min = 0.0, soft_max = math.pi }),
+            P.scalar_int("seed", { default = 0, min = 0, soft_max = 100 }),
         },
         outputs = {
             P.mesh("out_mesh"),
@@ -688,6 +2312,69 @@ local edit_ops = {
         },
         returns = "out_mesh",
     },
+    Sweep = {
+        label = "Sweep",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.sweep(
+                    inputs.profile,
+                    inputs.path,
+                    inputs.twist,
+                    inputs.scale_start,
+                    inputs.scale_end,
+                    inputs.align_mode
+                ),
+            }
+        end,
+        inputs = {
+            P.mesh("profile"),
+            P.mesh("path"),
+            P.scalar("twist", { default = 0.0, soft_min = -2 * math.pi, soft_max = 2 * math.pi }),
+            P.scalar("scale_start", { default = 1.0, min = 0.0, soft_max = 4.0 }),
+            P.scalar("scale_end", { default = 1.0, min = 0.0, soft_max = 4.0 }),
+            P.enum("align_mode", { "Frames", "Up", "Fixed" }, 0),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
+    Loft = {
+        label = "Loft",
+        op = function(inputs)
+            local profiles = {}
+            -- Unconnected inputs are nil, and are skipped
+            for i = 1, 6 do
+                local profile = inputs["profile_" .. i]
+                if profile ~= nil then
+                    table.insert(profiles, profile)
+                end
+            end
+            return {
+                out_mesh = Ops.loft(
+                    profiles,
+                    inputs.profiles == "Closed",
+                    inputs.ends == "Closed",
+                    inputs.smooth
+                ),
+            }
+        end,
+        inputs = {
+            P.mesh("profile_1"),
+            P.mesh("profile_2"),
+            P.mesh("profile_3"),
+            P.mesh("profile_4"),
+            P.mesh("profile_5"),
+            P.mesh("profile_6"),
+            P.enum("profiles", { "Open", "Closed" }, 1),
+            P.enum("ends", { "Open", "Closed" }, 0),
+            P.scalar_int("smooth", { default = 0, min = 0, soft_max = 16 }),
+        },
+        outputs = {
+            P.mesh("out_mesh"),
+        },
+        returns = "out_mesh",
+    },
     ResampleCurve = {
         label = "Resample Curve",
         op = function(inputs)
@@ -720,7 +2407,7 @@ local edit_ops = {
         end,
         inputs = {
             P.mesh("mesh"),
-            P.selection("points"),
+            P.selection("points", "vertex"),
         },
         outputs = {
             P.mesh("out_mesh"),
@@ -818,7 +2505,9 @@ local edit_ops = {
     },
 }
 
--- Math: Nodes to perform vector or scalar math operations
+-- Math: Nodes to perform vector or scalar math operations. Most of them are
+-- native: They have no `op`, and are evaluated by the engine itself. Logic
+-- nodes use scalars as booleans, where zero is false.
 local math_nodes = {
     MakeScalar = {
         label = "Scalar",
@@ -828,8 +2517,17 @@ local math_nodes = {
         outputs = {
             P.scalar("x"),
         },
+        native = true,
+    },
+    Time = {
+        label = "Time",
+        inputs = {},
+        outputs = {
+            P.scalar("frame"),
+            P.scalar("seconds"),
+        },
         op = function(inputs)
-            return { x = inputs.x }
+            return { frame = Time.frame(), seconds = Time.seconds() }
         end,
     },
     MakeVector = {
@@ -842,53 +2540,148 @@ local math_nodes = {
         outputs = {
             P.v3("v"),
         },
-        op = function(inputs)
-            return { v = vector(inputs.x, inputs.y, inputs.z) }
-        end,
+        native = true,
+    },
+    SeparateVector = {
+        label = "Separate Vector",
+        inputs = {
+            P.v3("v", vector(0, 0, 0)),
+        },
+        outputs = {
+            P.scalar("x"),
+            P.scalar("y"),
+            P.scalar("z"),
+        },
+        native = true,
     },
     VectorMath = {
         label = "Vector Math",
         inputs = {
-            P.enum("op", { "Add", "Sub", "Mul" }, 0),
+            P.enum("op", {
+                "Add",
+                "Sub",
+                "Mul",
+                "Div",
+                "Min",
+                "Max",
+                "Cross",
+                "Project",
+                "Reflect",
+                "Normalize",
+                "Negate",
+                "Abs",
+            }, 0),
             P.v3("vec_a", vector(0, 0, 0)),
             P.v3("vec_b", vector(0, 0, 0)),
         },
         outputs = {
             P.v3("out"),
         },
-        op = function(inputs)
-            local out
-            if inputs.op == "Add" then
-                out = inputs.vec_a + inputs.vec_b
-            elseif inputs.op == "Sub" then
-                out = inputs.vec_a - inputs.vec_b
-            elseif inputs.op == "Mul" then
-                out = inputs.vec_a * inputs.vec_b
-            end
-            return { out = out }
-        end,
+        native = true,
+    },
+    VectorScalarMath = {
+        label = "Vector to Scalar",
+        inputs = {
+            P.enum("op", { "Dot", "Length", "Distance", "Angle" }, 0),
+            P.v3("vec_a", vector(0, 0, 0)),
+            P.v3("vec_b", vector(0, 0, 0)),
+        },
+        outputs = {
+            P.scalar("out"),
+        },
+        native = true,
     },
     ScalarMath = {
         label = "Scalar Math",
         inputs = {
-            P.enum("op", { "Add", "Sub", "Mul" }, 0),
+            P.enum("op", {
+                "Add",
+                "Sub",
+                "Mul",
+                "Div",
+                "Mod",
+                "Pow",
+                "Min",
+                "Max",
+                "Atan2",
+                "Negate",
+                "Abs",
+                "Sign",
+                "Floor",
+                "Ceil",
+                "Round",
+                "Fract",
+                "Sqrt",
+                "Exp",
+                "Log",
+                "Sin",
+                "Cos",
+                "Tan",
+                "Asin",
+                "Acos",
+                "Atan",
+                "ToRadians",
+                "ToDegrees",
+            }, 0),
             P.scalar("x", { default = 0 }),
             P.scalar("y", { default = 0 }),
         },
         outputs = {
             P.scalar("out"),
         },
-        op = function(inputs)
-            local out
-            if inputs.op == "Add" then
-                out = inputs.x + inputs.y
-            elseif inputs.op == "Sub" then
-                out = inputs.x - inputs.y
-            elseif inputs.op == "Mul" then
-                out = inputs.x * inputs.y
-            end
-            return { out = out }
-        end,
+        native = true,
+    },
+    Clamp = {
+        label = "Clamp",
+        inputs = {
+            P.scalar("x", { default = 0.0 }),
+            P.scalar("min", { default = 0.0 }),
+            P.scalar("max", { default = 1.0 }),
+        },
+        outputs = {
+            P.scalar("out"),
+        },
+        native = true,
+    },
+    MapRange = {
+        label = "Map Range",
+        inputs = {
+            P.scalar("x", { default = 0.0 }),
+            P.scalar("from_min", { default = 0.0 }),
+            P.scalar("from_max", { default = 1.0 }),
+            P.scalar("to_min", { default = 0.0 }),
+            P.scalar("to_max", { default = 1.0 }),
+            P.enum("clamp", { "Unclamped", "Clamped" }, 0),
+        },
+        outputs = {
+            P.scalar("out"),
+        },
+        native = true,
+    },
+    Compare = {
+        label = "Compare",
+        inputs = {
+            P.enum("op", { "Less", "LessEqual", "Greater", "GreaterEqual", "Equal", "NotEqual" }, 0),
+            P.scalar("x", { default = 0.0 }),
+            P.scalar("y", { default = 0.0 }),
+            P.scalar("epsilon", { default = 0.0001, min = 0.0, soft_max = 0.1 }),
+        },
+        outputs = {
+            P.scalar("out"),
+        },
+        native = true,
+    },
+    Logic = {
+        label = "Logic",
+        inputs = {
+            P.enum("op", { "And", "Or", "Xor", "Not" }, 0),
+            P.scalar("a", { default = 0.0, min = 0.0, max = 1.0 }),
+            P.scalar("b", { default = 0.0, min = 0.0, max = 1.0 }),
+        },
+        outputs = {
+            P.scalar("out"),
+        },
+        native = true,
     },
 }
 
@@ -898,25 +2691,64 @@ local export = {
         label = "Export OBJ",
         inputs = {
             P.mesh("mesh"),
-            P.file("path"),
+            P.file("path", "save", { "obj" }),
+            P.enum("ordering", { "Internal", "Spatial" }, 0),
+        },
+        outputs = {},
+        executable = true,
+        op = function(inputs)
+            HalfEdgeMesh.to_wavefront_obj(inputs.mesh, inputs.path, inputs.ordering)
+        end,
+    },
+    ExportObjFaces = {
+        label = "Export OBJ (Selected Faces)",
+        inputs = {
+            P.mesh("mesh"),
+            P.selection("faces", "face"),
+            P.file("path", "save", { "obj" }),
+            P.enum("ordering", { "Internal", "Spatial" }, 0),
         },
         outputs = {},
         executable = true,
         op = function(inputs)
-            HalfEdgeMesh.to_wavefront_obj(inputs.mesh, inputs.path)
+            HalfEdgeMesh.to_wavefront_obj(inputs.mesh, inputs.path, inputs.ordering, inputs.faces)
         end,
     },
     ImportObj = {
         label = "Import OBJ",
         inputs = {
-            P.file("path", "open"),
+            P.file("path", "open", { "obj" }),
+            -- In proxy mode, only the bounding box is read until another node
+            -- needs the geometry. Useful for heavy files.
+            P.enum("mode", { "Full", "Proxy" }, 0),
+            -- Remembered options are the ones last set in a node importing
+            -- the same file type, and ignore the inputs below.
+            P.enum("options", { "Remembered", "Custom" }, 0),
+            P.scalar("scale", { default = 1.0, min = 0.0, soft_max = 100.0 }),
+            P.enum("axis_swap", { "None", "ZUpToYUp", "YUpToZUp" }, 0),
+            P.scalar("weld_distance", { default = 0.0, min = 0.0, soft_max = 0.01 }),
+            P.enum("ngons", { "Keep", "Triangulate" }, 0),
         },
         outputs = {
             P.mesh("out_mesh"),
         },
         returns = "out_mesh",
         op = function(inputs)
-            local out_mesh = HalfEdgeMesh.from_wavefront_obj(inputs.path)
+            local options = nil
+            if inputs.options == "Custom" then
+                options = {
+                    scale = inputs.scale,
+                    axis_swap = inputs.axis_swap,
+                    weld_distance = inputs.weld_distance,
+                    triangulate_ngons = inputs.ngons == "Triangulate",
+                }
+            end
+            local out_mesh
+            if inputs.mode == "Proxy" then
+                out_mesh = HalfEdgeMesh.lazy_wavefront_obj(inputs.path, options)
+            else
+                out_mesh = HalfEdgeMesh.from_wavefront_obj(inputs.path, options)
+            end
             return { out_mesh = out_mesh }
         end,
     },
@@ -24,6 +24,23 @@ struct LuaDocstring {
     /// A syntactically valid Lua string of a function definition plus any
     /// available comments.
     doc: String,
+    /// Structured metadata for the same item, used to build the
+    /// machine-readable API registry.
+    api_entry: LuaApiEntry,
+}
+
+/// The structured counterpart of a [`LuaDocstring`]. Gets emitted as a static
+/// `LuaApiEntry` value in the generated code.
+#[derive(Debug)]
+struct LuaApiEntry {
+    /// The name of the item, as it's exposed to Lua.
+    name: String,
+    /// Parameter names and (stringified) Rust types.
+    params: Vec<(String, String)>,
+    /// The (stringified) Rust return type, or the type of a constant.
+    returns: String,
+    /// One entry per docstring line.
+    doc_lines: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +70,16 @@ struct LuaConst {
     lua_docstr: LuaDocstring,
 }
 
+/// Returns the docstring lines for the API registry. Rustdoc comments keep the
+/// space after the `///`, which is stripped here.
+fn registry_doc_lines(attrs: &FunctionAttributes) -> Vec<String> {
+    attrs
+        .docstring_lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line).to_string())
+        .collect()
+}
+
 /// Generates the automatic Lua documentation for this `item_fn`, using LuaDoc
 /// format. The generated function will have no body, only signature.
 fn generate_lua_fn_documentation(
@@ -61,6 +88,31 @@ fn generate_lua_fn_documentation(
     fn_def_kind: &LuaFnDefKind,
 ) -> LuaDocstring {
     use std::fmt::Write;
+
+    let params = item_fn
+        .sig
+        .inputs
+        .iter()
+        .map(|param| match param {
+            syn::FnArg::Receiver(r) => {
+                let typ = match fn_def_kind {
+                    LuaFnDefKind::Method { class } => class.clone(),
+                    _ => "Self".into(),
+                };
+                let typ = if r.mutability.is_some() {
+                    format!("&mut {typ}")
+                } else {
+                    format!("&{typ}")
+                };
+                ("self".to_string(), typ)
+            }
+            syn::FnArg::Typed(tpd) => (
+                tpd.pat.to_token_stream().to_string(),
+                tpd.ty.to_token_stream().to_string(),
+            ),
+        })
+        .collect::<Vec<_>>();
+
     let doc = (|| -> Result<String, Box<dyn std::error::Error>> {
         let mut docstr = String::new();
         let mut first = true;
@@ -74,23 +126,17 @@ fn generate_lua_fn_documentation(
         }
         writeln!(docstr, "--")?;
 
-        let mut param_idents = vec![];
-        for param in item_fn.sig.inputs.iter() {
-            match param {
-                syn::FnArg::Receiver(_) => {
-                    writeln!(docstr, "-- @param self The current object")?;
-                }
-                syn::FnArg::Typed(tpd) => {
-                    let name = tpd.pat.to_token_stream().to_string();
-                    let typ = tpd.ty.to_token_stream().to_string();
-                    writeln!(docstr, "-- @param {name} {typ}")?;
-                    param_idents.push(name);
-                }
+        for (name, typ) in &params {
+            if name == "self" {
+                writeln!(docstr, "-- @param self The current object")?;
+            } else {
+                writeln!(docstr, "-- @param {name} {typ}")?;
             }
         }
+        let param_idents = params.iter().filter(|(name, _)| name != "self");
 
         let fn_name = &item_fn.sig.ident;
-        let param_list = join_str(param_idents.iter(), ", ");
+        let param_list = join_str(param_idents.map(|(name, _)| name), ", ");
         writeln!(docstr, "function {fn_name}({param_list})")?;
         writeln!(docstr, "    error('Documentation stub only')")?;
         writeln!(docstr, "end\n")?;
@@ -99,9 +145,20 @@ fn generate_lua_fn_documentation(
     })()
     .unwrap();
 
+    let returns = match &item_fn.sig.output {
+        ReturnType::Default => "()".into(),
+        ReturnType::Type(_, t) => t.to_token_stream().to_string(),
+    };
+
     LuaDocstring {
         def_kind: fn_def_kind.clone(),
         doc,
+        api_entry: LuaApiEntry {
+            name: item_fn.sig.ident.to_string(),
+            params,
+            returns,
+            doc_lines: registry_doc_lines(attrs),
+        },
     }
 }
 
@@ -140,6 +197,12 @@ fn generate_lua_const_documentation(
                 .unwrap_or_else(|| "Default".into()),
         },
         doc,
+        api_entry: LuaApiEntry {
+            name: item_const.ident.to_string(),
+            params: vec![],
+            returns: item_const.ty.to_token_stream().to_string(),
+            doc_lines: registry_doc_lines(attrs),
+        },
     }
 }
 
@@ -736,6 +799,37 @@ pub(crate) fn blackjack_lua_module2(
             quote! { (#typ, #name, #doc) }
         });
 
+    let static_api_entries_code = fn_defs
+        .iter()
+        .map(|x| &x.lua_docstr)
+        .chain(const_defs.iter().map(|x| &x.lua_docstr))
+        .map(|lua_docstr| {
+            let (kind, parent) = match &lua_docstr.def_kind {
+                LuaFnDefKind::Method { class } => ("method", class),
+                LuaFnDefKind::Global { table } => ("function", table),
+                LuaFnDefKind::GlobalConstant { table } => ("constant", table),
+            };
+            let LuaApiEntry {
+                name,
+                params,
+                returns,
+                doc_lines,
+            } = &lua_docstr.api_entry;
+            let param_names = params.iter().map(|(name, _)| name);
+            let param_types = params.iter().map(|(_, typ)| typ);
+
+            quote! {
+                blackjack_engine::lua_engine::lua_stdlib::LuaApiEntry {
+                    kind: #kind,
+                    parent: #parent,
+                    name: #name,
+                    params: &[#((#param_names, #param_types)),*],
+                    returns: #returns,
+                    doc: &[#(#doc_lines),*],
+                }
+            }
+        });
+
     let original_items_code = module.content.as_ref().unwrap().1.iter();
     let register_fns_code = fn_defs.iter().map(|n| &n.register_fn_item);
     let register_consts_code = const_defs.iter().map(|n| &n.register_const_fn_item);
@@ -774,6 +868,18 @@ pub(crate) fn blackjack_lua_module2(
                     data: __blackjack_lua_docstrings,
                 }
             }
+
+            // Same information as above, but structured, for the API registry
+            #[allow(non_upper_case_globals)]
+            pub static __blackjack_lua_api_entries : &'static [blackjack_engine::lua_engine::lua_stdlib::LuaApiEntry] = &[
+                #(#static_api_entries_code),*
+            ];
+
+            inventory::submit! {
+                blackjack_engine::lua_engine::lua_stdlib::LuaApiRegistryData {
+                    data: __blackjack_lua_api_entries,
+                }
+            }
         }
     })
 }
@@ -108,5 +108,6 @@ pub fn draw_gui_overlays(
         RenderableThing::HeightMap(_) => {
             // TODO @Heightmap
         }
+        RenderableThing::MeshProxy(_) => {}
     }
 }
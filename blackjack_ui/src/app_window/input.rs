@@ -6,7 +6,10 @@
 
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, ModifiersState, MouseButton, VirtualKeyCode, WindowEvent},
+    event::{
+        DeviceEvent, DeviceId, ElementState, ModifiersState, MouseButton, VirtualKeyCode,
+        WindowEvent,
+    },
 };
 
 use crate::{egui_ext::RectUtils, prelude::*};
@@ -16,7 +19,9 @@ pub struct InputSystem {
     pub mouse: MouseInput,
     pub shift_down: bool,
     pub ctrl_down: bool,
+    pub alt_down: bool,
     pub pressed: HashSet<VirtualKeyCode>,
+    pub space_mouse: SpaceMouseInput,
 }
 
 /// Transforms a window-relative position `pos` into viewport relative
@@ -40,6 +45,17 @@ impl InputSystem {
     /// Called every frame, updates the input data structures
     pub fn update(&mut self) {
         self.mouse.update();
+        self.space_mouse.update();
+    }
+
+    /// Called when a new `winit` device event is received. Device events are
+    /// not tied to any window, so they are registered regardless of where the
+    /// cursor is.
+    pub fn on_device_event(&mut self, device_id: DeviceId, event: &DeviceEvent) {
+        if let DeviceEvent::Motion { axis, value } = event {
+            self.space_mouse
+                .on_axis_motion(device_id, *axis, *value as f32);
+        }
     }
 
     pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
@@ -115,6 +131,7 @@ impl InputSystem {
             WindowEvent::ModifiersChanged(state) => {
                 self.shift_down = state.contains(ModifiersState::SHIFT);
                 self.ctrl_down = state.contains(ModifiersState::CTRL);
+                self.alt_down = state.contains(ModifiersState::ALT);
             }
             WindowEvent::KeyboardInput { input, .. } => {
                 if let Some(key) = input.virtual_keycode {
@@ -196,6 +213,43 @@ impl Default for MouseInput {
     }
 }
 
+/// Stores the input of 6-DoF devices, such as 3D mice. These devices report
+/// their translation and rotation as six motion axes, in this order: X, Y, Z
+/// translation, and X, Y, Z rotation.
+#[derive(Default)]
+pub struct SpaceMouseInput {
+    /// Regular mice report motion on the first two axes. Devices are only
+    /// considered 6-DoF once they have reported motion on any other axis.
+    devices: HashSet<DeviceId>,
+    axes: [f32; 6],
+}
+
+impl SpaceMouseInput {
+    pub fn on_axis_motion(&mut self, device_id: DeviceId, axis: u32, value: f32) {
+        if (2..6).contains(&axis) {
+            self.devices.insert(device_id);
+        }
+        if self.devices.contains(&device_id) && axis < 6 {
+            self.axes[axis as usize] = value;
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.axes = [0.0; 6];
+    }
+
+    /// The translation requested by the device during this frame.
+    pub fn translation(&self) -> Vec3 {
+        Vec3::new(self.axes[0], self.axes[1], self.axes[2])
+    }
+
+    /// The rotation requested by the device during this frame, as the
+    /// amount of rotation around each of the axes.
+    pub fn rotation(&self) -> Vec3 {
+        Vec3::new(self.axes[3], self.axes[4], self.axes[5])
+    }
+}
+
 #[derive(Default)]
 pub struct Input<Button> {
     pressed: HashSet<Button>,
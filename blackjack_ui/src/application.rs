@@ -4,10 +4,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use crate::{
     cli_args::CLI_ARGS,
+    keymap::{Action, KEYMAP},
     prelude::*,
     rendergraph::{
         face_routine::FaceRoutine, grid_routine::GridRoutine, id_picking_routine::IdPickingRoutine,
@@ -21,7 +22,8 @@ use winit::window::Window;
 use self::{
     app_viewport::AppViewport, application_context::ApplicationContext,
     gizmo_ui::UiNodeGizmoStates, graph_editor::GraphEditor, inspector::InspectorTabs,
-    root_ui::AppRootAction, viewport_3d::Viewport3d,
+    lua_console::LuaConsole, reference_images::ReferenceImages, root_ui::AppRootAction,
+    timeline::Timeline, undo_history::UndoHistory, viewport_3d::Viewport3d,
 };
 
 pub struct RootViewport {
@@ -37,8 +39,16 @@ pub struct RootViewport {
     offscreen_viewports: HashMap<OffscreenViewport, AppViewport>,
     inspector_tabs: InspectorTabs,
     diagnostics_open: bool,
+    lua_console: LuaConsole,
+    reference_images: ReferenceImages,
     lua_runtime: LuaRuntime,
     mouse_captured_by_split: bool,
+    undo_history: UndoHistory,
+    timeline: Timeline,
+    /// The scale factor reported by the monitor the window is currently on.
+    scale_factor: f32,
+    /// A user-defined multiplier on top of the monitor `scale_factor`.
+    ui_scale: f32,
 }
 
 /// The application context is state that is global to an instance of blackjack.
@@ -64,6 +74,12 @@ pub mod root_ui;
 /// Serialization code to load / store graphs
 pub mod serialization;
 
+/// Snapshot-based undo / redo for graph edits
+pub mod undo_history;
+
+/// Playback controls for animated graphs
+pub mod timeline;
+
 /// An egui widget that draws an offscreen-rendered texture
 pub mod app_viewport;
 
@@ -77,6 +93,12 @@ pub mod inspector;
 /// highlighting support
 pub mod code_viewer;
 
+/// An interactive Lua console to inspect the graph outputs
+pub mod lua_console;
+
+/// Images displayed in the viewport as a modeling guide
+pub mod reference_images;
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 enum OffscreenViewport {
     GraphEditor,
@@ -113,11 +135,26 @@ impl RootViewport {
 
         let mut egui_winit_state = egui_winit::State::new_with_wayland_display(None);
         egui_winit_state.set_max_texture_side(renderer.limits.max_texture_dimension_2d as usize);
-        egui_winit_state.set_pixels_per_point(scale_factor as f32);
+        let ui_scale = CLI_ARGS.ui_scale.unwrap_or(1.0);
+        egui_winit_state.set_pixels_per_point(scale_factor as f32 * ui_scale);
 
         // TODO: Hardcoded node libraries path. Read from cmd line?
-        let mut lua_runtime = LuaRuntime::initialize_with_std("./blackjack_lua/".into())
-            .unwrap_or_else(|err| panic!("Init lua should not fail. {err}"));
+        let lua_path = "./blackjack_lua/".to_string();
+        // The default cache folder is per user, since cached bytecode skips
+        // the checks done when compiling.
+        let cache_dir = CLI_ARGS
+            .lua_cache_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(|| dirs::cache_dir().map(|dir| dir.join("blackjack").join("lua_bytecode")));
+        let lua_runtime = match cache_dir {
+            Some(cache_dir) if !CLI_ARGS.disable_lua_cache => {
+                LuaRuntime::initialize_with_std_cached(lua_path, cache_dir)
+            }
+            _ => LuaRuntime::initialize_with_std(lua_path),
+        };
+        let mut lua_runtime =
+            lua_runtime.unwrap_or_else(|err| panic!("Init lua should not fail. {err}"));
         if !CLI_ARGS.disable_lua_watcher {
             lua_runtime
                 .start_file_watcher()
@@ -131,14 +168,14 @@ impl RootViewport {
             textures_to_free: Vec::new(),
             screen_descriptor: ScreenDescriptor {
                 size_in_pixels: window_size.to_array(),
-                pixels_per_point: scale_factor as f32,
+                pixels_per_point: scale_factor as f32 * ui_scale,
             },
             renderpass: RenderPass::new(&renderer.device, screen_format, 1),
             app_context: ApplicationContext::new(gizmo_state.share()),
             graph_editor: GraphEditor::new(
                 renderer,
                 screen_format,
-                scale_factor as f32,
+                scale_factor as f32 * ui_scale,
                 lua_runtime.node_definitions.share(),
                 gizmo_state.share(),
             ),
@@ -146,8 +183,31 @@ impl RootViewport {
             offscreen_viewports,
             inspector_tabs: InspectorTabs::new(),
             diagnostics_open: false,
+            lua_console: LuaConsole::default(),
+            reference_images: ReferenceImages::default(),
             lua_runtime,
             mouse_captured_by_split: false,
+            undo_history: UndoHistory::default(),
+            timeline: Timeline::default(),
+            scale_factor: scale_factor as f32,
+            ui_scale,
+        }
+    }
+
+    /// The number of physical pixels per logical point, taking into account
+    /// both the monitor scale factor and the user's UI scale setting.
+    pub fn pixels_per_point(&self) -> f32 {
+        self.scale_factor * self.ui_scale
+    }
+
+    /// Propagates the current pixels per point to the root egui instance and
+    /// the screen descriptor. The child viewports read it from the screen
+    /// descriptor, so their render textures and picking follow it as well.
+    fn apply_pixels_per_point(&mut self) {
+        let pixels_per_point = self.pixels_per_point();
+        self.screen_descriptor.pixels_per_point = pixels_per_point;
+        if self.egui_winit_state.pixels_per_point() != pixels_per_point {
+            self.egui_winit_state.set_pixels_per_point(pixels_per_point);
         }
     }
 
@@ -171,15 +231,25 @@ impl RootViewport {
                     self.screen_descriptor.size_in_pixels[1] = new_size.height;
                 }
                 winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                    self.screen_descriptor.pixels_per_point = *scale_factor as f32;
+                    // Happens when the window moves to a monitor with a
+                    // different DPI, or the monitor settings change.
+                    self.scale_factor = *scale_factor as f32;
                 }
                 _ => {}
             },
             _ => {}
         }
 
+        if let winit::event::Event::DeviceEvent { device_id, event } = &event {
+            self.viewport_3d.on_device_event(*device_id, event);
+        }
+
         if let winit::event::Event::WindowEvent { event, .. } = event {
             self.egui_winit_state.on_event(&self.egui_context, &event);
+            // NOTE: egui_winit resets its pixels per point to the monitor
+            // scale factor on ScaleFactorChanged, so the UI scale needs to be
+            // applied again after forwarding the event.
+            self.apply_pixels_per_point();
             let parent_scale = self.screen_descriptor.pixels_per_point;
             if let Some(event) = event.to_static() {
                 self.graph_editor.on_winit_event(
@@ -202,7 +272,7 @@ impl RootViewport {
         self.app_context.setup(render_ctx);
 
         if let Some(load) = &CLI_ARGS.load {
-            self.handle_root_action(AppRootAction::Load(std::path::PathBuf::from(load)))
+            self.handle_root_action(AppRootAction::Load(PathBuf::from(load)))
                 .expect("Error loading scene from CLI arg.");
         }
     }
@@ -210,12 +280,16 @@ impl RootViewport {
     pub fn update(&mut self, render_ctx: &mut RenderContext, window: &winit::window::Window) {
         let mut actions = vec![];
 
+        // The UI scale may have been changed from the menu last frame.
+        self.apply_pixels_per_point();
+
         if !CLI_ARGS.disable_lua_watcher {
             match self.lua_runtime.watch_for_changes() {
                 Ok(true) => {
                     if let Err(err) = self.graph_editor.on_node_definitions_update() {
                         println!("Error while updating graph after Lua code reload: {err}.");
                     }
+                    self.graph_editor.custom_state.graph_changed = true;
 
                     // Reset gizmo state when code is reloaded. This helps
                     // interactively develop gizmos, otherwise the init function
@@ -247,6 +321,10 @@ impl RootViewport {
             actions.push(menubar_action);
         }
 
+        egui::TopBottomPanel::bottom("timeline").show(&self.egui_context.clone(), |ui| {
+            self.timeline.ui(ui);
+        });
+
         egui::CentralPanel::default().show(&self.egui_context.clone(), |ui| {
             let mut split_tree = self.app_context.split_tree.clone();
 
@@ -261,7 +339,19 @@ impl RootViewport {
             self.app_context.split_tree = split_tree;
         });
 
-        self.diagnostics_ui();
+        self.diagnostics_ui(render_ctx);
+        self.lua_console.ui(
+            &self.egui_context,
+            &self.lua_runtime,
+            self.app_context.renderable_thing.as_ref(),
+        );
+        self.reference_images
+            .ui(&self.egui_context, &mut self.graph_editor.custom_state);
+
+        // The graph is cooked every frame, so playback only needs to update
+        // the frame seen by the nodes.
+        self.timeline.advance();
+        self.timeline.frame_context().install(&self.lua_runtime.lua);
 
         actions.extend(self.app_context.update(
             &self.egui_context,
@@ -271,12 +361,41 @@ impl RootViewport {
             &self.viewport_3d.settings,
             &self.lua_runtime,
         ));
+        self.reference_images
+            .add_to_scene(render_ctx, &self.graph_editor.custom_state);
+
+        let text_focused = self.egui_context.wants_keyboard_input()
+            || self.graph_editor.egui_context.wants_keyboard_input();
+        if !text_focused {
+            let input = self.egui_context.input();
+            if KEYMAP.pressed(Action::Undo, &input) {
+                actions.push(AppRootAction::Undo);
+            } else if KEYMAP.pressed(Action::Redo, &input) {
+                actions.push(AppRootAction::Redo);
+            }
+            if KEYMAP.pressed(Action::ToggleConsole, &input) {
+                self.lua_console.open = !self.lua_console.open;
+            }
+        }
 
         for action in actions {
             // TODO: Don't panic, report error to user in modal dialog
             self.handle_root_action(action)
                 .expect("Error executing action.");
         }
+
+        // Slider and gizmo drags, or text edits, are recorded as a single
+        // undo step once the user is done with them.
+        let interacting = text_focused
+            || self.egui_context.is_using_pointer()
+            || self.graph_editor.egui_context.is_using_pointer();
+        if let Err(err) = self.undo_history.track(
+            &self.graph_editor.editor_state,
+            &mut self.graph_editor.custom_state,
+            interacting,
+        ) {
+            println!("Error recording undo history: {err}");
+        }
     }
 
     pub fn handle_root_action(&mut self, action: AppRootAction) -> Result<()> {
@@ -285,17 +404,41 @@ impl RootViewport {
                 serialization::save(
                     &self.graph_editor.editor_state,
                     &self.graph_editor.custom_state,
-                    path,
+                    &path,
                 )?;
+                self.undo_history.save_for(&path)?;
+                self.graph_editor.custom_state.base_dir = path.parent().map(|p| p.to_path_buf());
             }
             AppRootAction::Load(path) => {
                 let (editor_state, custom_state) = serialization::load(
-                    path,
+                    path.clone(),
                     &self.graph_editor.custom_state.node_definitions,
                     &self.graph_editor.custom_state.gizmo_states,
                 )?;
                 self.graph_editor.editor_state = editor_state;
                 self.graph_editor.custom_state = custom_state;
+                // The history is not essential, so a broken history file
+                // does not prevent loading the graph.
+                self.undo_history = UndoHistory::load_for(&path).unwrap_or_else(|err| {
+                    println!("Error loading undo history, starting a new one: {err}");
+                    UndoHistory::default()
+                });
+            }
+            AppRootAction::Undo => {
+                if let Err(err) = self.undo_history.undo(
+                    &mut self.graph_editor.editor_state,
+                    &mut self.graph_editor.custom_state,
+                ) {
+                    println!("Error while undoing: {err}");
+                }
+            }
+            AppRootAction::Redo => {
+                if let Err(err) = self.undo_history.redo(
+                    &mut self.graph_editor.editor_state,
+                    &mut self.graph_editor.custom_state,
+                ) {
+                    println!("Error while redoing: {err}");
+                }
             }
         }
         Ok(())
@@ -322,6 +465,7 @@ impl RootViewport {
                 .hover_pos()
                 .unwrap_or(egui::Pos2::ZERO),
             self.viewport_3d.viewport_rect(),
+            self.screen_descriptor.pixels_per_point,
         );
 
         let frame = rend3::util::output::OutputFrame::Surface {
@@ -337,9 +481,9 @@ impl RootViewport {
                 pbr: pbr_routine,
                 tonemapping: tonemapping_routine,
                 grid: grid_routine,
-                wireframe: wireframe_routine,
-                point_cloud: point_cloud_routine,
-                face: face_routine,
+                wireframe: wireframe_routine.as_ref(),
+                point_cloud: point_cloud_routine.as_ref(),
+                face: face_routine.as_ref(),
                 id_picking: id_picking_routine,
             },
         );
@@ -377,8 +521,8 @@ pub struct ViewportRoutines<'a> {
     pub pbr: &'a r3::PbrRoutine,
     pub tonemapping: &'a r3::TonemappingRoutine,
     pub grid: &'a GridRoutine,
-    pub wireframe: &'a WireframeRoutine,
-    pub point_cloud: &'a PointCloudRoutine,
-    pub face: &'a FaceRoutine,
+    pub wireframe: Option<&'a WireframeRoutine>,
+    pub point_cloud: Option<&'a PointCloudRoutine>,
+    pub face: Option<&'a FaceRoutine>,
     pub id_picking: &'a IdPickingRoutine,
 }
@@ -8,9 +8,11 @@ use crate::graph::graph_interop::{self, NodeMapping};
 use crate::prelude::*;
 use anyhow::Error;
 
-use blackjack_engine::graph::BjkGraph;
-use blackjack_engine::graph_interpreter::ExternalParameterValues;
-use blackjack_engine::prelude::ChannelKeyType;
+use blackjack_engine::graph::{BjkGraph, BlackjackValue, DataType, DependencyKind};
+use blackjack_engine::graph_interpreter::{ExternalParameter, ExternalParameterValues};
+use blackjack_engine::materials::{Material, MaterialRegistry};
+use blackjack_engine::mesh::slicing::{self, SliceLayer};
+use blackjack_engine::prelude::{ChannelKeyType, HalfEdgeMesh, MeshStats, ShadingMode};
 use blackjack_engine::{
     lua_engine::{LuaRuntime, RenderableThing},
     prelude::{FaceOverlayBuffers, LineBuffers, PointBuffers, VertexIndexBuffers},
@@ -48,6 +50,20 @@ pub struct ApplicationContext {
     /// partition the state either horizontally or vertically. This separation
     /// is dynamic, very similar to Blender's UI model
     pub split_tree: SplitTree,
+    /// The time it took to run the graph for the current `renderable_thing`.
+    pub last_cook_time: Option<std::time::Duration>,
+    /// Statistics for the current `renderable_thing`, when it is a mesh. Only
+    /// computed when the statistics HUD is visible.
+    pub mesh_stats: Option<MeshStats>,
+    /// Lines highlighting the elements matched by the selection parameter
+    /// being edited, if any.
+    pub selection_preview: Option<LineBuffers>,
+    /// A transient mesh drawn as a wireframe over the displayed one, such as
+    /// the primitive being placed in the viewport. Not part of the graph.
+    pub preview_mesh: Option<HalfEdgeMesh>,
+    /// The current mesh sliced into layers along the Y axis. Only computed
+    /// when the layer preview is enabled in the viewport.
+    pub slice_layers: Option<Vec<SliceLayer>>,
 }
 
 impl ApplicationContext {
@@ -57,6 +73,11 @@ impl ApplicationContext {
             current_selection: None,
             node_gizmo_states: gizmo_states,
             split_tree: SplitTree::default_tree(),
+            last_cook_time: None,
+            mesh_stats: None,
+            selection_preview: None,
+            preview_mesh: None,
+            slice_layers: None,
         }
     }
 
@@ -83,17 +104,44 @@ impl ApplicationContext {
         // objects it's drawing and clear those instead.
         render_ctx.clear_objects();
 
+        // Nodes may look up materials by name while the graph runs.
+        custom_state.materials.install(&lua_runtime.lua);
+
         if let Err(err) = self.run_active_node(editor_state, custom_state, lua_runtime) {
             self.paint_errors(egui_ctx, err);
         };
 
+        // Selections are often invalid while they're being typed, so errors
+        // just hide the preview.
+        self.selection_preview = self
+            .run_selection_preview(editor_state, custom_state, lua_runtime)
+            .unwrap_or(None);
+
+        self.mesh_stats = match &self.renderable_thing {
+            Some(RenderableThing::HalfEdgeMesh(mesh)) if viewport_settings.show_stats_hud => {
+                Some(mesh.stats())
+            }
+            _ => None,
+        };
+        self.slice_layers = match &self.renderable_thing {
+            Some(RenderableThing::HalfEdgeMesh(mesh)) if viewport_settings.show_layers => {
+                slicing::slice_layers(mesh, viewport_settings.layer_height, Vec3::Y).ok()
+            }
+            _ => None,
+        };
+
         if let Err(err) = self.run_side_effects(editor_state, custom_state, lua_runtime) {
             eprintln!(
                 "There was an errror executing side effect: {err}\nBacktrace:\n----------\n{}",
                 err.backtrace()
             );
         }
-        if let Err(err) = self.build_and_render_mesh(render_ctx, viewport_settings) {
+        if let Err(err) = self.build_and_render_mesh(
+            render_ctx,
+            viewport_settings,
+            &custom_state.materials,
+            egui_ctx.pixels_per_point(),
+        ) {
             self.paint_errors(egui_ctx, err);
         }
 
@@ -104,39 +152,47 @@ impl ApplicationContext {
         &mut self,
         render_ctx: &mut RenderContext,
         viewport_settings: &Viewport3dSettings,
+        materials: &MaterialRegistry,
+        pixels_per_point: f32,
     ) -> Result<()> {
         match self.renderable_thing.as_mut() {
             Some(RenderableThing::HalfEdgeMesh(mesh)) => {
                 // Base mesh
                 {
-                    if let Some(VertexIndexBuffers {
-                        positions,
-                        normals,
-                        indices,
-                    }) = match viewport_settings.face_mode {
-                        FaceDrawMode::Real => {
-                            if mesh.gen_config.smooth_normals {
-                                Some(mesh.generate_triangle_buffers_smooth(false)?)
-                            } else {
-                                Some(mesh.generate_triangle_buffers_flat(false)?)
+                    let channels = viewport_settings.uploads.buffer_channels();
+                    if let Some(buffers) = match viewport_settings.face_mode {
+                        FaceDrawMode::Real => match mesh.gen_config.shading {
+                            ShadingMode::Flat => {
+                                Some(mesh.generate_triangle_buffers_flat(false, channels)?)
+                            }
+                            ShadingMode::Smooth => {
+                                Some(mesh.generate_triangle_buffers_smooth(false, channels)?)
                             }
+                            ShadingMode::AutoSmooth | ShadingMode::Mixed => {
+                                Some(mesh.generate_triangle_buffers_auto_smooth(channels)?)
+                            }
+                        },
+                        FaceDrawMode::Flat => {
+                            Some(mesh.generate_triangle_buffers_flat(true, channels)?)
+                        }
+                        FaceDrawMode::Smooth => {
+                            Some(mesh.generate_triangle_buffers_smooth(true, channels)?)
+                        }
+                        FaceDrawMode::Materials => {
+                            Self::add_material_objects(render_ctx, mesh, materials)?;
+                            None
                         }
-                        FaceDrawMode::Flat => Some(mesh.generate_triangle_buffers_flat(true)?),
-                        FaceDrawMode::Smooth => Some(mesh.generate_triangle_buffers_smooth(true)?),
                         FaceDrawMode::NoDraw => None,
                     } {
-                        if !positions.is_empty() {
-                            render_ctx.face_routine.add_base_mesh(
-                                &render_ctx.renderer,
-                                &positions,
-                                &normals,
-                                &indices,
-                            );
-                        }
+                        Self::add_base_mesh(render_ctx, buffers)?;
                     }
                 }
 
                 // Face overlays and ids
+                if let Some(face_routine) = render_ctx
+                    .face_routine
+                    .as_mut()
+                    .filter(|_| viewport_settings.uploads.ids)
                 {
                     let FaceOverlayBuffers {
                         positions,
@@ -147,7 +203,7 @@ impl ApplicationContext {
                         self.current_selection.as_ref().and_then(|x| x.hovered),
                     );
                     if !positions.is_empty() {
-                        render_ctx.face_routine.add_overlay_mesh(
+                        face_routine.add_overlay_mesh(
                             &render_ctx.renderer,
                             &positions,
                             &colors,
@@ -158,7 +214,7 @@ impl ApplicationContext {
                 }
 
                 // Edges
-                {
+                if let Some(wireframe_routine) = &mut render_ctx.wireframe_routine {
                     if let Some(LineBuffers { positions, colors }) =
                         match viewport_settings.edge_mode {
                             EdgeDrawMode::HalfEdge => Some(mesh.generate_halfedge_arrow_buffers()?),
@@ -167,7 +223,7 @@ impl ApplicationContext {
                         }
                     {
                         if !positions.is_empty() {
-                            render_ctx.wireframe_routine.add_wireframe(
+                            wireframe_routine.add_wireframe(
                                 &render_ctx.renderer.device,
                                 &positions,
                                 &colors,
@@ -177,33 +233,146 @@ impl ApplicationContext {
                 }
 
                 // Vertices
-                {
+                if let Some(point_cloud_routine) = &mut render_ctx.point_cloud_routine {
                     let PointBuffers { positions } = mesh.generate_point_buffers();
                     if !positions.is_empty() {
-                        render_ctx
-                            .point_cloud_routine
-                            .add_point_cloud(&render_ctx.renderer.device, &positions);
+                        point_cloud_routine.add_point_cloud(
+                            &render_ctx.renderer.device,
+                            &positions,
+                            pixels_per_point,
+                        );
                     }
                 }
             }
-            Some(RenderableThing::HeightMap(heightmap)) => {
-                let VertexIndexBuffers {
-                    positions,
-                    normals,
-                    indices,
-                } = heightmap.generate_triangle_buffers();
-
-                if !positions.is_empty() {
-                    render_ctx.face_routine.add_base_mesh(
-                        &render_ctx.renderer,
+            Some(RenderableThing::MeshProxy(proxy)) => {
+                // Only the bounding box is drawn, regardless of the edge mode
+                if let Some(wireframe_routine) = &mut render_ctx.wireframe_routine {
+                    let LineBuffers { positions, colors } =
+                        proxy.wire_box.generate_line_buffers()?;
+                    wireframe_routine.add_wireframe(
+                        &render_ctx.renderer.device,
                         &positions,
-                        &normals,
-                        &indices,
-                    );
+                        &colors,
+                    )
                 }
             }
+            Some(RenderableThing::HeightMap(heightmap)) => {
+                Self::add_base_mesh(render_ctx, heightmap.generate_triangle_buffers())?;
+            }
             None => { /* Ignore */ }
         }
+
+        // The selection preview is drawn over whatever is being displayed
+        if let (Some(LineBuffers { positions, colors }), Some(wireframe_routine)) =
+            (&self.selection_preview, &mut render_ctx.wireframe_routine)
+        {
+            if !positions.is_empty() {
+                wireframe_routine.add_wireframe(&render_ctx.renderer.device, positions, colors)
+            }
+        }
+        if let (Some(preview_mesh), Some(wireframe_routine)) =
+            (&self.preview_mesh, &mut render_ctx.wireframe_routine)
+        {
+            let LineBuffers { positions, colors } = preview_mesh.generate_line_buffers()?;
+            wireframe_routine.add_wireframe(&render_ctx.renderer.device, &positions, &colors)
+        }
+        if let (Some(layer), Some(wireframe_routine)) = (
+            self.slice_layers
+                .as_ref()
+                .and_then(|layers| layers.get(viewport_settings.layer_index)),
+            &mut render_ctx.wireframe_routine,
+        ) {
+            let LineBuffers { positions, colors } = Self::layer_line_buffers(layer);
+            if !positions.is_empty() {
+                wireframe_routine.add_wireframe(&render_ctx.renderer.device, &positions, &colors)
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the lines for the contours of a sliced layer. Outer contours are
+    /// green, and holes are red.
+    fn layer_line_buffers(layer: &SliceLayer) -> LineBuffers {
+        let mut positions = vec![];
+        let mut colors = vec![];
+        for contour in &layer.contours {
+            let color = if contour.is_hole {
+                Vec3::new(1.0, 0.2, 0.2)
+            } else {
+                Vec3::new(0.2, 1.0, 0.2)
+            };
+            for (a, b) in contour.points.iter_cpy().circular_tuple_windows() {
+                positions.extend([a, b]);
+                colors.extend([color, color]);
+            }
+        }
+        LineBuffers { positions, colors }
+    }
+
+    /// Draws the base mesh with the face routine. In the degraded rendering
+    /// mode, where the face routine is not available, the mesh is drawn using
+    /// the PBR renderer instead, which ignores vertex colors and uvs.
+    fn add_base_mesh(render_ctx: &mut RenderContext, buffers: VertexIndexBuffers) -> Result<()> {
+        if buffers.positions.is_empty() {
+            return Ok(());
+        }
+        if let Some(face_routine) = &mut render_ctx.face_routine {
+            face_routine.add_base_mesh(&render_ctx.renderer, &buffers);
+        } else {
+            let mut builder = r3::MeshBuilder::new(buffers.positions, r3::Handedness::Left)
+                .with_indices(buffers.indices);
+            // Without normals, rend3 computes them when building the mesh
+            if !buffers.normals.is_empty() {
+                builder = builder.with_vertex_normals(buffers.normals);
+            }
+            let r3_mesh = builder
+                .build()
+                .map_err(|err| anyhow!("Could not build mesh: {err:?}"))?;
+            render_ctx.add_mesh_as_object(
+                r3_mesh,
+                Some(r3::PbrMaterial {
+                    albedo: r3::AlbedoComponent::Value(Vec4::new(0.8, 0.8, 0.8, 1.0)),
+                    ..Default::default()
+                }),
+            );
+        }
+        Ok(())
+    }
+
+    /// Draws the mesh using the PBR renderer, with one object per material.
+    /// Faces without a valid material use the default material.
+    ///
+    /// NOTE: Material textures are not drawn yet, since the triangle buffers
+    /// don't carry UVs. Only the base color, roughness and metallic factors
+    /// are used.
+    fn add_material_objects(
+        render_ctx: &mut RenderContext,
+        mesh: &HalfEdgeMesh,
+        materials: &MaterialRegistry,
+    ) -> Result<()> {
+        for (material_idx, buffers) in mesh.generate_triangle_buffers_by_material()? {
+            if buffers.positions.is_empty() {
+                continue;
+            }
+            let r3_mesh = r3::MeshBuilder::new(buffers.positions, r3::Handedness::Left)
+                .with_vertex_normals(buffers.normals)
+                .with_indices(buffers.indices)
+                .build()
+                .map_err(|err| anyhow!("Could not build mesh: {err:?}"))?;
+            let material = materials
+                .for_channel_value(material_idx as f32)
+                .cloned()
+                .unwrap_or_else(|| Material::new("default"));
+            render_ctx.add_mesh_as_object(
+                r3_mesh,
+                Some(r3::PbrMaterial {
+                    albedo: r3::AlbedoComponent::Value(material.base_color.extend(1.0)),
+                    roughness_factor: Some(material.roughness),
+                    metallic_factor: Some(material.metallic),
+                    ..Default::default()
+                }),
+            );
+        }
         Ok(())
     }
 
@@ -250,6 +419,7 @@ impl ApplicationContext {
             let (bjk_graph, mapping, params) =
                 self.generate_bjk_graph(&editor_state.graph, custom_state)?;
             let gizmos = self.node_gizmo_states.to_bjk_data(&mapping);
+            let cook_start = std::time::Instant::now();
             let program_result = blackjack_engine::graph_interpreter::run_graph(
                 &lua_runtime.lua,
                 &bjk_graph,
@@ -257,8 +427,10 @@ impl ApplicationContext {
                 params,
                 &lua_runtime.node_definitions,
                 Some(gizmos),
+                custom_state.base_dir.as_deref(),
             )?;
 
+            self.last_cook_time = Some(cook_start.elapsed());
             self.renderable_thing = program_result.renderable;
             if let Some(updated_gizmos) = program_result.updated_gizmos {
                 self.node_gizmo_states
@@ -278,17 +450,78 @@ impl ApplicationContext {
 
             // Running gizmos returns a set of updated values, we need to
             // refresh the UI graph values with those here.
-            graph_interop::set_parameters_from_external_values(
+            if graph_interop::set_parameters_from_external_values(
                 &mut editor_state.graph,
                 program_result.updated_values,
                 mapping,
-            )?;
+            )? {
+                custom_state.graph_changed = true;
+            }
         } else {
             self.renderable_thing = None;
+            self.last_cook_time = None;
         }
         Ok(())
     }
 
+    /// Cooks the mesh going into the node whose selection parameter is being
+    /// edited, and returns the lines highlighting the elements that the
+    /// selection matches on it.
+    pub fn run_selection_preview(
+        &self,
+        editor_state: &graph::GraphEditorState,
+        custom_state: &graph::CustomGraphState,
+        lua_runtime: &LuaRuntime,
+    ) -> Result<Option<LineBuffers>> {
+        let focused = match &custom_state.focused_selection {
+            Some(focused) => focused,
+            None => return Ok(None),
+        };
+        let (bjk_graph, mapping, params) =
+            self.generate_bjk_graph(&editor_state.graph, custom_state)?;
+        let node_id = mapping[focused.node_id];
+
+        let selection = match params
+            .0
+            .get(&ExternalParameter::new(node_id, focused.param_name.clone()))
+        {
+            Some(BlackjackValue::Selection(_, Some(selection))) => selection.clone(),
+            _ => return Ok(None),
+        };
+
+        // Selections refer to the first mesh going into the node
+        let upstream = bjk_graph.nodes[node_id]
+            .inputs
+            .iter()
+            .find_map(|input| match &input.kind {
+                DependencyKind::Connection { node, param_name }
+                    if input.data_type == DataType::Mesh =>
+                {
+                    Some((*node, param_name.clone()))
+                }
+                _ => None,
+            });
+        let (upstream_node, output_name) = match upstream {
+            Some(upstream) => upstream,
+            None => return Ok(None),
+        };
+
+        match blackjack_engine::graph_interpreter::run_node_output(
+            &lua_runtime.lua,
+            &bjk_graph,
+            upstream_node,
+            &output_name,
+            params,
+            &lua_runtime.node_definitions,
+            custom_state.base_dir.as_deref(),
+        )? {
+            RenderableThing::HalfEdgeMesh(mesh) => Ok(Some(
+                mesh.generate_selection_highlight_buffers(focused.kind, &selection)?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
     pub fn run_side_effects(
         &mut self,
         editor_state: &mut graph::GraphEditorState,
@@ -307,6 +540,7 @@ impl ApplicationContext {
                 params,
                 &lua_runtime.node_definitions,
                 None,
+                custom_state.base_dir.as_deref(),
             )?;
         }
         Ok(())
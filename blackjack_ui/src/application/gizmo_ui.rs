@@ -18,6 +18,7 @@ use egui_node_graph::{Node, NodeId};
 use glam::Mat4;
 use slotmap::SecondaryMap;
 
+use crate::keymap::{Action, KEYMAP};
 use crate::{graph::graph_interop::NodeMapping, prelude::graph::NodeData};
 
 use super::viewport_3d::Viewport3d;
@@ -76,21 +77,20 @@ pub fn draw_gizmo_ui_viewport(
             if has_focus {
                 ui.allocate_ui_at_rect(viewport.viewport_rect().shrink(10.0), |ui| {
                     gizmo_label(ui);
+                    let button = |ui: &mut egui::Ui, label: &str, action: Action| {
+                        let text = format!("{label} ({})", KEYMAP.shortcut_text(action));
+                        ui.button(text).clicked() || KEYMAP.pressed(action, &ui.input())
+                    };
                     if transform_gizmo.translation_enabled
-                        && (ui.button("Move (G)").clicked() || ui.input().key_pressed(egui::Key::G))
+                        && button(ui, "Move", Action::GizmoTranslate)
                     {
                         transform_gizmo.gizmo_mode = TransformGizmoMode::Translate;
                     }
-                    if transform_gizmo.rotation_enabled
-                        && (ui.button("Rotate (R)").clicked()
-                            || ui.input().key_pressed(egui::Key::R))
+                    if transform_gizmo.rotation_enabled && button(ui, "Rotate", Action::GizmoRotate)
                     {
                         transform_gizmo.gizmo_mode = TransformGizmoMode::Rotate;
                     }
-                    if transform_gizmo.scale_enabled
-                        && (ui.button("Scale (S)").clicked()
-                            || ui.input().key_pressed(egui::Key::S))
-                    {
+                    if transform_gizmo.scale_enabled && button(ui, "Scale", Action::GizmoScale) {
                         transform_gizmo.gizmo_mode = TransformGizmoMode::Scale;
                     }
                 });
@@ -124,6 +124,11 @@ pub fn draw_gizmo_ui_viewport(
                 responses.push(GizmoViewportResponse::GizmoIsInteracted);
                 let updated_matrix = Mat4::from_cols_array_2d(&response.transform);
                 transform_gizmo.set_from_matrix(updated_matrix);
+                if matches!(transform_gizmo.gizmo_mode, TransformGizmoMode::Translate) {
+                    let position = transform_gizmo.pre_translation + transform_gizmo.translation;
+                    transform_gizmo.translation =
+                        viewport.snap_point(position) - transform_gizmo.pre_translation;
+                }
             }
         }
         BlackjackGizmo::None => {}
@@ -14,6 +14,7 @@ use crate::{
 use blackjack_engine::graph::{
     serialization::SerializedBjkSnippet, BlackjackValue, DataType, NodeDefinitions,
 };
+use egui_node_graph::NodeTemplateTrait;
 use egui_wgpu::renderer::{RenderPass, ScreenDescriptor};
 
 use super::{blackjack_theme, gizmo_ui::UiNodeGizmoStates};
@@ -533,4 +534,50 @@ impl GraphEditor {
 
         Ok(())
     }
+
+    /// Adds a new `op_name` node to the graph and makes it the active node.
+    /// The `values` override the defaults of its input parameters. The node is
+    /// placed to the right of the previously active node, if any.
+    pub fn add_active_node(
+        &mut self,
+        op_name: &str,
+        values: Vec<(&str, BlackjackValue)>,
+    ) -> Result<graph::NodeId> {
+        if self
+            .custom_state
+            .node_definitions
+            .node_def(op_name)
+            .is_none()
+        {
+            bail!("There is no node named {op_name}");
+        }
+        let template = graph::NodeOpName(op_name.into());
+        let custom_state = &mut self.custom_state;
+        let editor_state = &mut self.editor_state;
+        let node_id = editor_state.graph.add_node(
+            template.node_graph_label(custom_state),
+            template.user_data(custom_state),
+            |graph, node_id| template.build_node(graph, custom_state, node_id),
+        );
+        for (param_name, value) in values {
+            let input_id = editor_state.graph[node_id].get_input(param_name)?;
+            editor_state.graph[input_id].value = ValueTypeUi(value);
+        }
+
+        let position = custom_state
+            .active_node
+            .and_then(|active| editor_state.node_positions.get(active))
+            .map(|pos| *pos + egui::vec2(250.0, 0.0))
+            .unwrap_or_else(|| egui::pos2(50.0, 50.0) - editor_state.pan_zoom.pan);
+        editor_state.node_positions.insert(node_id, position);
+        editor_state.node_order.push(node_id);
+
+        if let Some(prev_active) = custom_state.active_node {
+            custom_state.gizmo_states.node_left_active(prev_active);
+        }
+        custom_state.active_node = Some(node_id);
+        custom_state.gizmo_states.node_is_active(node_id);
+        custom_state.graph_changed = true;
+        Ok(node_id)
+    }
 }
@@ -10,6 +10,7 @@ use crate::prelude::{
 };
 use blackjack_engine::{
     lua_engine::RenderableThing,
+    materials::{Material, MaterialRegistry},
     prelude::{selection::SelectionExpression, ChannelKeyType, ChannelValueType, HalfEdgeMesh},
 };
 use egui::*;
@@ -20,6 +21,7 @@ use slotmap::Key;
 pub enum InspectorTab {
     Properties,
     Spreadsheet,
+    Materials,
     Debug,
 }
 
@@ -27,6 +29,7 @@ pub struct InspectorTabs {
     current_view: InspectorTab,
     properties: PropertiesTab,
     spreadsheet: SpreadsheetTab,
+    materials: MaterialsTab,
     debug: DebugTab,
 }
 
@@ -40,6 +43,10 @@ impl InspectorTabs {
             spreadsheet: SpreadsheetTab {
                 current_view: SpreadsheetViews::Vertices,
             },
+            materials: MaterialsTab {
+                selected: None,
+                name_edit: String::new(),
+            },
             debug: DebugTab {
                 mesh_element: ChannelKeyType::VertexId,
                 v_query: "".into(),
@@ -76,6 +83,13 @@ pub struct SpreadsheetTab {
     pub current_view: SpreadsheetViews,
 }
 
+pub struct MaterialsTab {
+    /// The index of the material being edited, if any.
+    selected: Option<usize>,
+    /// The name of the selected material, as it's being edited.
+    name_edit: String,
+}
+
 pub struct DebugTab {
     pub mesh_element: ChannelKeyType,
     pub v_query: String,
@@ -90,6 +104,21 @@ impl InspectorTabs {
         renderable_thing: Option<&RenderableThing>,
         editor_state: &mut graph::GraphEditorState,
         custom_state: &mut graph::CustomGraphState,
+    ) {
+        let promoted_params = custom_state.promoted_params.clone();
+        let materials = custom_state.materials.clone();
+        self.tabs_ui(ui, renderable_thing, editor_state, custom_state);
+        if custom_state.promoted_params != promoted_params || custom_state.materials != materials {
+            custom_state.graph_changed = true;
+        }
+    }
+
+    fn tabs_ui(
+        &mut self,
+        ui: &mut Ui,
+        renderable_thing: Option<&RenderableThing>,
+        editor_state: &mut graph::GraphEditorState,
+        custom_state: &mut graph::CustomGraphState,
     ) {
         match renderable_thing {
             Some(RenderableThing::HalfEdgeMesh(mesh)) => {
@@ -104,6 +133,11 @@ impl InspectorTabs {
                         InspectorTab::Spreadsheet,
                         "Spreadsheet",
                     );
+                    ui.selectable_value(
+                        &mut self.current_view,
+                        InspectorTab::Materials,
+                        "Materials",
+                    );
                     ui.selectable_value(&mut self.current_view, InspectorTab::Debug, "Debug");
                 });
                 ui.separator();
@@ -111,9 +145,20 @@ impl InspectorTabs {
                 match self.current_view {
                     InspectorTab::Properties => self.properties.ui(ui, editor_state, custom_state),
                     InspectorTab::Spreadsheet => self.spreadsheet.ui(ui, Some(mesh)),
+                    InspectorTab::Materials => self.materials.ui(ui, custom_state),
                     InspectorTab::Debug => self.debug.ui(ui, Some(mesh)),
                 }
             }
+            Some(RenderableThing::MeshProxy(proxy)) => {
+                self.properties.ui(ui, editor_state, custom_state);
+                ui.separator();
+                ui.label(format!(
+                    "{} is not loaded yet. It has {} vertices and {} faces.",
+                    proxy.path.display(),
+                    proxy.summary.num_vertices,
+                    proxy.summary.num_faces,
+                ));
+            }
             Some(RenderableThing::HeightMap(_)) => {
                 // TODO: @Heightmap
             }
@@ -122,12 +167,14 @@ impl InspectorTabs {
     }
 }
 
-pub fn tiny_checkbox(ui: &mut Ui, value: &mut bool) {
+/// A checkbox without a label. Returns whether it was toggled.
+pub fn tiny_checkbox(ui: &mut Ui, value: &mut bool) -> bool {
     let mut child_ui = ui.child_ui(ui.available_rect_before_wrap(), *ui.layout());
     child_ui.spacing_mut().icon_spacing = 0.0;
     child_ui.spacing_mut().interact_size = egui::vec2(16.0, 16.0);
-    child_ui.checkbox(value, "");
+    let changed = child_ui.checkbox(value, "").changed();
     ui.add_space(24.0);
+    changed
 }
 
 impl PropertiesTab {
@@ -210,7 +257,9 @@ impl PropertiesTab {
                     ui.label(param_name);
                 } else {
                     ui.horizontal(|ui| {
-                        tiny_checkbox(ui, &mut graph[param].shown_inline);
+                        if tiny_checkbox(ui, &mut graph[param].shown_inline) {
+                            custom_state.graph_changed = true;
+                        }
                         let mut defer_remove_promoted_param = None;
                         if let Some(ref promoted_name) = custom_state.promoted_params.get(&param) {
                             if ui
@@ -333,6 +382,141 @@ impl SpreadsheetTab {
         }
     }
 }
+
+impl MaterialsTab {
+    fn select(&mut self, selected: Option<usize>, materials: &MaterialRegistry) {
+        self.selected = selected;
+        self.name_edit = selected
+            .and_then(|idx| materials.get(idx))
+            .map(|m| m.name.clone())
+            .unwrap_or_default();
+    }
+
+    fn ui(&mut self, ui: &mut Ui, custom_state: &mut CustomGraphState) {
+        let materials = &mut custom_state.materials;
+        ui.horizontal(|ui| {
+            if ui.button("+ New").clicked() {
+                let name = materials.unique_name("Material");
+                let added = materials.add(Material::new(name)).ok();
+                self.select(added, materials);
+            }
+            if let Some(selected) = self.selected {
+                if ui.button("✖ Remove").clicked() {
+                    materials.remove(selected);
+                    self.select(None, materials);
+                }
+            }
+        });
+        ui.separator();
+
+        let mut clicked = None;
+        ScrollArea::vertical()
+            .id_source("material_list")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for (i, material) in materials.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let c = material.base_color;
+                        let (rect, _) = ui.allocate_exact_size(vec2(12.0, 12.0), Sense::hover());
+                        ui.painter()
+                            .rect_filled(rect, 2.0, Rgba::from_rgb(c.x, c.y, c.z));
+                        if ui
+                            .selectable_label(self.selected == Some(i), &material.name)
+                            .clicked()
+                        {
+                            clicked = Some(i);
+                        }
+                    });
+                }
+            });
+        if clicked.is_some() {
+            self.select(clicked, materials);
+        }
+        ui.separator();
+
+        let selected = match self.selected {
+            Some(selected) => selected,
+            None => {
+                ui.label("No material selected.");
+                return;
+            }
+        };
+
+        // Names must be unique, so a new name is only applied when no other
+        // material is using it.
+        let name_taken = materials
+            .index_of(&self.name_edit)
+            .map(|idx| idx != selected)
+            .unwrap_or(false);
+        let material = match materials.get_mut(selected) {
+            Some(material) => material,
+            None => {
+                self.select(None, materials);
+                return;
+            }
+        };
+
+        Grid::new("material_properties")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut self.name_edit);
+                ui.end_row();
+
+                ui.label("Base color");
+                let mut rgb = material.base_color.to_array();
+                if color_picker::color_edit_button_rgb(ui, &mut rgb).changed() {
+                    material.base_color = Vec3::from(rgb);
+                }
+                ui.end_row();
+
+                ui.label("Roughness");
+                ui.add(Slider::new(&mut material.roughness, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Metallic");
+                ui.add(Slider::new(&mut material.metallic, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Base color texture");
+                texture_path_ui(ui, &mut material.base_color_texture);
+                ui.end_row();
+
+                ui.label("Normal texture");
+                texture_path_ui(ui, &mut material.normal_texture);
+                ui.end_row();
+            });
+
+        if name_taken {
+            ui.label(
+                RichText::new(format!("The name '{}' is already in use", self.name_edit))
+                    .color(Color32::YELLOW),
+            );
+        } else if !self.name_edit.is_empty() && self.name_edit != material.name {
+            material.name = self.name_edit.clone();
+        }
+    }
+}
+
+/// Shows a texture path with a button to pick a new one, and another one to
+/// clear it.
+fn texture_path_ui(ui: &mut Ui, path: &mut Option<String>) {
+    ui.horizontal(|ui| {
+        ui.label(path.as_deref().unwrap_or("None"));
+        if ui.small_button("Browse…").clicked() {
+            if let Some(new_path) = rfd::FileDialog::new()
+                .add_filter("Image", &["png", "jpg", "jpeg", "tga", "bmp"])
+                .pick_file()
+            {
+                *path = Some(new_path.to_string_lossy().into_owned());
+            }
+        }
+        if path.is_some() && ui.small_button("✖").clicked() {
+            *path = None;
+        }
+    });
+}
+
 impl DebugTab {
     fn ui(&mut self, ui: &mut Ui, mesh: Option<&HalfEdgeMesh>) {
         ui.horizontal(|ui| {
@@ -0,0 +1,159 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use blackjack_engine::lua_engine::{console, LuaRuntime, RenderableThing};
+
+use crate::prelude::*;
+
+/// The maximum number of entries kept in the console output. Older entries
+/// are discarded.
+const MAX_LOG_ENTRIES: usize = 200;
+
+struct ConsoleEntry {
+    input: String,
+    output: Result<String, String>,
+}
+
+/// An interactive Lua console, running code in the same runtime as the graph.
+/// The `sel()` function returns a copy of the output of the active node, for
+/// quick inspection (e.g. `sel():bbox()`).
+#[derive(Default)]
+pub struct LuaConsole {
+    pub open: bool,
+    input: String,
+    log: Vec<ConsoleEntry>,
+    /// Previously submitted lines, with the most recent one at the back.
+    history: Vec<String>,
+    /// The position in `history` while browsing it with the arrow keys.
+    history_cursor: Option<usize>,
+    scroll_to_bottom: bool,
+}
+
+impl LuaConsole {
+    pub fn ui(
+        &mut self,
+        ctx: &egui::Context,
+        lua_runtime: &LuaRuntime,
+        selected: Option<&RenderableThing>,
+    ) {
+        let mut open = self.open;
+        egui::Window::new("Lua Console")
+            .open(&mut open)
+            .default_size(egui::vec2(500.0, 300.0))
+            .show(ctx, |ui| {
+                let input_height = ui.spacing().interact_size.y * 2.0;
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .max_height(ui.available_height() - input_height)
+                    .show(ui, |ui| {
+                        for entry in &self.log {
+                            ui.monospace(format!("> {}", entry.input));
+                            match &entry.output {
+                                Ok(output) if output.is_empty() => {}
+                                Ok(output) => {
+                                    ui.monospace(output);
+                                }
+                                Err(err) => {
+                                    ui.colored_label(egui::Color32::RED, err);
+                                }
+                            }
+                        }
+                        if std::mem::take(&mut self.scroll_to_bottom) {
+                            ui.scroll_to_cursor(Some(egui::Align::BOTTOM));
+                        }
+                    });
+
+                ui.separator();
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.input)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("Lua expression or statement"),
+                );
+                if response.has_focus() {
+                    let input = ui.input();
+                    if input.key_pressed(egui::Key::ArrowUp) {
+                        self.browse_history(-1);
+                    } else if input.key_pressed(egui::Key::ArrowDown) {
+                        self.browse_history(1);
+                    }
+                }
+                if response.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+                    self.submit(lua_runtime, selected);
+                    response.request_focus();
+                }
+            });
+        self.open = open;
+    }
+
+    /// Moves through the history by `offset` entries, replacing the input
+    /// line. Moving past the most recent entry clears the input.
+    fn browse_history(&mut self, offset: isize) {
+        if self.history.is_empty() {
+            return;
+        }
+        let next = match self.history_cursor {
+            Some(cursor) => cursor as isize + offset,
+            None if offset < 0 => self.history.len() as isize - 1,
+            None => return,
+        };
+        if next >= self.history.len() as isize {
+            self.history_cursor = None;
+            self.input.clear();
+        } else {
+            let next = next.max(0) as usize;
+            self.history_cursor = Some(next);
+            self.input = self.history[next].clone();
+        }
+    }
+
+    fn submit(&mut self, lua_runtime: &LuaRuntime, selected: Option<&RenderableThing>) {
+        let code = std::mem::take(&mut self.input);
+        if code.trim().is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&code) {
+            self.history.push(code.clone());
+        }
+        self.history_cursor = None;
+
+        let output = Self::install_selection(lua_runtime, selected)
+            .and_then(|_| console::eval(&lua_runtime.lua, &code))
+            .map_err(|err| format!("{err}"));
+        self.log.push(ConsoleEntry {
+            input: code,
+            output,
+        });
+        if self.log.len() > MAX_LOG_ENTRIES {
+            self.log.remove(0);
+        }
+        self.scroll_to_bottom = true;
+    }
+
+    /// Defines the `sel()` global, returning a copy of the `selected` output
+    /// so the console can't modify the displayed one.
+    fn install_selection(
+        lua_runtime: &LuaRuntime,
+        selected: Option<&RenderableThing>,
+    ) -> Result<()> {
+        let lua = &lua_runtime.lua;
+        let sel = match selected {
+            Some(RenderableThing::HalfEdgeMesh(mesh)) => {
+                let mesh = mesh.clone();
+                lua.create_function(move |_, ()| Ok(Some(mesh.clone())))?
+            }
+            Some(RenderableThing::HeightMap(heightmap)) => {
+                let heightmap = heightmap.clone();
+                lua.create_function(move |_, ()| Ok(Some(heightmap.clone())))?
+            }
+            Some(RenderableThing::MeshProxy(_)) | None => {
+                lua.create_function(|_, ()| Ok(None::<bool>))?
+            }
+        };
+        lua.globals().set("sel", sel)?;
+        Ok(())
+    }
+}
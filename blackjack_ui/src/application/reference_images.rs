@@ -0,0 +1,219 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::path::{Path, PathBuf};
+
+use blackjack_engine::graph::file_path::{relativize_file_path, resolve_file_path};
+use blackjack_engine::reference_images::{ReferenceImage, ReferencePlane};
+use rend3::types::{MipmapCount, MipmapSource, Texture, TextureHandle};
+
+use crate::prelude::{graph::CustomGraphState, *};
+
+/// An image uploaded to the GPU, ready to be drawn.
+struct LoadedImage {
+    texture: TextureHandle,
+    /// Width divided by height.
+    aspect: f32,
+}
+
+/// Shows the reference images of the project in the viewport, as textured
+/// planes, and the window to manage them. Images are loaded the first time
+/// they are displayed and kept around, so changing their placement or opacity
+/// does not reload them.
+#[derive(Default)]
+pub struct ReferenceImages {
+    pub open: bool,
+    /// Indexed by resolved path. Failed loads are kept too, so they are not
+    /// retried every frame.
+    loaded: HashMap<PathBuf, Result<LoadedImage, String>>,
+}
+
+impl ReferenceImages {
+    pub fn ui(&mut self, ctx: &egui::Context, custom_state: &mut CustomGraphState) {
+        let previous_images = custom_state.reference_images.clone();
+        let mut open = self.open;
+        egui::Window::new("Reference Images")
+            .open(&mut open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Add…").clicked() {
+                        self.pick_images(custom_state);
+                    }
+                    if ui.button("Reload").clicked() {
+                        self.loaded.clear();
+                    }
+                });
+                ui.separator();
+
+                let base_dir = custom_state.base_dir.as_deref();
+                let mut to_remove = None;
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (idx, image) in custom_state.reference_images.iter_mut().enumerate() {
+                        ui.push_id(idx, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut image.visible, "");
+                                ui.label(&image.path);
+                                if ui.button("🗑").clicked() {
+                                    to_remove = Some(idx);
+                                }
+                            });
+                            let path = resolve_file_path(&image.path, base_dir);
+                            if let Some(Err(err)) = self.loaded.get(&path) {
+                                ui.colored_label(egui::Color32::RED, err);
+                            }
+                            image_properties_ui(ui, image);
+                        });
+                        ui.separator();
+                    }
+                });
+                if let Some(idx) = to_remove {
+                    custom_state.reference_images.remove(idx);
+                }
+            });
+        self.open = open;
+        if custom_state.reference_images != previous_images {
+            custom_state.graph_changed = true;
+        }
+    }
+
+    fn pick_images(&mut self, custom_state: &mut CustomGraphState) {
+        let paths = rfd::FileDialog::new()
+            .add_filter("Image", &["png", "jpg", "jpeg"])
+            .pick_files()
+            .unwrap_or_default();
+        for path in paths {
+            let path = relativize_file_path(&path, custom_state.base_dir.as_deref());
+            custom_state.reference_images.push(ReferenceImage::new(
+                path.to_string_lossy(),
+                ReferencePlane::Front,
+            ));
+        }
+    }
+
+    /// Adds the visible reference images to the scene. Must be called every
+    /// frame, after the scene objects are cleared.
+    pub fn add_to_scene(
+        &mut self,
+        render_ctx: &mut RenderContext,
+        custom_state: &CustomGraphState,
+    ) {
+        for image in &custom_state.reference_images {
+            if !image.visible || image.opacity <= 0.0 {
+                continue;
+            }
+            let path = resolve_file_path(&image.path, custom_state.base_dir.as_deref());
+            let loaded = self.loaded.entry(path).or_insert_with_key(|path| {
+                load_image(render_ctx, path).map_err(|err| format!("{err}"))
+            });
+            if let Ok(loaded) = loaded {
+                if let Err(err) = add_image_plane(render_ctx, image, loaded) {
+                    println!("Error drawing reference image {}: {err}", image.path);
+                }
+            }
+        }
+    }
+}
+
+fn image_properties_ui(ui: &mut egui::Ui, image: &mut ReferenceImage) {
+    egui::Grid::new("properties").num_columns(2).show(ui, |ui| {
+        ui.label("Plane");
+        ui.horizontal(|ui| {
+            for plane in ReferencePlane::ALL {
+                ui.selectable_value(&mut image.plane, plane, plane.label());
+            }
+        });
+        ui.end_row();
+
+        ui.label("Opacity");
+        ui.add(egui::Slider::new(&mut image.opacity, 0.0..=1.0));
+        ui.end_row();
+
+        ui.label("Width");
+        ui.add(
+            egui::DragValue::new(&mut image.width)
+                .speed(0.1)
+                .clamp_range(0.01..=f32::INFINITY),
+        );
+        ui.end_row();
+
+        ui.label("Center");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut image.center.x).speed(0.1));
+            ui.add(egui::DragValue::new(&mut image.center.y).speed(0.1));
+            ui.add(egui::DragValue::new(&mut image.center.z).speed(0.1));
+        });
+        ui.end_row();
+    });
+}
+
+fn load_image(render_ctx: &RenderContext, path: &Path) -> Result<LoadedImage> {
+    let image = image::open(path)
+        .with_context(|| format!("Could not load image {}", path.display()))?
+        .to_rgba8();
+    let max_size = render_ctx.renderer.limits.max_texture_dimension_2d;
+    if image.width() > max_size || image.height() > max_size {
+        bail!("The image is too large. The maximum size is {max_size}x{max_size}");
+    }
+    let aspect = image.width() as f32 / image.height() as f32;
+    let texture = render_ctx.renderer.add_texture_2d(Texture {
+        label: Some(path.to_string_lossy().into()),
+        size: UVec2::new(image.width(), image.height()),
+        data: image.into_raw(),
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        mip_count: MipmapCount::Maximum,
+        mip_source: MipmapSource::Generated,
+    });
+    Ok(LoadedImage { texture, aspect })
+}
+
+/// Draws the `image` as a quad, visible from both sides. Reference images are
+/// unlit, so they look the same regardless of the scene lights.
+fn add_image_plane(
+    render_ctx: &mut RenderContext,
+    image: &ReferenceImage,
+    loaded: &LoadedImage,
+) -> Result<()> {
+    let corners = image.corners(loaded.aspect);
+    let normal = (corners[1] - corners[0])
+        .cross(corners[3] - corners[0])
+        .normalize_or_zero();
+    let uvs = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(1.0, 0.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(0.0, 1.0),
+    ];
+
+    // The back side gets its own vertices, with flipped normals
+    let positions = corners.iter().chain(corners.iter()).copied().collect_vec();
+    let normals = std::iter::repeat(normal)
+        .take(4)
+        .chain(std::iter::repeat(-normal).take(4))
+        .collect_vec();
+    let uvs = uvs.iter().chain(uvs.iter()).copied().collect_vec();
+    let indices = vec![0, 1, 2, 0, 2, 3, 4, 6, 5, 4, 7, 6];
+
+    let mesh = r3::MeshBuilder::new(positions, r3::Handedness::Left)
+        .with_vertex_normals(normals)
+        .with_vertex_uv0(uvs)
+        .with_indices(indices)
+        .build()
+        .map_err(|err| anyhow!("Could not build mesh: {err:?}"))?;
+    render_ctx.add_mesh_as_object(
+        mesh,
+        Some(r3::PbrMaterial {
+            albedo: r3::AlbedoComponent::TextureValue {
+                texture: loaded.texture.clone(),
+                value: Vec4::new(1.0, 1.0, 1.0, image.opacity),
+            },
+            transparency: r3::Transparency::Blend,
+            unlit: true,
+            ..Default::default()
+        }),
+    );
+    Ok(())
+}
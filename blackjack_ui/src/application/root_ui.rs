@@ -10,6 +10,8 @@ use std::path::PathBuf;
 pub enum AppRootAction {
     Save(PathBuf),
     Load(PathBuf),
+    Undo,
+    Redo,
 }
 
 impl RootViewport {
@@ -41,8 +43,39 @@ impl RootViewport {
                     ui.separator();
                     ui.add_enabled_ui(false, |ui| ui.button("Quit"));
                 });
+                ui.menu_button("Edit", |ui| {
+                    if ui
+                        .add_enabled(self.undo_history.can_undo(), egui::Button::new("Undo"))
+                        .clicked()
+                    {
+                        action = Some(AppRootAction::Undo);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(self.undo_history.can_redo(), egui::Button::new("Redo"))
+                        .clicked()
+                    {
+                        action = Some(AppRootAction::Redo);
+                        ui.close_menu();
+                    }
+                });
                 ui.menu_button("Window", |ui| {
                     ui.checkbox(&mut self.diagnostics_open, "Diagnostics");
+                    ui.checkbox(&mut self.lua_console.open, "Lua Console");
+                    ui.checkbox(&mut self.reference_images.open, "Reference Images");
+                    ui.separator();
+                    ui.label("UI Scale");
+                    // NOTE: Discrete values instead of a slider, because the
+                    // slider would move under the cursor while rescaling.
+                    ui.horizontal(|ui| {
+                        for scale in [0.75, 1.0, 1.25, 1.5, 2.0] {
+                            ui.selectable_value(
+                                &mut self.ui_scale,
+                                scale,
+                                format!("{}%", (scale * 100.0) as u32),
+                            );
+                        }
+                    });
                 });
             });
         });
@@ -50,11 +83,20 @@ impl RootViewport {
         action
     }
 
-    pub fn diagnostics_ui(&mut self) {
+    pub fn diagnostics_ui(&mut self, render_ctx: &RenderContext) {
         egui::Window::new("Diagnostics")
             .open(&mut self.diagnostics_open)
             .show(&self.egui_context, |ui| {
+                if render_ctx.capabilities.degraded() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        "Degraded rendering mode: Wireframes, vertices and face \
+                         overlays are disabled.",
+                    );
+                }
                 ui.label(format!("HiDPI Scale: {}", ui.ctx().pixels_per_point()));
+                ui.label(format!("Monitor Scale: {}", self.scale_factor));
+                ui.label(format!("UI Scale: {}", self.ui_scale));
             });
     }
 
@@ -69,9 +111,8 @@ impl RootViewport {
                         .offscreen_viewports
                         .get_mut(&OffscreenViewport::Viewport3d)
                         .unwrap(),
-                    payload.app_context.renderable_thing.as_ref(),
-                    &payload.graph_editor,
-                    &mut payload.app_context.node_gizmo_states,
+                    &mut payload.app_context,
+                    &mut payload.graph_editor,
                 ) {
                     // TODO: Do something better for error reporting
                     println!("Error in viewport: {err}")
@@ -20,6 +20,16 @@ pub fn save(
     custom_state: &CustomGraphState,
     path: impl AsRef<Path>,
 ) -> Result<()> {
+    serialize(editor_state, custom_state)?.write_to_file(path)?;
+    Ok(())
+}
+
+/// Converts the current state of the graph editor into its serialized form,
+/// including the UI data.
+pub fn serialize(
+    editor_state: &GraphEditorState,
+    custom_state: &CustomGraphState,
+) -> Result<SerializedBjkGraph> {
     let (bjk_graph, mapping) =
         graph_interop::ui_graph_to_blackjack_graph(&editor_state.graph, custom_state)?;
     let external_param_values =
@@ -62,10 +72,10 @@ pub fn save(
         pan: Vec2::new(pan.x, pan.y),
         zoom: editor_state.pan_zoom.zoom,
     });
+    serialized.set_materials(custom_state.materials.clone());
+    serialized.set_reference_images(custom_state.reference_images.clone());
 
-    serialized.write_to_file(path)?;
-
-    Ok(())
+    Ok(serialized)
 }
 
 pub fn load(
@@ -74,15 +84,30 @@ pub fn load(
     gizmo_states: &UiNodeGizmoStates,
 ) -> Result<(GraphEditorState, CustomGraphState)> {
     let serialized = SerializedBjkGraph::load_from_file(&path)?;
+    let (editor_state, mut custom_state) = deserialize(serialized, node_definitions, gizmo_states)
+        .map_err(|err| {
+            anyhow!(
+                "Could not load the file at {}. {err}",
+                path.to_string_lossy()
+            )
+        })?;
+    custom_state.base_dir = path.parent().map(|p| p.to_path_buf());
+    Ok((editor_state, custom_state))
+}
+
+/// The inverse of [`serialize`]. Builds a new graph editor state from a
+/// serialized graph. The serialized graph must contain UI data.
+pub fn deserialize(
+    mut serialized: SerializedBjkGraph,
+    node_definitions: &NodeDefinitions,
+    gizmo_states: &UiNodeGizmoStates,
+) -> Result<(GraphEditorState, CustomGraphState)> {
+    let materials = std::mem::take(&mut serialized.materials);
+    let reference_images = std::mem::take(&mut serialized.reference_images);
     let (runtime, ui_data, id_idx_mappings) = serialized.into_runtime()?;
 
-    if ui_data.is_none() {
-        bail!(
-            "The file at {} doesn't have UI information. Cannot load.",
-            path.to_string_lossy()
-        )
-    }
-    let ui_data = ui_data.unwrap();
+    let ui_data =
+        ui_data.ok_or_else(|| anyhow!("The graph doesn't have UI information. Cannot load."))?;
 
     let (graph, mapping) = graph_interop::blackjack_graph_to_ui_graph(
         &runtime.graph,
@@ -145,6 +170,10 @@ pub fn load(
         node_definitions: node_definitions.share(),
         gizmo_states: gizmo_states.share(),
         promoted_params,
+        base_dir: None,
+        materials,
+        reference_images,
+        focused_selection: None,
     };
 
     Ok((editor_state, custom_state))
@@ -219,6 +248,14 @@ pub fn from_clipboard(
         node_definitions: _,
         promoted_params: _,
         gizmo_states: _,
+        base_dir: _,
+        // Materials are not part of snippets. Pasted nodes refer to materials
+        // by name, which are looked up in the destination graph.
+        materials: _,
+        // Reference images belong to the project, not to the pasted nodes.
+        reference_images: _,
+        // Transient UI state
+        focused_selection: _,
     } = custom_state;
     let GraphEditorState {
         // This is updated by `append_snippet_to_existing_ui_graph`
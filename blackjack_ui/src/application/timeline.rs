@@ -0,0 +1,107 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::time::{Duration, Instant};
+
+use blackjack_engine::time::FrameContext;
+
+/// Playback state for animated graphs. The current frame is installed in the
+/// Lua runtime before each cook, so nodes reading the time see it.
+pub struct Timeline {
+    pub frame: i32,
+    pub start: i32,
+    pub end: i32,
+    /// The playback rate. Playback never goes faster than this.
+    pub fps: f32,
+    pub playing: bool,
+    /// When the graph takes longer than a frame to cook, playback jumps
+    /// ahead to keep in sync with the wall clock instead of slowing down.
+    pub skip_frames: bool,
+    /// The instant at which the current frame started being shown.
+    last_advance: Option<Instant>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            frame: 1,
+            start: 1,
+            end: 120,
+            fps: 24.0,
+            playing: false,
+            skip_frames: true,
+            last_advance: None,
+        }
+    }
+}
+
+impl Timeline {
+    pub fn frame_context(&self) -> FrameContext {
+        FrameContext::new(self.frame, self.fps)
+    }
+
+    /// Advances the current frame according to the time elapsed since the
+    /// last call, looping back to the start when reaching the end. Returns
+    /// whether the frame changed.
+    pub fn advance(&mut self) -> bool {
+        if !self.playing {
+            self.last_advance = None;
+            return false;
+        }
+        let now = Instant::now();
+        let last = *self.last_advance.get_or_insert(now);
+        let frame_duration = Duration::from_secs_f32(1.0 / self.fps.max(1.0));
+        let elapsed = (now - last).as_secs_f32() / frame_duration.as_secs_f32();
+        let frames = elapsed.floor() as i32;
+        if frames == 0 {
+            return false;
+        }
+        let step = if self.skip_frames { frames } else { 1 };
+        self.last_advance = Some(if self.skip_frames {
+            last + frame_duration * frames as u32
+        } else {
+            now
+        });
+        let length = (self.end - self.start + 1).max(1);
+        self.frame = self.start + (self.frame - self.start + step).rem_euclid(length);
+        true
+    }
+
+    /// Draws the playback controls and the frame scrubber.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("⏮").clicked() {
+                self.frame = self.start;
+            }
+            let play_label = if self.playing { "⏸" } else { "▶" };
+            if ui.button(play_label).clicked() {
+                self.playing = !self.playing;
+            }
+            if ui.button("⏭").clicked() {
+                self.frame = self.end;
+            }
+
+            ui.label("Start");
+            ui.add(egui::DragValue::new(&mut self.start).clamp_range(i32::MIN..=self.end));
+            ui.label("End");
+            ui.add(egui::DragValue::new(&mut self.end).clamp_range(self.start..=i32::MAX));
+            ui.label("FPS");
+            ui.add(egui::DragValue::new(&mut self.fps).clamp_range(1.0..=240.0));
+            ui.checkbox(&mut self.skip_frames, "Skip frames");
+
+            // Scrubbing pauses playback
+            ui.spacing_mut().slider_width = ui.available_width() - 60.0;
+            let scrubber = ui.add(egui::Slider::new(&mut self.frame, self.start..=self.end));
+            if scrubber.dragged() {
+                self.playing = false;
+            }
+        });
+        self.frame = self.frame.clamp(self.start, self.end);
+        if self.playing {
+            ui.ctx().request_repaint();
+        }
+    }
+}
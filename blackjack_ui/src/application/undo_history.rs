@@ -0,0 +1,302 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use blackjack_engine::graph::serialization::SerializedBjkGraph;
+use serde::{Deserialize, Serialize};
+
+use crate::{application::serialization, prelude::graph::*, prelude::*};
+
+/// The maximum number of steps that can be undone. Older steps are discarded.
+const MAX_UNDO_STEPS: usize = 100;
+
+/// Snapshot-based undo history for the graph editor.
+///
+/// When the editor reports a change, the state of the graph is serialized and
+/// compared against the last known state. Any difference (created or deleted
+/// nodes, connections, parameter values, node positions...) is recorded as a
+/// new undo step. This captures edits done from the viewport, like gizmo
+/// manipulation, the same way as edits done from the graph editor.
+///
+/// While the user is interacting with a widget (e.g. dragging a slider or a
+/// gizmo) no snapshots are taken. This coalesces a whole drag into a single
+/// step that is recorded when the interaction ends.
+#[derive(Default, Serialize, Deserialize)]
+pub struct UndoHistory {
+    /// Snapshots of previous states, with the most recent one at the back.
+    undo_stack: VecDeque<String>,
+    /// Snapshots of undone states, with the most recently undone at the back.
+    redo_stack: Vec<String>,
+    /// The snapshot of the current state. `None` until the first call to
+    /// `track`.
+    #[serde(skip)]
+    current: Option<String>,
+    /// Set when a change was reported that has not been recorded yet.
+    #[serde(skip)]
+    pending: bool,
+}
+
+impl UndoHistory {
+    /// Serializes the graph for use as a snapshot. The view (pan and zoom) is
+    /// not part of the snapshot, so navigating the graph is not undoable.
+    fn snapshot(
+        editor_state: &GraphEditorState,
+        custom_state: &CustomGraphState,
+    ) -> Result<String> {
+        let mut serialized = serialization::serialize(editor_state, custom_state)?;
+        if let Some(ui_data) = &mut serialized.ui_data {
+            ui_data.pan = Vec2::ZERO;
+            ui_data.zoom = 1.0;
+        }
+        serialized.into_string()
+    }
+
+    /// Replaces the editor state with the given snapshot, keeping the current
+    /// view and the settings that don't belong to the graph. Returns the
+    /// snapshot of the restored state.
+    fn restore(
+        snapshot: &str,
+        editor_state: &mut GraphEditorState,
+        custom_state: &mut CustomGraphState,
+    ) -> Result<String> {
+        let (mut new_editor_state, mut new_custom_state) = serialization::deserialize(
+            SerializedBjkGraph::load_from_string(snapshot)?,
+            &custom_state.node_definitions,
+            &custom_state.gizmo_states,
+        )?;
+        std::mem::swap(&mut new_editor_state.pan_zoom, &mut editor_state.pan_zoom);
+        new_custom_state.base_dir = custom_state.base_dir.take();
+        *editor_state = new_editor_state;
+        *custom_state = new_custom_state;
+        // Snapshot again instead of reusing the given one. Node ids change
+        // when restoring, which may lead to slight differences in the output.
+        Self::snapshot(editor_state, custom_state)
+    }
+
+    /// Records a new undo step if the graph changed since the last recorded
+    /// state. Must be called once per frame. The graph is only snapshotted
+    /// after the editor reports a change through
+    /// [`CustomGraphState::graph_changed`], which is then cleared. When
+    /// `interacting` is set, recording is postponed until the interaction
+    /// ends.
+    pub fn track(
+        &mut self,
+        editor_state: &GraphEditorState,
+        custom_state: &mut CustomGraphState,
+        interacting: bool,
+    ) -> Result<()> {
+        self.pending |= std::mem::take(&mut custom_state.graph_changed);
+        self.record(interacting, || Self::snapshot(editor_state, custom_state))
+    }
+
+    /// Takes a snapshot with `snapshot` and records it as the current state,
+    /// when there's a pending change and the user is not interacting. The
+    /// first call always takes a snapshot, to know the initial state.
+    fn record(
+        &mut self,
+        interacting: bool,
+        snapshot: impl FnOnce() -> Result<String>,
+    ) -> Result<()> {
+        if interacting || !(self.pending || self.current.is_none()) {
+            return Ok(());
+        }
+        let snapshot = snapshot()?;
+        self.pending = false;
+        match self.current.replace(snapshot) {
+            Some(previous) if Some(&previous) != self.current.as_ref() => {
+                self.undo_stack.push_back(previous);
+                if self.undo_stack.len() > MAX_UNDO_STEPS {
+                    self.undo_stack.pop_front();
+                }
+                self.redo_stack.clear();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Restores the state before the last recorded step, if any. When the
+    /// snapshot can't be restored, the history is left unchanged.
+    pub fn undo(
+        &mut self,
+        editor_state: &mut GraphEditorState,
+        custom_state: &mut CustomGraphState,
+    ) -> Result<()> {
+        self.step_back(|snapshot| Self::restore(snapshot, editor_state, custom_state))
+    }
+
+    /// Restores the last undone state, if any. When the snapshot can't be
+    /// restored, the history is left unchanged.
+    pub fn redo(
+        &mut self,
+        editor_state: &mut GraphEditorState,
+        custom_state: &mut CustomGraphState,
+    ) -> Result<()> {
+        self.step_forward(|snapshot| Self::restore(snapshot, editor_state, custom_state))
+    }
+
+    /// Moves back one step, passing its snapshot to `restore`, which returns
+    /// the snapshot of the restored state.
+    fn step_back(&mut self, restore: impl FnOnce(&str) -> Result<String>) -> Result<()> {
+        if !self.can_undo() || self.current.is_none() {
+            return Ok(());
+        }
+        let snapshot = self.undo_stack.pop_back().expect("Checked above");
+        match restore(&snapshot) {
+            Ok(restored) => {
+                let current = self.current.replace(restored).expect("Checked above");
+                self.redo_stack.push(current);
+                Ok(())
+            }
+            Err(err) => {
+                self.undo_stack.push_back(snapshot);
+                Err(err)
+            }
+        }
+    }
+
+    /// Moves forward one undone step, passing its snapshot to `restore`,
+    /// which returns the snapshot of the restored state.
+    fn step_forward(&mut self, restore: impl FnOnce(&str) -> Result<String>) -> Result<()> {
+        if !self.can_redo() || self.current.is_none() {
+            return Ok(());
+        }
+        let snapshot = self.redo_stack.pop().expect("Checked above");
+        match restore(&snapshot) {
+            Ok(restored) => {
+                let current = self.current.replace(restored).expect("Checked above");
+                self.undo_stack.push_back(current);
+                Ok(())
+            }
+            Err(err) => {
+                self.redo_stack.push(snapshot);
+                Err(err)
+            }
+        }
+    }
+
+    /// The history of a graph file is stored next to it, so it can be
+    /// recovered when the file is opened again.
+    fn history_path(graph_path: &Path) -> PathBuf {
+        let mut path = graph_path.as_os_str().to_owned();
+        path.push(".history");
+        PathBuf::from(path)
+    }
+
+    /// Writes the history next to the graph file at `graph_path`.
+    pub fn save_for(&self, graph_path: &Path) -> Result<()> {
+        std::fs::write(Self::history_path(graph_path), ron::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Loads the history stored next to the graph file at `graph_path`.
+    /// Returns an empty history when there is none.
+    pub fn load_for(graph_path: &Path) -> Result<Self> {
+        let path = Self::history_path(graph_path);
+        if path.is_file() {
+            Ok(ron::from_str(&std::fs::read_to_string(path)?)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Records `state` as if the editor had reported a change to it.
+    fn edit(history: &mut UndoHistory, state: &str, interacting: bool) {
+        history.pending = true;
+        history
+            .record(interacting, || Ok(state.to_string()))
+            .unwrap();
+    }
+
+    /// Returns the snapshot restored by undoing or redoing.
+    fn restored(snapshot: &str) -> Result<String> {
+        Ok(snapshot.to_string())
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = UndoHistory::default();
+        edit(&mut history, "a", false);
+        edit(&mut history, "b", false);
+        edit(&mut history, "c", false);
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+
+        history.step_back(restored).unwrap();
+        history.step_back(restored).unwrap();
+        assert_eq!(history.current.as_deref(), Some("a"));
+        assert!(!history.can_undo());
+        history.step_forward(restored).unwrap();
+        assert_eq!(history.current.as_deref(), Some("b"));
+
+        // A new edit discards the undone steps
+        edit(&mut history, "d", false);
+        assert!(!history.can_redo());
+        history.step_back(restored).unwrap();
+        assert_eq!(history.current.as_deref(), Some("b"));
+
+        // Failing to restore leaves the history unchanged
+        assert!(history.step_back(|_| Err(anyhow!("Broken"))).is_err());
+        assert_eq!(history.current.as_deref(), Some("b"));
+        history.step_back(restored).unwrap();
+        assert_eq!(history.current.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_only_reported_changes_are_recorded() {
+        let mut history = UndoHistory::default();
+        edit(&mut history, "a", false);
+        history
+            .record(false, || panic!("Snapshot taken without a change"))
+            .unwrap();
+
+        // Reporting a change that leaves the graph as it was adds no step
+        edit(&mut history, "a", false);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_drag_coalescing() {
+        let mut history = UndoHistory::default();
+        edit(&mut history, "0", false);
+        for value in ["1", "2", "3"] {
+            edit(&mut history, value, true);
+        }
+        history.record(false, || Ok("3".to_string())).unwrap();
+        assert_eq!(history.current.as_deref(), Some("3"));
+        assert_eq!(history.undo_stack, ["0"]);
+    }
+
+    #[test]
+    fn test_load_corrupt_history() {
+        let graph_path = std::env::temp_dir().join(format!(
+            "blackjack_{}_corrupt_history.bjk",
+            std::process::id()
+        ));
+        std::fs::write(UndoHistory::history_path(&graph_path), "not a history").unwrap();
+        assert!(UndoHistory::load_for(&graph_path).is_err());
+        std::fs::remove_file(UndoHistory::history_path(&graph_path)).unwrap();
+
+        // A missing history is not an error
+        let history = UndoHistory::load_for(&graph_path).unwrap();
+        assert!(!history.can_undo() && !history.can_redo());
+    }
+}
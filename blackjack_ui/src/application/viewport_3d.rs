@@ -5,12 +5,15 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use blackjack_engine::lua_engine::RenderableThing;
-use winit::event::MouseButton;
+use blackjack_engine::mesh::halfedge::snapping::{SnapIndex, SnapTarget};
+use blackjack_engine::prelude::{BufferChannels, ChannelKeyType, HalfEdgeMesh, MeshStats};
 
 use crate::app_window::input::InputSystem;
+use crate::keymap::{Action, KEYMAP};
 use crate::{prelude::*, rendergraph};
 
 use super::app_viewport::AppViewport;
+use super::application_context::{ApplicationContext, MeshViewportSelection};
 use super::gizmo_ui::{self, GizmoViewportResponse, UiNodeGizmoStates};
 use super::graph_editor::GraphEditor;
 
@@ -18,6 +21,14 @@ use super::graph_editor::GraphEditor;
 mod lerp;
 use lerp::*;
 
+/// Interactive placement of primitives by click-dragging
+mod placement;
+use placement::{PlacementPrimitive, PrimitivePlacement};
+
+/// Camera rays and intersection tests
+mod ray;
+use ray::Ray;
+
 #[derive(PartialEq, Eq)]
 pub enum EdgeDrawMode {
     HalfEdge,
@@ -27,13 +38,16 @@ pub enum EdgeDrawMode {
 
 #[derive(PartialEq, Eq)]
 pub enum FaceDrawMode {
-    /// Will read the actual configured value for the mesh and use its channel,
-    /// if any. Defaults to flat shading otherwise.
+    /// Will read the actual shading mode configured for the mesh and use its
+    /// channel, if any. Generates the normals otherwise.
     Real,
     /// Force flat shading, ignoring mesh data.
     Flat,
     /// Force smooth shading, ignoring mesh data
     Smooth,
+    /// Shade faces with the PBR renderer, using the project materials
+    /// assigned to them.
+    Materials,
     /// Don't draw faces.
     NoDraw,
 }
@@ -55,12 +69,94 @@ pub enum TextOverlayMode {
     DevDebug,
 }
 
+/// What the gizmos and viewport tools snap to while dragging them. Snapping
+/// only affects positions, rotations and scales are never snapped.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SnapMode {
+    Off,
+    /// Round positions to multiples of the grid size.
+    Grid,
+    Vertex,
+    EdgeMidpoint,
+    FaceCenter,
+}
+
+impl SnapMode {
+    pub const ALL: [Self; 5] = [
+        Self::Off,
+        Self::Grid,
+        Self::Vertex,
+        Self::EdgeMidpoint,
+        Self::FaceCenter,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnapMode::Off => "Off",
+            SnapMode::Grid => "Grid",
+            SnapMode::Vertex => "Vertex",
+            SnapMode::EdgeMidpoint => "Edge Midpoint",
+            SnapMode::FaceCenter => "Face Center",
+        }
+    }
+
+    /// The mesh elements to snap to, for the modes that snap to geometry.
+    fn snap_target(&self) -> Option<SnapTarget> {
+        match self {
+            SnapMode::Off | SnapMode::Grid => None,
+            SnapMode::Vertex => Some(SnapTarget::Vertex),
+            SnapMode::EdgeMidpoint => Some(SnapTarget::EdgeMidpoint),
+            SnapMode::FaceCenter => Some(SnapTarget::FaceCenter),
+        }
+    }
+}
+
+/// Which optional mesh channels are generated and uploaded to the GPU for
+/// display. Positions are always uploaded. Large meshes are cheaper to display
+/// when the unused channels are left out.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ChannelUploads {
+    /// When disabled, faces are shaded using flat normals computed on the GPU.
+    pub normals: bool,
+    /// Tint faces with the `color` vertex channel, if the mesh has one.
+    pub colors: bool,
+    /// Draw a checker pattern using the mesh uvs, if the mesh has them.
+    pub uvs: bool,
+    /// Face ids, used to highlight and pick faces in the viewport.
+    pub ids: bool,
+}
+
+impl ChannelUploads {
+    pub fn buffer_channels(&self) -> BufferChannels {
+        BufferChannels {
+            normals: self.normals,
+            colors: self.colors,
+            uvs: self.uvs,
+        }
+    }
+}
+
 pub struct Viewport3dSettings {
     pub render_vertices: bool,
     pub matcap: usize,
     pub edge_mode: EdgeDrawMode,
     pub face_mode: FaceDrawMode,
     pub overlay_mode: TextOverlayMode,
+    /// Show a HUD on the viewport with statistics about the current mesh. Off
+    /// by default, since the statistics are computed every frame.
+    pub show_stats_hud: bool,
+    /// Multiplier for the camera motion of 3D mice. Zero disables them.
+    pub space_mouse_sensitivity: f32,
+    pub uploads: ChannelUploads,
+    pub snap_mode: SnapMode,
+    /// The increment used by [`SnapMode::Grid`].
+    pub grid_snap_size: f32,
+    /// Slice the mesh into layers, like a 3D printing slicer, and draw the
+    /// contours of the current layer.
+    pub show_layers: bool,
+    pub layer_height: f32,
+    /// Index of the layer whose contours are drawn, starting from the bottom.
+    pub layer_index: usize,
 }
 
 pub struct Viewport3d {
@@ -75,6 +171,14 @@ pub struct Viewport3d {
     // True when a mouse drag does not belong to the camera. Such as when
     // dragging a gizmo.
     mouse_captured: bool,
+    /// When set, click-dragging in the viewport places this primitive instead
+    /// of moving the camera.
+    placement_tool: Option<PlacementPrimitive>,
+    /// The primitive being placed, while the mouse button is held.
+    placement: Option<PrimitivePlacement>,
+    /// The geometry that can be snapped to during the current drag. Built when
+    /// a drag starts, so moving parts of the mesh don't snap onto themselves.
+    snap_index: Option<SnapIndex>,
 }
 
 struct OrbitCamera {
@@ -122,11 +226,27 @@ impl Viewport3d {
                 overlay_mode: TextOverlayMode::NoDraw,
                 render_vertices: true,
                 matcap: 0,
+                show_stats_hud: false,
+                space_mouse_sensitivity: 1.0,
+                uploads: ChannelUploads {
+                    normals: true,
+                    colors: false,
+                    uvs: false,
+                    ids: true,
+                },
+                snap_mode: SnapMode::Off,
+                grid_snap_size: 0.25,
+                show_layers: false,
+                layer_height: 0.2,
+                layer_index: 0,
             },
             view_proj_matrix: Mat4::default(),
             view_matrix: Mat4::default(),
             projection_matrix: Mat4::default(),
             mouse_captured: false,
+            placement_tool: None,
+            placement: None,
+            snap_index: None,
         }
     }
 
@@ -145,6 +265,35 @@ impl Viewport3d {
         );
     }
 
+    pub fn on_device_event(
+        &mut self,
+        device_id: winit::event::DeviceId,
+        event: &winit::event::DeviceEvent,
+    ) {
+        if self.settings.space_mouse_sensitivity > 0.0 {
+            self.input.on_device_event(device_id, event);
+        }
+    }
+
+    /// Moves the camera so that the whole `mesh` is in view.
+    fn frame_mesh(&mut self, mesh: &HalfEdgeMesh) {
+        let positions = mesh.read_positions();
+        let (min, max) = positions.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), (_, pos)| (min.min(*pos), max.max(*pos)),
+        );
+        if !min.is_finite() || !max.is_finite() {
+            return;
+        }
+        // The view matrix translates by the focus point, so it is negated
+        self.camera.focus_point.set(|_| -(min + max) * 0.5);
+        let radius = (max - min).length() * 0.5;
+        let half_fov = (self.camera.fov.get() * 0.5).to_radians();
+        self.camera
+            .distance
+            .set(|_| (radius / half_fov.sin()).clamp(0.1, 120.0));
+    }
+
     fn update_camera(&mut self, render_ctx: &mut RenderContext) {
         const MIN_DIST: f32 = 0.1;
         const MAX_DIST: f32 = 120.0;
@@ -153,20 +302,18 @@ impl Viewport3d {
 
         if !self.mouse_captured {
             // Update status
-            if self.input.mouse.buttons().pressed(MouseButton::Left) {
-                if self.input.shift_down {
-                    let cam_rotation = Mat4::from_rotation_y(self.camera.yaw.get().to_radians())
-                        * Mat4::from_rotation_x(self.camera.pitch.get().to_radians());
-                    let camera_right = cam_rotation.transform_point3(Vec3::X);
-                    let camera_up = cam_rotation.transform_vector3(Vec3::Y);
-                    let move_speed = self.camera.distance.get() / MAX_DIST;
-                    self.camera.focus_point +=
-                        self.input.mouse.cursor_delta().x * camera_right * move_speed
-                            + self.input.mouse.cursor_delta().y * -camera_up * move_speed;
-                } else {
-                    self.camera.yaw += self.input.mouse.cursor_delta().x * 2.0;
-                    self.camera.pitch += self.input.mouse.cursor_delta().y * 2.0;
-                }
+            if KEYMAP.camera.pan.held(&self.input) {
+                let cam_rotation = Mat4::from_rotation_y(self.camera.yaw.get().to_radians())
+                    * Mat4::from_rotation_x(self.camera.pitch.get().to_radians());
+                let camera_right = cam_rotation.transform_point3(Vec3::X);
+                let camera_up = cam_rotation.transform_vector3(Vec3::Y);
+                let move_speed = self.camera.distance.get() / MAX_DIST;
+                self.camera.focus_point +=
+                    self.input.mouse.cursor_delta().x * camera_right * move_speed
+                        + self.input.mouse.cursor_delta().y * -camera_up * move_speed;
+            } else if KEYMAP.camera.orbit.held(&self.input) {
+                self.camera.yaw += self.input.mouse.cursor_delta().x * 2.0;
+                self.camera.pitch += self.input.mouse.cursor_delta().y * 2.0;
             }
             self.camera.distance.set(|dist| {
                 (dist - self.input.mouse.wheel_delta() * 0.5).clamp(MIN_DIST, MAX_DIST)
@@ -176,6 +323,27 @@ impl Viewport3d {
             // .set(|fov| (fov - self.input.mouse.wheel_delta() * 4.0).clamp(MIN_FOV, MAX_FOV));
         }
 
+        // 3D mice pan with their X/Y translation, zoom with Z, and orbit with
+        // their X/Y rotation. Roll is not supported by the orbit camera. Raw
+        // axis values go up to roughly 350 at full deflection.
+        let sensitivity = self.settings.space_mouse_sensitivity / 350.0;
+        let translation = self.input.space_mouse.translation() * sensitivity;
+        let rotation = self.input.space_mouse.rotation() * sensitivity;
+        if translation != Vec3::ZERO || rotation != Vec3::ZERO {
+            let cam_rotation = Mat4::from_rotation_y(self.camera.yaw.get().to_radians())
+                * Mat4::from_rotation_x(self.camera.pitch.get().to_radians());
+            let camera_right = cam_rotation.transform_point3(Vec3::X);
+            let camera_up = cam_rotation.transform_vector3(Vec3::Y);
+            let move_speed = self.camera.distance.get() * 0.05;
+            self.camera.focus_point +=
+                (-translation.x * camera_right + translation.y * camera_up) * move_speed;
+            self.camera
+                .distance
+                .set(|dist| (dist + translation.z * move_speed).clamp(MIN_DIST, MAX_DIST));
+            self.camera.pitch += rotation.x * 4.0;
+            self.camera.yaw += rotation.y * 4.0;
+        }
+
         // Compute view matrix
         let view = Mat4::from_translation(Vec3::Z * self.camera.distance.get())
             * Mat4::from_rotation_x(-self.camera.pitch.get().to_radians())
@@ -250,10 +418,31 @@ impl Viewport3d {
         &mut self,
         ui: &mut egui::Ui,
         offscreen_viewport: &mut AppViewport,
-        renderable_thing: Option<&RenderableThing>,
-        graph_editor: &GraphEditor,
-        node_gizmo_states: &mut UiNodeGizmoStates,
+        app_context: &mut ApplicationContext,
+        graph_editor: &mut GraphEditor,
     ) -> Result<()> {
+        let renderable_thing = app_context.renderable_thing.as_ref();
+        let node_gizmo_states = &mut app_context.node_gizmo_states;
+
+        if !ui.ctx().wants_keyboard_input() {
+            if KEYMAP.pressed(Action::ToggleWireframe, &ui.input()) {
+                self.settings.edge_mode = match self.settings.edge_mode {
+                    EdgeDrawMode::NoDraw => EdgeDrawMode::FullEdge,
+                    _ => EdgeDrawMode::NoDraw,
+                };
+            }
+            if KEYMAP.pressed(Action::FrameMesh, &ui.input()) {
+                match renderable_thing {
+                    Some(RenderableThing::HalfEdgeMesh(mesh)) => self.frame_mesh(mesh),
+                    Some(RenderableThing::MeshProxy(proxy)) => self.frame_mesh(&proxy.wire_box),
+                    _ => {}
+                }
+            }
+            if ui.input().key_pressed(egui::Key::Escape) {
+                self.placement_tool = None;
+                self.placement = None;
+            }
+        }
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
                 mesh_visuals_popup(ui, |ui| {
@@ -298,6 +487,11 @@ impl Viewport3d {
                             FaceDrawMode::Smooth,
                             "Smooth",
                         );
+                        ui.selectable_value(
+                            &mut self.settings.face_mode,
+                            FaceDrawMode::Materials,
+                            "Materials",
+                        );
                         ui.selectable_value(
                             &mut self.settings.face_mode,
                             FaceDrawMode::NoDraw,
@@ -305,6 +499,15 @@ impl Viewport3d {
                         );
                     });
 
+                    ui.horizontal(|ui| {
+                        ui.label("Upload:");
+                        let uploads = &mut self.settings.uploads;
+                        ui.checkbox(&mut uploads.normals, "Normals");
+                        ui.checkbox(&mut uploads.colors, "Colors");
+                        ui.checkbox(&mut uploads.uvs, "UVs");
+                        ui.checkbox(&mut uploads.ids, "Ids");
+                    });
+
                     ui.horizontal(|ui| {
                         ui.label("Matcap:");
                         if ui.button("<").clicked() {
@@ -352,10 +555,85 @@ impl Viewport3d {
                             "Debug",
                         );
                     });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Statistics:");
+                        ui.checkbox(&mut self.settings.show_stats_hud, "");
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("3D Mouse Sensitivity:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.space_mouse_sensitivity)
+                                .speed(0.05)
+                                .clamp_range(0.0..=5.0),
+                        );
+                    });
                 });
+                egui::ComboBox::from_id_source("placement_tool")
+                    .selected_text(self.placement_tool.map_or("Create…", |p| p.label()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.placement_tool, None, "None");
+                        for primitive in PlacementPrimitive::ALL {
+                            ui.selectable_value(
+                                &mut self.placement_tool,
+                                Some(primitive),
+                                primitive.label(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("Click and drag in the viewport to place a primitive");
+                egui::ComboBox::from_id_source("snap_mode")
+                    .selected_text(format!("Snap: {}", self.settings.snap_mode.label()))
+                    .show_ui(ui, |ui| {
+                        for mode in SnapMode::ALL {
+                            ui.selectable_value(&mut self.settings.snap_mode, mode, mode.label());
+                        }
+                    });
+                if self.settings.snap_mode == SnapMode::Grid {
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.grid_snap_size)
+                            .speed(0.01)
+                            .clamp_range(0.001..=f32::INFINITY),
+                    );
+                }
+                ui.checkbox(&mut self.settings.show_layers, "Layers")
+                    .on_hover_text("Preview the contours of the mesh, sliced into layers");
+                if self.settings.show_layers {
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.layer_height)
+                            .speed(0.01)
+                            .clamp_range(0.01..=f32::INFINITY),
+                    )
+                    .on_hover_text("Layer height");
+                    let layers = app_context.slice_layers.as_deref().unwrap_or_default();
+                    if !layers.is_empty() {
+                        let last = layers.len() - 1;
+                        self.settings.layer_index = self.settings.layer_index.min(last);
+                        ui.add(
+                            egui::Slider::new(&mut self.settings.layer_index, 0..=last).text(
+                                format!("h = {:.2}", layers[self.settings.layer_index].height),
+                            ),
+                        );
+                    }
+                }
             });
             offscreen_viewport.show(ui, ui.available_size());
         });
+        if self.settings.show_stats_hud {
+            draw_stats_hud(
+                ui,
+                offscreen_viewport.rect,
+                renderable_thing,
+                app_context.mesh_stats.as_ref(),
+                app_context.last_cook_time,
+                app_context.current_selection.as_ref(),
+            );
+        }
+        // The mouse is captured for the whole duration of a drag
+        self.update_snap_index(self.mouse_captured, renderable_thing);
+        self.mouse_captured = false;
         if let Some(renderable_thing) = renderable_thing {
             crate::app_window::gui_overlay::draw_gui_overlays(
                 &self.view_proj_matrix,
@@ -365,7 +643,6 @@ impl Viewport3d {
                 self.settings.overlay_mode,
             );
 
-            self.mouse_captured = false;
             node_gizmo_states.iterate_gizmos_for_drawing(
                 |node_id, gizmo_idx, gizmo, has_focus| {
                     let node = &graph_editor.editor_state.graph[node_id];
@@ -393,6 +670,125 @@ impl Viewport3d {
                 },
             )?;
         }
+        self.update_placement(ui, offscreen_viewport.rect, app_context, graph_editor)
+    }
+
+    /// Builds the snap index when a drag starts, if the snap mode needs one,
+    /// and drops it when the drag ends.
+    fn update_snap_index(&mut self, dragging: bool, renderable_thing: Option<&RenderableThing>) {
+        if !dragging {
+            self.snap_index = None;
+            return;
+        }
+        if self.snap_index.is_some() {
+            return;
+        }
+        if let (Some(target), Some(RenderableThing::HalfEdgeMesh(mesh))) =
+            (self.settings.snap_mode.snap_target(), renderable_thing)
+        {
+            match SnapIndex::build(mesh, &[target]) {
+                Ok(index) => self.snap_index = Some(index),
+                Err(err) => println!("Error building the snap index: {err}"),
+            }
+        }
+    }
+
+    /// Snaps a world-space `point` according to the current snap mode.
+    /// Geometry is only snapped to when it's close to the point, relative to
+    /// the camera distance. Otherwise, the point is returned unchanged.
+    pub fn snap_point(&self, point: Vec3) -> Vec3 {
+        const SNAP_RADIUS: f32 = 0.05;
+        match self.settings.snap_mode {
+            SnapMode::Off => point,
+            SnapMode::Grid => {
+                let size = self.settings.grid_snap_size;
+                (point / size).round() * size
+            }
+            SnapMode::Vertex | SnapMode::EdgeMidpoint | SnapMode::FaceCenter => self
+                .snap_index
+                .as_ref()
+                .and_then(|index| index.nearest(point, self.camera.distance.get() * SNAP_RADIUS))
+                .map_or(point, |(snapped, _)| snapped),
+        }
+    }
+
+    /// Returns the point of the ground plane under the `cursor`, if any.
+    fn ground_point_under(&self, viewport_rect: egui::Rect, cursor: egui::Pos2) -> Option<Vec3> {
+        let ray = Ray::from_screen(
+            self.view_matrix,
+            self.view_proj_matrix,
+            Vec2::new(viewport_rect.width(), viewport_rect.height()),
+            Vec2::new(viewport_rect.left(), viewport_rect.top()),
+            Vec2::new(cursor.x, cursor.y),
+        )?;
+        let t = ray.intersect_plane(Vec3::ZERO, Vec3::Y)?;
+        Some(ray.at(t))
+    }
+
+    /// Handles click-dragging in the viewport to place the primitive of the
+    /// `placement_tool`. A wireframe preview is shown during the drag, and the
+    /// node creating the primitive is added to the graph when it ends.
+    fn update_placement(
+        &mut self,
+        ui: &egui::Ui,
+        viewport_rect: egui::Rect,
+        app_context: &mut ApplicationContext,
+        graph_editor: &mut GraphEditor,
+    ) -> Result<()> {
+        app_context.preview_mesh = None;
+        let primitive = match self.placement_tool {
+            Some(primitive) => primitive,
+            None => {
+                self.placement = None;
+                return Ok(());
+            }
+        };
+
+        let (pressed, down, hover_pos) = {
+            let pointer = &ui.input().pointer;
+            (
+                pointer.primary_pressed(),
+                pointer.primary_down(),
+                pointer.hover_pos(),
+            )
+        };
+        let ground_point = hover_pos
+            .and_then(|pos| self.ground_point_under(viewport_rect, pos))
+            .map(|point| self.snap_point(point));
+
+        let in_viewport = hover_pos.map_or(false, |pos| viewport_rect.contains(pos));
+        if pressed && in_viewport && !self.mouse_captured {
+            if let Some(start) = ground_point {
+                self.placement = Some(PrimitivePlacement {
+                    primitive,
+                    start,
+                    end: start,
+                });
+            }
+        }
+
+        if let Some(placement) = &mut self.placement {
+            if let Some(end) = ground_point {
+                placement.end = end;
+            }
+        }
+
+        match self.placement {
+            Some(placement) if !down => {
+                self.placement = None;
+                if !placement.is_degenerate() {
+                    graph_editor
+                        .add_active_node(placement.primitive.op_name(), placement.node_values())?;
+                }
+            }
+            Some(placement) => {
+                self.mouse_captured = true;
+                if !placement.is_degenerate() {
+                    app_context.preview_mesh = Some(placement.preview_mesh()?);
+                }
+            }
+            None => {}
+        }
         Ok(())
     }
 
@@ -409,6 +805,84 @@ impl Viewport3d {
     }
 }
 
+/// Formats a number of bytes using binary unit prefixes, e.g. "1.5 MiB"
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Draws the statistics HUD on the top-left corner of the viewport. Shows the
+/// element counts and memory usage of the displayed mesh, the number of
+/// selected elements and the time it took to cook the active node.
+fn draw_stats_hud(
+    ui: &egui::Ui,
+    viewport_rect: egui::Rect,
+    renderable_thing: Option<&RenderableThing>,
+    mesh_stats: Option<&MeshStats>,
+    last_cook_time: Option<std::time::Duration>,
+    selection: Option<&MeshViewportSelection>,
+) {
+    let mut lines = Vec::new();
+    match (renderable_thing, mesh_stats) {
+        (Some(RenderableThing::HalfEdgeMesh(_)), Some(stats)) => {
+            lines.push(format!("Vertices:  {}", stats.vertices));
+            lines.push(format!("Edges:     {}", stats.edges));
+            lines.push(format!("Faces:     {}", stats.faces));
+            lines.push(format!("Triangles: {}", stats.triangles));
+            lines.push(format!("Memory:    {}", format_bytes(stats.memory_bytes)));
+        }
+        (Some(RenderableThing::MeshProxy(proxy)), _) => {
+            lines.push("Proxy (not loaded)".into());
+            lines.push(format!("Vertices:  {}", proxy.summary.num_vertices));
+            lines.push(format!("Faces:     {}", proxy.summary.num_faces));
+        }
+        (Some(RenderableThing::HeightMap(_)), _) => lines.push("Heightmap".into()),
+        _ => {}
+    }
+    if let Some(selection) = selection {
+        let kind = match selection.primitive_type {
+            ChannelKeyType::VertexId => "vertices",
+            ChannelKeyType::FaceId => "faces",
+            ChannelKeyType::HalfEdgeId => "halfedges",
+        };
+        lines.push(format!("Selected:  {} {kind}", selection.selected.len()));
+    }
+    if let Some(cook_time) = last_cook_time {
+        lines.push(format!(
+            "Cook time: {:.2} ms",
+            cook_time.as_secs_f64() * 1000.0
+        ));
+    }
+    if lines.is_empty() {
+        return;
+    }
+
+    let painter = ui.painter_at(viewport_rect);
+    let galley = painter.layout_no_wrap(
+        lines.join("\n"),
+        egui::FontId::monospace(12.0),
+        egui::Color32::WHITE,
+    );
+    let margin = egui::vec2(6.0, 4.0);
+    let pos = viewport_rect.left_top() + egui::vec2(8.0, 8.0);
+    painter.rect_filled(
+        egui::Rect::from_min_size(pos, galley.size() + margin * 2.0),
+        3.0,
+        egui::Color32::from_black_alpha(160),
+    );
+    painter.galley(pos + margin, galley);
+}
+
 /// Draws the "Mesh Visuals" popup.
 /// This code was adapted from egui's Color Picker widget
 pub fn mesh_visuals_popup(
@@ -0,0 +1,128 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use blackjack_engine::graph::BlackjackValue;
+use blackjack_engine::mesh::halfedge::primitives;
+use blackjack_engine::prelude::HalfEdgeMesh;
+
+use crate::prelude::*;
+
+/// Primitives smaller than this are not created when the drag ends.
+const MIN_SIZE: f32 = 0.01;
+
+/// The primitives that can be placed by click-dragging in the viewport.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlacementPrimitive {
+    Box,
+    UVSphere,
+    Cylinder,
+}
+
+impl PlacementPrimitive {
+    pub const ALL: [Self; 3] = [Self::Box, Self::UVSphere, Self::Cylinder];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlacementPrimitive::Box => "Box",
+            PlacementPrimitive::UVSphere => "UV Sphere",
+            PlacementPrimitive::Cylinder => "Cylinder",
+        }
+    }
+
+    /// The name of the node that creates this primitive.
+    pub fn op_name(&self) -> &'static str {
+        match self {
+            PlacementPrimitive::Box => "MakeBox",
+            PlacementPrimitive::UVSphere => "MakeUVSphere",
+            PlacementPrimitive::Cylinder => "MakeCylinder",
+        }
+    }
+}
+
+/// An ongoing click-drag placing a primitive on the ground plane. The drag
+/// starts at the center of the primitive's base, and the distance to the
+/// cursor sets its size. Primitives rest on the ground.
+#[derive(Clone, Copy, Debug)]
+pub struct PrimitivePlacement {
+    pub primitive: PlacementPrimitive,
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl PrimitivePlacement {
+    fn radius(&self) -> f32 {
+        let delta = self.end - self.start;
+        Vec2::new(delta.x, delta.z).length()
+    }
+
+    fn box_size(&self) -> Vec3 {
+        let delta = (self.end - self.start).abs() * 2.0;
+        Vec3::new(delta.x, (delta.x + delta.z) * 0.5, delta.z)
+    }
+
+    pub fn is_degenerate(&self) -> bool {
+        match self.primitive {
+            PlacementPrimitive::Box => self.box_size().min_element() < MIN_SIZE,
+            PlacementPrimitive::UVSphere | PlacementPrimitive::Cylinder => self.radius() < MIN_SIZE,
+        }
+    }
+
+    /// Returns the parameter values for the node creating the primitive.
+    pub fn node_values(&self) -> Vec<(&'static str, BlackjackValue)> {
+        match self.primitive {
+            PlacementPrimitive::Box => {
+                let size = self.box_size();
+                vec![
+                    (
+                        "origin",
+                        BlackjackValue::Vector(self.start + Vec3::Y * size.y * 0.5),
+                    ),
+                    ("size", BlackjackValue::Vector(size)),
+                ]
+            }
+            PlacementPrimitive::UVSphere => {
+                let radius = self.radius();
+                vec![
+                    (
+                        "center",
+                        BlackjackValue::Vector(self.start + Vec3::Y * radius),
+                    ),
+                    ("radius", BlackjackValue::Scalar(radius)),
+                ]
+            }
+            PlacementPrimitive::Cylinder => {
+                let radius = self.radius();
+                vec![
+                    (
+                        "center",
+                        BlackjackValue::Vector(self.start + Vec3::Y * radius),
+                    ),
+                    ("radius", BlackjackValue::Scalar(radius)),
+                    ("height", BlackjackValue::Scalar(radius * 2.0)),
+                ]
+            }
+        }
+    }
+
+    /// Builds a mesh matching the node that will be created, to preview the
+    /// primitive during the drag. Uses the default resolution of the nodes.
+    pub fn preview_mesh(&self) -> Result<HalfEdgeMesh> {
+        match self.primitive {
+            PlacementPrimitive::Box => {
+                let size = self.box_size();
+                primitives::Box::build(self.start + Vec3::Y * size.y * 0.5, size)
+            }
+            PlacementPrimitive::UVSphere => {
+                let radius = self.radius();
+                primitives::UVSphere::build(self.start + Vec3::Y * radius, 12, 6, radius)
+            }
+            PlacementPrimitive::Cylinder => {
+                let radius = self.radius();
+                primitives::Cylinder::build(self.start + Vec3::Y * radius, radius, radius * 2.0, 8)
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use glam::{Mat4, Vec2, Vec3};
+
+/// A half-line in world space, used to find what's under the cursor.
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Always normalized.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Returns the ray starting at the camera and going through the point of
+    /// the screen at `cursor`. This is the inverse of
+    /// `RenderContext::project_point`.
+    pub fn from_screen(
+        view_matrix: Mat4,
+        view_proj: Mat4,
+        viewport_size: Vec2,
+        viewport_offset: Vec2,
+        cursor: Vec2,
+    ) -> Option<Self> {
+        let zero_to_one = (cursor - viewport_offset) / viewport_size;
+        let ndc = zero_to_one * 2.0 - Vec2::ONE;
+        // Any depth inside the frustum gives a point along the ray.
+        let point = view_proj
+            .inverse()
+            .project_point3(Vec3::new(ndc.x, -ndc.y, 0.5));
+        let origin = view_matrix.inverse().transform_point3(Vec3::ZERO);
+        let direction = (point - origin).try_normalize()?;
+        Some(Self { origin, direction })
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    /// Returns the distance along the ray where it hits the plane through
+    /// `point` with the given `normal`. Returns `None` when the plane is
+    /// parallel to the ray or behind its origin.
+    pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+        let denom = self.direction.dot(normal);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let t = (point - self.origin).dot(normal) / denom;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ray_plane_intersection() {
+        let ray = Ray {
+            origin: Vec3::new(1.0, 5.0, 2.0),
+            direction: Vec3::new(0.0, -1.0, 0.0),
+        };
+        let t = ray.intersect_plane(Vec3::ZERO, Vec3::Y).unwrap();
+        assert!((ray.at(t) - Vec3::new(1.0, 0.0, 2.0)).length() < 1e-6);
+
+        // Parallel, and behind the origin
+        assert!(ray.intersect_plane(Vec3::ZERO, Vec3::X).is_none());
+        assert!(ray.intersect_plane(Vec3::Y * 10.0, Vec3::Y).is_none());
+    }
+}
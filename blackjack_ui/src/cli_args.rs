@@ -22,6 +22,32 @@ pub struct Args {
     /// and the Lua code will be loaded once at startup.
     #[arg(long)]
     pub disable_lua_watcher: bool,
+
+    /// Folder where the compiled Lua node libraries are cached, to speed up
+    /// startup. Defaults to a folder in the user's cache directory. The folder
+    /// is only used when other users can't access it.
+    #[arg(long)]
+    pub lua_cache_dir: Option<String>,
+
+    /// If this argument is present, the Lua node libraries are compiled on
+    /// every startup instead of being cached.
+    #[arg(long)]
+    pub disable_lua_cache: bool,
+
+    /// The keymap to use. Either the name of a preset ("blackjack", "blender"
+    /// or "maya") or the path to a keymap file.
+    #[arg(long)]
+    pub keymap: Option<String>,
+
+    /// Scale factor for the user interface, applied on top of the monitor
+    /// scale factor. Can also be changed from the Window menu.
+    #[arg(long)]
+    pub ui_scale: Option<f32>,
+
+    /// Forces the degraded rendering mode, used when the GPU lacks some of
+    /// the features required by the 3d viewport.
+    #[arg(long)]
+    pub fallback_renderer: bool,
 }
 
 /// CLI args are stored in a lazy static variable so they're accessible from
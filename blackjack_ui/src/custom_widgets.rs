@@ -6,3 +6,6 @@
 
 /// A better drag value, with support for a range selector
 pub mod smart_dragvalue;
+
+/// An editor for ramp (curve and gradient) parameters
+pub mod ramp_editor;
@@ -0,0 +1,136 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use blackjack_engine::graph::ramp::{Ramp, RampInterpolation, RampKey};
+use egui::*;
+
+const PREVIEW_HEIGHT: f32 = 32.0;
+const PREVIEW_SAMPLES: usize = 64;
+
+fn to_color32(v: glam::Vec3) -> Color32 {
+    Rgba::from_rgb(v.x, v.y, v.z).into()
+}
+
+/// Draws a preview of the ramp. Color ramps are displayed as a gradient, and
+/// scalar ramps as a curve.
+fn ramp_preview(ui: &mut Ui, ramp: &Ramp, color: bool) {
+    let width = ui.available_width().clamp(100.0, 200.0);
+    let (rect, _) = ui.allocate_exact_size(vec2(width, PREVIEW_HEIGHT), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let t_at = |i: usize| i as f32 / (PREVIEW_SAMPLES - 1) as f32;
+    if color {
+        let strip_width = rect.width() / PREVIEW_SAMPLES as f32;
+        for i in 0..PREVIEW_SAMPLES {
+            let x = rect.left() + strip_width * i as f32;
+            painter.rect_filled(
+                Rect::from_min_max(
+                    pos2(x, rect.top()),
+                    pos2(x + strip_width + 0.5, rect.bottom()),
+                ),
+                0.0,
+                to_color32(ramp.sample_color(t_at(i))),
+            );
+        }
+    } else {
+        // The curve is normalized to the range of the key values, so it always
+        // fills the preview area.
+        let (min, max) = ramp
+            .keys
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), k| {
+                (min.min(k.value.x), max.max(k.value.x))
+            });
+        let range = if max - min > f32::EPSILON {
+            max - min
+        } else {
+            1.0
+        };
+        let points = (0..PREVIEW_SAMPLES)
+            .map(|i| {
+                let t = t_at(i);
+                let v = (ramp.sample(t) - min) / range;
+                pos2(
+                    rect.left() + t * rect.width(),
+                    rect.bottom() - v * (rect.height() - 4.0) - 2.0,
+                )
+            })
+            .collect();
+        painter.add(Shape::line(
+            points,
+            Stroke::new(1.5, ui.visuals().widgets.active.fg_stroke.color),
+        ));
+    }
+}
+
+/// Draws an editor widget for a `ramp`. The editor shows a preview of the
+/// ramp, followed by the list of keys, which can be edited, added or removed.
+/// When `color` is set, key values are edited as colors, otherwise as scalars.
+pub fn ramp_edit_ui(ui: &mut Ui, ramp: &mut Ramp, color: bool) {
+    ramp_preview(ui, ramp, color);
+
+    ComboBox::from_id_source(ui.id().with("ramp_interpolation"))
+        .selected_text(ramp.interpolation.name())
+        .show_ui(ui, |ui| {
+            for interpolation in RampInterpolation::all() {
+                ui.selectable_value(&mut ramp.interpolation, interpolation, interpolation.name());
+            }
+        });
+
+    let can_remove = ramp.keys.len() > 1;
+    let mut to_remove = None;
+    let mut positions_changed = false;
+    for (i, key) in ramp.keys.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            positions_changed |= ui
+                .add(
+                    DragValue::new(&mut key.position)
+                        .speed(0.01)
+                        .clamp_range(0.0..=1.0)
+                        .fixed_decimals(2),
+                )
+                .changed();
+            if color {
+                let mut rgb = key.value.to_array();
+                if color_picker::color_edit_button_rgb(ui, &mut rgb).changed() {
+                    key.value = glam::Vec3::from(rgb);
+                }
+            } else {
+                let mut value = key.value.x;
+                if ui.add(DragValue::new(&mut value).speed(0.01)).changed() {
+                    key.value = glam::Vec3::splat(value);
+                }
+            }
+            if can_remove && ui.small_button("✖").clicked() {
+                to_remove = Some(i);
+            }
+        });
+    }
+
+    if let Some(i) = to_remove {
+        ramp.keys.remove(i);
+    }
+    if positions_changed {
+        ramp.sort_keys();
+    }
+
+    if ui.small_button("+ Add key").clicked() {
+        // Insert the new key in the middle of the widest gap between keys, so
+        // it doesn't land on top of an existing one.
+        let mut bounds = vec![0.0];
+        bounds.extend(ramp.keys.iter().map(|k| k.position));
+        bounds.push(1.0);
+        let position = bounds
+            .windows(2)
+            .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+            .map(|w| (w[0] + w[1]) * 0.5)
+            .unwrap_or(0.5);
+        let value = ramp.sample_color(position);
+        ramp.keys.push(RampKey { position, value });
+        ramp.sort_keys();
+    }
+}
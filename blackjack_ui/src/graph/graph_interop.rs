@@ -304,20 +304,24 @@ pub fn extract_graph_params(
     Ok(params)
 }
 
+/// Writes back the parameter values updated by running the graph, e.g. by
+/// gizmos. Returns whether any of them changed.
 pub fn set_parameters_from_external_values(
     graph: &mut Graph,
     updated_values: ExternalParameterValues,
     mapping: NodeMapping,
-) -> Result<()> {
+) -> Result<bool> {
+    let mut changed = false;
     for (param, value) in updated_values.0 {
         let node_id = mapping[param.node_id];
         let input_id = graph[node_id].get_input(&param.param_name)?;
 
         let input = &mut graph[input_id];
 
-        if input.typ.0.is_valid_value(&value) {
-            graph[input_id].value = ValueTypeUi(value);
+        if input.typ.0.is_valid_value(&value) && input.value.0 != value {
+            input.value = ValueTypeUi(value);
+            changed = true;
         }
     }
-    Ok(())
+    Ok(changed)
 }
@@ -5,16 +5,23 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::borrow::Cow;
+use std::path::PathBuf;
 
 use crate::application::gizmo_ui::UiNodeGizmoStates;
 use crate::application::graph_editor::GraphEditor;
 use crate::application::serialization;
-use crate::custom_widgets::smart_dragvalue::SmartDragValue;
+use crate::custom_widgets::{ramp_editor::ramp_edit_ui, smart_dragvalue::SmartDragValue};
+use crate::keymap::{Action, KEYMAP};
 use crate::{application::code_viewer::code_edit_ui, prelude::*};
+use blackjack_engine::graph::file_path::{
+    relativize_file_path, resolve_file_path, validate_file_path,
+};
 use blackjack_engine::graph::serialization::SerializedBjkSnippet;
+use blackjack_engine::materials::MaterialRegistry;
+use blackjack_engine::reference_images::ReferenceImage;
 use blackjack_engine::{
     graph::{BlackjackValue, DataType, FilePathMode, InputValueConfig, NodeDefinitions},
-    prelude::selection::SelectionExpression,
+    prelude::{selection::SelectionExpression, ChannelKeyType},
 };
 use egui::RichText;
 use egui_node_graph::{
@@ -45,6 +52,15 @@ pub enum CustomNodeResponse {
     UnlockGizmos(NodeId),
 }
 
+/// A selection parameter that is being edited in the graph. While it is
+/// focused, the viewport highlights the elements it matches.
+#[derive(Clone, Debug)]
+pub struct FocusedSelection {
+    pub node_id: NodeId,
+    pub param_name: String,
+    pub kind: ChannelKeyType,
+}
+
 /// Blackjack-specific global graph state
 pub struct CustomGraphState {
     /// When this option is set by the UI, the side effect encoded by the node
@@ -60,6 +76,21 @@ pub struct CustomGraphState {
     pub promoted_params: HashMap<InputId, String>,
 
     pub gizmo_states: UiNodeGizmoStates,
+    /// The folder containing the currently open graph file, if any. Relative
+    /// file path parameters are resolved against it.
+    pub base_dir: Option<PathBuf>,
+    /// The materials defined in this project. Saved along with the graph.
+    pub materials: MaterialRegistry,
+    /// The images shown in the viewport as a modeling guide. Saved along with
+    /// the graph.
+    pub reference_images: Vec<ReferenceImage>,
+    /// The selection parameter that currently has keyboard focus, if any.
+    /// Updated every frame while drawing the graph.
+    pub focused_selection: Option<FocusedSelection>,
+    /// Set by the UI when it edits the graph, or the project data saved with
+    /// it. The undo history only takes a snapshot after a change is reported
+    /// here, and clears the flag.
+    pub graph_changed: bool,
 }
 
 impl CustomGraphState {
@@ -70,6 +101,11 @@ impl CustomGraphState {
             active_node: None,
             promoted_params: HashMap::default(),
             gizmo_states,
+            base_dir: None,
+            materials: MaterialRegistry::default(),
+            reference_images: Vec::new(),
+            focused_selection: None,
+            graph_changed: false,
         }
     }
 }
@@ -85,6 +121,7 @@ impl DataTypeTrait<CustomGraphState> for DataTypeUi {
             DataType::Scalar => color_from_hex("#4ecdc4").unwrap(),
             DataType::Selection => color_from_hex("#f7fff7").unwrap(),
             DataType::String => color_from_hex("#ffe66d").unwrap(),
+            DataType::Ramp => color_from_hex("#ff6b6b").unwrap(),
         }
     }
 
@@ -96,6 +133,7 @@ impl DataTypeTrait<CustomGraphState> for DataTypeUi {
             DataType::Mesh => "mesh",
             DataType::HeightMap => "heightmap",
             DataType::String => "string",
+            DataType::Ramp => "ramp",
         })
     }
 }
@@ -199,16 +237,22 @@ pub fn draw_node_graph(graph_editor: &mut GraphEditor) {
         skip_pending_paste_check,
         ..
     } = graph_editor;
+    // The selection widgets set this again if they still have focus
+    custom_state.focused_selection = None;
     egui::CentralPanel::default().show(ctx, |ui| {
         // We clone the old graph here, so we can get a hold of the old state
         // before the graph is mutated. This is useful on some operations.
         let old_graph = editor_state.graph.clone();
+        let old_positions = editor_state.node_positions.clone();
 
         let responses = editor_state.draw_graph_editor(
             ui,
             NodeOpNames(custom_state.node_definitions.node_names()),
             custom_state,
         );
+        if !responses.node_responses.is_empty() || editor_state.node_positions != old_positions {
+            custom_state.graph_changed = true;
+        }
 
         // Store whether the mouse is in the node finder. This helps prevent
         // scroll wheel events.
@@ -271,8 +315,7 @@ pub fn draw_node_graph(graph_editor: &mut GraphEditor) {
             }
         }
 
-        if ui.input().key_released(egui::Key::C)
-            && ui.input().modifiers.ctrl
+        if KEYMAP.released(Action::CopyNodes, &ui.input())
             && !editor_state.selected_nodes.is_empty()
         {
             match serialization::to_clipboard(
@@ -298,6 +341,7 @@ pub fn draw_node_graph(graph_editor: &mut GraphEditor) {
             {
                 println!("Error: Could not paste clipboard data: {err:?}")
             }
+            custom_state.graph_changed = true;
         };
 
         if let Some(paste_contents) = input.events.iter().find_map(|ev| match ev {
@@ -371,6 +415,7 @@ pub fn data_type_to_input_param_kind(data_type: DataType) -> InputParamKind {
         DataType::Mesh => InputParamKind::ConnectionOnly,
         DataType::HeightMap => InputParamKind::ConnectionOnly,
         DataType::String => InputParamKind::ConnectionOrConstant,
+        DataType::Ramp => InputParamKind::ConnectionOrConstant,
     }
 }
 
@@ -381,7 +426,7 @@ pub fn default_shown_inline() -> bool {
 }
 
 #[derive(Clone, Debug)]
-pub struct NodeOpName(String);
+pub struct NodeOpName(pub String);
 impl NodeTemplateTrait for NodeOpName {
     type NodeData = NodeData;
     type DataType = DataTypeUi;
@@ -460,7 +505,7 @@ impl WidgetValueTrait for ValueTypeUi {
     fn value_widget(
         &mut self,
         param_name: &str,
-        _node_id: NodeId,
+        node_id: NodeId,
         ui: &mut egui::Ui,
         user_state: &mut CustomGraphState,
         node_data: &NodeData,
@@ -483,6 +528,7 @@ impl WidgetValueTrait for ValueTypeUi {
             return Default::default();
         }
         let input_def = input_def.unwrap();
+        let previous_value = self.0.clone();
 
         match (&mut self.0, &input_def.config) {
             (BlackjackValue::Vector(vector), InputValueConfig::Vector { .. }) => {
@@ -508,6 +554,15 @@ impl WidgetValueTrait for ValueTypeUi {
                     );
                 });
             }
+            (BlackjackValue::Vector(vector), InputValueConfig::Color { .. }) => {
+                ui.horizontal(|ui| {
+                    ui.label(param_name);
+                    let mut rgb = vector.to_array();
+                    if egui::color_picker::color_edit_button_rgb(ui, &mut rgb).changed() {
+                        *vector = Vec3::from(rgb);
+                    }
+                });
+            }
             (
                 BlackjackValue::Scalar(value),
                 InputValueConfig::Scalar {
@@ -557,17 +612,33 @@ impl WidgetValueTrait for ValueTypeUi {
                         }
                     });
             }
-            (BlackjackValue::String(path), InputValueConfig::FilePath { file_path_mode, .. }) => {
+            (
+                BlackjackValue::String(path),
+                InputValueConfig::FilePath {
+                    file_path_mode,
+                    extensions,
+                    ..
+                },
+            ) => {
+                let base_dir = user_state.base_dir.as_deref();
                 ui.label(param_name);
                 ui.horizontal(|ui| {
                     if ui.button("Select").clicked() {
+                        let mut dialog = rfd::FileDialog::new();
+                        if !extensions.is_empty() {
+                            let extensions = extensions.iter().map(|e| e.as_str()).collect_vec();
+                            dialog = dialog.add_filter(&extensions.join(", "), &extensions);
+                        }
+                        if let Some(base_dir) = base_dir {
+                            dialog = dialog.set_directory(base_dir);
+                        }
                         let new_path = match file_path_mode {
-                            FilePathMode::Open => rfd::FileDialog::new().pick_file(),
-                            FilePathMode::Save => rfd::FileDialog::new().save_file(),
+                            FilePathMode::Open => dialog.pick_file(),
+                            FilePathMode::Save => dialog.save_file(),
                         };
 
                         if let Some(new_path) = new_path {
-                            *path = new_path
+                            *path = relativize_file_path(&new_path, base_dir)
                                 .into_os_string()
                                 .into_string()
                                 .unwrap_or_else(|err| format!("INVALID PATH: {err:?}"))
@@ -579,6 +650,13 @@ impl WidgetValueTrait for ValueTypeUi {
                         ui.label("No file selected");
                     }
                 });
+                if !path.is_empty() {
+                    let resolved = resolve_file_path(path, base_dir);
+                    if let Err(err) = validate_file_path(&resolved, *file_path_mode, extensions) {
+                        ui.colored_label(egui::Color32::YELLOW, "⚠ Invalid path")
+                            .on_hover_text(err.to_string());
+                    }
+                }
             }
             (BlackjackValue::String(text), InputValueConfig::String { multiline, .. }) => {
                 if *multiline {
@@ -600,10 +678,25 @@ impl WidgetValueTrait for ValueTypeUi {
                 code_edit_ui(ui, text);
                 //ui.add(egui::TextEdit::multiline(text).text_style(egui::TextStyle::Monospace).desired_width(f32::INFINITY));
             }
-            (BlackjackValue::Selection(text, selection), InputValueConfig::Selection { .. }) => {
-                if ui.text_edit_singleline(text).changed() {
+            (
+                BlackjackValue::Selection(text, selection),
+                InputValueConfig::Selection { kind, .. },
+            ) => {
+                let response = ui.text_edit_singleline(text);
+                if response.changed() {
                     *selection = SelectionExpression::parse(text).ok();
                 }
+                if let (true, Some(kind)) = (response.has_focus(), kind) {
+                    user_state.focused_selection = Some(FocusedSelection {
+                        node_id,
+                        param_name: param_name.into(),
+                        kind: *kind,
+                    });
+                }
+            }
+            (BlackjackValue::Ramp(ramp), InputValueConfig::Ramp { color, .. }) => {
+                ui.label(param_name);
+                ramp_edit_ui(ui, ramp, *color);
             }
             (BlackjackValue::None, InputValueConfig::None) => {
                 ui.label(param_name);
@@ -613,6 +706,9 @@ impl WidgetValueTrait for ValueTypeUi {
             }
         }
 
+        if self.0 != previous_value {
+            user_state.graph_changed = true;
+        }
         Vec::new()
     }
 }
@@ -0,0 +1,341 @@
+// Copyright (C) 2023 setzer22 and contributors
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use winit::event::MouseButton;
+
+use crate::app_window::input::InputSystem;
+use crate::cli_args::CLI_ARGS;
+use crate::prelude::*;
+
+/// The path where the keymap is looked up when none is given in the command
+/// line.
+const DEFAULT_KEYMAP_PATH: &str = "./keymap.ron";
+
+/// An action of the application that can be bound to keys.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Undo,
+    Redo,
+    /// Copy the selected nodes in the graph editor to the clipboard.
+    CopyNodes,
+    GizmoTranslate,
+    GizmoRotate,
+    GizmoScale,
+    /// Toggle drawing the edges of the mesh in the 3d viewport.
+    ToggleWireframe,
+    /// Move the 3d viewport camera so the whole mesh is visible.
+    FrameMesh,
+    /// Show or hide the Lua console.
+    ToggleConsole,
+}
+
+/// A key, along with the modifiers that must be held when pressing it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    /// The name of the key, as in `egui::Key`. For instance, "A", "Num1",
+    /// "F5" or "Escape".
+    pub key: String,
+    /// Ctrl, or Cmd on Mac.
+    #[serde(default)]
+    pub command: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl KeyChord {
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.into(),
+            command: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub fn command(mut self) -> Self {
+        self.command = true;
+        self
+    }
+
+    pub fn shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    fn modifiers_match(&self, modifiers: &egui::Modifiers) -> bool {
+        modifiers.command == self.command
+            && modifiers.shift == self.shift
+            && modifiers.alt == self.alt
+    }
+
+    /// Returns whether this chord was pressed during the current frame.
+    pub fn pressed(&self, input: &egui::InputState) -> bool {
+        key_from_name(&self.key)
+            .map(|key| input.key_pressed(key) && self.modifiers_match(&input.modifiers))
+            .unwrap_or(false)
+    }
+
+    /// Returns whether the key of this chord was released during the current
+    /// frame, with the chord's modifiers still held.
+    pub fn released(&self, input: &egui::InputState) -> bool {
+        key_from_name(&self.key)
+            .map(|key| input.key_released(key) && self.modifiers_match(&input.modifiers))
+            .unwrap_or(false)
+    }
+}
+
+/// A mouse button, along with the modifiers that must be held with it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MouseChord {
+    pub button: MouseChordButton,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseChordButton {
+    Left,
+    Middle,
+    Right,
+}
+
+impl MouseChord {
+    pub fn new(button: MouseChordButton) -> Self {
+        Self {
+            button,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    /// Returns whether this chord is currently held in the given `input`.
+    pub fn held(&self, input: &InputSystem) -> bool {
+        let button = match self.button {
+            MouseChordButton::Left => MouseButton::Left,
+            MouseChordButton::Middle => MouseButton::Middle,
+            MouseChordButton::Right => MouseButton::Right,
+        };
+        input.mouse.buttons().pressed(button)
+            && input.ctrl_down == self.ctrl
+            && input.shift_down == self.shift
+            && input.alt_down == self.alt
+    }
+}
+
+/// Mouse bindings for the 3d viewport camera.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CameraBindings {
+    pub orbit: MouseChord,
+    pub pan: MouseChord,
+}
+
+/// Maps the application actions to the keys triggering them. The keymap is
+/// stored as a RON file, and can start from one of the presets for users
+/// coming from other tools.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Keymap {
+    pub actions: HashMap<Action, Vec<KeyChord>>,
+    pub camera: CameraBindings,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::blackjack()
+    }
+}
+
+impl Keymap {
+    /// The default bindings.
+    pub fn blackjack() -> Self {
+        let mut actions = HashMap::new();
+        actions.insert(Action::Undo, vec![KeyChord::new("Z").command()]);
+        actions.insert(
+            Action::Redo,
+            vec![
+                KeyChord::new("Z").command().shift(),
+                KeyChord::new("Y").command(),
+            ],
+        );
+        actions.insert(Action::CopyNodes, vec![KeyChord::new("C").command()]);
+        actions.insert(Action::GizmoTranslate, vec![KeyChord::new("G")]);
+        actions.insert(Action::GizmoRotate, vec![KeyChord::new("R")]);
+        actions.insert(Action::GizmoScale, vec![KeyChord::new("S")]);
+        actions.insert(Action::ToggleWireframe, vec![KeyChord::new("W").shift()]);
+        actions.insert(Action::FrameMesh, vec![KeyChord::new("Home")]);
+        actions.insert(Action::ToggleConsole, vec![KeyChord::new("F12")]);
+        Self {
+            actions,
+            camera: CameraBindings {
+                orbit: MouseChord::new(MouseChordButton::Left),
+                pan: MouseChord {
+                    shift: true,
+                    ..MouseChord::new(MouseChordButton::Left)
+                },
+            },
+        }
+    }
+
+    /// Bindings for Blender users.
+    pub fn blender() -> Self {
+        let mut keymap = Self::blackjack();
+        keymap.camera = CameraBindings {
+            orbit: MouseChord::new(MouseChordButton::Middle),
+            pan: MouseChord {
+                shift: true,
+                ..MouseChord::new(MouseChordButton::Middle)
+            },
+        };
+        keymap
+    }
+
+    /// Bindings for Maya users.
+    pub fn maya() -> Self {
+        let mut keymap = Self::blackjack();
+        keymap.actions.extend([
+            (Action::Redo, vec![KeyChord::new("Y").command()]),
+            (Action::GizmoTranslate, vec![KeyChord::new("W")]),
+            (Action::GizmoRotate, vec![KeyChord::new("E")]),
+            (Action::GizmoScale, vec![KeyChord::new("R")]),
+            (Action::ToggleWireframe, vec![KeyChord::new("Num4")]),
+            (Action::FrameMesh, vec![KeyChord::new("F")]),
+        ]);
+        keymap.camera = CameraBindings {
+            orbit: MouseChord {
+                alt: true,
+                ..MouseChord::new(MouseChordButton::Left)
+            },
+            pan: MouseChord {
+                alt: true,
+                ..MouseChord::new(MouseChordButton::Middle)
+            },
+        };
+        keymap
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "blackjack" => Some(Self::blackjack()),
+            "blender" => Some(Self::blender()),
+            "maya" => Some(Self::maya()),
+            _ => None,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(ron::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let pretty = ron::ser::PrettyConfig::default();
+        std::fs::write(path, ron::ser::to_string_pretty(self, pretty)?)?;
+        Ok(())
+    }
+
+    /// Returns whether any of the chords bound to `action` was pressed during
+    /// the current frame.
+    pub fn pressed(&self, action: Action, input: &egui::InputState) -> bool {
+        self.chords(action).iter().any(|chord| chord.pressed(input))
+    }
+
+    /// Same as `pressed`, but triggers when the key is released.
+    pub fn released(&self, action: Action, input: &egui::InputState) -> bool {
+        self.chords(action)
+            .iter()
+            .any(|chord| chord.released(input))
+    }
+
+    /// Returns the chords bound to `action`.
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        self.actions
+            .get(&action)
+            .map(|c| c.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns a short description of the first chord bound to `action`,
+    /// suitable to be shown next to a button, like "Ctrl+Z".
+    pub fn shortcut_text(&self, action: Action) -> String {
+        self.chords(action)
+            .first()
+            .map(|chord| {
+                let mut text = String::new();
+                if chord.command {
+                    text.push_str("Ctrl+");
+                }
+                if chord.shift {
+                    text.push_str("Shift+");
+                }
+                if chord.alt {
+                    text.push_str("Alt+");
+                }
+                text.push_str(&chord.key);
+                text
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The keymap for the application. The `--keymap` command line argument can
+/// name one of the presets or a keymap file. Otherwise, the keymap is read
+/// from `keymap.ron` in the working directory, if present.
+pub static KEYMAP: Lazy<Keymap> = Lazy::new(|| {
+    let path = match &CLI_ARGS.keymap {
+        Some(arg) => {
+            if let Some(preset) = Keymap::preset(arg) {
+                return preset;
+            }
+            arg.as_str()
+        }
+        None if Path::new(DEFAULT_KEYMAP_PATH).exists() => DEFAULT_KEYMAP_PATH,
+        None => return Keymap::default(),
+    };
+    Keymap::load(Path::new(path)).unwrap_or_else(|err| {
+        println!("Error loading keymap at {path}, using the default one. {err}");
+        Keymap::default()
+    })
+});
+
+/// Parses the name of an `egui::Key`.
+fn key_from_name(name: &str) -> Option<egui::Key> {
+    use egui::Key::*;
+    const KEYS: &[egui::Key] = &[
+        ArrowDown, ArrowLeft, ArrowRight, ArrowUp, Escape, Tab, Backspace, Enter, Space, Insert,
+        Delete, Home, End, PageUp, PageDown, Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8,
+        Num9, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, F1, F2,
+        F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    ];
+    KEYS.iter().copied().find(|key| format!("{key:?}") == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keymap_roundtrip() {
+        for preset in ["blackjack", "blender", "maya"] {
+            let keymap = Keymap::preset(preset).unwrap();
+            let path = std::env::temp_dir().join(format!("keymap_{preset}.ron"));
+            keymap.save(&path).unwrap();
+            let loaded = Keymap::load(&path).unwrap();
+            assert_eq!(keymap.actions, loaded.actions);
+            for chords in loaded.actions.values() {
+                assert!(chords.iter().all(|c| key_from_name(&c.key).is_some()));
+            }
+        }
+    }
+}
@@ -34,6 +34,9 @@ pub mod custom_widgets;
 /// Command line argument parsing.
 pub mod cli_args;
 
+/// Configurable bindings from keys to application actions.
+pub mod keymap;
+
 fn main() {
     #[cfg(feature = "tracy")]
     let _client = profiling::tracy_client::Client::start();
@@ -27,7 +27,9 @@ pub mod r3 {
     pub use rend3_routine::base::{BaseRenderGraph, BaseRenderGraphIntermediateState};
     pub use rend3_routine::common::PerMaterialArchetypeInterface;
     pub use rend3_routine::culling::PerMaterialArchetypeData;
-    pub use rend3_routine::pbr::{AlbedoComponent, PbrMaterial, PbrRoutine, TransparencyType};
+    pub use rend3_routine::pbr::{
+        AlbedoComponent, PbrMaterial, PbrRoutine, Transparency, TransparencyType,
+    };
     pub use rend3_routine::tonemapping::TonemappingRoutine;
     pub use rend3_routine::{depth::DepthRoutine, forward::ForwardRoutine};
 }
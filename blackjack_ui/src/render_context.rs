@@ -7,10 +7,14 @@
 use std::sync::Arc;
 
 use crate::{
+    cli_args::CLI_ARGS,
     prelude::*,
     rendergraph::{
-        face_routine::FaceRoutine, grid_routine::GridRoutine, id_picking_routine::IdPickingRoutine,
-        point_cloud_routine::PointCloudRoutine, shader_manager::ShaderManager,
+        face_routine::{self, FaceRoutine},
+        grid_routine::GridRoutine,
+        id_picking_routine::IdPickingRoutine,
+        point_cloud_routine::PointCloudRoutine,
+        shader_manager::ShaderManager,
         wireframe_routine::WireframeRoutine,
     },
 };
@@ -28,6 +32,38 @@ fn get_present_mode(surface: &Surface, adapter: &Adapter) -> rend3::types::Prese
     }
 }
 
+/// The optional GPU capabilities the viewport routines rely on.
+#[derive(Clone, Copy, Debug)]
+pub struct GpuCapabilities {
+    /// Whether vertex shaders can read from storage buffers. The wireframe,
+    /// point cloud and face routines fetch all their vertex data this way.
+    pub vertex_storage: bool,
+}
+
+impl GpuCapabilities {
+    /// The largest number of storage buffers bound by any of the viewport
+    /// routines, which is the base mesh pass of the face routine.
+    const REQUIRED_STORAGE_BUFFERS: u32 = face_routine::BASE_MESH_NUM_BUFFERS as u32;
+
+    pub fn detect(adapter: &Adapter, limits: &wgpu::Limits) -> Self {
+        let vertex_storage = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::VERTEX_STORAGE)
+            && limits.max_storage_buffers_per_shader_stage >= Self::REQUIRED_STORAGE_BUFFERS;
+        Self {
+            vertex_storage: vertex_storage && !CLI_ARGS.fallback_renderer,
+        }
+    }
+
+    /// When degraded, meshes are drawn using rend3's vertex-buffer based
+    /// pipeline, and the wireframe, vertex and face overlays are not
+    /// available.
+    pub fn degraded(&self) -> bool {
+        !self.vertex_storage
+    }
+}
+
 pub struct RenderContext {
     pub renderer: Arc<r3::Renderer>,
 
@@ -35,10 +71,13 @@ pub struct RenderContext {
     pub pbr_routine: r3::PbrRoutine,
     pub tonemapping_routine: r3::TonemappingRoutine,
     pub grid_routine: GridRoutine,
-    pub wireframe_routine: WireframeRoutine,
-    pub face_routine: FaceRoutine,
-    pub point_cloud_routine: PointCloudRoutine,
+    /// The routines below are None when the GPU lacks the capabilities they
+    /// need. See [`GpuCapabilities`].
+    pub wireframe_routine: Option<WireframeRoutine>,
+    pub face_routine: Option<FaceRoutine>,
+    pub point_cloud_routine: Option<PointCloudRoutine>,
     pub id_picking_routine: IdPickingRoutine,
+    pub capabilities: GpuCapabilities,
     pub surface: Arc<Surface>,
     pub adapter: Arc<Adapter>,
     pub texture_format: TextureFormat,
@@ -88,13 +127,34 @@ impl RenderContext {
             r3::TonemappingRoutine::new(&renderer, &spp, &base_graph.interfaces, format);
         drop(data_core); // Release the lock
 
+        let capabilities = GpuCapabilities::detect(&adapter, &renderer.limits);
+        if capabilities.degraded() {
+            println!(
+                "WARNING: The GPU does not support storage buffers in vertex shaders. \
+                 Using the degraded rendering mode: Wireframes, vertices and face \
+                 overlays will not be drawn."
+            );
+        }
+
         let shader_manager = ShaderManager::new(&renderer.device);
         let grid_routine = GridRoutine::new(&renderer.device);
-        let wireframe_routine =
-            WireframeRoutine::new(&renderer.device, &base_graph, &shader_manager);
-        let point_cloud_routine =
-            PointCloudRoutine::new(&renderer.device, &base_graph, &shader_manager);
-        let face_routine = FaceRoutine::new(&renderer, &base_graph, &shader_manager);
+        let (wireframe_routine, point_cloud_routine, face_routine) = if capabilities.degraded() {
+            (None, None, None)
+        } else {
+            (
+                Some(WireframeRoutine::new(
+                    &renderer.device,
+                    &base_graph,
+                    &shader_manager,
+                )),
+                Some(PointCloudRoutine::new(
+                    &renderer.device,
+                    &base_graph,
+                    &shader_manager,
+                )),
+                Some(FaceRoutine::new(&renderer, &base_graph, &shader_manager)),
+            )
+        };
         let id_picking_routine = IdPickingRoutine::new(&renderer.device);
 
         RenderContext {
@@ -107,6 +167,7 @@ impl RenderContext {
             point_cloud_routine,
             face_routine,
             id_picking_routine,
+            capabilities,
             surface,
             adapter,
             texture_format: format,
@@ -118,9 +179,15 @@ impl RenderContext {
 
     pub fn clear_objects(&mut self) {
         self.objects.clear();
-        self.point_cloud_routine.clear();
-        self.wireframe_routine.clear();
-        self.face_routine.clear();
+        if let Some(point_cloud_routine) = &mut self.point_cloud_routine {
+            point_cloud_routine.clear();
+        }
+        if let Some(wireframe_routine) = &mut self.wireframe_routine {
+            wireframe_routine.clear();
+        }
+        if let Some(face_routine) = &mut self.face_routine {
+            face_routine.clear();
+        }
     }
 
     pub fn add_mesh_as_object<M: r3::Material>(&mut self, mesh: r3::Mesh, material: Option<M>) {
@@ -73,16 +73,24 @@ pub fn blackjack_viewport_rendergraph<'node>(
             | r3::TextureUsages::COPY_SRC,
     });
 
+    // The wireframe, point cloud and face routines are missing in the
+    // degraded rendering mode. Meshes are drawn by the PBR passes above.
     use crate::application::viewport_3d::EdgeDrawMode::*;
-    if matches!(settings.edge_mode, FullEdge | HalfEdge) {
-        routines.wireframe.add_to_graph(graph, &state);
+    if let Some(wireframe) = routines.wireframe {
+        if matches!(settings.edge_mode, FullEdge | HalfEdge) {
+            wireframe.add_to_graph(graph, &state);
+        }
     }
-    if settings.render_vertices {
-        routines.point_cloud.add_to_graph(graph, &state);
+    if let Some(point_cloud) = routines.point_cloud {
+        if settings.render_vertices {
+            point_cloud.add_to_graph(graph, &state);
+        }
     }
     use crate::application::viewport_3d::FaceDrawMode::*;
-    if matches!(settings.face_mode, Flat | Smooth | Real) {
-        routines.face.add_to_graph(graph, &state, id_map, settings);
+    if let Some(face) = routines.face {
+        if matches!(settings.face_mode, Flat | Smooth | Real) {
+            face.add_to_graph(graph, &state, id_map, settings);
+        }
     }
 
     routines.id_picking.add_to_graph(graph, resolution, id_map);
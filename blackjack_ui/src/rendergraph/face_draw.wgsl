@@ -4,6 +4,9 @@
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) color: vec3<f32>,
+    @location(4) uv: vec2<f32>,
 };
 
 struct FragmentOutput {
@@ -15,18 +18,40 @@ var<storage> positions: Vec3Array;
 @group(1) @binding(1)
 var<storage> normals: Vec3Array;
 @group(1) @binding(2)
+var<storage> colors: Vec3Array;
+@group(1) @binding(3)
+var<storage> uvs: Vec3Array;
+@group(1) @binding(4)
 var matcap: texture_2d<f32>;
+@group(1) @binding(5)
+var<uniform> channel_flags: u32;
+
+// Must match the BASE_MESH_HAS_* constants in face_routine.rs
+let HAS_NORMALS: u32 = 1u;
+let HAS_COLORS: u32 = 2u;
+let HAS_UVS: u32 = 4u;
 
 @vertex
 fn vs_main(
     @builtin(vertex_index) vertex_idx: u32,
 ) -> VertexOutput {
     let position = unpack_v3(positions.inner[vertex_idx]);
-    let normal = unpack_v3(normals.inner[vertex_idx]);
 
     var output : VertexOutput;
     output.clip_position = uniforms.view_proj * vec4<f32>(position, 1.0);
-    output.normal = normalize(normal);
+    output.world_position = position;
+    output.normal = vec3<f32>(0.0, 0.0, 0.0);
+    output.color = vec3<f32>(1.0, 1.0, 1.0);
+    output.uv = vec2<f32>(0.0, 0.0);
+    if ((channel_flags & HAS_NORMALS) != 0u) {
+        output.normal = normalize(unpack_v3(normals.inner[vertex_idx]));
+    }
+    if ((channel_flags & HAS_COLORS) != 0u) {
+        output.color = unpack_v3(colors.inner[vertex_idx]);
+    }
+    if ((channel_flags & HAS_UVS) != 0u) {
+        output.uv = unpack_v3(uvs.inner[vertex_idx]).xy;
+    }
     return output;
 }
 
@@ -34,10 +59,27 @@ fn vs_main(
 fn fs_main(input: VertexOutput) -> FragmentOutput {
     var out : FragmentOutput;
 
-    let muv = (uniforms.view * vec4<f32>(normalize(input.normal), 0.0)).xy;
+    // Without uploaded normals, faces are shaded flat using the screen-space
+    // derivatives of the position.
+    var normal = cross(dpdx(input.world_position), dpdy(input.world_position));
+    if ((channel_flags & HAS_NORMALS) != 0u) {
+        normal = input.normal;
+    }
+
+    let muv = (uniforms.view * vec4<f32>(normalize(normal), 0.0)).xy;
     let muv = muv * 0.5 + vec2<f32>(0.5, 0.5);
 
-    out.color = textureSample(matcap, primary_sampler, vec2<f32>(muv.x, 1.0 - muv.y));
+    var color = textureSample(matcap, primary_sampler, vec2<f32>(muv.x, 1.0 - muv.y));
+    color = vec4<f32>(color.rgb * input.color, color.a);
+
+    // A checker pattern, to visualize uv layouts
+    if ((channel_flags & HAS_UVS) != 0u) {
+        let cell = floor(input.uv * 8.0);
+        if (fract((cell.x + cell.y) * 0.5) > 0.25) {
+            color = vec4<f32>(color.rgb * 0.6, color.a);
+        }
+    }
 
+    out.color = color;
     return out;
 }
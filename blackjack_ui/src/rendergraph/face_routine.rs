@@ -7,6 +7,7 @@
 use std::sync::Arc;
 
 use crate::{application::viewport_3d::Viewport3dSettings, prelude::r3};
+use blackjack_engine::prelude::VertexIndexBuffers;
 use glam::{Vec3, Vec4};
 
 use rend3::{
@@ -34,18 +35,33 @@ pub const NUM_MATCAPS: usize = 6;
 pub struct MeshFacesLayout {
     indices: Buffer,
     positions: Buffer,
+    /// The optional channels. Bind groups can't have empty buffers, so
+    /// channels that were not uploaded get a placeholder with one element.
     normals: Buffer,
+    colors: Buffer,
+    uvs: Buffer,
+    /// A single u32 with the `BASE_MESH_HAS_*` flags of the uploaded
+    /// channels.
+    channel_flags: Buffer,
     matcaps: Arc<Vec<TextureHandle>>,
     num_indices: usize,
 }
 
-const BASE_MESH_NUM_BUFFERS: usize = 2;
+pub const BASE_MESH_NUM_BUFFERS: usize = 4;
 const BASE_MESH_NUM_TEXTURES: usize = 1;
-impl RoutineLayout<BASE_MESH_NUM_BUFFERS, BASE_MESH_NUM_TEXTURES> for MeshFacesLayout {
+const BASE_MESH_NUM_UNIFORMS: usize = 1;
+
+const BASE_MESH_HAS_NORMALS: u32 = 1;
+const BASE_MESH_HAS_COLORS: u32 = 2;
+const BASE_MESH_HAS_UVS: u32 = 4;
+
+impl RoutineLayout<BASE_MESH_NUM_BUFFERS, BASE_MESH_NUM_TEXTURES, BASE_MESH_NUM_UNIFORMS>
+    for MeshFacesLayout
+{
     type Settings = Viewport3dSettings;
 
     fn get_wgpu_buffers(&self, _settings: &Viewport3dSettings) -> [&Buffer; BASE_MESH_NUM_BUFFERS] {
-        [&self.positions, &self.normals]
+        [&self.positions, &self.normals, &self.colors, &self.uvs]
     }
 
     fn get_wgpu_textures<'a>(
@@ -56,8 +72,8 @@ impl RoutineLayout<BASE_MESH_NUM_BUFFERS, BASE_MESH_NUM_TEXTURES> for MeshFacesL
         [texture_manager.get_view(self.matcaps[settings.matcap % NUM_MATCAPS].get_raw())]
     }
 
-    fn get_wgpu_uniforms(&self, _settings: &Self::Settings) -> [&Buffer; 0] {
-        []
+    fn get_wgpu_uniforms(&self, _settings: &Self::Settings) -> [&Buffer; BASE_MESH_NUM_UNIFORMS] {
+        [&self.channel_flags]
     }
 
     fn get_draw_type(&self, _settings: &Self::Settings) -> DrawType<'_> {
@@ -117,8 +133,12 @@ impl RoutineLayout<OVERLAY_NUM_BUFFERS, 0, OVERLAY_NUM_UNIFORMS> for FaceOverlay
 
 pub struct FaceRoutine {
     matcaps: Arc<Vec<TextureHandle>>,
-    base_mesh_routine:
-        Viewport3dRoutine<MeshFacesLayout, BASE_MESH_NUM_BUFFERS, BASE_MESH_NUM_TEXTURES>,
+    base_mesh_routine: Viewport3dRoutine<
+        MeshFacesLayout,
+        BASE_MESH_NUM_BUFFERS,
+        BASE_MESH_NUM_TEXTURES,
+        BASE_MESH_NUM_UNIFORMS,
+    >,
     face_overlay_routine:
         Viewport3dRoutine<FaceOverlayLayout, OVERLAY_NUM_BUFFERS, 0, OVERLAY_NUM_UNIFORMS>,
 }
@@ -179,36 +199,51 @@ impl FaceRoutine {
         }
     }
 
-    pub fn add_base_mesh(
-        &mut self,
-        renderer: &r3::Renderer,
-        positions: &[Vec3],
-        normals: &[Vec3],
-        indices: &[u32],
-    ) {
-        let num_indices = indices.len();
-
-        assert_eq!(positions.len(), normals.len());
+    /// Uploads the base mesh. Only the optional channels present in
+    /// `buffers` are uploaded, the others are left to the shader defaults.
+    pub fn add_base_mesh(&mut self, renderer: &r3::Renderer, buffers: &VertexIndexBuffers) {
+        let num_indices = buffers.indices.len();
+        let mut channel_flags = 0;
 
         let positions = renderer.device.create_buffer_init(&BufferInitDescriptor {
             label: None,
-            contents: bytemuck::cast_slice(positions),
+            contents: bytemuck::cast_slice(&buffers.positions),
             usage: BufferUsages::STORAGE,
         });
-        let normals = renderer.device.create_buffer_init(&BufferInitDescriptor {
+        let mut optional_channel = |values: &[Vec3], flag: u32| {
+            let values = if values.is_empty() {
+                &[Vec3::ZERO][..]
+            } else {
+                assert_eq!(values.len(), buffers.positions.len());
+                channel_flags |= flag;
+                values
+            };
+            renderer.device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(values),
+                usage: BufferUsages::STORAGE,
+            })
+        };
+        let normals = optional_channel(&buffers.normals, BASE_MESH_HAS_NORMALS);
+        let colors = optional_channel(&buffers.colors, BASE_MESH_HAS_COLORS);
+        let uvs = optional_channel(&buffers.uvs, BASE_MESH_HAS_UVS);
+        let channel_flags = renderer.device.create_buffer_init(&BufferInitDescriptor {
             label: None,
-            contents: bytemuck::cast_slice(normals),
-            usage: BufferUsages::STORAGE,
+            contents: bytemuck::bytes_of(&channel_flags),
+            usage: BufferUsages::UNIFORM,
         });
         let indices = renderer.device.create_buffer_init(&BufferInitDescriptor {
             label: None,
-            contents: bytemuck::cast_slice(indices),
+            contents: bytemuck::cast_slice(&buffers.indices),
             usage: wgpu::BufferUsages::INDEX,
         });
 
         self.base_mesh_routine.layouts.push(MeshFacesLayout {
             positions,
             normals,
+            colors,
+            uvs,
+            channel_flags,
             indices,
             matcaps: self.matcaps.clone(),
             num_indices,
@@ -67,17 +67,26 @@ impl IdPickingRoutine {
     /// parameters:
     ///
     /// - `window_cursor_pos`: Global cursor position, relative to the top-left
-    /// corner of the window, in pixels.
+    /// corner of the window, in logical points.
     ///
-    /// - `viewport_origin`: The top-left corner of the 3d viewport, relative to
-    /// the top-left corner of the window, in pixels.
-
-    /// - `viewport_size`: The size of the 3d viewport, in pixels.
+    /// - `viewport_rect`: The rect of the 3d viewport, relative to the top-left
+    /// corner of the window, in logical points.
+    ///
+    /// - `pixels_per_point`: The scale factor used to convert points to
+    /// physical pixels. The viewport3d render texture is rendered at this
+    /// scale, so both the cursor and the rect are converted to texels.
     ///
     /// NOTE: This function assumes the resolution for the viewport3d render
     /// texture is exactly 1 texel per pixel. This is usually true within a
     /// 1-frame delay.
-    pub fn set_cursor_pos(&mut self, window_cursor_pos: egui::Pos2, viewport_rect: egui::Rect) {
+    pub fn set_cursor_pos(
+        &mut self,
+        window_cursor_pos: egui::Pos2,
+        viewport_rect: egui::Rect,
+        pixels_per_point: f32,
+    ) {
+        let window_cursor_pos = (window_cursor_pos.to_vec2() * pixels_per_point).to_pos2();
+        let viewport_rect = viewport_rect.scale_from_origin(pixels_per_point);
         if viewport_rect.contains(window_cursor_pos) {
             let cursor_pos_f = window_cursor_pos - viewport_rect.left_top();
             let cursor_pos = UVec2::new(cursor_pos_f.x as u32, cursor_pos_f.y as u32);
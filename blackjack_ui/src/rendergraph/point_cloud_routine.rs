@@ -21,12 +21,19 @@ use super::{
 
 pub struct PointCloudLayout {
     buffer: Buffer,
+    /// A single f32, the size of the points in physical pixels.
+    point_size: Buffer,
     len: usize,
 }
 
 const NUM_BUFFERS: usize = 1;
+const NUM_UNIFORMS: usize = 1;
 
-impl RoutineLayout<NUM_BUFFERS> for PointCloudLayout {
+/// Size of the points, in logical pixels. Scaled by the pixels per point so
+/// points look the same on high-DPI monitors.
+const POINT_SIZE: f32 = 5.0;
+
+impl RoutineLayout<NUM_BUFFERS, 0, NUM_UNIFORMS> for PointCloudLayout {
     type Settings = ();
     fn get_wgpu_buffers(&self, _settings: &()) -> [&Buffer; NUM_BUFFERS] {
         [&self.buffer]
@@ -40,8 +47,8 @@ impl RoutineLayout<NUM_BUFFERS> for PointCloudLayout {
         []
     }
 
-    fn get_wgpu_uniforms(&self, _settings: &Self::Settings) -> [&Buffer; 0] {
-        []
+    fn get_wgpu_uniforms(&self, _settings: &Self::Settings) -> [&Buffer; NUM_UNIFORMS] {
+        [&self.point_size]
     }
 
     fn get_draw_type(&self, _settings: &Self::Settings) -> DrawType<'_> {
@@ -53,7 +60,7 @@ impl RoutineLayout<NUM_BUFFERS> for PointCloudLayout {
 }
 
 pub struct PointCloudRoutine {
-    inner: Viewport3dRoutine<PointCloudLayout, NUM_BUFFERS, 0>,
+    inner: Viewport3dRoutine<PointCloudLayout, NUM_BUFFERS, 0, NUM_UNIFORMS>,
 }
 
 impl PointCloudRoutine {
@@ -70,14 +77,20 @@ impl PointCloudRoutine {
         }
     }
 
-    pub fn add_point_cloud(&mut self, device: &Device, points: &[Vec3]) {
+    pub fn add_point_cloud(&mut self, device: &Device, points: &[Vec3], pixels_per_point: f32) {
         let buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: None,
             contents: bytemuck::cast_slice(points),
             usage: BufferUsages::STORAGE,
         });
+        let point_size = device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::bytes_of(&(POINT_SIZE * pixels_per_point)),
+            usage: BufferUsages::UNIFORM,
+        });
         self.inner.layouts.push(PointCloudLayout {
             buffer,
+            point_size,
             len: points.len(),
         });
     }